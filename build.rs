@@ -26,5 +26,18 @@ fn main() {
     writeln!(f, "pub const ARCH: &str = \"{}\";", arch).ok();
     writeln!(f, "/// The user agent for sentry events").ok();
     writeln!(f, "pub const USER_AGENT: &str = \"sentry-cli/{}\";", arch).ok();
+
+    // the OpenSSL version decides how FIPS mode is queried, see `utils::fips`
+    println!("cargo:rustc-check-cfg=cfg(ossl110)");
+    println!("cargo:rustc-check-cfg=cfg(ossl300)");
+    if let Ok(version) = env::var("DEP_OPENSSL_VERSION_NUMBER") {
+        let version = u64::from_str_radix(&version, 16).unwrap();
+        if version >= 0x1010_0000 {
+            println!("cargo:rustc-cfg=ossl110");
+        }
+        if version >= 0x3000_0000 {
+            println!("cargo:rustc-cfg=ossl300");
+        }
+    }
     println!("cargo:rerun-if-changed=build.rs\n");
 }
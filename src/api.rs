@@ -27,6 +27,7 @@ use log::{debug, info, warn};
 use parking_lot::{Mutex, RwLock};
 use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
 use regex::{Captures, Regex};
+use sentry::internals::Dsn;
 use serde::de::{DeserializeOwned, Deserializer};
 use serde::{Deserialize, Serialize};
 use sha1::Digest;
@@ -35,17 +36,27 @@ use symbolic::debuginfo::ObjectKind;
 use uuid::Uuid;
 
 use crate::config::{Auth, Config};
-use crate::constants::{ARCH, EXT, PLATFORM, RELEASE_REGISTRY_LATEST_URL, VERSION};
+use crate::constants::{ARCH, EXT, PLATFORM, USER_AGENT, VERSION};
 use crate::utils::android::AndroidManifest;
+use crate::utils::cancel;
+use crate::utils::envelope;
+use crate::utils::fips;
 use crate::utils::http::{self, is_absolute_url, parse_link_header};
 use crate::utils::progress::{make_progress_bar, ProgressBar};
+use crate::utils::resource_usage::ResourceUsage;
 use crate::utils::retry::{get_default_backoff, DurationAsMilliseconds};
 use crate::utils::sourcemaps::get_sourcemap_reference_from_headers;
+use crate::utils::spool::Spool;
 use crate::utils::ui::{capitalize_string, make_byte_progress_bar};
+use crate::utils::webhooks::{SIGNATURE_HEADER, TIMESTAMP_HEADER};
 use crate::utils::xcode::InfoPlist;
 
 const QUERY_ENCODE_SET: AsciiSet = CONTROLS.add(b' ').add(b'"').add(b'#').add(b'<').add(b'>');
 const DEFAULT_ENCODE_SET: AsciiSet = QUERY_ENCODE_SET.add(b'`').add(b'?').add(b'{').add(b'}');
+/// Search queries are free text and can contain query string delimiters.
+const SEARCH_QUERY_ENCODE_SET: AsciiSet = QUERY_ENCODE_SET.add(b'%').add(b'&').add(b'+').add(b'=');
+/// GitLab addresses projects by their full path with encoded slashes.
+const GITLAB_PROJECT_ENCODE_SET: AsciiSet = DEFAULT_ENCODE_SET.add(b'/');
 
 /// Represents file contents temporarily
 #[derive(Clone, Debug)]
@@ -60,6 +71,9 @@ pub struct PathArg<A: fmt::Display>(A);
 /// Wrapper that escapes arguments for URL query segments.
 pub struct QueryArg<A: fmt::Display>(A);
 
+/// Wrapper that escapes Sentry search queries for the `query` parameter.
+pub struct SearchQueryArg<A: fmt::Display>(A);
+
 struct CurlConnectionManager;
 
 impl r2d2::ManageConnection for CurlConnectionManager {
@@ -128,6 +142,12 @@ impl<A: fmt::Display> fmt::Display for QueryArg<A> {
     }
 }
 
+impl<A: fmt::Display> fmt::Display for SearchQueryArg<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        utf8_percent_encode(&format!("{}", self.0), &SEARCH_QUERY_ENCODE_SET).fmt(f)
+    }
+}
+
 impl<A: fmt::Display> fmt::Display for PathArg<A> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         // if we put values into the path we need to url encode them.  However
@@ -236,6 +256,10 @@ pub enum ApiErrorKind {
     RequestFailed,
     #[fail(display = "could not compress data")]
     CompressionFailed,
+    #[fail(display = "request was cancelled")]
+    RequestCancelled,
+    #[fail(display = "request not allowed in FIPS mode")]
+    FipsViolation,
 }
 
 #[derive(Debug)]
@@ -416,6 +440,16 @@ impl Api {
         handle.ssl_verify_host(self.config.should_verify_ssl())?;
         handle.ssl_verify_peer(self.config.should_verify_ssl())?;
 
+        if let Err(err) = fips::check_request(self.config.should_verify_ssl()) {
+            return Err(err.context(ApiErrorKind::FipsViolation).into());
+        }
+        if fips::is_enabled() {
+            handle.ssl_version(curl::easy::SslVersion::Tlsv12)?;
+        }
+        if let Some(ciphers) = fips::tls_cipher_list() {
+            handle.ssl_cipher_list(ciphers)?;
+        }
+
         // This toggles gzipping, useful for uploading large files
         handle.transfer_encoding(self.config.allow_transfer_encoding())?;
 
@@ -425,6 +459,68 @@ impl Api {
         ApiRequest::create(handle, &method, &url, auth, env, custom_header)
     }
 
+    /// Fetches all pages of a cursor paginated listing.
+    ///
+    /// Pages are fetched until the server runs out of results or `max_rows`
+    /// items have been collected.  If the first page is not found this fails
+    /// with `not_found`.
+    fn paginate<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        max_rows: Option<usize>,
+        not_found: ApiErrorKind,
+    ) -> ApiResult<Vec<T>> {
+        self.paginate_until(path, max_rows, not_found, |_| false)
+    }
+
+    /// Like `paginate` but stops before the first item matching `stop`.
+    fn paginate_until<T: DeserializeOwned, F: FnMut(&T) -> bool>(
+        &self,
+        path: &str,
+        max_rows: Option<usize>,
+        not_found: ApiErrorKind,
+        mut stop: F,
+    ) -> ApiResult<Vec<T>> {
+        let separator = if path.contains('?') { '&' } else { '?' };
+        let mut rv = vec![];
+        let mut cursor = "".to_string();
+        loop {
+            let resp = self.get(&format!(
+                "{}{}cursor={}",
+                path,
+                separator,
+                QueryArg(&cursor)
+            ))?;
+            if resp.status() == 404 || (resp.status() == 400 && !cursor.is_empty()) {
+                if rv.is_empty() {
+                    return Err(not_found.into());
+                } else {
+                    break;
+                }
+            }
+            let pagination = resp.pagination();
+            let items = resp.convert::<Vec<T>>()?;
+            let count = items.len();
+            let len = rv.len();
+            rv.extend(items.into_iter().take_while(|item| !stop(item)));
+            if rv.len() - len < count {
+                break;
+            }
+            if let Some(max_rows) = max_rows {
+                if rv.len() >= max_rows {
+                    rv.truncate(max_rows);
+                    break;
+                }
+            }
+            if let Some(next) = pagination.into_next_cursor() {
+                cursor = next;
+            } else {
+                break;
+            }
+        }
+        Ok(rv)
+    }
+
     /// Convenience method that performs a `GET` request.
     pub fn get(&self, path: &str) -> ApiResult<ApiResponse> {
         self.request(Method::Get, path)?.send()
@@ -868,6 +964,51 @@ impl Api {
         }
     }
 
+    /// Returns the releases of an organization that were seen in the given
+    /// environment.
+    pub fn list_environment_releases(
+        &self,
+        org: &str,
+        environment: &str,
+    ) -> ApiResult<Vec<ReleaseInfo>> {
+        let path = format!(
+            "/organizations/{}/releases/?environment={}",
+            PathArg(org),
+            QueryArg(environment)
+        );
+        self.paginate(&path, None, ApiErrorKind::OrganizationNotFound)
+    }
+
+    /// Lists the commits associated with a release.
+    pub fn list_release_commits(&self, org: &str, version: &str) -> ApiResult<Vec<ReleaseCommit>> {
+        self.paginate(
+            &format!(
+                "/organizations/{}/releases/{}/commits/",
+                PathArg(org),
+                PathArg(version)
+            ),
+            None,
+            ApiErrorKind::ReleaseNotFound,
+        )
+    }
+
+    /// Lists the files changed by the commits of a release.
+    pub fn list_release_commit_files(
+        &self,
+        org: &str,
+        version: &str,
+    ) -> ApiResult<Vec<CommitFileChange>> {
+        self.paginate(
+            &format!(
+                "/organizations/{}/releases/{}/commitfiles/",
+                PathArg(org),
+                PathArg(version)
+            ),
+            None,
+            ApiErrorKind::ReleaseNotFound,
+        )
+    }
+
     // Finds the most recent release with commits and returns it.
     // If it does not exist `None` will be returned.
     pub fn get_previous_release_with_commits(
@@ -910,6 +1051,185 @@ impl Api {
         self.get(&path)?.convert_rnf(ApiErrorKind::ReleaseNotFound)
     }
 
+    /// Lists the deploys of all releases to an environment.
+    pub fn list_environment_deploys(
+        &self,
+        org: &str,
+        environment: &str,
+    ) -> ApiResult<Vec<ReleaseDeploy>> {
+        let path = format!(
+            "/organizations/{}/deploys/?environment={}",
+            PathArg(org),
+            QueryArg(environment)
+        );
+        self.paginate(&path, None, ApiErrorKind::OrganizationNotFound)
+    }
+
+    /// Fetches all pages of a GitHub or GitLab listing.
+    ///
+    /// `prepare` adds the headers to each request.  There is a next page as
+    /// long as GitLab sends an `X-Next-Page` or the `Link` header has a
+    /// `rel="next"` link.  The result has at least one page.
+    fn get_provider_pages<T, F>(&self, url: &str, prepare: F) -> ApiResult<Vec<T>>
+    where
+        T: DeserializeOwned,
+        F: Fn(ApiRequest) -> ApiResult<ApiRequest>,
+    {
+        let separator = if url.contains('?') { '&' } else { '?' };
+        let mut rv = vec![];
+        let mut page = 1;
+        loop {
+            let page_url = format!("{}{}per_page=100&page={}", url, separator, page);
+            let resp = prepare(self.request(Method::Get, &page_url)?)?.send()?;
+            let next_page = match resp.get_header("x-next-page") {
+                Some(next) => next.trim().parse().ok(),
+                None => resp
+                    .get_header("link")
+                    .filter(|link| {
+                        parse_link_header(link)
+                            .iter()
+                            .any(|item| item.get("rel") == Some(&"next"))
+                    })
+                    .map(|_| page + 1),
+            };
+            rv.push(resp.convert_rnf(ApiErrorKind::ResourceNotFound)?);
+            match next_page {
+                Some(next_page) => page = next_page,
+                None => return Ok(rv),
+            }
+        }
+    }
+
+    /// Fetches the commits between two revisions of a GitHub repository.
+    ///
+    /// The commits are returned oldest first and without their files.  GitHub
+    /// stops listing commits of very large ranges, check the returned
+    /// `total_commits` against the number of commits.
+    pub fn compare_github_commits(
+        &self,
+        api_url: &str,
+        token: Option<&str>,
+        repo: &str,
+        base: &str,
+        head: &str,
+    ) -> ApiResult<GitHubComparison> {
+        let url = format!(
+            "{}/repos/{}/compare/{}...{}",
+            api_url.trim_end_matches('/'),
+            repo,
+            PathArg(base),
+            PathArg(head)
+        );
+        let mut pages: Vec<GitHubComparison> =
+            self.get_provider_pages(&url, |req| github_authorize(req, token))?;
+        let mut rv = pages.remove(0);
+        for page in pages {
+            rv.commits.extend(page.commits);
+        }
+        Ok(rv)
+    }
+
+    /// Fetches a single commit of a GitHub repository including its files.
+    pub fn get_github_commit(
+        &self,
+        api_url: &str,
+        token: Option<&str>,
+        repo: &str,
+        sha: &str,
+    ) -> ApiResult<GitHubCommit> {
+        let url = format!(
+            "{}/repos/{}/commits/{}",
+            api_url.trim_end_matches('/'),
+            repo,
+            PathArg(sha)
+        );
+        let mut pages: Vec<GitHubCommit> =
+            self.get_provider_pages(&url, |req| github_authorize(req, token))?;
+        let mut rv = pages.remove(0);
+        for page in pages {
+            rv.files.extend(page.files);
+        }
+        Ok(rv)
+    }
+
+    /// Fetches the commits between two revisions of a GitLab project.
+    pub fn compare_gitlab_commits(
+        &self,
+        api_url: &str,
+        token: Option<GitLabToken<'_>>,
+        project: &str,
+        from: &str,
+        to: &str,
+    ) -> ApiResult<Vec<GitLabCommit>> {
+        let url = format!(
+            "{}/projects/{}/repository/compare?from={}&to={}",
+            api_url.trim_end_matches('/'),
+            utf8_percent_encode(project, &GITLAB_PROJECT_ENCODE_SET),
+            QueryArg(from),
+            QueryArg(to)
+        );
+        let mut req = self.request(Method::Get, &url)?;
+        if let Some(token) = token {
+            req = token.authorize(req)?;
+        }
+        Ok(req
+            .send()?
+            .convert_rnf::<GitLabComparison>(ApiErrorKind::ResourceNotFound)?
+            .commits)
+    }
+
+    /// Fetches the diff of a single commit of a GitLab project.
+    pub fn get_gitlab_commit_diff(
+        &self,
+        api_url: &str,
+        token: Option<GitLabToken<'_>>,
+        project: &str,
+        sha: &str,
+    ) -> ApiResult<Vec<GitLabDiff>> {
+        let url = format!(
+            "{}/projects/{}/repository/commits/{}/diff",
+            api_url.trim_end_matches('/'),
+            utf8_percent_encode(project, &GITLAB_PROJECT_ENCODE_SET),
+            PathArg(sha)
+        );
+        let pages: Vec<Vec<GitLabDiff>> = self.get_provider_pages(&url, |req| match token {
+            Some(token) => token.authorize(req),
+            None => Ok(req),
+        })?;
+        Ok(pages.into_iter().flatten().collect())
+    }
+
+    /// Returns the dSYM download URLs of an app build on App Store Connect.
+    pub fn get_app_store_dsym_urls(
+        &self,
+        api_url: &str,
+        token: &str,
+        app_id: &str,
+        build: &str,
+    ) -> ApiResult<Vec<String>> {
+        let url = format!(
+            "{}/v1/builds?filter[app]={}&filter[version]={}\
+             &include=buildBundles&fields[buildBundles]=dSYMUrl",
+            api_url.trim_end_matches('/'),
+            QueryArg(app_id),
+            QueryArg(build)
+        );
+        let resp: AppStoreBuilds = self
+            .request(Method::Get, &url)?
+            .with_header("Authorization", &format!("Bearer {}", token))?
+            .send()?
+            .convert_rnf(ApiErrorKind::ResourceNotFound)?;
+        if resp.data.is_empty() {
+            return Err(ApiErrorKind::ResourceNotFound.into());
+        }
+        Ok(resp
+            .included
+            .into_iter()
+            .filter(|x| x.ty == "buildBundles")
+            .filter_map(|x| x.attributes.dsym_url)
+            .collect())
+    }
+
     /// Updates a bunch of issues within a project that match a provided filter
     /// and performs `changes` changes.
     pub fn bulk_update_issue(
@@ -925,6 +1245,9 @@ impl Api {
             }
             Some(qs) => qs,
         };
+        if changes.is_empty() {
+            return Ok(false);
+        }
         self.put(
             &format!(
                 "/projects/{}/{}/issues/?{}",
@@ -938,9 +1261,280 @@ impl Api {
         .map(|_| true)
     }
 
+    /// Lists issues of a project matching the given query.  Pages are
+    /// fetched until the server runs out of results or `max_rows` issues
+    /// have been collected.
+    pub fn list_issues(
+        &self,
+        org: &str,
+        project: &str,
+        query: &IssueQuery<'_>,
+        max_rows: Option<usize>,
+    ) -> ApiResult<Vec<Issue>> {
+        let mut path = format!("/projects/{}/{}/issues/", PathArg(org), PathArg(project));
+        let qs = query.get_query_params();
+        if !qs.is_empty() {
+            path.push('?');
+            path.push_str(&qs.join("&"));
+        }
+        self.paginate(&path, max_rows, ApiErrorKind::ProjectNotFound)
+    }
+
+    /// Looks up an issue by its numeric ID or its short ID (e.g.
+    /// `PROJECT-1A`).  If it does not exist `None` will be returned.
+    pub fn get_issue(&self, org: &str, issue_id: &str) -> ApiResult<Option<Issue>> {
+        if issue_id.chars().all(|c| c.is_ascii_digit()) {
+            let resp = self.get(&format!("/issues/{}/", PathArg(issue_id)))?;
+            if resp.status() == 404 {
+                Ok(None)
+            } else {
+                resp.convert()
+            }
+        } else {
+            #[derive(Deserialize)]
+            struct ShortIdInfo {
+                group: Issue,
+            }
+
+            let resp = self.get(&format!(
+                "/organizations/{}/shortids/{}/",
+                PathArg(org),
+                PathArg(issue_id)
+            ))?;
+            if resp.status() == 404 {
+                Ok(None)
+            } else {
+                resp.convert::<ShortIdInfo>().map(|info| Some(info.group))
+            }
+        }
+    }
+
+    /// Returns the latest event of an issue.  If the issue does not exist
+    /// `None` will be returned.
+    pub fn get_latest_issue_event(&self, issue_id: &str) -> ApiResult<Option<IssueEvent>> {
+        let resp = self.get(&format!("/issues/{}/events/latest/", PathArg(issue_id)))?;
+        if resp.status() == 404 {
+            Ok(None)
+        } else {
+            resp.convert()
+        }
+    }
+
+    /// Lists the events of an issue, newest first.  Pages are fetched until
+    /// the server runs out of results or `max_rows` events have been
+    /// collected.
+    pub fn list_issue_events(
+        &self,
+        issue_id: &str,
+        max_rows: Option<usize>,
+    ) -> ApiResult<Vec<IssueEvent>> {
+        self.list_issue_events_until(issue_id, max_rows, |_| false)
+    }
+
+    /// Like `list_issue_events` but stops before the first event matching
+    /// `stop`, e.g. one that was already seen.
+    pub fn list_issue_events_until<F: FnMut(&IssueEvent) -> bool>(
+        &self,
+        issue_id: &str,
+        max_rows: Option<usize>,
+        stop: F,
+    ) -> ApiResult<Vec<IssueEvent>> {
+        self.paginate_until(
+            &format!("/issues/{}/events/?full=true", PathArg(issue_id)),
+            max_rows,
+            ApiErrorKind::ResourceNotFound,
+            stop,
+        )
+    }
+
+    /// Lists the events of a project matching a search query, newest first.
+    pub fn list_project_events(
+        &self,
+        org: &str,
+        project: &str,
+        query: Option<&str>,
+        max_rows: Option<usize>,
+    ) -> ApiResult<Vec<IssueEvent>> {
+        self.list_project_events_until(org, project, query, max_rows, |_| false)
+    }
+
+    /// Like `list_project_events` but stops before the first event matching
+    /// `stop`.
+    pub fn list_project_events_until<F: FnMut(&IssueEvent) -> bool>(
+        &self,
+        org: &str,
+        project: &str,
+        query: Option<&str>,
+        max_rows: Option<usize>,
+        stop: F,
+    ) -> ApiResult<Vec<IssueEvent>> {
+        let mut path = format!("/projects/{}/{}/events/", PathArg(org), PathArg(project));
+        if let Some(query) = query {
+            path.push_str(&format!("?query={}", SearchQueryArg(query)));
+        }
+        self.paginate_until(&path, max_rows, ApiErrorKind::ProjectNotFound, stop)
+    }
+
+    /// Lists the integrations installed in an organization, optionally
+    /// restricted to a single provider such as `jira` or `github`.
+    pub fn list_organization_integrations(
+        &self,
+        org: &str,
+        provider: Option<&str>,
+    ) -> ApiResult<Vec<Integration>> {
+        let mut path = format!("/organizations/{}/integrations/", PathArg(org));
+        if let Some(provider) = provider {
+            path.push_str(&format!("?provider_key={}", QueryArg(provider)));
+        }
+        self.get(&path)?
+            .convert_rnf(ApiErrorKind::OrganizationNotFound)
+    }
+
+    /// Links an issue to an existing issue of an external tracker through
+    /// the given integration.
+    pub fn link_external_issue(
+        &self,
+        issue_id: &str,
+        integration_id: &str,
+        external_issue: &str,
+    ) -> ApiResult<ExternalIssue> {
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct LinkRequest<'a> {
+            external_issue: &'a str,
+        }
+
+        self.put(
+            &format!(
+                "/groups/{}/integrations/{}/",
+                PathArg(issue_id),
+                PathArg(integration_id)
+            ),
+            &LinkRequest { external_issue },
+        )?
+        .convert_rnf(ApiErrorKind::ResourceNotFound)
+    }
+
+    /// Posts a JSON body to a webhook outside of Sentry.
+    pub fn send_webhook(
+        &self,
+        url: &str,
+        body: Vec<u8>,
+        timestamp: i64,
+        signature: Option<&str>,
+    ) -> ApiResult<()> {
+        let mut req = self
+            .request(Method::Post, url)?
+            .with_header(TIMESTAMP_HEADER, &timestamp.to_string())?
+            .with_raw_body(body, "application/json")?;
+        if let Some(signature) = signature {
+            req = req.with_header(SIGNATURE_HEADER, signature)?;
+        }
+        if req.send()?.failed() {
+            return Err(ApiErrorKind::RequestFailed.into());
+        }
+        Ok(())
+    }
+
+    /// Sends a serialized event to the store endpoint of a DSN.
+    ///
+    /// Unlike the Sentry client, this reports the response so that callers
+    /// can retry events that were not accepted.
+    pub fn store_event(&self, dsn: &Dsn, body: Vec<u8>) -> ApiResult<ApiResponse> {
+        if self.config.should_spool() {
+            let envelope =
+                envelope::to_event_json_envelope(&body).context(ApiErrorKind::RequestFailed)?;
+            return self.send_envelope(dsn, envelope);
+        }
+        let auth = dsn.to_auth(Some(USER_AGENT));
+        self.request(Method::Post, dsn.store_api_url().as_str())?
+            .with_raw_body(body, "application/json")?
+            .with_header("X-Sentry-Auth", &auth.to_string())?
+            .send()
+    }
+
+    /// Sends an envelope to the envelope endpoint of a DSN, or writes it into
+    /// the spool folder if spooling is enabled.
+    pub fn send_envelope(&self, dsn: &Dsn, body: Vec<u8>) -> ApiResult<ApiResponse> {
+        if self.config.should_spool() {
+            Spool::from_config(&self.config)
+                .and_then(|spool| spool.write_envelope(dsn, &body))
+                .context(ApiErrorKind::RequestFailed)?;
+            return Ok(ApiResponse {
+                status: 200,
+                headers: vec![],
+                body: None,
+            });
+        }
+        self.post_envelope(dsn, body)
+    }
+
+    /// Posts an envelope to the envelope endpoint of a DSN, bypassing the
+    /// spool.
+    pub fn post_envelope(&self, dsn: &Dsn, body: Vec<u8>) -> ApiResult<ApiResponse> {
+        let auth = dsn.to_auth(Some(USER_AGENT));
+        let url = dsn.store_api_url().join("../envelope/").unwrap();
+        self.request(Method::Post, url.as_str())?
+            .with_raw_body(body, "application/x-sentry-envelope")?
+            .with_header("X-Sentry-Auth", &auth.to_string())?
+            .send()
+    }
+
+    /// Posts a minidump to the minidump endpoint of a DSN and returns the ID
+    /// of the event created for it.
+    ///
+    /// `event` is the JSON of the event payload the crash is merged into, the
+    /// attachments are stored with the event.
+    pub fn upload_minidump(
+        &self,
+        dsn: &Dsn,
+        minidump: &Path,
+        attachments: &[PathBuf],
+        event: &str,
+    ) -> ApiResult<String> {
+        if self.config.should_spool() {
+            let event_id = Uuid::new_v4().to_simple().to_string();
+            let mut event: serde_json::Value =
+                serde_json::from_str(event).context(ApiErrorKind::CannotSerializeAsJson)?;
+            event["event_id"] = event_id.as_str().into();
+            let envelope = envelope::to_minidump_envelope(&event, minidump, attachments)
+                .context(ApiErrorKind::RequestFailed)?;
+            self.send_envelope(dsn, envelope)?;
+            return Ok(event_id);
+        }
+        let auth = dsn.to_auth(Some(USER_AGENT));
+        let url = dsn.store_api_url().join("../minidump/").unwrap();
+        let mut form = curl::easy::Form::new();
+        form.part("upload_file_minidump").file(minidump).add()?;
+        for (idx, path) in attachments.iter().enumerate() {
+            form.part(&format!("attachment{}", idx + 1))
+                .file(path)
+                .add()?;
+        }
+        form.part("sentry").contents(event.as_bytes()).add()?;
+
+        let resp = self
+            .request(Method::Post, url.as_str())?
+            .with_form_data(form)?
+            .with_header("X-Sentry-Auth", &auth.to_string())?
+            .send()?
+            .into_result()?;
+
+        // older servers answer with JSON, relay with the plain event ID
+        if resp.is_json() {
+            #[derive(Deserialize)]
+            struct MinidumpResponse {
+                id: String,
+            }
+            return Ok(resp.deserialize::<MinidumpResponse>()?.id);
+        }
+        let body = resp.body.as_deref().unwrap_or_default();
+        Ok(String::from_utf8_lossy(body).trim().to_string())
+    }
+
     /// Finds the latest release for sentry-cli on GitHub.
-    pub fn get_latest_sentrycli_release(&self) -> ApiResult<Option<SentryCliRelease>> {
-        let resp = self.get(RELEASE_REGISTRY_LATEST_URL)?;
+    pub fn get_latest_sentrycli_release(&self, url: &str) -> ApiResult<Option<SentryCliRelease>> {
+        let resp = self.get(url)?;
 
         // Prefer universal binary on macOS
         let arch = match PLATFORM {
@@ -952,13 +1546,18 @@ impl Api {
         info!("Looking for file named: {}", ref_name);
 
         if resp.status() == 200 {
-            let info: RegistryRelease = resp.convert()?;
+            let mut info: RegistryRelease = resp.convert()?;
             for (filename, download_url) in info.file_urls {
                 info!("Found asset {}", filename);
                 if filename == ref_name {
+                    let sha256 = info
+                        .files
+                        .remove(&filename)
+                        .and_then(|mut file| file.checksums.remove("sha256-hex"));
                     return Ok(Some(SentryCliRelease {
                         version: info.version,
                         download_url,
+                        sha256,
                     }));
                 }
             }
@@ -970,6 +1569,17 @@ impl Api {
         }
     }
 
+    /// Returns the versions published in a release registry listing.
+    pub fn list_registry_versions(&self, url: &str) -> ApiResult<Vec<String>> {
+        let resp = self.get(url)?;
+        if resp.status() == 200 {
+            Ok(resp.convert::<RegistryVersions>()?.versions)
+        } else {
+            info!("Release registry returned {}", resp.status());
+            Ok(vec![])
+        }
+    }
+
     /// Given a list of checksums for DIFs, this returns a list of those
     /// that do not exist for the project yet.
     pub fn find_missing_dif_checksums<I>(
@@ -998,6 +1608,22 @@ impl Api {
         Ok(state.missing)
     }
 
+    /// Lists the debug information files of a project with a debug identifier.
+    pub fn list_difs_by_id(
+        &self,
+        org: &str,
+        project: &str,
+        id: DebugId,
+    ) -> ApiResult<Vec<DebugInfoFile>> {
+        let path = format!(
+            "/projects/{}/{}/files/dsyms/?debug_id={}",
+            PathArg(org),
+            PathArg(project),
+            QueryArg(id)
+        );
+        self.get(&path)?.convert_rnf(ApiErrorKind::ProjectNotFound)
+    }
+
     /// Uploads a ZIP archive containing DIFs from the given path.
     pub fn upload_dif_archive(
         &self,
@@ -1091,6 +1717,29 @@ impl Api {
             .convert_rnf(ApiErrorKind::ReleaseNotFound)
     }
 
+    /// Request artifact bundle assembling and processing for the release and
+    /// dist in the request.  The same chunks can be assembled for several dists.
+    pub fn assemble_artifact_bundle(
+        &self,
+        org: &str,
+        request: &ChunkedArtifactBundleRequest<'_>,
+    ) -> ApiResult<AssembleArtifactsResponse> {
+        let url = format!("/organizations/{}/artifactbundle/assemble/", PathArg(org));
+
+        self.request(Method::Post, &url)?
+            .with_json_body(request)?
+            .with_retry(
+                self.config.get_max_retry_count().unwrap(),
+                &[
+                    http::HTTP_STATUS_502_BAD_GATEWAY,
+                    http::HTTP_STATUS_503_SERVICE_UNAVAILABLE,
+                    http::HTTP_STATUS_504_GATEWAY_TIMEOUT,
+                ],
+            )?
+            .send()?
+            .convert()
+    }
+
     /// Compresses a file with the given compression.
     fn compress(data: &[u8], compression: ChunkCompression) -> Result<Vec<u8>, io::Error> {
         Ok(match compression {
@@ -1271,30 +1920,35 @@ impl Api {
 
     /// List all monitors associated with an organization
     pub fn list_organization_monitors(&self, org: &str) -> ApiResult<Vec<Monitor>> {
-        let mut rv = vec![];
-        let mut cursor = "".to_string();
-        loop {
-            let resp = self.get(&format!(
-                "/organizations/{}/monitors/?cursor={}",
-                PathArg(org),
-                QueryArg(&cursor)
-            ))?;
-            if resp.status() == 404 || (resp.status() == 400 && !cursor.is_empty()) {
-                if rv.is_empty() {
-                    return Err(ApiErrorKind::ResourceNotFound.into());
-                } else {
-                    break;
-                }
-            }
-            let pagination = resp.pagination();
-            rv.extend(resp.convert::<Vec<Monitor>>()?.into_iter());
-            if let Some(next) = pagination.into_next_cursor() {
-                cursor = next;
-            } else {
-                break;
-            }
+        self.paginate(
+            &format!("/organizations/{}/monitors/", PathArg(org)),
+            None,
+            ApiErrorKind::ResourceNotFound,
+        )
+    }
+
+    /// Looks up a monitor by its slug.  If it does not exist `None` will be
+    /// returned.
+    pub fn get_monitor(&self, org: &str, slug: &str) -> ApiResult<Option<Monitor>> {
+        let resp = self.get(&format!(
+            "/organizations/{}/monitors/{}/",
+            PathArg(org),
+            PathArg(slug)
+        ))?;
+        if resp.status() == 404 {
+            Ok(None)
+        } else {
+            resp.convert()
         }
-        Ok(rv)
+    }
+
+    /// Creates a new monitor.
+    pub fn create_monitor(&self, org: &str, monitor: &NewMonitor) -> ApiResult<Monitor> {
+        self.post(
+            &format!("/organizations/{}/monitors/", PathArg(org)),
+            monitor,
+        )?
+        .convert_rnf(ApiErrorKind::OrganizationNotFound)
     }
 
     /// Create a new checkin for a monitor
@@ -1330,32 +1984,628 @@ impl Api {
         resp.convert()
     }
 
+    /// Writes a check-in for the relay spool folder instead of sending it to
+    /// the API.  Check-ins in envelopes report their duration in seconds.
+    fn spool_monitor_checkin(
+        &self,
+        monitor_slug: &str,
+        checkin_id: &Uuid,
+        status: MonitorStatus,
+        duration: Option<u64>,
+        monitor_config: Option<&MonitorConfig>,
+    ) -> ApiResult<()> {
+        let mut check_in = serde_json::json!({
+            "check_in_id": checkin_id.to_simple().to_string(),
+            "monitor_slug": monitor_slug,
+            "status": status,
+        });
+        if let Some(duration) = duration {
+            check_in["duration"] = (duration as f64 / 1000.0).into();
+        }
+        if let Some(config) = monitor_config {
+            // envelopes nest the schedule with its type
+            check_in["monitor_config"] = serde_json::json!({
+                "schedule": {"type": config.schedule_type, "value": config.schedule},
+                "checkin_margin": config.checkin_margin,
+                "max_runtime": config.max_runtime,
+                "timezone": config.timezone,
+            });
+        }
+        if let Some(environment) = self.config.get_environment() {
+            check_in["environment"] = environment.into();
+        }
+        let dsn = self.config.get_dsn().context(ApiErrorKind::RequestFailed)?;
+        let envelope =
+            envelope::to_check_in_envelope(&check_in).context(ApiErrorKind::RequestFailed)?;
+        self.send_envelope(&dsn, envelope)?;
+        Ok(())
+    }
+
+    /// Create a new checkin for a monitor identified by its slug
+    pub fn create_monitor_checkin_by_slug(
+        &self,
+        org: &str,
+        monitor_slug: &str,
+        checkin: &CreateMonitorCheckIn,
+    ) -> ApiResult<MonitorCheckIn> {
+        if self.config.should_spool() {
+            let id = Uuid::new_v4();
+            self.spool_monitor_checkin(
+                monitor_slug,
+                &id,
+                checkin.status,
+                None,
+                checkin.monitor_config.as_ref(),
+            )?;
+            return Ok(MonitorCheckIn {
+                id,
+                status: checkin.status,
+                duration: None,
+            });
+        }
+        let path = &format!(
+            "/organizations/{}/monitors/{}/checkins/",
+            PathArg(org),
+            PathArg(monitor_slug),
+        );
+        let resp = self.post(path, checkin)?;
+        if resp.status() == 404 {
+            return Err(ApiErrorKind::ResourceNotFound.into());
+        }
+        resp.convert()
+    }
+
+    /// Update a checkin for a monitor identified by its slug
+    pub fn update_monitor_checkin_by_slug(
+        &self,
+        org: &str,
+        monitor_slug: &str,
+        checkin_id: &Uuid,
+        checkin: &UpdateMonitorCheckIn,
+    ) -> ApiResult<MonitorCheckIn> {
+        if self.config.should_spool() {
+            let status = checkin.status.unwrap_or(MonitorStatus::Ok);
+            self.spool_monitor_checkin(monitor_slug, checkin_id, status, checkin.duration, None)?;
+            return Ok(MonitorCheckIn {
+                id: *checkin_id,
+                status,
+                duration: checkin.duration,
+            });
+        }
+        let path = &format!(
+            "/organizations/{}/monitors/{}/checkins/{}/",
+            PathArg(org),
+            PathArg(monitor_slug),
+            PathArg(checkin_id),
+        );
+        let resp = self.put(path, checkin)?;
+        if resp.status() == 404 {
+            return Err(ApiErrorKind::ResourceNotFound.into());
+        }
+        resp.convert()
+    }
+
     /// List all projects associated with an organization
     pub fn list_organization_projects(&self, org: &str) -> ApiResult<Vec<Project>> {
-        let mut rv = vec![];
-        let mut cursor = "".to_string();
-        loop {
-            let resp = self.get(&format!(
-                "/organizations/{}/projects/?cursor={}",
+        self.paginate(
+            &format!("/organizations/{}/projects/", PathArg(org)),
+            None,
+            ApiErrorKind::OrganizationNotFound,
+        )
+    }
+
+    /// Creates a new project owned by the given team.
+    pub fn create_project(
+        &self,
+        org: &str,
+        team: &str,
+        project: &NewProject,
+    ) -> ApiResult<Project> {
+        self.post(
+            &format!("/teams/{}/{}/projects/", PathArg(org), PathArg(team)),
+            project,
+        )?
+        .convert_rnf(ApiErrorKind::ResourceNotFound)
+    }
+
+    /// Deletes a project.  Returns `false` if the project did not exist.
+    pub fn delete_project(&self, org: &str, project: &str) -> ApiResult<bool> {
+        let resp = self.delete(&format!("/projects/{}/{}/", PathArg(org), PathArg(project)))?;
+        if resp.status() == 404 {
+            Ok(false)
+        } else {
+            resp.into_result().map(|_| true)
+        }
+    }
+
+    /// Lists the client keys (DSNs) of a project.
+    pub fn list_project_keys(&self, org: &str, project: &str) -> ApiResult<Vec<ProjectKey>> {
+        self.get(&format!(
+            "/projects/{}/{}/keys/",
+            PathArg(org),
+            PathArg(project)
+        ))?
+        .convert_rnf(ApiErrorKind::ProjectNotFound)
+    }
+
+    /// Creates a new client key for a project.
+    pub fn create_project_key(
+        &self,
+        org: &str,
+        project: &str,
+        name: Option<&str>,
+    ) -> ApiResult<ProjectKey> {
+        #[derive(Serialize)]
+        struct NewProjectKey<'a> {
+            #[serde(skip_serializing_if = "Option::is_none")]
+            name: Option<&'a str>,
+        }
+
+        self.post(
+            &format!("/projects/{}/{}/keys/", PathArg(org), PathArg(project)),
+            &NewProjectKey { name },
+        )?
+        .convert_rnf(ApiErrorKind::ProjectNotFound)
+    }
+
+    /// Enables or disables a client key of a project.
+    pub fn set_project_key_active(
+        &self,
+        org: &str,
+        project: &str,
+        key_id: &str,
+        is_active: bool,
+    ) -> ApiResult<ProjectKey> {
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct ProjectKeyChanges {
+            is_active: bool,
+        }
+
+        self.put(
+            &format!(
+                "/projects/{}/{}/keys/{}/",
                 PathArg(org),
-                QueryArg(&cursor)
-            ))?;
-            if resp.status() == 404 || (resp.status() == 400 && !cursor.is_empty()) {
-                if rv.is_empty() {
-                    return Err(ApiErrorKind::OrganizationNotFound.into());
-                } else {
-                    break;
-                }
-            }
-            let pagination = resp.pagination();
-            rv.extend(resp.convert::<Vec<Project>>()?.into_iter());
-            if let Some(next) = pagination.into_next_cursor() {
-                cursor = next;
-            } else {
-                break;
-            }
+                PathArg(project),
+                PathArg(key_id)
+            ),
+            &ProjectKeyChanges { is_active },
+        )?
+        .convert_rnf(ApiErrorKind::ResourceNotFound)
+    }
+
+    /// Deletes a client key of a project.  Returns `false` if the key did
+    /// not exist.
+    pub fn delete_project_key(&self, org: &str, project: &str, key_id: &str) -> ApiResult<bool> {
+        let resp = self.delete(&format!(
+            "/projects/{}/{}/keys/{}/",
+            PathArg(org),
+            PathArg(project),
+            PathArg(key_id)
+        ))?;
+        if resp.status() == 404 {
+            Ok(false)
+        } else {
+            resp.into_result().map(|_| true)
         }
-        Ok(rv)
+    }
+
+    /// Lists all teams of an organization.
+    pub fn list_organization_teams(&self, org: &str) -> ApiResult<Vec<Team>> {
+        self.paginate(
+            &format!("/organizations/{}/teams/", PathArg(org)),
+            None,
+            ApiErrorKind::OrganizationNotFound,
+        )
+    }
+
+    /// Creates a new team in an organization.
+    pub fn create_team(&self, org: &str, name: &str, slug: Option<&str>) -> ApiResult<Team> {
+        #[derive(Serialize)]
+        struct NewTeam<'a> {
+            name: &'a str,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            slug: Option<&'a str>,
+        }
+
+        self.post(
+            &format!("/organizations/{}/teams/", PathArg(org)),
+            &NewTeam { name, slug },
+        )?
+        .convert_rnf(ApiErrorKind::OrganizationNotFound)
+    }
+
+    /// Adds an organization member to a team.
+    pub fn add_team_member(&self, org: &str, team: &str, member_id: &str) -> ApiResult<()> {
+        self.request(
+            Method::Post,
+            &format!(
+                "/organizations/{}/members/{}/teams/{}/",
+                PathArg(org),
+                PathArg(member_id),
+                PathArg(team)
+            ),
+        )?
+        .send()?
+        .into_result()
+        .map(|_| ())
+    }
+
+    /// Gives a team access to a project.
+    pub fn add_team_project(&self, org: &str, team: &str, project: &str) -> ApiResult<()> {
+        self.request(
+            Method::Post,
+            &format!(
+                "/projects/{}/{}/teams/{}/",
+                PathArg(org),
+                PathArg(project),
+                PathArg(team)
+            ),
+        )?
+        .send()?
+        .into_result()
+        .map(|_| ())
+    }
+
+    /// Lists the members of an organization, optionally filtered by a
+    /// search query such as `email:jane@example.com`.
+    pub fn list_organization_members(
+        &self,
+        org: &str,
+        query: Option<&str>,
+    ) -> ApiResult<Vec<OrganizationMember>> {
+        let mut path = format!("/organizations/{}/members/", PathArg(org));
+        if let Some(query) = query {
+            path.push_str(&format!("?query={}", SearchQueryArg(query)));
+        }
+        self.paginate(&path, None, ApiErrorKind::OrganizationNotFound)
+    }
+
+    /// Returns the general settings of a project.
+    pub fn get_project_settings(&self, org: &str, project: &str) -> ApiResult<ProjectSettings> {
+        self.get(&format!("/projects/{}/{}/", PathArg(org), PathArg(project)))?
+            .convert_rnf(ApiErrorKind::ProjectNotFound)
+    }
+
+    /// Updates the general settings of a project.  Settings that are `None`
+    /// are left untouched.
+    pub fn update_project_settings(
+        &self,
+        org: &str,
+        project: &str,
+        settings: &ProjectSettings,
+    ) -> ApiResult<ProjectSettings> {
+        self.put(
+            &format!("/projects/{}/{}/", PathArg(org), PathArg(project)),
+            settings,
+        )?
+        .convert_rnf(ApiErrorKind::ProjectNotFound)
+    }
+
+    /// Lists the issue alert rules of a project.
+    pub fn list_alert_rules(&self, org: &str, project: &str) -> ApiResult<Vec<AlertRule>> {
+        self.get(&format!(
+            "/projects/{}/{}/rules/",
+            PathArg(org),
+            PathArg(project)
+        ))?
+        .convert_rnf(ApiErrorKind::ProjectNotFound)
+    }
+
+    /// Creates a new issue alert rule.
+    pub fn create_alert_rule(
+        &self,
+        org: &str,
+        project: &str,
+        rule: &AlertRule,
+    ) -> ApiResult<AlertRule> {
+        self.post(
+            &format!("/projects/{}/{}/rules/", PathArg(org), PathArg(project)),
+            rule,
+        )?
+        .convert_rnf(ApiErrorKind::ProjectNotFound)
+    }
+
+    /// Replaces an existing issue alert rule.
+    pub fn update_alert_rule(
+        &self,
+        org: &str,
+        project: &str,
+        rule_id: &str,
+        rule: &AlertRule,
+    ) -> ApiResult<AlertRule> {
+        self.put(
+            &format!(
+                "/projects/{}/{}/rules/{}/",
+                PathArg(org),
+                PathArg(project),
+                PathArg(rule_id)
+            ),
+            rule,
+        )?
+        .convert_rnf(ApiErrorKind::ResourceNotFound)
+    }
+
+    /// Deletes an issue alert rule.  Returns `false` if the rule did not
+    /// exist.
+    pub fn delete_alert_rule(&self, org: &str, project: &str, rule_id: &str) -> ApiResult<bool> {
+        let resp = self.delete(&format!(
+            "/projects/{}/{}/rules/{}/",
+            PathArg(org),
+            PathArg(project),
+            PathArg(rule_id)
+        ))?;
+        if resp.status() == 404 {
+            Ok(false)
+        } else {
+            resp.into_result().map(|_| true)
+        }
+    }
+
+    /// Lists the metric alert rules of a project.
+    pub fn list_metric_alert_rules(
+        &self,
+        org: &str,
+        project: &str,
+    ) -> ApiResult<Vec<MetricAlertRule>> {
+        self.get(&format!(
+            "/projects/{}/{}/alert-rules/",
+            PathArg(org),
+            PathArg(project)
+        ))?
+        .convert_rnf(ApiErrorKind::ProjectNotFound)
+    }
+
+    /// Creates a new metric alert rule for a project.
+    pub fn create_metric_alert_rule(
+        &self,
+        org: &str,
+        project: &str,
+        rule: &MetricAlertRule,
+    ) -> ApiResult<MetricAlertRule> {
+        self.post(
+            &format!(
+                "/projects/{}/{}/alert-rules/",
+                PathArg(org),
+                PathArg(project)
+            ),
+            rule,
+        )?
+        .convert_rnf(ApiErrorKind::ProjectNotFound)
+    }
+
+    /// Deletes a metric alert rule.  Returns `false` if the rule did not
+    /// exist.
+    pub fn delete_metric_alert_rule(
+        &self,
+        org: &str,
+        project: &str,
+        rule_id: &str,
+    ) -> ApiResult<bool> {
+        let resp = self.delete(&format!(
+            "/projects/{}/{}/alert-rules/{}/",
+            PathArg(org),
+            PathArg(project),
+            PathArg(rule_id)
+        ))?;
+        if resp.status() == 404 {
+            Ok(false)
+        } else {
+            resp.into_result().map(|_| true)
+        }
+    }
+
+    /// Returns the ownership rules of a project.
+    pub fn get_project_ownership(&self, org: &str, project: &str) -> ApiResult<ProjectOwnership> {
+        self.get(&format!(
+            "/projects/{}/{}/ownership/",
+            PathArg(org),
+            PathArg(project)
+        ))?
+        .convert_rnf(ApiErrorKind::ProjectNotFound)
+    }
+
+    /// Replaces the ownership rules of a project.
+    pub fn update_project_ownership(
+        &self,
+        org: &str,
+        project: &str,
+        ownership: &ProjectOwnership,
+    ) -> ApiResult<ProjectOwnership> {
+        self.put(
+            &format!("/projects/{}/{}/ownership/", PathArg(org), PathArg(project)),
+            ownership,
+        )?
+        .convert_rnf(ApiErrorKind::ProjectNotFound)
+    }
+
+    /// Lists the inbound data filters of a project.
+    pub fn list_inbound_filters(&self, org: &str, project: &str) -> ApiResult<Vec<InboundFilter>> {
+        self.get(&format!(
+            "/projects/{}/{}/filters/",
+            PathArg(org),
+            PathArg(project)
+        ))?
+        .convert_rnf(ApiErrorKind::ProjectNotFound)
+    }
+
+    /// Enables or disables an inbound data filter.  Some filters (like
+    /// `legacy-browsers`) accept a list of sub-filters instead of a flag.
+    pub fn update_inbound_filter(
+        &self,
+        org: &str,
+        project: &str,
+        filter_id: &str,
+        active: &serde_json::Value,
+    ) -> ApiResult<()> {
+        #[derive(Serialize)]
+        struct FilterChanges<'a> {
+            active: &'a serde_json::Value,
+        }
+
+        self.put(
+            &format!(
+                "/projects/{}/{}/filters/{}/",
+                PathArg(org),
+                PathArg(project),
+                PathArg(filter_id)
+            ),
+            &FilterChanges { active },
+        )?
+        .into_result()
+        .map(|_| ())
+    }
+
+    /// Invites a new member to an organization.
+    pub fn invite_organization_member(
+        &self,
+        org: &str,
+        email: &str,
+        role: &str,
+        teams: &[&str],
+    ) -> ApiResult<OrganizationMember> {
+        #[derive(Serialize)]
+        struct NewMember<'a> {
+            email: &'a str,
+            role: &'a str,
+            teams: &'a [&'a str],
+        }
+
+        self.post(
+            &format!("/organizations/{}/members/", PathArg(org)),
+            &NewMember { email, role, teams },
+        )?
+        .convert_rnf(ApiErrorKind::OrganizationNotFound)
+    }
+
+    /// Removes a member from an organization.  Returns `false` if the
+    /// member did not exist.
+    pub fn remove_organization_member(&self, org: &str, member_id: &str) -> ApiResult<bool> {
+        let resp = self.delete(&format!(
+            "/organizations/{}/members/{}/",
+            PathArg(org),
+            PathArg(member_id)
+        ))?;
+        if resp.status() == 404 {
+            Ok(false)
+        } else {
+            resp.into_result().map(|_| true)
+        }
+    }
+
+    /// Returns the details of a project.
+    pub fn get_project_details(&self, org: &str, project: &str) -> ApiResult<ProjectDetails> {
+        self.get(&format!("/projects/{}/{}/", PathArg(org), PathArg(project)))?
+            .convert_rnf(ApiErrorKind::ProjectNotFound)
+    }
+
+    /// Replaces the custom symbol sources of a project.
+    pub fn update_symbol_sources(
+        &self,
+        org: &str,
+        project: &str,
+        sources: &[serde_json::Value],
+    ) -> ApiResult<()> {
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct SymbolSourcesChanges {
+            symbol_sources: String,
+        }
+
+        let symbol_sources =
+            serde_json::to_string(sources).context(ApiErrorKind::CannotSerializeAsJson)?;
+        self.put(
+            &format!("/projects/{}/{}/", PathArg(org), PathArg(project)),
+            &SymbolSourcesChanges { symbol_sources },
+        )?
+        .into_result()
+        .map(|_| ())
+    }
+
+    /// Returns the number of items of a data category (e.g. `error`) per
+    /// outcome (`accepted`, `filtered`, `rate_limited`, ...) for a project
+    /// over the given stats period.
+    pub fn get_project_outcomes(
+        &self,
+        org: &str,
+        project_id: &str,
+        category: &str,
+        stats_period: &str,
+    ) -> ApiResult<Vec<(String, u64)>> {
+        #[derive(Deserialize)]
+        struct OutcomeGroup {
+            by: HashMap<String, String>,
+            totals: HashMap<String, u64>,
+        }
+
+        #[derive(Deserialize)]
+        struct Outcomes {
+            groups: Vec<OutcomeGroup>,
+        }
+
+        let outcomes: Outcomes = self
+            .get(&format!(
+                "/organizations/{}/stats_v2/?field=sum(quantity)&groupBy=outcome\
+                 &project={}&category={}&statsPeriod={}&interval=1d",
+                PathArg(org),
+                QueryArg(project_id),
+                QueryArg(category),
+                QueryArg(stats_period)
+            ))?
+            .convert_rnf(ApiErrorKind::OrganizationNotFound)?;
+
+        Ok(outcomes
+            .groups
+            .into_iter()
+            .filter_map(|mut group| {
+                let outcome = group.by.remove("outcome")?;
+                let quantity = group.totals.get("sum(quantity)").copied().unwrap_or(0);
+                Some((outcome, quantity))
+            })
+            .collect())
+    }
+
+    /// Lists the environments of a project.  `visibility` is one of
+    /// `visible`, `hidden` or `all`.
+    pub fn list_project_environments(
+        &self,
+        org: &str,
+        project: &str,
+        visibility: &str,
+    ) -> ApiResult<Vec<Environment>> {
+        self.get(&format!(
+            "/projects/{}/{}/environments/?visibility={}",
+            PathArg(org),
+            PathArg(project),
+            QueryArg(visibility)
+        ))?
+        .convert_rnf(ApiErrorKind::ProjectNotFound)
+    }
+
+    /// Hides or unhides an environment of a project.
+    pub fn set_environment_hidden(
+        &self,
+        org: &str,
+        project: &str,
+        environment: &str,
+        is_hidden: bool,
+    ) -> ApiResult<Environment> {
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct EnvironmentChanges {
+            is_hidden: bool,
+        }
+
+        self.put(
+            &format!(
+                "/projects/{}/{}/environments/{}/",
+                PathArg(org),
+                PathArg(project),
+                PathArg(environment)
+            ),
+            &EnvironmentChanges { is_hidden },
+        )?
+        .convert_rnf(ApiErrorKind::ResourceNotFound)
     }
 
     /// List all repos associated with an organization
@@ -1383,6 +2633,28 @@ impl Api {
         }
         Ok(rv)
     }
+
+    /// Adds a repository to an organization.
+    pub fn create_repo(&self, org: &str, repo: &NewRepo) -> ApiResult<Repo> {
+        let path = format!("/organizations/{}/repos/", PathArg(org));
+        self.post(&path, repo)?
+            .convert_rnf(ApiErrorKind::OrganizationNotFound)
+    }
+
+    /// Removes a repository from an organization.
+    pub fn delete_repo(&self, org: &str, repo_id: &str) -> ApiResult<bool> {
+        let path = format!(
+            "/organizations/{}/repos/{}/",
+            PathArg(org),
+            PathArg(repo_id)
+        );
+        let resp = self.delete(&path)?;
+        if resp.status() == 404 {
+            Ok(false)
+        } else {
+            resp.into_result().map(|_| true)
+        }
+    }
 }
 
 fn send_req<W: Write>(
@@ -1409,9 +2681,8 @@ fn handle_req<W: Write>(
     progress_bar_mode: ProgressBarMode,
     read: &mut dyn FnMut(&mut [u8]) -> usize,
 ) -> ApiResult<(u32, Vec<String>)> {
-    if progress_bar_mode.active() {
-        handle.progress(true)?;
-    }
+    // the progress callback also aborts the transfer on cancellation
+    handle.progress(true)?;
 
     // enable verbose mode
     handle.verbose(true)?;
@@ -1428,7 +2699,7 @@ fn handle_req<W: Write>(
                     counts.write()[idx] = (uploaded / total * (len as f64)) as u64;
                     pb_progress.set_position(counts.read().iter().sum());
                 }
-                true
+                !cancel::is_cancelled()
             })?;
         } else if progress_bar_mode.active() {
             let pb_progress = pb.clone();
@@ -1455,8 +2726,10 @@ fn handle_req<W: Write>(
                         pb.take().unwrap().finish_and_clear();
                     }
                 }
-                true
+                !cancel::is_cancelled()
             })?;
+        } else {
+            handle.progress_function(|_, _, _, _| !cancel::is_cancelled())?;
         }
 
         handle.read_function(move |buf| Ok(read(buf)))?;
@@ -1611,6 +2884,14 @@ impl ApiRequest {
         Ok(self)
     }
 
+    /// sets a raw request body with the given content type.
+    pub fn with_raw_body(mut self, body: Vec<u8>, content_type: &str) -> ApiResult<Self> {
+        self.body = Some(body);
+        self.headers
+            .append(&format!("Content-Type: {}", content_type))?;
+        Ok(self)
+    }
+
     /// attaches some form data to the request.
     pub fn with_form_data(mut self, form: curl::easy::Form) -> ApiResult<Self> {
         debug!("sending form data");
@@ -1655,6 +2936,9 @@ impl ApiRequest {
     /// Sends the request and writes response data into the given file
     /// instead of the response object's in memory buffer.
     pub fn send_into<W: Write>(&mut self, out: &mut W) -> ApiResult<ApiResponse> {
+        if cancel::is_cancelled() {
+            return Err(ApiErrorKind::RequestCancelled.into());
+        }
         let headers = self.get_headers();
         self.handle.http_headers(headers)?;
         let body = self.body.as_deref();
@@ -1838,6 +3122,9 @@ impl ApiResponse {
 fn log_headers(is_response: bool, data: &[u8]) {
     lazy_static! {
         static ref AUTH_RE: Regex = Regex::new(r"(?i)(authorization):\s*([\w]+)\s+(.*)").unwrap();
+        static ref TOKEN_RE: Regex = Regex::new(r"(?i)(private-token|job-token):\s*(.*)").unwrap();
+        static ref SENTRY_AUTH_RE: Regex =
+            Regex::new(r"(?i)(sentry_(?:key|secret))=([^,\s]+)").unwrap();
     }
     if let Ok(header) = std::str::from_utf8(data) {
         for line in header.lines() {
@@ -1853,6 +3140,20 @@ fn log_headers(is_response: bool, data: &[u8]) {
                 };
                 format!("{}: {} {}", &caps[1], &caps[2], info)
             });
+            let replaced = TOKEN_RE.replace_all(&replaced, |caps: &Captures<'_>| {
+                format!(
+                    "{}: {}***",
+                    &caps[1],
+                    caps[2].chars().take(8).collect::<String>()
+                )
+            });
+            let replaced = SENTRY_AUTH_RE.replace_all(&replaced, |caps: &Captures<'_>| {
+                format!(
+                    "{}={}***",
+                    &caps[1],
+                    caps[2].chars().take(8).collect::<String>()
+                )
+            });
             debug!("{} {}", if is_response { ">" } else { "<" }, replaced);
         }
     }
@@ -1896,33 +3197,202 @@ pub struct Artifact {
     pub headers: HashMap<String, String>,
 }
 
-impl Artifact {
-    pub fn get_header<'a, 'b>(&'a self, key: &'b str) -> Option<&'a str> {
-        let ikey = key.to_lowercase();
-        for (k, v) in &self.headers {
-            if k.to_lowercase() == ikey {
-                return Some(v.as_str());
-            }
+impl Artifact {
+    pub fn get_header<'a, 'b>(&'a self, key: &'b str) -> Option<&'a str> {
+        let ikey = key.to_lowercase();
+        for (k, v) in &self.headers {
+            if k.to_lowercase() == ikey {
+                return Some(v.as_str());
+            }
+        }
+        None
+    }
+
+    pub fn get_sourcemap_reference(&self) -> Option<&str> {
+        get_sourcemap_reference_from_headers(self.headers.iter())
+    }
+}
+
+/// Information for new releases
+#[derive(Debug, Serialize, Default)]
+pub struct NewRelease {
+    pub version: String,
+    pub projects: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(rename = "dateStarted", skip_serializing_if = "Option::is_none")]
+    pub date_started: Option<DateTime<Utc>>,
+    #[serde(rename = "dateReleased", skip_serializing_if = "Option::is_none")]
+    pub date_released: Option<DateTime<Utc>>,
+}
+
+/// A client key (DSN) of a project.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectKey {
+    pub id: String,
+    pub name: String,
+    pub is_active: bool,
+    pub dsn: ProjectKeyDsn,
+    #[serde(default)]
+    pub rate_limit: Option<RateLimit>,
+    #[serde(default)]
+    pub date_created: Option<DateTime<Utc>>,
+}
+
+/// The rate limit of a client key: at most `count` events per `window`
+/// seconds.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RateLimit {
+    pub window: u64,
+    pub count: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProjectKeyDsn {
+    pub public: String,
+    pub secret: String,
+}
+
+/// The general settings of a project that can be managed from the CLI.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub platform: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject_prefix: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolve_age: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_domains: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_scrubber: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scrub_ip_addresses: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sensitive_fields: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub safe_fields: Option<Vec<String>>,
+}
+
+/// An issue alert rule of a project.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertRule {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action_match: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter_match: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frequency: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conditions: Option<Vec<serde_json::Value>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filters: Option<Vec<serde_json::Value>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actions: Option<Vec<serde_json::Value>>,
+}
+
+impl AlertRule {
+    /// Returns this rule with all fields it leaves unset taken from `base`.
+    pub fn merged_onto(self, base: &AlertRule) -> AlertRule {
+        AlertRule {
+            id: self.id.or_else(|| base.id.clone()),
+            name: self.name,
+            environment: self.environment.or_else(|| base.environment.clone()),
+            action_match: self.action_match.or_else(|| base.action_match.clone()),
+            filter_match: self.filter_match.or_else(|| base.filter_match.clone()),
+            frequency: self.frequency.or(base.frequency),
+            conditions: self.conditions.or_else(|| base.conditions.clone()),
+            filters: self.filters.or_else(|| base.filters.clone()),
+            actions: self.actions.or_else(|| base.actions.clone()),
+        }
+    }
+}
+
+/// A metric alert rule.  Apart from the name the definition (dataset,
+/// aggregate, triggers, ...) is passed through as is.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricAlertRule {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub name: String,
+    #[serde(flatten)]
+    pub definition: serde_json::Map<String, serde_json::Value>,
+}
+
+/// The ownership rules of a project.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectOwnership {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallthrough: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_assignment: Option<bool>,
+}
+
+impl ProjectOwnership {
+    /// Returns these rules with all fields they leave unset taken from `base`.
+    pub fn merged_onto(self, base: &ProjectOwnership) -> ProjectOwnership {
+        ProjectOwnership {
+            raw: self.raw.or_else(|| base.raw.clone()),
+            fallthrough: self.fallthrough.or(base.fallthrough),
+            auto_assignment: self.auto_assignment.or(base.auto_assignment),
         }
-        None
     }
+}
 
-    pub fn get_sourcemap_reference(&self) -> Option<&str> {
-        get_sourcemap_reference_from_headers(self.headers.iter())
-    }
+/// An inbound data filter of a project.
+#[derive(Debug, Deserialize)]
+pub struct InboundFilter {
+    pub id: String,
+    pub active: serde_json::Value,
 }
 
-/// Information for new releases
+/// Details of a single project including its options.
+#[derive(Debug, Deserialize)]
+pub struct ProjectDetails {
+    pub id: String,
+    pub slug: String,
+    pub name: String,
+    #[serde(default)]
+    pub platform: Option<String>,
+    #[serde(default)]
+    pub options: HashMap<String, serde_json::Value>,
+    /// The custom symbol sources, serialized as a JSON string.
+    #[serde(default, rename = "symbolSources")]
+    pub symbol_sources: Option<String>,
+}
+
+/// An environment of a project.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Environment {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub is_hidden: bool,
+}
+
+/// Information for new projects
 #[derive(Debug, Serialize, Default)]
-pub struct NewRelease {
-    pub version: String,
-    pub projects: Vec<String>,
+pub struct NewProject {
+    pub name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub url: Option<String>,
-    #[serde(rename = "dateStarted", skip_serializing_if = "Option::is_none")]
-    pub date_started: Option<DateTime<Utc>>,
-    #[serde(rename = "dateReleased", skip_serializing_if = "Option::is_none")]
-    pub date_released: Option<DateTime<Utc>>,
+    pub slug: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub platform: Option<String>,
+    pub default_rules: bool,
 }
 
 /// A head commit on a release
@@ -1985,6 +3455,34 @@ pub struct ReleaseInfo {
         skip_serializing_if = "Option::is_none"
     )]
     pub last_commit: Option<LastCommit>,
+    #[serde(default, rename = "lastDeploy")]
+    pub last_deploy: Option<Box<Deploy>>,
+}
+
+/// A commit associated with a release.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseCommit {
+    pub id: String,
+    pub message: Option<String>,
+    pub date_created: DateTime<Utc>,
+    pub author: Option<CommitAuthor>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommitAuthor {
+    pub name: Option<String>,
+    pub email: Option<String>,
+}
+
+/// A file changed by one of the commits of a release.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitFileChange {
+    pub filename: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+    pub commit_message: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -2015,16 +3513,137 @@ struct GitHubRelease {
     assets: Vec<GitHubAsset>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct GitHubComparison {
+    pub total_commits: usize,
+    pub commits: Vec<GitHubCommit>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitHubCommit {
+    pub sha: String,
+    pub commit: GitHubCommitDetails,
+    #[serde(default)]
+    pub files: Vec<GitHubFile>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitHubCommitDetails {
+    pub message: Option<String>,
+    pub author: Option<GitHubCommitAuthor>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitHubCommitAuthor {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub date: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitHubFile {
+    pub filename: String,
+    pub status: String,
+}
+
+/// Adds the headers for the GitHub API to a request.
+fn github_authorize(req: ApiRequest, token: Option<&str>) -> ApiResult<ApiRequest> {
+    let req = req.with_header("Accept", "application/vnd.github.v3+json")?;
+    match token {
+        Some(token) => req.with_header("Authorization", &format!("token {}", token)),
+        None => Ok(req),
+    }
+}
+
+/// A token to authenticate with the GitLab API.
+#[derive(Debug, Clone, Copy)]
+pub enum GitLabToken<'a> {
+    /// A personal, project or group access token.
+    Access(&'a str),
+    /// The `CI_JOB_TOKEN` of a running GitLab CI job.
+    Job(&'a str),
+}
+
+impl<'a> GitLabToken<'a> {
+    fn authorize(self, req: ApiRequest) -> ApiResult<ApiRequest> {
+        match self {
+            GitLabToken::Access(token) => {
+                req.with_header("Authorization", &format!("Bearer {}", token))
+            }
+            GitLabToken::Job(token) => req.with_header("JOB-TOKEN", token),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GitLabComparison {
+    commits: Vec<GitLabCommit>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitLabCommit {
+    pub id: String,
+    pub message: Option<String>,
+    pub author_name: Option<String>,
+    pub author_email: Option<String>,
+    pub authored_date: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitLabDiff {
+    pub new_path: String,
+    #[serde(default)]
+    pub new_file: bool,
+    #[serde(default)]
+    pub deleted_file: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct AppStoreBuilds {
+    data: Vec<AppStoreResource>,
+    #[serde(default)]
+    included: Vec<AppStoreResource>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AppStoreResource {
+    #[serde(rename = "type")]
+    ty: String,
+    #[serde(default)]
+    attributes: AppStoreBuildBundleAttributes,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AppStoreBuildBundleAttributes {
+    #[serde(rename = "dSYMUrl")]
+    dsym_url: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct RegistryRelease {
     version: String,
     file_urls: HashMap<String, String>,
+    #[serde(default)]
+    files: HashMap<String, RegistryFile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RegistryVersions {
+    #[serde(default)]
+    versions: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RegistryFile {
+    #[serde(default)]
+    checksums: HashMap<String, String>,
 }
 
 /// Information about sentry CLI releases
 pub struct SentryCliRelease {
     pub version: String,
     pub download_url: String,
+    pub sha256: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -2078,10 +3697,24 @@ struct MissingChecksumsResponse {
 /// Change information for issue bulk updates.
 #[derive(Serialize, Default)]
 pub struct IssueChanges {
-    #[serde(rename = "status")]
+    #[serde(rename = "status", skip_serializing_if = "Option::is_none")]
     pub new_status: Option<String>,
-    #[serde(rename = "snoozeDuration")]
+    #[serde(rename = "snoozeDuration", skip_serializing_if = "Option::is_none")]
     pub snooze_duration: Option<i64>,
+    #[serde(rename = "assignedTo", skip_serializing_if = "Option::is_none")]
+    pub assigned_to: Option<String>,
+    #[serde(rename = "isSubscribed", skip_serializing_if = "Option::is_none")]
+    pub is_subscribed: Option<bool>,
+}
+
+impl IssueChanges {
+    /// Returns `true` if no changes were requested.
+    pub fn is_empty(&self) -> bool {
+        self.new_status.is_none()
+            && self.snooze_duration.is_none()
+            && self.assigned_to.is_none()
+            && self.is_subscribed.is_none()
+    }
 }
 
 /// Filters for issue bulk requests.
@@ -2094,6 +3727,8 @@ pub enum IssueFilter {
     ExplicitIds(Vec<u64>),
     /// Match on issues with the given status
     Status(String),
+    /// Match on issues matching a Sentry search query
+    Query(String),
 }
 
 impl IssueFilter {
@@ -2115,17 +3750,235 @@ impl IssueFilter {
             IssueFilter::Status(ref status) => {
                 rv.push(format!("status={}", status));
             }
+            IssueFilter::Query(ref query) => {
+                rv.push(format!("query={}", SearchQueryArg(query)));
+            }
         }
         Some(rv.join("&"))
     }
 }
 
+/// Parameters for listing issues.
+#[derive(Debug, Default)]
+pub struct IssueQuery<'a> {
+    /// A Sentry search query, e.g. `is:unresolved release:1.0.0`.
+    pub query: Option<&'a str>,
+    /// The sort order (`date`, `new`, `priority`, `freq` or `user`).
+    pub sort: Option<&'a str>,
+    /// The period of the event histogram attached to each issue (`24h` or `14d`).
+    pub stats_period: Option<&'a str>,
+}
+
+impl<'a> IssueQuery<'a> {
+    fn get_query_params(&self) -> Vec<String> {
+        let mut rv = vec![];
+        if let Some(query) = self.query {
+            rv.push(format!("query={}", SearchQueryArg(query)));
+        }
+        if let Some(sort) = self.sort {
+            rv.push(format!("sort={}", QueryArg(sort)));
+        }
+        if let Some(stats_period) = self.stats_period {
+            rv.push(format!("statsPeriod={}", QueryArg(stats_period)));
+        }
+        rv
+    }
+}
+
+/// The project an issue belongs to.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct IssueProject {
+    pub id: String,
+    pub slug: String,
+    pub name: String,
+}
+
+/// An issue (group of events) as returned by the issues endpoints.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Issue {
+    pub id: String,
+    pub short_id: String,
+    pub title: String,
+    #[serde(default)]
+    pub culprit: Option<String>,
+    #[serde(default)]
+    pub permalink: Option<String>,
+    #[serde(default)]
+    pub level: Option<String>,
+    pub status: String,
+    #[serde(default)]
+    pub count: String,
+    #[serde(default)]
+    pub user_count: u64,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    #[serde(default)]
+    pub project: Option<IssueProject>,
+    /// Event histograms keyed by period, only present if requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<HashMap<String, Vec<(i64, u64)>>>,
+    #[serde(default)]
+    pub status_details: IssueStatusDetails,
+}
+
+/// Details of an issue's status, e.g. the release it was resolved in.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct IssueStatusDetails {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub in_release: Option<String>,
+}
+
+/// A tag on an event.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EventTag {
+    pub key: String,
+    pub value: String,
+}
+
+/// A single event of an issue.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct IssueEvent {
+    #[serde(rename = "eventID")]
+    pub event_id: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub date_created: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub platform: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<EventTag>,
+    /// The raw event interfaces (exception, message, breadcrumbs, ...).
+    #[serde(default)]
+    pub entries: Vec<serde_json::Value>,
+}
+
+impl IssueEvent {
+    fn get_entry<T: DeserializeOwned>(&self, ty: &str) -> Option<T> {
+        self.entries
+            .iter()
+            .find(|entry| entry.get("type").and_then(|x| x.as_str()) == Some(ty))
+            .and_then(|entry| entry.get("data"))
+            .and_then(|data| serde_json::from_value(data.clone()).ok())
+    }
+
+    /// Returns the exceptions of this event in the order they were raised.
+    pub fn exceptions(&self) -> Vec<EventException> {
+        #[derive(Deserialize)]
+        struct ExceptionData {
+            #[serde(default)]
+            values: Vec<EventException>,
+        }
+
+        self.get_entry::<ExceptionData>("exception")
+            .map(|data| data.values)
+            .unwrap_or_default()
+    }
+
+    /// Returns the formatted log message of this event if there is one.
+    pub fn log_message(&self) -> Option<String> {
+        #[derive(Deserialize)]
+        struct MessageData {
+            formatted: Option<String>,
+        }
+
+        self.get_entry::<MessageData>("message")
+            .and_then(|data| data.formatted)
+    }
+}
+
+/// An exception from the exception interface of an event.
+#[derive(Deserialize, Debug)]
+pub struct EventException {
+    #[serde(default, rename = "type")]
+    pub ty: Option<String>,
+    #[serde(default)]
+    pub value: Option<String>,
+    #[serde(default)]
+    pub module: Option<String>,
+    #[serde(default)]
+    pub stacktrace: Option<EventStacktrace>,
+}
+
+/// A stacktrace as returned by the API with the oldest frame first.
+#[derive(Deserialize, Debug)]
+pub struct EventStacktrace {
+    #[serde(default)]
+    pub frames: Vec<EventFrame>,
+}
+
+/// A single (symbolicated) stack frame.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EventFrame {
+    #[serde(default)]
+    pub filename: Option<String>,
+    #[serde(default)]
+    pub abs_path: Option<String>,
+    #[serde(default)]
+    pub module: Option<String>,
+    #[serde(default)]
+    pub function: Option<String>,
+    #[serde(default)]
+    pub line_no: Option<u64>,
+    #[serde(default)]
+    pub col_no: Option<u64>,
+    #[serde(default)]
+    pub in_app: Option<bool>,
+    #[serde(default)]
+    pub context: Vec<(u64, Option<String>)>,
+    #[serde(default)]
+    pub vars: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+/// An integration installed in an organization.
+#[derive(Deserialize, Debug)]
+pub struct Integration {
+    pub id: String,
+    pub name: String,
+    pub provider: IntegrationProvider,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct IntegrationProvider {
+    pub key: String,
+    pub name: String,
+}
+
+/// An issue of an external tracker linked to a Sentry issue.
+#[derive(Deserialize, Debug)]
+pub struct ExternalIssue {
+    pub key: String,
+    pub url: Option<String>,
+}
+
 #[derive(Deserialize)]
 pub struct AssociateDsymsResponse {
     #[serde(rename = "associatedDsymFiles")]
     pub associated_dsyms: Vec<DebugInfoFile>,
 }
 
+/// A member of an organization.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OrganizationMember {
+    pub id: String,
+    pub email: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub role: String,
+    /// `true` if the member has not accepted the invitation yet.
+    #[serde(default)]
+    pub pending: bool,
+    #[serde(default)]
+    pub date_created: Option<DateTime<Utc>>,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct Team {
     pub id: String,
@@ -2152,9 +4005,37 @@ pub struct Monitor {
     pub id: String,
     pub name: String,
     pub status: String,
+    #[serde(default)]
+    pub slug: Option<String>,
+    #[serde(default)]
+    pub config: Option<MonitorConfig>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// The schedule of a monitor and how late check-ins may be.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct MonitorConfig {
+    pub schedule_type: String,
+    /// A crontab expression, or an interval like `[1, "day"]`.
+    pub schedule: serde_json::Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checkin_margin: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_runtime: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+}
+
+/// Information for new monitors
+#[derive(Debug, Serialize)]
+pub struct NewMonitor {
+    pub name: String,
+    pub slug: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+    pub config: MonitorConfig,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum MonitorStatus {
     Unknown,
@@ -2173,6 +4054,9 @@ pub struct MonitorCheckIn {
 #[derive(Debug, Serialize)]
 pub struct CreateMonitorCheckIn {
     pub status: MonitorStatus,
+    /// Creates or updates the monitor along with the check-in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monitor_config: Option<MonitorConfig>,
 }
 
 #[derive(Debug, Serialize, Default)]
@@ -2181,6 +4065,9 @@ pub struct UpdateMonitorCheckIn {
     pub status: Option<MonitorStatus>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub duration: Option<u64>,
+    /// The resources used by the wrapped command.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource_usage: Option<ResourceUsage>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -2200,6 +4087,18 @@ pub struct Repo {
     pub date_created: DateTime<Utc>,
 }
 
+/// A repository to add to an organization.
+#[derive(Debug, Serialize)]
+pub struct NewRepo {
+    /// The provider of the repository, e.g. `integrations:github`.
+    pub provider: String,
+    /// The ID of the integration installation the repository belongs to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub installation: Option<String>,
+    /// The name of the repository on the provider, e.g. `getsentry/sentry`.
+    pub identifier: String,
+}
+
 impl fmt::Display for Repo {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}:{}", &self.provider.id, &self.id)?;
@@ -2212,6 +4111,8 @@ impl fmt::Display for Repo {
 
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct Deploy {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
     #[serde(rename = "environment")]
     pub env: String,
     pub name: Option<String>,
@@ -2222,6 +4123,15 @@ pub struct Deploy {
     pub finished: Option<DateTime<Utc>>,
 }
 
+/// A deploy together with the version of its release.
+#[derive(Debug, Deserialize)]
+pub struct ReleaseDeploy {
+    #[serde(rename = "releaseVersion")]
+    pub version: String,
+    #[serde(flatten)]
+    pub deploy: Deploy,
+}
+
 impl Deploy {
     /// Returns the name of this deploy, defaulting to `"unnamed"`.
     pub fn name(&self) -> &str {
@@ -2230,6 +4140,11 @@ impl Deploy {
             Some(name) => name,
         }
     }
+
+    /// Returns when this deploy finished, falling back to its start.
+    pub fn date(&self) -> Option<DateTime<Utc>> {
+        self.finished.or(self.started)
+    }
 }
 
 #[derive(Debug, Deserialize, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
@@ -2302,6 +4217,10 @@ pub enum ChunkUploadCapability {
     /// Uploads of source archives
     Sources,
 
+    /// Artifact bundles that are assembled for a release and dist given
+    /// with the request, rather than in the bundle
+    ArtifactBundles,
+
     /// Upload of BCSymbolMap and PList auxiliary DIFs
     BcSymbolmap,
 
@@ -2319,6 +4238,7 @@ impl<'de> Deserialize<'de> for ChunkUploadCapability {
             "release_files" => ChunkUploadCapability::ReleaseFiles,
             "pdbs" => ChunkUploadCapability::Pdbs,
             "sources" => ChunkUploadCapability::Sources,
+            "artifact_bundles" => ChunkUploadCapability::ArtifactBundles,
             "bcsymbolmaps" => ChunkUploadCapability::BcSymbolmap,
             _ => ChunkUploadCapability::Unknown,
         })
@@ -2411,6 +4331,16 @@ pub struct ChunkedArtifactRequest<'a> {
     pub chunks: &'a [Digest],
 }
 
+#[derive(Debug, Serialize)]
+pub struct ChunkedArtifactBundleRequest<'a> {
+    pub checksum: Digest,
+    pub chunks: &'a [Digest],
+    pub projects: &'a [&'a str],
+    pub version: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dist: Option<&'a str>,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AssembleArtifactsResponse {
@@ -1,5 +1,13 @@
 _SENTRY_TRACEBACK_FILE="___SENTRY_TRACEBACK_FILE___"
 _SENTRY_LOG_FILE="___SENTRY_LOG_FILE___"
+_SENTRY_COMMANDS_FILE="___SENTRY_COMMANDS_FILE___"
+_SENTRY_COMMAND_BREADCRUMBS="___SENTRY_COMMAND_BREADCRUMBS___"
+_SENTRY_MONITOR_SLUG="___SENTRY_MONITOR_SLUG___"
+_SENTRY_MONITOR_ORG="___SENTRY_MONITOR_ORG___"
+_SENTRY_SPANS_FILE="___SENTRY_SPANS_FILE___"
+_SENTRY_TRACING="___SENTRY_TRACING___"
+_SENTRY_SCRIPT="$0"
+_SENTRY_CHECKIN_ID=""
 
 if [ "$SENTRY_CLI_NO_EXIT_TRAP" != 1 ]; then
   trap _sentry_exit_trap EXIT
@@ -14,7 +22,9 @@ _sentry_exit_trap() {
   if [[ $_exit_code != 0 && "${_sentry_shown_traceback}" != 1 ]]; then
     _sentry_err_trap "$_command" "$_exit_code"
   fi
-  rm -f "$_SENTRY_TRACEBACK_FILE" "$_SENTRY_LOG_FILE"
+  _sentry_finish_checkin "$_exit_code"
+  _sentry_send_transaction "$_exit_code"
+  rm -f "$_SENTRY_TRACEBACK_FILE" "$_SENTRY_LOG_FILE" "$_SENTRY_COMMANDS_FILE" "$_SENTRY_SPANS_FILE"
   exit $_exit_code
 }
 
@@ -27,15 +37,75 @@ _sentry_err_trap() {
   if [ "x$2" != x ]; then
     _exit_code="$2"
   fi
+  # the debug trap already recorded the failed command before this trap
+  _sentry_last_command=""
   _sentry_traceback 1
   echo "@command:${_command}" >> "$_SENTRY_TRACEBACK_FILE"
   echo "@exit_code:${_exit_code}" >> "$_SENTRY_TRACEBACK_FILE"
 
   : >> "$_SENTRY_LOG_FILE"
-  export SENTRY_LAST_EVENT=$(___SENTRY_CLI___ bash-hook --send-event --traceback "$_SENTRY_TRACEBACK_FILE" --log "$_SENTRY_LOG_FILE" ___SENTRY_NO_ENVIRON___)
+  export SENTRY_LAST_EVENT=$(___SENTRY_CLI___ bash-hook --send-event --traceback "$_SENTRY_TRACEBACK_FILE" --log "$_SENTRY_LOG_FILE" ___SENTRY_COMMANDS_ARGS___ ___SENTRY_NO_ENVIRON___)
   rm -f "$_SENTRY_TRACEBACK_FILE" "$_SENTRY_LOG_FILE"
 }
 
+_sentry_record_command() {
+  if [ "x$_sentry_last_command" != x ]; then
+    printf '%s\t%s\t%s\n' "$_sentry_last_command_ts" "$1" "$_sentry_last_command" >> "$_SENTRY_COMMANDS_FILE"
+  fi
+  _sentry_last_command=""
+}
+
+_sentry_debug_trap() {
+  local _exit_code="$?"
+  case "$BASH_COMMAND" in
+    _sentry_*) return ;;
+  esac
+  _sentry_record_command "$_exit_code"
+  _sentry_last_command="${BASH_COMMAND//$'\n'/\\n}"
+  _sentry_last_command="${_sentry_last_command//$'\t'/ }"
+  _sentry_last_command_ts="${EPOCHSECONDS:-$(date +%s)}"
+}
+
+_sentry_now() {
+  if [ "x$EPOCHREALTIME" != x ]; then
+    echo "${EPOCHREALTIME/,/.}"
+  else
+    date +%s.%N
+  fi
+}
+
+# marks a section of the script that shows up as a span of the transaction
+sentry_span_start() {
+  if [ "$_SENTRY_TRACING" = 1 ]; then
+    printf 'start\t%s\t%s\n' "$(_sentry_now)" "${1:-section}" >> "$_SENTRY_SPANS_FILE"
+  fi
+}
+
+sentry_span_end() {
+  if [ "$_SENTRY_TRACING" = 1 ]; then
+    printf 'end\t%s\n' "$(_sentry_now)" >> "$_SENTRY_SPANS_FILE"
+  fi
+}
+
+_sentry_send_transaction() {
+  if [ "$_SENTRY_TRACING" = 1 ]; then
+    printf 'exit\t%s\n' "$(_sentry_now)" >> "$_SENTRY_SPANS_FILE"
+    ___SENTRY_CLI___ bash-hook --send-transaction --spans "$_SENTRY_SPANS_FILE" --exit-code "$1" --script "$_SENTRY_SCRIPT" > /dev/null || true
+  fi
+}
+
+_sentry_start_checkin() {
+  if [ "x$_SENTRY_MONITOR_SLUG" != x ]; then
+    _SENTRY_CHECKIN_ID=$(___SENTRY_CLI___ bash-hook --start-checkin --monitor-slug "$_SENTRY_MONITOR_SLUG" ${_SENTRY_MONITOR_ORG:+--org "$_SENTRY_MONITOR_ORG"} || true)
+  fi
+}
+
+_sentry_finish_checkin() {
+  if [ "x$_SENTRY_CHECKIN_ID" != x ]; then
+    ___SENTRY_CLI___ bash-hook --finish-checkin "$_SENTRY_CHECKIN_ID" --exit-code "$1" --duration "$SECONDS" --monitor-slug "$_SENTRY_MONITOR_SLUG" ${_SENTRY_MONITOR_ORG:+--org "$_SENTRY_MONITOR_ORG"} || true
+  fi
+}
+
 _sentry_traceback() {
   _sentry_shown_traceback=1
   local -i start=$(( ${1:-0} + 1 ))
@@ -54,6 +124,10 @@ _sentry_traceback() {
 }
 
 : > "$_SENTRY_LOG_FILE"
+if [ "$_SENTRY_TRACING" = 1 ]; then
+  printf 'run\t%s\n' "$(_sentry_now)" > "$_SENTRY_SPANS_FILE"
+fi
+_sentry_start_checkin
 
 if command -v perl >/dev/null; then
   exec \
@@ -64,3 +138,8 @@ else
     1> >(tee >(awk '{ system(""); print strftime("%Y-%m-%d %H:%M:%S %z:"), "stdout:", $0; system(""); }' >> "$_SENTRY_LOG_FILE")) \
     2> >(tee >(awk '{ system(""); print strftime("%Y-%m-%d %H:%M:%S %z:"), "stderr:", $0; system(""); }' >> "$_SENTRY_LOG_FILE") >&2)
 fi
+
+if [ "$_SENTRY_COMMAND_BREADCRUMBS" = 1 ]; then
+  : > "$_SENTRY_COMMANDS_FILE"
+  trap _sentry_debug_trap DEBUG
+fi
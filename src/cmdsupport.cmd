@@ -0,0 +1,17 @@
+@echo off
+setlocal
+set "_SENTRY_TRACEBACK_FILE=%TEMP%\.sentry-%RANDOM%%RANDOM%.traceback"
+set "_SENTRY_LOG_FILE=%TEMP%\.sentry-%RANDOM%%RANDOM%.out"
+
+call %*
+set _SENTRY_EXIT_CODE=%ERRORLEVEL%
+
+if %_SENTRY_EXIT_CODE% NEQ 0 (
+  > "%_SENTRY_TRACEBACK_FILE%" echo @command:%*
+  >> "%_SENTRY_TRACEBACK_FILE%" echo @exit_code:%_SENTRY_EXIT_CODE%
+  type nul > "%_SENTRY_LOG_FILE%"
+  "___SENTRY_CLI___" powershell-hook --send-event --traceback "%_SENTRY_TRACEBACK_FILE%" --log "%_SENTRY_LOG_FILE%" ___SENTRY_NO_ENVIRON___ > nul
+  del /q "%_SENTRY_TRACEBACK_FILE%" "%_SENTRY_LOG_FILE%" 2> nul
+)
+
+exit /b %_SENTRY_EXIT_CODE%
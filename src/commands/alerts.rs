@@ -0,0 +1,298 @@
+//! Implements a command for managing alert rules.
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+
+use clap::{App, AppSettings, Arg, ArgMatches};
+use failure::{bail, Error, ResultExt};
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+
+use crate::api::{AlertRule, Api, MetricAlertRule};
+use crate::config::Config;
+use crate::utils::args::ArgExt;
+use crate::utils::formatting::Table;
+use crate::utils::ui::confirm;
+
+lazy_static! {
+    static ref VARIABLE_RE: Regex = Regex::new(r"\$\{([A-Za-z0-9_]+)\}").unwrap();
+}
+
+pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.about("Manage issue and metric alert rules.")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .org_project_args()
+        .subcommand(
+            App::new("list")
+                .about("List the alert rules of a project.")
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help("Print the alert rules as JSON."),
+                ),
+        )
+        .subcommand(
+            App::new("create")
+                .about("Create alert rules from a YAML or JSON spec.")
+                .long_about(
+                    "Create alert rules from a YAML or JSON spec.{n}{n}\
+                     The spec contains one rule or a list of rules. Each rule has a `type` \
+                     (`issue` or `metric`) and the fields of the respective API. \
+                     `${ORG}`, `${PROJECT}` and variables passed with `--var` are \
+                     replaced in the string values of the spec.",
+                )
+                .arg(
+                    Arg::with_name("path")
+                        .value_name("PATH")
+                        .required(true)
+                        .index(1)
+                        .help("The path to the alert rule spec."),
+                )
+                .arg(
+                    Arg::with_name("vars")
+                        .long("var")
+                        .value_name("KEY=VALUE")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("Define a variable for the spec."),
+                ),
+        )
+        .subcommand(
+            App::new("delete")
+                .about("Delete an alert rule.")
+                .arg(
+                    Arg::with_name("id")
+                        .value_name("ID")
+                        .required(true)
+                        .index(1)
+                        .help("The ID of the alert rule."),
+                )
+                .arg(
+                    Arg::with_name("type")
+                        .long("type")
+                        .value_name("TYPE")
+                        .possible_values(&["issue", "metric"])
+                        .help("The type of the alert rule. [defaults to 'issue']"),
+                )
+                .arg(
+                    Arg::with_name("confirm")
+                        .long("confirm")
+                        .help("Skip the confirmation prompt."),
+                ),
+        )
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum AlertSpec {
+    Issue(AlertRule),
+    Metric(MetricAlertRule),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AlertSpecs {
+    Many(Vec<AlertSpec>),
+    One(AlertSpec),
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AlertRules {
+    issue_alerts: Vec<AlertRule>,
+    metric_alerts: Vec<MetricAlertRule>,
+}
+
+/// Replaces `${KEY}` references in a string with the given variables.
+fn expand_string(value: &str, vars: &HashMap<String, String>, missing: &mut Vec<String>) -> String {
+    VARIABLE_RE
+        .replace_all(value, |caps: &Captures<'_>| match vars.get(&caps[1]) {
+            Some(value) => value.clone(),
+            None => {
+                missing.push(caps[1].to_string());
+                String::new()
+            }
+        })
+        .into_owned()
+}
+
+/// Replaces `${KEY}` references in the string scalars of a parsed spec, so
+/// that values cannot change the structure of the spec.  A scalar that is
+/// only a reference to a number or boolean becomes that number or boolean.
+fn expand_value(value: Value, vars: &HashMap<String, String>, missing: &mut Vec<String>) -> Value {
+    match value {
+        Value::String(string) => {
+            let expanded = expand_string(&string, vars, missing);
+            let is_reference = matches!(
+                VARIABLE_RE.find(&string),
+                Some(m) if m.start() == 0 && m.end() == string.len()
+            );
+            match serde_yaml::from_str(&expanded) {
+                Ok(scalar @ Value::Number(_)) | Ok(scalar @ Value::Bool(_)) if is_reference => {
+                    scalar
+                }
+                _ => Value::String(expanded),
+            }
+        }
+        Value::Sequence(items) => Value::Sequence(
+            items
+                .into_iter()
+                .map(|item| expand_value(item, vars, missing))
+                .collect(),
+        ),
+        Value::Mapping(mapping) => Value::Mapping(
+            mapping
+                .into_iter()
+                .map(|(key, value)| {
+                    (
+                        expand_value(key, vars, missing),
+                        expand_value(value, vars, missing),
+                    )
+                })
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+fn expand_variables(spec: Value, vars: &HashMap<String, String>) -> Result<Value, Error> {
+    let mut missing = vec![];
+    let rv = expand_value(spec, vars, &mut missing);
+    if !missing.is_empty() {
+        bail!("Undefined variables in spec: {}", missing.join(", "));
+    }
+    Ok(rv)
+}
+
+fn execute_list(org: &str, project: &str, matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let api = Api::current();
+    let rules = AlertRules {
+        issue_alerts: api.list_alert_rules(org, project)?,
+        metric_alerts: api.list_metric_alert_rules(org, project)?,
+    };
+
+    if matches.is_present("json") {
+        serde_json::to_writer_pretty(&mut io::stdout(), &rules)?;
+        println!();
+        return Ok(());
+    }
+
+    let mut table = Table::new();
+    table.title_row().add("Type").add("ID").add("Name");
+    for rule in &rules.issue_alerts {
+        table
+            .add_row()
+            .add("issue")
+            .add(rule.id.as_deref().unwrap_or("-"))
+            .add(&rule.name);
+    }
+    for rule in &rules.metric_alerts {
+        table
+            .add_row()
+            .add("metric")
+            .add(rule.id.as_deref().unwrap_or("-"))
+            .add(&rule.name);
+    }
+
+    if table.is_empty() {
+        println!("No alert rules found");
+    } else {
+        table.print();
+    }
+
+    Ok(())
+}
+
+fn execute_create(org: &str, project: &str, matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let api = Api::current();
+    let path = matches.value_of("path").unwrap();
+
+    let mut vars = HashMap::new();
+    vars.insert("ORG".to_string(), org.to_string());
+    vars.insert("PROJECT".to_string(), project.to_string());
+    for var in matches.values_of("vars").unwrap_or_default() {
+        match var.split_once('=') {
+            Some((key, value)) => {
+                vars.insert(key.to_owned(), value.to_owned());
+            }
+            None => bail!("Invalid variable '{}', expected KEY=VALUE", var),
+        }
+    }
+
+    let spec = fs::read_to_string(path).with_context(|_| format!("Could not read {}", path))?;
+    let spec = serde_yaml::from_str(&spec).with_context(|_| format!("Could not parse {}", path))?;
+    let specs = match serde_yaml::from_value(expand_variables(spec, &vars)?)
+        .with_context(|_| format!("Could not parse {}", path))?
+    {
+        AlertSpecs::Many(specs) => specs,
+        AlertSpecs::One(spec) => vec![spec],
+    };
+
+    for spec in specs {
+        match spec {
+            AlertSpec::Issue(rule) => {
+                let rule = api.create_alert_rule(org, project, &rule)?;
+                println!(
+                    "Created issue alert {} ({})",
+                    rule.name,
+                    rule.id.as_deref().unwrap_or("-")
+                );
+            }
+            AlertSpec::Metric(rule) => {
+                let rule = api.create_metric_alert_rule(org, project, &rule)?;
+                println!(
+                    "Created metric alert {} ({})",
+                    rule.name,
+                    rule.id.as_deref().unwrap_or("-")
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn execute_delete(org: &str, project: &str, matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let api = Api::current();
+    let id = matches.value_of("id").unwrap();
+    let ty = matches.value_of("type").unwrap_or("issue");
+
+    if !confirm(
+        matches.is_present("confirm"),
+        &format!("Do you really want to delete {} alert {}?", ty, id),
+    )? {
+        println!("Aborted!");
+        return Ok(());
+    }
+
+    let deleted = if ty == "metric" {
+        api.delete_metric_alert_rule(org, project, id)?
+    } else {
+        api.delete_alert_rule(org, project, id)?
+    };
+    if deleted {
+        println!("Deleted {} alert {}!", ty, id);
+    } else {
+        println!("Did nothing. The {} alert {} does not exist.", ty, id);
+    }
+
+    Ok(())
+}
+
+pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let config = Config::current();
+    let (org, project) = config.get_org_and_project(matches)?;
+
+    if let Some(sub_matches) = matches.subcommand_matches("list") {
+        return execute_list(&org, &project, sub_matches);
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("create") {
+        return execute_create(&org, &project, sub_matches);
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("delete") {
+        return execute_delete(&org, &project, sub_matches);
+    }
+    unreachable!();
+}
@@ -0,0 +1,313 @@
+//! Implements a command for injecting debug metadata into Android apps.
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use clap::{App, Arg, ArgMatches};
+use console::style;
+use failure::{bail, err_msg, Error, ResultExt};
+use uuid::Uuid;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::utils::args::validate_uuid;
+
+/// The asset read by the Sentry Android SDK.
+const DEBUG_META_ASSET: &str = "assets/sentry-debug-meta.properties";
+
+pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.about("Inject debug metadata into an APK or app bundle.")
+        .long_about(
+            "Inject debug metadata into an APK or app bundle.{n}{n}\
+             This writes the ProGuard UUIDs and source bundle IDs into the assets \
+             of the app, the same way the Sentry Android Gradle plugin does.  \
+             Use this for build systems that cannot run the Gradle plugin.  \
+             Since this invalidates the signature of the app, it has to be \
+             signed again, either with --keystore or afterwards.",
+        )
+        .arg(
+            Arg::with_name("path")
+                .value_name("PATH")
+                .index(1)
+                .required(true)
+                .help("The path to the APK or AAB."),
+        )
+        .arg(
+            Arg::with_name("proguard_uuids")
+                .long("proguard-uuid")
+                .value_name("UUID")
+                .validator(validate_uuid)
+                .multiple(true)
+                .number_of_values(1)
+                .help("The UUID of an uploaded ProGuard mapping."),
+        )
+        .arg(
+            Arg::with_name("bundle_ids")
+                .long("bundle-id")
+                .value_name("UUID")
+                .validator(validate_uuid)
+                .multiple(true)
+                .number_of_values(1)
+                .help("The debug ID of an uploaded source bundle."),
+        )
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .short("o")
+                .value_name("PATH")
+                .help("Write the app to this path instead of modifying it in place."),
+        )
+        .arg(
+            Arg::with_name("keystore")
+                .long("keystore")
+                .value_name("PATH")
+                .requires("keystore_password")
+                .help(
+                    "Sign the app with this keystore.{n}APKs are signed with \
+                     apksigner, app bundles with jarsigner.  Both need to be \
+                     available on the PATH.",
+                ),
+        )
+        .arg(
+            Arg::with_name("keystore_password")
+                .long("keystore-password")
+                .value_name("PASSWORD")
+                .help(
+                    "The password of the keystore in the format of apksigner, \
+                     e.g. `pass:secret`, `env:VARIABLE` or `file:PATH`.",
+                ),
+        )
+        .arg(
+            Arg::with_name("key_alias")
+                .long("key-alias")
+                .value_name("ALIAS")
+                .help("The alias of the signing key in the keystore."),
+        )
+        .arg(
+            Arg::with_name("key_password")
+                .long("key-password")
+                .value_name("PASSWORD")
+                .help("The password of the signing key, if different from the keystore."),
+        )
+}
+
+/// Checks whether an entry belongs to a JAR signature, which becomes invalid
+/// when the archive is modified.
+fn is_signature_entry(name: &str) -> bool {
+    match name.strip_prefix("META-INF/") {
+        Some(file) if !file.contains('/') => {
+            let file = file.to_uppercase();
+            file == "MANIFEST.MF"
+                || [".SF", ".RSA", ".DSA", ".EC"]
+                    .iter()
+                    .any(|ext| file.ends_with(ext))
+        }
+        _ => false,
+    }
+}
+
+/// Returns the alignment of an uncompressed entry as done by `zipalign -p`.
+fn entry_alignment(name: &str) -> u16 {
+    if name.ends_with(".so") {
+        4096
+    } else {
+        4
+    }
+}
+
+/// Renders the debug metadata asset, retaining unrelated existing properties.
+fn render_debug_meta(
+    existing: Option<&[u8]>,
+    proguard_uuids: &[Uuid],
+    bundle_ids: &[Uuid],
+) -> Result<Vec<u8>, Error> {
+    let mut props = match existing {
+        Some(data) => java_properties::read(data).unwrap_or_else(|_| HashMap::new()),
+        None => HashMap::new(),
+    };
+
+    if !proguard_uuids.is_empty() {
+        let uuids: Vec<_> = proguard_uuids.iter().map(Uuid::to_string).collect();
+        props.insert("io.sentry.ProguardUuids".to_string(), uuids.join("|"));
+    }
+    if !bundle_ids.is_empty() {
+        let ids: Vec<_> = bundle_ids.iter().map(Uuid::to_string).collect();
+        props.insert("io.sentry.bundle-ids".to_string(), ids.join(","));
+    }
+
+    let mut rv = vec![];
+    java_properties::write(&mut rv, &props)
+        .map_err(|_| err_msg("Could not write debug metadata"))?;
+    Ok(rv)
+}
+
+/// Copies the app to `dst`, replacing the debug metadata asset and aligning
+/// all uncompressed entries.
+fn rewrite_app(
+    src: &Path,
+    dst: &Path,
+    asset: &str,
+    proguard_uuids: &[Uuid],
+    bundle_ids: &[Uuid],
+) -> Result<(), Error> {
+    let file = fs::File::open(src).with_context(|_| format!("Could not open {}", src.display()))?;
+    let mut archive = ZipArchive::new(io::BufReader::new(file))
+        .with_context(|_| format!("{} is not a valid APK or AAB", src.display()))?;
+
+    let existing = match archive.by_name(asset) {
+        Ok(mut entry) => {
+            let mut data = vec![];
+            entry.read_to_end(&mut data)?;
+            Some(data)
+        }
+        Err(_) => None,
+    };
+    let debug_meta = render_debug_meta(existing.as_deref(), proguard_uuids, bundle_ids)?;
+
+    let mut writer = ZipWriter::new(io::BufWriter::new(fs::File::create(dst)?));
+    let mut removed_signature = false;
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+        let name = entry.name().to_string();
+        if name == asset {
+            continue;
+        }
+        if is_signature_entry(&name) {
+            removed_signature = true;
+            continue;
+        }
+
+        if entry.compression() == CompressionMethod::Stored && !entry.is_dir() {
+            let options = FileOptions::default()
+                .compression_method(CompressionMethod::Stored)
+                .last_modified_time(entry.last_modified());
+            writer.start_file_aligned(name.as_str(), options, entry_alignment(&name))?;
+            io::copy(&mut entry, &mut writer)?;
+        } else {
+            writer.raw_copy_file(entry)?;
+        }
+    }
+
+    writer.start_file(asset, FileOptions::default())?;
+    writer.write_all(&debug_meta)?;
+    writer.finish()?;
+
+    if removed_signature {
+        println!("{} removed the previous signature", style(">").dim());
+    }
+    Ok(())
+}
+
+/// Translates a password in the format of apksigner to jarsigner arguments.
+fn jarsigner_password_args(flag: &str, password: &str) -> Vec<String> {
+    if let Some(var) = password.strip_prefix("env:") {
+        vec![format!("{}:env", flag), var.to_string()]
+    } else if let Some(path) = password.strip_prefix("file:") {
+        vec![format!("{}:file", flag), path.to_string()]
+    } else {
+        vec![
+            flag.to_string(),
+            password.trim_start_matches("pass:").to_string(),
+        ]
+    }
+}
+
+fn sign_app(matches: &ArgMatches<'_>, path: &Path, is_bundle: bool) -> Result<(), Error> {
+    let keystore = matches.value_of("keystore").unwrap();
+    let keystore_password = matches.value_of("keystore_password").unwrap();
+    let key_alias = matches.value_of("key_alias");
+    let key_password = matches.value_of("key_password");
+
+    let mut cmd = if is_bundle {
+        let alias = match key_alias {
+            Some(alias) => alias,
+            None => bail!("Signing app bundles requires --key-alias"),
+        };
+        let mut cmd = Command::new("jarsigner");
+        cmd.arg("-keystore").arg(keystore);
+        cmd.args(jarsigner_password_args("-storepass", keystore_password));
+        if let Some(password) = key_password {
+            cmd.args(jarsigner_password_args("-keypass", password));
+        }
+        cmd.arg(path).arg(alias);
+        cmd
+    } else {
+        let mut cmd = Command::new("apksigner");
+        cmd.arg("sign")
+            .arg("--ks")
+            .arg(keystore)
+            .arg("--ks-pass")
+            .arg(keystore_password);
+        if let Some(alias) = key_alias {
+            cmd.arg("--ks-key-alias").arg(alias);
+        }
+        if let Some(password) = key_password {
+            cmd.arg("--key-pass").arg(password);
+        }
+        cmd.arg(path);
+        cmd
+    };
+
+    let status = cmd
+        .status()
+        .context("Could not run the signing tool. Is it on the PATH?")?;
+    if !status.success() {
+        bail!("Signing the app failed");
+    }
+    Ok(())
+}
+
+pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let path = Path::new(matches.value_of("path").unwrap());
+    let output = matches.value_of("output").map(PathBuf::from);
+    let parse_ids = |name| -> Vec<Uuid> {
+        matches
+            .values_of(name)
+            .unwrap_or_default()
+            .map(|s| s.parse().unwrap())
+            .collect()
+    };
+    let proguard_uuids = parse_ids("proguard_uuids");
+    let bundle_ids = parse_ids("bundle_ids");
+    if proguard_uuids.is_empty() && bundle_ids.is_empty() {
+        bail!("Nothing to inject. Pass --proguard-uuid or --bundle-id.");
+    }
+
+    // app bundles keep the assets of the base module in a subfolder
+    let is_bundle = path.extension() == Some("aab".as_ref());
+    let asset = if is_bundle {
+        format!("base/{}", DEBUG_META_ASSET)
+    } else {
+        DEBUG_META_ASSET.to_string()
+    };
+
+    let dst = output.unwrap_or_else(|| path.to_path_buf());
+    let mut tmp = dst.clone().into_os_string();
+    tmp.push(".sentry-tmp");
+    let tmp = PathBuf::from(tmp);
+
+    if let Err(err) = rewrite_app(path, &tmp, &asset, &proguard_uuids, &bundle_ids) {
+        fs::remove_file(&tmp).ok();
+        return Err(err);
+    }
+    fs::rename(&tmp, &dst)?;
+    println!(
+        "{} injected debug metadata into {}",
+        style(">").dim(),
+        dst.display()
+    );
+
+    if matches.is_present("keystore") {
+        sign_app(matches, &dst, is_bundle)?;
+        println!("{} signed {}", style(">").dim(), dst.display());
+    } else {
+        println!(
+            "{} the app needs to be signed before it can be installed",
+            style(">").dim()
+        );
+    }
+
+    Ok(())
+}
@@ -1,27 +1,23 @@
 //! Implements a command for showing infos from Sentry.
-use std::cmp::min;
-use std::collections::HashMap;
 use std::env;
-use std::fs;
-use std::io::{BufRead, BufReader};
-use std::path::Path;
 
 use clap::{App, Arg, ArgMatches};
-use failure::Error;
-use lazy_static::lazy_static;
+use failure::{bail, Error};
+use log::debug;
 use regex::Regex;
-use sentry::protocol::{Event, Exception, Frame, Stacktrace, User, Value};
-use username::get_user_name;
 use uuid::Uuid;
 
+use crate::api::{Api, CreateMonitorCheckIn, MonitorStatus, UpdateMonitorCheckIn};
 use crate::config::Config;
-use crate::utils::event::{attach_logfile, get_sdk_info, with_sentry_client};
-use crate::utils::releases::detect_release_name;
+use crate::utils::args::{
+    validate_int, validate_monitor_slug, validate_regex, validate_uuid, ArgExt,
+};
+use crate::utils::script_hook::{send_event, send_transaction};
+use crate::utils::spool::Spool;
 
 const BASH_SCRIPT: &str = include_str!("../bashsupport.sh");
-lazy_static! {
-    static ref FRAME_RE: Regex = Regex::new(r#"^(.*?):(.*):(\d+)$"#).unwrap();
-}
+const ZSH_SCRIPT: &str = include_str!("../zshsupport.zsh");
+const SH_SCRIPT: &str = include_str!("../shsupport.sh");
 
 pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
     app.about("Prints out a bash script that does error handling.")
@@ -30,6 +26,19 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
                 .long("no-exit")
                 .help("Do not turn on -e (exit immediately) flag automatically"),
         )
+        .arg(
+            Arg::with_name("shell")
+                .long("shell")
+                .value_name("SHELL")
+                .possible_values(&["bash", "zsh", "sh"])
+                .default_value("bash")
+                .help(
+                    "The shell to generate the hook for.{n}\
+                     The `sh` variant only uses POSIX features, so it works with \
+                     dash and busybox but reports failures without a traceback \
+                     once the script exits.",
+                ),
+        )
         .arg(
             Arg::with_name("no_environ")
                 .long("no-environ")
@@ -41,6 +50,104 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
                 .value_name("CMD")
                 .help("Explicitly set/override the sentry-cli command"),
         )
+        .arg(
+            Arg::with_name("monitor_slug")
+                .long("monitor-slug")
+                .value_name("SLUG")
+                .validator(validate_monitor_slug)
+                .help(
+                    "Report every run of the script as a check-in of this monitor.{n}\
+                     Successful runs are reported as ok, failed runs as errors \
+                     in addition to the error event.",
+                ),
+        )
+        .org_arg()
+        .arg(
+            Arg::with_name("command_breadcrumbs")
+                .long("command-breadcrumbs")
+                .help(
+                    "Record all executed commands with their exit codes and send \
+                     them as breadcrumbs along with errors.",
+                ),
+        )
+        .arg(
+            Arg::with_name("redact")
+                .long("redact")
+                .value_name("REGEX")
+                .multiple(true)
+                .number_of_values(1)
+                .validator(validate_regex)
+                .help(
+                    "Replace matches of this regular expression in recorded \
+                     commands, e.g. to remove passwords and tokens.  Can be \
+                     supplied multiple times.",
+                ),
+        )
+        .arg(Arg::with_name("async").long("async").help(
+            "Queue error events on disk and send them in the background.{n}\
+                     The script exits without waiting for the upload and events \
+                     that cannot be sent, e.g. while offline, are retried the \
+                     next time an event is sent.",
+        ))
+        .arg(Arg::with_name("flush").long("flush").hidden(true))
+        .arg(Arg::with_name("tracing").long("tracing").help(
+            "Send a transaction for every run of the script.{n}\
+                     Sections of the script between `sentry_span_start NAME` \
+                     and `sentry_span_end` are recorded as spans.  Sections \
+                     can be nested.",
+        ))
+        .arg(
+            Arg::with_name("send_transaction")
+                .long("send-transaction")
+                .requires_all(&["spans", "exit_code"])
+                .hidden(true),
+        )
+        .arg(
+            Arg::with_name("spans")
+                .long("spans")
+                .value_name("PATH")
+                .hidden(true),
+        )
+        .arg(
+            Arg::with_name("script")
+                .long("script")
+                .value_name("NAME")
+                .hidden(true),
+        )
+        .arg(
+            Arg::with_name("commands")
+                .long("commands")
+                .value_name("PATH")
+                .hidden(true),
+        )
+        .arg(
+            Arg::with_name("start_checkin")
+                .long("start-checkin")
+                .requires("monitor_slug")
+                .hidden(true),
+        )
+        .arg(
+            Arg::with_name("finish_checkin")
+                .long("finish-checkin")
+                .value_name("ID")
+                .requires_all(&["monitor_slug", "exit_code"])
+                .validator(validate_uuid)
+                .hidden(true),
+        )
+        .arg(
+            Arg::with_name("exit_code")
+                .long("exit-code")
+                .value_name("CODE")
+                .validator(validate_int)
+                .hidden(true),
+        )
+        .arg(
+            Arg::with_name("duration")
+                .long("duration")
+                .value_name("SECONDS")
+                .validator(validate_int)
+                .hidden(true),
+        )
         .arg(
             Arg::with_name("send_event")
                 .long("send-event")
@@ -53,131 +160,123 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
                 .value_name("PATH")
                 .hidden(true),
         )
+        .arg(Arg::with_name("log").long("log").value_name("PATH").help(
+            "Attach the end of this log file to error events, e.g. the \
+                     log of the service that the script manages.",
+        ))
+        .arg(
+            Arg::with_name("log_max_size")
+                .long("log-max-size")
+                .value_name("BYTES")
+                .default_value("1048576")
+                .validator(validate_size)
+                .help("The maximum number of bytes attached from the end of --log."),
+        )
         .arg(
-            Arg::with_name("log")
-                .long("log")
+            Arg::with_name("attach_log")
+                .long("attach-log")
                 .value_name("PATH")
                 .hidden(true),
         )
 }
 
-fn send_event(traceback: &str, logfile: &str, environ: bool) -> Result<(), Error> {
-    let config = Config::current();
-
-    let mut event = Event {
-        environment: config.get_environment().map(Into::into),
-        release: detect_release_name().ok().map(Into::into),
-        sdk: Some(get_sdk_info()),
-        user: get_user_name().ok().map(|n| User {
-            username: Some(n),
-            ip_address: Some(Default::default()),
-            ..Default::default()
-        }),
-        ..Event::default()
-    };
-
-    if environ {
-        event.extra.insert(
-            "environ".into(),
-            Value::Object(env::vars().map(|(k, v)| (k, Value::String(v))).collect()),
-        );
-    }
-
-    let mut cmd = "unknown".to_string();
-    let mut exit_code = 1;
-    let mut frames = vec![];
-
-    if let Ok(f) = fs::File::open(traceback) {
-        let f = BufReader::new(f);
-        for line in f.lines() {
-            let line = line?;
-
-            // meta info
-            if line.starts_with('@') {
-                if let Some(rest) = line.strip_prefix("@command:") {
-                    cmd = rest.to_string();
-                } else if let Some(rest) = line.strip_prefix("@exit_code:") {
-                    exit_code = rest.parse().unwrap_or(exit_code);
-                } else {
-                    continue;
-                }
-            }
-
-            if let Some(cap) = FRAME_RE.captures(&line) {
-                match &cap[1] {
-                    "_sentry_err_trap" | "_sentry_exit_trap" | "_sentry_traceback" => continue,
-                    _ => {}
-                }
-                frames.push(Frame {
-                    filename: Some(cap[2].to_string()),
-                    abs_path: Path::new(&cap[2])
-                        .canonicalize()
-                        .map(|x| x.display().to_string())
-                        .ok(),
-                    lineno: cap[3].parse().ok(),
-                    function: Some(cap[1].to_string()),
-                    ..Default::default()
-                });
-            }
-        }
+fn validate_size(v: String) -> Result<(), String> {
+    match v.parse::<u64>() {
+        Ok(size) if size > 0 => Ok(()),
+        _ => Err("Invalid size, a positive number of bytes is required.".to_string()),
     }
+}
 
-    {
-        let mut source_caches = HashMap::new();
-        for frame in &mut frames {
-            let lineno = match frame.lineno {
-                Some(line) => line as usize,
-                None => continue,
-            };
-
-            let filename = frame.filename.as_deref().expect("frame without location");
-
-            if !source_caches.contains_key(filename) {
-                if let Ok(f) = fs::File::open(filename) {
-                    let lines: Vec<_> = BufReader::new(f)
-                        .lines()
-                        .map(|x| x.unwrap_or_else(|_| "".to_string()))
-                        .collect();
-                    source_caches.insert(filename, lines);
-                } else {
-                    source_caches.insert(filename, vec![]);
-                }
-            }
-            let source = &source_caches[filename];
-            frame.context_line = source.get(lineno.saturating_sub(1)).cloned();
-            if let Some(slice) = source.get(lineno.saturating_sub(5)..lineno.saturating_sub(1)) {
-                frame.pre_context = slice.to_vec();
-            };
-            if let Some(slice) = source.get(lineno..min(lineno + 5, source.len())) {
-                frame.post_context = slice.to_vec();
-            };
-        }
-    }
+fn start_checkin(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let config = Config::current();
+    let org = config.get_org(matches)?;
+    let checkin = Api::current().create_monitor_checkin_by_slug(
+        &org,
+        matches.value_of("monitor_slug").unwrap(),
+        &CreateMonitorCheckIn {
+            status: MonitorStatus::InProgress,
+            monitor_config: None,
+        },
+    )?;
+    println!("{}", checkin.id);
+    Ok(())
+}
 
-    attach_logfile(&mut event, logfile, true)?;
+fn finish_checkin(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let config = Config::current();
+    let org = config.get_org(matches)?;
+    let checkin_id: Uuid = matches.value_of("finish_checkin").unwrap().parse()?;
+    let exit_code: i32 = matches.value_of("exit_code").unwrap().parse()?;
+    let duration = matches
+        .value_of("duration")
+        .map(|s| s.parse::<u64>())
+        .transpose()?
+        .map(|secs| secs * 1000);
 
-    event.exception.values.push(Exception {
-        ty: "BashError".into(),
-        value: Some(format!("command {} exited with status {}", cmd, exit_code)),
-        stacktrace: Some(Stacktrace {
-            frames,
+    Api::current().update_monitor_checkin_by_slug(
+        &org,
+        matches.value_of("monitor_slug").unwrap(),
+        &checkin_id,
+        &UpdateMonitorCheckIn {
+            status: Some(if exit_code == 0 {
+                MonitorStatus::Ok
+            } else {
+                MonitorStatus::Error
+            }),
+            duration,
             ..Default::default()
-        }),
-        ..Default::default()
-    });
-
-    let id = with_sentry_client(config.get_dsn()?, |c| c.capture_event(event, None));
-    println!("{}", id);
-
+        },
+    )?;
     Ok(())
 }
 
+/// Quotes a value for use in the generated shell script.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
 pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    if matches.is_present("start_checkin") {
+        return start_checkin(matches);
+    }
+    if matches.is_present("finish_checkin") {
+        return finish_checkin(matches);
+    }
+    if matches.is_present("flush") {
+        let stats = Spool::open()?.flush()?;
+        debug!(
+            "Flushed spool: {} sent, {} pending, {} dropped",
+            stats.sent, stats.pending, stats.dropped
+        );
+        return Ok(());
+    }
+    if matches.is_present("send_transaction") {
+        return send_transaction(
+            matches.value_of("spans").unwrap(),
+            matches.value_of("script").unwrap_or("script"),
+            matches.value_of("exit_code").unwrap().parse()?,
+        );
+    }
     if matches.is_present("send_event") {
+        let commands = matches.value_of("commands").map(|path| {
+            let redact = matches
+                .values_of("redact")
+                .unwrap_or_default()
+                .map(|pattern| Regex::new(pattern).unwrap())
+                .collect();
+            (path, redact)
+        });
         return send_event(
+            "BashError",
             matches.value_of("traceback").unwrap(),
             matches.value_of("log").unwrap(),
+            commands,
             !matches.is_present("no_environ"),
+            matches.is_present("async"),
+            matches.value_of("attach_log").map(|path| {
+                let max_size = matches.value_of("log_max_size").unwrap().parse().unwrap();
+                (path, max_size)
+            }),
         );
     }
 
@@ -190,12 +289,56 @@ pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
         ".sentry-{}.traceback",
         Uuid::new_v4().to_hyphenated_ref().to_string()
     ));
-    let mut script = BASH_SCRIPT
-        .replace(
-            "___SENTRY_TRACEBACK_FILE___",
-            &traceback.display().to_string(),
-        )
-        .replace("___SENTRY_LOG_FILE___", &log.display().to_string());
+    let commands = path.join(format!(
+        ".sentry-{}.commands",
+        Uuid::new_v4().to_hyphenated_ref()
+    ));
+    let spans = path.join(format!(
+        ".sentry-{}.spans",
+        Uuid::new_v4().to_hyphenated_ref()
+    ));
+    let shell = matches.value_of("shell").unwrap();
+    if shell == "sh" && matches.is_present("command_breadcrumbs") {
+        bail!("--command-breadcrumbs requires bash or zsh");
+    }
+    let mut script = match shell {
+        "zsh" => ZSH_SCRIPT,
+        "sh" => SH_SCRIPT,
+        _ => BASH_SCRIPT,
+    }
+    .replace(
+        "___SENTRY_TRACEBACK_FILE___",
+        &traceback.display().to_string(),
+    )
+    .replace("___SENTRY_LOG_FILE___", &log.display().to_string())
+    .replace(
+        "___SENTRY_COMMANDS_FILE___",
+        &commands.display().to_string(),
+    )
+    .replace("___SENTRY_SPANS_FILE___", &spans.display().to_string())
+    .replace(
+        "___SENTRY_TRACING___",
+        if matches.is_present("tracing") {
+            "1"
+        } else {
+            ""
+        },
+    );
+
+    if matches.is_present("command_breadcrumbs") {
+        let mut args = "--commands \"$_SENTRY_COMMANDS_FILE\"".to_string();
+        for pattern in matches.values_of("redact").unwrap_or_default() {
+            args.push_str(" --redact ");
+            args.push_str(&shell_quote(pattern));
+        }
+        script = script
+            .replace("___SENTRY_COMMAND_BREADCRUMBS___", "1")
+            .replace("___SENTRY_COMMANDS_ARGS___", &args);
+    } else {
+        script = script
+            .replace("___SENTRY_COMMAND_BREADCRUMBS___", "")
+            .replace("___SENTRY_COMMANDS_ARGS___", "");
+    }
 
     if matches.is_present("cli") {
         script = script.replace("___SENTRY_CLI___", matches.value_of("cli").unwrap());
@@ -206,11 +349,39 @@ pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
         );
     }
 
+    let mut send_args = vec![];
     if matches.is_present("no_environ") {
-        script = script.replace("___SENTRY_NO_ENVIRON___", "--no-environ");
-    } else {
-        script = script.replace("___SENTRY_NO_ENVIRON___", "");
+        send_args.push("--no-environ".to_string());
+    }
+    if matches.is_present("async") {
+        send_args.push("--async".to_string());
     }
+    if let Some(log) = matches.value_of("log") {
+        // the script may change its working directory before it fails
+        let log = env::current_dir()?.join(log);
+        send_args.push(format!(
+            "--attach-log {} --log-max-size {}",
+            shell_quote(&log.display().to_string()),
+            matches.value_of("log_max_size").unwrap()
+        ));
+    }
+    script = script.replace("___SENTRY_NO_ENVIRON___", &send_args.join(" "));
+
+    // the organization is only needed for check-ins and ends up in a quoted
+    // shell string
+    let monitor_slug = matches.value_of("monitor_slug").unwrap_or("");
+    let monitor_org = match matches.value_of("org") {
+        Some(org) if !monitor_slug.is_empty() => {
+            if validate_monitor_slug(org.to_string()).is_err() {
+                bail!("Invalid organization slug {}", org);
+            }
+            org
+        }
+        _ => "",
+    };
+    script = script
+        .replace("___SENTRY_MONITOR_SLUG___", monitor_slug)
+        .replace("___SENTRY_MONITOR_ORG___", monitor_org);
 
     if !matches.is_present("no_exit") {
         script.insert_str(0, "set -e\n\n");
@@ -0,0 +1,166 @@
+//! Implements a command for bundling information for bug reports.
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+
+use chrono::Utc;
+use clap::{App, Arg, ArgMatches};
+use console::style;
+use failure::{Error, ResultExt};
+use serde::Serialize;
+use zip::{write::FileOptions, ZipWriter};
+
+use crate::config::{Auth, Config};
+use crate::constants::{ARCH, PLATFORM, VERSION};
+use crate::utils::crash_report::{find_crash_reports, get_crash_dir, is_secret_name, redact};
+use crate::utils::tls;
+
+/// The number of recent crash reports added to the bundle.
+const MAX_CRASH_REPORTS: usize = 10;
+
+const PROXY_VARS: &[&str] = &["http_proxy", "https_proxy", "no_proxy", "all_proxy"];
+
+#[derive(Serialize)]
+struct BugReport {
+    version: &'static str,
+    platform: &'static str,
+    arch: &'static str,
+    os: &'static str,
+    tls_backend: String,
+    available_tls_backends: Vec<String>,
+    config_file: Option<String>,
+    url: Option<String>,
+    org: Option<String>,
+    project: Option<String>,
+    auth: Option<&'static str>,
+    have_dsn: bool,
+    environment: BTreeMap<String, String>,
+    crash_reports: Vec<String>,
+}
+
+pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.about("Bundle crash reports and environment information for a bug report.")
+        .long_about(
+            "Bundle crash reports and environment information for a bug report.{n}{n}\
+             The zip archive contains the versions of sentry-cli and its TLS \
+             library, the platform, the configuration file, the SENTRY_ and \
+             proxy environment variables and the recent crash reports, which \
+             include the log of the crashed runs.  Auth tokens, keys, \
+             passwords and DSNs are removed, but please review the archive \
+             before sharing it.",
+        )
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .short("o")
+                .value_name("PATH")
+                .help(
+                    "The path of the archive to write.{n}\
+                     [defaults to sentry-cli-bugreport-TIMESTAMP.zip]",
+                ),
+        )
+}
+
+fn describe_auth(auth: Option<&Auth>) -> Option<&'static str> {
+    auth.map(|auth| match auth {
+        Auth::Token(_) => "token",
+        Auth::Key(_) => "api_key",
+    })
+}
+
+fn get_environment() -> BTreeMap<String, String> {
+    env::vars()
+        .filter(|(key, _)| {
+            key.starts_with("SENTRY_") || PROXY_VARS.contains(&key.to_ascii_lowercase().as_str())
+        })
+        .map(|(key, value)| {
+            let value = if is_secret_name(&key) {
+                "[redacted]".to_string()
+            } else {
+                redact(&value)
+            };
+            (key, value)
+        })
+        .collect()
+}
+
+/// Removes the values of secret settings from an ini file.
+fn redact_config(contents: &str) -> String {
+    let mut rv = String::new();
+    for line in contents.lines() {
+        match line.find('=') {
+            Some(idx) if is_secret_name(line[..idx].trim()) => {
+                rv.push_str(line[..idx].trim_end());
+                rv.push_str(" = [redacted]");
+            }
+            _ => rv.push_str(&redact(line)),
+        }
+        rv.push('\n');
+    }
+    rv
+}
+
+pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let config = Config::current();
+    let output = matches
+        .value_of("output")
+        .map(str::to_owned)
+        .unwrap_or_else(|| {
+            format!(
+                "sentry-cli-bugreport-{}.zip",
+                Utc::now().format("%Y%m%d-%H%M%S")
+            )
+        });
+
+    let mut reports = find_crash_reports(&get_crash_dir()?)?;
+    let skip = reports.len().saturating_sub(MAX_CRASH_REPORTS);
+    reports.drain(..skip);
+    let config_file = Some(config.get_filename()).filter(|path| path.is_file());
+    let (org, project) = config.get_org_and_project_defaults();
+
+    let report = BugReport {
+        version: VERSION,
+        platform: PLATFORM,
+        arch: ARCH,
+        os: env::consts::OS,
+        tls_backend: tls::describe_backend(),
+        available_tls_backends: tls::available_backends(),
+        config_file: config_file.map(|path| path.display().to_string()),
+        url: config.get_base_url().ok().map(redact),
+        org,
+        project,
+        auth: describe_auth(config.get_auth()),
+        have_dsn: config.get_dsn().is_ok(),
+        environment: get_environment(),
+        crash_reports: reports
+            .iter()
+            .filter_map(|path| Some(path.file_name()?.to_string_lossy().into_owned()))
+            .collect(),
+    };
+
+    let file = fs::File::create(&output)
+        .with_context(|_| format!("Could not create bug report {}", output))?;
+    let mut zip = ZipWriter::new(io::BufWriter::new(file));
+    zip.start_file("report.json", FileOptions::default())?;
+    serde_json::to_writer_pretty(&mut zip, &report)?;
+    if let Some(path) = config_file {
+        zip.start_file("sentryclirc", FileOptions::default())?;
+        zip.write_all(redact_config(&fs::read_to_string(path)?).as_bytes())?;
+    }
+    for (path, name) in reports.iter().zip(&report.crash_reports) {
+        zip.start_file(format!("crashes/{}", name), FileOptions::default())?;
+        zip.write_all(&fs::read(path)?)?;
+    }
+    zip.finish()?;
+
+    println!(
+        "{} Wrote bug report with {} crash report{} to {}",
+        style(">").dim(),
+        reports.len(),
+        if reports.len() == 1 { "" } else { "s" },
+        output
+    );
+    println!("  Please review it before attaching it to an issue.");
+    Ok(())
+}
@@ -0,0 +1,60 @@
+//! Implements a command for inspecting the CI environment.
+use clap::{App, AppSettings, Arg, ArgMatches};
+use failure::Error;
+
+use crate::utils::ci::detect_ci;
+
+pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.about("Inspect the CI environment.")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            App::new("detect")
+                .about("Detect the CI provider and print metadata about the build.")
+                .long_about(
+                    "Detect the CI provider and print metadata about the build.{n}{n}\
+                     The commit SHA, branch, pull request number, build URL and \
+                     repository are read from the variables of the provider and \
+                     printed as SENTRY_CI_* variables.  These are the same values \
+                     sentry-cli uses for release names and deploy URLs.{n}{n}\
+                     Example: eval \"$(sentry-cli ci detect)\"",
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .possible_values(&["shell", "dotenv", "json"])
+                        .default_value("shell")
+                        .help("The output format."),
+                ),
+        )
+}
+
+/// Quotes a value for POSIX shells.
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+fn execute_detect(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let info = detect_ci();
+    match matches.value_of("format").unwrap() {
+        "json" => println!("{}", serde_json::to_string_pretty(&info)?),
+        "dotenv" => {
+            for (key, value) in info.to_env() {
+                println!("{}={}", key, value);
+            }
+        }
+        _ => {
+            for (key, value) in info.to_env() {
+                println!("export {}={}", key, shell_quote(value));
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    if let Some(sub_matches) = matches.subcommand_matches("detect") {
+        return execute_detect(sub_matches);
+    }
+    unreachable!();
+}
@@ -0,0 +1,40 @@
+use clap::{App, AppSettings, ArgMatches};
+use failure::Error;
+
+use crate::commands;
+
+macro_rules! each_subcommand {
+    ($mac:ident) => {
+        $mac!(debug_files_watch);
+    };
+}
+
+pub fn make_app<'a, 'b: 'a>(mut app: App<'a, 'b>) -> App<'a, 'b> {
+    macro_rules! add_subcommand {
+        ($name:ident) => {{
+            app = app.subcommand(commands::$name::make_app(App::new(
+                stringify!($name)[12..].replace('_', "-"),
+            )));
+        }};
+    }
+
+    app = app
+        .about("Manage debug information files.")
+        .setting(AppSettings::SubcommandRequiredElseHelp);
+    each_subcommand!(add_subcommand);
+    app
+}
+
+pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    macro_rules! execute_subcommand {
+        ($name:ident) => {{
+            if let Some(sub_matches) =
+                matches.subcommand_matches(&stringify!($name)[12..].replace('_', "-"))
+            {
+                return commands::$name::execute(&sub_matches);
+            }
+        }};
+    }
+    each_subcommand!(execute_subcommand);
+    unreachable!();
+}
@@ -0,0 +1,13 @@
+//! Implements a command for uploading debug files as they appear.
+use clap::{App, ArgMatches};
+use failure::Error;
+
+use crate::commands::upload_dif;
+
+pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
+    upload_dif::make_watch_app(app)
+}
+
+pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    upload_dif::execute_watch(matches)
+}
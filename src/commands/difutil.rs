@@ -5,9 +5,11 @@ use crate::commands;
 
 macro_rules! each_subcommand {
     ($mac:ident) => {
+        $mac!(difutil_bundle_jvm_sources);
         $mac!(difutil_bundle_sources);
         $mac!(difutil_find);
         $mac!(difutil_check);
+        $mac!(difutil_check_ipa);
         $mac!(difutil_id);
     };
 }
@@ -0,0 +1,185 @@
+//! Implements a command for bundling Java and Kotlin sources.
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use clap::{App, Arg, ArgMatches};
+use console::style;
+use failure::{bail, Error, ResultExt};
+use lazy_static::lazy_static;
+use log::{debug, warn};
+use regex::Regex;
+use symbolic::common::DebugId;
+use symbolic::debuginfo::sourcebundle::{SourceBundleWriter, SourceFileInfo, SourceFileType};
+use walkdir::WalkDir;
+
+use crate::utils::args::validate_id;
+
+lazy_static! {
+    static ref PACKAGE_RE: Regex =
+        Regex::new(r"(?m)^\s*package\s+([A-Za-z_][\w.]*)\s*;?\s*$").unwrap();
+}
+
+pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.about("Create a source bundle for Java and Kotlin sources.")
+        .long_about(
+            "Create a source bundle for Java and Kotlin sources.{n}{n}\
+             The sources are stored by their package path, which is read from \
+             the package declaration of each file, so that Sentry can look them \
+             up for deobfuscated stack frames.  The bundle is tied to the \
+             ProGuard UUID of the build and can be uploaded with `upload-dif`.",
+        )
+        .arg(
+            Arg::with_name("paths")
+                .value_name("PATH")
+                .index(1)
+                .required(true)
+                .multiple(true)
+                .help("The source roots to bundle, e.g. `src/main/java`."),
+        )
+        .arg(
+            Arg::with_name("debug_id")
+                .long("debug-id")
+                .value_name("UUID")
+                .required(true)
+                .validator(validate_id)
+                .help("The ProGuard UUID or build ID to attach the bundle to."),
+        )
+        .arg(
+            Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .value_name("PATH")
+                .help(
+                    "The path to the output folder.  If not provided the \
+                     bundle is placed in the current working directory.",
+                ),
+        )
+}
+
+fn is_jvm_source(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("java") | Some("kt")
+    )
+}
+
+/// Returns the path of a source file within its package, e.g.
+/// `io/sentry/Main.jvm` for `Main.java` in package `io.sentry`.
+///
+/// Files without a package declaration fall back to their path relative to
+/// the source root.
+fn get_package_path(root: &Path, path: &Path, contents: &str) -> String {
+    let stem = path.file_stem().unwrap().to_string_lossy();
+    let package = match PACKAGE_RE.captures(contents) {
+        Some(caps) => caps[1].replace('.', "/"),
+        None => path
+            .parent()
+            .and_then(|parent| parent.strip_prefix(root).ok())
+            .map(|parent| {
+                parent
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                    .collect::<Vec<_>>()
+                    .join("/")
+            })
+            .unwrap_or_default(),
+    };
+
+    if package.is_empty() {
+        format!("{}.jvm", stem)
+    } else {
+        format!("{}/{}.jvm", package, stem)
+    }
+}
+
+/// Collects all sources of the given roots keyed by their package path.
+fn collect_sources<'a, I>(roots: I) -> Result<BTreeMap<String, PathBuf>, Error>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut rv: BTreeMap<String, PathBuf> = BTreeMap::new();
+    for root in roots {
+        let root = Path::new(root);
+        if !root.is_dir() {
+            bail!("{} is not a directory", root.display());
+        }
+
+        let entries = WalkDir::new(root)
+            .follow_links(true)
+            .sort_by(|a, b| a.file_name().cmp(b.file_name()))
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'));
+
+        for entry in entries {
+            let entry = entry?;
+            if !entry.file_type().is_file() || !is_jvm_source(entry.path()) {
+                continue;
+            }
+
+            let contents = match fs::read_to_string(entry.path()) {
+                Ok(contents) => contents,
+                Err(err) => {
+                    warn!("Skipping {}: {}", entry.path().display(), err);
+                    continue;
+                }
+            };
+
+            let package_path = get_package_path(root, entry.path(), &contents);
+            if let Some(existing) = rv.get(&package_path) {
+                warn!(
+                    "Skipping {}: {} is already bundled from {}",
+                    entry.path().display(),
+                    package_path,
+                    existing.display()
+                );
+                continue;
+            }
+            debug!("Bundling {} as {}", entry.path().display(), package_path);
+            rv.insert(package_path, entry.into_path());
+        }
+    }
+    Ok(rv)
+}
+
+pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let debug_id: DebugId = matches.value_of("debug_id").unwrap().parse().unwrap();
+    let output_path = matches
+        .value_of("output")
+        .map_or_else(|| PathBuf::from("."), PathBuf::from);
+
+    let sources = collect_sources(matches.values_of("paths").unwrap())?;
+    if sources.is_empty() {
+        bail!("No Java or Kotlin sources found");
+    }
+
+    fs::create_dir_all(&output_path)?;
+    let out = output_path.join(format!("{}.zip", debug_id));
+    let file =
+        fs::File::create(&out).with_context(|_| format!("Could not create {}", out.display()))?;
+    let mut bundle = SourceBundleWriter::start(BufWriter::new(file))?;
+    bundle.set_attribute("debug_id", debug_id.to_string());
+
+    for (package_path, path) in &sources {
+        let mut info = SourceFileInfo::new();
+        info.set_ty(SourceFileType::Source);
+        info.set_url(format!("~/{}", package_path));
+        let file = fs::File::open(path)?;
+        bundle.add_file(package_path, file, info)?;
+    }
+    bundle.finish()?;
+
+    println!(
+        "{} Bundled {} {} into {}",
+        style(">").dim(),
+        style(sources.len()).yellow(),
+        match sources.len() {
+            1 => "file",
+            _ => "files",
+        },
+        out.display()
+    );
+
+    Ok(())
+}
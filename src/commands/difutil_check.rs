@@ -62,7 +62,10 @@ pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
     }
 
     println!("  Contained debug identifiers:");
-    for variant in dif.variants() {
+    // Universal binaries report the status of every slice
+    let variants = dif.variants();
+    let multiple = variants.len() > 1;
+    for variant in variants {
         println!("    > Debug ID: {}", style(variant.debug_id).dim());
         if let Some(code_id) = variant.code_id {
             println!("      Code ID:  {}", style(code_id).dim());
@@ -70,6 +73,19 @@ pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
         if let Some(arch) = variant.arch {
             println!("      Arch:     {}", style(arch).dim());
         }
+        if let Some(platform) = variant.platform {
+            println!("      Platform: {}", style(platform).dim());
+        }
+        if let Some(warning) = variant.warning {
+            println!("      Warning:  {}", style(warning).yellow());
+        }
+        if multiple {
+            println!("      Features: {}", style(variant.features).dim());
+            match variant.problem {
+                Some(prob) => println!("      Usable:   {} ({})", style("no").red(), prob),
+                None => println!("      Usable:   {}", style("yes").green()),
+            }
+        }
     }
 
     println!("  Contained debug information:");
@@ -0,0 +1,145 @@
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+use clap::{App, Arg, ArgMatches};
+use console::style;
+use failure::{Error, ResultExt};
+use serde::Serialize;
+use symbolic::common::{ByteView, DebugId};
+use symbolic::debuginfo::{Archive, FileFormat};
+use zip::ZipArchive;
+
+use crate::api::Api;
+use crate::config::Config;
+use crate::utils::args::ArgExt;
+use crate::utils::system::QuietExit;
+
+#[derive(Serialize)]
+struct IpaBinary {
+    name: String,
+    arch: String,
+    debug_id: DebugId,
+    features: Option<Vec<String>>,
+}
+
+pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.about("Check that the debug symbols of an IPA have been uploaded.")
+        .long_about(
+            "Check that the debug symbols of an IPA have been uploaded.{n}{n}\
+             This reads the debug identifiers of all binaries in the app bundle, \
+             including embedded frameworks and app extensions, and looks them up \
+             on Sentry.  The command fails if any of them is missing.",
+        )
+        .org_project_args()
+        .arg(
+            Arg::with_name("json")
+                .long("json")
+                .help("Format outputs as JSON."),
+        )
+        .arg(
+            Arg::with_name("path")
+                .index(1)
+                .required(true)
+                .help("The path to the IPA."),
+        )
+}
+
+/// Reads the debug identifiers of all Mach-O binaries in the app payload.
+fn find_ipa_binaries(path: &Path) -> Result<Vec<IpaBinary>, Error> {
+    let file = File::open(path).with_context(|_| format!("Could not open {}", path.display()))?;
+    let mut zip = ZipArchive::new(BufReader::new(file))
+        .with_context(|_| format!("{} is not a valid IPA", path.display()))?;
+
+    let mut rv = vec![];
+    for index in 0..zip.len() {
+        let mut entry = zip.by_index(index)?;
+        let name = entry.name().to_string();
+        if entry.is_dir() || !name.starts_with("Payload/") {
+            continue;
+        }
+
+        let mut data = Vec::with_capacity(entry.size() as usize);
+        entry.read_to_end(&mut data)?;
+        if Archive::peek(&data) != FileFormat::MachO {
+            continue;
+        }
+
+        let buffer = ByteView::from_vec(data);
+        let archive = match Archive::parse(&buffer) {
+            Ok(archive) => archive,
+            Err(_) => continue,
+        };
+        for object in archive.objects().filter_map(Result::ok) {
+            if object.debug_id().is_nil() {
+                continue;
+            }
+            rv.push(IpaBinary {
+                name: name.trim_start_matches("Payload/").to_string(),
+                arch: object.arch().name().to_string(),
+                debug_id: object.debug_id(),
+                features: None,
+            });
+        }
+    }
+
+    Ok(rv)
+}
+
+pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let config = Config::current();
+    let (org, project) = config.get_org_and_project(matches)?;
+    let api = Api::current();
+
+    let mut binaries = find_ipa_binaries(Path::new(matches.value_of("path").unwrap()))?;
+    for binary in &mut binaries {
+        let difs = api.list_difs_by_id(&org, &project, binary.debug_id)?;
+        if !difs.is_empty() {
+            let mut features: Vec<_> = difs.into_iter().flat_map(|dif| dif.data.features).collect();
+            features.sort();
+            features.dedup();
+            binary.features = Some(features);
+        }
+    }
+    let missing = binaries.iter().filter(|x| x.features.is_none()).count();
+
+    if matches.is_present("json") {
+        serde_json::to_writer_pretty(&mut io::stdout(), &binaries)?;
+        println!();
+    } else {
+        println!("{}", style("IPA Debug Symbols Check").dim().bold());
+        for binary in &binaries {
+            println!(
+                "  > {} ({}) {}",
+                binary.name,
+                binary.arch,
+                style(binary.debug_id).dim()
+            );
+            match binary.features {
+                Some(ref features) if features.is_empty() => {
+                    println!("      Uploaded: {}", style("yes").green())
+                }
+                Some(ref features) => println!(
+                    "      Uploaded: {} ({})",
+                    style("yes").green(),
+                    features.join(", ")
+                ),
+                None => println!("      Uploaded: {}", style("no").red()),
+            }
+        }
+    }
+
+    if binaries.is_empty() {
+        eprintln!("warning: no binaries found in the IPA");
+    }
+    if missing > 0 {
+        eprintln!(
+            "error: debug symbols of {} of {} binaries are missing on Sentry",
+            missing,
+            binaries.len()
+        );
+        return Err(QuietExit(1).into());
+    }
+
+    Ok(())
+}
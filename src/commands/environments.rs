@@ -0,0 +1,136 @@
+//! Implements a command for managing environments.
+use std::io;
+
+use clap::{App, AppSettings, Arg, ArgMatches};
+use failure::Error;
+use glob::Pattern;
+
+use crate::api::Api;
+use crate::config::Config;
+use crate::utils::args::ArgExt;
+use crate::utils::formatting::Table;
+
+pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
+    let patterns_arg = Arg::with_name("patterns")
+        .value_name("ENVIRONMENT")
+        .required(true)
+        .multiple(true)
+        .index(1)
+        .help(
+            "The name of an environment.  Glob patterns like `pr-*` \
+             select all matching environments.",
+        );
+
+    app.about("Manage the environments of a project.")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .org_project_args()
+        .subcommand(
+            App::new("list")
+                .about("List the environments of a project.")
+                .arg(
+                    Arg::with_name("visibility")
+                        .long("visibility")
+                        .value_name("VISIBILITY")
+                        .possible_values(&["visible", "hidden", "all"])
+                        .help("Which environments to list. [defaults to 'visible']"),
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help("Print the environments as JSON."),
+                ),
+        )
+        .subcommand(
+            App::new("hide")
+                .about("Hide environments from the environment selectors.")
+                .arg(patterns_arg.clone()),
+        )
+        .subcommand(
+            App::new("unhide")
+                .about("Show hidden environments again.")
+                .arg(patterns_arg),
+        )
+}
+
+fn execute_list(org: &str, project: &str, matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let environments = Api::current().list_project_environments(
+        org,
+        project,
+        matches.value_of("visibility").unwrap_or("visible"),
+    )?;
+
+    if matches.is_present("json") {
+        serde_json::to_writer_pretty(&mut io::stdout(), &environments)?;
+        println!();
+        return Ok(());
+    }
+
+    let mut table = Table::new();
+    table.title_row().add("Name").add("Hidden");
+    for environment in &environments {
+        table
+            .add_row()
+            .add(&environment.name)
+            .add(if environment.is_hidden { "yes" } else { "no" });
+    }
+
+    if table.is_empty() {
+        println!("No environments found");
+    } else {
+        table.print();
+    }
+
+    Ok(())
+}
+
+fn execute_set_hidden(
+    org: &str,
+    project: &str,
+    matches: &ArgMatches<'_>,
+    hide: bool,
+) -> Result<(), Error> {
+    let api = Api::current();
+    let patterns = matches
+        .values_of("patterns")
+        .unwrap()
+        .map(Pattern::new)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // only look at environments whose visibility actually changes
+    let visibility = if hide { "visible" } else { "hidden" };
+    let mut changed = 0;
+    for environment in api.list_project_environments(org, project, visibility)? {
+        if !patterns.iter().any(|p| p.matches(&environment.name)) {
+            continue;
+        }
+        api.set_environment_hidden(org, project, &environment.name, hide)?;
+        println!(
+            "{} {}",
+            if hide { "Hid" } else { "Unhid" },
+            environment.name
+        );
+        changed += 1;
+    }
+
+    if changed == 0 {
+        println!("No matching {} environments found.", visibility);
+    }
+
+    Ok(())
+}
+
+pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let config = Config::current();
+    let (org, project) = config.get_org_and_project(matches)?;
+
+    if let Some(sub_matches) = matches.subcommand_matches("list") {
+        return execute_list(&org, &project, sub_matches);
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("hide") {
+        return execute_set_hidden(&org, &project, sub_matches, true);
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("unhide") {
+        return execute_set_hidden(&org, &project, sub_matches, false);
+    }
+    unreachable!();
+}
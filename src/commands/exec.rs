@@ -0,0 +1,271 @@
+//! Implements a container entrypoint that reports failures to Sentry.
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process;
+use std::time::Instant;
+
+use clap::{App, Arg, ArgMatches};
+use console::style;
+use failure::Error;
+use log::warn;
+use sentry::protocol::{Event, Level};
+use uuid::Uuid;
+
+use crate::api::{Api, CreateMonitorCheckIn, MonitorStatus, UpdateMonitorCheckIn};
+use crate::commands::run;
+use crate::config::Config;
+use crate::utils::args::{validate_monitor_slug, ArgExt};
+use crate::utils::event::with_sentry_client;
+use crate::utils::resource_usage::{ResourceUsage, UsageTracker};
+use crate::utils::system::QuietExit;
+
+const K8S_NAMESPACE_FILE: &str = "/var/run/secrets/kubernetes.io/serviceaccount/namespace";
+
+pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
+    run::add_sandbox_args(run::add_report_args(
+        app.about("Run a command as a container entrypoint and report failures.")
+            .long_about(
+                "Run a command as a container entrypoint and report failures.{n}{n}\
+                 Signals sent to sentry-cli (SIGTERM, SIGINT, SIGHUP, SIGQUIT, \
+                 SIGUSR1, SIGUSR2 and SIGWINCH) are forwarded to the command and \
+                 sentry-cli exits with its status, or 128 plus the signal number \
+                 if the command was killed.  When running as PID 1 orphaned \
+                 processes are reaped.  Crashes and non-zero exits are reported \
+                 like with `sentry-cli run`, tagged with the hostname, the image \
+                 and the container runtime.{n}{n}    \
+                 ENTRYPOINT [\"sentry-cli\", \"exec\", \"--\"]{n}    \
+                 CMD [\"./server\"]",
+            ),
+    ))
+    .org_arg()
+    .arg(
+        Arg::with_name("image")
+            .long("image")
+            .value_name("IMAGE")
+            .help(
+                "The image of the container, e.g. `registry.example.com/app:1.2`.{n}\
+                 Defaults to the SENTRY_CONTAINER_IMAGE environment variable.",
+            ),
+    )
+    .arg(
+        Arg::with_name("monitor_slug")
+            .long("monitor-slug")
+            .value_name("SLUG")
+            .validator(validate_monitor_slug)
+            .help("Report the run of the command as a check-in of this monitor."),
+    )
+}
+
+/// Splits an image reference into the image and its tag, ignoring the port
+/// of a registry and any digest.
+fn split_image_tag(image: &str) -> (&str, Option<&str>) {
+    let image = image.split('@').next().unwrap_or(image);
+    match image.rfind(':') {
+        Some(idx) if !image[idx..].contains('/') => (&image[..idx], Some(&image[idx + 1..])),
+        _ => (image, None),
+    }
+}
+
+fn detect_runtime() -> Option<&'static str> {
+    if env::var_os("KUBERNETES_SERVICE_HOST").is_some() {
+        Some("kubernetes")
+    } else if Path::new("/run/.containerenv").exists() {
+        Some("podman")
+    } else if Path::new("/.dockerenv").exists() {
+        Some("docker")
+    } else {
+        None
+    }
+}
+
+/// Adds what is known about the container to the event.
+fn add_container_context(event: &mut Event<'static>, image: Option<&str>) {
+    if let Ok(hostname) = env::var("HOSTNAME") {
+        event.server_name = Some(hostname.clone().into());
+        event.tags.insert("container.hostname".into(), hostname);
+    }
+    if let Some(image) = image {
+        let (name, tag) = split_image_tag(image);
+        event.tags.insert("container.image".into(), name.into());
+        if let Some(tag) = tag {
+            event.tags.insert("container.image_tag".into(), tag.into());
+        }
+    }
+    if let Some(runtime) = detect_runtime() {
+        event
+            .tags
+            .insert("container.runtime".into(), runtime.into());
+    }
+    if let Ok(namespace) = fs::read_to_string(K8S_NAMESPACE_FILE) {
+        event
+            .tags
+            .insert("kubernetes.namespace".into(), namespace.trim().into());
+    }
+}
+
+fn start_checkin(matches: &ArgMatches<'_>, slug: &str) -> Result<Uuid, Error> {
+    let org = Config::current().get_org(matches)?;
+    let checkin = Api::current().create_monitor_checkin_by_slug(
+        &org,
+        slug,
+        &CreateMonitorCheckIn {
+            status: MonitorStatus::InProgress,
+            monitor_config: None,
+        },
+    )?;
+    Ok(checkin.id)
+}
+
+fn finish_checkin(
+    matches: &ArgMatches<'_>,
+    slug: &str,
+    checkin_id: Uuid,
+    success: bool,
+    duration: u64,
+    usage: Option<ResourceUsage>,
+) -> Result<(), Error> {
+    let org = Config::current().get_org(matches)?;
+    Api::current().update_monitor_checkin_by_slug(
+        &org,
+        slug,
+        &checkin_id,
+        &UpdateMonitorCheckIn {
+            status: Some(if success {
+                MonitorStatus::Ok
+            } else {
+                MonitorStatus::Error
+            }),
+            duration: Some(duration),
+            resource_usage: usage,
+        },
+    )?;
+    Ok(())
+}
+
+/// Passes signals received by sentry-cli on to the child.
+#[cfg(unix)]
+fn forward_signals(pid: u32) -> Result<(), Error> {
+    use signal_hook::consts::*;
+
+    crate::utils::system::forward_interrupts();
+    let mut signals = signal_hook::iterator::Signals::new([
+        SIGTERM, SIGINT, SIGHUP, SIGQUIT, SIGUSR1, SIGUSR2, SIGWINCH,
+    ])?;
+    std::thread::spawn(move || {
+        for signal in signals.forever() {
+            unsafe {
+                libc::kill(pid as libc::pid_t, signal);
+            }
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn forward_signals(_pid: u32) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Waits for the child and returns its status and resource usage.  As PID 1
+/// every orphaned process in the container becomes our child, so they are
+/// reaped along the way.
+#[cfg(unix)]
+fn wait_child(
+    child: &mut process::Child,
+) -> io::Result<(process::ExitStatus, Option<ResourceUsage>)> {
+    use std::os::unix::process::ExitStatusExt;
+
+    if process::id() != 1 {
+        return UsageTracker::new(child).wait(child);
+    }
+    let pid = child.id() as libc::pid_t;
+    loop {
+        let mut status = 0;
+        let mut usage = unsafe { std::mem::zeroed() };
+        match unsafe { libc::wait4(-1, &mut status, 0, &mut usage) } {
+            -1 => {
+                let err = io::Error::last_os_error();
+                if err.kind() != io::ErrorKind::Interrupted {
+                    return Err(err);
+                }
+            }
+            reaped if reaped == pid => {
+                return Ok((
+                    process::ExitStatus::from_raw(status),
+                    Some(ResourceUsage::from_rusage(&usage)),
+                ))
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn wait_child(
+    child: &mut process::Child,
+) -> io::Result<(process::ExitStatus, Option<ResourceUsage>)> {
+    UsageTracker::new(child).wait(child)
+}
+
+pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let args: Vec<_> = matches.values_of("args").unwrap().collect();
+    let tail_lines: usize = matches.value_of("tail_lines").unwrap().parse().unwrap();
+    let image = matches
+        .value_of("image")
+        .map(str::to_owned)
+        .or_else(|| env::var("SENTRY_CONTAINER_IMAGE").ok());
+    let dsn = Config::current().get_dsn()?;
+
+    // the service must start even if sentry is unreachable
+    let slug = matches.value_of("monitor_slug");
+    let checkin_id = slug.and_then(|slug| match start_checkin(matches, slug) {
+        Ok(id) => Some(id),
+        Err(err) => {
+            warn!("Could not start check-in: {}", err);
+            None
+        }
+    });
+
+    let started = Instant::now();
+    let sandbox = run::Sandbox::from_matches(matches);
+    let (mut child, tee) = run::spawn_with_stderr_tail(&sandbox, &args, tail_lines)?;
+    forward_signals(child.id())?;
+    let (exit_status, usage) = wait_child(&mut child)?;
+    let elapsed = started.elapsed();
+    let tail = tee.join().unwrap_or_default();
+
+    if let (Some(slug), Some(checkin_id)) = (slug, checkin_id) {
+        let duration = elapsed.as_secs() * 1000 + u64::from(elapsed.subsec_millis());
+        if let Err(err) = finish_checkin(
+            matches,
+            slug,
+            checkin_id,
+            exit_status.success(),
+            duration,
+            usage,
+        ) {
+            warn!("Could not finish check-in: {}", err);
+        }
+    }
+
+    if exit_status.success() {
+        return Ok(());
+    }
+
+    let mut event = run::make_event(matches, &args, exit_status, elapsed, usage, tail)?;
+    if exit_status.code().is_none() {
+        event.level = Level::Fatal;
+    }
+    add_container_context(&mut event, image.as_deref());
+    let id = with_sentry_client(dsn, |c| c.capture_event(event, None));
+    eprintln!(
+        "{} command {}, reported as event {}",
+        style(">").dim(),
+        run::describe_exit(exit_status),
+        id
+    );
+
+    Err(QuietExit(run::exit_code(exit_status)).into())
+}
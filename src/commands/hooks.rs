@@ -0,0 +1,282 @@
+//! Implements a command for installing git hooks that run Sentry checks.
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::{App, AppSettings, Arg, ArgMatches};
+use console::style;
+use failure::{bail, Error, ResultExt};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::commands::ci::shell_quote;
+use crate::utils::args::{validate_regex, validate_version};
+use crate::utils::file_search::ReleaseFileSearch;
+use crate::utils::fs::path_as_url;
+use crate::utils::releases::detect_release_name;
+use crate::utils::sourcemaps::SourceMapProcessor;
+
+/// Marks hooks written by sentry-cli so they can be replaced safely.
+const HOOK_MARKER: &str = "# installed by sentry-cli hooks install";
+const HOOKS: &[&str] = &["pre-commit", "pre-push"];
+
+lazy_static! {
+    static ref DSN_RE: Regex = Regex::new(
+        r"https?://[0-9a-fA-F]{32}(?::[0-9a-fA-F]{32})?@[^\s/'\x22`]+/[^\s'\x22`]*?[0-9]+"
+    )
+    .unwrap();
+}
+
+fn check_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("sourcemaps")
+            .long("sourcemaps")
+            .value_name("PATH")
+            .multiple(true)
+            .number_of_values(1)
+            .help(
+                "Validate the JavaScript files and sourcemaps in this path.  \
+                 Can be given multiple times.",
+            ),
+        Arg::with_name("no_dsn_check")
+            .long("no-dsn-check")
+            .help("Do not check staged files for Sentry DSNs."),
+        Arg::with_name("release_pattern")
+            .long("release-pattern")
+            .value_name("REGEX")
+            .validator(validate_regex)
+            .help("Require the detected release name to match this regular expression."),
+    ]
+}
+
+pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.about("Manage git hooks that run Sentry checks.")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            App::new("install")
+                .about("Install git hooks that run Sentry checks.")
+                .long_about(
+                    "Install git hooks that run Sentry checks.{n}{n}\
+                     The pre-commit hook rejects staged files that contain a \
+                     Sentry DSN and the pre-push hook verifies the release name \
+                     sentry-cli detects for the pushed commit.  Both validate \
+                     the sourcemaps in the paths given with --sourcemaps.  The \
+                     options are stored in the hooks, install them again to \
+                     change them.",
+                )
+                .arg(
+                    Arg::with_name("hooks")
+                        .long("hook")
+                        .value_name("HOOK")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .possible_values(HOOKS)
+                        .help("The hook to install.  Defaults to all hooks."),
+                )
+                .arg(
+                    Arg::with_name("force")
+                        .long("force")
+                        .short("f")
+                        .help("Replace existing hooks not installed by sentry-cli."),
+                )
+                .args(&check_args()),
+        )
+        .subcommand(
+            App::new("run")
+                .about("Run the checks of a git hook.")
+                .setting(AppSettings::Hidden)
+                .arg(
+                    Arg::with_name("hook")
+                        .value_name("HOOK")
+                        .index(1)
+                        .required(true)
+                        .possible_values(HOOKS),
+                )
+                .arg(
+                    Arg::with_name("git_args")
+                        .value_name("ARGS")
+                        .index(2)
+                        .multiple(true),
+                )
+                .args(&check_args()),
+        )
+}
+
+fn open_repository() -> Result<git2::Repository, Error> {
+    Ok(git2::Repository::open_from_env().context("Could not find a git repository")?)
+}
+
+fn get_hooks_dir(repo: &git2::Repository) -> Result<PathBuf, Error> {
+    if let Ok(path) = repo.config()?.get_path("core.hooksPath") {
+        // relative hook paths are resolved against the work tree
+        return Ok(match repo.workdir() {
+            Some(workdir) if path.is_relative() => workdir.join(path),
+            _ => path,
+        });
+    }
+    Ok(repo.path().join("hooks"))
+}
+
+fn make_hook_script(hook: &str, matches: &ArgMatches<'_>) -> String {
+    let mut command = vec![
+        shell_quote(&env::current_exe().unwrap().display().to_string()),
+        "hooks".into(),
+        "run".into(),
+        hook.into(),
+    ];
+    for path in matches.values_of("sourcemaps").into_iter().flatten() {
+        command.push("--sourcemaps".into());
+        command.push(shell_quote(path));
+    }
+    if matches.is_present("no_dsn_check") {
+        command.push("--no-dsn-check".into());
+    }
+    if let Some(pattern) = matches.value_of("release_pattern") {
+        command.push("--release-pattern".into());
+        command.push(shell_quote(pattern));
+    }
+    format!(
+        "#!/bin/sh\n{}\nexec {} -- \"$@\"\n",
+        HOOK_MARKER,
+        command.join(" ")
+    )
+}
+
+fn write_hook(path: &Path, script: &str, force: bool) -> Result<(), Error> {
+    if let Ok(existing) = fs::read_to_string(path) {
+        if !force && !existing.contains(HOOK_MARKER) {
+            bail!(
+                "{} already exists. Use --force to replace it.",
+                path.display()
+            );
+        }
+    }
+    fs::write(path, script).with_context(|_| format!("Could not write {}", path.display()))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+    }
+
+    Ok(())
+}
+
+fn execute_install(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let repo = open_repository()?;
+    let hooks_dir = get_hooks_dir(&repo)?;
+    fs::create_dir_all(&hooks_dir)?;
+
+    let hooks = match matches.values_of("hooks") {
+        Some(hooks) => hooks.collect(),
+        None => HOOKS.to_vec(),
+    };
+    for hook in hooks {
+        let path = hooks_dir.join(hook);
+        write_hook(
+            &path,
+            &make_hook_script(hook, matches),
+            matches.is_present("force"),
+        )?;
+        println!("Installed {} hook at {}", hook, path.display());
+    }
+
+    Ok(())
+}
+
+/// Reports the DSNs in files staged for the next commit.
+fn check_dsns(repo: &git2::Repository) -> Result<bool, Error> {
+    let head_tree = repo.head().ok().and_then(|head| head.peel_to_tree().ok());
+    let diff = repo.diff_tree_to_index(head_tree.as_ref(), None, None)?;
+    let mut found = false;
+
+    for delta in diff.deltas() {
+        if delta.status() == git2::Delta::Deleted {
+            continue;
+        }
+        let file = delta.new_file();
+        let blob = match repo.find_blob(file.id()) {
+            Ok(blob) if !blob.is_binary() => blob,
+            _ => continue,
+        };
+        let path = file.path().unwrap_or_else(|| Path::new("?"));
+        let contents = String::from_utf8_lossy(blob.content());
+        for (idx, line) in contents.lines().enumerate() {
+            if DSN_RE.is_match(line) {
+                println!("  {}:{}: contains a Sentry DSN", path.display(), idx + 1);
+                found = true;
+            }
+        }
+    }
+
+    Ok(found)
+}
+
+fn check_sourcemaps(workdir: &Path, paths: clap::Values<'_>) -> Result<(), Error> {
+    let mut processor = SourceMapProcessor::new();
+    for path in paths {
+        let mut search = ReleaseFileSearch::new(workdir.join(path));
+        search.extensions(vec!["js", "map"]);
+        for source in search.collect_files()? {
+            let url = match source.path.strip_prefix(&source.base_path) {
+                Ok(local_path) => format!("~/{}", path_as_url(local_path)),
+                Err(_) => format!("~/{}", path_as_url(&source.path)),
+            };
+            processor.add(&url, source)?;
+        }
+    }
+    processor.validate_all()
+}
+
+fn check_release_name(pattern: Option<&str>) -> Result<(), Error> {
+    let release = detect_release_name()?;
+    if let Err(err) = validate_version(release.clone()) {
+        bail!("{} ({})", err, release);
+    }
+    if let Some(pattern) = pattern {
+        let re = Regex::new(&format!("^(?:{})$", pattern))?;
+        if !re.is_match(&release) {
+            bail!(
+                "Release name {} does not match the pattern {}",
+                release,
+                pattern
+            );
+        }
+    }
+    println!("Release name {} is valid", style(&release).cyan());
+    Ok(())
+}
+
+fn execute_run(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let repo = open_repository()?;
+    let workdir = repo.workdir().unwrap_or_else(|| repo.path()).to_path_buf();
+
+    match matches.value_of("hook").unwrap() {
+        "pre-commit" => {
+            if !matches.is_present("no_dsn_check") && check_dsns(&repo)? {
+                bail!(
+                    "Staged files contain Sentry DSNs. Load them from the \
+                     environment or configuration instead, or commit with \
+                     --no-verify to skip this check."
+                );
+            }
+        }
+        _ => check_release_name(matches.value_of("release_pattern"))?,
+    }
+
+    if let Some(paths) = matches.values_of("sourcemaps") {
+        check_sourcemaps(&workdir, paths)?;
+    }
+
+    Ok(())
+}
+
+pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    if let Some(sub_matches) = matches.subcommand_matches("install") {
+        return execute_install(sub_matches);
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("run") {
+        return execute_run(sub_matches);
+    }
+    unreachable!();
+}
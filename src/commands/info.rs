@@ -8,7 +8,9 @@ use serde::Serialize;
 
 use crate::api::Api;
 use crate::config::{Auth, Config};
+use crate::utils::fips;
 use crate::utils::system::QuietExit;
+use crate::utils::tls;
 
 #[derive(Serialize, Default)]
 pub struct AuthStatus {
@@ -89,6 +91,27 @@ pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
             org.unwrap_or_else(|| "-".into())
         );
         println!("Default Project: {}", project.unwrap_or_else(|| "-".into()));
+        println!("TLS Backend: {}", tls::describe_backend());
+        println!(
+            "FIPS Mode: {}",
+            if fips::is_enabled() {
+                "enabled"
+            } else {
+                "disabled"
+            }
+        );
+        for (label, module) in &[
+            ("TLS", fips::tls_module()),
+            ("Hashing", fips::hashing_module()),
+        ] {
+            match module.violation {
+                None => println!("  {}: {} (FIPS validated)", label, module.name),
+                Some(violation) => println!(
+                    "  {}: {} (not FIPS validated: {})",
+                    label, module.name, violation
+                ),
+            }
+        }
 
         if config.get_auth().is_some() {
             println!();
@@ -0,0 +1,67 @@
+//! Implements a command for working with Info.plist files.
+use clap::{App, AppSettings, Arg, ArgMatches};
+use failure::{bail, Error};
+
+use crate::utils::releases::get_xcode_release_name;
+use crate::utils::xcode::InfoPlist;
+
+pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.about("Read information from Info.plist files.")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            App::new("release-name")
+                .about("Print the release name of an Apple app.")
+                .long_about(
+                    "Print the release name of an Apple app.{n}{n}\
+                     The release name has the form `BUNDLE_ID@VERSION`, the same \
+                     name that sentry-cli uses for releases it creates from Xcode.  \
+                     When run from an Xcode build phase, the Info.plist of the \
+                     current target is used by default.",
+                )
+                .arg(
+                    Arg::with_name("path")
+                        .value_name("PATH")
+                        .index(1)
+                        .help("The path to the Info.plist."),
+                )
+                .arg(
+                    Arg::with_name("with_build")
+                        .long("with-build")
+                        .help("Append the build number to the release name (`+BUILD`)."),
+                ),
+        )
+}
+
+fn execute_release_name(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let plist = match matches.value_of("path") {
+        Some(path) => InfoPlist::from_path(path)?,
+        None => match InfoPlist::discover_from_env()? {
+            Some(plist) => plist,
+            None => bail!("Could not find an Info.plist. Pass the path or run from Xcode."),
+        },
+    };
+
+    // the name is always known for an explicit Info.plist
+    let build = plist.build().to_string();
+    let mut release_name = get_xcode_release_name(Some(plist))?.unwrap();
+    if matches.is_present("with_build") {
+        release_name = format!("{}+{}", release_name, build);
+    }
+    if release_name.contains("$(") || release_name.contains("${") {
+        bail!(
+            "The Info.plist contains unresolved build settings ({}). \
+             Run from Xcode or pass the processed Info.plist of the build.",
+            release_name
+        );
+    }
+
+    println!("{}", release_name);
+    Ok(())
+}
+
+pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    if let Some(sub_matches) = matches.subcommand_matches("release-name") {
+        return execute_release_name(sub_matches);
+    }
+    unreachable!();
+}
@@ -1,11 +1,27 @@
 //! Implements a command for issue management.
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::thread;
+
+use chrono::{DateTime, Utc};
 use clap::{App, AppSettings, Arg, ArgMatches};
-use failure::{Error, ResultExt};
+use console::style;
+use failure::{bail, err_msg, Error, ResultExt};
 use log::info;
+use sentry::protocol::{Event, Level, LogEntry};
+use serde::Serialize;
 
-use crate::api::{Api, IssueChanges, IssueFilter};
+use crate::api::{Api, EventFrame, Issue, IssueChanges, IssueEvent, IssueFilter, IssueQuery};
 use crate::config::Config;
-use crate::utils::args::ArgExt;
+use crate::utils::args::{
+    get_duration, validate_duration, validate_int, validate_interval, ArgExt,
+};
+use crate::utils::event::{get_sdk_info, with_sentry_client};
+use crate::utils::formatting::{HumanDuration, Sparkline, Table};
+use crate::utils::releases::detect_release_name;
+use crate::utils::system::QuietExit;
 
 pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
     app.about("Manage issues in Sentry.")
@@ -33,18 +49,392 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
                 .long("id")
                 .help("Select the issue with the given ID."),
         )
+        .arg(
+            Arg::with_name("query")
+                .long("query")
+                .short("q")
+                .value_name("QUERY")
+                .conflicts_with_all(&["all", "status", "id", "stdin"])
+                .help("Select all issues matching a Sentry search query."),
+        )
+        .arg(Arg::with_name("stdin").long("stdin").help(
+            "Read IDs of issues to select from stdin.{n}\
+                     IDs can be separated by any whitespace and are combined \
+                     with IDs passed via `--id`.",
+        ))
+        .subcommand(
+            App::new("list")
+                .about("List issues matching a search query.")
+                .arg(
+                    Arg::with_name("query")
+                        .long("query")
+                        .short("q")
+                        .value_name("QUERY")
+                        .help(
+                            "A Sentry search query, e.g. `is:unresolved release:1.0.0`.{n}\
+                             If `--status` or `--query` are passed to the issues command \
+                             they are added to this query.  [defaults to `is:unresolved`]",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("sort")
+                        .long("sort")
+                        .value_name("SORT")
+                        .possible_values(&["date", "new", "priority", "freq", "user"])
+                        .help("The order in which issues are returned. [defaults to `date`]"),
+                )
+                .arg(
+                    Arg::with_name("max_rows")
+                        .long("max-rows")
+                        .value_name("COUNT")
+                        .validator(validate_int)
+                        .help(
+                            "Stop fetching pages once this many issues were found.{n}\
+                             Set to 0 to fetch all matching issues. [defaults to 100]",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help("Print the matching issues as JSON."),
+                ),
+        )
+        .subcommand(
+            App::new("export")
+                .about("Export issues matching a search query to a file.")
+                .arg(
+                    Arg::with_name("query")
+                        .long("query")
+                        .short("q")
+                        .value_name("QUERY")
+                        .help("A Sentry search query, e.g. `is:unresolved release:1.0.0`."),
+                )
+                .arg(
+                    Arg::with_name("sort")
+                        .long("sort")
+                        .value_name("SORT")
+                        .possible_values(&["date", "new", "priority", "freq", "user"])
+                        .help("The order in which issues are exported. [defaults to `date`]"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .possible_values(&["jsonl", "csv"])
+                        .default_value("jsonl")
+                        .help("The output format."),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .long("output")
+                        .short("o")
+                        .value_name("PATH")
+                        .help("The file to write to. [defaults to stdout]"),
+                )
+                .arg(
+                    Arg::with_name("max_rows")
+                        .long("max-rows")
+                        .value_name("COUNT")
+                        .validator(validate_int)
+                        .help("Stop after exporting this many issues. [defaults to all]"),
+                )
+                .arg(Arg::with_name("with_events").long("with-events").help(
+                    "Include the events of every issue as an `events` array.{n}\
+                             This is only supported for the jsonl format.",
+                ))
+                .arg(
+                    Arg::with_name("max_events")
+                        .long("max-events")
+                        .value_name("COUNT")
+                        .validator(validate_int)
+                        .requires("with_events")
+                        .help("The maximum number of events exported per issue. [defaults to 100]"),
+                ),
+        )
+        .subcommand(
+            App::new("stats")
+                .about("Show event counts and trends of issues matching a search query.")
+                .arg(
+                    Arg::with_name("query")
+                        .long("query")
+                        .short("q")
+                        .value_name("QUERY")
+                        .help("A Sentry search query, e.g. `is:unresolved release:1.0.0`."),
+                )
+                .arg(
+                    Arg::with_name("period")
+                        .long("period")
+                        .value_name("PERIOD")
+                        .possible_values(&["24h", "14d"])
+                        .help("The period to show statistics for. [defaults to `24h`]"),
+                )
+                .arg(
+                    Arg::with_name("sort")
+                        .long("sort")
+                        .value_name("SORT")
+                        .possible_values(&["date", "new", "priority", "freq", "user"])
+                        .help("The order in which issues are shown. [defaults to `freq`]"),
+                )
+                .arg(
+                    Arg::with_name("max_rows")
+                        .long("max-rows")
+                        .value_name("COUNT")
+                        .validator(validate_int)
+                        .help("The maximum number of issues to show. [defaults to 25]"),
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help("Print the statistics as JSON."),
+                ),
+        )
+        .subcommand(
+            App::new("compare")
+                .about("Compare the issues of two releases to find new and regressed issues.")
+                .arg(
+                    Arg::with_name("base")
+                        .long("base")
+                        .value_name("RELEASE")
+                        .required(true)
+                        .help("The release to compare against, e.g. the one currently deployed."),
+                )
+                .arg(
+                    Arg::with_name("head")
+                        .long("head")
+                        .value_name("RELEASE")
+                        .required(true)
+                        .help("The release to check for new or regressed issues."),
+                )
+                .arg(
+                    Arg::with_name("query")
+                        .long("query")
+                        .short("q")
+                        .value_name("QUERY")
+                        .help("Only compare issues matching a Sentry search query."),
+                )
+                .arg(Arg::with_name("fail_on_new").long("fail-on-new").help(
+                    "Exit with a non-zero status if the head release introduced \
+                             new or regressed issues.",
+                ))
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help("Print the comparison as JSON."),
+                ),
+        )
+        .subcommand(
+            App::new("show")
+                .about("Show an issue and the stacktrace of its latest event.")
+                .arg(
+                    Arg::with_name("issue")
+                        .value_name("ISSUE")
+                        .required(true)
+                        .index(1)
+                        .help("The numeric ID or the short ID (e.g. `PROJECT-1A`) of the issue."),
+                )
+                .arg(
+                    Arg::with_name("in_app")
+                        .long("in-app")
+                        .help("Only show frames that belong to the application."),
+                )
+                .arg(
+                    Arg::with_name("no_context")
+                        .long("no-context")
+                        .help("Do not show source context around frames."),
+                )
+                .arg(
+                    Arg::with_name("vars")
+                        .long("vars")
+                        .help("Show local variables of frames if they were captured."),
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help("Print the issue and its latest event as JSON."),
+                ),
+        )
+        .subcommand(
+            App::new("create")
+                .visible_alias("annotate")
+                .about("Record an operational event, e.g. a manual intervention.")
+                .long_about(
+                    "Record an operational event, e.g. a manual intervention.{n}{n}\
+                     This sends an event with the given message through the configured DSN \
+                     so that it shows up alongside real errors. Annotations are sent with the \
+                     `annotation` logger and can be found by searching for `logger:annotation`.",
+                )
+                .arg(
+                    Arg::with_name("message")
+                        .value_name("MESSAGE")
+                        .required(true)
+                        .index(1)
+                        .help("The message of the annotation, e.g. `cache flush performed`."),
+                )
+                .arg(
+                    Arg::with_name("level")
+                        .long("level")
+                        .short("l")
+                        .value_name("LEVEL")
+                        .possible_values(&["debug", "info", "warning", "error", "fatal"])
+                        .help("The severity of the event. [defaults to 'info']"),
+                )
+                .arg(
+                    Arg::with_name("tags")
+                        .long("tag")
+                        .short("t")
+                        .value_name("KEY:VALUE")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("Add a tag (key:value) to the event."),
+                )
+                .arg(
+                    Arg::with_name("fingerprint")
+                        .long("fingerprint")
+                        .short("f")
+                        .value_name("FINGERPRINT")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help(
+                            "Change the fingerprint of the event.{n}\
+                             By default all annotations with the same message are grouped \
+                             into one issue.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("release")
+                        .long("release")
+                        .short("r")
+                        .value_name("RELEASE")
+                        .help("Optional identifier of the release."),
+                )
+                .arg(
+                    Arg::with_name("environment")
+                        .long("env")
+                        .short("E")
+                        .value_name("ENVIRONMENT")
+                        .help("Send with a specific environment."),
+                ),
+        )
+        .subcommand(
+            App::new("link")
+                .about("Link an issue to an issue of an external tracker.")
+                .arg(
+                    Arg::with_name("issue")
+                        .value_name("ISSUE")
+                        .required(true)
+                        .index(1)
+                        .help("The numeric ID or the short ID (e.g. `PROJECT-1A`) of the issue."),
+                )
+                .arg(
+                    Arg::with_name("provider")
+                        .long("provider")
+                        .value_name("PROVIDER")
+                        .required(true)
+                        .help("The integration provider, e.g. `jira` or `github`."),
+                )
+                .arg(
+                    Arg::with_name("key")
+                        .long("key")
+                        .value_name("KEY")
+                        .required(true)
+                        .help("The key of the external issue, e.g. `PROJ-123`."),
+                )
+                .arg(
+                    Arg::with_name("integration")
+                        .long("integration")
+                        .value_name("ID")
+                        .help(
+                            "The ID of the integration to use if more than one \
+                             integration of the provider is installed.",
+                        ),
+                ),
+        )
+        .subcommand(
+            App::new("tail")
+                .about("Print new events of an issue or a search query as they arrive.")
+                .arg(
+                    Arg::with_name("target")
+                        .value_name("ISSUE_OR_QUERY")
+                        .required(true)
+                        .index(1)
+                        .help(
+                            "The numeric ID or the short ID of an issue, or a Sentry \
+                             search query matching the events of the project.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("interval")
+                        .long("interval")
+                        .value_name("DURATION")
+                        .validator(validate_interval)
+                        .help(
+                            "How often to poll for new events, at least one second. \
+                             [defaults to 10s]",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("lines")
+                        .long("lines")
+                        .short("n")
+                        .value_name("COUNT")
+                        .validator(validate_int)
+                        .help("The number of recent events to print initially. [defaults to 10]"),
+                )
+                .arg(
+                    Arg::with_name("no_follow")
+                        .long("no-follow")
+                        .help("Print the recent events and exit instead of polling."),
+                ),
+        )
         .subcommand(
             App::new("resolve")
                 .about("Bulk resolve all selected issues.")
                 .arg(
                     Arg::with_name("next_release")
                         .long("next-release")
+                        .alias("in-next-release")
                         .short("n")
                         .help("Only select issues in the next release."),
                 ),
         )
         .subcommand(App::new("mute").about("Bulk mute all selected issues."))
+        .subcommand(
+            App::new("ignore")
+                .about("Bulk ignore all selected issues.")
+                .arg(
+                    Arg::with_name("until")
+                        .long("until")
+                        .value_name("DURATION")
+                        .validator(validate_duration)
+                        .help(
+                            "Only ignore the issues for the given duration, \
+                             e.g. `30m`, `12h` or `7d`.",
+                        ),
+                ),
+        )
         .subcommand(App::new("unresolve").about("Bulk unresolve all selected issues."))
+        .subcommand(
+            App::new("assign")
+                .about("Bulk assign all selected issues.")
+                .arg(
+                    Arg::with_name("actor")
+                        .value_name("ACTOR")
+                        .required(true)
+                        .index(1)
+                        .help(
+                            "The user or team to assign the issues to.{n}\
+                             This can be a username, an email address, `user:ID` \
+                             or `team:ID`.",
+                        ),
+                ),
+        )
+        .subcommand(
+            App::new("subscribe").about("Subscribe to notifications for all selected issues."),
+        )
+        .subcommand(
+            App::new("unsubscribe")
+                .about("Unsubscribe from notifications for all selected issues."),
+        )
 }
 
 fn get_filter_from_matches(matches: &ArgMatches<'_>) -> Result<IssueFilter, Error> {
@@ -54,12 +444,22 @@ fn get_filter_from_matches(matches: &ArgMatches<'_>) -> Result<IssueFilter, Erro
     if let Some(status) = matches.value_of("status") {
         return Ok(IssueFilter::Status(status.into()));
     }
+    if let Some(query) = matches.value_of("query") {
+        return Ok(IssueFilter::Query(query.into()));
+    }
     let mut ids = vec![];
     if let Some(values) = matches.values_of("id") {
         for value in values {
             ids.push(value.parse::<u64>().context("Invalid issue ID")?);
         }
     }
+    if matches.is_present("stdin") {
+        let mut input = String::new();
+        io::stdin().read_to_string(&mut input)?;
+        for value in input.split_whitespace() {
+            ids.push(value.parse::<u64>().context("Invalid issue ID")?);
+        }
+    }
 
     if ids.is_empty() {
         Ok(IssueFilter::Empty)
@@ -79,15 +479,795 @@ fn execute_change(
         if let Some(status) = changes.new_status.as_ref() {
             println!("  new status: {}", status);
         }
+        if let Some(duration) = changes.snooze_duration {
+            println!("  ignored for: {} minutes", duration);
+        }
+        if let Some(actor) = changes.assigned_to.as_ref() {
+            println!("  assigned to: {}", actor);
+        }
+        if let Some(subscribed) = changes.is_subscribed {
+            println!(
+                "  notifications: {}",
+                if subscribed {
+                    "subscribed"
+                } else {
+                    "unsubscribed"
+                }
+            );
+        }
     } else {
         println!("No changes requested.");
     }
     Ok(())
 }
 
+/// Combines the selection of the issues command with the query of a
+/// subcommand into a single search query.
+fn get_query_from_matches(
+    matches: &ArgMatches<'_>,
+    sub_matches: &ArgMatches<'_>,
+) -> Option<String> {
+    let mut query = vec![];
+    if let Some(status) = matches.value_of("status") {
+        query.push(format!("is:{}", status));
+    }
+    query.extend(matches.value_of("query").map(str::to_owned));
+    query.extend(sub_matches.value_of("query").map(str::to_owned));
+    if query.is_empty() {
+        None
+    } else {
+        Some(query.join(" "))
+    }
+}
+
+fn get_limit(
+    matches: &ArgMatches<'_>,
+    name: &str,
+    default: Option<usize>,
+) -> Result<Option<usize>, Error> {
+    Ok(match matches.value_of(name) {
+        Some("0") => None,
+        Some(value) => Some(value.parse()?),
+        None => default,
+    })
+}
+
+fn execute_list(
+    org: &str,
+    project: &str,
+    matches: &ArgMatches<'_>,
+    sub_matches: &ArgMatches<'_>,
+) -> Result<(), Error> {
+    let query = get_query_from_matches(matches, sub_matches);
+    let max_rows = get_limit(sub_matches, "max_rows", Some(100))?;
+
+    let issues = Api::current().list_issues(
+        org,
+        project,
+        &IssueQuery {
+            query: query.as_deref(),
+            sort: sub_matches.value_of("sort"),
+            ..Default::default()
+        },
+        max_rows,
+    )?;
+
+    if sub_matches.is_present("json") {
+        serde_json::to_writer_pretty(&mut io::stdout(), &issues)?;
+        println!();
+        return Ok(());
+    }
+
+    let mut table = Table::new();
+    table
+        .title_row()
+        .add("Issue")
+        .add("Title")
+        .add("Events")
+        .add("Users")
+        .add("Last Seen");
+
+    for issue in &issues {
+        table
+            .add_row()
+            .add(&issue.short_id)
+            .add(&issue.title)
+            .add(&issue.count)
+            .add(issue.user_count)
+            .add(format!(
+                "{} ago",
+                HumanDuration(Utc::now().signed_duration_since(issue.last_seen))
+            ));
+    }
+
+    if table.is_empty() {
+        println!("No issues found");
+    } else {
+        table.print();
+    }
+
+    Ok(())
+}
+
+fn print_frame(frame: &EventFrame, show_context: bool, show_vars: bool) {
+    let location = frame
+        .filename
+        .as_deref()
+        .or(frame.abs_path.as_deref())
+        .or(frame.module.as_deref())
+        .unwrap_or("<unknown>");
+    let location = match (frame.line_no, frame.col_no) {
+        (Some(line), Some(col)) => format!("{}:{}:{}", location, line, col),
+        (Some(line), None) => format!("{}:{}", location, line),
+        _ => location.to_string(),
+    };
+    let function = frame.function.as_deref().unwrap_or("?");
+    if frame.in_app == Some(true) {
+        println!("  at {} ({})", style(function).bold(), location);
+    } else {
+        println!("  at {} ({})", function, style(location).dim());
+    }
+
+    if show_context {
+        let width = frame
+            .context
+            .iter()
+            .map(|&(lineno, _)| lineno.to_string().len())
+            .max()
+            .unwrap_or(0);
+        for (lineno, line) in &frame.context {
+            let line = line.as_deref().unwrap_or("");
+            if Some(*lineno) == frame.line_no {
+                println!(
+                    "    > {:>width$} | {}",
+                    lineno,
+                    style(line).bold(),
+                    width = width
+                );
+            } else {
+                println!(
+                    "      {} {}",
+                    style(format!("{:>width$} |", lineno, width = width)).dim(),
+                    line
+                );
+            }
+        }
+    }
+
+    if show_vars {
+        if let Some(ref vars) = frame.vars {
+            for (key, value) in vars {
+                println!("      {} = {}", style(key).cyan(), value);
+            }
+        }
+    }
+}
+
+fn print_event(event: &IssueEvent, matches: &ArgMatches<'_>) {
+    let show_context = !matches.is_present("no_context");
+    let show_vars = matches.is_present("vars");
+    let in_app_only = matches.is_present("in_app");
+
+    print!("Latest event: {}", event.event_id);
+    if let Some(date) = event.date_created {
+        print!(
+            " ({} ago)",
+            HumanDuration(Utc::now().signed_duration_since(date))
+        );
+    }
+    println!();
+    for tag in &event.tags {
+        println!("  {}: {}", tag.key, tag.value);
+    }
+
+    let exceptions = event.exceptions();
+    if exceptions.is_empty() {
+        if let Some(message) = event.log_message().or_else(|| event.message.clone()) {
+            println!();
+            println!("{}", message);
+        }
+        return;
+    }
+
+    for exception in &exceptions {
+        println!();
+        match (exception.ty.as_deref(), exception.value.as_deref()) {
+            (Some(ty), Some(value)) => println!("{}: {}", style(ty).red().bold(), value),
+            (Some(ty), None) => println!("{}", style(ty).red().bold()),
+            (None, Some(value)) => println!("{}", value),
+            (None, None) => println!("{}", style("<unknown exception>").red()),
+        }
+
+        let frames = match exception.stacktrace {
+            Some(ref stacktrace) => &stacktrace.frames[..],
+            None => &[],
+        };
+        let mut hidden = 0;
+        for frame in frames {
+            if in_app_only && frame.in_app != Some(true) {
+                hidden += 1;
+                continue;
+            }
+            print_frame(frame, show_context, show_vars);
+        }
+        if hidden > 0 {
+            println!("  {}", style(format!("({} frames hidden)", hidden)).dim());
+        }
+    }
+}
+
+fn execute_show(org: &str, matches: &ArgMatches<'_>) -> Result<(), Error> {
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct IssueDetails {
+        #[serde(flatten)]
+        issue: Issue,
+        latest_event: Option<IssueEvent>,
+    }
+
+    let api = Api::current();
+    let issue_id = matches.value_of("issue").unwrap();
+    let issue = match api.get_issue(org, issue_id)? {
+        Some(issue) => issue,
+        None => bail!("Issue {} not found", issue_id),
+    };
+    let latest_event = api.get_latest_issue_event(&issue.id)?;
+
+    if matches.is_present("json") {
+        serde_json::to_writer_pretty(
+            &mut io::stdout(),
+            &IssueDetails {
+                issue,
+                latest_event,
+            },
+        )?;
+        println!();
+        return Ok(());
+    }
+
+    println!("{} {}", style(&issue.short_id).bold(), issue.title);
+    if let Some(ref culprit) = issue.culprit {
+        println!("  Culprit: {}", culprit);
+    }
+    println!("  Status: {}", issue.status);
+    if let Some(ref level) = issue.level {
+        println!("  Level: {}", level);
+    }
+    println!("  Events: {}", issue.count);
+    println!("  Users: {}", issue.user_count);
+    println!(
+        "  First seen: {} ago",
+        HumanDuration(Utc::now().signed_duration_since(issue.first_seen))
+    );
+    println!(
+        "  Last seen: {} ago",
+        HumanDuration(Utc::now().signed_duration_since(issue.last_seen))
+    );
+    if let Some(ref permalink) = issue.permalink {
+        println!("  URL: {}", permalink);
+    }
+
+    if let Some(ref event) = latest_event {
+        println!();
+        print_event(event, matches);
+    }
+
+    Ok(())
+}
+
+fn execute_link(org: &str, matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let api = Api::current();
+    let issue_id = matches.value_of("issue").unwrap();
+    let provider = matches.value_of("provider").unwrap();
+
+    let issue = match api.get_issue(org, issue_id)? {
+        Some(issue) => issue,
+        None => bail!("Issue {} not found", issue_id),
+    };
+
+    let integrations = api.list_organization_integrations(org, Some(provider))?;
+    let integration = match matches.value_of("integration") {
+        Some(id) => integrations.iter().find(|i| i.id == id),
+        None if integrations.len() > 1 => bail!(
+            "Found {} {} integrations, select one with --integration: {}",
+            integrations.len(),
+            provider,
+            integrations
+                .iter()
+                .map(|i| format!("{} ({})", i.id, i.name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        None => integrations.first(),
+    };
+    let integration = match integration {
+        Some(integration) => integration,
+        None => bail!("No matching {} integration installed", provider),
+    };
+
+    let external =
+        api.link_external_issue(&issue.id, &integration.id, matches.value_of("key").unwrap())?;
+    println!(
+        "Linked {} to {} issue {}",
+        issue.short_id, integration.provider.name, external.key
+    );
+    if let Some(url) = external.url {
+        println!("  URL: {}", url);
+    }
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IssueStats<'a> {
+    id: &'a str,
+    short_id: &'a str,
+    title: &'a str,
+    events: u64,
+    users: u64,
+    trend: Vec<u64>,
+    sparkline: String,
+}
+
+fn execute_stats(
+    org: &str,
+    project: &str,
+    matches: &ArgMatches<'_>,
+    sub_matches: &ArgMatches<'_>,
+) -> Result<(), Error> {
+    let query = get_query_from_matches(matches, sub_matches);
+    let period = sub_matches.value_of("period").unwrap_or("24h");
+
+    let issues = Api::current().list_issues(
+        org,
+        project,
+        &IssueQuery {
+            query: query.as_deref(),
+            sort: Some(sub_matches.value_of("sort").unwrap_or("freq")),
+            stats_period: Some(period),
+        },
+        get_limit(sub_matches, "max_rows", Some(25))?,
+    )?;
+
+    let stats: Vec<_> = issues
+        .iter()
+        .map(|issue| {
+            let trend: Vec<u64> = issue
+                .stats
+                .as_ref()
+                .and_then(|stats| stats.get(period))
+                .map(|buckets| buckets.iter().map(|&(_, count)| count).collect())
+                .unwrap_or_default();
+            IssueStats {
+                id: &issue.id,
+                short_id: &issue.short_id,
+                title: &issue.title,
+                events: trend.iter().sum(),
+                users: issue.user_count,
+                sparkline: Sparkline(&trend).to_string(),
+                trend,
+            }
+        })
+        .collect();
+
+    if sub_matches.is_present("json") {
+        serde_json::to_writer_pretty(&mut io::stdout(), &stats)?;
+        println!();
+        return Ok(());
+    }
+
+    let mut table = Table::new();
+    table
+        .title_row()
+        .add("Issue")
+        .add("Title")
+        .add(format!("Events ({})", period))
+        .add("Users")
+        .add("Trend");
+
+    for issue in &stats {
+        table
+            .add_row()
+            .add(issue.short_id)
+            .add(issue.title)
+            .add(issue.events)
+            .add(issue.users)
+            .add(&issue.sparkline);
+    }
+
+    if table.is_empty() {
+        println!("No issues found");
+    } else {
+        table.print();
+    }
+
+    Ok(())
+}
+
+fn print_tail_event(event: &IssueEvent) {
+    let time = match event.date_created {
+        Some(date) => date.format("%Y-%m-%d %H:%M:%S").to_string(),
+        None => "-".repeat(19),
+    };
+    let release = event
+        .tags
+        .iter()
+        .find(|tag| tag.key == "release")
+        .map(|tag| format!(" [{}]", tag.value))
+        .unwrap_or_default();
+    println!(
+        "{} {} {}{}",
+        style(time).dim(),
+        style(event.event_id.get(..8).unwrap_or(&event.event_id)).cyan(),
+        event.title,
+        style(release).dim()
+    );
+}
+
+/// Remembers the ids of tailed events and forgets those older than the
+/// oldest of the new events.  Returns the date of the oldest event still
+/// remembered.
+fn remember_events(
+    events: &[IssueEvent],
+    seen: &mut HashMap<String, Option<DateTime<Utc>>>,
+    cutoff: DateTime<Utc>,
+) -> DateTime<Utc> {
+    let cutoff = events
+        .iter()
+        .filter_map(|event| event.date_created)
+        .min()
+        .unwrap_or(cutoff);
+    for event in events {
+        seen.insert(event.event_id.clone(), event.date_created);
+    }
+    seen.retain(|_, date| date.is_none_or(|date| date >= cutoff));
+    cutoff
+}
+
+fn execute_tail(config: &Config, matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let api = Api::current();
+    let sub_matches = matches.subcommand_matches("tail").unwrap();
+    let target = sub_matches.value_of("target").unwrap();
+    let lines = match sub_matches.value_of("lines") {
+        Some(lines) => lines.parse()?,
+        None => 10,
+    };
+    let interval = match sub_matches.value_of("interval") {
+        Some(interval) => get_duration(interval)?.to_std()?,
+        None => std::time::Duration::from_secs(10),
+    };
+
+    // anything that could be an issue ID is looked up first, everything
+    // else is treated as a search query for the events of the project.
+    let org = config.get_org(matches)?;
+    let issue = if target.contains(|c: char| c == ':' || c.is_whitespace()) {
+        None
+    } else {
+        api.get_issue(&org, target)?
+    };
+    let project = match issue {
+        Some(_) => None,
+        None => Some(config.get_org_and_project(matches)?.1),
+    };
+
+    let fetch = |max_rows, stop: &dyn Fn(&IssueEvent) -> bool| match (&issue, &project) {
+        (Some(issue), _) => api.list_issue_events_until(&issue.id, max_rows, stop),
+        (None, project) => api.list_project_events_until(
+            &org,
+            project.as_ref().unwrap(),
+            Some(target),
+            max_rows,
+            stop,
+        ),
+    };
+
+    if let Some(ref issue) = issue {
+        info!("Tailing events of issue {}", issue.short_id);
+    } else {
+        info!("Tailing events matching '{}'", target);
+    }
+
+    // events are listed newest first, polling stops at the first event
+    // that was already printed or is older than the remembered ones.
+    let mut seen = HashMap::new();
+    let mut events = fetch(Some(lines.max(100)), &|_| false)?;
+    let mut cutoff = remember_events(&events, &mut seen, Utc::now());
+    events.truncate(lines);
+    for event in events.iter().rev() {
+        print_tail_event(event);
+    }
+
+    if sub_matches.is_present("no_follow") {
+        return Ok(());
+    }
+
+    loop {
+        thread::sleep(interval);
+        let events = fetch(None, &|event| {
+            seen.contains_key(&event.event_id)
+                || event.date_created.is_some_and(|date| date < cutoff)
+        })?;
+        for event in events.iter().rev() {
+            print_tail_event(event);
+        }
+        cutoff = remember_events(&events, &mut seen, cutoff);
+    }
+}
+
+#[derive(Serialize)]
+struct IssueComparison<'a> {
+    base: &'a str,
+    head: &'a str,
+    new: Vec<&'a Issue>,
+    regressed: Vec<&'a Issue>,
+    persisting: usize,
+    gone: usize,
+}
+
+fn print_compared_issues(heading: &str, issues: &[&Issue]) {
+    if issues.is_empty() {
+        return;
+    }
+    println!();
+    println!("{}:", heading);
+    for issue in issues {
+        println!("  {} {}", style(&issue.short_id).bold(), issue.title);
+    }
+}
+
+fn execute_compare(
+    org: &str,
+    project: &str,
+    matches: &ArgMatches<'_>,
+    sub_matches: &ArgMatches<'_>,
+) -> Result<(), Error> {
+    let api = Api::current();
+    let base = sub_matches.value_of("base").unwrap();
+    let head = sub_matches.value_of("head").unwrap();
+    let query = get_query_from_matches(matches, sub_matches);
+
+    let fetch = |filter: String| {
+        let query = match query {
+            Some(ref query) => format!("{} {}", query, filter),
+            None => filter,
+        };
+        api.list_issues(
+            org,
+            project,
+            &IssueQuery {
+                query: Some(&query),
+                ..Default::default()
+            },
+            None,
+        )
+    };
+
+    let base_issues = fetch(format!("release:\"{}\"", base))?;
+    let head_issues = fetch(format!("release:\"{}\"", head))?;
+    let first_seen_in_head: HashSet<_> = fetch(format!("first-release:\"{}\"", head))?
+        .into_iter()
+        .map(|issue| issue.id)
+        .collect();
+    // Sentry marks resolved issues that occur again as regressed
+    let regressed_in_head: HashSet<_> = fetch(format!("is:regressed release:\"{}\"", head))?
+        .into_iter()
+        .map(|issue| issue.id)
+        .collect();
+    let base_ids: HashSet<_> = base_issues.iter().map(|issue| &issue.id).collect();
+    let head_ids: HashSet<_> = head_issues.iter().map(|issue| &issue.id).collect();
+
+    let mut comparison = IssueComparison {
+        base,
+        head,
+        new: vec![],
+        regressed: vec![],
+        persisting: 0,
+        gone: base_ids.difference(&head_ids).count(),
+    };
+    for issue in &head_issues {
+        if first_seen_in_head.contains(&issue.id) {
+            comparison.new.push(issue);
+        } else if regressed_in_head.contains(&issue.id) {
+            comparison.regressed.push(issue);
+        } else {
+            comparison.persisting += 1;
+        }
+    }
+
+    if sub_matches.is_present("json") {
+        serde_json::to_writer_pretty(&mut io::stdout(), &comparison)?;
+        println!();
+    } else {
+        println!("Comparing {} to {}", style(base).cyan(), style(head).cyan());
+        println!("  New issues: {}", comparison.new.len());
+        println!("  Regressed issues: {}", comparison.regressed.len());
+        println!("  Persisting issues: {}", comparison.persisting);
+        println!("  Gone issues: {}", comparison.gone);
+        print_compared_issues("New issues", &comparison.new);
+        print_compared_issues("Regressed issues", &comparison.regressed);
+    }
+
+    if sub_matches.is_present("fail_on_new")
+        && (!comparison.new.is_empty() || !comparison.regressed.is_empty())
+    {
+        return Err(QuietExit(1).into());
+    }
+
+    Ok(())
+}
+
+fn execute_export(
+    org: &str,
+    project: &str,
+    matches: &ArgMatches<'_>,
+    sub_matches: &ArgMatches<'_>,
+) -> Result<(), Error> {
+    let format = sub_matches.value_of("format").unwrap();
+    let with_events = sub_matches.is_present("with_events");
+    if with_events && format != "jsonl" {
+        bail!("Events can only be exported in the jsonl format");
+    }
+
+    let api = Api::current();
+    let query = get_query_from_matches(matches, sub_matches);
+    let max_events = get_limit(sub_matches, "max_events", Some(100))?;
+    let issues = api.list_issues(
+        org,
+        project,
+        &IssueQuery {
+            query: query.as_deref(),
+            sort: sub_matches.value_of("sort"),
+            ..Default::default()
+        },
+        get_limit(sub_matches, "max_rows", None)?,
+    )?;
+
+    let output = sub_matches.value_of("output");
+    let mut out: Box<dyn Write> = match output {
+        Some(path) => Box::new(BufWriter::new(
+            File::create(path).context("Could not create output file")?,
+        )),
+        None => Box::new(io::stdout()),
+    };
+
+    if format == "csv" {
+        let mut writer = csv::Writer::from_writer(out);
+        writer.write_record([
+            "id",
+            "shortId",
+            "title",
+            "culprit",
+            "status",
+            "level",
+            "count",
+            "userCount",
+            "firstSeen",
+            "lastSeen",
+            "permalink",
+        ])?;
+        for issue in &issues {
+            writer.write_record([
+                issue.id.as_str(),
+                &issue.short_id,
+                &issue.title,
+                issue.culprit.as_deref().unwrap_or(""),
+                &issue.status,
+                issue.level.as_deref().unwrap_or(""),
+                &issue.count,
+                &issue.user_count.to_string(),
+                &issue.first_seen.to_rfc3339(),
+                &issue.last_seen.to_rfc3339(),
+                issue.permalink.as_deref().unwrap_or(""),
+            ])?;
+        }
+        writer.flush()?;
+    } else {
+        #[derive(Serialize)]
+        struct ExportedIssue<'a> {
+            #[serde(flatten)]
+            issue: &'a Issue,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            events: Option<Vec<IssueEvent>>,
+        }
+
+        for issue in &issues {
+            let events = if with_events {
+                Some(api.list_issue_events(&issue.id, max_events)?)
+            } else {
+                None
+            };
+            serde_json::to_writer(&mut out, &ExportedIssue { issue, events })?;
+            writeln!(out)?;
+        }
+        out.flush()?;
+    }
+
+    if let Some(path) = output {
+        println!("Exported {} issues to {}", issues.len(), path);
+    }
+
+    Ok(())
+}
+
+fn execute_create(config: &Config, matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let message = matches.value_of("message").unwrap();
+
+    let mut event = Event {
+        sdk: Some(get_sdk_info()),
+        level: matches
+            .value_of("level")
+            .and_then(|l| l.parse().ok())
+            .unwrap_or(Level::Info),
+        logger: Some("annotation".into()),
+        release: matches
+            .value_of("release")
+            .map(str::to_owned)
+            .or_else(|| detect_release_name().ok())
+            .map(Cow::from),
+        environment: matches
+            .value_of("environment")
+            .map(|x| x.to_string().into()),
+        logentry: Some(LogEntry {
+            message: message.into(),
+            params: vec![],
+        }),
+        ..Event::default()
+    };
+
+    for tag in matches.values_of("tags").unwrap_or_default() {
+        let mut split = tag.splitn(2, ':');
+        let key = split.next().ok_or_else(|| err_msg("missing tag key"))?;
+        let value = split.next().ok_or_else(|| err_msg("missing tag value"))?;
+        event.tags.insert(key.into(), value.into());
+    }
+
+    event.fingerprint = match matches.values_of("fingerprint") {
+        Some(fingerprint) => fingerprint.map(|x| x.to_string().into()).collect(),
+        None => vec!["annotation".into(), message.to_string().into()],
+    }
+    .into();
+
+    let id = with_sentry_client(config.get_dsn()?, |c| c.capture_event(event, None));
+    println!("{}", id);
+
+    Ok(())
+}
+
 pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
     let config = Config::current();
+
+    // annotations are sent through the DSN
+    if let Some(sub_matches) = matches.subcommand_matches("create") {
+        return execute_create(&config, sub_matches);
+    }
+
+    // these only need an org
+    if let Some(sub_matches) = matches.subcommand_matches("show") {
+        return execute_show(&config.get_org(matches)?, sub_matches);
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("link") {
+        return execute_link(&config.get_org(matches)?, sub_matches);
+    }
+    if matches.subcommand_matches("tail").is_some() {
+        return execute_tail(&config, matches);
+    }
+
     let (org, project) = config.get_org_and_project(matches)?;
+
+    if let Some(sub_matches) = matches.subcommand_matches("list") {
+        return execute_list(&org, &project, matches, sub_matches);
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("export") {
+        return execute_export(&org, &project, matches, sub_matches);
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("stats") {
+        return execute_stats(&org, &project, matches, sub_matches);
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("compare") {
+        return execute_compare(&org, &project, matches, sub_matches);
+    }
+
     let filter = get_filter_from_matches(matches)?;
     let mut changes: IssueChanges = Default::default();
 
@@ -104,8 +1284,24 @@ pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
         }
     } else if matches.subcommand_matches("mute").is_some() {
         changes.new_status = Some("muted".into());
+    } else if let Some(sub_matches) = matches.subcommand_matches("ignore") {
+        changes.new_status = Some("ignored".into());
+        if let Some(until) = sub_matches.value_of("until") {
+            // Sentry snoozes for whole minutes, where 0 means forever
+            let secs = get_duration(until)?.num_seconds();
+            if secs < 60 {
+                bail!("Issues can only be ignored for one minute or longer.");
+            }
+            changes.snooze_duration = Some((secs + 59) / 60);
+        }
     } else if matches.subcommand_matches("unresolve").is_some() {
         changes.new_status = Some("unresolved".into());
+    } else if let Some(sub_matches) = matches.subcommand_matches("assign") {
+        changes.assigned_to = sub_matches.value_of("actor").map(str::to_owned);
+    } else if matches.subcommand_matches("subscribe").is_some() {
+        changes.is_subscribed = Some(true);
+    } else if matches.subcommand_matches("unsubscribe").is_some() {
+        changes.is_subscribed = Some(false);
     }
 
     execute_change(&org, &project, &filter, &changes)
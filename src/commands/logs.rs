@@ -0,0 +1,291 @@
+//! Implements a command for sending log files to Sentry.
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use clap::{App, AppSettings, Arg, ArgMatches};
+use failure::{Error, ResultExt};
+use sentry::internals::Uuid;
+use sentry::protocol::{Event, Level, LogEntry};
+use serde_json::{json, Map, Value};
+
+use crate::config::Config;
+use crate::utils::args::validate_int;
+use crate::utils::envelope::{send_logs, send_with_attachments};
+use crate::utils::event::get_sdk_info;
+use crate::utils::log_parser::{LogLevel, LogParser, LogRecord};
+
+/// The most log records sent in a single envelope.
+const LOGS_PER_ENVELOPE: usize = 100;
+
+fn validate_parser(v: String) -> Result<(), String> {
+    v.parse::<LogParser>()
+        .map(|_| ())
+        .map_err(|err| err.to_string())
+}
+
+fn validate_level(v: String) -> Result<(), String> {
+    v.parse::<LogLevel>()
+        .map(|_| ())
+        .map_err(|err| err.to_string())
+}
+
+pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.about("Send log files to Sentry.")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            App::new("send")
+                .about("Send the records of a log file.")
+                .long_about(
+                    "Send the records of a log file.{n}{n}\
+                     Every line of the file is parsed into a record with a level, a \
+                     message and attributes.  Records are sent as Sentry logs or, with \
+                     `--as events`, as one event each, so applications that cannot use \
+                     an SDK still show up in Sentry.  Lines the parser does not match \
+                     are skipped.",
+                )
+                .arg(
+                    Arg::with_name("file")
+                        .long("file")
+                        .short("f")
+                        .value_name("PATH")
+                        .required(true)
+                        .help("The path to the log file, or - to read from stdin."),
+                )
+                .arg(
+                    Arg::with_name("parser")
+                        .long("parser")
+                        .value_name("PARSER")
+                        .default_value("json")
+                        .validator(validate_parser)
+                        .help(
+                            "How lines are parsed: json, logfmt or regex:PATTERN.  The \
+                             named groups of the pattern become the fields of the \
+                             record.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("as")
+                        .long("as")
+                        .value_name("TYPE")
+                        .possible_values(&["logs", "events"])
+                        .default_value("logs")
+                        .help("Send the records as logs or as events."),
+                )
+                .arg(
+                    Arg::with_name("min_level")
+                        .long("min-level")
+                        .value_name("LEVEL")
+                        .validator(validate_level)
+                        .help(
+                            "Skip records below this level. \
+                             [defaults to info for logs and error for events]",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("rate_limit")
+                        .long("rate-limit")
+                        .value_name("RECORDS")
+                        .default_value("100")
+                        .validator(validate_int)
+                        .help("The most records sent per second, 0 for no limit."),
+                )
+                .arg(
+                    Arg::with_name("release")
+                        .long("release")
+                        .short("r")
+                        .value_name("RELEASE")
+                        .help("The release of the logging application."),
+                )
+                .arg(
+                    Arg::with_name("environment")
+                        .long("env")
+                        .short("E")
+                        .value_name("ENVIRONMENT")
+                        .help("The environment the application runs in."),
+                ),
+        )
+}
+
+/// Holds back records so no more than `rate` are sent per second.
+struct RateLimiter {
+    rate: u64,
+    started: Instant,
+    sent: u64,
+}
+
+impl RateLimiter {
+    fn new(rate: u64) -> RateLimiter {
+        RateLimiter {
+            rate,
+            started: Instant::now(),
+            sent: 0,
+        }
+    }
+
+    fn wait(&mut self, records: usize) {
+        if self.rate > 0 {
+            let due = self.started + Duration::from_secs_f64(self.sent as f64 / self.rate as f64);
+            let now = Instant::now();
+            if due > now {
+                thread::sleep(due - now);
+            }
+        }
+        self.sent += records as u64;
+    }
+}
+
+fn to_attribute(value: &Value) -> Value {
+    match value {
+        Value::Bool(value) => json!({ "value": value, "type": "boolean" }),
+        Value::Number(value) if value.is_f64() => json!({ "value": value, "type": "double" }),
+        Value::Number(value) => json!({ "value": value, "type": "integer" }),
+        Value::String(value) => json!({ "value": value, "type": "string" }),
+        other => json!({ "value": other.to_string(), "type": "string" }),
+    }
+}
+
+fn to_log_item(record: &LogRecord, trace_id: &str, defaults: &Map<String, Value>) -> Value {
+    let mut attributes = defaults.clone();
+    for (key, value) in &record.attributes {
+        attributes.insert(key.clone(), to_attribute(value));
+    }
+    let timestamp = record.timestamp.unwrap_or_else(Utc::now);
+    json!({
+        "timestamp": timestamp.timestamp_nanos() as f64 / 1e9,
+        "trace_id": trace_id,
+        "level": record.level.to_string(),
+        "body": record.message,
+        "attributes": attributes,
+    })
+}
+
+fn to_event(record: LogRecord, release: Option<&str>, environment: Option<&str>) -> Event<'static> {
+    let mut event = Event {
+        sdk: Some(get_sdk_info()),
+        level: match record.level {
+            LogLevel::Trace | LogLevel::Debug => Level::Debug,
+            LogLevel::Info => Level::Info,
+            LogLevel::Warn => Level::Warning,
+            LogLevel::Error => Level::Error,
+            LogLevel::Fatal => Level::Fatal,
+        },
+        release: release.map(|release| Cow::Owned(release.to_string())),
+        environment: environment.map(|environment| Cow::Owned(environment.to_string())),
+        platform: "other".into(),
+        logentry: Some(LogEntry {
+            message: record.message,
+            params: vec![],
+        }),
+        extra: record.attributes.into_iter().collect(),
+        ..Event::default()
+    };
+    if let Some(timestamp) = record.timestamp {
+        event.timestamp = timestamp;
+    }
+    event
+}
+
+fn execute_send(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let config = Config::current();
+    let dsn = config.get_dsn()?;
+    let parser: LogParser = matches.value_of("parser").unwrap().parse()?;
+    let as_events = matches.value_of("as") == Some("events");
+    let min_level = match matches.value_of("min_level") {
+        Some(level) => level.parse()?,
+        None if as_events => LogLevel::Error,
+        None => LogLevel::Info,
+    };
+    let release = matches.value_of("release");
+    let environment = matches
+        .value_of("environment")
+        .map(str::to_owned)
+        .or_else(|| config.get_environment());
+    let mut limiter = RateLimiter::new(matches.value_of("rate_limit").unwrap().parse()?);
+
+    let path = matches.value_of("file").unwrap();
+    let reader: Box<dyn BufRead> = if path == "-" {
+        Box::new(BufReader::new(io::stdin()))
+    } else {
+        Box::new(BufReader::new(
+            File::open(path).with_context(|_| format!("Could not open {}", path))?,
+        ))
+    };
+
+    let trace_id = Uuid::new_v4().to_simple().to_string();
+    let mut defaults = Map::new();
+    defaults.insert("sentry.sdk.name".into(), to_attribute(&"sentry-cli".into()));
+    if let Some(release) = release {
+        defaults.insert("sentry.release".into(), to_attribute(&release.into()));
+    }
+    if let Some(ref environment) = environment {
+        defaults.insert(
+            "sentry.environment".into(),
+            to_attribute(&environment.as_str().into()),
+        );
+    }
+
+    let mut batch = vec![];
+    let mut sent = 0;
+    let mut skipped = 0;
+    for line in reader.lines() {
+        let line = line.with_context(|_| format!("Could not read {}", path))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = match parser.parse(&line) {
+            Some(record) => record,
+            None => {
+                skipped += 1;
+                continue;
+            }
+        };
+        if record.level < min_level {
+            continue;
+        }
+
+        if as_events {
+            limiter.wait(1);
+            send_with_attachments(
+                &dsn,
+                &to_event(record, release, environment.as_deref()),
+                &[],
+            )?;
+            sent += 1;
+        } else {
+            batch.push(to_log_item(&record, &trace_id, &defaults));
+            if batch.len() == LOGS_PER_ENVELOPE {
+                limiter.wait(batch.len());
+                send_logs(&dsn, &batch)?;
+                sent += batch.len();
+                batch.clear();
+            }
+        }
+    }
+    if !batch.is_empty() {
+        limiter.wait(batch.len());
+        send_logs(&dsn, &batch)?;
+        sent += batch.len();
+    }
+
+    println!(
+        "Sent {} {}",
+        sent,
+        if as_events { "events" } else { "log records" }
+    );
+    if skipped > 0 {
+        println!("Skipped {} lines the parser did not match", skipped);
+    }
+
+    Ok(())
+}
+
+pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    if let Some(sub_matches) = matches.subcommand_matches("send") {
+        return execute_send(sub_matches);
+    }
+    unreachable!();
+}
@@ -11,7 +11,14 @@ use log::{debug, info};
 use crate::api::Api;
 use crate::config::{prepare_environment, Auth, Config};
 use crate::constants::{ARCH, PLATFORM, VERSION};
+use crate::utils::annotations::{self, CiOutput};
+use crate::utils::bazel::{is_persistent_worker, run_worker};
+use crate::utils::cancel;
+use crate::utils::crash_report;
+use crate::utils::fips;
+use crate::utils::machine;
 use crate::utils::system::{print_error, QuietExit};
+use crate::utils::tls;
 use crate::utils::update::run_sentrycli_update_nagger;
 
 const ABOUT: &str = "
@@ -25,22 +32,43 @@ macro_rules! each_subcommand {
     ($mac:ident) => {
         $mac!(upload_dif);
         $mac!(upload_dsym);
+        $mac!(upload_minidump);
         $mac!(upload_proguard);
+        $mac!(upload_profile);
         $mac!(releases);
         $mac!(issues);
+        $mac!(alerts);
         $mac!(repos);
+        $mac!(organizations);
         $mac!(projects);
+        $mac!(teams);
+        $mac!(environments);
+        $mac!(ci);
+        $mac!(hooks);
         $mac!(monitors);
+        $mac!(uptime);
         #[cfg(not(feature = "managed"))]
         $mac!(update);
         #[cfg(not(feature = "managed"))]
         $mac!(uninstall);
         $mac!(info);
+        $mac!(info_plist);
+        $mac!(bugreport);
         $mac!(login);
+        $mac!(logs);
+        $mac!(send_attachment);
         $mac!(send_event);
+        $mac!(send_metric);
+        $mac!(send_replay);
         $mac!(react_native);
         $mac!(difutil);
+        $mac!(debug_files);
         $mac!(bash_hook);
+        $mac!(powershell_hook);
+        $mac!(systemd_hook);
+        $mac!(run);
+        $mac!(exec);
+        $mac!(android);
 
         // these here exist for legacy reasons only.  They were moved
         // to subcommands of the react-native command.  Note that
@@ -53,27 +81,59 @@ macro_rules! each_subcommand {
 
 // commands we want to run the update nagger on
 const UPDATE_NAGGER_CMDS: &[&str] = &[
-    "releases", "issues", "repos", "projects", "monitors", "info", "login", "difutil",
+    "releases",
+    "issues",
+    "alerts",
+    "repos",
+    "organizations",
+    "projects",
+    "teams",
+    "environments",
+    "monitors",
+    "info",
+    "login",
+    "difutil",
 ];
 
 // it would be great if this could be a macro expansion as well
 // but rust bug #37663 breaks location information then.
+pub mod alerts;
+pub mod android;
+pub mod android_inject_debug_meta;
 pub mod bash_hook;
+pub mod bugreport;
+pub mod ci;
+pub mod environments;
+pub mod exec;
+pub mod hooks;
 pub mod info;
+pub mod info_plist;
 pub mod issues;
 pub mod login;
+pub mod logs;
 pub mod monitors;
+pub mod organizations;
+pub mod powershell_hook;
 pub mod projects;
 pub mod releases;
 pub mod repos;
+pub mod run;
+pub mod send_attachment;
 pub mod send_event;
+pub mod send_metric;
+pub mod send_replay;
+pub mod systemd_hook;
+pub mod teams;
 #[cfg(not(feature = "managed"))]
 pub mod uninstall;
 #[cfg(not(feature = "managed"))]
 pub mod update;
 pub mod upload_dif;
 pub mod upload_dsym;
+pub mod upload_minidump;
+pub mod upload_profile;
 pub mod upload_proguard;
+pub mod uptime;
 
 pub mod react_native;
 pub mod react_native_appcenter;
@@ -82,9 +142,13 @@ pub mod react_native_gradle;
 #[cfg(target_os = "macos")]
 pub mod react_native_xcode;
 
+pub mod debug_files;
+pub mod debug_files_watch;
 pub mod difutil;
+pub mod difutil_bundle_jvm_sources;
 pub mod difutil_bundle_sources;
 pub mod difutil_check;
+pub mod difutil_check_ipa;
 pub mod difutil_find;
 pub mod difutil_id;
 
@@ -123,6 +187,37 @@ fn configure_args(config: &mut Config, matches: &ArgMatches<'_>) -> Result<(), E
         config.set_auth(Auth::Token(auth_token.to_owned()));
     }
 
+    annotations::set_output(if let Some(format) = matches.value_of("ci_output") {
+        format.parse()?
+    } else if matches.is_present("ci_annotations") {
+        CiOutput::GitHub
+    } else {
+        config.get_ci_output()?
+    });
+
+    if matches.is_present("fips") || config.get_fips_mode() {
+        fips::enable();
+    }
+
+    let tls_backend = match matches.value_of("tls_backend") {
+        Some(backend) => Some(backend.parse()?),
+        None => config.get_tls_backend()?,
+    };
+    if let Some(backend) = tls_backend {
+        tls::select_backend(backend)?;
+    }
+
+    if let Some(dir) = matches.value_of("spool_dir") {
+        config.set_spool_dir(dir);
+    }
+    if matches.is_present("spool") {
+        config.set_spool(true);
+    }
+
+    if matches.is_present("machine_readable") && !machine::is_enabled() {
+        machine::enable()?;
+    }
+
     if let Some(level_str) = matches.value_of("log_level") {
         match level_str.parse() {
             Ok(level) => {
@@ -191,6 +286,7 @@ impl<'a> fmt::Display for DebugArgs<'a> {
 /// Given an argument vector and a `Config` this executes the
 /// command line and returns the result.
 pub fn execute(args: &[String]) -> Result<(), Error> {
+    crash_report::set_args(args);
     let mut config = Config::from_cli_config()?;
 
     // special case for the xcode integration for react native.  For more
@@ -232,6 +328,67 @@ pub fn execute(args: &[String]) -> Result<(), Error> {
                 .case_insensitive(true)
                 .global(true)
                 .help("Set the log output verbosity."),
+        )
+        .arg(
+            Arg::with_name("ci_annotations")
+                .long("ci-annotations")
+                .help(
+                    "Report problems as GitHub Actions annotations and write a job summary.{n}\
+                     [enabled by default when GITHUB_ACTIONS is set]",
+                ),
+        )
+        .arg(
+            Arg::with_name("ci_output")
+                .long("ci-output")
+                .value_name("FORMAT")
+                .possible_values(&["none", "github", "teamcity", "jenkins", "azure"])
+                .conflicts_with("ci_annotations")
+                .help(
+                    "Format problems and upload steps for a CI server.{n}\
+                     TeamCity gets service messages, Jenkins `[sentry-cli]` markers and \
+                     Azure Pipelines logging commands.  \
+                     [defaults to github, teamcity or azure when detected]",
+                ),
+        )
+        .arg(
+            Arg::with_name("tls_backend")
+                .long("tls-backend")
+                .value_name("BACKEND")
+                .possible_values(&["native", "rustls"])
+                .help(
+                    "The TLS library to connect with.  native uses the TLS library \
+                     and trust store of the system, rustls one without system \
+                     dependencies if sentry-cli was built with it.{n}\
+                     [defaults to the http.tls_backend setting or the backend \
+                     sentry-cli was built with]",
+                ),
+        )
+        .arg(Arg::with_name("fips").long("fips").help(
+            "Only use FIPS validated cryptography.  Requests are refused if the \
+             TLS library or hashing are not FIPS validated, see `sentry-cli info`.",
+        ))
+        .arg(Arg::with_name("spool").long("spool").help(
+            "Write events, check-ins and other envelopes into the spool folder \
+             instead of sending them, for a local Relay or `bash-hook --flush` \
+             to deliver.",
+        ))
+        .arg(
+            Arg::with_name("spool_dir")
+                .long("spool-dir")
+                .value_name("PATH")
+                .help(
+                    "The folder in which envelopes are spooled.{n}\
+                     [defaults to SENTRY_SPOOL_DIR or a folder in the cache]",
+                ),
+        )
+        .arg(
+            Arg::with_name("machine_readable")
+                .long("machine-readable")
+                .help(
+                    "Print progress, warnings, errors and the result as newline \
+                     delimited JSON events on stdout.{n}\
+                     The regular output goes to stderr instead.",
+                ),
         );
 
     app = add_commands(app);
@@ -254,15 +411,50 @@ pub fn execute(args: &[String]) -> Result<(), Error> {
 
     info!(
         "sentry-cli was invoked with the following command line: {}",
-        DebugArgs(args.iter().map(String::as_str).collect())
+        DebugArgs(
+            crash_report::redact_args(args)
+                .iter()
+                .map(String::as_str)
+                .collect()
+        )
     );
 
     run_command(&matches)
 }
 
+/// Runs the command line of a persistent worker request and returns the
+/// exit code instead of exiting.
+fn execute_request(args: &[String]) -> i32 {
+    let result = execute(args);
+    // the api is bound to the config of this request, the next one may
+    // use another server or auth token
+    Api::dispose_pool();
+    match result {
+        Ok(()) => 0,
+        Err(err) => {
+            if let Some(&QuietExit(code)) = err.downcast_ref() {
+                code
+            } else if let Some(clap_err) = err.downcast_ref::<clap::Error>() {
+                eprintln!("{}", clap_err.message);
+                match clap_err.kind {
+                    clap::ErrorKind::HelpDisplayed | clap::ErrorKind::VersionDisplayed => 0,
+                    _ => 1,
+                }
+            } else {
+                print_error(&err);
+                1
+            }
+        }
+    }
+}
+
 fn run() -> Result<(), Error> {
     prepare_environment();
-    execute(&env::args().collect::<Vec<String>>())
+    let args: Vec<String> = env::args().collect();
+    if is_persistent_worker(&args) {
+        return run_worker(&args, execute_request);
+    }
+    execute(&args)
 }
 
 fn setup() {
@@ -295,6 +487,11 @@ pub fn main() {
 
     let status_code = match result {
         Ok(()) => 0,
+        // the error of a cancelled command is a consequence of the interrupt
+        Err(_) if cancel::is_cancelled() => {
+            cancel::finish();
+            cancel::exit_code()
+        }
         Err(err) => {
             let code = if let Some(&QuietExit(code)) = err.downcast_ref() {
                 code
@@ -320,5 +517,6 @@ pub fn main() {
     // a chance to collect.  Not doing so has shown to cause hung threads
     // on windows.
     Api::dispose_pool();
+    machine::finish(status_code);
     process::exit(status_code);
 }
@@ -1,17 +1,29 @@
 //! Implements a command for managing projects.
+use std::env;
 use std::process;
 use std::sync::Arc;
 use std::time::Instant;
 
 use clap::{App, AppSettings, Arg, ArgMatches};
-use failure::{Error, ResultExt};
+use failure::{bail, err_msg, Error, ResultExt};
+use log::warn;
 use uuid::Uuid;
 
-use crate::api::{Api, CreateMonitorCheckIn, MonitorStatus, UpdateMonitorCheckIn};
+use crate::api::{
+    Api, CreateMonitorCheckIn, MonitorConfig, MonitorStatus, NewMonitor, UpdateMonitorCheckIn,
+};
+use crate::commands::run;
 use crate::config::Config;
-use crate::utils::args::ArgExt;
+use crate::utils::args::{
+    get_exit_code, validate_exit_code, validate_int, validate_monitor_slug, ArgExt,
+};
 use crate::utils::formatting::Table;
+use crate::utils::machine;
+use crate::utils::resource_usage::UsageTracker;
 use crate::utils::system::QuietExit;
+use crate::utils::windows_task::{
+    command_line, local_timezone, report_to_event_log, task_schedule,
+};
 
 struct MonitorContext {
     pub api: Arc<Api>,
@@ -24,6 +36,59 @@ impl<'a> MonitorContext {
     }
 }
 
+fn monitor_config_args<'a, 'b>(require_schedule: bool) -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("schedule")
+            .long("schedule")
+            .value_name("CRONTAB")
+            .required(require_schedule)
+            .help("The crontab schedule of the job, e.g. `0 * * * *`."),
+        Arg::with_name("timezone")
+            .long("timezone")
+            .value_name("TZ")
+            .requires("schedule")
+            .help("The timezone of the schedule, e.g. `Europe/Vienna`."),
+        Arg::with_name("checkin_margin")
+            .long("checkin-margin")
+            .value_name("MINUTES")
+            .requires("schedule")
+            .validator(validate_int)
+            .help("The minutes a check-in may be late before it is missed."),
+        Arg::with_name("max_runtime")
+            .long("max-runtime")
+            .value_name("MINUTES")
+            .requires("schedule")
+            .validator(validate_int)
+            .help("The minutes a job may run before it is marked as failed."),
+    ]
+}
+
+fn success_exit_codes_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("success_exit_codes")
+        .long("success-exit-code")
+        .value_name("CODE")
+        .multiple(true)
+        .number_of_values(1)
+        .validator(validate_exit_code)
+        .help(
+            "Report the run as successful if the command exits with this code, \
+             e.g. 3010 for installers that ask for a reboot.  sentry-cli then \
+             exits with 0.",
+        )
+}
+
+fn get_monitor_config(matches: &ArgMatches<'_>) -> Option<MonitorConfig> {
+    Some(MonitorConfig {
+        schedule_type: "crontab".into(),
+        schedule: matches.value_of("schedule")?.into(),
+        checkin_margin: matches
+            .value_of("checkin_margin")
+            .map(|v| v.parse().unwrap()),
+        max_runtime: matches.value_of("max_runtime").map(|v| v.parse().unwrap()),
+        timezone: matches.value_of("timezone").map(str::to_owned),
+    })
+}
+
 pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
     app.about("Manage monitors on Sentry.")
         .setting(AppSettings::SubcommandRequiredElseHelp)
@@ -31,19 +96,100 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
         .org_arg()
         .subcommand(App::new("list").about("List all monitors for an organization."))
         .subcommand(
-            App::new("run")
-                .about("Wraps a command")
+            App::new("create")
+                .about("Create a new cron monitor.")
                 .arg(
-                    Arg::with_name("monitor")
-                        .help("The monitor ID")
+                    Arg::with_name("slug")
+                        .value_name("SLUG")
                         .required(true)
-                        .index(1),
+                        .index(1)
+                        .validator(validate_monitor_slug)
+                        .help("The slug of the new monitor."),
                 )
                 .arg(
-                    Arg::with_name("allow_failure")
-                        .short("f")
-                        .long("allow-failure")
-                        .help("Run provided command even when Sentry reports an error."),
+                    Arg::with_name("name")
+                        .long("name")
+                        .value_name("NAME")
+                        .help("The name of the monitor. [defaults to the slug]"),
+                )
+                .args(&monitor_config_args(true))
+                .idempotent_arg(),
+        )
+        .subcommand(
+            run::add_sandbox_args(
+                App::new("run")
+                    .about("Wraps a command")
+                    .long_about(
+                        "Wraps a command and reports its run as check-ins of a monitor.{n}{n}\
+                         The final check-in carries the duration of the command and \
+                         the peak memory, CPU time and I/O it used.{n}{n}\
+                         With --schedule the monitor given by its slug is created or \
+                         updated with the first check-in, so it does not have to be set \
+                         up beforehand.{n}{n}\
+                         With --clean-env the command does not see the environment of \
+                         sentry-cli, so SENTRY_AUTH_TOKEN does not leak into the job.",
+                    )
+                    .arg(
+                        Arg::with_name("monitor")
+                            .help("The monitor ID or slug")
+                            .required(true)
+                            .index(1),
+                    )
+                    .args(&monitor_config_args(false))
+                    .arg(
+                        Arg::with_name("allow_failure")
+                            .short("f")
+                            .long("allow-failure")
+                            .help("Run provided command even when Sentry reports an error."),
+                    )
+                    .arg(success_exit_codes_arg())
+                    .arg(Arg::with_name("event_log").long("event-log").help(
+                        "Also write failed runs and check-ins to the Application \
+                         log of the Windows Event Log. [Windows only]",
+                    )),
+            )
+            .arg(
+                Arg::with_name("args")
+                    .required(true)
+                    .multiple(true)
+                    .last(true),
+            ),
+        )
+        .subcommand(
+            App::new("install-task")
+                .about("Install a Windows scheduled task that runs a command as a monitor.")
+                .long_about(
+                    "Install a Windows scheduled task that runs a command as a monitor.{n}{n}\
+                     The task runs `sentry-cli monitors run` with the monitor, its \
+                     schedule and --event-log, so failures also show up in the \
+                     Windows Event Log.  The crontab schedule is converted to a \
+                     Task Scheduler schedule, which supports schedules that run \
+                     every few minutes or hours, daily, weekly or monthly on a \
+                     day.  The task runs in the local time of the machine, which \
+                     the monitor is given as its timezone.{n}{n}\
+                     Example: sentry-cli monitors install-task nightly-backup \
+                     --schedule \"30 2 * * *\" -- C:\\backup\\run.exe",
+                )
+                .arg(
+                    Arg::with_name("slug")
+                        .value_name("SLUG")
+                        .required(true)
+                        .index(1)
+                        .validator(validate_monitor_slug)
+                        .help("The slug of the monitor."),
+                )
+                .args(&monitor_config_args(true))
+                .arg(success_exit_codes_arg())
+                .arg(
+                    Arg::with_name("task_name")
+                        .long("task-name")
+                        .value_name("NAME")
+                        .help("The name of the task. [defaults to `Sentry monitor SLUG`]"),
+                )
+                .arg(
+                    Arg::with_name("dry_run")
+                        .long("dry-run")
+                        .help("Print the schtasks command instead of running it."),
                 )
                 .arg(
                     Arg::with_name("args")
@@ -65,9 +211,15 @@ pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
     if let Some(sub_matches) = matches.subcommand_matches("list") {
         return execute_list(&ctx, sub_matches);
     }
+    if let Some(sub_matches) = matches.subcommand_matches("create") {
+        return execute_create(&ctx, sub_matches);
+    }
     if let Some(sub_matches) = matches.subcommand_matches("run") {
         return execute_run(&ctx, sub_matches);
     }
+    if let Some(sub_matches) = matches.subcommand_matches("install-task") {
+        return execute_install_task(&ctx, sub_matches);
+    }
     unreachable!();
 }
 
@@ -91,48 +243,132 @@ fn execute_list<'a>(ctx: &MonitorContext, _matches: &ArgMatches<'a>) -> Result<(
     Ok(())
 }
 
+fn execute_create<'a>(ctx: &MonitorContext, matches: &ArgMatches<'a>) -> Result<(), Error> {
+    let slug = matches.value_of("slug").unwrap();
+    let monitor = NewMonitor {
+        name: matches.value_of("name").unwrap_or(slug).to_owned(),
+        slug: slug.to_owned(),
+        ty: "cron_job".into(),
+        config: get_monitor_config(matches).unwrap(),
+    };
+
+    if matches.is_present("idempotent") {
+        if let Some(existing) = ctx.api.get_monitor(ctx.get_org()?, slug)? {
+            let same_config = existing.config.as_ref().is_some_and(|config| {
+                config.schedule_type == monitor.config.schedule_type
+                    && config.schedule == monitor.config.schedule
+                    && (monitor.config.checkin_margin.is_none()
+                        || config.checkin_margin == monitor.config.checkin_margin)
+                    && (monitor.config.max_runtime.is_none()
+                        || config.max_runtime == monitor.config.max_runtime)
+                    && (monitor.config.timezone.is_none()
+                        || config.timezone == monitor.config.timezone)
+            });
+            if existing.name != monitor.name || !same_config {
+                bail!("Monitor {} already exists with different settings", slug);
+            }
+            println!(
+                "Monitor {} ({}) already exists, nothing changed.",
+                slug, existing.id
+            );
+            machine::set_result("changed", false);
+            return Ok(());
+        }
+        machine::set_result("changed", true);
+    }
+
+    let created = ctx.api.create_monitor(ctx.get_org()?, &monitor)?;
+    println!("Created monitor {} ({})", slug, created.id);
+    Ok(())
+}
+
 fn execute_run<'a>(ctx: &MonitorContext, matches: &ArgMatches<'a>) -> Result<(), Error> {
-    let monitor = matches
-        .value_of("monitor")
-        .unwrap()
-        .parse::<Uuid>()
-        .context("invalid monitor ID")?;
+    let monitor = matches.value_of("monitor").unwrap();
+    let monitor_id = monitor.parse::<Uuid>().ok();
+    let monitor_config = get_monitor_config(matches);
+    match monitor_id {
+        Some(_) if monitor_config.is_some() => {
+            bail!("--schedule requires the slug of the monitor instead of its ID")
+        }
+        Some(_) => {}
+        None => validate_monitor_slug(monitor.to_owned())
+            .map_err(err_msg)
+            .context("invalid monitor ID or slug")?,
+    }
     let allow_failure = matches.is_present("allow_failure");
+    let event_log = matches.is_present("event_log");
+    if event_log && !cfg!(windows) {
+        bail!("--event-log is only supported on Windows");
+    }
+    let success_codes = matches
+        .values_of("success_exit_codes")
+        .unwrap_or_default()
+        .map(get_exit_code)
+        .collect::<Result<Vec<_>, _>>()?;
     let args: Vec<_> = matches.values_of("args").unwrap().collect();
 
-    let monitor_checkin = ctx.api.create_monitor_checkin(
-        &monitor,
-        &CreateMonitorCheckIn {
-            status: MonitorStatus::InProgress,
-        },
-    );
+    let checkin = CreateMonitorCheckIn {
+        status: MonitorStatus::InProgress,
+        monitor_config,
+    };
+    let monitor_checkin = match monitor_id {
+        Some(ref id) => ctx.api.create_monitor_checkin(id, &checkin),
+        None => ctx
+            .api
+            .create_monitor_checkin_by_slug(ctx.get_org()?, monitor, &checkin),
+    };
 
     let started = Instant::now();
-    let mut p = process::Command::new(args[0]);
-    p.args(&args[1..]);
-    let exit_status = p.status()?;
+    let mut child = run::Sandbox::from_matches(matches)
+        .command(&args)
+        .spawn()
+        .with_context(|_| format!("Could not run {}", args[0]))?;
+    let (exit_status, usage) = UsageTracker::new(&child).wait(&mut child)?;
+    let success = exit_status.success()
+        || exit_status
+            .code()
+            .is_some_and(|code| success_codes.contains(&code));
+    if event_log && !success {
+        log_event(&format!(
+            "Monitor {}: command {} {}",
+            monitor,
+            args[0],
+            run::describe_exit(exit_status)
+        ));
+    }
 
     match monitor_checkin {
         Ok(checkin) => {
-            ctx.api
-                .update_monitor_checkin(
-                    &monitor,
+            let update = UpdateMonitorCheckIn {
+                status: Some(if success {
+                    MonitorStatus::Ok
+                } else {
+                    MonitorStatus::Error
+                }),
+                duration: Some({
+                    let elapsed = started.elapsed();
+                    elapsed.as_secs() * 1000 + u64::from(elapsed.subsec_millis())
+                }),
+                resource_usage: usage,
+            };
+            match monitor_id {
+                Some(ref id) => ctx.api.update_monitor_checkin(id, &checkin.id, &update),
+                None => ctx.api.update_monitor_checkin_by_slug(
+                    ctx.get_org()?,
+                    monitor,
                     &checkin.id,
-                    &UpdateMonitorCheckIn {
-                        status: Some(if exit_status.success() {
-                            MonitorStatus::Ok
-                        } else {
-                            MonitorStatus::Error
-                        }),
-                        duration: Some({
-                            let elapsed = started.elapsed();
-                            elapsed.as_secs() * 1000 + u64::from(elapsed.subsec_millis())
-                        }),
-                    },
-                )
-                .ok();
+                    &update,
+                ),
+            }
+            .ok();
         }
         Err(e) => {
+            if event_log {
+                log_event(&format!(
+                    "Monitor {}: could not send check-in: {}",
+                    monitor, e
+                ));
+            }
             if allow_failure {
                 eprintln!("{}", e);
             } else {
@@ -141,7 +377,7 @@ fn execute_run<'a>(ctx: &MonitorContext, matches: &ArgMatches<'a>) -> Result<(),
         }
     }
 
-    if !exit_status.success() {
+    if !success {
         if let Some(code) = exit_status.code() {
             Err(QuietExit(code).into())
         } else {
@@ -151,3 +387,100 @@ fn execute_run<'a>(ctx: &MonitorContext, matches: &ArgMatches<'a>) -> Result<(),
         Ok(())
     }
 }
+
+/// Writes a failure to the Windows Event Log, which must not keep the
+/// check-in from being sent.
+fn log_event(message: &str) {
+    if let Err(err) = report_to_event_log(message) {
+        warn!("Could not write to the Windows Event Log: {}", err);
+    }
+}
+
+fn execute_install_task<'a>(ctx: &MonitorContext, matches: &ArgMatches<'a>) -> Result<(), Error> {
+    let slug = matches.value_of("slug").unwrap();
+    let schedule = matches.value_of("schedule").unwrap();
+    let task_name = matches
+        .value_of("task_name")
+        .map(str::to_owned)
+        .unwrap_or_else(|| format!("Sentry monitor {}", slug));
+
+    let mut run_args = vec![
+        env::current_exe()?.display().to_string(),
+        "monitors".into(),
+        "--org".into(),
+        ctx.get_org()?.into(),
+        "run".into(),
+        slug.into(),
+        "--schedule".into(),
+        schedule.into(),
+    ];
+    // the task starts in local time, which the monitor has to use as well
+    match (matches.value_of("timezone"), local_timezone()) {
+        (Some(timezone), Some(local)) if timezone != local => bail!(
+            "Scheduled tasks run in the local time of this machine ({}), which \
+             differs from the timezone {}. Leave out --timezone to use the \
+             local timezone.",
+            local,
+            timezone
+        ),
+        (Some(_), None) => bail!(
+            "Could not determine the timezone of this machine. Leave out \
+             --timezone, scheduled tasks run in local time."
+        ),
+        (_, Some(local)) => {
+            run_args.push("--timezone".into());
+            run_args.push(local);
+        }
+        (None, None) => warn!(
+            "Could not determine the timezone of this machine, the monitor \
+             assumes that the schedule is in UTC."
+        ),
+    }
+    for &(name, flag) in &[
+        ("checkin_margin", "--checkin-margin"),
+        ("max_runtime", "--max-runtime"),
+    ] {
+        if let Some(value) = matches.value_of(name) {
+            run_args.push(flag.into());
+            run_args.push(value.into());
+        }
+    }
+    for code in matches.values_of("success_exit_codes").unwrap_or_default() {
+        run_args.push("--success-exit-code".into());
+        run_args.push(code.into());
+    }
+    run_args.push("--event-log".into());
+    run_args.push("--".into());
+    run_args.extend(matches.values_of("args").unwrap().map(str::to_owned));
+
+    let mut schtasks_args = vec![
+        "/Create".to_string(),
+        "/F".into(),
+        "/TN".into(),
+        task_name.clone(),
+        "/TR".into(),
+        command_line(&run_args),
+    ];
+    schtasks_args.extend(task_schedule(schedule)?);
+
+    if matches.is_present("dry_run") {
+        println!("schtasks {}", command_line(&schtasks_args));
+        return Ok(());
+    }
+    if !cfg!(windows) {
+        bail!(
+            "Scheduled tasks can only be installed on Windows. Use --dry-run to \
+             print the schtasks command instead."
+        );
+    }
+
+    let status = process::Command::new("schtasks")
+        .args(&schtasks_args)
+        .status()
+        .context("Could not run schtasks")?;
+    if !status.success() {
+        bail!("schtasks {}", run::describe_exit(status));
+    }
+    println!("Installed scheduled task {}", task_name);
+    Ok(())
+}
@@ -0,0 +1,196 @@
+//! Implements a command for managing organizations.
+use std::io;
+
+use clap::{App, AppSettings, Arg, ArgMatches};
+use failure::Error;
+
+use crate::api::{Api, OrganizationMember};
+use crate::config::Config;
+use crate::utils::args::ArgExt;
+use crate::utils::formatting::Table;
+use crate::utils::ui::confirm;
+
+const ROLES: &[&str] = &["member", "admin", "manager", "owner", "billing"];
+
+pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.about("Manage organizations on Sentry.")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .org_arg()
+        .subcommand(
+            App::new("members")
+                .about("Manage the members of an organization.")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    App::new("list")
+                        .about("List the members of an organization.")
+                        .arg(
+                            Arg::with_name("role")
+                                .long("role")
+                                .value_name("ROLE")
+                                .possible_values(ROLES)
+                                .help("Only list members with the given role."),
+                        )
+                        .arg(
+                            Arg::with_name("json")
+                                .long("json")
+                                .help("Print the members as JSON."),
+                        ),
+                )
+                .subcommand(
+                    App::new("invite")
+                        .about("Invite new members to an organization.")
+                        .arg(
+                            Arg::with_name("emails")
+                                .value_name("EMAIL")
+                                .required(true)
+                                .multiple(true)
+                                .index(1)
+                                .help("The email address of the person to invite."),
+                        )
+                        .arg(
+                            Arg::with_name("role")
+                                .long("role")
+                                .value_name("ROLE")
+                                .possible_values(ROLES)
+                                .help("The role of the new members. [defaults to 'member']"),
+                        )
+                        .arg(
+                            Arg::with_name("teams")
+                                .long("team")
+                                .short("t")
+                                .value_name("TEAM")
+                                .multiple(true)
+                                .number_of_values(1)
+                                .help("Add the new members to a team."),
+                        ),
+                )
+                .subcommand(
+                    App::new("remove")
+                        .about("Remove members from an organization.")
+                        .arg(
+                            Arg::with_name("members")
+                                .value_name("MEMBER")
+                                .required(true)
+                                .multiple(true)
+                                .index(1)
+                                .help("The email address or the member ID of a member."),
+                        )
+                        .arg(
+                            Arg::with_name("confirm")
+                                .long("confirm")
+                                .help("Skip the confirmation prompt."),
+                        ),
+                ),
+        )
+}
+
+fn find_member(api: &Api, org: &str, member: &str) -> Result<Option<OrganizationMember>, Error> {
+    let query = if member.contains('@') {
+        format!("email:{}", member)
+    } else {
+        format!("id:{}", member)
+    };
+    Ok(api
+        .list_organization_members(org, Some(&query))?
+        .into_iter()
+        .find(|m| m.id == member || m.email.eq_ignore_ascii_case(member)))
+}
+
+fn execute_list(org: &str, matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let mut members = Api::current().list_organization_members(org, None)?;
+    if let Some(role) = matches.value_of("role") {
+        members.retain(|m| m.role == role);
+    }
+
+    if matches.is_present("json") {
+        serde_json::to_writer_pretty(&mut io::stdout(), &members)?;
+        println!();
+        return Ok(());
+    }
+
+    let mut table = Table::new();
+    table
+        .title_row()
+        .add("ID")
+        .add("Email")
+        .add("Name")
+        .add("Role")
+        .add("Pending");
+
+    for member in &members {
+        table
+            .add_row()
+            .add(&member.id)
+            .add(&member.email)
+            .add(member.name.as_deref().unwrap_or("-"))
+            .add(&member.role)
+            .add(if member.pending { "yes" } else { "no" });
+    }
+
+    table.print();
+
+    Ok(())
+}
+
+fn execute_invite(org: &str, matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let api = Api::current();
+    let role = matches.value_of("role").unwrap_or("member");
+    let teams: Vec<_> = matches.values_of("teams").unwrap_or_default().collect();
+
+    for email in matches.values_of("emails").unwrap() {
+        let member = api.invite_organization_member(org, email, role, &teams)?;
+        println!(
+            "Invited {} as {} ({})",
+            member.email, member.role, member.id
+        );
+    }
+
+    Ok(())
+}
+
+fn execute_remove(org: &str, matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let api = Api::current();
+
+    for member in matches.values_of("members").unwrap() {
+        let found = match find_member(&api, org, member)? {
+            Some(found) => found,
+            None => {
+                println!("Did nothing. {} is not a member of {}.", member, org);
+                continue;
+            }
+        };
+
+        if !confirm(
+            matches.is_present("confirm"),
+            &format!("Do you really want to remove {}?", found.email),
+        )? {
+            println!("Skipped {}.", found.email);
+            continue;
+        }
+
+        if api.remove_organization_member(org, &found.id)? {
+            println!("Removed {} from {}", found.email, org);
+        } else {
+            println!("Did nothing. {} is not a member of {}.", member, org);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let config = Config::current();
+    let org = config.get_org(matches)?;
+
+    let matches = matches.subcommand_matches("members").unwrap();
+    if let Some(sub_matches) = matches.subcommand_matches("list") {
+        return execute_list(&org, sub_matches);
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("invite") {
+        return execute_invite(&org, sub_matches);
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("remove") {
+        return execute_remove(&org, sub_matches);
+    }
+    unreachable!();
+}
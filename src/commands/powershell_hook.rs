@@ -0,0 +1,112 @@
+//! Implements a command for instrumenting PowerShell and cmd.exe scripts.
+use std::env;
+
+use clap::{App, Arg, ArgMatches};
+use failure::Error;
+
+use crate::utils::script_hook::send_event;
+
+const POWERSHELL_SCRIPT: &str = include_str!("../powershellsupport.ps1");
+const CMD_SCRIPT: &str = include_str!("../cmdsupport.cmd");
+
+pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.about("Prints out a PowerShell script that does error handling.")
+        .long_about(
+            "Prints out a PowerShell script that does error handling.{n}{n}\
+             Paste the output at the top of a script to report all terminating \
+             errors through a trap, or dot-source it and wrap the script in \
+             `Invoke-WithSentry { ... }`:{n}{n}    \
+             . ([scriptblock]::Create((sentry-cli powershell-hook) -join \"`n\")){n}{n}\
+             Native commands that exit with a non-zero status are reported as \
+             well.  Use --cmd to print a batch file that runs the command given \
+             to it and reports a non-zero exit code.",
+        )
+        .arg(
+            Arg::with_name("no_exit")
+                .long("no-exit")
+                .help("Continue after errors instead of exiting the script."),
+        )
+        .arg(
+            Arg::with_name("no_environ")
+                .long("no-environ")
+                .help("Do not send environment variables along"),
+        )
+        .arg(
+            Arg::with_name("cli")
+                .long("cli")
+                .value_name("CMD")
+                .help("Explicitly set/override the sentry-cli command"),
+        )
+        .arg(
+            Arg::with_name("cmd")
+                .long("cmd")
+                .conflicts_with("no_exit")
+                .help(
+                    "Print a cmd.exe wrapper instead, to be saved as a batch file \
+                     and called as `sentry-wrap.cmd COMMAND [ARGS]`.",
+                ),
+        )
+        .arg(
+            Arg::with_name("send_event")
+                .long("send-event")
+                .requires_all(&["traceback", "log"])
+                .hidden(true),
+        )
+        .arg(
+            Arg::with_name("traceback")
+                .long("traceback")
+                .value_name("PATH")
+                .hidden(true),
+        )
+        .arg(
+            Arg::with_name("log")
+                .long("log")
+                .value_name("PATH")
+                .hidden(true),
+        )
+}
+
+pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    if matches.is_present("send_event") {
+        return send_event(
+            "PowerShellError",
+            matches.value_of("traceback").unwrap(),
+            matches.value_of("log").unwrap(),
+            None,
+            !matches.is_present("no_environ"),
+            false,
+            None,
+        );
+    }
+
+    let cli = match matches.value_of("cli") {
+        Some(cli) => cli.to_string(),
+        None => env::current_exe().unwrap().display().to_string(),
+    };
+    let no_environ = if matches.is_present("no_environ") {
+        "--no-environ"
+    } else {
+        ""
+    };
+
+    if matches.is_present("cmd") {
+        let script = CMD_SCRIPT
+            .replace("___SENTRY_CLI___", &cli)
+            .replace("___SENTRY_NO_ENVIRON___", no_environ);
+        print!("{}", script);
+        return Ok(());
+    }
+
+    let mut script = POWERSHELL_SCRIPT
+        .replace("___SENTRY_CLI___", &cli)
+        .replace("___SENTRY_NO_ENVIRON___", no_environ);
+
+    if matches.is_present("no_exit") {
+        script = script.replace("___SENTRY_EXIT_ON_ERROR___", "false");
+    } else {
+        script = script.replace("___SENTRY_EXIT_ON_ERROR___", "true");
+        script.insert_str(0, "$ErrorActionPreference = 'Stop'\n\n");
+    }
+    println!("{}", script);
+    Ok(())
+}
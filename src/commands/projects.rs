@@ -1,24 +1,1000 @@
 //! Implements a command for managing projects.
-use clap::{App, AppSettings, ArgMatches};
-use failure::Error;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
 
-use crate::api::Api;
+use clap::{App, AppSettings, Arg, ArgMatches};
+use failure::{bail, Error, ResultExt};
+use log::info;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::api::{
+    AlertRule, Api, ApiErrorKind, NewProject, ProjectDetails, ProjectKey, ProjectOwnership,
+    ProjectSettings, RateLimit,
+};
 use crate::config::Config;
 use crate::utils::args::ArgExt;
+use crate::utils::codeowners::{parse_codeowners, CODEOWNERS_PATHS};
 use crate::utils::formatting::Table;
+use crate::utils::machine;
+use crate::utils::system::QuietExit;
+use crate::utils::ui::confirm;
 
 pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
     app.about("Manage projects on Sentry.")
         .setting(AppSettings::SubcommandRequiredElseHelp)
         .org_arg()
         .subcommand(App::new("list").about("List all projects for an organization."))
+        .subcommand(
+            App::new("create")
+                .about("Create a new project.")
+                .arg(
+                    Arg::with_name("name")
+                        .value_name("NAME")
+                        .required(true)
+                        .index(1)
+                        .help("The name of the new project."),
+                )
+                .arg(
+                    Arg::with_name("team")
+                        .long("team")
+                        .short("t")
+                        .value_name("TEAM")
+                        .required(true)
+                        .help("The slug of the team that owns the project."),
+                )
+                .arg(
+                    Arg::with_name("slug").long("slug").value_name("SLUG").help(
+                        "The slug of the project. [defaults to a slug derived from the name]",
+                    ),
+                )
+                .arg(
+                    Arg::with_name("platform")
+                        .long("platform")
+                        .short("p")
+                        .value_name("PLATFORM")
+                        .help("The platform of the project, e.g. `python` or `javascript-react`."),
+                )
+                .arg(
+                    Arg::with_name("no_default_rules")
+                        .long("no-default-rules")
+                        .help("Do not create the default alert rules for the project."),
+                )
+                .idempotent_arg(),
+        )
+        .subcommand(keys_app(App::new("keys")))
+        .subcommand(ownership_app(App::new("ownership")))
+        .subcommand(symbol_sources_app(App::new("symbol-sources")))
+        .subcommand(
+            App::new("quota")
+                .about("Show rate limits, spike protection and dropped events of a project.")
+                .arg(
+                    Arg::with_name("project")
+                        .value_name("PROJECT")
+                        .index(1)
+                        .help("The project slug. [defaults to the configured project]"),
+                )
+                .arg(
+                    Arg::with_name("period")
+                        .long("period")
+                        .value_name("PERIOD")
+                        .help("The period to report event counts for, e.g. `24h` or `30d`. [defaults to `24h`]"),
+                )
+                .arg(
+                    Arg::with_name("category")
+                        .long("category")
+                        .value_name("CATEGORY")
+                        .possible_values(&["error", "transaction", "attachment", "session"])
+                        .help("The data category to report. [defaults to `error`]"),
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help("Print the report as JSON."),
+                ),
+        )
+        .subcommand(
+            App::new("export-config")
+                .about("Export the configuration of a project as YAML.")
+                .long_about(
+                    "Export the configuration of a project as YAML.{n}{n}\
+                     The exported file contains the general settings, the issue alert rules, \
+                     the ownership rules and the inbound filters of the project and can be \
+                     applied again with `apply-config`.",
+                )
+                .project_arg()
+                .arg(
+                    Arg::with_name("output")
+                        .long("output")
+                        .short("O")
+                        .value_name("PATH")
+                        .help("Write the configuration to a file instead of stdout."),
+                ),
+        )
+        .subcommand(
+            App::new("apply-config")
+                .about("Apply a YAML configuration to a project.")
+                .long_about(
+                    "Apply a YAML configuration to a project.{n}{n}\
+                     Prints the differences to the live configuration before applying them. \
+                     Sections and settings missing from the file are left untouched. Alert \
+                     rules without an `id` are matched by name or created.",
+                )
+                .project_arg()
+                .arg(
+                    Arg::with_name("path")
+                        .value_name("PATH")
+                        .required(true)
+                        .index(1)
+                        .help("The path to the YAML configuration file."),
+                )
+                .arg(Arg::with_name("check").long("check").help(
+                    "Only print the differences and exit with a non-zero status \
+                             if the project is not up to date.",
+                ))
+                .arg(
+                    Arg::with_name("confirm")
+                        .long("confirm")
+                        .help("Skip the confirmation prompt."),
+                ),
+        )
+        .subcommand(
+            App::new("delete")
+                .about("Delete a project.")
+                .arg(
+                    Arg::with_name("project")
+                        .value_name("SLUG")
+                        .required(true)
+                        .index(1)
+                        .help("The slug of the project to delete."),
+                )
+                .arg(
+                    Arg::with_name("confirm")
+                        .long("confirm")
+                        .help("Skip the confirmation prompt."),
+                ),
+        )
 }
 
-pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
-    let config = Config::current();
+fn keys_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
+    let key_id_arg = Arg::with_name("key_id")
+        .value_name("KEY_ID")
+        .required(true)
+        .index(1)
+        .help("The ID of the client key.");
+
+    app.about("Manage the client keys (DSNs) of a project.")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .project_arg()
+        .subcommand(
+            App::new("list")
+                .about("List the client keys of a project.")
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help("Print the client keys as JSON."),
+                ),
+        )
+        .subcommand(
+            App::new("create")
+                .about("Create a new client key and print its DSN.")
+                .arg(
+                    Arg::with_name("name")
+                        .long("name")
+                        .value_name("NAME")
+                        .help("The name of the new client key."),
+                ),
+        )
+        .subcommand(
+            App::new("disable")
+                .about("Disable a client key.")
+                .arg(key_id_arg.clone()),
+        )
+        .subcommand(
+            App::new("rotate")
+                .about("Replace a client key with a new one and print the new DSN.")
+                .long_about(
+                    "Replace a client key with a new one and print the new DSN.{n}{n}\
+                     A new key with the same name is created. The old key stays active so \
+                     clients can move to the new DSN, disable it afterwards with `disable` \
+                     or right away with `--disable-old`. Only the new DSN is printed to \
+                     stdout so it can be written to a secret store directly.",
+                )
+                .arg(key_id_arg)
+                .arg(
+                    Arg::with_name("disable_old")
+                        .long("disable-old")
+                        .help("Disable the old client key right away."),
+                )
+                .arg(
+                    Arg::with_name("delete_old")
+                        .long("delete-old")
+                        .conflicts_with("disable_old")
+                        .help("Delete the old client key right away."),
+                ),
+        )
+}
+
+fn ownership_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.about("Manage the ownership rules of a project.")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .project_arg()
+        .subcommand(App::new("show").about("Print the ownership rules of a project."))
+        .subcommand(
+            App::new("sync")
+                .about("Replace the ownership rules with the rules of a CODEOWNERS file.")
+                .long_about(
+                    "Replace the ownership rules with the rules of a CODEOWNERS file.{n}{n}\
+                     GitHub teams (`@org/team`) become Sentry teams with the same slug and \
+                     email addresses are kept. GitHub users cannot be resolved automatically \
+                     and need to be mapped with `--map`, otherwise they are skipped.",
+                )
+                .arg(Arg::with_name("from").long("from").value_name("PATH").help(
+                    "The path to the CODEOWNERS file. [defaults to \
+                             .github/CODEOWNERS, CODEOWNERS or docs/CODEOWNERS]",
+                ))
+                .arg(
+                    Arg::with_name("map")
+                        .long("map")
+                        .value_name("OWNER=SENTRY_OWNER")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help(
+                            "Map a CODEOWNERS owner to a Sentry owner, \
+                             e.g. `@octocat=jane@example.com`.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("dry_run")
+                        .long("dry-run")
+                        .help("Print the translated rules without uploading them."),
+                ),
+        )
+}
+
+fn execute_ownership(org: &str, matches: &ArgMatches<'_>) -> Result<(), Error> {
     let api = Api::current();
-    let org = config.get_org(matches)?;
-    let mut projects = api.list_organization_projects(&org)?;
+    let project = get_project(matches)?;
+
+    if matches.subcommand_matches("show").is_some() {
+        let ownership = api.get_project_ownership(org, &project)?;
+        print!("{}", ownership.raw.unwrap_or_default());
+        return Ok(());
+    }
+
+    let sub_matches = matches.subcommand_matches("sync").unwrap();
+    let path = match sub_matches.value_of("from") {
+        Some(path) => path,
+        None => match CODEOWNERS_PATHS.iter().find(|p| Path::new(p).is_file()) {
+            Some(path) => path,
+            None => bail!("Could not find a CODEOWNERS file, specify one with --from"),
+        },
+    };
+
+    let mut mappings = HashMap::new();
+    for mapping in sub_matches.values_of("map").unwrap_or_default() {
+        match mapping.split_once('=') {
+            Some((owner, sentry_owner)) => {
+                mappings.insert(owner.to_owned(), sentry_owner.to_owned());
+            }
+            None => bail!("Invalid mapping '{}', expected OWNER=SENTRY_OWNER", mapping),
+        }
+    }
+
+    let contents = fs::read_to_string(path).with_context(|_| format!("Could not read {}", path))?;
+    let rules = parse_codeowners(&contents, &mappings);
+    for owner in &rules.unmapped {
+        eprintln!(
+            "warning: skipping owner {} without a Sentry equivalent",
+            owner
+        );
+    }
+
+    if sub_matches.is_present("dry_run") {
+        print!("{}", rules.to_raw());
+        return Ok(());
+    }
+
+    let mut ownership = api.get_project_ownership(org, &project)?;
+    ownership.raw = Some(rules.to_raw());
+    api.update_project_ownership(org, &project, &ownership)?;
+    println!(
+        "Synced {} ownership rules from {} to {}",
+        rules.rules.len(),
+        path,
+        project
+    );
+
+    Ok(())
+}
+
+fn symbol_sources_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.about("Manage the custom symbol sources of a project.")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .project_arg()
+        .subcommand(
+            App::new("list")
+                .about("List the custom symbol sources of a project.")
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help("Print the symbol sources as JSON."),
+                ),
+        )
+        .subcommand(
+            App::new("add")
+                .about("Add a custom symbol source.")
+                .arg(
+                    Arg::with_name("type")
+                        .long("type")
+                        .value_name("TYPE")
+                        .required(true)
+                        .possible_values(&["http", "s3", "gcs"])
+                        .help("The type of the symbol source."),
+                )
+                .arg(
+                    Arg::with_name("name")
+                        .long("name")
+                        .value_name("NAME")
+                        .required(true)
+                        .help("The display name of the symbol source."),
+                )
+                .arg(
+                    Arg::with_name("id")
+                        .long("id")
+                        .value_name("ID")
+                        .help("The unique ID of the symbol source. [defaults to a random ID]"),
+                )
+                .arg(
+                    Arg::with_name("layout")
+                        .long("layout")
+                        .value_name("LAYOUT")
+                        .possible_values(&[
+                            "native",
+                            "symstore",
+                            "symstore_index2",
+                            "ssqp",
+                            "unified",
+                            "debuginfod",
+                        ])
+                        .help("The directory layout of the symbol source. [defaults to 'native']"),
+                )
+                .arg(
+                    Arg::with_name("url")
+                        .long("url")
+                        .value_name("URL")
+                        .required_if("type", "http")
+                        .help("The URL of an HTTP symbol server."),
+                )
+                .arg(
+                    Arg::with_name("username")
+                        .long("username")
+                        .value_name("USERNAME")
+                        .help("The username for an HTTP symbol server."),
+                )
+                .arg(
+                    Arg::with_name("password")
+                        .long("password")
+                        .value_name("PASSWORD")
+                        .help(
+                            "The password for an HTTP symbol server.  Other users can \
+                             see command lines, prefer --password-file or \
+                             SENTRY_SYMBOL_SOURCE_PASSWORD.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("password_file")
+                        .long("password-file")
+                        .value_name("PATH")
+                        .conflicts_with("password")
+                        .help("Read the password for an HTTP symbol server from a file."),
+                )
+                .arg(
+                    Arg::with_name("bucket")
+                        .long("bucket")
+                        .value_name("BUCKET")
+                        .required_ifs(&[("type", "s3"), ("type", "gcs")])
+                        .help("The S3 or GCS bucket."),
+                )
+                .arg(
+                    Arg::with_name("prefix")
+                        .long("prefix")
+                        .value_name("PREFIX")
+                        .help("The path prefix within the bucket."),
+                )
+                .arg(
+                    Arg::with_name("region")
+                        .long("region")
+                        .value_name("REGION")
+                        .required_if("type", "s3")
+                        .help("The AWS region of an S3 bucket."),
+                )
+                .arg(
+                    Arg::with_name("access_key")
+                        .long("access-key")
+                        .value_name("KEY")
+                        .required_if("type", "s3")
+                        .help("The AWS access key ID."),
+                )
+                .arg(
+                    Arg::with_name("secret_key")
+                        .long("secret-key")
+                        .value_name("KEY")
+                        .help(
+                            "The AWS secret access key.  Other users can see command \
+                             lines, prefer --secret-key-file or \
+                             SENTRY_SYMBOL_SOURCE_SECRET_KEY.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("secret_key_file")
+                        .long("secret-key-file")
+                        .value_name("PATH")
+                        .conflicts_with("secret_key")
+                        .help("Read the AWS secret access key from a file."),
+                )
+                .arg(
+                    Arg::with_name("client_email")
+                        .long("client-email")
+                        .value_name("EMAIL")
+                        .required_if("type", "gcs")
+                        .help("The email address of the GCS service account."),
+                )
+                .arg(
+                    Arg::with_name("private_key_file")
+                        .long("private-key-file")
+                        .value_name("PATH")
+                        .required_if("type", "gcs")
+                        .help("The path to the private key of the GCS service account."),
+                ),
+        )
+        .subcommand(
+            App::new("remove")
+                .about("Remove a custom symbol source.")
+                .arg(
+                    Arg::with_name("id")
+                        .value_name("ID")
+                        .required(true)
+                        .index(1)
+                        .help("The ID of the symbol source."),
+                ),
+        )
+}
+
+fn get_symbol_sources(api: &Api, org: &str, project: &str) -> Result<Vec<Value>, Error> {
+    match api.get_project_details(org, project)?.symbol_sources {
+        Some(ref sources) if !sources.is_empty() => Ok(serde_json::from_str(sources)
+            .context("Could not parse the symbol sources of the project")?),
+        _ => Ok(vec![]),
+    }
+}
+
+/// Returns a secret of a symbol source from its option, the file of its
+/// `--*-file` option or the environment variable.
+fn get_symbol_source_secret(
+    matches: &ArgMatches<'_>,
+    name: &str,
+    env_var: &str,
+) -> Result<Option<String>, Error> {
+    if let Some(value) = matches.value_of(name) {
+        return Ok(Some(value.into()));
+    }
+    if let Some(path) = matches.value_of(format!("{}_file", name)) {
+        let value =
+            fs::read_to_string(path).with_context(|_| format!("Could not read {}", path))?;
+        return Ok(Some(value.trim_end_matches(&['\r', '\n'][..]).into()));
+    }
+    Ok(env::var(env_var).ok().filter(|value| !value.is_empty()))
+}
+
+fn build_symbol_source(matches: &ArgMatches<'_>) -> Result<Value, Error> {
+    let password = get_symbol_source_secret(matches, "password", "SENTRY_SYMBOL_SOURCE_PASSWORD")?;
+    let secret_key =
+        get_symbol_source_secret(matches, "secret_key", "SENTRY_SYMBOL_SOURCE_SECRET_KEY")?;
+    if matches.value_of("type") == Some("s3") && secret_key.is_none() {
+        bail!(
+            "S3 symbol sources need a secret key, pass --secret-key-file or set \
+             SENTRY_SYMBOL_SOURCE_SECRET_KEY"
+        );
+    }
+
+    let mut source = serde_json::Map::new();
+    let mut set = |key: &str, value: Option<&str>| {
+        if let Some(value) = value {
+            source.insert(key.into(), Value::String(value.into()));
+        }
+    };
+
+    let id = matches
+        .value_of("id")
+        .map(str::to_owned)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    set("id", Some(&id));
+    set("type", matches.value_of("type"));
+    set("name", matches.value_of("name"));
+    set("url", matches.value_of("url"));
+    set("username", matches.value_of("username"));
+    set("password", password.as_deref());
+    set("bucket", matches.value_of("bucket"));
+    set("prefix", matches.value_of("prefix"));
+    set("region", matches.value_of("region"));
+    set("access_key", matches.value_of("access_key"));
+    set("secret_key", secret_key.as_deref());
+    set("client_email", matches.value_of("client_email"));
+    if let Some(path) = matches.value_of("private_key_file") {
+        let key = fs::read_to_string(path).with_context(|_| format!("Could not read {}", path))?;
+        set("private_key", Some(&key));
+    }
+
+    source.insert(
+        "layout".into(),
+        serde_json::json!({
+            "type": matches.value_of("layout").unwrap_or("native"),
+            "casing": "default",
+        }),
+    );
+    Ok(Value::Object(source))
+}
+
+fn execute_symbol_sources(org: &str, matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let api = Api::current();
+    let project = get_project(matches)?;
+    let mut sources = get_symbol_sources(&api, org, &project)?;
+    let source_id = |source: &Value| source["id"].as_str().unwrap_or_default().to_owned();
+
+    if let Some(sub_matches) = matches.subcommand_matches("add") {
+        let source = build_symbol_source(sub_matches)?;
+        let id = source_id(&source);
+        if sources.iter().any(|s| source_id(s) == id) {
+            bail!("A symbol source with the ID {} already exists", id);
+        }
+        sources.push(source);
+        api.update_symbol_sources(org, &project, &sources)?;
+        println!("Added symbol source {}", id);
+    } else if let Some(sub_matches) = matches.subcommand_matches("remove") {
+        let id = sub_matches.value_of("id").unwrap();
+        let len = sources.len();
+        sources.retain(|s| source_id(s) != id);
+        if sources.len() == len {
+            println!("Did nothing. Symbol source {} does not exist.", id);
+        } else {
+            api.update_symbol_sources(org, &project, &sources)?;
+            println!("Removed symbol source {}", id);
+        }
+    } else if let Some(sub_matches) = matches.subcommand_matches("list") {
+        if sub_matches.is_present("json") {
+            serde_json::to_writer_pretty(&mut io::stdout(), &sources)?;
+            println!();
+            return Ok(());
+        }
+
+        let mut table = Table::new();
+        table
+            .title_row()
+            .add("ID")
+            .add("Type")
+            .add("Name")
+            .add("Location");
+        for source in &sources {
+            let location = match source["type"].as_str() {
+                Some("http") => source["url"].as_str().unwrap_or_default().to_owned(),
+                _ => format!(
+                    "{}/{}",
+                    source["bucket"].as_str().unwrap_or_default(),
+                    source["prefix"].as_str().unwrap_or_default()
+                ),
+            };
+            table
+                .add_row()
+                .add(source_id(source))
+                .add(source["type"].as_str().unwrap_or("-"))
+                .add(source["name"].as_str().unwrap_or("-"))
+                .add(location);
+        }
+
+        if table.is_empty() {
+            println!("No custom symbol sources configured");
+        } else {
+            table.print();
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct QuotaReport<'a> {
+    project: &'a str,
+    spike_protection: bool,
+    rate_limits: Vec<KeyRateLimit<'a>>,
+    period: &'a str,
+    category: &'a str,
+    outcomes: BTreeMap<String, u64>,
+    dropped: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct KeyRateLimit<'a> {
+    key_id: &'a str,
+    name: &'a str,
+    rate_limit: Option<&'a RateLimit>,
+}
+
+fn execute_quota(org: &str, matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let api = Api::current();
+    let project = get_project(matches)?;
+    let period = matches.value_of("period").unwrap_or("24h");
+    let category = matches.value_of("category").unwrap_or("error");
+
+    let details = api.get_project_details(org, &project)?;
+    let keys = api.list_project_keys(org, &project)?;
+    let outcomes: BTreeMap<_, _> = api
+        .get_project_outcomes(org, &details.id, category, period)?
+        .into_iter()
+        .collect();
+
+    let report = QuotaReport {
+        project: &details.slug,
+        spike_protection: details
+            .options
+            .get("quotas:spike-protection-disabled")
+            .and_then(Value::as_bool)
+            != Some(true),
+        rate_limits: keys
+            .iter()
+            .filter(|key| key.is_active)
+            .map(|key| KeyRateLimit {
+                key_id: &key.id,
+                name: &key.name,
+                rate_limit: key.rate_limit.as_ref(),
+            })
+            .collect(),
+        period,
+        category,
+        dropped: outcomes
+            .iter()
+            .filter(|(outcome, _)| *outcome != "accepted")
+            .map(|(_, quantity)| quantity)
+            .sum(),
+        outcomes,
+    };
+
+    if matches.is_present("json") {
+        serde_json::to_writer_pretty(&mut io::stdout(), &report)?;
+        println!();
+        return Ok(());
+    }
+
+    println!("Project: {} ({})", details.slug, details.id);
+    println!(
+        "Spike protection: {}",
+        if report.spike_protection {
+            "enabled"
+        } else {
+            "disabled"
+        }
+    );
+    println!("Rate limits:");
+    for key in &report.rate_limits {
+        match key.rate_limit {
+            Some(limit) => println!(
+                "  {} ({}): {} events per {}s",
+                key.name, key.key_id, limit.count, limit.window
+            ),
+            None => println!("  {} ({}): unlimited", key.name, key.key_id),
+        }
+    }
+    println!("Events ({}, {}):", category, period);
+    for (outcome, quantity) in &report.outcomes {
+        println!("  {}: {}", outcome, quantity);
+    }
+    let total: u64 = report.outcomes.values().sum();
+    if total > 0 {
+        println!(
+            "  dropped: {} ({:.1}%)",
+            report.dropped,
+            report.dropped as f64 * 100.0 / total as f64
+        );
+    }
+
+    Ok(())
+}
+
+fn get_project(matches: &ArgMatches<'_>) -> Result<String, Error> {
+    match matches.value_of("project") {
+        Some(project) => Ok(project.to_owned()),
+        None => Config::current().get_project_default(),
+    }
+}
+
+fn execute_keys(org: &str, matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let api = Api::current();
+    let project = get_project(matches)?;
+
+    if let Some(sub_matches) = matches.subcommand_matches("create") {
+        let key = api.create_project_key(org, &project, sub_matches.value_of("name"))?;
+        println!("{}", key.dsn.public);
+    } else if let Some(sub_matches) = matches.subcommand_matches("disable") {
+        let key = api.set_project_key_active(
+            org,
+            &project,
+            sub_matches.value_of("key_id").unwrap(),
+            false,
+        )?;
+        println!("Disabled client key {} ({})", key.id, key.name);
+    } else if let Some(sub_matches) = matches.subcommand_matches("rotate") {
+        let key_id = sub_matches.value_of("key_id").unwrap();
+        let old_key = match api
+            .list_project_keys(org, &project)?
+            .into_iter()
+            .find(|key| key.id == key_id)
+        {
+            Some(key) => key,
+            None => bail!("Client key {} not found", key_id),
+        };
+
+        let new_key = api.create_project_key(org, &project, Some(&old_key.name))?;
+        if sub_matches.is_present("delete_old") {
+            api.delete_project_key(org, &project, &old_key.id)?;
+            info!("Deleted client key {}", old_key.id);
+        } else if sub_matches.is_present("disable_old") {
+            api.set_project_key_active(org, &project, &old_key.id, false)?;
+            info!("Disabled client key {}", old_key.id);
+        } else {
+            eprintln!(
+                "Client key {} is still active, disable it with `projects keys disable` \
+                 once all clients use the new DSN.",
+                old_key.id
+            );
+        }
+        println!("{}", new_key.dsn.public);
+    } else if let Some(sub_matches) = matches.subcommand_matches("list") {
+        let keys = api.list_project_keys(org, &project)?;
+        if sub_matches.is_present("json") {
+            serde_json::to_writer_pretty(&mut io::stdout(), &keys)?;
+            println!();
+        } else {
+            print_keys(&keys);
+        }
+    }
+
+    Ok(())
+}
+
+fn print_keys(keys: &[ProjectKey]) {
+    let mut table = Table::new();
+    table
+        .title_row()
+        .add("ID")
+        .add("Name")
+        .add("Active")
+        .add("DSN");
+
+    for key in keys {
+        table
+            .add_row()
+            .add(&key.id)
+            .add(&key.name)
+            .add(if key.is_active { "yes" } else { "no" })
+            .add(&key.dsn.public);
+    }
+
+    table.print();
+}
+
+/// The managed configuration of a project as stored in YAML files.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct ProjectConfig {
+    #[serde(default)]
+    settings: ProjectSettings,
+    #[serde(default)]
+    alert_rules: Vec<AlertRule>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ownership: Option<ProjectOwnership>,
+    #[serde(default)]
+    filters: BTreeMap<String, Value>,
+}
+
+impl ProjectConfig {
+    fn fetch(api: &Api, org: &str, project: &str) -> Result<ProjectConfig, Error> {
+        Ok(ProjectConfig {
+            settings: api.get_project_settings(org, project)?,
+            alert_rules: api.list_alert_rules(org, project)?,
+            ownership: Some(api.get_project_ownership(org, project)?),
+            filters: api
+                .list_inbound_filters(org, project)?
+                .into_iter()
+                .map(|filter| (filter.id, filter.active))
+                .collect(),
+        })
+    }
+}
+
+/// The changes needed to bring a project in line with a configuration.
+#[derive(Default)]
+struct ConfigPlan {
+    diff: Vec<String>,
+    settings: Option<ProjectSettings>,
+    create_rules: Vec<AlertRule>,
+    update_rules: Vec<AlertRule>,
+    ownership: Option<ProjectOwnership>,
+    filters: Vec<(String, Value)>,
+}
+
+impl ConfigPlan {
+    fn is_empty(&self) -> bool {
+        self.diff.is_empty()
+    }
+}
+
+/// Records the differences of all keys present in `desired`.  Keys that
+/// only exist in `current` are not managed and therefore ignored.
+fn diff_values(path: &str, current: &Value, desired: &Value, diff: &mut Vec<String>) {
+    match (current, desired) {
+        (Value::Object(current), Value::Object(desired)) => {
+            for (key, desired) in desired {
+                let current = current.get(key).unwrap_or(&Value::Null);
+                diff_values(&format!("{}.{}", path, key), current, desired, diff);
+            }
+        }
+        _ if current != desired => diff.push(format!("~ {}: {} -> {}", path, current, desired)),
+        _ => {}
+    }
+}
+
+fn plan_config(current: &ProjectConfig, desired: ProjectConfig) -> Result<ConfigPlan, Error> {
+    let mut plan = ConfigPlan::default();
+
+    let len = plan.diff.len();
+    diff_values(
+        "settings",
+        &serde_json::to_value(&current.settings)?,
+        &serde_json::to_value(&desired.settings)?,
+        &mut plan.diff,
+    );
+    if plan.diff.len() > len {
+        plan.settings = Some(desired.settings);
+    }
+
+    for rule in desired.alert_rules {
+        let existing = match rule.id {
+            Some(ref id) => match current
+                .alert_rules
+                .iter()
+                .find(|r| r.id.as_ref() == Some(id))
+            {
+                Some(existing) => Some(existing),
+                None => bail!("Alert rule {} ({}) does not exist", id, rule.name),
+            },
+            None => current.alert_rules.iter().find(|r| r.name == rule.name),
+        };
+        match existing {
+            Some(existing) => {
+                let rule = rule.merged_onto(existing);
+                let len = plan.diff.len();
+                diff_values(
+                    &format!("alertRules[{}]", rule.name),
+                    &serde_json::to_value(existing)?,
+                    &serde_json::to_value(&rule)?,
+                    &mut plan.diff,
+                );
+                if plan.diff.len() > len {
+                    plan.update_rules.push(rule);
+                }
+            }
+            None => {
+                plan.diff.push(format!("+ alertRules[{}]", rule.name));
+                plan.create_rules.push(rule);
+            }
+        }
+    }
+
+    if let Some(ownership) = desired.ownership {
+        let ownership = match current.ownership {
+            Some(ref existing) => ownership.merged_onto(existing),
+            None => ownership,
+        };
+        let len = plan.diff.len();
+        diff_values(
+            "ownership",
+            &serde_json::to_value(&current.ownership)?,
+            &serde_json::to_value(&ownership)?,
+            &mut plan.diff,
+        );
+        if plan.diff.len() > len {
+            plan.ownership = Some(ownership);
+        }
+    }
+
+    for (id, active) in desired.filters {
+        match current.filters.get(&id) {
+            Some(current) if *current == active => {}
+            Some(current) => {
+                plan.diff
+                    .push(format!("~ filters.{}: {} -> {}", id, current, active));
+                plan.filters.push((id, active));
+            }
+            None => bail!("Inbound filter {} does not exist", id),
+        }
+    }
+
+    Ok(plan)
+}
+
+fn execute_export_config(org: &str, matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let project = get_project(matches)?;
+    let config = ProjectConfig::fetch(&Api::current(), org, &project)?;
+
+    if let Some(path) = matches.value_of("output") {
+        let file = fs::File::create(path).with_context(|_| format!("Could not create {}", path))?;
+        serde_yaml::to_writer(file, &config)?;
+        println!("Exported configuration of {} to {}", project, path);
+    } else {
+        serde_yaml::to_writer(io::stdout(), &config)?;
+        println!();
+    }
+
+    Ok(())
+}
+
+fn execute_apply_config(org: &str, matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let api = Api::current();
+    let project = get_project(matches)?;
+    let path = matches.value_of("path").unwrap();
+    let desired: ProjectConfig = serde_yaml::from_reader(
+        fs::File::open(path).with_context(|_| format!("Could not open {}", path))?,
+    )
+    .with_context(|_| format!("Could not parse {}", path))?;
+
+    let current = ProjectConfig::fetch(&api, org, &project)?;
+    let plan = plan_config(&current, desired)?;
+
+    if plan.is_empty() {
+        println!("Configuration of {} is up to date.", project);
+        return Ok(());
+    }
+
+    println!("Changes to {}:", project);
+    for line in &plan.diff {
+        println!("  {}", line);
+    }
+
+    if matches.is_present("check") {
+        return Err(QuietExit(1).into());
+    }
+    if !confirm(matches.is_present("confirm"), "Apply these changes?")? {
+        println!("Aborted!");
+        return Ok(());
+    }
+
+    if let Some(ref settings) = plan.settings {
+        api.update_project_settings(org, &project, settings)?;
+    }
+    for rule in &plan.create_rules {
+        api.create_alert_rule(org, &project, rule)?;
+    }
+    for rule in &plan.update_rules {
+        api.update_alert_rule(org, &project, rule.id.as_ref().unwrap(), rule)?;
+    }
+    if let Some(ref ownership) = plan.ownership {
+        api.update_project_ownership(org, &project, ownership)?;
+    }
+    for (id, active) in &plan.filters {
+        api.update_inbound_filter(org, &project, id, active)?;
+    }
+
+    println!("Applied {} changes.", plan.diff.len());
+    Ok(())
+}
+
+fn execute_list(org: &str) -> Result<(), Error> {
+    let mut projects = Api::current().list_organization_projects(org)?;
     projects.sort_by_key(|p| {
         (
             p.team.as_ref().map_or(String::new(), |t| t.name.clone()),
@@ -52,3 +1028,119 @@ pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
 
     Ok(())
 }
+
+/// Finds the project `projects create` would create.
+fn find_existing_project(
+    org: &str,
+    matches: &ArgMatches<'_>,
+) -> Result<Option<ProjectDetails>, Error> {
+    let api = Api::current();
+    let name = matches.value_of("name").unwrap();
+    let slug = match matches.value_of("slug") {
+        Some(slug) => slug.to_owned(),
+        // without a slug the one the server derives is unknown, so look
+        // for a project of the same name
+        None => match api
+            .list_organization_projects(org)?
+            .into_iter()
+            .find(|project| project.name == name)
+        {
+            Some(project) => project.slug,
+            None => return Ok(None),
+        },
+    };
+    match api.get_project_details(org, &slug) {
+        Ok(project) => Ok(Some(project)),
+        Err(err) if err.kind() == ApiErrorKind::ProjectNotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn execute_create(org: &str, matches: &ArgMatches<'_>) -> Result<(), Error> {
+    if matches.is_present("idempotent") {
+        if let Some(existing) = find_existing_project(org, matches)? {
+            let platform = matches.value_of("platform");
+            if existing.name != matches.value_of("name").unwrap()
+                || (platform.is_some() && existing.platform.as_deref() != platform)
+            {
+                bail!(
+                    "Project {} already exists with different settings",
+                    existing.slug
+                );
+            }
+            println!(
+                "Project {} ({}) already exists, nothing changed.",
+                existing.slug, existing.id
+            );
+            machine::set_result("changed", false);
+            return Ok(());
+        }
+        machine::set_result("changed", true);
+    }
+
+    let project = Api::current().create_project(
+        org,
+        matches.value_of("team").unwrap(),
+        &NewProject {
+            name: matches.value_of("name").unwrap().to_owned(),
+            slug: matches.value_of("slug").map(str::to_owned),
+            platform: matches.value_of("platform").map(str::to_owned),
+            default_rules: !matches.is_present("no_default_rules"),
+        },
+    )?;
+    println!("Created project {} ({})", project.slug, project.id);
+    Ok(())
+}
+
+fn execute_delete(org: &str, matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let project = matches.value_of("project").unwrap();
+
+    if !confirm(
+        matches.is_present("confirm"),
+        &format!(
+            "Do you really want to delete project {}? All of its data will be lost.",
+            project
+        ),
+    )? {
+        println!("Aborted!");
+        return Ok(());
+    }
+
+    if Api::current().delete_project(org, project)? {
+        println!("Deleted project {}!", project);
+    } else {
+        println!("Did nothing. Project {} does not exist.", project);
+    }
+    Ok(())
+}
+
+pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let config = Config::current();
+    let org = config.get_org(matches)?;
+
+    if let Some(sub_matches) = matches.subcommand_matches("create") {
+        return execute_create(&org, sub_matches);
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("delete") {
+        return execute_delete(&org, sub_matches);
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("keys") {
+        return execute_keys(&org, sub_matches);
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("quota") {
+        return execute_quota(&org, sub_matches);
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("symbol-sources") {
+        return execute_symbol_sources(&org, sub_matches);
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("ownership") {
+        return execute_ownership(&org, sub_matches);
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("export-config") {
+        return execute_export_config(&org, sub_matches);
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("apply-config") {
+        return execute_apply_config(&org, sub_matches);
+    }
+    execute_list(&org)
+}
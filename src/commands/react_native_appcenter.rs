@@ -1,6 +1,7 @@
 use std::env;
 use std::ffi::OsStr;
 use std::fs;
+use std::path::Path;
 
 use clap::{App, Arg, ArgMatches};
 use console::style;
@@ -12,7 +13,7 @@ use crate::api::{Api, NewRelease};
 use crate::config::Config;
 use crate::utils::appcenter::{get_appcenter_package, get_react_native_appcenter_release};
 use crate::utils::args::ArgExt;
-use crate::utils::file_search::ReleaseFileSearch;
+use crate::utils::file_search::{IgnoreMatcher, ReleaseFileSearch};
 use crate::utils::file_upload::UploadContext;
 use crate::utils::sourcemaps::SourceMapProcessor;
 
@@ -90,6 +91,7 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
                 .long("wait")
                 .help("Wait for the server to fully process uploaded files."),
         )
+        .ignore_args()
 }
 
 pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
@@ -135,8 +137,10 @@ pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
 
     let mut processor = SourceMapProcessor::new();
     for path in matches.values_of("paths").unwrap() {
+        let ignores = IgnoreMatcher::from_args(Path::new(path), matches)?;
         for entry in (fs::read_dir(path)?).flatten() {
             if_chain! {
+                if !ignores.is_ignored(&entry.path(), false);
                 if let Some(filename) = entry.file_name().to_str();
                 if let Some(ext) = entry.path().extension();
                 if ext == OsStr::new("jsbundle") ||
@@ -178,20 +182,24 @@ pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
             })?;
         }
         Some(dists) => {
-            for dist in dists {
+            let dists: Vec<_> = dists.collect();
+            for dist in &dists {
                 println!(
                     "Uploading sourcemaps for release {} distribution {}",
                     &release.version, dist
                 );
+            }
 
-                processor.upload(&UploadContext {
+            processor.upload_to_dists(
+                &UploadContext {
                     org: &org,
                     project: Some(&project),
                     release: &release.version,
-                    dist: Some(dist),
+                    dist: None,
                     wait: matches.is_present("wait"),
-                })?;
-            }
+                },
+                &dists,
+            )?;
         }
     }
 
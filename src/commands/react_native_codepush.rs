@@ -1,6 +1,7 @@
 use std::env;
 use std::ffi::OsStr;
 use std::fs;
+use std::path::Path;
 
 use clap::{App, AppSettings, Arg, ArgMatches};
 use console::style;
@@ -12,7 +13,7 @@ use crate::api::{Api, NewRelease};
 use crate::config::Config;
 use crate::utils::args::ArgExt;
 use crate::utils::codepush::{get_codepush_package, get_react_native_codepush_release};
-use crate::utils::file_search::ReleaseFileSearch;
+use crate::utils::file_search::{IgnoreMatcher, ReleaseFileSearch};
 use crate::utils::file_upload::UploadContext;
 use crate::utils::sourcemaps::SourceMapProcessor;
 
@@ -70,6 +71,7 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
                 .long("wait")
                 .help("Wait for the server to fully process uploaded files."),
         )
+        .ignore_args()
 }
 
 pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
@@ -109,8 +111,10 @@ pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
 
     let mut processor = SourceMapProcessor::new();
     for path in matches.values_of("paths").unwrap() {
+        let ignores = IgnoreMatcher::from_args(Path::new(path), matches)?;
         for entry in (fs::read_dir(path)?).flatten() {
             if_chain! {
+                if !ignores.is_ignored(&entry.path(), false);
                 if let Some(filename) = entry.file_name().to_str();
                 if let Some(ext) = entry.path().extension();
                 if ext == OsStr::new("jsbundle") ||
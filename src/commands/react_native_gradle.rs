@@ -1,9 +1,12 @@
 use std::env;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 use clap::{App, Arg, ArgMatches};
-use failure::Error;
+use failure::{bail, Error, ResultExt};
 use log::{debug, info};
+use sentry::internals::Dsn;
+use serde::Deserialize;
 use sourcemap::ram_bundle::RamBundle;
 
 use crate::api::{Api, NewRelease};
@@ -11,8 +14,45 @@ use crate::config::Config;
 use crate::utils::args::ArgExt;
 use crate::utils::file_search::ReleaseFileSearch;
 use crate::utils::file_upload::UploadContext;
+use crate::utils::size_report::SizeReport;
 use crate::utils::sourcemaps::SourceMapProcessor;
 
+/// A build variant to upload, as described by the variants file.
+#[derive(Debug, Deserialize)]
+struct Variant {
+    name: String,
+    bundle: PathBuf,
+    sourcemap: PathBuf,
+    release: String,
+    #[serde(default)]
+    dists: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VariantsFile {
+    variants: Vec<Variant>,
+}
+
+/// Reads the variants written by the Gradle plugin.  Relative paths are
+/// resolved against the directory of the file.
+fn load_variants(path: &Path) -> Result<Vec<Variant>, Error> {
+    let contents = fs::read_to_string(path)
+        .with_context(|_| format!("Could not read variants file {}", path.display()))?;
+    let file: VariantsFile = serde_json::from_str(&contents)
+        .with_context(|_| format!("Invalid variants file {}", path.display()))?;
+
+    let base = path.parent().unwrap_or_else(|| Path::new(""));
+    Ok(file
+        .variants
+        .into_iter()
+        .map(|mut variant| {
+            variant.bundle = base.join(&variant.bundle);
+            variant.sourcemap = base.join(&variant.sourcemap);
+            variant
+        })
+        .collect())
+}
+
 pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
     app.about("Upload react-native projects in a gradle build step.")
         .org_project_args()
@@ -20,58 +60,92 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
             Arg::with_name("sourcemap")
                 .long("sourcemap")
                 .value_name("PATH")
-                .required(true)
+                .required_unless("variants")
                 .help("The path to a sourcemap that should be uploaded."),
         )
         .arg(
             Arg::with_name("bundle")
                 .long("bundle")
                 .value_name("PATH")
-                .required(true)
+                .required_unless("variants")
                 .help("The path to a bundle that should be uploaded."),
         )
         .arg(
             Arg::with_name("release")
                 .long("release")
                 .value_name("RELEASE")
-                .required(true)
+                .required_unless("variants")
                 .help("The name of the release to publish."),
         )
         .arg(
             Arg::with_name("dist")
                 .long("dist")
                 .value_name("DISTRIBUTION")
-                .required(true)
+                .required_unless("variants")
                 .multiple(true)
                 .number_of_values(1)
                 .help("The names of the distributions to publish. Can be supplied multiple times."),
         )
+        .arg(
+            Arg::with_name("variants")
+                .long("variants")
+                .value_name("PATH")
+                .conflicts_with_all(&["sourcemap", "bundle", "release", "dist"])
+                .help(
+                    "Upload several build variants at once.{n}\
+                     The path to a JSON file with a `variants` list, where every \
+                     variant has a `name`, the `bundle` and `sourcemap` paths, the \
+                     `release` and optionally a list of `dists`.",
+                ),
+        )
+        .arg(
+            Arg::with_name("variant")
+                .long("variant")
+                .value_name("NAME")
+                .requires("variants")
+                .multiple(true)
+                .number_of_values(1)
+                .help(
+                    "Only upload the variant with the given name from the variants \
+                     file.  Can be supplied multiple times.",
+                ),
+        )
         .arg(
             Arg::with_name("wait")
                 .long("wait")
                 .help("Wait for the server to fully process uploaded files."),
         )
+        .arg(
+            Arg::with_name("size_report")
+                .long("size-report")
+                .help("Print the sizes of the uploaded bundle and sourcemap."),
+        )
+        .arg(
+            Arg::with_name("send_size_report")
+                .long("send-size-report")
+                .help(
+                    "Send the size report as an event to the project of the \
+                     configured DSN, tagged with the release of the bundle.",
+                ),
+        )
 }
 
-pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
-    let config = Config::current();
-    let (org, project) = config.get_org_and_project(matches)?;
-    let api = Api::current();
+fn upload_variant(
+    api: &Api,
+    org: &str,
+    project: &str,
+    variant: &Variant,
+    wait: bool,
+) -> Result<SizeReport, Error> {
     let base = env::current_dir()?;
-
-    let sourcemap_path = PathBuf::from(matches.value_of("sourcemap").unwrap());
-    let bundle_path = PathBuf::from(matches.value_of("bundle").unwrap());
+    let sourcemap_path = &variant.sourcemap;
+    let bundle_path = &variant.bundle;
     let sourcemap_url = format!(
         "~/{}",
         sourcemap_path.file_name().unwrap().to_string_lossy()
     );
     let bundle_url = format!("~/{}", bundle_path.file_name().unwrap().to_string_lossy());
 
-    info!(
-        "Issuing a command for Organization: {} Project: {}",
-        org, project
-    );
-
     println!("Processing react-native sourcemaps for Sentry upload.");
     info!("  bundle path: {}", bundle_path.display());
     info!("  sourcemap path: {}", sourcemap_path.display());
@@ -83,10 +157,10 @@ pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
     )?;
     processor.add(
         &sourcemap_url,
-        ReleaseFileSearch::collect_file(sourcemap_path)?,
+        ReleaseFileSearch::collect_file(sourcemap_path.clone())?,
     )?;
 
-    if let Ok(ram_bundle) = RamBundle::parse_unbundle_from_path(&bundle_path) {
+    if let Ok(ram_bundle) = RamBundle::parse_unbundle_from_path(bundle_path) {
         debug!("File RAM bundle found, extracting its contents...");
         processor.unpack_ram_bundle(&ram_bundle, &bundle_url)?;
     } else {
@@ -97,27 +171,105 @@ pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
     processor.add_sourcemap_references()?;
 
     let release = api.new_release(
-        &org,
+        org,
         &NewRelease {
-            version: matches.value_of("release").unwrap().to_string(),
+            version: variant.release.clone(),
             projects: vec![project.to_string()],
             ..Default::default()
         },
     )?;
 
-    for dist in matches.values_of("dist").unwrap() {
-        println!(
-            "Uploading sourcemaps for release {} distribution {}",
-            &release.version, dist
-        );
-
-        processor.upload(&UploadContext {
-            org: &org,
-            project: Some(&project),
-            release: &release.version,
-            dist: Some(dist),
-            wait: matches.is_present("wait"),
-        })?;
+    let context = UploadContext {
+        org,
+        project: Some(project),
+        release: &release.version,
+        dist: None,
+        wait,
+    };
+    if variant.dists.is_empty() {
+        println!("Uploading sourcemaps for release {}", &release.version);
+        processor.upload(&context)?;
+    } else {
+        let dists: Vec<_> = variant.dists.iter().map(String::as_str).collect();
+        for dist in &dists {
+            println!(
+                "Uploading sourcemaps for release {} distribution {}",
+                &release.version, dist
+            );
+        }
+        processor.upload_to_dists(&context, &dists)?;
+    }
+
+    Ok(processor.size_report())
+}
+
+/// Prints and optionally sends the size report of an uploaded variant.
+fn handle_size_report(
+    matches: &ArgMatches<'_>,
+    dsn: Option<&Dsn>,
+    variant: &Variant,
+    report: &SizeReport,
+) {
+    if matches.is_present("size_report") || dsn.is_some() {
+        report.print();
+    }
+    if let Some(dsn) = dsn {
+        let id = report.send(dsn.clone(), Some(&variant.release));
+        println!("Sent size report {}", id);
+    }
+}
+
+pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let config = Config::current();
+    let (org, project) = config.get_org_and_project(matches)?;
+    let api = Api::current();
+    let wait = matches.is_present("wait");
+    let dsn = if matches.is_present("send_size_report") {
+        Some(config.get_dsn()?)
+    } else {
+        None
+    };
+
+    info!(
+        "Issuing a command for Organization: {} Project: {}",
+        org, project
+    );
+
+    let variants_path = match matches.value_of("variants") {
+        Some(path) => Path::new(path),
+        None => {
+            let variant = Variant {
+                name: String::new(),
+                bundle: PathBuf::from(matches.value_of("bundle").unwrap()),
+                sourcemap: PathBuf::from(matches.value_of("sourcemap").unwrap()),
+                release: matches.value_of("release").unwrap().to_string(),
+                dists: matches
+                    .values_of("dist")
+                    .unwrap()
+                    .map(str::to_string)
+                    .collect(),
+            };
+            let report = upload_variant(&api, &org, &project, &variant, wait)?;
+            handle_size_report(matches, dsn.as_ref(), &variant, &report);
+            return Ok(());
+        }
+    };
+
+    let mut variants = load_variants(variants_path)?;
+    if let Some(names) = matches.values_of("variant") {
+        let names: Vec<_> = names.collect();
+        for name in &names {
+            if !variants.iter().any(|variant| variant.name == *name) {
+                bail!("Variant {} not found in {}", name, variants_path.display());
+            }
+        }
+        variants.retain(|variant| names.contains(&variant.name.as_str()));
+    }
+
+    for variant in &variants {
+        println!("Uploading variant {}", variant.name);
+        let report = upload_variant(&api, &org, &project, variant, wait)?;
+        handle_size_report(matches, dsn.as_ref(), variant, &report);
     }
 
     Ok(())
@@ -19,7 +19,7 @@ use crate::utils::file_upload::UploadContext;
 use crate::utils::fs::TempFile;
 use crate::utils::sourcemaps::SourceMapProcessor;
 use crate::utils::system::propagate_exit_status;
-use crate::utils::xcode::{InfoPlist, MayDetach};
+use crate::utils::xcode::{InfoPlist, MayDetach, XcodeIssues};
 
 #[derive(Serialize, Deserialize, Default, Debug)]
 struct SourceMapReport {
@@ -66,7 +66,8 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
                      detach and continue in the background.  When an error happens, \
                      a dialog is shown.  If this parameter is passed, Xcode will wait \
                      for the process to finish before the build finishes and output \
-                     will be shown in the Xcode build output.",
+                     will be shown in the Xcode build output.  Progress and problems \
+                     of the upload are then also reported as Xcode build issues.",
                 ),
         )
         .arg(
@@ -79,6 +80,30 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
                      to use.  By default the bundled build script is used.",
                 ),
         )
+        .arg(
+            Arg::with_name("bundler_command")
+                .long("bundler-command")
+                .value_name("COMMAND")
+                .help(
+                    "The bundler command invoked by the build script.{n}\
+                     Use this for packagers that do not use the `bundle` command \
+                     of the React Native CLI, e.g. `webpack-bundle` for Re.Pack or \
+                     `export:embed` for Expo.  This is exported to the build script \
+                     as BUNDLE_COMMAND.",
+                ),
+        )
+        .arg(
+            Arg::with_name("bundler_args")
+                .long("bundler-args")
+                .value_name("ARG")
+                .multiple(true)
+                .number_of_values(1)
+                .allow_hyphen_values(true)
+                .help(
+                    "Additional arguments to pass to the bundler command.  \
+                     Can be supplied multiple times.",
+                ),
+        )
         .arg(
             Arg::with_name("dist")
                 .long("dist")
@@ -111,6 +136,19 @@ fn find_node() -> String {
 }
 
 pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    // Only report build issues if Xcode waits for us, otherwise there is
+    // nobody to read them.
+    let issues = XcodeIssues::new(
+        matches.is_present("force_foreground") && env::var_os("XCODE_VERSION_ACTUAL").is_some(),
+    );
+    let rv = execute_internal(matches, &issues);
+    if let Err(ref err) = rv {
+        issues.error(err);
+    }
+    rv
+}
+
+fn execute_internal(matches: &ArgMatches<'_>, issues: &XcodeIssues) -> Result<(), Error> {
     let config = Config::current();
     let (org, project) = config.get_org_and_project(matches)?;
     let api = Api::current();
@@ -154,7 +192,7 @@ pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
     if !should_wrap && fetch_url.is_none() {
         info!("Running in debug mode, skipping script wrapping.");
         let rv = process::Command::new(&script).spawn()?.wait()?;
-        propagate_exit_status(rv);
+        propagate_exit_status(rv)?;
         return Ok(());
     }
 
@@ -222,7 +260,19 @@ pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
         // With that we we then have all the information we need to invoke the
         // upload process.
         } else {
-            let rv = process::Command::new(&script)
+            let mut cmd = process::Command::new(&script);
+            if let Some(bundle_command) = matches.value_of("bundler_command") {
+                cmd.env("BUNDLE_COMMAND", bundle_command)
+                    .env("SENTRY_RN_BUNDLE_COMMAND", bundle_command);
+            }
+            if let Some(bundler_args) = matches.values_of("bundler_args") {
+                let bundler_args: Vec<_> = bundler_args.collect();
+                cmd.env(
+                    "SENTRY_RN_BUNDLER_ARGS",
+                    serde_json::to_string(&bundler_args)?,
+                );
+            }
+            let rv = cmd
                 .env("NODE_BINARY", env::current_exe()?.to_str().unwrap())
                 .env("SENTRY_RN_REAL_NODE_BINARY", &node)
                 .env(
@@ -232,7 +282,7 @@ pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
                 .env("__SENTRY_RN_WRAP_XCODE_CALL", "1")
                 .spawn()?
                 .wait()?;
-            propagate_exit_status(rv);
+            propagate_exit_status(rv)?;
 
             if !matches.is_present("force_foreground") {
                 md.may_detach()?;
@@ -246,7 +296,7 @@ pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
                 panic!("{}", err_msg);
             });
             if report.bundle_path.is_none() || report.sourcemap_path.is_none() {
-                println!("Warning: build produced no sourcemaps.");
+                issues.warning("build produced no sourcemaps.");
                 return Ok(());
             }
 
@@ -291,6 +341,10 @@ pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
             },
         )?;
 
+        issues.note(&format!(
+            "Uploading sourcemaps for release {}",
+            release.version
+        ));
         match matches.values_of("dist") {
             None => {
                 processor.upload(&UploadContext {
@@ -302,17 +356,22 @@ pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
                 })?;
             }
             Some(dists) => {
-                for dist in dists {
-                    processor.upload(&UploadContext {
+                processor.upload_to_dists(
+                    &UploadContext {
                         org: &org,
                         project: Some(&project),
                         release: &release.version,
-                        dist: Some(dist),
+                        dist: None,
                         wait: matches.is_present("wait"),
-                    })?;
-                }
+                    },
+                    &dists.collect::<Vec<_>>(),
+                )?;
             }
         }
+        issues.note(&format!(
+            "Uploaded sourcemaps for release {}",
+            release.version
+        ));
 
         Ok(())
     })
@@ -322,8 +381,20 @@ pub fn wrap_call() -> Result<(), Error> {
     let mut args: Vec<_> = env::args().skip(1).collect();
     let mut bundle_path = None;
     let mut sourcemap_path = None;
+    let bundle_command = env::var("SENTRY_RN_BUNDLE_COMMAND").ok();
+
+    let is_bundle = args.len() > 1
+        && match bundle_command {
+            Some(ref command) => &args[1] == command,
+            None => args[1] == "bundle" || args[1] == "ram-bundle",
+        };
+
+    if is_bundle {
+        if let Ok(bundler_args) = env::var("SENTRY_RN_BUNDLER_ARGS") {
+            let bundler_args: Vec<String> = serde_json::from_str(&bundler_args)?;
+            args.extend(bundler_args);
+        }
 
-    if args.len() > 1 && (args[1] == "bundle" || args[1] == "ram-bundle") {
         let mut iter = args.iter().fuse();
         while let Some(item) = iter.next() {
             if item == "--sourcemap-output" {
@@ -358,7 +429,7 @@ pub fn wrap_call() -> Result<(), Error> {
         .args(&args)
         .spawn()?
         .wait()?;
-    propagate_exit_status(rv);
+    propagate_exit_status(rv)?;
 
     let mut f = fs::File::create(env::var("SENTRY_RN_SOURCEMAP_REPORT").unwrap())?;
     serde_json::to_writer(&mut f, &sourcemap_report)?;
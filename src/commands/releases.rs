@@ -1,36 +1,64 @@
 //! Implements a command for managing releases.
+use std::cmp::Reverse;
 use std::collections::HashSet;
 use std::ffi::OsStr;
+use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use chrono::{DateTime, Duration, Utc};
 use clap::{App, AppSettings, Arg, ArgMatches};
-use failure::{bail, err_msg, Error};
-use glob::{glob_with, MatchOptions};
+use console::style;
+use failure::{bail, err_msg, format_err, Error, ResultExt};
+use glob::{glob_with, MatchOptions, Pattern};
 use indicatif::HumanBytes;
 use lazy_static::lazy_static;
 use log::{debug, warn};
 use regex::Regex;
+use serde::Serialize;
+use serde_json::json;
 use symbolic::debuginfo::sourcebundle::SourceFileType;
 
 use crate::api::{
-    Api, Deploy, FileContents, NewRelease, NoneReleaseInfo, OptionalReleaseInfo, ProgressBarMode,
+    Api, CommitFileChange, Deploy, FileContents, IssueChanges, IssueFilter, IssueQuery, NewRelease,
+    NoneReleaseInfo, OptionalReleaseInfo, ProgressBarMode, ReleaseCommit, ReleaseInfo,
     ReleaseStatus, UpdatedRelease,
 };
 use crate::config::Config;
+use crate::utils::annotations::{add_summary, release_link, tag_build};
+use crate::utils::archive::is_archive;
 use crate::utils::args::{
-    get_timestamp, validate_int, validate_project, validate_timestamp, ArgExt,
+    get_byte_size, get_timestamp, validate_byte_size, validate_glob, validate_int,
+    validate_project, validate_timestamp, validate_version, ArgExt,
+};
+use crate::utils::build_manifest::read_build_manifest;
+use crate::utils::cancel;
+use crate::utils::changelog::{render_changelog, ChangelogGrouping};
+use crate::utils::ci::detect_build_url;
+use crate::utils::file_search::{ignores_from_args, ReleaseFileSearch};
+use crate::utils::file_upload::{
+    read_upload_plan, write_checksums_manifest, write_upload_plan, ArtifactChecksum, ReleaseFile,
+    ReleaseFileUpload, ReleaseFiles, UploadContext, UploadLimits,
 };
-use crate::utils::file_search::ReleaseFileSearch;
-use crate::utils::file_upload::{ReleaseFile, ReleaseFileUpload, UploadContext};
 use crate::utils::formatting::{HumanDuration, Table};
+use crate::utils::fs::{normalize_path, path_as_url, TempDir};
+use crate::utils::gitlab;
+use crate::utils::machine;
 use crate::utils::releases::detect_release_name;
+use crate::utils::remote_source::{fetch_source, is_remote};
+use crate::utils::sbom::Sbom;
 use crate::utils::sourcemaps::SourceMapProcessor;
 use crate::utils::system::QuietExit;
 use crate::utils::vcs::{
-    find_heads, generate_patch_set, get_commits_from_git, get_repo_from_remote, CommitSpec,
+    filter_commits_by_paths, find_heads, find_previous_release, generate_patch_set,
+    get_commits_from_git, get_commits_from_provider, get_repo_from_remote, CommitSpec,
+    PreviousReleaseStrategy,
 };
+use crate::utils::webhooks::Webhook;
+
+/// The release file `set-dependencies` stores the dependencies in.
+const DEPENDENCIES_FILE_NAME: &str = "~/dependencies.json";
 
 struct ReleaseContext<'a> {
     pub api: Arc<Api>,
@@ -89,7 +117,9 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
                 .help("Optional URL to the release for information purposes."))
             .arg(Arg::with_name("finalize")
                  .long("finalize")
-                 .help("Immediately finalize the release. (sets it to released)")))
+                 .help("Immediately finalize the release. (sets it to released)"))
+            .idempotent_arg()
+            .args(&ci_report_args()))
         .subcommand(App::new("propose-version")
             .about("Propose a version name for a new release."))
         .subcommand(App::new("set-commits")
@@ -120,6 +150,48 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
                         This requires that the command is run from within a git repository.  \
                         sentry-cli will then automatically find remotely configured \
                         repositories and discover commits."))
+            .arg(Arg::with_name("repo")
+                .long("repo")
+                .value_name("REPO")
+                .requires("range")
+                .conflicts_with_all(&["auto", "clear", "commits", "local"])
+                .help("Fetch the commits from the API of the repository's provider \
+                        instead of a local checkout.{n}\
+                        The value is the name of the repository in the Sentry config. \
+                        Supported are GitHub and GitLab, using the `GITHUB_TOKEN` and \
+                        `GITLAB_TOKEN` tokens."))
+            .arg(Arg::with_name("range")
+                .long("range")
+                .value_name("FROM..TO")
+                .requires("repo")
+                .help("The range of commits to fetch with `--repo`."))
+            .arg(Arg::with_name("paths")
+                .long("paths")
+                .value_name("PATTERN")
+                .multiple(true)
+                .number_of_values(1)
+                .conflicts_with_all(&["clear", "commits"])
+                .validator(validate_glob)
+                .help("Only associate commits that touch files matching the given glob \
+                        patterns, e.g. `services/payments/**`.{n}\
+                        This requires the commits to be read from a local git \
+                        repository or with `--repo`, so `--auto` falls back to the \
+                        local git tree when paths are given."))
+            .arg(Arg::with_name("previous")
+                .long("previous")
+                .value_name("STRATEGY")
+                .possible_values(&["default", "release-on-same-branch", "latest-finalized"])
+                .conflicts_with_all(&["clear", "repo"])
+                .help("How to find the previous release whose commit the new commits start at.{n}\
+                       `default` lets Sentry pick the previous release with commits, \
+                       `release-on-same-branch` picks the most recent release whose commit \
+                       is an ancestor of HEAD and `latest-finalized` picks the most recent \
+                       finalized release."))
+            .arg(Arg::with_name("previous-commit")
+                .long("previous-commit")
+                .value_name("SHA")
+                .conflicts_with_all(&["clear", "repo", "previous"])
+                .help("Start the commits of the release after this commit."))
             .arg(Arg::with_name("initial-depth")
                 .conflicts_with("auto")
                 .long("initial-depth")
@@ -145,6 +217,19 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
                         the current commit of the repository at the given PATH is \
                         assumed.  To override the revision `@REV` can be appended \
                         which will force the revision to a certain value.")))
+        .subcommand(App::new("set-dependencies")
+            .about("Store the dependencies of a release from a software bill of materials.")
+            .long_about("Store the dependencies of a release from a software bill of materials.{n}{n}\
+                         The packages listed in a CycloneDX or SPDX document are stored \
+                         with the release as `~/dependencies.json`, replacing the \
+                         dependencies set before.  This makes it possible to find the \
+                         releases that ship a vulnerable version of a package.")
+            .version_arg(1)
+            .arg(Arg::with_name("sbom")
+                .long("sbom")
+                .value_name("PATH")
+                .required(true)
+                .help("The path to a CycloneDX or SPDX document, as JSON or SPDX tag-value.")))
         .subcommand(App::new("delete")
             .about("Delete a release.")
             .version_arg(1))
@@ -166,7 +251,9 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
                  .long("released")
                  .validator(validate_timestamp)
                  .value_name("TIMESTAMP")
-                 .help("Set the release time. [defaults to the current time]")))
+                 .help("Set the release time. [defaults to the current time]"))
+            .arg(webhook_arg())
+            .args(&ci_report_args()))
         .subcommand(App::new("list")
             .about("List the most recent releases.")
             .arg(Arg::with_name("no_abbrev")
@@ -195,6 +282,45 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
                 .help("Do not print any output.{n}If this is passed the command can be \
                        used to determine if a release already exists.  The exit status \
                        will be 0 if the release exists or 1 otherwise.")))
+        .subcommand(App::new("changelog")
+            .about("Render a Markdown changelog from the commits of releases.")
+            .arg(Arg::with_name("to")
+                .long("to")
+                .value_name("VERSION")
+                .required(true)
+                .validator(validate_version)
+                .help("The release to render the changelog for."))
+            .arg(Arg::with_name("from")
+                .long("from")
+                .value_name("VERSION")
+                .validator(validate_version)
+                .help("Include the commits of all releases after this one.{n}\
+                       By default only the commits of the `--to` release are included."))
+            .arg(Arg::with_name("group_by")
+                .long("group-by")
+                .value_name("GROUPING")
+                .possible_values(&["type", "author"])
+                .default_value("type")
+                .help("Group by conventional commit type or by author."))
+            .arg(Arg::with_name("output")
+                .long("output")
+                .short("O")
+                .value_name("PATH")
+                .help("Write the changelog to a file instead of stdout.")))
+        .subcommand(App::new("suspects")
+            .about("Show the commits and files associated with a release.")
+            .long_about("Show the commits and files associated with a release.{n}{n}\
+                         These are the commits Sentry uses to find suspect commits of issues.  \
+                         Run this before finalizing a release to catch wrong commit ranges.")
+            .version_arg(1)
+            .arg(Arg::with_name("max_commits")
+                .long("max-commits")
+                .value_name("COUNT")
+                .validator(validate_int)
+                .help("Warn if more commits are associated with the release. [defaults to 250]"))
+            .arg(Arg::with_name("json")
+                .long("json")
+                .help("Print the commits and files as JSON.")))
         .subcommand(App::new("files")
             .about("Manage release artifacts.")
             .setting(AppSettings::SubcommandRequiredElseHelp)
@@ -221,6 +347,23 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
                 .arg(Arg::with_name("wait")
                     .long("wait")
                     .help("Wait for the server to fully process uploaded files."))
+                .args(&upload_limit_args())
+                .arg(Arg::with_name("checksums_out")
+                    .long("checksums-out")
+                    .value_name("PATH")
+                    .conflicts_with("plan")
+                    .help("Write the name, size, SHA256, release and dist of every \
+                           uploaded file to a JSON manifest at this path, e.g. to sign \
+                           it for supply-chain attestation."))
+                .arg(plan_arg())
+                .arg(atomic_arg())
+                .arg(Arg::with_name("apply")
+                    .long("apply")
+                    .value_name("DIR")
+                    .conflicts_with_all(&["path", "plan", "dist"])
+                    .help("Upload the files of a plan written with --plan.{n}\
+                           The release has to match the one of the plan, the \
+                           distribution is taken from the plan."))
                 .arg(Arg::with_name("headers")
                     .long("header")
                     .short("H")
@@ -228,11 +371,29 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
                     .multiple(true)
                     .number_of_values(1)
                     .help("Store a header with this file."))
+                .arg(Arg::with_name("content_type")
+                    .long("content-type")
+                    .value_name("TYPE")
+                    .help("Store a Content-Type header with this file, \
+                           e.g. application/wasm."))
+                .arg(Arg::with_name("header_rules")
+                    .long("header-rule")
+                    .value_name("GLOB=KEY:VALUE")
+                    .multiple(true)
+                    .number_of_values(1)
+                    .help("Store a header only with the files whose path matches the \
+                           glob, e.g. '*.wasm=Content-Type:application/wasm'.{n}\
+                           Paths are relative to the uploaded directory.  Rules take \
+                           precedence over --header and --content-type and later rules \
+                           over earlier ones."))
                 .arg(Arg::with_name("path")
                     .value_name("PATH")
                     .index(1)
-                    .required(true)
-                    .help("The path to the file or directory to upload."))
+                    .required_unless("apply")
+                    .help("The path to the file or directory to upload.  ZIP archives \
+                           and tarballs are uploaded like a directory of their files.  \
+                           This can also be a s3://, gs:// or http(s):// URL, a bucket \
+                           URL ending with a slash uploads everything below it."))
                 .arg(Arg::with_name("name")
                     .index(2)
                     .value_name("NAME")
@@ -246,18 +407,26 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
                     .long("url-suffix")
                     .value_name("SUFFIX")
                     .help("The URL suffix to append to all filenames."))
-                .arg(Arg::with_name("ignore")
-                    .long("ignore")
-                    .short("i")
-                    .value_name("IGNORE")
-                    .multiple(true)
-                    .help("Ignores all files and folders matching the given glob"))
-                .arg(Arg::with_name("ignore_file")
-                    .long("ignore-file")
-                    .short("I")
-                    .value_name("IGNORE_FILE")
-                    .help("Ignore all files and folders specified in the given \
-                           ignore file, e.g. .gitignore."))
+                .arg(Arg::with_name("url_case")
+                    .long("url-case")
+                    .value_name("CASE")
+                    .possible_values(&["preserve", "lower"])
+                    .default_value("preserve")
+                    .help("How to case the file paths in URLs.  'lower' lowercases \
+                           them, for servers that serve files case-insensitively.  \
+                           Prefix and suffix are kept as given."))
+                .arg(Arg::with_name("no_follow_symlinks")
+                    .long("no-follow-symlinks")
+                    .overrides_with("follow_symlinks")
+                    .help("Skip symbolic links instead of uploading the files and \
+                           folders they point to."))
+                .arg(Arg::with_name("follow_symlinks")
+                    .long("follow-symlinks")
+                    .overrides_with("no_follow_symlinks")
+                    .help("Upload the files and folders symbolic links point to.  \
+                           This is the default, broken links and link cycles are \
+                           skipped with a warning."))
+                .ignore_args()
                 .arg(Arg::with_name("extensions")
                     .long("ext")
                     .short("x")
@@ -272,9 +441,19 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
                 .arg(Arg::with_name("paths")
                     .value_name("PATHS")
                     .index(1)
-                    .required_unless_one(&["bundle", "bundle_sourcemap"])
+                    .required_unless_one(&["bundle", "bundle_sourcemap", "from_build_manifest"])
                     .multiple(true)
-                    .help("The files to upload."))
+                    .help("The files to upload.  Directories are searched, as are ZIP \
+                           archives and tarballs (.tar, .tar.gz, .tgz)."))
+                .arg(Arg::with_name("from_build_manifest")
+                    .long("from-build-manifest")
+                    .value_name("PATH")
+                    .conflicts_with_all(&["paths", "bundle", "bundle_sourcemap"])
+                    .help("Upload the output directories of the apps in an Nx project graph \
+                           (`nx graph --affected --file`) or a Turborepo task list \
+                           (`turbo run build --dry=json`).{n}Every app is uploaded to its \
+                           own release named `<app>@<VERSION>`.  The output directories \
+                           are resolved against the current directory."))
                 .arg(Arg::with_name("url_prefix")
                     .short("u")
                     .long("url-prefix")
@@ -284,6 +463,25 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
                     .long("url-suffix")
                     .value_name("SUFFIX")
                     .help("The URL suffix to append to all filenames."))
+                .arg(Arg::with_name("url_case")
+                    .long("url-case")
+                    .value_name("CASE")
+                    .possible_values(&["preserve", "lower"])
+                    .default_value("preserve")
+                    .help("How to case the file paths in URLs.  'lower' lowercases \
+                           them, for servers that serve files case-insensitively.  \
+                           Prefix and suffix are kept as given."))
+                .arg(Arg::with_name("no_follow_symlinks")
+                    .long("no-follow-symlinks")
+                    .overrides_with("follow_symlinks")
+                    .help("Skip symbolic links instead of uploading the files and \
+                           folders they point to."))
+                .arg(Arg::with_name("follow_symlinks")
+                    .long("follow-symlinks")
+                    .overrides_with("no_follow_symlinks")
+                    .help("Upload the files and folders symbolic links point to.  \
+                           This is the default, broken links and link cycles are \
+                           skipped with a warning."))
                 .arg(Arg::with_name("dist")
                     .long("dist")
                     .short("d")
@@ -295,6 +493,16 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
                 .arg(Arg::with_name("wait")
                     .long("wait")
                     .help("Wait for the server to fully process uploaded files."))
+                .args(&upload_limit_args())
+                .arg(Arg::with_name("checksums_out")
+                    .long("checksums-out")
+                    .value_name("PATH")
+                    .conflicts_with("plan")
+                    .help("Write the name, size, SHA256, release and dist of every \
+                           uploaded file to a JSON manifest at this path, e.g. to sign \
+                           it for supply-chain attestation."))
+                .arg(plan_arg().conflicts_with("from_build_manifest"))
+                .arg(atomic_arg())
                 .arg(Arg::with_name("no_sourcemap_reference")
                     .long("no-sourcemap-reference")
                     .help("Disable emitting of automatic sourcemap references.{n}\
@@ -329,18 +537,7 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
                     .help("Similar to --strip-prefix but strips the most common \
                            prefix on all sources references.")
                     .conflicts_with("no_rewrite"))
-                .arg(Arg::with_name("ignore")
-                    .long("ignore")
-                    .short("i")
-                    .value_name("IGNORE")
-                    .multiple(true)
-                    .help("Ignores all files and folders matching the given glob"))
-                .arg(Arg::with_name("ignore_file")
-                    .long("ignore-file")
-                    .short("I")
-                    .value_name("IGNORE_FILE")
-                    .help("Ignore all files and folders specified in the given \
-                           ignore file, e.g. .gitignore."))
+                .ignore_args()
                 .arg(Arg::with_name("bundle")
                     .long("bundle")
                     .value_name("BUNDLE")
@@ -382,6 +579,7 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
         .subcommand(App::new("deploys")
             .about("Manage release deployments.")
             .setting(AppSettings::SubcommandRequiredElseHelp)
+            .setting(AppSettings::SubcommandsNegateReqs)
             .version_arg(1)
             .subcommand(App::new("new")
                 .about("Creates a new release deployment.")
@@ -401,7 +599,12 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
                      .long("url")
                      .short("u")
                      .value_name("URL")
-                     .help("Optional URL that points to the deployment."))
+                     .help("Optional URL that points to the deployment.{n}\
+                            Defaults to the URL of the CI build if one is detected."))
+                .arg(Arg::with_name("no_build_url")
+                     .long("no-build-url")
+                     .conflicts_with("url")
+                     .help("Do not attach the URL of the CI build to the deployment."))
                 .arg(Arg::with_name("started")
                      .long("started")
                      .value_name("TIMESTAMP")
@@ -418,9 +621,182 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
                      .value_name("SECONDS")
                      .validator(validate_int)
                      .help("Optional deployment duration in seconds.{n}\
-                            This can be specified alternatively to `--started` and `--finished`.")))
+                            This can be specified alternatively to `--started` and `--finished`."))
+                .idempotent_arg()
+                .arg(webhook_arg()))
             .subcommand(App::new("list")
-                .about("List all deployments of a release.")))
+                .about("List all deployments of a release.")
+                .arg(Arg::with_name("env")
+                     .long("env")
+                     .short("e")
+                     .value_name("ENV")
+                     .multiple(true)
+                     .number_of_values(1)
+                     .help("Only list deployments to the given environments."))
+                .arg(Arg::with_name("since")
+                     .long("since")
+                     .value_name("TIMESTAMP")
+                     .validator(validate_timestamp)
+                     .help("Only list deployments that finished after this timestamp."))
+                .arg(Arg::with_name("until")
+                     .long("until")
+                     .value_name("TIMESTAMP")
+                     .validator(validate_timestamp)
+                     .help("Only list deployments that finished before this timestamp."))
+                .arg(Arg::with_name("json")
+                     .long("json")
+                     .help("Print the deployments as JSON.")))
+            .subcommand(App::new("latest")
+                .about("Print the release of the most recent deployment to an environment.")
+                .long_about("Print the release of the most recent deployment to an environment.{n}{n}\
+                             This does not take a release version and is meant for scripts, \
+                             e.g. to find the release to roll back to: \
+                             `sentry-cli releases deploys latest --env production`")
+                .arg(Arg::with_name("env")
+                     .long("env")
+                     .short("e")
+                     .value_name("ENV")
+                     .required(true)
+                     .help("The environment to look up."))
+                .arg(Arg::with_name("json")
+                     .long("json")
+                     .help("Print the release and deployment as JSON.")))
+            .subcommand(App::new("rollback")
+                .about("Roll an environment back to the previously deployed release.")
+                .long_about("Roll an environment back to the previously deployed release.{n}{n}\
+                             This finds the release of the most recent deployment to the \
+                             environment and records a new deployment of the release that was \
+                             deployed before it.  It does not take a release version.")
+                .arg(Arg::with_name("env")
+                     .long("env")
+                     .short("e")
+                     .value_name("ENV")
+                     .required(true)
+                     .help("The environment to roll back."))
+                .arg(Arg::with_name("to")
+                     .long("to")
+                     .value_name("VERSION")
+                     .help("Roll back to this release instead of the previously deployed one."))
+                .arg(Arg::with_name("reopen_resolved")
+                     .long("reopen-resolved")
+                     .help("Mark issues that were resolved in the rolled back release and \
+                            seen in the release rolled back to as unresolved again, as \
+                            their fixes are no longer deployed.{n}This requires a project."))
+                .arg(Arg::with_name("dry_run")
+                     .long("dry-run")
+                     .help("Print what would change without changing anything."))))
+}
+
+fn webhook_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("webhook")
+        .long("webhook")
+        .value_name("URL")
+        .help(
+            "Post a JSON notification to this URL afterwards.{n}\
+             Defaults to `SENTRY_WEBHOOK_URL`.  The `X-Sentry-Cli-Timestamp` header \
+             holds the Unix time it was sent at.  If `SENTRY_WEBHOOK_SECRET` is set, \
+             `TIMESTAMP.BODY` is signed with HMAC-SHA256 in the \
+             `X-Sentry-Cli-Signature` header.",
+        )
+}
+
+fn atomic_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("atomic")
+        .long("atomic")
+        .conflicts_with("plan")
+        .help(
+            "Make the files visible only once all of them are uploaded and \
+             processed, then finalize the release.{n}If the upload fails or is \
+             interrupted, none of the files are visible and the release is \
+             not finalized.  Fails if the server can only receive files one \
+             by one.",
+        )
+}
+
+fn plan_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("plan").long("plan").value_name("DIR").help(
+        "Write the processed files with their URLs, headers and checksums \
+             to this directory instead of uploading them.{n}The plan can be \
+             uploaded later, e.g. from another machine, with \
+             `sentry-cli releases files VERSION upload --apply DIR`.",
+    )
+}
+
+fn upload_limit_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("max_file_size")
+            .long("max-file-size")
+            .value_name("SIZE")
+            .validator(validate_byte_size)
+            .help(
+                "Skip files larger than this, e.g. 10MB.  Defaults to the \
+                 upload.max_file_size setting.",
+            ),
+        Arg::with_name("max_total_size")
+            .long("max-total-size")
+            .value_name("SIZE")
+            .validator(validate_byte_size)
+            .help(
+                "Skip files once all files together exceed this size, e.g. 500MB.  \
+                 Defaults to the upload.max_total_size setting.",
+            ),
+        Arg::with_name("fail_on_oversize")
+            .long("fail-on-oversize")
+            .help("Fail listing the files that exceed a size limit instead of skipping them."),
+    ]
+}
+
+fn get_upload_limits_from_args(matches: &ArgMatches<'_>) -> Result<UploadLimits, Error> {
+    let config = Config::current();
+    Ok(UploadLimits {
+        max_file_size: match matches.value_of("max_file_size") {
+            Some(size) => Some(get_byte_size(size)?),
+            None => config.get_max_upload_file_size()?,
+        },
+        max_total_size: match matches.value_of("max_total_size") {
+            Some(size) => Some(get_byte_size(size)?),
+            None => config.get_max_upload_total_size()?,
+        },
+        fail: matches.is_present("fail_on_oversize"),
+    })
+}
+
+fn ci_report_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("tag_build")
+            .long("tag-build")
+            .help("Tag the Azure Pipelines build with the name of the release."),
+        Arg::with_name("dotenv")
+            .long("dotenv")
+            .value_name("PATH")
+            .help(
+                "Write SENTRY_RELEASE, SENTRY_ORG and SENTRY_RELEASE_URL to a dotenv \
+                 file for GitLab's `artifacts:reports:dotenv`.",
+            ),
+        Arg::with_name("evidence")
+            .long("evidence")
+            .value_name("PATH")
+            .help("Write a JSON description of the release for GitLab's release evidence."),
+    ]
+}
+
+/// Writes the GitLab reports requested with `--dotenv` and `--evidence` and
+/// tags the build for `--tag-build`.
+fn write_ci_reports(
+    matches: &ArgMatches<'_>,
+    org: &str,
+    release: &ReleaseInfo,
+) -> Result<(), Error> {
+    if let Some(path) = matches.value_of("dotenv") {
+        gitlab::write_dotenv(Path::new(path), org, release)?;
+    }
+    if let Some(path) = matches.value_of("evidence") {
+        gitlab::write_evidence(Path::new(path), org, release)?;
+    }
+    if matches.is_present("tag_build") {
+        tag_build(&release.version);
+    }
+    Ok(())
 }
 
 fn strip_sha(sha: &str) -> &str {
@@ -434,23 +810,39 @@ fn strip_sha(sha: &str) -> &str {
     }
 }
 
-#[cfg(windows)]
-fn path_as_url(path: &Path) -> String {
-    path.display().to_string().replace("\\", "/")
-}
+fn execute_new<'a>(ctx: &ReleaseContext<'_>, matches: &ArgMatches<'a>) -> Result<(), Error> {
+    let version = matches.value_of("version").unwrap();
+    let projects = ctx.get_projects(matches)?;
+    let url = matches.value_of("url").map(str::to_owned);
+    let idempotent = matches.is_present("idempotent");
 
-#[cfg(not(windows))]
-fn path_as_url(path: &Path) -> String {
-    path.display().to_string()
-}
+    let existing = if idempotent {
+        ctx.api.get_release(ctx.get_org()?, None, version)?
+    } else {
+        None
+    };
+    if let Some(existing) = existing.as_ref().filter(|existing| {
+        projects
+            .iter()
+            .all(|project| existing.projects.iter().any(|p| &p.slug == project))
+            && (url.is_none() || existing.url == url)
+            && (!matches.is_present("finalize") || existing.date_released.is_some())
+    }) {
+        println!(
+            "Release {} already exists, nothing changed.",
+            existing.version
+        );
+        machine::set_result("release", existing.version.clone());
+        machine::set_result("changed", false);
+        return write_ci_reports(matches, ctx.get_org()?, existing);
+    }
 
-fn execute_new<'a>(ctx: &ReleaseContext<'_>, matches: &ArgMatches<'a>) -> Result<(), Error> {
     let info_rv = ctx.api.new_release(
         ctx.get_org()?,
         &NewRelease {
-            version: matches.value_of("version").unwrap().to_owned(),
-            projects: ctx.get_projects(matches)?,
-            url: matches.value_of("url").map(str::to_owned),
+            version: version.to_owned(),
+            projects,
+            url,
             date_started: Some(Utc::now()),
             date_released: if matches.is_present("finalize") {
                 Some(Utc::now())
@@ -459,7 +851,20 @@ fn execute_new<'a>(ctx: &ReleaseContext<'_>, matches: &ArgMatches<'a>) -> Result
             },
         },
     )?;
-    println!("Created release {}.", info_rv.version);
+    if existing.is_some() {
+        println!("Updated release {}.", info_rv.version);
+    } else {
+        println!("Created release {}.", info_rv.version);
+    }
+    machine::set_result("release", info_rv.version.clone());
+    if idempotent {
+        machine::set_result("changed", true);
+    }
+    write_ci_reports(matches, ctx.get_org()?, &info_rv)?;
+    add_summary(&format!(
+        "- Created release {}",
+        release_link(ctx.get_org()?, &info_rv.version)
+    ));
     Ok(())
 }
 
@@ -483,6 +888,22 @@ fn execute_finalize<'a>(ctx: &ReleaseContext<'_>, matches: &ArgMatches<'a>) -> R
         },
     )?;
     println!("Finalized release {}.", info_rv.version);
+    write_ci_reports(matches, ctx.get_org()?, &info_rv)?;
+
+    if let Some(webhook) = Webhook::from_matches(matches) {
+        webhook.send(
+            "release.finalized",
+            ctx.get_org()?,
+            json!({
+                "release": {
+                    "version": info_rv.version,
+                    "dateReleased": info_rv.date_released,
+                    "projects": info_rv.projects.iter().map(|p| &p.slug).collect::<Vec<_>>(),
+                }
+            }),
+        )?;
+    }
+
     Ok(())
 }
 
@@ -491,6 +912,50 @@ fn execute_propose_version() -> Result<(), Error> {
     Ok(())
 }
 
+fn execute_set_dependencies<'a>(
+    ctx: &ReleaseContext<'_>,
+    matches: &ArgMatches<'a>,
+) -> Result<(), Error> {
+    let version = matches.value_of("version").unwrap();
+    let org = ctx.get_org()?;
+    let project = ctx.get_project_default().ok();
+    let path = matches.value_of("sbom").unwrap();
+    let contents = fs::read(path).with_context(|_| format!("Could not read {}", path))?;
+    let sbom = Sbom::parse(&contents).with_context(|_| format!("Could not parse {}", path))?;
+
+    // release files cannot be overwritten, the old manifest has to go first
+    for artifact in ctx
+        .api
+        .list_release_files(org, project.as_deref(), version)?
+    {
+        if artifact.name == DEPENDENCIES_FILE_NAME {
+            ctx.api
+                .delete_release_file(org, project.as_deref(), version, &artifact.id)?;
+        }
+    }
+    let manifest = serde_json::to_vec(&sbom.to_manifest())?;
+    ctx.api.upload_release_file(
+        org,
+        project.as_deref(),
+        version,
+        &FileContents::FromBytes(&manifest),
+        DEPENDENCIES_FILE_NAME,
+        None,
+        Some(&[("Content-Type".to_string(), "application/json".to_string())]),
+        ProgressBarMode::Disabled,
+    )?;
+
+    machine::set_result("dependencies", sbom.dependencies.len() as u64);
+    println!(
+        "Stored {} dependencies of release {} ({} {}).",
+        sbom.dependencies.len(),
+        version,
+        sbom.format,
+        sbom.spec_version
+    );
+    Ok(())
+}
+
 fn execute_set_commits<'a>(
     ctx: &ReleaseContext<'_>,
     matches: &ArgMatches<'a>,
@@ -502,7 +967,51 @@ fn execute_set_commits<'a>(
     let mut commit_specs = vec![];
     let config = Config::current();
 
-    let heads = if repos.is_empty() {
+    let paths = matches
+        .values_of("paths")
+        .unwrap_or_default()
+        .map(Pattern::new)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if let Some(name) = matches.value_of("repo") {
+        let repo = match repos.iter().find(|r| r.name == name) {
+            Some(repo) => repo,
+            None => bail!("Unknown repo '{}'", name),
+        };
+        let commits = filter_commits_by_paths(
+            get_commits_from_provider(&ctx.api, repo, matches.value_of("range").unwrap())?,
+            &paths,
+        );
+        if commits.is_empty() && !matches.is_present("ignore-empty") {
+            bail!("No commits found. Change commits range or use --ignore-empty to allow empty patch sets.");
+        }
+
+        if let Ok(projects) = ctx.get_projects(matches) {
+            ctx.api.new_release(
+                org,
+                &NewRelease {
+                    version: version.into(),
+                    projects,
+                    ..Default::default()
+                },
+            )?;
+        }
+        ctx.api.update_release(
+            org,
+            version,
+            &UpdatedRelease {
+                commits: Some(commits),
+                ..Default::default()
+            },
+        )?;
+
+        println!("Success! Set commits for release {}.", version);
+        return Ok(());
+    }
+
+    // commits associated through refs are discovered by the server and
+    // cannot be filtered by path
+    let heads = if repos.is_empty() || !paths.is_empty() {
         None
     } else if matches.is_present("auto") {
         let commits = find_heads(None, &repos, Some(config.get_cached_vcs_remote()))?;
@@ -550,6 +1059,45 @@ fn execute_set_commits<'a>(
         )?;
     }
 
+    // Without a strategy, Sentry picks the previous release with commits.
+    let mut ask_sentry = false;
+    let previous = if let Some(commit) = matches.value_of("previous-commit") {
+        println!("Using previous commit {}.", strip_sha(commit));
+        Some(commit.to_string())
+    } else {
+        let strategy =
+            PreviousReleaseStrategy::parse(matches.value_of("previous").unwrap_or("default"))?;
+        if strategy == PreviousReleaseStrategy::Default {
+            ask_sentry = true;
+            None
+        } else {
+            let project = ctx.get_project_default().ok();
+            let releases = ctx.api.list_releases(org, project.as_deref())?;
+            let repo = git2::Repository::open_from_env().ok();
+            find_previous_release(repo.as_ref(), &releases, version, strategy)?.map(|previous| {
+                println!(
+                    "Using commit {} of release {} as previous commit ({}).",
+                    strip_sha(&previous.commit),
+                    previous.version,
+                    matches.value_of("previous").unwrap()
+                );
+                previous.commit
+            })
+        }
+    };
+
+    let heads = match (heads, &previous) {
+        (Some(mut heads), Some(previous)) => {
+            if heads.len() == 1 {
+                heads[0].prev_rev.get_or_insert_with(|| previous.clone());
+            } else if !heads.is_empty() {
+                eprintln!("warning: the previous commit is ignored for multiple repositories");
+            }
+            Some(heads)
+        }
+        (heads, _) => heads,
+    };
+
     if let Some(heads) = heads {
         if heads.is_empty() {
             println!("Clearing commits for release.");
@@ -578,13 +1126,20 @@ fn execute_set_commits<'a>(
             .unwrap_or("20")
             .parse::<usize>()?;
 
-        if matches.is_present("auto") {
+        if matches.is_present("auto") && paths.is_empty() {
             println!("Could not determine any commits to be associated with a repo-based integration. Proceeding to find commits from local git tree.");
         }
-        // Get the commit of the most recent release.
-        let prev_commit = match ctx.api.get_previous_release_with_commits(org, version)? {
-            OptionalReleaseInfo::Some(prev) => prev.last_commit.map(|c| c.id).unwrap_or_default(),
-            OptionalReleaseInfo::None(NoneReleaseInfo {}) => String::new(),
+        // Get the commit of the most recent release.  Without one, the
+        // default number of commits is used.
+        let prev_commit = match previous {
+            Some(previous) => previous,
+            None if ask_sentry => match ctx.api.get_previous_release_with_commits(org, version)? {
+                OptionalReleaseInfo::Some(prev) => {
+                    prev.last_commit.map(|c| c.id).unwrap_or_default()
+                }
+                OptionalReleaseInfo::None(NoneReleaseInfo {}) => String::new(),
+            },
+            None => String::new(),
         };
 
         // Find and connect to local git.
@@ -600,7 +1155,10 @@ fn execute_set_commits<'a>(
             get_commits_from_git(&repo, &prev_commit, default_count, ignore_missing)?;
 
         // Calculate the diff for each commit in the Vec<GitCommit>.
-        let commits = generate_patch_set(&repo, commit_log, prev_commit, &parsed)?;
+        let commits = filter_commits_by_paths(
+            generate_patch_set(&repo, commit_log, prev_commit, &parsed)?,
+            &paths,
+        );
 
         if commits.is_empty() {
             // TODO(v2): Make it a default behavior on next major release instead?
@@ -765,6 +1323,158 @@ fn execute_info<'a>(ctx: &ReleaseContext<'_>, matches: &ArgMatches<'a>) -> Resul
     Ok(())
 }
 
+fn execute_changelog<'a>(ctx: &ReleaseContext<'_>, matches: &ArgMatches<'a>) -> Result<(), Error> {
+    let org = ctx.get_org()?;
+    let to = matches.value_of("to").unwrap();
+
+    let versions = match matches.value_of("from") {
+        Some(from) => {
+            let project = ctx.get_project_default().ok();
+            let releases = ctx.api.list_releases(org, project.as_deref())?;
+            let find = |version: &str| match releases.iter().find(|r| r.version == version) {
+                Some(release) => Ok(release.date_created),
+                None => Err(format_err!("Release {} not found", version)),
+            };
+            let (start, end) = (find(from)?, find(to)?);
+            if start >= end {
+                bail!("Release {} is not older than {}", from, to);
+            }
+            let mut versions: Vec<_> = releases
+                .iter()
+                .filter(|r| r.date_created > start && r.date_created <= end)
+                .collect();
+            versions.sort_by_key(|r| Reverse(r.date_created));
+            versions.into_iter().map(|r| r.version.clone()).collect()
+        }
+        None => vec![to.to_string()],
+    };
+
+    let mut seen = HashSet::new();
+    let mut commits = vec![];
+    for version in &versions {
+        for commit in ctx.api.list_release_commits(org, version)? {
+            if seen.insert(commit.id.clone()) {
+                commits.push(commit);
+            }
+        }
+    }
+
+    let grouping = match matches.value_of("group_by") {
+        Some("author") => ChangelogGrouping::Author,
+        _ => ChangelogGrouping::Type,
+    };
+    let changelog = render_changelog(to, &commits, grouping);
+
+    if let Some(path) = matches.value_of("output") {
+        fs::write(path, changelog)?;
+        println!("Wrote changelog of {} commits to {}", commits.len(), path);
+    } else {
+        print!("{}", changelog);
+    }
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct ReleaseSuspects {
+    commits: Vec<ReleaseCommit>,
+    files: Vec<CommitFileChange>,
+    warnings: Vec<String>,
+}
+
+fn execute_suspects<'a>(ctx: &ReleaseContext<'_>, matches: &ArgMatches<'a>) -> Result<(), Error> {
+    let version = matches.value_of("version").unwrap();
+    let org = ctx.get_org()?;
+    let max_commits: usize = matches.value_of("max_commits").unwrap_or("250").parse()?;
+
+    let commits = ctx.api.list_release_commits(org, version)?;
+    let files = ctx.api.list_release_commit_files(org, version)?;
+
+    let mut warnings = vec![];
+    if commits.is_empty() {
+        warnings.push(format!(
+            "No commits are associated with release {}. Use `releases set-commits` to add them.",
+            version
+        ));
+    } else if commits.len() > max_commits {
+        let previous = match ctx.api.get_previous_release_with_commits(org, version)? {
+            OptionalReleaseInfo::Some(prev) => Some(prev.version),
+            OptionalReleaseInfo::None(NoneReleaseInfo {}) => None,
+        };
+        warnings.push(match previous {
+            Some(previous) => format!(
+                "{} commits are associated with release {}. The commit of the previous \
+                 release {} was probably not found, check the commit range.",
+                commits.len(),
+                version,
+                previous
+            ),
+            None => format!(
+                "{} commits are associated with release {} and there is no previous \
+                 release with commits. Check the commit range.",
+                commits.len(),
+                version
+            ),
+        });
+    }
+    if !commits.is_empty() && files.is_empty() {
+        warnings
+            .push("The commits have no file changes, suspect commits cannot be determined.".into());
+    }
+
+    if matches.is_present("json") {
+        serde_json::to_writer_pretty(
+            &mut io::stdout(),
+            &ReleaseSuspects {
+                commits,
+                files,
+                warnings,
+            },
+        )?;
+        println!();
+        return Ok(());
+    }
+
+    println!(
+        "Release {} has {} commits touching {} files",
+        version,
+        commits.len(),
+        files.len()
+    );
+    if !commits.is_empty() {
+        println!();
+        println!("Commits:");
+        for commit in &commits {
+            println!(
+                "  {} {}: {}",
+                strip_sha(&commit.id),
+                commit
+                    .author
+                    .as_ref()
+                    .and_then(|a| a.name.as_deref().or(a.email.as_deref()))
+                    .unwrap_or("unknown"),
+                commit
+                    .message
+                    .as_deref()
+                    .and_then(|m| m.lines().next())
+                    .unwrap_or("")
+            );
+        }
+    }
+    if !files.is_empty() {
+        println!();
+        println!("Files:");
+        for file in &files {
+            println!("  {} {}", file.ty, file.filename);
+        }
+    }
+    for warning in &warnings {
+        eprintln!("warning: {}", warning);
+    }
+
+    Ok(())
+}
+
 fn execute_files_list<'a>(
     ctx: &ReleaseContext<'_>,
     _matches: &ArgMatches<'a>,
@@ -839,28 +1549,41 @@ fn execute_files_upload<'a>(
 ) -> Result<(), Error> {
     let dist = matches.value_of("dist");
     let mut headers = vec![];
-    if let Some(header_list) = matches.values_of("header") {
+    if let Some(header_list) = matches.values_of("headers") {
         for header in header_list {
-            if !header.contains(':') {
-                bail!("Invalid header. Needs to be in key:value format");
-            }
-            let mut iter = header.splitn(2, ':');
-            let key = iter.next().unwrap();
-            let value = iter.next().unwrap();
-            headers.push((key.trim().to_string(), value.trim().to_string()));
+            headers.push(parse_header(header)?);
         }
     };
+    if let Some(content_type) = matches.value_of("content_type") {
+        set_header(&mut headers, "Content-Type", content_type);
+    }
+    let header_rules = matches
+        .values_of("header_rules")
+        .map(|rules| rules.map(parse_header_rule).collect::<Result<Vec<_>, _>>())
+        .transpose()?
+        .unwrap_or_default();
+    if let Some(plan) = matches.value_of("apply") {
+        return execute_files_apply_plan(ctx, matches, version, Path::new(plan));
+    }
     let org = ctx.get_org()?;
     let project = ctx.get_project_default().ok();
-    let path = Path::new(matches.value_of("path").unwrap());
+    let source = matches.value_of("path").unwrap();
+    // remote sources are downloaded into a directory that has to outlive the upload
+    let temp_dir = if is_remote(source) {
+        Some(TempDir::create()?)
+    } else {
+        None
+    };
+    let path = match temp_dir {
+        Some(ref dir) => fetch_source(source, dir.path())?,
+        None => normalize_path(source),
+    };
+    let path = path.as_path();
 
-    // Batch files upload
-    if path.is_dir() {
+    // Batch files upload, archives are uploaded like the directory they contain
+    if path.is_dir() || is_archive(path) {
         let ignore_file = matches.value_of("ignore_file").unwrap_or("");
-        let ignores = matches
-            .values_of("ignore")
-            .map(|ignores| ignores.map(|i| format!("!{}", i)).collect())
-            .unwrap_or_else(Vec::new);
+        let ignores = ignores_from_args(matches);
         let extensions = matches
             .values_of("extensions")
             .map(|extensions| extensions.map(|ext| ext.trim_start_matches('.')).collect())
@@ -870,39 +1593,65 @@ fn execute_files_upload<'a>(
             .ignore_file(ignore_file)
             .ignores(ignores)
             .extensions(extensions)
+            .follow_links(!matches.is_present("no_follow_symlinks"))
             .collect_files()?;
 
-        let url_prefix = get_url_prefix_from_args(matches);
-        let url_suffix = get_url_suffix_from_args(matches);
-        let files = sources
-            .iter()
-            .map(|source| {
-                let local_path = source.path.strip_prefix(&source.base_path).unwrap();
-                let url = format!("{}/{}{}", url_prefix, path_as_url(local_path), url_suffix);
-
-                (
-                    url.to_string(),
-                    ReleaseFile {
-                        url,
-                        path: source.path.clone(),
-                        contents: source.contents.clone(),
-                        ty: SourceFileType::Source,
-                        headers: headers.clone(),
-                        messages: vec![],
-                    },
-                )
-            })
-            .collect();
+        let mut files = ReleaseFiles::new();
+        for source in &sources {
+            let local_path = source.path.strip_prefix(&source.base_path).unwrap();
+            let url = get_url_from_args(matches, local_path);
+            if let Some(other) = files.get(&url) {
+                warn!(
+                    "{} and {} are both uploaded as {}, keeping the latter",
+                    other.path.display(),
+                    source.path.display(),
+                    url
+                );
+            }
+            files.insert(
+                url.clone(),
+                ReleaseFile {
+                    url,
+                    path: source.path.clone(),
+                    contents: source.contents.clone(),
+                    ty: SourceFileType::Source,
+                    headers: headers_for_path(&headers, &header_rules, local_path),
+                    messages: vec![],
+                },
+            );
+        }
 
-        let ctx = &UploadContext {
+        get_upload_limits_from_args(matches)?.apply(&mut files)?;
+
+        let context = &UploadContext {
             org,
             project: project.as_deref(),
             release: version,
             dist,
             wait: matches.is_present("wait"),
         };
+        if let Some(plan) = matches.value_of("plan") {
+            return write_plan(Path::new(plan), context, &files);
+        }
 
-        ReleaseFileUpload::new(ctx).files(&files).upload()
+        let _hint = cancel::resume_hint(format!(
+            "Files that were not uploaded yet are missing from release {}, upload \
+             them again to complete it. Use --plan DIR and --apply DIR to resume \
+             without searching and processing the files again.",
+            version
+        ));
+        let mut upload = ReleaseFileUpload::new(context);
+        upload
+            .files(&files)
+            .atomic(matches.is_present("atomic"))
+            .upload()?;
+        if matches.is_present("atomic") {
+            commit_release(ctx, matches, version)?;
+        }
+        if let Some(checksums_out) = matches.value_of("checksums_out") {
+            write_checksums_manifest(Path::new(checksums_out), &upload.checksums())?;
+        }
+        Ok(())
     }
     // Single file upload
     else {
@@ -913,6 +1662,36 @@ fn execute_files_upload<'a>(
                 .and_then(OsStr::to_str)
                 .ok_or_else(|| err_msg("No filename provided."))?,
         };
+        let headers = headers_for_path(&headers, &header_rules, Path::new(name));
+        let size = fs::metadata(path)?.len();
+        if !get_upload_limits_from_args(matches)?
+            .exceeding(vec![(name.to_string(), size)])?
+            .is_empty()
+        {
+            return Ok(());
+        }
+        if let Some(plan) = matches.value_of("plan") {
+            let context = UploadContext {
+                org,
+                project: project.as_deref(),
+                release: version,
+                dist,
+                wait: false,
+            };
+            let mut files = ReleaseFiles::new();
+            files.insert(
+                name.to_string(),
+                ReleaseFile {
+                    url: name.to_string(),
+                    path: path.to_path_buf(),
+                    contents: fs::read(path)?,
+                    ty: SourceFileType::Source,
+                    headers,
+                    messages: vec![],
+                },
+            );
+            return write_plan(Path::new(plan), &context, &files);
+        }
 
         if let Some(artifact) = ctx.api.upload_release_file(
             org,
@@ -928,10 +1707,167 @@ fn execute_files_upload<'a>(
         } else {
             bail!("File already present!");
         }
+        if matches.is_present("atomic") {
+            commit_release(ctx, matches, version)?;
+        }
+        if let Some(checksums_out) = matches.value_of("checksums_out") {
+            let context = UploadContext {
+                org,
+                project: project.as_deref(),
+                release: version,
+                dist,
+                wait: false,
+            };
+            let contents = fs::read(path)?;
+            let checksum = ArtifactChecksum::new(&context, name, &contents);
+            write_checksums_manifest(Path::new(checksums_out), &[checksum])?;
+        }
         Ok(())
     }
 }
 
+fn write_plan(dir: &Path, context: &UploadContext<'_>, files: &ReleaseFiles) -> Result<(), Error> {
+    write_upload_plan(dir, context, files)?;
+    println!(
+        "{} Wrote upload plan for {} files to {}",
+        style(">").dim(),
+        files.len(),
+        dir.display()
+    );
+    Ok(())
+}
+
+/// Finalizes a release after all of its files were uploaded with `--atomic`.
+fn commit_release<'a>(
+    ctx: &ReleaseContext<'_>,
+    matches: &ArgMatches<'a>,
+    version: &str,
+) -> Result<(), Error> {
+    let release = ctx.api.update_release(
+        ctx.get_org()?,
+        version,
+        &UpdatedRelease {
+            projects: ctx.get_projects(matches).ok(),
+            date_released: Some(Utc::now()),
+            ..Default::default()
+        },
+    )?;
+    println!("{} Finalized release {}", style(">").dim(), release.version);
+    Ok(())
+}
+
+/// Uploads the files of a plan written with `--plan`.
+fn execute_files_apply_plan<'a>(
+    ctx: &ReleaseContext<'_>,
+    matches: &ArgMatches<'a>,
+    version: &str,
+    dir: &Path,
+) -> Result<(), Error> {
+    let plan = read_upload_plan(dir)?;
+    if plan.release != version {
+        bail!(
+            "The upload plan is for release {}, not {}",
+            plan.release,
+            version
+        );
+    }
+    println!(
+        "{} Applying upload plan with {} files",
+        style(">").dim(),
+        plan.files.len()
+    );
+
+    let org = ctx.get_org()?;
+    let project = ctx.get_project_default().ok();
+    let release = ctx.api.new_release(
+        org,
+        &NewRelease {
+            version: version.into(),
+            projects: ctx.get_projects(matches)?,
+            ..Default::default()
+        },
+    )?;
+    let context = UploadContext {
+        org,
+        project: project.as_deref(),
+        release: &release.version,
+        dist: plan.dist.as_deref(),
+        wait: matches.is_present("wait"),
+    };
+
+    let _hint = cancel::resume_hint(format!(
+        "The upload plan in {} is unchanged, resume the upload with \
+         `sentry-cli releases files {} upload --apply {}`.",
+        dir.join("manifest.json").display(),
+        version,
+        dir.display()
+    ));
+    let mut upload = ReleaseFileUpload::new(&context);
+    upload
+        .files(&plan.files)
+        .atomic(matches.is_present("atomic"))
+        .upload()?;
+    if matches.is_present("atomic") {
+        commit_release(ctx, matches, version)?;
+    }
+    if let Some(checksums_out) = matches.value_of("checksums_out") {
+        write_checksums_manifest(Path::new(checksums_out), &upload.checksums())?;
+    }
+    Ok(())
+}
+
+/// Parses a header given as `key:value`.
+fn parse_header(header: &str) -> Result<(String, String), Error> {
+    match header.split_once(':') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.trim().to_string()))
+        }
+        _ => bail!("Invalid header. Needs to be in key:value format"),
+    }
+}
+
+/// Parses a header that is only stored with some files, given as
+/// `glob=key:value`.
+fn parse_header_rule(rule: &str) -> Result<(Pattern, (String, String)), Error> {
+    let (glob, header) = match rule.split_once('=') {
+        Some(parts) => parts,
+        None => bail!("Invalid header rule. Needs to be in glob=key:value format"),
+    };
+    let pattern = Pattern::new(glob.trim())
+        .with_context(|_| format!("Invalid glob in header rule '{}'", rule))?;
+    Ok((pattern, parse_header(header)?))
+}
+
+/// Sets a header, replacing any header of the same name.
+fn set_header(headers: &mut Vec<(String, String)>, key: &str, value: &str) {
+    headers.retain(|(k, _)| !k.eq_ignore_ascii_case(key));
+    headers.push((key.to_string(), value.to_string()));
+}
+
+/// Returns the headers to store with the file at the given relative path.
+/// Without a slash in the glob only the file name is matched, so `*.wasm`
+/// applies to files in all folders.
+fn headers_for_path(
+    headers: &[(String, String)],
+    rules: &[(Pattern, (String, String))],
+    path: &Path,
+) -> Vec<(String, String)> {
+    let mut rv = headers.to_vec();
+    let path = path_as_url(path);
+    let file_name = path.rsplit('/').next().unwrap_or(&path);
+    for (pattern, (key, value)) in rules {
+        let subject = if pattern.as_str().contains('/') {
+            path.as_str()
+        } else {
+            file_name
+        };
+        if pattern.matches(subject) {
+            set_header(&mut rv, key, value);
+        }
+    }
+    rv
+}
+
 fn get_url_prefix_from_args<'a, 'b>(matches: &'b ArgMatches<'a>) -> &'b str {
     let mut rv = matches.value_of("url_prefix").unwrap_or("~");
     // remove a single slash from the end.  so ~/ becomes ~ and app:/// becomes app://
@@ -945,6 +1881,20 @@ fn get_url_suffix_from_args<'a, 'b>(matches: &'b ArgMatches<'a>) -> &'b str {
     matches.value_of("url_suffix").unwrap_or("")
 }
 
+/// Returns the URL of a file at the path relative to the upload directory.
+fn get_url_from_args(matches: &ArgMatches<'_>, local_path: &Path) -> String {
+    let mut path = path_as_url(local_path);
+    if matches.value_of("url_case") == Some("lower") {
+        path = path.to_lowercase();
+    }
+    format!(
+        "{}/{}{}",
+        get_url_prefix_from_args(matches),
+        path,
+        get_url_suffix_from_args(matches)
+    )
+}
+
 fn get_prefixes_from_args<'a, 'b>(matches: &'b ArgMatches<'a>) -> Vec<&'b str> {
     let mut prefixes: Vec<&str> = match matches.values_of("strip_prefix") {
         Some(paths) => paths.collect(),
@@ -1015,23 +1965,32 @@ fn process_sources_from_bundle<'a>(
     Ok(())
 }
 
-fn process_sources_from_paths<'a>(
+fn process_sources_from_paths<'a, 'p, I>(
     matches: &ArgMatches<'a>,
+    paths: I,
     processor: &mut SourceMapProcessor,
-) -> Result<(), Error> {
-    let paths = matches.values_of("paths").unwrap();
+) -> Result<(), Error>
+where
+    I: IntoIterator<Item = &'p str>,
+{
     let ignore_file = matches.value_of("ignore_file").unwrap_or("");
     let extensions = matches
         .values_of("extensions")
         .map(|extensions| extensions.map(|ext| ext.trim_start_matches('.')).collect())
         .unwrap_or_else(|| vec!["js", "map", "jsbundle", "bundle"]);
-    let ignores = matches
-        .values_of("ignore")
-        .map(|ignores| ignores.map(|i| format!("!{}", i)).collect())
-        .unwrap_or_else(Vec::new);
+    let ignores = ignores_from_args(matches);
 
     let opts = MatchOptions::new();
-    let collected_paths = paths.flat_map(|path| glob_with(path, opts).unwrap().flatten());
+    let mut collected_paths = vec![];
+    for path in paths {
+        // existing paths are not globbed, this also keeps verbatim paths
+        // working, which glob does not support
+        if Path::new(path).exists() {
+            collected_paths.push(normalize_path(path));
+        } else {
+            collected_paths.extend(glob_with(path, opts)?.flatten().map(normalize_path));
+        }
+    }
 
     for path in collected_paths {
         // if we start walking over something that is an actual file then
@@ -1039,13 +1998,14 @@ fn process_sources_from_paths<'a>(
         // handle that case here specifically to figure out what the path is
         // we should strip off.
         let path = path.as_path();
-        let (base_path, check_ignore) = if path.is_file() {
+        let (base_path, check_ignore) = if path.is_file() && !is_archive(path) {
             (path.parent().unwrap(), false)
         } else {
             (path, true)
         };
 
         let mut search = ReleaseFileSearch::new(path.to_path_buf());
+        search.follow_links(!matches.is_present("no_follow_symlinks"));
 
         if check_ignore {
             search
@@ -1056,12 +2016,9 @@ fn process_sources_from_paths<'a>(
 
         let sources = search.collect_files()?;
 
-        let url_prefix = get_url_prefix_from_args(matches);
-        let url_suffix = get_url_suffix_from_args(matches);
-
         for source in sources {
             let local_path = source.path.strip_prefix(base_path).unwrap();
-            let url = format!("{}/{}{}", url_prefix, path_as_url(local_path), url_suffix);
+            let url = get_url_from_args(matches, local_path);
             processor.add(&url, source)?;
         }
     }
@@ -1087,16 +2044,99 @@ fn execute_files_upload_sourcemaps<'a>(
     matches: &ArgMatches<'a>,
     version: &str,
 ) -> Result<(), Error> {
+    if let Some(manifest) = matches.value_of("from_build_manifest") {
+        return execute_files_upload_build_manifest(ctx, matches, version, Path::new(manifest));
+    }
+
     let mut processor = SourceMapProcessor::new();
 
     if matches.is_present("bundle") && matches.is_present("bundle_sourcemap") {
         process_sources_from_bundle(matches, &mut processor)?;
     } else {
-        process_sources_from_paths(matches, &mut processor)?;
+        process_sources_from_paths(matches, matches.values_of("paths").unwrap(), &mut processor)?;
     }
 
+    let checksums = upload_sourcemaps_to_release(ctx, matches, version, &mut processor)?;
+    if let Some(checksums_out) = matches.value_of("checksums_out") {
+        write_checksums_manifest(Path::new(checksums_out), &checksums)?;
+    }
+    Ok(())
+}
+
+/// Uploads the output directory of every app in a build manifest to a
+/// release of its own.
+fn execute_files_upload_build_manifest<'a>(
+    ctx: &ReleaseContext<'_>,
+    matches: &ArgMatches<'a>,
+    version: &str,
+    manifest: &Path,
+) -> Result<(), Error> {
+    let apps = read_build_manifest(manifest)?;
+    if apps.is_empty() {
+        println!("No apps with build outputs in {}", manifest.display());
+        return Ok(());
+    }
+
+    let mut checksums = vec![];
+    for app in apps {
+        if !app.output_dir.is_dir() {
+            warn!(
+                "Skipping {}: {} does not exist",
+                app.name,
+                app.output_dir.display()
+            );
+            continue;
+        }
+        let release = format!("{}@{}", app.release_prefix(), version);
+        println!(
+            "{} Uploading {} to release {}",
+            style(">").dim(),
+            app.output_dir.display(),
+            style(&release).cyan()
+        );
+        let output_dir = app.output_dir.to_string_lossy();
+        let mut processor = SourceMapProcessor::new();
+        process_sources_from_paths(matches, vec![&*output_dir], &mut processor)?;
+        checksums.extend(upload_sourcemaps_to_release(
+            ctx,
+            matches,
+            &release,
+            &mut processor,
+        )?);
+    }
+
+    if let Some(checksums_out) = matches.value_of("checksums_out") {
+        write_checksums_manifest(Path::new(checksums_out), &checksums)?;
+    }
+    Ok(())
+}
+
+fn upload_sourcemaps_to_release<'a>(
+    ctx: &ReleaseContext<'_>,
+    matches: &ArgMatches<'a>,
+    version: &str,
+    processor: &mut SourceMapProcessor,
+) -> Result<Vec<ArtifactChecksum>, Error> {
     let org = ctx.get_org()?;
     let project = ctx.get_project_default().ok();
+    processor.apply_limits(&get_upload_limits_from_args(matches)?)?;
+
+    if let Some(plan) = matches.value_of("plan") {
+        let context = UploadContext {
+            org,
+            project: project.as_deref(),
+            release: version,
+            dist: matches.value_of("dist"),
+            wait: false,
+        };
+        processor.write_plan(Path::new(plan), &context)?;
+        println!(
+            "{} Wrote upload plan to {}",
+            style(">").dim(),
+            Path::new(plan).display()
+        );
+        return Ok(vec![]);
+    }
 
     // make sure the release exists
     let release = ctx.api.new_release(
@@ -1108,15 +2148,21 @@ fn execute_files_upload_sourcemaps<'a>(
         },
     )?;
 
-    processor.upload(&UploadContext {
+    let context = UploadContext {
         org,
         project: project.as_deref(),
         release: &release.version,
         dist: matches.value_of("dist"),
         wait: matches.is_present("wait"),
-    })?;
+    };
+    if matches.is_present("atomic") {
+        processor.upload_atomic(&context)?;
+        commit_release(ctx, matches, &release.version)?;
+    } else {
+        processor.upload(&context)?;
+    }
 
-    Ok(())
+    Ok(processor.checksums(&context))
 }
 
 fn execute_files<'a>(ctx: &ReleaseContext<'_>, matches: &ArgMatches<'a>) -> Result<(), Error> {
@@ -1148,6 +2194,10 @@ fn execute_deploys_new<'a>(
         ..Default::default()
     };
 
+    if deploy.url.is_none() && !matches.is_present("no_build_url") {
+        deploy.url = detect_build_url();
+    }
+
     if let Some(value) = matches.value_of("time") {
         let finished = Utc::now();
         deploy.finished = Some(finished);
@@ -1164,18 +2214,75 @@ fn execute_deploys_new<'a>(
     }
 
     let org = ctx.get_org()?;
+    if matches.is_present("idempotent") {
+        // the build URL changes with every run, only an explicit URL counts
+        let url = matches.value_of("url");
+        let existing = ctx.api.list_deploys(org, version)?.into_iter().find(|d| {
+            d.env == deploy.env
+                && d.name() == deploy.name()
+                && (url.is_none() || d.url.as_deref() == url)
+        });
+        if let Some(existing) = existing {
+            println!(
+                "Deploy {} for '{}' already exists, nothing changed.",
+                existing.name(),
+                existing.env
+            );
+            machine::set_result("changed", false);
+            return Ok(());
+        }
+        machine::set_result("changed", true);
+    }
     let deploy = ctx.api.create_deploy(org, version, &deploy)?;
 
     println!("Created new deploy {} for '{}'", deploy.name(), deploy.env);
 
+    if let Some(webhook) = Webhook::from_matches(matches) {
+        webhook.send(
+            "deploy.created",
+            org,
+            json!({
+                "release": { "version": version },
+                "deploy": deploy,
+            }),
+        )?;
+    }
+
     Ok(())
 }
 
 fn execute_deploys_list<'a>(
     ctx: &ReleaseContext<'_>,
-    _matches: &ArgMatches<'a>,
+    matches: &ArgMatches<'a>,
     version: &str,
 ) -> Result<(), Error> {
+    let envs: Option<Vec<&str>> = matches.values_of("env").map(Iterator::collect);
+    let since = matches.value_of("since").map(get_timestamp).transpose()?;
+    let until = matches.value_of("until").map(get_timestamp).transpose()?;
+
+    let deploys: Vec<Deploy> = ctx
+        .api
+        .list_deploys(ctx.get_org()?, version)?
+        .into_iter()
+        .filter(|deploy| {
+            envs.as_ref()
+                .is_none_or(|envs| envs.contains(&deploy.env.as_str()))
+        })
+        .filter(|deploy| match (deploy.date(), since, until) {
+            (None, None, None) => true,
+            (None, _, _) => false,
+            (Some(date), since, until) => {
+                since.is_none_or(|since| date >= since) && until.is_none_or(|until| date <= until)
+            }
+        })
+        .collect();
+
+    if matches.is_present("json") {
+        serde_json::to_writer_pretty(&mut io::stdout(), &deploys)?;
+        println!();
+        return Ok(());
+    }
+
     let mut table = Table::new();
     table
         .title_row()
@@ -1183,7 +2290,7 @@ fn execute_deploys_list<'a>(
         .add("Name")
         .add("Finished");
 
-    for deploy in ctx.api.list_deploys(ctx.get_org()?, version)? {
+    for deploy in deploys {
         table
             .add_row()
             .add(&deploy.env)
@@ -1202,8 +2309,197 @@ fn execute_deploys_list<'a>(
     Ok(())
 }
 
+#[derive(Serialize)]
+struct LatestDeploy {
+    version: String,
+    deploy: Deploy,
+}
+
+fn execute_deploys_latest<'a>(
+    ctx: &ReleaseContext<'_>,
+    matches: &ArgMatches<'a>,
+) -> Result<(), Error> {
+    let org = ctx.get_org()?;
+    let env = matches.value_of("env").unwrap();
+    let mut latest: Option<LatestDeploy> = None;
+
+    for release in ctx.api.list_environment_releases(org, env)? {
+        let last_deploy = match release.last_deploy {
+            Some(deploy) => deploy,
+            None => continue,
+        };
+        // the last deploy of a release can go to another environment, in
+        // which case the release's deploys have to be searched.
+        let deploy = if last_deploy.env == env {
+            Some(*last_deploy)
+        } else {
+            ctx.api
+                .list_deploys(org, &release.version)?
+                .into_iter()
+                .filter(|deploy| deploy.env == env)
+                .max_by_key(Deploy::date)
+        };
+        if let Some(deploy) = deploy {
+            if latest
+                .as_ref()
+                .is_none_or(|x| deploy.date() > x.deploy.date())
+            {
+                latest = Some(LatestDeploy {
+                    version: release.version,
+                    deploy,
+                });
+            }
+        }
+    }
+
+    let latest = match latest {
+        Some(latest) => latest,
+        None => {
+            eprintln!("No deploys to {} found", env);
+            return Err(QuietExit(1).into());
+        }
+    };
+
+    if matches.is_present("json") {
+        serde_json::to_writer_pretty(&mut io::stdout(), &latest)?;
+        println!();
+    } else {
+        println!("{}", latest.version);
+    }
+
+    Ok(())
+}
+
+/// Returns the finished deploys to an environment, most recent first.
+fn list_environment_deploys(
+    ctx: &ReleaseContext<'_>,
+    env: &str,
+) -> Result<Vec<LatestDeploy>, Error> {
+    let org = ctx.get_org()?;
+    let mut rv: Vec<_> = ctx
+        .api
+        .list_environment_deploys(org, env)?
+        .into_iter()
+        .filter(|d| d.deploy.env == env && d.deploy.finished.is_some())
+        .map(|d| LatestDeploy {
+            version: d.version,
+            deploy: d.deploy,
+        })
+        .collect();
+    rv.sort_by_key(|d| Reverse(d.deploy.date()));
+    Ok(rv)
+}
+
+fn execute_deploys_rollback<'a>(
+    ctx: &ReleaseContext<'_>,
+    matches: &ArgMatches<'a>,
+) -> Result<(), Error> {
+    let org = ctx.get_org()?;
+    let env = matches.value_of("env").unwrap();
+    let dry_run = matches.is_present("dry_run");
+
+    let deploys = list_environment_deploys(ctx, env)?;
+    let current = match deploys.first() {
+        Some(current) => &current.version,
+        None => bail!("No deploys to {} found", env),
+    };
+    let target = match matches.value_of("to") {
+        Some(target) => target,
+        None => match deploys.iter().find(|d| &d.version != current) {
+            Some(previous) => &previous.version,
+            None => bail!("No release was deployed to {} before {}", env, current),
+        },
+    };
+    if target == current {
+        bail!("Release {} is already deployed to {}", target, env);
+    }
+
+    // look up the issues first so that a missing project fails before the
+    // deploy is recorded.  Only issues seen in the target release can come
+    // back with it.
+    let reopen = if matches.is_present("reopen_resolved") {
+        let project = ctx.get_project_default()?;
+        let query = format!("is:resolved release:\"{}\"", target);
+        let issues = ctx
+            .api
+            .list_issues(
+                org,
+                &project,
+                &IssueQuery {
+                    query: Some(&query),
+                    ..Default::default()
+                },
+                None,
+            )?
+            .into_iter()
+            .filter(|issue| issue.status_details.in_release.as_deref() == Some(current.as_str()))
+            .collect::<Vec<_>>();
+        Some((project, issues))
+    } else {
+        None
+    };
+
+    let prefix = if dry_run { "Would roll" } else { "Rolled" };
+    println!("{} back {} from {} to {}", prefix, env, current, target);
+
+    if !dry_run {
+        let now = Utc::now();
+        let deploy = ctx.api.create_deploy(
+            org,
+            target,
+            &Deploy {
+                env: env.to_string(),
+                name: Some(format!("Rollback from {}", current)),
+                url: detect_build_url(),
+                started: Some(now),
+                finished: Some(now),
+                ..Default::default()
+            },
+        )?;
+        println!("  Created deploy {} of {}", deploy.name(), target);
+    }
+
+    if let Some((project, issues)) = reopen {
+        if !dry_run && !issues.is_empty() {
+            let ids = issues
+                .iter()
+                .map(|issue| issue.id.parse())
+                .collect::<Result<Vec<u64>, _>>()?;
+            ctx.api.bulk_update_issue(
+                org,
+                &project,
+                &IssueFilter::ExplicitIds(ids),
+                &IssueChanges {
+                    new_status: Some("unresolved".into()),
+                    ..Default::default()
+                },
+            )?;
+        }
+        println!(
+            "  {} {} issues resolved in {}",
+            if dry_run { "Would reopen" } else { "Reopened" },
+            issues.len(),
+            current
+        );
+        for issue in &issues {
+            println!("    {} {}", issue.short_id, issue.title);
+        }
+    }
+
+    Ok(())
+}
+
 fn execute_deploys<'a>(ctx: &ReleaseContext<'_>, matches: &ArgMatches<'a>) -> Result<(), Error> {
-    let release = matches.value_of("version").unwrap();
+    if let Some(sub_matches) = matches.subcommand_matches("latest") {
+        return execute_deploys_latest(ctx, sub_matches);
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("rollback") {
+        return execute_deploys_rollback(ctx, sub_matches);
+    }
+    let release = match matches.value_of("version") {
+        Some(release) => release,
+        None => bail!("A release version is required."),
+    };
     if let Some(sub_matches) = matches.subcommand_matches("new") {
         return execute_deploys_new(ctx, sub_matches, release);
     }
@@ -1234,6 +2530,9 @@ pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
     if let Some(sub_matches) = matches.subcommand_matches("set-commits") {
         return execute_set_commits(&ctx, sub_matches);
     }
+    if let Some(sub_matches) = matches.subcommand_matches("set-dependencies") {
+        return execute_set_dependencies(&ctx, sub_matches);
+    }
     if let Some(sub_matches) = matches.subcommand_matches("delete") {
         return execute_delete(&ctx, sub_matches);
     }
@@ -1249,6 +2548,12 @@ pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
     if let Some(sub_matches) = matches.subcommand_matches("info") {
         return execute_info(&ctx, sub_matches);
     }
+    if let Some(sub_matches) = matches.subcommand_matches("changelog") {
+        return execute_changelog(&ctx, sub_matches);
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("suspects") {
+        return execute_suspects(&ctx, sub_matches);
+    }
     if let Some(sub_matches) = matches.subcommand_matches("files") {
         return execute_files(&ctx, sub_matches);
     }
@@ -1,25 +1,68 @@
 //! Implements a command for managing repos.
-use clap::{App, AppSettings, ArgMatches};
-use failure::Error;
+use clap::{App, AppSettings, Arg, ArgMatches};
+use failure::{bail, Error};
 
-use crate::api::Api;
+use crate::api::{Api, NewRepo};
 use crate::config::Config;
 use crate::utils::args::ArgExt;
 use crate::utils::formatting::Table;
+use crate::utils::ui::confirm;
 
 pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
     app.about("Manage repositories on Sentry.")
         .setting(AppSettings::SubcommandRequiredElseHelp)
         .org_arg()
         .subcommand(App::new("list").about("List all repositories in your organization."))
+        .subcommand(
+            App::new("create")
+                .about("Add a repository to your organization.")
+                .arg(
+                    Arg::with_name("name")
+                        .value_name("NAME")
+                        .required(true)
+                        .index(1)
+                        .help(
+                            "The name of the repository on the provider, e.g. `getsentry/sentry`.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("provider")
+                        .long("provider")
+                        .value_name("PROVIDER")
+                        .required(true)
+                        .help("The provider of the repository, e.g. `github` or `gitlab`."),
+                )
+                .arg(
+                    Arg::with_name("integration")
+                        .long("integration")
+                        .value_name("ID")
+                        .help(
+                            "The ID of the integration to add the repository with.{n}\
+                             Required if more than one integration of the provider is installed.",
+                        ),
+                ),
+        )
+        .subcommand(
+            App::new("delete")
+                .about("Remove a repository from your organization.")
+                .arg(
+                    Arg::with_name("name")
+                        .value_name("NAME")
+                        .required(true)
+                        .index(1)
+                        .help("The name or ID of the repository."),
+                )
+                .arg(
+                    Arg::with_name("confirm")
+                        .long("confirm")
+                        .help("Skip the confirmation prompt."),
+                ),
+        )
 }
 
-pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+fn execute_list(org: &str) -> Result<(), Error> {
     let api = Api::current();
-
-    let config = Config::current();
-    let org = config.get_org(matches)?;
-    let repos = api.list_organization_repos(&org)?;
+    let repos = api.list_organization_repos(org)?;
 
     let mut table = Table::new();
     table.title_row().add("Name").add("Provider").add("URL");
@@ -40,3 +83,90 @@ pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
 
     Ok(())
 }
+
+fn execute_create(org: &str, matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let api = Api::current();
+    let name = matches.value_of("name").unwrap();
+    let provider = matches.value_of("provider").unwrap();
+
+    let integrations = api.list_organization_integrations(org, Some(provider))?;
+    let integration = match matches.value_of("integration") {
+        Some(id) => integrations.iter().find(|i| i.id == id),
+        None if integrations.len() > 1 => bail!(
+            "Found {} {} integrations, select one with --integration: {}",
+            integrations.len(),
+            provider,
+            integrations
+                .iter()
+                .map(|i| format!("{} ({})", i.id, i.name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        None => integrations.first(),
+    };
+    let integration = match integration {
+        Some(integration) => integration,
+        None => bail!("No matching {} integration installed", provider),
+    };
+
+    let repo = api.create_repo(
+        org,
+        &NewRepo {
+            provider: format!("integrations:{}", integration.provider.key),
+            installation: Some(integration.id.clone()),
+            identifier: name.into(),
+        },
+    )?;
+    println!("Created repository {} ({})", repo.name, repo.id);
+    if let Some(url) = repo.url {
+        println!("  URL: {}", url);
+    }
+
+    Ok(())
+}
+
+fn execute_delete(org: &str, matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let api = Api::current();
+    let name = matches.value_of("name").unwrap();
+
+    let repos = api.list_organization_repos(org)?;
+    let repo = match repos.iter().find(|r| r.name == name || r.id == name) {
+        Some(repo) => repo,
+        None => {
+            println!("Did nothing. The repository {} does not exist.", name);
+            return Ok(());
+        }
+    };
+
+    if !confirm(
+        matches.is_present("confirm"),
+        &format!("Do you really want to remove the repository {}?", repo.name),
+    )? {
+        println!("Aborted!");
+        return Ok(());
+    }
+
+    if api.delete_repo(org, &repo.id)? {
+        println!("Deleted repository {}!", repo.name);
+    } else {
+        println!("Did nothing. The repository {} does not exist.", repo.name);
+    }
+
+    Ok(())
+}
+
+pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let config = Config::current();
+    let org = config.get_org(matches)?;
+
+    if matches.subcommand_matches("list").is_some() {
+        return execute_list(&org);
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("create") {
+        return execute_create(&org, sub_matches);
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("delete") {
+        return execute_delete(&org, sub_matches);
+    }
+    unreachable!();
+}
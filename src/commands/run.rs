@@ -0,0 +1,428 @@
+//! Implements a command for reporting failed processes to Sentry.
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{self, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use clap::{App, Arg, ArgMatches};
+use console::style;
+use failure::{err_msg, Error, ResultExt};
+use sentry::protocol::{Event, Exception, Level, User, Value};
+use username::get_user_name;
+
+use crate::config::Config;
+use crate::utils::args::validate_env_var;
+use crate::utils::ci::detect_build_url;
+use crate::utils::crash_report::{is_secret_name, redact};
+use crate::utils::event::{get_sdk_info, with_sentry_client};
+use crate::utils::releases::detect_release_name;
+use crate::utils::resource_usage::{ResourceUsage, UsageTracker};
+use crate::utils::system::QuietExit;
+
+pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
+    add_sandbox_args(add_report_args(
+        app.about("Run a command and report it to Sentry if it fails.")
+            .long_about(
+                "Run a command and report it to Sentry if it fails.{n}{n}\
+             The command is passed after `--`.  If it exits with a non-zero \
+             status an error event is sent with the arguments, the duration, \
+             the exit code, the memory, CPU time and I/O the command used and \
+             the last lines of its standard error.  The \
+             output of the command is passed through and sentry-cli exits with \
+             the same status as the command.  With --clean-env the command \
+             runs without the environment of sentry-cli, which keeps \
+             SENTRY_AUTH_TOKEN out of it.{n}{n}\
+             Example: sentry-cli run -- ./nightly-backup.sh --full",
+            ),
+    ))
+}
+
+/// Adds the arguments that control the event sent for a failed command.
+pub fn add_report_args<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.arg(
+        Arg::with_name("release")
+            .value_name("RELEASE")
+            .long("release")
+            .short("r")
+            .help("Optional identifier of the release."),
+    )
+    .arg(
+        Arg::with_name("environment")
+            .value_name("ENVIRONMENT")
+            .long("env")
+            .short("E")
+            .help("Send with a specific environment."),
+    )
+    .arg(
+        Arg::with_name("tags")
+            .value_name("KEY:VALUE")
+            .long("tag")
+            .short("t")
+            .multiple(true)
+            .number_of_values(1)
+            .help("Add a tag (key:value) to the event."),
+    )
+    .arg(
+        Arg::with_name("tail_lines")
+            .value_name("LINES")
+            .long("tail-lines")
+            .default_value("50")
+            .validator(|value| {
+                value
+                    .parse::<usize>()
+                    .map(|_| ())
+                    .map_err(|_| "expected a number of lines".to_string())
+            })
+            .help("The number of trailing lines of standard error to send."),
+    )
+    .arg(Arg::with_name("send_environ").long("send-environ").help(
+        "Send the environment variables of the command along.  Variables \
+                 whose names look like secrets, such as tokens, keys and \
+                 passwords, are left out.",
+    ))
+    .arg(
+        Arg::with_name("args")
+            .value_name("COMMAND")
+            .required(true)
+            .multiple(true)
+            .last(true),
+    )
+}
+
+/// Adds the arguments that control the environment and working directory
+/// of the command.
+pub fn add_sandbox_args<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.arg(Arg::with_name("clean_env").long("clean-env").help(
+        "Run the command without the environment of sentry-cli, such as \
+                 SENTRY_AUTH_TOKEN.{n}Only basic variables like PATH, HOME and \
+                 the locale are passed on.",
+    ))
+    .arg(
+        Arg::with_name("set_env")
+            .value_name("KEY=VALUE")
+            .long("set-env")
+            .multiple(true)
+            .number_of_values(1)
+            .validator(validate_env_var)
+            .help(
+                "Set an environment variable for the command.{n}This is not \
+                 called --env because --env sets the Sentry environment of the \
+                 report.",
+            ),
+    )
+    .arg(
+        Arg::with_name("cwd")
+            .value_name("PATH")
+            .long("cwd")
+            .help("Run the command in this working directory."),
+    )
+}
+
+/// Variables that are passed on to commands run with `--clean-env`.
+#[cfg(not(windows))]
+const CLEAN_ENV_VARS: &[&str] = &[
+    "HOME", "LANG", "LANGUAGE", "LOGNAME", "PATH", "SHELL", "TERM", "TMPDIR", "TZ", "USER",
+];
+
+/// Variables that are passed on to commands run with `--clean-env`.
+#[cfg(windows)]
+const CLEAN_ENV_VARS: &[&str] = &[
+    "APPDATA",
+    "COMPUTERNAME",
+    "COMSPEC",
+    "HOMEDRIVE",
+    "HOMEPATH",
+    "LOCALAPPDATA",
+    "PATH",
+    "PATHEXT",
+    "PROGRAMDATA",
+    "PROGRAMFILES",
+    "PROGRAMFILES(X86)",
+    "SYSTEMDRIVE",
+    "SYSTEMROOT",
+    "TEMP",
+    "TMP",
+    "USERDOMAIN",
+    "USERNAME",
+    "USERPROFILE",
+    "WINDIR",
+];
+
+fn is_clean_env_var(key: &OsStr) -> bool {
+    // variables with names that are not valid unicode are never basic
+    let key = match key.to_str() {
+        Some(key) => key,
+        None => return false,
+    };
+    // variable names are case-insensitive on Windows
+    key.starts_with("LC_")
+        || CLEAN_ENV_VARS.iter().any(|var| {
+            if cfg!(windows) {
+                var.eq_ignore_ascii_case(key)
+            } else {
+                *var == key
+            }
+        })
+}
+
+/// The environment and working directory a wrapped command runs in.
+#[derive(Debug, Default)]
+pub struct Sandbox {
+    clean_env: bool,
+    vars: Vec<(String, String)>,
+    cwd: Option<PathBuf>,
+}
+
+impl Sandbox {
+    pub fn from_matches(matches: &ArgMatches<'_>) -> Sandbox {
+        Sandbox {
+            clean_env: matches.is_present("clean_env"),
+            vars: matches
+                .values_of("set_env")
+                .unwrap_or_default()
+                .filter_map(|var| {
+                    let mut split = var.splitn(2, '=');
+                    Some((split.next()?.to_owned(), split.next()?.to_owned()))
+                })
+                .collect(),
+            cwd: matches.value_of("cwd").map(PathBuf::from),
+        }
+    }
+
+    /// Returns the environment the command sees.
+    ///
+    /// Variables are not required to be valid unicode.
+    pub fn environ(&self) -> Vec<(OsString, OsString)> {
+        let mut environ: Vec<_> = env::vars_os()
+            .filter(|(key, _)| !self.clean_env || is_clean_env_var(key))
+            .filter(|(key, _)| !self.vars.iter().any(|(k, _)| OsStr::new(k) == key))
+            .collect();
+        environ.extend(self.vars.iter().map(|(k, v)| (k.into(), v.into())));
+        environ
+    }
+
+    /// Creates the command to run the given arguments.
+    pub fn command(&self, args: &[&str]) -> process::Command {
+        let mut command = process::Command::new(args[0]);
+        command.args(&args[1..]);
+        if self.clean_env {
+            command.env_clear();
+            command.envs(self.environ());
+        } else {
+            command.envs(self.vars.iter().cloned());
+        }
+        if let Some(ref cwd) = self.cwd {
+            command.current_dir(cwd);
+        }
+        command
+    }
+}
+
+/// Passes the standard error of the child through and keeps its last lines.
+fn tee_stderr<R: io::Read>(stream: R, limit: usize) -> VecDeque<String> {
+    let mut tail = VecDeque::with_capacity(limit);
+    let stderr = io::stderr();
+    let mut reader = BufReader::new(stream);
+    let mut buf = vec![];
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        stderr.lock().write_all(&buf).ok();
+        if limit > 0 {
+            if tail.len() == limit {
+                tail.pop_front();
+            }
+            let line = String::from_utf8_lossy(&buf);
+            tail.push_back(line.trim_end_matches(&['\r', '\n'][..]).to_string());
+        }
+    }
+    tail
+}
+
+/// Starts a command with its standard error passed through a thread that
+/// keeps the last `tail_lines` lines.
+pub fn spawn_with_stderr_tail(
+    sandbox: &Sandbox,
+    args: &[&str],
+    tail_lines: usize,
+) -> Result<(process::Child, thread::JoinHandle<VecDeque<String>>), Error> {
+    let mut child = sandbox
+        .command(args)
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|_| format!("Could not run {}", args[0]))?;
+    let stderr = child.stderr.take().unwrap();
+    let tee = thread::spawn(move || tee_stderr(stderr, tail_lines));
+    Ok((child, tee))
+}
+
+#[cfg(unix)]
+fn exit_signal(status: process::ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal()
+}
+
+#[cfg(not(unix))]
+fn exit_signal(_status: process::ExitStatus) -> Option<i32> {
+    None
+}
+
+/// Describes how a process ended, e.g. `exited with status 3`.
+pub fn describe_exit(status: process::ExitStatus) -> String {
+    match (status.code(), exit_signal(status)) {
+        // codes of Windows are often HRESULT or NTSTATUS values
+        (Some(code), _) if !(0..=0xffff).contains(&code) => {
+            format!("exited with status {} ({:#010x})", code, code)
+        }
+        (Some(code), _) => format!("exited with status {}", code),
+        (None, Some(signal)) => format!("was terminated by signal {}", signal),
+        (None, None) => "was terminated by a signal".to_string(),
+    }
+}
+
+/// The status to exit with for a finished child, following the shell
+/// convention of 128 plus the signal number for processes that were killed.
+pub fn exit_code(status: process::ExitStatus) -> i32 {
+    match (status.code(), exit_signal(status)) {
+        (Some(code), _) => code,
+        (None, Some(signal)) => 128 + signal,
+        (None, None) => 1,
+    }
+}
+
+/// Builds the error event for a failed command.
+pub fn make_event(
+    matches: &ArgMatches<'_>,
+    args: &[&str],
+    exit_status: process::ExitStatus,
+    elapsed: Duration,
+    usage: Option<ResourceUsage>,
+    tail: VecDeque<String>,
+) -> Result<Event<'static>, Error> {
+    let config = Config::current();
+    let exit_code = exit_status.code();
+    let status = describe_exit(exit_status);
+
+    let mut event = Event {
+        level: Level::Error,
+        sdk: Some(get_sdk_info()),
+        release: matches
+            .value_of("release")
+            .map(str::to_owned)
+            .or_else(|| detect_release_name().ok())
+            .map(Cow::from),
+        environment: matches
+            .value_of("environment")
+            .map(str::to_owned)
+            .or_else(|| config.get_environment())
+            .map(Cow::from),
+        user: get_user_name().ok().map(|n| User {
+            username: Some(n),
+            ip_address: Some(Default::default()),
+            ..Default::default()
+        }),
+        ..Event::default()
+    };
+
+    event.exception.values.push(Exception {
+        ty: "ProcessError".into(),
+        value: Some(format!("command {} {}", args[0], status)),
+        ..Default::default()
+    });
+
+    event.tags.insert("command".into(), args[0].into());
+    event.tags.insert(
+        "exit_code".into(),
+        exit_code.map_or_else(|| "signal".to_string(), |code| code.to_string()),
+    );
+    event.tags.insert("os".into(), env::consts::OS.into());
+    event.tags.insert("arch".into(), env::consts::ARCH.into());
+    if let Some(url) = detect_build_url() {
+        event.tags.insert("ci.build_url".into(), url);
+    }
+    for tag in matches.values_of("tags").unwrap_or_default() {
+        let mut split = tag.splitn(2, ':');
+        let key = split.next().ok_or_else(|| err_msg("missing tag key"))?;
+        let value = split.next().ok_or_else(|| err_msg("missing tag value"))?;
+        event.tags.insert(key.into(), value.into());
+    }
+
+    event.extra.insert(
+        "argv".into(),
+        Value::Array(args.iter().map(|&arg| arg.into()).collect()),
+    );
+    event.extra.insert(
+        "duration_ms".into(),
+        Value::from(elapsed.as_secs() * 1000 + u64::from(elapsed.subsec_millis())),
+    );
+    if let Some(code) = exit_code {
+        event.extra.insert("exit_code".into(), Value::from(code));
+    }
+    if let Some(usage) = usage {
+        event
+            .extra
+            .insert("resource_usage".into(), serde_json::to_value(usage)?);
+    }
+    if !tail.is_empty() {
+        event.extra.insert(
+            "stderr".into(),
+            Value::String(tail.into_iter().collect::<Vec<_>>().join("\n")),
+        );
+    }
+    if matches.is_present("send_environ") {
+        event.extra.insert(
+            "environ".into(),
+            Value::Object(
+                Sandbox::from_matches(matches)
+                    .environ()
+                    .into_iter()
+                    .map(|(k, v)| (k.to_string_lossy().into_owned(), v))
+                    .filter(|(k, _)| !is_secret_name(k))
+                    .map(|(k, v)| (k, Value::String(redact(&v.to_string_lossy()))))
+                    .collect(),
+            ),
+        );
+    }
+
+    Ok(event)
+}
+
+pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let config = Config::current();
+    let args: Vec<_> = matches.values_of("args").unwrap().collect();
+    let tail_lines: usize = matches.value_of("tail_lines").unwrap().parse().unwrap();
+
+    // resolve the DSN first so that a misconfiguration fails before the
+    // command runs rather than after.
+    let dsn = config.get_dsn()?;
+
+    let started = Instant::now();
+    let sandbox = Sandbox::from_matches(matches);
+    let (mut child, tee) = spawn_with_stderr_tail(&sandbox, &args, tail_lines)?;
+    let (exit_status, usage) = UsageTracker::new(&child).wait(&mut child)?;
+    let elapsed = started.elapsed();
+    let tail = tee.join().unwrap_or_default();
+
+    if exit_status.success() {
+        return Ok(());
+    }
+
+    let event = make_event(matches, &args, exit_status, elapsed, usage, tail)?;
+    let id = with_sentry_client(dsn, |c| c.capture_event(event, None));
+    eprintln!(
+        "{} command {}, reported as event {}",
+        style(">").dim(),
+        describe_exit(exit_status),
+        id
+    );
+
+    Err(QuietExit(exit_code(exit_status)).into())
+}
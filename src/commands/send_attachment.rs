@@ -0,0 +1,63 @@
+//! Implements a command for attaching files to events that were already sent.
+use std::path::PathBuf;
+
+use clap::{App, Arg, ArgMatches};
+use console::style;
+use failure::{bail, Error, ResultExt};
+use sentry::internals::Uuid;
+
+use crate::config::Config;
+use crate::utils::args::validate_uuid;
+use crate::utils::envelope::send_attachments;
+
+pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.about("Attach files to an event that was already sent.")
+        .long_about(
+            "Attach files to an event that was already sent.{n}{n}\
+             The files show up as attachments of the event, e.g. logs or core \
+             files gathered after the crash was reported.  The event has to belong \
+             to the project of the DSN.",
+        )
+        .arg(
+            Arg::with_name("event_id")
+                .long("event-id")
+                .value_name("EVENT_ID")
+                .required(true)
+                .validator(validate_uuid)
+                .help("The ID of the event to attach the files to."),
+        )
+        .arg(
+            Arg::with_name("paths")
+                .value_name("PATH")
+                .index(1)
+                .required(true)
+                .multiple(true)
+                .help("The files to attach."),
+        )
+}
+
+pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let config = Config::current();
+    let event_id: Uuid = matches.value_of("event_id").unwrap().parse()?;
+    let paths: Vec<PathBuf> = matches
+        .values_of("paths")
+        .unwrap()
+        .map(PathBuf::from)
+        .collect();
+    for path in &paths {
+        if !path.is_file() {
+            bail!("{} does not exist", path.display());
+        }
+    }
+
+    send_attachments(&config.get_dsn()?, event_id, &paths)
+        .context("Could not attach files to the event")?;
+    println!(
+        "Attached {} file{} to event {}",
+        paths.len(),
+        if paths.len() == 1 { "" } else { "s" },
+        style(event_id.to_simple()).cyan()
+    );
+
+    Ok(())
+}
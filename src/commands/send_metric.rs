@@ -0,0 +1,147 @@
+//! Implements a command for sending custom metrics to Sentry.
+use std::collections::BTreeMap;
+
+use chrono::Utc;
+use clap::{App, AppSettings, Arg, ArgMatches};
+use failure::{err_msg, Error};
+
+use crate::config::Config;
+use crate::utils::args::{get_timestamp, validate_timestamp};
+use crate::utils::envelope::send_metrics;
+use crate::utils::metrics::{Metric, MetricType};
+
+const METRIC_TYPES: &[(&str, MetricType, &str)] = &[
+    (
+        "increment",
+        MetricType::Counter,
+        "Increment a counter.  The value defaults to 1.",
+    ),
+    (
+        "distribution",
+        MetricType::Distribution,
+        "Add a value to a distribution, e.g. a duration or a size.",
+    ),
+    (
+        "gauge",
+        MetricType::Gauge,
+        "Set a gauge to its current value.",
+    ),
+    (
+        "set",
+        MetricType::Set,
+        "Add a value to a set that counts unique values.",
+    ),
+];
+
+fn metric_args<'a, 'b>(ty: MetricType) -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("name")
+            .long("name")
+            .short("n")
+            .value_name("NAME")
+            .required(true)
+            .help("The name of the metric."),
+        Arg::with_name("value")
+            .long("value")
+            .short("v")
+            .value_name("VALUE")
+            .required(ty != MetricType::Counter)
+            .allow_hyphen_values(true)
+            .help(if ty == MetricType::Set {
+                "The value to add to the set.  Values other than integers are hashed."
+            } else {
+                "The numeric value of the metric."
+            }),
+        Arg::with_name("unit")
+            .long("unit")
+            .short("u")
+            .value_name("UNIT")
+            .help("The unit of the value, e.g. `millisecond` or `byte`."),
+        Arg::with_name("tags")
+            .long("tag")
+            .short("t")
+            .value_name("KEY:VALUE")
+            .multiple(true)
+            .number_of_values(1)
+            .help("Add a tag (key:value) to the metric."),
+        Arg::with_name("release")
+            .long("release")
+            .short("r")
+            .value_name("RELEASE")
+            .help("Tag the metric with a release."),
+        Arg::with_name("environment")
+            .long("env")
+            .short("E")
+            .value_name("ENVIRONMENT")
+            .help("Tag the metric with an environment."),
+        Arg::with_name("timestamp")
+            .long("timestamp")
+            .value_name("TIMESTAMP")
+            .validator(validate_timestamp)
+            .help(
+                "The time the value was recorded in one of supported formats: unix \
+                 timestamp, RFC2822 or RFC3339. [defaults to now]",
+            ),
+    ]
+}
+
+pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
+    let mut app = app
+        .about("Send a custom metric to Sentry.")
+        .long_about(
+            "Send a custom metric to Sentry.{n}{n}\
+             Metrics are sent to the project of the DSN.  Like events, they are not \
+             guaranteed to arrive, check the debug output for transmission errors by \
+             passing --log-level=debug.",
+        )
+        .setting(AppSettings::SubcommandRequiredElseHelp);
+    for &(name, ty, about) in METRIC_TYPES {
+        app = app.subcommand(App::new(name).about(about).args(&metric_args(ty)));
+    }
+    app
+}
+
+fn parse_metric(ty: MetricType, matches: &ArgMatches<'_>) -> Result<Metric, Error> {
+    let mut tags = BTreeMap::new();
+    for tag in matches.values_of("tags").unwrap_or_default() {
+        let mut split = tag.splitn(2, ':');
+        let key = split.next().ok_or_else(|| err_msg("missing tag key"))?;
+        let value = split.next().ok_or_else(|| err_msg("missing tag value"))?;
+        tags.insert(key.to_string(), value.to_string());
+    }
+    if let Some(release) = matches.value_of("release") {
+        tags.insert("release".into(), release.into());
+    }
+    if let Some(environment) = matches.value_of("environment") {
+        tags.insert("environment".into(), environment.into());
+    }
+
+    Ok(Metric {
+        ty,
+        name: matches.value_of("name").unwrap().into(),
+        unit: matches.value_of("unit").map(str::to_string),
+        value: Metric::normalize_value(ty, matches.value_of("value").unwrap_or("1"))?,
+        tags,
+        timestamp: match matches.value_of("timestamp") {
+            Some(timestamp) => get_timestamp(timestamp)?,
+            None => Utc::now(),
+        },
+    })
+}
+
+pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let config = Config::current();
+    let (name, sub_matches) = matches.subcommand();
+    let sub_matches = sub_matches.unwrap();
+    let ty = METRIC_TYPES
+        .iter()
+        .find(|(type_name, ..)| *type_name == name)
+        .map(|(_, ty, _)| *ty)
+        .unwrap();
+
+    let metric = parse_metric(ty, sub_matches)?;
+    send_metrics(&config.get_dsn()?, std::slice::from_ref(&metric))?;
+    println!("Sent metric {}", metric);
+
+    Ok(())
+}
@@ -0,0 +1,170 @@
+//! Implements a command for sending recorded session replays to Sentry.
+use std::fs;
+use std::path::Path;
+
+use clap::{App, Arg, ArgMatches};
+use console::style;
+use failure::{bail, Error, ResultExt};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::utils::args::{validate_int, validate_uuid};
+use crate::utils::envelope::send_replay_segment;
+use crate::utils::event::get_sdk_info;
+use crate::utils::machine;
+
+pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.about("Send a recorded session replay to Sentry.")
+        .long_about(
+            "Send a recorded session replay to Sentry.{n}{n}\
+             Every path is a segment of the replay with the rrweb events recorded \
+             for it as a JSON array.  Segments are sent in the order given, each \
+             with the replay event describing the replay.  Replays recorded \
+             offline can be submitted this way from any machine that can reach \
+             Sentry.",
+        )
+        .arg(
+            Arg::with_name("segments")
+                .value_name("PATH")
+                .index(1)
+                .required(true)
+                .multiple(true)
+                .help("The paths to the recorded segments."),
+        )
+        .arg(
+            Arg::with_name("replay_event")
+                .long("replay-event")
+                .value_name("PATH")
+                .help(
+                    "The replay event written by the recorder, e.g. with the URLs and \
+                     the user of the replay.",
+                ),
+        )
+        .arg(
+            Arg::with_name("replay_id")
+                .long("replay-id")
+                .value_name("ID")
+                .validator(validate_uuid)
+                .help(
+                    "The ID of the replay. [defaults to the ID in the replay event or a new one]",
+                ),
+        )
+        .arg(
+            Arg::with_name("first_segment")
+                .long("first-segment")
+                .value_name("INDEX")
+                .default_value("0")
+                .validator(validate_int)
+                .help("The index of the first segment, to resume a partially sent replay."),
+        )
+        .arg(
+            Arg::with_name("release")
+                .long("release")
+                .short("r")
+                .value_name("RELEASE")
+                .help("The release of the recorded application."),
+        )
+        .arg(
+            Arg::with_name("environment")
+                .long("env")
+                .short("E")
+                .value_name("ENVIRONMENT")
+                .help("The environment the replay was recorded in."),
+        )
+}
+
+fn read_json(path: &Path) -> Result<Value, Error> {
+    let contents = fs::read(path).with_context(|_| format!("Could not read {}", path.display()))?;
+    Ok(serde_json::from_slice(&contents)
+        .with_context(|_| format!("Could not parse {}", path.display()))?)
+}
+
+/// Returns the range of the rrweb timestamps of a segment in seconds.
+fn segment_timestamps(path: &Path, recording: &Value) -> Result<(f64, f64), Error> {
+    let events = match recording.as_array() {
+        Some(events) => events,
+        None => bail!("{} is not a list of rrweb events", path.display()),
+    };
+    let timestamps = events
+        .iter()
+        .filter_map(|event| event["timestamp"].as_f64())
+        .map(|timestamp| timestamp / 1000.0);
+    match timestamps.fold(None, |range: Option<(f64, f64)>, timestamp| match range {
+        Some((start, end)) => Some((start.min(timestamp), end.max(timestamp))),
+        None => Some((timestamp, timestamp)),
+    }) {
+        Some(range) => Ok(range),
+        None => bail!("{} does not contain any rrweb events", path.display()),
+    }
+}
+
+pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let config = Config::current();
+    let dsn = config.get_dsn()?;
+
+    let mut replay_event = match matches.value_of("replay_event") {
+        Some(path) => read_json(Path::new(path))?,
+        None => json!({}),
+    };
+    if !replay_event.is_object() {
+        bail!("The replay event must be a JSON object");
+    }
+    let replay_id = match matches
+        .value_of("replay_id")
+        .or_else(|| replay_event["replay_id"].as_str())
+    {
+        Some(replay_id) => replay_id.parse::<Uuid>()?,
+        None => Uuid::new_v4(),
+    }
+    .to_simple()
+    .to_string();
+
+    for (key, default) in &[
+        ("type", json!("replay_event")),
+        ("replay_type", json!("session")),
+        ("platform", json!("javascript")),
+        ("urls", json!([])),
+        ("error_ids", json!([])),
+        ("trace_ids", json!([])),
+        ("sdk", json!(get_sdk_info())),
+    ] {
+        if replay_event[key].is_null() {
+            replay_event[key] = default.clone();
+        }
+    }
+    replay_event["replay_id"] = replay_id.as_str().into();
+    replay_event["event_id"] = replay_id.as_str().into();
+    if let Some(release) = matches.value_of("release") {
+        replay_event["release"] = release.into();
+    }
+    if let Some(environment) = matches
+        .value_of("environment")
+        .map(str::to_owned)
+        .or_else(|| config.get_environment())
+    {
+        replay_event["environment"] = environment.into();
+    }
+
+    let first_segment: u64 = matches.value_of("first_segment").unwrap().parse()?;
+    for (idx, path) in matches.values_of("segments").unwrap().enumerate() {
+        let path = Path::new(path);
+        let recording = read_json(path)?;
+        let (start, end) = segment_timestamps(path, &recording)?;
+        let segment_id = first_segment + idx as u64;
+
+        replay_event["segment_id"] = segment_id.into();
+        replay_event["timestamp"] = end.into();
+        if replay_event["replay_start_timestamp"].is_null() {
+            replay_event["replay_start_timestamp"] = start.into();
+        }
+        send_replay_segment(&dsn, &replay_event, &serde_json::to_vec(&recording)?)
+            .with_context(|_| format!("Could not send segment {}", segment_id))?;
+        println!("Sent segment {} ({})", segment_id, path.display());
+    }
+
+    machine::set_result("replay_id", replay_id.as_str());
+    println!("Sent replay {}", style(&replay_id).cyan());
+
+    Ok(())
+}
@@ -0,0 +1,223 @@
+//! Implements a command for reporting failed systemd units.
+use std::collections::BTreeMap;
+use std::process::Command;
+
+use chrono::{TimeZone, Utc};
+use clap::{App, Arg, ArgMatches};
+use failure::{bail, Error, ResultExt};
+use log::warn;
+use sentry::protocol::{Breadcrumb, Event, Exception, Level, Value};
+
+use crate::config::Config;
+use crate::utils::args::validate_int;
+use crate::utils::event::{get_sdk_info, with_sentry_client};
+use crate::utils::releases::detect_release_name;
+
+/// The unit properties sent along with the event.
+const PROPERTIES: &[&str] = &[
+    "Id",
+    "Description",
+    "ActiveState",
+    "SubState",
+    "Result",
+    "ExecMainCode",
+    "ExecMainStatus",
+    "ExecMainStartTimestamp",
+    "ExecMainExitTimestamp",
+    "NRestarts",
+    "InvocationID",
+    "FragmentPath",
+];
+
+pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.about("Report a failed systemd unit to Sentry.")
+        .long_about(
+            "Report a failed systemd unit to Sentry.{n}{n}\
+             This reads the properties of the unit with systemctl and its \
+             recent log lines with journalctl and sends them as an event.  It \
+             is meant to be started by `OnFailure=` from a template unit:{n}{n}    \
+             # /etc/systemd/system/sentry-report@.service{n}    \
+             [Service]{n}    \
+             Type=oneshot{n}    \
+             ExecStart=/usr/local/bin/sentry-cli systemd-hook %i{n}{n}\
+             and `OnFailure=sentry-report@%n.service` in the units to watch.",
+        )
+        .arg(
+            Arg::with_name("unit")
+                .value_name("UNIT")
+                .index(1)
+                .required(true)
+                .help("The name of the failed unit, e.g. `backup.service`."),
+        )
+        .arg(
+            Arg::with_name("lines")
+                .long("lines")
+                .short("n")
+                .value_name("LINES")
+                .default_value("100")
+                .validator(validate_int)
+                .help("The number of journal lines to send as breadcrumbs."),
+        )
+        .arg(
+            Arg::with_name("user")
+                .long("user")
+                .help("Query the user service manager instead of the system one."),
+        )
+}
+
+fn run_tool(cmd: &mut Command) -> Result<String, Error> {
+    let output = cmd
+        .output()
+        .with_context(|_| format!("Could not run {:?}", cmd))?;
+    if !output.status.success() {
+        bail!(
+            "{:?} failed: {}",
+            cmd,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Reads the unit properties from the output of `systemctl show`.
+fn parse_properties(output: &str) -> BTreeMap<String, String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut split = line.splitn(2, '=');
+            Some((split.next()?.to_string(), split.next()?.to_string()))
+        })
+        .filter(|(_, value)| !value.is_empty())
+        .collect()
+}
+
+fn level_from_priority(priority: Option<&str>) -> Level {
+    match priority.and_then(|p| p.parse::<u8>().ok()) {
+        Some(0..=2) => Level::Fatal,
+        Some(3) => Level::Error,
+        Some(4) => Level::Warning,
+        Some(7) => Level::Debug,
+        _ => Level::Info,
+    }
+}
+
+/// Converts the JSON output of `journalctl` into breadcrumbs.
+fn parse_journal(output: &str) -> Vec<Breadcrumb> {
+    let mut rv = vec![];
+    for line in output.lines() {
+        let entry: BTreeMap<String, Value> = match serde_json::from_str(line) {
+            Ok(entry) => entry,
+            Err(_) => continue,
+        };
+        let message = match entry.get("MESSAGE") {
+            Some(Value::String(message)) => message.clone(),
+            // journald stores messages that are not valid UTF-8 as bytes
+            Some(Value::Array(bytes)) => String::from_utf8_lossy(
+                &bytes
+                    .iter()
+                    .filter_map(|b| b.as_u64().map(|b| b as u8))
+                    .collect::<Vec<_>>(),
+            )
+            .into_owned(),
+            _ => continue,
+        };
+        let field = |name: &str| entry.get(name).and_then(Value::as_str);
+
+        let mut breadcrumb = Breadcrumb {
+            category: Some(field("SYSLOG_IDENTIFIER").unwrap_or("journal").into()),
+            message: Some(message),
+            level: level_from_priority(field("PRIORITY")),
+            ..Default::default()
+        };
+        if let Some(micros) = field("__REALTIME_TIMESTAMP").and_then(|t| t.parse::<i64>().ok()) {
+            breadcrumb.timestamp =
+                Utc.timestamp(micros / 1_000_000, (micros % 1_000_000) as u32 * 1000);
+        }
+        rv.push(breadcrumb);
+    }
+    rv
+}
+
+pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let config = Config::current();
+    let unit = matches.value_of("unit").unwrap();
+    let lines = matches.value_of("lines").unwrap();
+    let scope = if matches.is_present("user") {
+        "--user"
+    } else {
+        "--system"
+    };
+
+    let properties = parse_properties(&run_tool(
+        Command::new("systemctl")
+            .arg("show")
+            .arg(scope)
+            .arg(format!("--property={}", PROPERTIES.join(",")))
+            .arg("--")
+            .arg(unit),
+    )?);
+    let journal = match run_tool(
+        Command::new("journalctl")
+            .arg(scope)
+            .args(["--output=json", "--no-pager", "--lines"])
+            .arg(lines)
+            .arg("--unit")
+            .arg(unit),
+    ) {
+        Ok(output) => parse_journal(&output),
+        Err(err) => {
+            warn!("Could not read the journal: {}", err);
+            vec![]
+        }
+    };
+
+    let unit = properties.get("Id").map_or(unit, String::as_str);
+    let result = properties.get("Result").map_or("unknown", String::as_str);
+    let exit_code = properties.get("ExecMainStatus");
+
+    let mut event = Event {
+        level: Level::Error,
+        sdk: Some(get_sdk_info()),
+        release: detect_release_name().ok().map(Into::into),
+        environment: config.get_environment().map(Into::into),
+        fingerprint: vec![
+            "systemd".into(),
+            unit.to_string().into(),
+            result.to_string().into(),
+        ]
+        .into(),
+        ..Event::default()
+    };
+    event.exception.values.push(Exception {
+        ty: "SystemdUnitFailure".into(),
+        value: Some(match exit_code {
+            Some(code) => format!(
+                "unit {} failed with result {} (status {})",
+                unit, result, code
+            ),
+            None => format!("unit {} failed with result {}", unit, result),
+        }),
+        ..Default::default()
+    });
+
+    event.tags.insert("unit".into(), unit.into());
+    event.tags.insert("result".into(), result.into());
+    if let Some(code) = exit_code {
+        event.tags.insert("exit_code".into(), code.clone());
+    }
+    event.extra.insert(
+        "properties".into(),
+        Value::Object(
+            properties
+                .iter()
+                .map(|(key, value)| (key.clone(), Value::String(value.clone())))
+                .collect(),
+        ),
+    );
+    event.breadcrumbs.values = journal;
+
+    let id = with_sentry_client(config.get_dsn()?, |c| c.capture_event(event, None));
+    println!("{}", id);
+
+    Ok(())
+}
@@ -0,0 +1,142 @@
+//! Implements a command for managing teams.
+use clap::{App, AppSettings, Arg, ArgMatches};
+use failure::{bail, Error};
+
+use crate::api::Api;
+use crate::config::Config;
+use crate::utils::args::ArgExt;
+use crate::utils::formatting::Table;
+
+pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
+    let team_arg = Arg::with_name("team")
+        .value_name("TEAM")
+        .required(true)
+        .index(1)
+        .help("The slug of the team.");
+
+    app.about("Manage teams on Sentry.")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .org_arg()
+        .subcommand(App::new("list").about("List all teams of an organization."))
+        .subcommand(
+            App::new("create")
+                .about("Create a new team.")
+                .arg(
+                    Arg::with_name("name")
+                        .value_name("NAME")
+                        .required(true)
+                        .index(1)
+                        .help("The name of the new team."),
+                )
+                .arg(
+                    Arg::with_name("slug")
+                        .long("slug")
+                        .value_name("SLUG")
+                        .help("The slug of the team. [defaults to a slug derived from the name]"),
+                ),
+        )
+        .subcommand(
+            App::new("add-member")
+                .about("Add organization members to a team.")
+                .arg(team_arg.clone())
+                .arg(
+                    Arg::with_name("members")
+                        .value_name("MEMBER")
+                        .required(true)
+                        .multiple(true)
+                        .index(2)
+                        .help("The email address or the member ID of an organization member."),
+                ),
+        )
+        .subcommand(
+            App::new("add-project")
+                .about("Give a team access to projects.")
+                .arg(team_arg)
+                .arg(
+                    Arg::with_name("projects")
+                        .value_name("PROJECT")
+                        .required(true)
+                        .multiple(true)
+                        .index(2)
+                        .help("The slug of a project."),
+                ),
+        )
+}
+
+fn execute_list(org: &str) -> Result<(), Error> {
+    let mut teams = Api::current().list_organization_teams(org)?;
+    teams.sort_by_key(|t| t.slug.clone());
+
+    let mut table = Table::new();
+    table.title_row().add("ID").add("Slug").add("Name");
+
+    for team in &teams {
+        table
+            .add_row()
+            .add(&team.id)
+            .add(&team.slug)
+            .add(&team.name);
+    }
+
+    table.print();
+
+    Ok(())
+}
+
+fn execute_add_member(org: &str, matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let api = Api::current();
+    let team = matches.value_of("team").unwrap();
+
+    for member in matches.values_of("members").unwrap() {
+        let member_id = if member.contains('@') {
+            match api
+                .list_organization_members(org, Some(&format!("email:{}", member)))?
+                .into_iter()
+                .find(|m| m.email.eq_ignore_ascii_case(member))
+            {
+                Some(m) => m.id,
+                None => bail!("{} is not a member of organization {}", member, org),
+            }
+        } else {
+            member.to_owned()
+        };
+        api.add_team_member(org, team, &member_id)?;
+        println!("Added {} to team {}", member, team);
+    }
+
+    Ok(())
+}
+
+fn execute_add_project(org: &str, matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let api = Api::current();
+    let team = matches.value_of("team").unwrap();
+
+    for project in matches.values_of("projects").unwrap() {
+        api.add_team_project(org, team, project)?;
+        println!("Gave team {} access to project {}", team, project);
+    }
+
+    Ok(())
+}
+
+pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let config = Config::current();
+    let org = config.get_org(matches)?;
+
+    if let Some(sub_matches) = matches.subcommand_matches("create") {
+        let team = Api::current().create_team(
+            &org,
+            sub_matches.value_of("name").unwrap(),
+            sub_matches.value_of("slug"),
+        )?;
+        println!("Created team {} ({})", team.slug, team.id);
+        return Ok(());
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("add-member") {
+        return execute_add_member(&org, sub_matches);
+    }
+    if let Some(sub_matches) = matches.subcommand_matches("add-project") {
+        return execute_add_project(&org, sub_matches);
+    }
+    execute_list(&org)
+}
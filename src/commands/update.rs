@@ -3,8 +3,12 @@ use std::env;
 
 use clap::{App, AppSettings, Arg, ArgMatches};
 use failure::{bail, Error};
+use serde_json::json;
 
-use crate::utils::update::{assert_updatable, can_update_sentrycli, get_latest_sentrycli_release};
+use crate::config::Config;
+use crate::utils::update::{
+    assert_updatable, can_update_sentrycli, get_latest_sentrycli_release, SentryCliUpdateInfo,
+};
 
 pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
     app.about("Update the sentry-cli executable.")
@@ -19,22 +23,72 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
                 .short("f")
                 .help("Force the update even if the latest version is already installed."),
         )
+        .arg(
+            Arg::with_name("channel")
+                .long("channel")
+                .value_name("CHANNEL")
+                .possible_values(&["stable", "beta"])
+                .help(
+                    "The release channel to update from.{n}\
+                     Defaults to the `update.channel` setting or stable.",
+                ),
+        )
+        .arg(
+            Arg::with_name("check_only")
+                .long("check-only")
+                .alias("update-check-only")
+                .help(
+                    "Print the latest release of the channel and its checksum \
+                     as JSON instead of installing it.",
+                ),
+        )
+}
+
+fn print_update_info(update: &SentryCliUpdateInfo) -> Result<(), Error> {
+    let info = json!({
+        "channel": update.channel().name(),
+        "current_version": update.current_version(),
+        "latest_version": update.latest_version(),
+        "update_available": !update.is_latest_version(),
+        "download_url": update.download_url()?,
+        "sha256": update.sha256(),
+        "updatable": can_update_sentrycli(),
+    });
+    println!("{}", serde_json::to_string_pretty(&info)?);
+    Ok(())
 }
 
 pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
     // Disable update check in case of errors
     env::set_var("SENTRY_DISABLE_UPDATE_CHECK", "true");
 
+    let channel = match matches.value_of("channel") {
+        Some(channel) => channel.parse()?,
+        None => Config::current().get_update_channel()?,
+    };
+
+    if matches.is_present("check_only") {
+        let update = get_latest_sentrycli_release(channel)?;
+        if !update.have_version_info() {
+            bail!("Could not get the latest release version.");
+        }
+        return print_update_info(&update);
+    }
+
     // Aborts with an error if this installation is not updatable.
     assert_updatable()?;
 
     let exe = env::current_exe()?;
-    let update = get_latest_sentrycli_release()?;
+    let update = get_latest_sentrycli_release(channel)?;
     if !update.have_version_info() {
         bail!("Could not get the latest release version.");
     }
 
-    println!("Latest release is {}", update.latest_version());
+    println!(
+        "Latest {} release is {}",
+        channel.name(),
+        update.latest_version()
+    );
     if update.is_latest_version() {
         if matches.is_present("force") {
             println!("Forcing update");
@@ -1,25 +1,37 @@
 //! Implements a command for uploading dSYM files.
 use std::collections::BTreeSet;
 use std::env;
+use std::path::{Path, PathBuf};
 use std::str::{self, FromStr};
+use std::thread;
+use std::time::Duration;
 
 use clap::{App, Arg, ArgMatches};
 use console::style;
 use failure::{bail, err_msg, Error};
 use log::info;
-use symbolic::common::DebugId;
+use serde_json::json;
+use symbolic::common::{Arch, DebugId};
 use symbolic::debuginfo::FileFormat;
 
 use crate::api::Api;
 use crate::config::Config;
-use crate::utils::args::{validate_id, ArgExt};
+use crate::utils::annotations::{add_summary, web_link};
+use crate::utils::args::{validate_arch, validate_id, ArgExt};
+use crate::utils::cancel;
 use crate::utils::dif::ObjectDifFeatures;
 use crate::utils::dif_upload::{DifFormat, DifUpload};
+use crate::utils::fs::{extract_zip, TempDir};
+use crate::utils::machine;
 use crate::utils::progress::{ProgressBar, ProgressStyle};
+use crate::utils::releases::detect_release_name;
+use crate::utils::remote_source::{fetch_source, is_remote};
 use crate::utils::system::QuietExit;
+use crate::utils::watch::DirWatcher;
 use crate::utils::xcode::{InfoPlist, MayDetach};
 
 static DERIVED_DATA: &str = "Library/Developer/Xcode/DerivedData";
+static DIF_UPLOAD_LOG: &str = "sentry-dif-upload.log";
 
 pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
     app.about("Upload debugging information files.")
@@ -27,7 +39,19 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
         .arg(
             Arg::with_name("paths")
                 .value_name("PATH")
-                .help("A path to search recursively for symbol files.")
+                .help(
+                    "A path to search recursively for symbol files.{n}\
+                     This includes the contents of XCFrameworks, SwiftPM build \
+                     artifacts and the object files of static libraries.  \
+                     ZIP files are unpacked and only the dSYMs of Xcode \
+                     archives (.xcarchive) are searched.  For APKs, split APK \
+                     sets (.apks) and Android App Bundles (.aab), the native \
+                     libraries and their debug symbols in BUNDLE-METADATA are \
+                     searched, ART profiles (.prof) are not uploaded.{n}\
+                     Files can also be fetched from s3://, gs:// and http(s):// \
+                     URLs, a bucket URL ending with a slash fetches everything \
+                     below it.",
+                )
                 .multiple(true)
                 .number_of_values(1)
                 .index(1),
@@ -97,6 +121,19 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
                 .multiple(true)
                 .number_of_values(1),
         )
+        .arg(
+            Arg::with_name("archs")
+                .long("arch")
+                .value_name("ARCH")
+                .validator(validate_arch)
+                .multiple(true)
+                .number_of_values(1)
+                .help(
+                    "Only upload slices of the given architecture, e.g. `arm64`.{n}\
+                     Can be supplied multiple times.  This allows to skip slices \
+                     of universal binaries that are not shipped.",
+                ),
+        )
         .arg(
             Arg::with_name("require_all")
                 .long("require-all")
@@ -113,16 +150,27 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
                      available.",
                 ),
         )
+        .arg(Arg::with_name("derived_data").long("derived-data").help(
+            "Search for debug symbols in Xcode's derived data.{n}\
+                     When run from an Xcode build phase, only the dSYMs of the \
+                     current build in DWARF_DSYM_FOLDER_PATH are searched.",
+        ))
         .arg(
-            Arg::with_name("derived_data")
-                .long("derived-data")
-                .help("Search for debug symbols in Xcode's derived data."),
+            Arg::with_name("log_file")
+                .long("log-file")
+                .value_name("PATH")
+                .help(
+                    "Write the output of the background upload to this file.{n}\
+                     Defaults to sentry-dif-upload.log in PROJECT_TEMP_DIR when \
+                     running with --derived-data from an Xcode build phase.",
+                ),
         )
         .arg(
             Arg::with_name("no_zips")
                 .long("no-zips")
                 .help("Do not search in ZIP files."),
         )
+        .ignore_args()
         .arg(
             Arg::with_name("info_plist")
                 .long("info-plist")
@@ -158,6 +206,21 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
                      will be shown in the Xcode build output.",
                 ),
         )
+        .arg(
+            Arg::with_name("size_report")
+                .long("size-report")
+                .help("Print the sizes of the found files grouped by architecture."),
+        )
+        .arg(
+            Arg::with_name("send_size_report")
+                .long("send-size-report")
+                .help(
+                    "Send the size report as an event to the project of the \
+                     configured DSN.{n}The event carries the release and one \
+                     measurement per architecture, so that symbol growth can \
+                     be tracked across releases.",
+                ),
+        )
         .arg(
             Arg::with_name("include_sources")
                 .long("include-sources")
@@ -186,7 +249,110 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
         )
 }
 
-fn execute_internal(matches: &ArgMatches<'_>, legacy: bool) -> Result<(), Error> {
+/// Creates the app of `debug-files watch`, which takes the arguments of
+/// `upload-dif` and the poll interval.
+pub fn make_watch_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
+    make_app(app)
+        .about("Upload debug information files as they appear in directories.")
+        .long_about(
+            "Upload debug information files as they appear in directories.{n}{n}\
+             The debug information files in the given directories are uploaded \
+             first, then new and modified files are uploaded until the command \
+             is stopped.  Files whose checksums Sentry already knows are not \
+             uploaded again.  This is useful to upload the output of build \
+             systems that cannot invoke sentry-cli directly.",
+        )
+        .arg(
+            Arg::with_name("interval")
+                .long("interval")
+                .value_name("SECONDS")
+                .validator(validate_interval)
+                .help("The interval in seconds to check for new files. [defaults to 5]"),
+        )
+}
+
+fn validate_interval(s: String) -> Result<(), String> {
+    match s.parse::<u64>() {
+        Ok(secs) if secs > 0 => Ok(()),
+        _ => Err("Invalid interval, expected a positive number of seconds".to_string()),
+    }
+}
+
+/// Uploads debug information files that appear in the search paths of the
+/// given upload until the process is terminated.
+///
+/// Every upload first asks Sentry which checksums it knows already, so
+/// rewriting an unchanged file does not cause another upload.
+fn watch_search_paths(upload: &DifUpload, interval: Duration) -> Result<(), Error> {
+    let mut watcher = DirWatcher::new(upload.get_search_paths().to_vec());
+
+    println!(
+        "{} Watching for new debug information files. Press Ctrl+C to stop.",
+        style(">").dim()
+    );
+
+    loop {
+        thread::sleep(interval);
+
+        // Files might have been removed again in the meanwhile
+        let paths: Vec<_> = watcher.poll().into_iter().filter(|p| p.is_file()).collect();
+        if paths.is_empty() {
+            continue;
+        }
+
+        let mut batch = upload.clone();
+        batch.clear_search_paths().search_paths(&paths);
+        if let Err(err) = batch.upload() {
+            eprintln!("{} {}", style("error:").red(), err);
+            watcher.retry(&paths);
+        }
+    }
+}
+
+/// Resolves the paths to search for a given path argument.
+///
+/// ZIP files and split APK sets are unpacked to a temporary directory and
+/// Xcode archives are narrowed down to the folders containing debug symbols.
+fn resolve_search_path(
+    path: &Path,
+    matches: &ArgMatches<'_>,
+    temp_dirs: &mut Vec<TempDir>,
+) -> Result<Vec<PathBuf>, Error> {
+    let is_archive =
+        path.extension() == Some("zip".as_ref()) || path.extension() == Some("apks".as_ref());
+    if path.is_file() && is_archive && !matches.is_present("no_zips") {
+        let temp_dir = TempDir::create()?;
+        info!(
+            "unpacking {} to {}",
+            path.display(),
+            temp_dir.path().display()
+        );
+        extract_zip(path, temp_dir.path())?;
+        let rv = vec![temp_dir.path().to_path_buf()];
+        temp_dirs.push(temp_dir);
+        return Ok(rv);
+    }
+
+    if path.is_dir() && path.extension() == Some("xcarchive".as_ref()) {
+        let rv: Vec<_> = ["dSYMs", "BCSymbolMaps"]
+            .iter()
+            .map(|dir| path.join(dir))
+            .filter(|dir| dir.is_dir())
+            .collect();
+        if !rv.is_empty() {
+            return Ok(rv);
+        }
+    }
+
+    Ok(vec![path.to_path_buf()])
+}
+
+fn execute_internal(
+    matches: &ArgMatches<'_>,
+    legacy: bool,
+    extra_paths: &[PathBuf],
+    watch: Option<Duration>,
+) -> Result<(), Error> {
     let api = Api::current();
     let config = Config::current();
     let (org, project) = config.get_org_and_project(matches)?;
@@ -205,9 +371,33 @@ fn execute_internal(matches: &ArgMatches<'_>, legacy: bool) -> Result<(), Error>
     let mut upload = DifUpload::new(org.clone(), project.clone());
     upload
         .wait(matches.is_present("wait"))
-        .search_paths(matches.values_of("paths").unwrap_or_default())
+        .search_paths(extra_paths)
         .allow_zips(!matches.is_present("no_zips"))
-        .filter_ids(ids);
+        .ignores(matches.values_of("ignore").unwrap_or_default())
+        .ignore_file(matches.value_of("ignore_file").unwrap_or(""))
+        .filter_ids(ids)
+        .filter_archs(
+            matches
+                .values_of("archs")
+                .unwrap_or_default()
+                .filter_map(|s| Arch::from_str(s).ok()),
+        );
+
+    // Archives are unpacked into temporary directories, which need to live
+    // until the upload has finished.
+    let mut temp_dirs = vec![];
+    for path in matches.values_of("paths").unwrap_or_default() {
+        let path = if is_remote(path) {
+            let temp_dir = TempDir::create()?;
+            let local = fetch_source(path, temp_dir.path())?;
+            temp_dirs.push(temp_dir);
+            local
+        } else {
+            PathBuf::from(path)
+        };
+        let paths = resolve_search_path(&path, matches, &mut temp_dirs)?;
+        upload.search_paths(paths);
+    }
 
     if legacy {
         // Configure `upload-dsym` behavior (only dSYM files)
@@ -220,7 +410,7 @@ fn execute_internal(matches: &ArgMatches<'_>, legacy: bool) -> Result<(), Error>
                 sources: false,
             });
 
-        if !matches.is_present("paths") {
+        if !matches.is_present("paths") && extra_paths.is_empty() {
             if let Some(dsym_path) = env::var_os("DWARF_DSYM_FOLDER_PATH") {
                 upload.search_path(dsym_path);
             }
@@ -266,16 +456,27 @@ fn execute_internal(matches: &ArgMatches<'_>, legacy: bool) -> Result<(), Error>
             .map_err(|_| err_msg("--symbol-maps requires Apple dsymutil to be available."))?;
     }
 
-    // Add a path to XCode's DerivedData, if configured
+    // Add a path to XCode's DerivedData, if configured.  Inside a build phase
+    // only the dSYMs that were just built are of interest.
+    let build_dsym_path = env::var_os("DWARF_DSYM_FOLDER_PATH").map(PathBuf::from);
     if matches.is_present("derived_data") {
-        let derived_data = dirs::home_dir().map(|x| x.join(DERIVED_DATA));
-        if let Some(path) = derived_data {
+        if let Some(ref path) = build_dsym_path {
+            upload.search_path(path);
+        } else if let Some(path) = dirs::home_dir().map(|x| x.join(DERIVED_DATA)) {
             if path.is_dir() {
                 upload.search_path(path);
             }
         }
     }
 
+    let log_file = matches.value_of("log_file").map(PathBuf::from).or_else(|| {
+        if matches.is_present("derived_data") && build_dsym_path.is_some() {
+            env::var_os("PROJECT_TEMP_DIR").map(|dir| Path::new(&dir).join(DIF_UPLOAD_LOG))
+        } else {
+            None
+        }
+    });
+
     // Try to resolve the Info.plist either by path or from Xcode
     let info_plist = match matches.value_of("info_plist") {
         Some(path) => Some(InfoPlist::from_path(path)?),
@@ -287,14 +488,61 @@ fn execute_internal(matches: &ArgMatches<'_>, legacy: bool) -> Result<(), Error>
         return Ok(());
     }
 
+    // Resolve the DSN before uploading, so that a missing DSN fails early
+    let dsn = if matches.is_present("send_size_report") {
+        Some(config.get_dsn()?)
+    } else {
+        None
+    };
+
     MayDetach::wrap("Debug symbol upload", |handle| {
         // Optionally detach if run from Xcode
-        if !matches.is_present("force_foreground") {
+        if !matches.is_present("force_foreground") && watch.is_none() {
+            if let Some(ref log_file) = log_file {
+                handle.set_log_file(log_file);
+            }
             handle.may_detach()?;
         }
 
         // Execute the upload
+        let _hint = cancel::resume_hint(
+            "Run the upload again to resume it, debug files and chunks that were \
+             already uploaded are skipped."
+                .to_string(),
+        );
         let (uploaded, has_processing_errors) = upload.upload()?;
+        for dif in &uploaded {
+            machine::push_result(
+                "debug_files",
+                json!({
+                    "debug_id": dif.id().to_string(),
+                    "object_name": dif.object_name,
+                    "cpu_name": dif.cpu_name,
+                }),
+            );
+        }
+        add_summary(&format!(
+            "- Uploaded {} debug information files to {}",
+            uploaded.len(),
+            web_link(
+                &format!("{}/{}", org, project),
+                &["settings", &org, "projects", &project, "debug-symbols"]
+            )
+        ));
+
+        if matches.is_present("size_report") || matches.is_present("send_size_report") {
+            if let Some(report) = upload.size_report().filter(|r| !r.is_empty()) {
+                report.print();
+                if let Some(ref dsn) = dsn {
+                    let release = match info_plist {
+                        Some(ref info_plist) => Some(info_plist.get_release_name_with_build()),
+                        None => detect_release_name().ok(),
+                    };
+                    let id = report.send(dsn.clone(), release.as_deref());
+                    println!("{} Sent size report {}", style(">").dim(), id);
+                }
+            }
+        }
 
         // Associate the dSYMs with the Info.plist data, if available
         if let Some(ref info_plist) = info_plist {
@@ -362,14 +610,38 @@ fn execute_internal(matches: &ArgMatches<'_>, legacy: bool) -> Result<(), Error>
             return Err(QuietExit(1).into());
         }
 
+        if let Some(interval) = watch {
+            watch_search_paths(&upload, interval)?;
+        }
+
         Ok(())
     })
 }
 
 pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
-    execute_internal(matches, false)
+    execute_internal(matches, false, &[], None)
+}
+
+/// Runs `debug-files watch`.
+pub fn execute_watch(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    if matches.values_of("paths").is_none() {
+        bail!("Specify the directories to watch.");
+    }
+    if matches.is_present("no_upload") {
+        bail!("--no-upload cannot be used while watching for files.");
+    }
+    let interval = matches
+        .value_of("interval")
+        .map_or(5, |s| s.parse().unwrap());
+    execute_internal(matches, false, &[], Some(Duration::from_secs(interval)))
 }
 
 pub fn execute_legacy(matches: &ArgMatches<'_>) -> Result<(), Error> {
-    execute_internal(matches, true)
+    execute_internal(matches, true, &[], None)
+}
+
+/// Runs the `upload-dsym` behavior on additional search paths, e.g. dSYMs
+/// that were downloaded before.
+pub fn execute_legacy_with_paths(matches: &ArgMatches<'_>, paths: &[PathBuf]) -> Result<(), Error> {
+    execute_internal(matches, true, paths, None)
 }
@@ -1,9 +1,17 @@
 //! Implements a command for uploading dSYM files.
+use std::env;
+use std::fs;
+
 use clap::{App, AppSettings, Arg, ArgMatches};
-use failure::Error;
+use console::style;
+use failure::{bail, Error};
 
+use crate::api::Api;
 use crate::commands::upload_dif;
-use crate::utils::args::{validate_uuid, ArgExt};
+use crate::utils::appstore::{AppStoreConnectKey, APP_STORE_CONNECT_URL};
+use crate::utils::args::{validate_arch, validate_uuid, ArgExt};
+use crate::utils::fs::TempDir;
+use crate::utils::xcode::InfoPlist;
 
 pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
     app.about("DEPRECATED: Upload Mac debug symbols to a project.")
@@ -26,6 +34,19 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
                 .multiple(true)
                 .number_of_values(1),
         )
+        .arg(
+            Arg::with_name("archs")
+                .long("arch")
+                .value_name("ARCH")
+                .validator(validate_arch)
+                .multiple(true)
+                .number_of_values(1)
+                .help(
+                    "Only upload slices of the given architecture, e.g. `arm64`.{n}\
+                     Can be supplied multiple times.  This allows to skip slices \
+                     of universal binaries that are not shipped.",
+                ),
+        )
         .arg(
             Arg::with_name("require_all")
                 .long("require-all")
@@ -80,8 +101,98 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
                      will be shown in the Xcode build output.",
                 ),
         )
+        .arg(
+            Arg::with_name("download_from_appstore")
+                .long("download-from-appstore")
+                .requires_all(&["issuer_id", "key_id", "key", "app_id"])
+                .help(
+                    "Download the dSYMs of a build from App Store Connect and \
+                     upload them.  This requires an App Store Connect API key.",
+                ),
+        )
+        .arg(
+            Arg::with_name("issuer_id")
+                .long("issuer-id")
+                .value_name("ID")
+                .help("The issuer ID of the App Store Connect API key."),
+        )
+        .arg(
+            Arg::with_name("key_id")
+                .long("key-id")
+                .value_name("ID")
+                .help("The ID of the App Store Connect API key."),
+        )
+        .arg(
+            Arg::with_name("key")
+                .long("key")
+                .value_name("PATH")
+                .help("The path to the private key (.p8) of the App Store Connect API key."),
+        )
+        .arg(
+            Arg::with_name("app_id")
+                .long("app-id")
+                .value_name("ID")
+                .help("The Apple ID of the app on App Store Connect."),
+        )
+        .arg(
+            Arg::with_name("build")
+                .long("build")
+                .value_name("BUILD")
+                .help(
+                    "The build number to download dSYMs for.{n}\
+                     Defaults to the CFBundleVersion of the Info.plist.",
+                ),
+        )
+}
+
+fn execute_download(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let api = Api::current();
+    let key = AppStoreConnectKey::from_path(
+        matches.value_of("issuer_id").unwrap(),
+        matches.value_of("key_id").unwrap(),
+        matches.value_of("key").unwrap(),
+    )?;
+    let app_id = matches.value_of("app_id").unwrap();
+    let build = match matches.value_of("build") {
+        Some(build) => build.to_string(),
+        None => match matches.value_of("info_plist") {
+            Some(path) => InfoPlist::from_path(path)?.build().to_string(),
+            None => match InfoPlist::discover_from_env()? {
+                Some(plist) => plist.build().to_string(),
+                None => bail!("Could not determine the build. Provide it with --build."),
+            },
+        },
+    };
+
+    let api_url =
+        env::var("APP_STORE_CONNECT_API_URL").unwrap_or_else(|_| APP_STORE_CONNECT_URL.to_string());
+    let dsym_urls = api.get_app_store_dsym_urls(&api_url, &key.token()?, app_id, &build)?;
+    if dsym_urls.is_empty() {
+        bail!("App Store Connect has no dSYMs for build {} yet.", build);
+    }
+
+    println!(
+        "{} Downloading {} dSYM archives for build {}",
+        style(">").dim(),
+        style(dsym_urls.len()).yellow(),
+        style(&build).cyan()
+    );
+    let dir = TempDir::create()?;
+    for (idx, url) in dsym_urls.iter().enumerate() {
+        // debug file search only looks into files with a zip extension
+        let path = dir.path().join(format!("dsyms-{}.zip", idx));
+        let resp = api.download_with_progress(url, &mut fs::File::create(&path)?)?;
+        if resp.failed() {
+            bail!("Could not download dSYMs from {}", url);
+        }
+    }
+
+    upload_dif::execute_legacy_with_paths(matches, &[dir.path().to_path_buf()])
 }
 
 pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    if matches.is_present("download_from_appstore") {
+        return execute_download(matches);
+    }
     upload_dif::execute_legacy(matches)
 }
@@ -0,0 +1,157 @@
+//! Implements a command for uploading minidumps of native crashes.
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use clap::{App, Arg, ArgMatches};
+use failure::{bail, err_msg, Error, ResultExt};
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+
+use crate::api::Api;
+use crate::config::Config;
+use crate::utils::event::get_sdk_info;
+use crate::utils::machine;
+
+pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.about("Upload a minidump of a native crash to Sentry.")
+        .long_about(
+            "Upload a minidump of a native crash to Sentry.{n}{n}\
+             The minidump is sent to the project of the DSN, which processes it into \
+             an event.  The ID of that event is printed once the upload is done.",
+        )
+        .arg(
+            Arg::with_name("path")
+                .value_name("PATH")
+                .index(1)
+                .required(true)
+                .help("The path to the minidump."),
+        )
+        .arg(
+            Arg::with_name("attachments")
+                .long("attach")
+                .short("a")
+                .value_name("PATH")
+                .multiple(true)
+                .number_of_values(1)
+                .help("Store a file, e.g. a log, with the event.  Can be given multiple times."),
+        )
+        .arg(
+            Arg::with_name("release")
+                .long("release")
+                .short("r")
+                .value_name("RELEASE")
+                .help("The release of the crashed program."),
+        )
+        .arg(
+            Arg::with_name("dist")
+                .long("dist")
+                .short("d")
+                .value_name("DISTRIBUTION")
+                .help("Set the distribution."),
+        )
+        .arg(
+            Arg::with_name("environment")
+                .long("env")
+                .short("E")
+                .value_name("ENVIRONMENT")
+                .help("The environment the program ran in."),
+        )
+        .arg(
+            Arg::with_name("tags")
+                .long("tag")
+                .short("t")
+                .value_name("KEY:VALUE")
+                .multiple(true)
+                .number_of_values(1)
+                .help("Add a tag (key:value) to the event."),
+        )
+        .arg(
+            Arg::with_name("extra")
+                .long("extra")
+                .short("e")
+                .value_name("KEY:VALUE")
+                .multiple(true)
+                .number_of_values(1)
+                .help("Add extra information (key:value) to the event."),
+        )
+}
+
+fn check_minidump(path: &Path) -> Result<(), Error> {
+    let mut magic = [0; 4];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .with_context(|_| format!("Could not read {}", path.display()))?;
+    if &magic != b"MDMP" {
+        bail!("{} is not a minidump", path.display());
+    }
+    Ok(())
+}
+
+fn parse_pairs(matches: &ArgMatches<'_>, name: &str, what: &str) -> Result<Value, Error> {
+    let mut rv = Map::new();
+    for pair in matches.values_of(name).unwrap_or_default() {
+        let mut split = pair.splitn(2, ':');
+        let key = split
+            .next()
+            .ok_or_else(|| err_msg(format!("missing {} key", what)))?;
+        let value = split
+            .next()
+            .ok_or_else(|| err_msg(format!("missing {} value", what)))?;
+        rv.insert(key.into(), value.into());
+    }
+    Ok(Value::Object(rv))
+}
+
+pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let config = Config::current();
+    let path = Path::new(matches.value_of("path").unwrap());
+    check_minidump(path)?;
+
+    let attachments: Vec<PathBuf> = matches
+        .values_of("attachments")
+        .unwrap_or_default()
+        .map(PathBuf::from)
+        .collect();
+    for attachment in &attachments {
+        if !attachment.is_file() {
+            bail!("Attachment {} does not exist", attachment.display());
+        }
+    }
+
+    let mut event = json!({
+        "platform": "native",
+        "sdk": get_sdk_info(),
+        "tags": parse_pairs(matches, "tags", "tag")?,
+        "extra": parse_pairs(matches, "extra", "extra")?,
+    });
+    if let Some(release) = matches.value_of("release") {
+        event["release"] = release.into();
+    }
+    if let Some(dist) = matches.value_of("dist") {
+        event["dist"] = dist.into();
+    }
+    if let Some(environment) = matches
+        .value_of("environment")
+        .map(str::to_owned)
+        .or_else(|| config.get_environment())
+    {
+        event["environment"] = environment.into();
+    }
+
+    let event_id = Api::current().upload_minidump(
+        &config.get_dsn()?,
+        path,
+        &attachments,
+        &event.to_string(),
+    )?;
+    // relay answers with the hyphenated form, the web interface uses the simple one
+    let event_id = match Uuid::parse_str(&event_id) {
+        Ok(uuid) => uuid.to_simple().to_string(),
+        Err(_) => event_id,
+    };
+    machine::set_result("event_id", event_id.as_str());
+    println!("{}", event_id);
+
+    Ok(())
+}
@@ -0,0 +1,205 @@
+//! Implements a command for uploading profiles of batch jobs.
+use std::fs;
+
+use chrono::{Duration, Utc};
+use clap::{App, Arg, ArgMatches};
+use console::style;
+use failure::{Error, ResultExt};
+use sentry::internals::Uuid;
+use serde_json::json;
+
+use crate::config::Config;
+use crate::utils::args::{get_timestamp, validate_int, validate_timestamp};
+use crate::utils::envelope::send_profile;
+use crate::utils::event::get_sdk_info;
+use crate::utils::profile::{device_info, Profile, ProfileFormat};
+use crate::utils::releases::detect_release_name;
+
+fn validate_trace_id(v: String) -> Result<(), String> {
+    if v.len() == 32 && v.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(())
+    } else {
+        Err("Invalid trace ID, expected 32 hex characters.".to_string())
+    }
+}
+
+pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.about("Upload a profile of a job to Sentry.")
+        .long_about(
+            "Upload a profile of a job to Sentry.{n}{n}\
+             Reads collapsed stacks, e.g. from `py-spy record --format raw`, or the \
+             output of `perf script` and sends them as a profile together with a \
+             transaction for the job, so its flamegraph shows up in Sentry.",
+        )
+        .arg(
+            Arg::with_name("path")
+                .value_name("PATH")
+                .index(1)
+                .required(true)
+                .help("The path to the profiler output."),
+        )
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .value_name("FORMAT")
+                .possible_values(&["collapsed", "perf"])
+                .help("The format of the profiler output. [defaults to auto detection]"),
+        )
+        .arg(
+            Arg::with_name("interval")
+                .long("interval")
+                .value_name("MS")
+                .default_value("10")
+                .validator(validate_int)
+                .help("The milliseconds between samples of collapsed stacks."),
+        )
+        .arg(
+            Arg::with_name("transaction")
+                .long("transaction")
+                .value_name("NAME")
+                .required(true)
+                .help("The name of the transaction the profile belongs to."),
+        )
+        .arg(
+            Arg::with_name("op")
+                .long("op")
+                .value_name("OP")
+                .default_value("job")
+                .help("The operation of the transaction."),
+        )
+        .arg(
+            Arg::with_name("trace_id")
+                .long("trace-id")
+                .value_name("TRACE_ID")
+                .validator(validate_trace_id)
+                .help("Add the transaction to an existing trace."),
+        )
+        .arg(
+            Arg::with_name("release")
+                .long("release")
+                .short("r")
+                .value_name("RELEASE")
+                .help("The release of the profiled program. [defaults to the detected release]"),
+        )
+        .arg(
+            Arg::with_name("environment")
+                .long("env")
+                .short("E")
+                .value_name("ENVIRONMENT")
+                .help("The environment the program ran in."),
+        )
+        .arg(
+            Arg::with_name("platform")
+                .long("platform")
+                .value_name("PLATFORM")
+                .help(
+                    "The platform of the profiled program. \
+                     [defaults to 'python' for collapsed stacks and 'native' for perf]",
+                ),
+        )
+        .arg(
+            Arg::with_name("timestamp")
+                .long("timestamp")
+                .value_name("TIMESTAMP")
+                .validator(validate_timestamp)
+                .help(
+                    "The time profiling started in one of supported formats: unix \
+                     timestamp, RFC2822 or RFC3339. [defaults to now minus the \
+                     duration of the profile]",
+                ),
+        )
+}
+
+pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let config = Config::current();
+    let path = matches.value_of("path").unwrap();
+    let contents = fs::read_to_string(path).with_context(|_| format!("Could not read {}", path))?;
+    let format = match matches.value_of("format") {
+        Some(format) => format.parse()?,
+        None => ProfileFormat::detect(&contents),
+    };
+    let interval_ns = matches.value_of("interval").unwrap().parse::<u64>()? * 1_000_000;
+    let profile = Profile::parse(contents.as_bytes(), format, interval_ns)?;
+
+    let duration = Duration::nanoseconds(profile.duration_ns() as i64);
+    let started = match matches.value_of("timestamp") {
+        Some(timestamp) => get_timestamp(timestamp)?,
+        None => Utc::now() - duration,
+    };
+    let finished = started + duration;
+
+    let platform = matches
+        .value_of("platform")
+        .unwrap_or(match format {
+            ProfileFormat::Collapsed => "python",
+            ProfileFormat::Perf => "native",
+        })
+        .to_string();
+    let release = matches
+        .value_of("release")
+        .map(str::to_owned)
+        .or_else(|| detect_release_name().ok());
+    let environment = matches
+        .value_of("environment")
+        .map(str::to_owned)
+        .or_else(|| config.get_environment());
+    let name = matches.value_of("transaction").unwrap();
+    let trace_id = matches
+        .value_of("trace_id")
+        .map(str::to_owned)
+        .unwrap_or_else(|| Uuid::new_v4().to_simple().to_string());
+    let transaction_id = Uuid::new_v4().to_simple().to_string();
+    let profile_id = Uuid::new_v4().to_simple().to_string();
+
+    let transaction = json!({
+        "type": "transaction",
+        "event_id": transaction_id,
+        "transaction": name,
+        "platform": platform,
+        "sdk": get_sdk_info(),
+        "release": release,
+        "environment": environment,
+        "start_timestamp": started.timestamp_nanos() as f64 / 1e9,
+        "timestamp": finished.timestamp_nanos() as f64 / 1e9,
+        "contexts": {
+            "trace": {
+                "trace_id": trace_id,
+                "span_id": &Uuid::new_v4().to_simple().to_string()[..16],
+                "op": matches.value_of("op").unwrap(),
+                "status": "ok",
+            },
+            "profile": {
+                "profile_id": profile_id,
+            },
+        },
+        "spans": [],
+    });
+
+    let (os, device) = device_info();
+    let payload = json!({
+        "version": "1",
+        "event_id": profile_id,
+        "platform": platform,
+        "release": release,
+        "environment": environment,
+        "timestamp": started,
+        "os": os,
+        "device": device,
+        "transaction": {
+            "name": name,
+            "id": transaction_id,
+            "trace_id": trace_id,
+            "active_thread_id": profile.active_thread_id().to_string(),
+        },
+        "profile": profile.to_sample_profile(),
+    });
+
+    send_profile(&config.get_dsn()?, &transaction, &payload)?;
+    println!(
+        "Uploaded profile {} for transaction {}",
+        style(&profile_id).cyan(),
+        style(name).cyan()
+    );
+
+    Ok(())
+}
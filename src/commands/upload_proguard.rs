@@ -1,11 +1,11 @@
 //! Implements a command for uploading proguard mapping files.
 use std::fs;
-use std::io;
+use std::io::{self, BufWriter, Write};
 use std::path::PathBuf;
 
 use clap::{App, Arg, ArgMatches};
 use console::style;
-use failure::{bail, Error, SyncFailure};
+use failure::{bail, Error, ResultExt, SyncFailure};
 use log::{debug, info};
 use proguard::ProguardMapping;
 use symbolic::common::ByteView;
@@ -16,6 +16,7 @@ use crate::config::Config;
 use crate::utils::android::{dump_proguard_uuids_as_properties, AndroidManifest};
 use crate::utils::args::{validate_uuid, ArgExt};
 use crate::utils::fs::{get_sha1_checksum, TempFile};
+use crate::utils::proguard_mapping::{is_dexguard_mapping, sanitize_mapping, MergedMapping};
 use crate::utils::system::QuietExit;
 use crate::utils::ui::{copy_with_progress, make_byte_progress_bar};
 
@@ -54,10 +55,14 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
                 .value_name("VERSION_CODE")
                 .requires("app_id")
                 .requires("version")
+                .multiple(true)
+                .number_of_values(1)
                 .help(
                     "Optionally associate the mapping files with a version \
                      code.{n}This helps you understand which ProGuard files \
-                     go with which version of your app.",
+                     go with which version of your app.  Can be supplied \
+                     multiple times if the same mapping is shipped with several \
+                     version codes, e.g. for per-ABI APKs.",
                 ),
         )
         .arg(
@@ -100,6 +105,46 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
                 .conflicts_with("app_id")
                 .help("Read version and version code from an Android manifest file."),
         )
+        .arg(
+            Arg::with_name("android_app")
+                .long("android-app")
+                .value_name("PATH")
+                .conflicts_with_all(&["app_id", "android_manifest"])
+                .help(
+                    "Read the application ID, version and version code from the \
+                     compiled manifest of an APK or Android App Bundle (AAB).",
+                ),
+        )
+        .arg(
+            Arg::with_name("android_project")
+                .long("android-project")
+                .conflicts_with_all(&["app_id", "android_manifest", "android_app"])
+                .help(
+                    "Read the application ID, version and version code from the \
+                     build.gradle or AndroidManifest.xml of the Android project \
+                     in the current directory.",
+                ),
+        )
+        .arg(
+            Arg::with_name("mapping_format")
+                .long("mapping-format")
+                .value_name("FORMAT")
+                .possible_values(&["auto", "proguard", "r8", "dexguard", "dexprotector"])
+                .default_value("auto")
+                .help(
+                    "The format of the mapping files.{n}DexGuard and DexProtector \
+                     mappings contain additional directives, which are removed \
+                     with a warning before uploading.  By default, the format is \
+                     detected from the header of each mapping file.",
+                ),
+        )
+        .arg(Arg::with_name("merge").long("merge").help(
+            "Merge all mapping files into a single mapping before uploading.{n}\
+             Use this if libraries ship their own mapping files.  The first \
+             path is the mapping of the app, all others are the mappings of \
+             obfuscated libraries.  The mapping files are validated and \
+             classes that will remain obfuscated are reported.",
+        ))
         .arg(
             Arg::with_name("write_properties")
                 .long("write-properties")
@@ -131,6 +176,91 @@ pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
         )
 }
 
+/// Removes unsupported directives from a DexGuard or DexProtector mapping and
+/// writes the result to a temporary file.
+///
+/// Returns `None` for mappings in the ProGuard format that can be uploaded as
+/// they are.
+fn sanitize_mapping_file(path: &str, format: &str) -> Result<Option<TempFile>, Error> {
+    let contents = match fs::read(path) {
+        Ok(contents) => contents,
+        // missing files are reported later
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(Error::from(err)
+                .context(format!("failed to open proguard mapping '{}'", path))
+                .into())
+        }
+    };
+
+    let tolerant = match format {
+        "dexguard" | "dexprotector" => true,
+        "auto" => is_dexguard_mapping(&contents),
+        _ => false,
+    };
+    if !tolerant {
+        return Ok(None);
+    }
+
+    let (sanitized, removed) = sanitize_mapping(&contents);
+    if !removed.is_empty() {
+        eprintln!(
+            "warning: ignored {} unsupported lines in proguard mapping '{}'",
+            removed.len(),
+            path
+        );
+        for (line_number, line) in &removed {
+            debug!("  line {}: {}", line_number, line);
+        }
+    }
+
+    let tf = TempFile::create()?;
+    tf.open()?.write_all(&sanitized)?;
+    Ok(Some(tf))
+}
+
+/// Merges the given mapping files into a temporary file.
+fn merge_mapping_files(paths: &[&str]) -> Result<TempFile, Error> {
+    let contents = paths
+        .iter()
+        .map(|path| {
+            fs::read(path).with_context(|_| format!("failed to open proguard mapping '{}'", path))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let files: Vec<_> = paths
+        .iter()
+        .zip(&contents)
+        .map(|(path, contents)| (*path, contents.as_slice()))
+        .collect();
+
+    let merged = MergedMapping::merge(&files)?;
+    println!(
+        "{} merged {} mapping files with {} classes",
+        style(">").dim(),
+        paths.len(),
+        style(merged.class_count()).yellow()
+    );
+
+    let obfuscated = merged.obfuscated_classes();
+    if !obfuscated.is_empty() {
+        eprintln!(
+            "warning: {} classes will remain obfuscated, their original names \
+             look minified. Is the mapping file of a library missing?",
+            obfuscated.len()
+        );
+        for class in obfuscated.iter().take(10) {
+            eprintln!("  {}", class);
+        }
+        if obfuscated.len() > 10 {
+            eprintln!("  ... and {} more", obfuscated.len() - 10);
+        }
+    }
+
+    let tf = TempFile::create()?;
+    merged.write(BufWriter::new(tf.open()?))?;
+    Ok(tf)
+}
+
 pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
     let api = Api::current();
 
@@ -140,11 +270,47 @@ pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
             return Ok(());
         }
     };
+
+    let format = matches.value_of("mapping_format").unwrap();
+    let sanitized_mappings = paths
+        .iter()
+        .map(|path| sanitize_mapping_file(path, format))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut paths: Vec<_> = paths
+        .iter()
+        .zip(&sanitized_mappings)
+        .map(|(path, sanitized)| {
+            sanitized
+                .as_ref()
+                .map_or(*path, |tf| tf.path().to_str().unwrap())
+        })
+        .collect();
+
+    let merged_mapping;
+    if matches.is_present("merge") {
+        merged_mapping = merge_mapping_files(&paths)?;
+        paths = vec![merged_mapping.path().to_str().unwrap()];
+    }
     let mut mappings = vec![];
     let mut all_checksums = vec![];
 
     let android_manifest = if let Some(path) = matches.value_of("android_manifest") {
         Some(AndroidManifest::from_path(path)?)
+    } else if let Some(path) = matches.value_of("android_app") {
+        Some(AndroidManifest::from_app_path(path)?)
+    } else if matches.is_present("android_project") {
+        let manifest = match AndroidManifest::discover(None)? {
+            Some(manifest) => manifest,
+            None => bail!("Could not find an Android app in the current project"),
+        };
+        println!(
+            "{} detected app {} {} ({})",
+            style(">").dim(),
+            manifest.package(),
+            manifest.version_name(),
+            manifest.version_code()
+        );
+        Some(manifest)
     } else {
         None
     };
@@ -261,21 +427,27 @@ pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
 
     // if values are given associate
     } else if let Some(app_id) = matches.value_of("app_id") {
-        api.associate_dsyms(
-            &org,
-            &project,
-            &AssociateDsyms {
-                platform: matches
-                    .value_of("platform")
-                    .unwrap_or("android")
-                    .to_string(),
-                checksums: all_checksums,
-                name: app_id.to_string(),
-                app_id: app_id.to_string(),
-                version: matches.value_of("version").unwrap().to_owned(),
-                build: matches.value_of("version_code").map(str::to_owned),
-            },
-        )?;
+        let builds: Vec<_> = match matches.values_of("version_code") {
+            Some(codes) => codes.map(|code| Some(code.to_owned())).collect(),
+            None => vec![None],
+        };
+        for build in builds {
+            api.associate_dsyms(
+                &org,
+                &project,
+                &AssociateDsyms {
+                    platform: matches
+                        .value_of("platform")
+                        .unwrap_or("android")
+                        .to_string(),
+                    checksums: all_checksums.clone(),
+                    name: app_id.to_string(),
+                    app_id: app_id.to_string(),
+                    version: matches.value_of("version").unwrap().to_owned(),
+                    build,
+                },
+            )?;
+        }
     }
 
     // If wanted trigger reprocessing
@@ -0,0 +1,301 @@
+//! Implements a command for synthetic checks of HTTP(S) endpoints.
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use chrono::Utc;
+use clap::{App, AppSettings, Arg, ArgMatches};
+use console::style;
+use failure::Error;
+use sentry::internals::Uuid;
+use sentry::protocol::{Event, Level, LogEntry};
+use serde_json::json;
+use url::Url;
+
+use crate::config::Config;
+use crate::utils::args::{validate_int, validate_monitor_slug};
+use crate::utils::envelope::{send_check_in, send_metrics, send_with_attachments};
+use crate::utils::event::get_sdk_info;
+use crate::utils::metrics::{Metric, MetricType};
+use crate::utils::system::QuietExit;
+use crate::utils::uptime::{probe, ProbeResult};
+
+fn validate_url(v: String) -> Result<(), String> {
+    match Url::parse(&v) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => Ok(()),
+        _ => Err("Invalid URL, expected an http or https URL.".to_string()),
+    }
+}
+
+pub fn make_app<'a, 'b: 'a>(app: App<'a, 'b>) -> App<'a, 'b> {
+    app.about("Check the availability of HTTP services.")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            App::new("check")
+                .about("Probe a URL and report the result to Sentry.")
+                .long_about(
+                    "Probe a URL and report the result to Sentry.{n}{n}\
+                     Sends a single request and checks the status code, the latency \
+                     and when the TLS certificate expires.  The result can be sent \
+                     as a monitor check-in, as metrics and, when the check failed, \
+                     as an event.  Run it from cron to monitor internal services.  \
+                     The command exits with 1 when the check failed.",
+                )
+                .arg(
+                    Arg::with_name("url")
+                        .value_name("URL")
+                        .index(1)
+                        .required(true)
+                        .validator(validate_url)
+                        .help("The URL to probe."),
+                )
+                .arg(
+                    Arg::with_name("method")
+                        .long("method")
+                        .short("X")
+                        .value_name("METHOD")
+                        .default_value("GET")
+                        .help("The HTTP method of the request."),
+                )
+                .arg(
+                    Arg::with_name("headers")
+                        .long("header")
+                        .short("H")
+                        .value_name("KEY:VALUE")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("Add a header to the request.  Can be given multiple times."),
+                )
+                .arg(
+                    Arg::with_name("timeout")
+                        .long("timeout")
+                        .value_name("SECONDS")
+                        .default_value("10")
+                        .validator(validate_int)
+                        .help("Fail the check when there is no response in time."),
+                )
+                .arg(
+                    Arg::with_name("expect_status")
+                        .long("expect-status")
+                        .value_name("CODE")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .validator(validate_int)
+                        .help(
+                            "The status codes that pass the check.  Can be given \
+                             multiple times. [defaults to any code below 400]",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("max_latency")
+                        .long("max-latency")
+                        .value_name("MS")
+                        .validator(validate_int)
+                        .help("Fail the check when the response takes longer."),
+                )
+                .arg(
+                    Arg::with_name("tls_min_days")
+                        .long("tls-min-days")
+                        .value_name("DAYS")
+                        .default_value("14")
+                        .validator(validate_int)
+                        .help("Fail the check when the TLS certificate expires sooner."),
+                )
+                .arg(
+                    Arg::with_name("monitor")
+                        .long("monitor")
+                        .value_name("SLUG")
+                        .validator(validate_monitor_slug)
+                        .help("Send the result as a check-in of this monitor."),
+                )
+                .arg(
+                    Arg::with_name("metrics").long("metrics").help(
+                        "Send the latency, the result and the certificate expiry as metrics.",
+                    ),
+                )
+                .arg(
+                    Arg::with_name("event")
+                        .long("event")
+                        .help("Send an event when the check failed."),
+                )
+                .arg(
+                    Arg::with_name("environment")
+                        .long("env")
+                        .short("E")
+                        .value_name("ENVIRONMENT")
+                        .help("The environment of the service."),
+                ),
+        )
+}
+
+/// Returns why the probe did not pass the check.
+fn get_failures(result: &ProbeResult, matches: &ArgMatches<'_>) -> Result<Vec<String>, Error> {
+    let mut rv = vec![];
+    if let Some(ref error) = result.error {
+        rv.push(error.clone());
+    }
+    if let Some(status) = result.status {
+        let passed = match matches.values_of("expect_status") {
+            Some(codes) => codes.map(str::parse).any(|code| code == Ok(status)),
+            None => status < 400,
+        };
+        if !passed {
+            rv.push(format!("unexpected status {}", status));
+        }
+    }
+    if let Some(max_latency) = matches.value_of("max_latency") {
+        let max_latency = Duration::from_millis(max_latency.parse()?);
+        if result.latency > max_latency {
+            rv.push(format!(
+                "took {}ms, more than {}ms",
+                result.latency.as_millis(),
+                max_latency.as_millis()
+            ));
+        }
+    }
+    if let Some(expiry) = result.tls_expiry {
+        let min_days: i64 = matches.value_of("tls_min_days").unwrap().parse()?;
+        let days = (expiry - Utc::now()).num_days();
+        if days < min_days {
+            rv.push(format!("TLS certificate expires in {} days", days));
+        }
+    }
+    Ok(rv)
+}
+
+fn get_metrics(url: &Url, result: &ProbeResult, passed: bool) -> Vec<Metric> {
+    let mut tags = BTreeMap::new();
+    tags.insert("host".to_string(), url.host_str().unwrap_or("").to_string());
+    if let Some(status) = result.status {
+        tags.insert("status".to_string(), status.to_string());
+    }
+    let metric = |ty, name: &str, unit: Option<&str>, value: String| Metric {
+        ty,
+        name: name.into(),
+        unit: unit.map(str::to_string),
+        value,
+        tags: tags.clone(),
+        timestamp: Utc::now(),
+    };
+
+    let mut rv = vec![
+        metric(
+            MetricType::Distribution,
+            "uptime.latency",
+            Some("millisecond"),
+            result.latency.as_millis().to_string(),
+        ),
+        metric(
+            MetricType::Counter,
+            if passed {
+                "uptime.success"
+            } else {
+                "uptime.failure"
+            },
+            None,
+            "1".into(),
+        ),
+    ];
+    if let Some(expiry) = result.tls_expiry {
+        rv.push(metric(
+            MetricType::Gauge,
+            "uptime.tls_expiry",
+            Some("day"),
+            (expiry - Utc::now()).num_days().to_string(),
+        ));
+    }
+    rv
+}
+
+fn execute_check(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    let config = Config::current();
+    let url = matches.value_of("url").unwrap();
+    let headers: Vec<_> = matches.values_of("headers").unwrap_or_default().collect();
+    let timeout = Duration::from_secs(matches.value_of("timeout").unwrap().parse()?);
+    let result = probe(
+        url,
+        &matches.value_of("method").unwrap().to_uppercase(),
+        &headers,
+        timeout,
+    );
+
+    let failures = get_failures(&result, matches)?;
+    match result.status {
+        Some(status) => println!("{}: {} in {}ms", url, status, result.latency.as_millis()),
+        None => println!("{}: no response", url),
+    }
+    if let Some(expiry) = result.tls_expiry {
+        println!("TLS certificate expires {}", expiry.format("%Y-%m-%d"));
+    }
+    if failures.is_empty() {
+        println!("{}", style("Check passed").green());
+    } else {
+        println!("{}", style("Check failed").red());
+        for failure in &failures {
+            println!("  - {}", failure);
+        }
+    }
+
+    let environment = matches
+        .value_of("environment")
+        .map(str::to_owned)
+        .or_else(|| config.get_environment());
+    if let Some(slug) = matches.value_of("monitor") {
+        let mut check_in = json!({
+            "check_in_id": Uuid::new_v4().to_simple().to_string(),
+            "monitor_slug": slug,
+            "status": if failures.is_empty() { "ok" } else { "error" },
+            "duration": result.latency.as_secs_f64(),
+        });
+        if let Some(ref environment) = environment {
+            check_in["environment"] = environment.as_str().into();
+        }
+        send_check_in(&config.get_dsn()?, &check_in)?;
+    }
+    if matches.is_present("metrics") {
+        let parsed_url = Url::parse(url)?;
+        send_metrics(
+            &config.get_dsn()?,
+            &get_metrics(&parsed_url, &result, failures.is_empty()),
+        )?;
+    }
+    if matches.is_present("event") && !failures.is_empty() {
+        let mut event = Event {
+            sdk: Some(get_sdk_info()),
+            level: Level::Error,
+            platform: "other".into(),
+            environment: environment.map(Into::into),
+            logentry: Some(LogEntry {
+                message: format!("Uptime check of {} failed: {}", url, failures.join(", ")),
+                params: vec![],
+            }),
+            ..Event::default()
+        };
+        event.tags.insert("url".into(), url.into());
+        if let Some(status) = result.status {
+            event.tags.insert("status".into(), status.to_string());
+        }
+        event.extra.insert(
+            "latency_ms".into(),
+            (result.latency.as_millis() as u64).into(),
+        );
+        if let Some(expiry) = result.tls_expiry {
+            event
+                .extra
+                .insert("tls_expiry".into(), expiry.to_rfc3339().into());
+        }
+        send_with_attachments(&config.get_dsn()?, &event, &[])?;
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(QuietExit(1).into())
+    }
+}
+
+pub fn execute(matches: &ArgMatches<'_>) -> Result<(), Error> {
+    if let Some(sub_matches) = matches.subcommand_matches("check") {
+        return execute_check(sub_matches);
+    }
+    unreachable!();
+}
@@ -13,9 +13,15 @@ use lazy_static::lazy_static;
 use parking_lot::Mutex;
 use sentry::internals::Dsn;
 
-use crate::constants::{CONFIG_RC_FILE_NAME, DEFAULT_RETRIES, DEFAULT_URL};
+use crate::constants::{
+    APP_INFO, CONFIG_RC_FILE_NAME, DEFAULT_RELEASE_REGISTRY_URL, DEFAULT_RETRIES, DEFAULT_URL,
+};
+use crate::utils::annotations::CiOutput;
+use crate::utils::args::get_byte_size;
 use crate::utils::http::is_absolute_url;
 use crate::utils::logging::set_max_level;
+use crate::utils::tls::TlsBackend;
+use crate::utils::update::UpdateChannel;
 
 /// Represents the auth information
 #[derive(Debug, Clone)]
@@ -41,6 +47,8 @@ pub struct Config {
     cached_base_url: String,
     cached_log_level: log::LevelFilter,
     cached_vcs_remote: String,
+    cached_spool_dir: Option<PathBuf>,
+    cached_spool: bool,
 }
 
 impl Config {
@@ -59,6 +67,8 @@ impl Config {
             cached_base_url: get_default_url(&ini),
             cached_log_level: get_default_log_level(&ini),
             cached_vcs_remote: get_default_vcs_remote(&ini),
+            cached_spool_dir: get_default_spool_dir(&ini),
+            cached_spool: get_default_spool(&ini),
             ini,
         })
     }
@@ -267,6 +277,27 @@ impl Config {
         }
     }
 
+    /// Returns the TLS backend libcurl should use, if one was configured.
+    pub fn get_tls_backend(&self) -> Result<Option<TlsBackend>, Error> {
+        if let Ok(var) = env::var("SENTRY_HTTP_TLS_BACKEND") {
+            Ok(Some(var.parse()?))
+        } else if let Some(val) = self.ini.get_from(Some("http"), "tls_backend") {
+            Ok(Some(val.parse()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Indicates whether FIPS mode was enabled in the config.
+    pub fn get_fips_mode(&self) -> bool {
+        let val = env::var("SENTRY_FIPS_MODE").ok().or_else(|| {
+            self.ini
+                .get_from(Some("fips"), "enabled")
+                .map(str::to_owned)
+        });
+        matches!(val.as_deref(), Some("1") | Some("true"))
+    }
+
     /// Given a match object from clap, this returns the org from it.
     pub fn get_org(&self, matches: &ArgMatches<'_>) -> Result<String, Error> {
         matches
@@ -324,6 +355,22 @@ impl Config {
         })
     }
 
+    /// Return the URL of the webhook notified about releases and deploys.
+    pub fn get_webhook_url(&self) -> Option<String> {
+        env::var("SENTRY_WEBHOOK_URL")
+            .ok()
+            .or_else(|| self.ini.get_from(Some("webhook"), "url").map(str::to_owned))
+    }
+
+    /// Return the secret used to sign webhooks.
+    pub fn get_webhook_secret(&self) -> Option<String> {
+        env::var("SENTRY_WEBHOOK_SECRET").ok().or_else(|| {
+            self.ini
+                .get_from(Some("webhook"), "secret")
+                .map(str::to_owned)
+        })
+    }
+
     /// Returns the defaults for org and project.
     pub fn get_org_and_project_defaults(&self) -> (Option<String>, Option<String>) {
         (
@@ -358,6 +405,29 @@ impl Config {
             .unwrap_or(35 * 1024 * 1024))
     }
 
+    /// Returns the size above which release files are not uploaded.
+    pub fn get_max_upload_file_size(&self) -> Result<Option<u64>, Error> {
+        self.get_upload_limit("SENTRY_MAX_UPLOAD_FILE_SIZE", "max_file_size")
+    }
+
+    /// Returns the maximum size of all release files of one upload.
+    pub fn get_max_upload_total_size(&self) -> Result<Option<u64>, Error> {
+        self.get_upload_limit("SENTRY_MAX_UPLOAD_TOTAL_SIZE", "max_total_size")
+    }
+
+    fn get_upload_limit(&self, var: &str, key: &str) -> Result<Option<u64>, Error> {
+        let value = match env::var(var) {
+            Ok(value) => value,
+            Err(_) => match self.ini.get_from(Some("upload"), key) {
+                Some(value) => value.to_string(),
+                None => return Ok(None),
+            },
+        };
+        Ok(Some(get_byte_size(&value).with_context(|_| {
+            format!("Invalid upload.{} setting", key)
+        })?))
+    }
+
     pub fn get_max_retry_count(&self) -> Result<u32, Error> {
         if env::var_os("SENTRY_HTTP_MAX_RETRIES").is_some() {
             Ok(env::var("SENTRY_HTTP_MAX_RETRIES")?.parse()?)
@@ -390,6 +460,45 @@ impl Config {
         }
     }
 
+    /// Returns the folder in which envelopes are spooled for later delivery.
+    pub fn get_spool_dir(&self) -> Result<PathBuf, Error> {
+        if let Some(ref dir) = self.cached_spool_dir {
+            Ok(dir.clone())
+        } else {
+            let mut path = app_dirs::app_root(app_dirs::AppDataType::UserCache, APP_INFO)
+                .context("Could not get cache folder")?;
+            path.push("spool");
+            Ok(path)
+        }
+    }
+
+    /// Sets the folder in which envelopes are spooled.
+    pub fn set_spool_dir(&mut self, path: &str) {
+        self.cached_spool_dir = Some(PathBuf::from(path));
+    }
+
+    /// Indicates whether events, check-ins and other envelopes are written
+    /// into the spool folder instead of sent.
+    pub fn should_spool(&self) -> bool {
+        self.cached_spool
+    }
+
+    /// Enables or disables writing envelopes into the spool folder.
+    pub fn set_spool(&mut self, spool: bool) {
+        self.cached_spool = spool;
+    }
+
+    /// Returns the CI server to format annotations and steps for.
+    pub fn get_ci_output(&self) -> Result<CiOutput, Error> {
+        if let Ok(var) = env::var("SENTRY_CI_OUTPUT") {
+            var.parse()
+        } else if let Some(val) = self.ini.get_from(Some("ui"), "ci_output") {
+            val.parse()
+        } else {
+            Ok(CiOutput::detect())
+        }
+    }
+
     /// Return VCS remote
     pub fn get_cached_vcs_remote(&self) -> String {
         self.cached_vcs_remote.clone()
@@ -406,6 +515,29 @@ impl Config {
         }
     }
 
+    /// Returns the release channel `sentry-cli update` installs from.
+    pub fn get_update_channel(&self) -> Result<UpdateChannel, Error> {
+        if let Ok(var) = env::var("SENTRY_UPDATE_CHANNEL") {
+            var.parse()
+        } else if let Some(val) = self.ini.get_from(Some("update"), "channel") {
+            val.parse()
+        } else {
+            Ok(UpdateChannel::Stable)
+        }
+    }
+
+    /// Returns the URL of the release registry, e.g. of an internal mirror.
+    pub fn get_release_registry_url(&self) -> String {
+        env::var("SENTRY_RELEASE_REGISTRY_URL")
+            .ok()
+            .or_else(|| {
+                self.ini
+                    .get_from(Some("update"), "registry_url")
+                    .map(str::to_owned)
+            })
+            .unwrap_or_else(|| DEFAULT_RELEASE_REGISTRY_URL.to_string())
+    }
+
     /// Does this installation want errors to sentry?
     pub fn internal_sentry_dsn(&self) -> Option<Dsn> {
         if !self
@@ -534,6 +666,8 @@ impl Clone for Config {
             cached_base_url: self.cached_base_url.clone(),
             cached_log_level: self.cached_log_level,
             cached_vcs_remote: self.cached_vcs_remote.clone(),
+            cached_spool_dir: self.cached_spool_dir.clone(),
+            cached_spool: self.cached_spool,
         }
     }
 }
@@ -579,6 +713,21 @@ fn get_default_log_level(ini: &Ini) -> log::LevelFilter {
     log::LevelFilter::Warn
 }
 
+fn get_default_spool_dir(ini: &Ini) -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("SENTRY_SPOOL_DIR") {
+        Some(PathBuf::from(dir))
+    } else {
+        ini.get_from(Some("spool"), "dir").map(PathBuf::from)
+    }
+}
+
+fn get_default_spool(ini: &Ini) -> bool {
+    let val = env::var("SENTRY_SPOOL")
+        .ok()
+        .or_else(|| ini.get_from(Some("spool"), "enabled").map(str::to_owned));
+    matches!(val.as_deref(), Some("1") | Some("true"))
+}
+
 /// Get the default VCS remote.
 ///
 /// To be backward compatible the default remote is still
@@ -18,9 +18,8 @@ pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 /// The name of the configuration file.
 pub const CONFIG_RC_FILE_NAME: &str = ".sentryclirc";
 
-/// The release registry URL where the latest released version of sentry-cli can be found
-pub const RELEASE_REGISTRY_LATEST_URL: &str =
-    "https://release-registry.services.sentry.io/apps/sentry-cli/latest";
+/// The release registry where the released versions of sentry-cli can be found
+pub const DEFAULT_RELEASE_REGISTRY_URL: &str = "https://release-registry.services.sentry.io";
 
 /// The file extension of the binary (.exe or empty string)
 #[cfg(windows)]
@@ -0,0 +1,64 @@
+$_SentryCli = "___SENTRY_CLI___"
+$_SentryExitOnError = $___SENTRY_EXIT_ON_ERROR___
+$_SentryTracebackFile = Join-Path ([System.IO.Path]::GetTempPath()) ".sentry-$([guid]::NewGuid()).traceback"
+$_SentryLogFile = Join-Path ([System.IO.Path]::GetTempPath()) ".sentry-$([guid]::NewGuid()).out"
+
+# PowerShell 7.3 and later raise errors for native commands with a non-zero exit code
+$PSNativeCommandUseErrorActionPreference = $true
+
+function Send-SentryError {
+  param($ErrorRecord, [int]$ExitCode = 1)
+
+  $lines = @()
+  foreach ($frame in ($ErrorRecord.ScriptStackTrace -split "`r?`n")) {
+    if ($frame -match '^at (.*?), (.*): line (\d+)$' -and $Matches[1] -notlike '*-Sentry*') {
+      $lines += "$($Matches[1]):$($Matches[2]):$($Matches[3])"
+    }
+  }
+
+  $command = "unknown"
+  if ($ErrorRecord.InvocationInfo -and $ErrorRecord.InvocationInfo.Line) {
+    $command = $ErrorRecord.InvocationInfo.Line.Trim()
+  }
+  $lines += "@command:$command"
+  $lines += "@exit_code:$ExitCode"
+  $lines += "@message:$($ErrorRecord.Exception.Message -replace "`r?`n", ' ')"
+  Set-Content -Path $_SentryTracebackFile -Value $lines
+
+  if (-not (Test-Path $_SentryLogFile)) {
+    Set-Content -Path $_SentryLogFile -Value @()
+  }
+  $env:SENTRY_LAST_EVENT = & $_SentryCli powershell-hook --send-event --traceback $_SentryTracebackFile --log $_SentryLogFile ___SENTRY_NO_ENVIRON___
+  Remove-Item -Force -ErrorAction SilentlyContinue $_SentryTracebackFile, $_SentryLogFile
+}
+
+function Invoke-WithSentry {
+  param([Parameter(Mandatory = $true)][scriptblock]$ScriptBlock)
+
+  $global:LASTEXITCODE = 0
+  try {
+    . $ScriptBlock
+  } catch {
+    $exitCode = if ($LASTEXITCODE) { $LASTEXITCODE } else { 1 }
+    Send-SentryError $_ $exitCode
+    if ($_SentryExitOnError) { exit $exitCode }
+    return
+  }
+
+  # older versions of PowerShell do not fail on native commands
+  if ($LASTEXITCODE) {
+    $exitCode = $LASTEXITCODE
+    $record = [System.Management.Automation.ErrorRecord]::new(
+      [System.Exception]::new("native command exited with status $exitCode"),
+      "NativeCommandFailed", "NotSpecified", $null)
+    Send-SentryError $record $exitCode
+    if ($_SentryExitOnError) { exit $exitCode }
+  }
+}
+
+trap {
+  $exitCode = if ($LASTEXITCODE) { $LASTEXITCODE } else { 1 }
+  Send-SentryError $_ $exitCode
+  if ($_SentryExitOnError) { exit $exitCode }
+  continue
+}
@@ -0,0 +1,107 @@
+_SENTRY_TRACEBACK_FILE="___SENTRY_TRACEBACK_FILE___"
+_SENTRY_LOG_FILE="___SENTRY_LOG_FILE___"
+_SENTRY_MONITOR_SLUG="___SENTRY_MONITOR_SLUG___"
+_SENTRY_MONITOR_ORG="___SENTRY_MONITOR_ORG___"
+_SENTRY_SPANS_FILE="___SENTRY_SPANS_FILE___"
+_SENTRY_TRACING="___SENTRY_TRACING___"
+_SENTRY_SCRIPT="$0"
+_SENTRY_CHECKIN_ID=""
+_sentry_started_at=$(date +%s)
+_sentry_stdout_pid=""
+_sentry_stderr_pid=""
+
+if [ "$SENTRY_CLI_NO_EXIT_TRAP" != 1 ]; then
+  trap _sentry_exit_trap EXIT
+fi
+
+# POSIX sh has neither an ERR trap nor call stacks, so failures are only
+# reported once the script exits and the script itself is the command.
+_sentry_exit_trap() {
+  _sentry_exit_code="$?"
+  trap - EXIT
+  _sentry_close_log
+  if [ "$_sentry_exit_code" != 0 ]; then
+    printf '@command:%s\n@exit_code:%s\n' "$0" "$_sentry_exit_code" > "$_SENTRY_TRACEBACK_FILE"
+    : >> "$_SENTRY_LOG_FILE"
+    SENTRY_LAST_EVENT=$(___SENTRY_CLI___ bash-hook --send-event --traceback "$_SENTRY_TRACEBACK_FILE" --log "$_SENTRY_LOG_FILE" ___SENTRY_NO_ENVIRON___)
+    export SENTRY_LAST_EVENT
+  fi
+  _sentry_finish_checkin "$_sentry_exit_code"
+  _sentry_send_transaction "$_sentry_exit_code"
+  rm -f "$_SENTRY_TRACEBACK_FILE" "$_SENTRY_LOG_FILE" "$_SENTRY_SPANS_FILE"
+  exit "$_sentry_exit_code"
+}
+
+_sentry_now() {
+  date +%s.%N
+}
+
+# marks a section of the script that shows up as a span of the transaction
+sentry_span_start() {
+  if [ "$_SENTRY_TRACING" = 1 ]; then
+    printf 'start\t%s\t%s\n' "$(_sentry_now)" "${1:-section}" >> "$_SENTRY_SPANS_FILE"
+  fi
+}
+
+sentry_span_end() {
+  if [ "$_SENTRY_TRACING" = 1 ]; then
+    printf 'end\t%s\n' "$(_sentry_now)" >> "$_SENTRY_SPANS_FILE"
+  fi
+}
+
+_sentry_send_transaction() {
+  if [ "$_SENTRY_TRACING" = 1 ]; then
+    printf 'exit\t%s\n' "$(_sentry_now)" >> "$_SENTRY_SPANS_FILE"
+    ___SENTRY_CLI___ bash-hook --send-transaction --spans "$_SENTRY_SPANS_FILE" --exit-code "$1" --script "$_SENTRY_SCRIPT" > /dev/null || true
+  fi
+}
+
+_sentry_start_checkin() {
+  if [ "x$_SENTRY_MONITOR_SLUG" != x ]; then
+    _SENTRY_CHECKIN_ID=$(___SENTRY_CLI___ bash-hook --start-checkin --monitor-slug "$_SENTRY_MONITOR_SLUG" ${_SENTRY_MONITOR_ORG:+--org "$_SENTRY_MONITOR_ORG"} || true)
+  fi
+}
+
+_sentry_finish_checkin() {
+  if [ "x$_SENTRY_CHECKIN_ID" != x ]; then
+    ___SENTRY_CLI___ bash-hook --finish-checkin "$_SENTRY_CHECKIN_ID" --exit-code "$1" --duration "$(( $(date +%s) - _sentry_started_at ))" --monitor-slug "$_SENTRY_MONITOR_SLUG" ${_SENTRY_MONITOR_ORG:+--org "$_SENTRY_MONITOR_ORG"} || true
+  fi
+}
+
+# copies lines from stdin to stdout and appends them to the log file with a
+# timestamp and the name of the stream
+_sentry_log_reader() {
+  if command -v perl >/dev/null; then
+    perl '-MPOSIX' -ne 'BEGIN { $stream = shift; open(LOG, ">>", shift) or die; } $|++; print; print LOG strftime("%Y-%m-%d %H:%M:%S %z: ", localtime()), "$stream: ", $_; LOG->flush();' "$1" "$_SENTRY_LOG_FILE"
+  else
+    while IFS= read -r _sentry_line || [ -n "$_sentry_line" ]; do
+      printf '%s\n' "$_sentry_line"
+      printf '%s: %s: %s\n' "$(date '+%Y-%m-%d %H:%M:%S %z')" "$1" "$_sentry_line" >> "$_SENTRY_LOG_FILE"
+    done
+  fi
+}
+
+# restores the original stdout and stderr and waits for the log to be written
+_sentry_close_log() {
+  if [ "x$_sentry_stdout_pid" != x ]; then
+    exec 1>&8 2>&9 8>&- 9>&-
+    wait "$_sentry_stdout_pid" "$_sentry_stderr_pid" 2>/dev/null
+    _sentry_stdout_pid=""
+  fi
+}
+
+: > "$_SENTRY_LOG_FILE"
+if [ "$_SENTRY_TRACING" = 1 ]; then
+  printf 'run\t%s\n' "$(_sentry_now)" > "$_SENTRY_SPANS_FILE"
+fi
+_sentry_start_checkin
+
+# without process substitution the output is piped through named pipes
+_sentry_fifo_dir=$(mktemp -d "${TMPDIR:-/tmp}/.sentry-XXXXXX")
+mkfifo "$_sentry_fifo_dir/stdout" "$_sentry_fifo_dir/stderr"
+_sentry_log_reader stdout < "$_sentry_fifo_dir/stdout" &
+_sentry_stdout_pid=$!
+_sentry_log_reader stderr < "$_sentry_fifo_dir/stderr" >&2 &
+_sentry_stderr_pid=$!
+exec 8>&1 9>&2 1>"$_sentry_fifo_dir/stdout" 2>"$_sentry_fifo_dir/stderr"
+rm -rf "$_sentry_fifo_dir"
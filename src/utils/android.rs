@@ -1,13 +1,17 @@
 use std::collections::HashMap;
+use std::env;
 use std::fmt;
 use std::fs;
-use std::io;
-use std::path::Path;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
 
 use elementtree::Element;
-use failure::{err_msg, Error};
+use failure::{bail, err_msg, Error, ResultExt};
 use itertools::Itertools;
+use lazy_static::lazy_static;
+use regex::Regex;
 use uuid::Uuid;
+use zip::ZipArchive;
 
 pub struct AndroidManifest {
     root: Element,
@@ -22,6 +26,104 @@ impl AndroidManifest {
         Ok(AndroidManifest { root })
     }
 
+    /// Discovers the metadata of the Android app in the given directory or
+    /// one of its parents, defaulting to the current directory.
+    ///
+    /// The search stops at the root of the project, which is the directory
+    /// with the Gradle settings or the version control checkout.  The
+    /// `applicationId`, `versionName` and `versionCode` are read from the
+    /// `build.gradle` or `build.gradle.kts` of the app module, falling back to
+    /// the values in its `AndroidManifest.xml`.
+    pub fn discover(path: Option<PathBuf>) -> Result<Option<AndroidManifest>, Error> {
+        let mut here = match path {
+            Some(path) => path,
+            None => env::current_dir()?,
+        };
+        loop {
+            for module in &[here.join("app"), here.clone()] {
+                if let Some(manifest) = Self::from_module(module) {
+                    return Ok(Some(manifest));
+                }
+            }
+            let is_project_root = ["settings.gradle", "settings.gradle.kts", ".git"]
+                .iter()
+                .any(|name| here.join(name).exists());
+            if is_project_root || !here.pop() {
+                break;
+            }
+        }
+        Ok(None)
+    }
+
+    fn from_module(module: &Path) -> Option<AndroidManifest> {
+        lazy_static! {
+            static ref APP_ID_RE: Regex =
+                Regex::new(r#"applicationId\s*[=(]?\s*["']([^"']*)["']"#).unwrap();
+            static ref VERSION_NAME_RE: Regex =
+                Regex::new(r#"versionName\s*[=(]?\s*["']([^"']*)["']"#).unwrap();
+            static ref VERSION_CODE_RE: Regex =
+                Regex::new(r#"versionCode\s*[=(]?\s*(\d+)"#).unwrap();
+        }
+
+        let mut root = ["src/main/AndroidManifest.xml", "AndroidManifest.xml"]
+            .iter()
+            .find_map(|path| AndroidManifest::from_path(module.join(path)).ok())
+            .map_or_else(|| Element::new("manifest"), |manifest| manifest.root);
+
+        let gradle = ["build.gradle", "build.gradle.kts"]
+            .iter()
+            .find_map(|name| fs::read_to_string(module.join(name)).ok());
+        if let Some(contents) = gradle {
+            if let Some(caps) = APP_ID_RE.captures(&contents) {
+                root.set_attr("package", &caps[1]);
+            }
+            if let Some(caps) = VERSION_NAME_RE.captures(&contents) {
+                root.set_attr((ANDROID_NS, "versionName"), &caps[1]);
+            }
+            if let Some(caps) = VERSION_CODE_RE.captures(&contents) {
+                root.set_attr((ANDROID_NS, "versionCode"), &caps[1]);
+            }
+        }
+
+        if root.get_attr("package").is_some()
+            && root.get_attr((ANDROID_NS, "versionName")).is_some()
+        {
+            Some(AndroidManifest { root })
+        } else {
+            None
+        }
+    }
+
+    /// Reads the compiled manifest of an APK or an Android App Bundle (AAB).
+    pub fn from_app_path<P: AsRef<Path>>(path: P) -> Result<AndroidManifest, Error> {
+        let path = path.as_ref();
+        let f =
+            fs::File::open(path).with_context(|_| format!("Could not open {}", path.display()))?;
+        let mut zip = ZipArchive::new(f)
+            .with_context(|_| format!("{} is not a valid APK or AAB", path.display()))?;
+
+        // APKs contain binary XML, app bundles the protobuf format of aapt2
+        let is_bundle = zip.by_name("AndroidManifest.xml").is_err();
+        let name = if is_bundle {
+            "base/manifest/AndroidManifest.xml"
+        } else {
+            "AndroidManifest.xml"
+        };
+
+        let mut data = vec![];
+        match zip.by_name(name) {
+            Ok(mut entry) => entry.read_to_end(&mut data)?,
+            Err(_) => bail!("Could not find AndroidManifest.xml in {}", path.display()),
+        };
+
+        let root = if is_bundle {
+            parse_proto_xml_root(&data)?
+        } else {
+            parse_binary_xml_root(&data)?
+        };
+        Ok(AndroidManifest { root })
+    }
+
     /// Returns the package ID
     pub fn package(&self) -> &str {
         self.root.get_attr("package").unwrap_or("unknown")
@@ -73,6 +175,236 @@ impl fmt::Debug for AndroidManifest {
     }
 }
 
+/// Returns `len` bytes at `offset`, failing instead of reading past the end.
+fn read_bytes(data: &[u8], offset: usize, len: usize) -> Result<&[u8], Error> {
+    match data.get(offset..).and_then(|rest| rest.get(..len)) {
+        Some(bytes) => Ok(bytes),
+        None => bail!("Unexpected end of binary XML"),
+    }
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, Error> {
+    let b = read_bytes(data, offset, 2)?;
+    Ok(u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, Error> {
+    let b = read_bytes(data, offset, 4)?;
+    Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// Adds offsets read from binary XML, which might overflow on 32-bit targets.
+fn add_offsets(offsets: &[usize]) -> Result<usize, Error> {
+    offsets
+        .iter()
+        .try_fold(0usize, |sum, &offset| sum.checked_add(offset))
+        .ok_or_else(|| err_msg("Invalid offset in binary XML"))
+}
+
+/// Reads a string from the string pool chunk of a binary XML document.
+fn read_pool_string(pool: &[u8], index: u32) -> Result<String, Error> {
+    let count = read_u32(pool, 8)?;
+    if index >= count {
+        bail!("Invalid string reference in binary XML");
+    }
+    let is_utf8 = read_u32(pool, 16)? & (1 << 8) != 0;
+    let strings_start = read_u32(pool, 20)? as usize;
+    let header_size = read_u16(pool, 2)? as usize;
+    let index_offset = add_offsets(&[header_size, 4 * index as usize])?;
+    let mut offset = add_offsets(&[strings_start, read_u32(pool, index_offset)? as usize])?;
+
+    if is_utf8 {
+        fn read_len(pool: &[u8], offset: &mut usize) -> Result<usize, Error> {
+            let b = read_bytes(pool, *offset, 1)?[0] as usize;
+            *offset += 1;
+            if b & 0x80 == 0 {
+                return Ok(b);
+            }
+            let len = ((b & 0x7f) << 8) | read_bytes(pool, *offset, 1)?[0] as usize;
+            *offset += 1;
+            Ok(len)
+        }
+
+        // the length in UTF-16 code units is followed by the length in bytes
+        read_len(pool, &mut offset)?;
+        let len = read_len(pool, &mut offset)?;
+        let bytes = read_bytes(pool, offset, len)?;
+        Ok(String::from_utf8_lossy(bytes).into_owned())
+    } else {
+        let mut len = read_u16(pool, offset)? as usize;
+        offset += 2;
+        if len & 0x8000 != 0 {
+            len = ((len & 0x7fff) << 16) | read_u16(pool, offset)? as usize;
+            offset += 2;
+        }
+        let chars = read_bytes(pool, offset, add_offsets(&[len, len])?)?
+            .chunks(2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+            .collect::<Vec<_>>();
+        Ok(String::from_utf16_lossy(&chars))
+    }
+}
+
+/// Parses the root element of an XML document in the binary format used
+/// by APKs.  Only the attributes of the root element are read.
+fn parse_binary_xml_root(data: &[u8]) -> Result<Element, Error> {
+    const NO_ENTRY: u32 = 0xffff_ffff;
+
+    if read_u16(data, 0)? != 0x0003 {
+        bail!("AndroidManifest.xml is not a binary XML document");
+    }
+
+    let mut pool = None;
+    let mut offset = read_u16(data, 2)? as usize;
+    while offset < data.len() {
+        let chunk_type = read_u16(data, offset)?;
+        let header_size = read_u16(data, offset + 2)? as usize;
+        let size = read_u32(data, offset + 4)? as usize;
+        if size == 0 {
+            bail!("Invalid binary XML chunk");
+        }
+        let chunk = read_bytes(data, offset, size)?;
+
+        match chunk_type {
+            // string pool
+            0x0001 => pool = Some(chunk),
+            // start of an element
+            0x0102 => {
+                let pool = pool.ok_or_else(|| err_msg("Binary XML is missing a string pool"))?;
+                let string = |index| read_pool_string(pool, index);
+
+                let mut element = Element::new(string(read_u32(chunk, header_size + 4)?)?.as_str());
+                let attr_start = read_u16(chunk, header_size + 8)? as usize;
+                let attr_size = read_u16(chunk, header_size + 10)? as usize;
+                let attr_count = read_u16(chunk, header_size + 12)? as usize;
+
+                for i in 0..attr_count {
+                    let attr = read_bytes(
+                        chunk,
+                        add_offsets(&[header_size, attr_start, i * attr_size])?,
+                        20,
+                    )?;
+                    let ns = read_u32(attr, 0)?;
+                    let name = string(read_u32(attr, 4)?)?;
+                    let raw_value = read_u32(attr, 8)?;
+                    let data_type = attr[15];
+                    let value_data = read_u32(attr, 16)?;
+
+                    let value = match (raw_value, data_type) {
+                        (NO_ENTRY, 0x03) => string(value_data)?,
+                        (NO_ENTRY, 0x10) => (value_data as i32).to_string(),
+                        (NO_ENTRY, 0x11) => format!("0x{:x}", value_data),
+                        (NO_ENTRY, 0x12) => (value_data != 0).to_string(),
+                        (NO_ENTRY, _) => value_data.to_string(),
+                        (index, _) => string(index)?,
+                    };
+
+                    if ns == NO_ENTRY {
+                        element.set_attr(name.as_str(), value);
+                    } else {
+                        element.set_attr((string(ns)?.as_str(), name.as_str()), value);
+                    }
+                }
+
+                return Ok(element);
+            }
+            _ => {}
+        }
+
+        offset += size;
+    }
+
+    bail!("Binary XML does not contain any elements")
+}
+
+/// Returns the length delimited fields of a protobuf message.
+fn proto_fields(mut data: &[u8]) -> Result<Vec<(u64, &[u8])>, Error> {
+    fn varint(data: &mut &[u8]) -> Result<u64, Error> {
+        let mut rv = 0;
+        for shift in (0..64).step_by(7) {
+            let (&byte, rest) = data
+                .split_first()
+                .ok_or_else(|| err_msg("Unexpected end of protobuf message"))?;
+            *data = rest;
+            rv |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(rv);
+            }
+        }
+        bail!("Invalid varint in protobuf message")
+    }
+
+    let mut rv = vec![];
+    while !data.is_empty() {
+        let key = varint(&mut data)?;
+        let skip = match key & 0x7 {
+            0 => {
+                varint(&mut data)?;
+                0
+            }
+            1 => 8,
+            2 => {
+                let len = varint(&mut data)? as usize;
+                if len > data.len() {
+                    bail!("Unexpected end of protobuf message");
+                }
+                rv.push((key >> 3, &data[..len]));
+                len
+            }
+            5 => 4,
+            _ => bail!("Unsupported protobuf wire type"),
+        };
+        if skip > data.len() {
+            bail!("Unexpected end of protobuf message");
+        }
+        data = &data[skip..];
+    }
+    Ok(rv)
+}
+
+/// Parses the root element of an XML document in the protobuf format of
+/// aapt2, which is used by Android App Bundles.  Only the attributes of the
+/// root element are read.
+fn parse_proto_xml_root(data: &[u8]) -> Result<Element, Error> {
+    // XmlNode.element
+    let element = match proto_fields(data)?.into_iter().find(|&(tag, _)| tag == 1) {
+        Some((_, element)) => element,
+        None => bail!("AndroidManifest.xml does not contain any elements"),
+    };
+
+    let mut name = String::new();
+    let mut attrs = vec![];
+    for (tag, field) in proto_fields(element)? {
+        match tag {
+            3 => name = String::from_utf8_lossy(field).into_owned(),
+            4 => {
+                let (mut ns, mut name, mut value) = (String::new(), String::new(), String::new());
+                for (tag, field) in proto_fields(field)? {
+                    let field = String::from_utf8_lossy(field).into_owned();
+                    match tag {
+                        1 => ns = field,
+                        2 => name = field,
+                        3 => value = field,
+                        _ => {}
+                    }
+                }
+                attrs.push((ns, name, value));
+            }
+            _ => {}
+        }
+    }
+
+    let mut element = Element::new(name.as_str());
+    for (ns, name, value) in attrs {
+        if ns.is_empty() {
+            element.set_attr(name.as_str(), value);
+        } else {
+            element.set_attr((ns.as_str(), name.as_str()), value);
+        }
+    }
+    Ok(element)
+}
+
 pub fn dump_proguard_uuids_as_properties<P: AsRef<Path>>(
     p: P,
     uuids: &[Uuid],
@@ -101,3 +433,218 @@ pub fn dump_proguard_uuids_as_properties<P: AsRef<Path>>(
         .map_err(|_| err_msg("Could not persist proguard UUID in properties file"))?;
     Ok(())
 }
+
+#[cfg(test)]
+fn build_binary_manifest(utf8: bool) -> Vec<u8> {
+    fn u16s(buf: &mut Vec<u8>, values: &[u16]) {
+        for v in values {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+    }
+    fn u32s(buf: &mut Vec<u8>, values: &[u32]) {
+        for v in values {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+    }
+
+    let strings = [
+        "manifest",
+        "package",
+        "versionCode",
+        "versionName",
+        ANDROID_NS,
+        "io.sentry.sample",
+        "1.2.3",
+    ];
+
+    let mut string_data = vec![];
+    let mut offsets = vec![];
+    for s in &strings {
+        offsets.push(string_data.len() as u32);
+        if utf8 {
+            string_data.extend_from_slice(&[s.len() as u8, s.len() as u8]);
+            string_data.extend_from_slice(s.as_bytes());
+            string_data.push(0);
+        } else {
+            let chars: Vec<_> = s.encode_utf16().collect();
+            u16s(&mut string_data, &[chars.len() as u16]);
+            u16s(&mut string_data, &chars);
+            u16s(&mut string_data, &[0]);
+        }
+    }
+    while string_data.len() % 4 != 0 {
+        string_data.push(0);
+    }
+
+    let mut pool = vec![];
+    let strings_start = 28 + 4 * strings.len() as u32;
+    u16s(&mut pool, &[0x0001, 28]);
+    u32s(&mut pool, &[strings_start + string_data.len() as u32]);
+    u32s(
+        &mut pool,
+        &[strings.len() as u32, 0, if utf8 { 1 << 8 } else { 0 }],
+    );
+    u32s(&mut pool, &[strings_start, 0]);
+    u32s(&mut pool, &offsets);
+    pool.extend_from_slice(&string_data);
+
+    // (namespace, name, raw value, data type, data)
+    let attrs: [(u32, u32, u32, u8, u32); 3] = [
+        (!0, 1, 5, 0x03, 5),
+        (4, 2, !0, 0x10, 42),
+        (4, 3, 6, 0x03, 6),
+    ];
+    let mut element = vec![];
+    u16s(&mut element, &[0x0102, 16]);
+    u32s(&mut element, &[36 + 20 * attrs.len() as u32, 1, !0, !0, 0]);
+    u16s(&mut element, &[20, 20, attrs.len() as u16, 0, 0, 0]);
+    for &(ns, name, raw_value, data_type, data) in &attrs {
+        u32s(&mut element, &[ns, name, raw_value]);
+        u16s(&mut element, &[8]);
+        element.extend_from_slice(&[0, data_type]);
+        u32s(&mut element, &[data]);
+    }
+
+    let mut rv = vec![];
+    u16s(&mut rv, &[0x0003, 8]);
+    u32s(&mut rv, &[8 + pool.len() as u32 + element.len() as u32]);
+    rv.extend_from_slice(&pool);
+    rv.extend_from_slice(&element);
+    rv
+}
+
+#[test]
+fn test_parse_binary_xml_root() {
+    for &utf8 in &[false, true] {
+        let manifest = AndroidManifest {
+            root: parse_binary_xml_root(&build_binary_manifest(utf8)).unwrap(),
+        };
+        assert_eq!(manifest.package(), "io.sentry.sample");
+        assert_eq!(manifest.version_code(), "42");
+        assert_eq!(manifest.version_name(), "1.2.3");
+    }
+}
+
+#[cfg(test)]
+fn build_proto_manifest() -> Vec<u8> {
+    fn field(tag: u8, data: &[u8]) -> Vec<u8> {
+        let mut rv = vec![tag << 3 | 2];
+        let mut len = data.len();
+        while len >= 0x80 {
+            rv.push(len as u8 | 0x80);
+            len >>= 7;
+        }
+        rv.push(len as u8);
+        rv.extend_from_slice(data);
+        rv
+    }
+    fn attr(ns: &str, name: &str, value: &str) -> Vec<u8> {
+        let mut rv = field(1, ns.as_bytes());
+        rv.extend(field(2, name.as_bytes()));
+        rv.extend(field(3, value.as_bytes()));
+        // resource id as varint, which must be skipped
+        rv.extend_from_slice(&[5 << 3, 0x9b, 0x84, 0x04]);
+        field(4, &rv)
+    }
+
+    let mut element = field(3, b"manifest");
+    element.extend(attr("", "package", "io.sentry.sample"));
+    element.extend(attr(ANDROID_NS, "versionCode", "42"));
+    element.extend(attr(ANDROID_NS, "versionName", "1.2.3"));
+    field(1, &element)
+}
+
+#[test]
+fn test_parse_proto_xml_root() {
+    let manifest = AndroidManifest {
+        root: parse_proto_xml_root(&build_proto_manifest()).unwrap(),
+    };
+    assert_eq!(manifest.package(), "io.sentry.sample");
+    assert_eq!(manifest.version_code(), "42");
+    assert_eq!(manifest.version_name(), "1.2.3");
+}
+
+#[test]
+fn test_parse_malformed_manifests() {
+    type Parse = fn(&[u8]) -> Result<Element, Error>;
+    let manifests: [(Parse, Vec<u8>); 3] = [
+        (parse_binary_xml_root, build_binary_manifest(false)),
+        (parse_binary_xml_root, build_binary_manifest(true)),
+        (parse_proto_xml_root, build_proto_manifest()),
+    ];
+
+    for (parse, data) in &manifests {
+        // truncated manifests must be rejected instead of reading past the end
+        for len in 0..data.len() {
+            assert!(parse(&data[..len]).is_err(), "truncated at {}", len);
+        }
+
+        // corrupted lengths, offsets and indexes must not panic
+        for index in 0..data.len() {
+            for &byte in &[0x00, 0x01, 0x7f, 0x80, 0xff] {
+                let mut data = data.clone();
+                data[index] = byte;
+                parse(&data).ok();
+            }
+        }
+    }
+}
+
+#[test]
+fn test_discover_android_app() {
+    let dir = tempfile::tempdir().unwrap();
+    let app = dir.path().join("app");
+    fs::create_dir_all(app.join("src/main")).unwrap();
+    fs::write(
+        app.join("src/main/AndroidManifest.xml"),
+        format!(
+            r#"<manifest xmlns:android="{}" package="io.sentry.manifest" android:versionCode="1" android:versionName="0.1" />"#,
+            ANDROID_NS
+        ),
+    )
+    .unwrap();
+
+    let manifest = AndroidManifest::discover(Some(app.join("src")))
+        .unwrap()
+        .unwrap();
+    assert_eq!(manifest.package(), "io.sentry.manifest");
+    assert_eq!(manifest.version_code(), "1");
+    assert_eq!(manifest.version_name(), "0.1");
+
+    // values in the gradle files take precedence
+    fs::write(
+        app.join("build.gradle.kts"),
+        "android {\n    defaultConfig {\n        applicationId = \"io.sentry.sample\"\n        \
+         versionCode = 42\n        versionName = \"1.2.3\"\n    }\n}\n",
+    )
+    .unwrap();
+
+    let manifest = AndroidManifest::discover(Some(dir.path().to_path_buf()))
+        .unwrap()
+        .unwrap();
+    assert_eq!(manifest.package(), "io.sentry.sample");
+    assert_eq!(manifest.version_code(), "42");
+    assert_eq!(manifest.version_name(), "1.2.3");
+}
+
+#[test]
+fn test_discover_stops_at_project_root() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("app")).unwrap();
+    fs::write(
+        dir.path().join("app/build.gradle"),
+        "applicationId \"io.sentry.outer\"\nversionName \"1.0\"\n",
+    )
+    .unwrap();
+
+    let project = dir.path().join("project");
+    fs::create_dir_all(project.join("src")).unwrap();
+    fs::write(project.join("settings.gradle"), "").unwrap();
+
+    assert!(AndroidManifest::discover(Some(project.join("src")))
+        .unwrap()
+        .is_none());
+    assert!(AndroidManifest::discover(Some(dir.path().join("app")))
+        .unwrap()
+        .is_some());
+}
@@ -0,0 +1,362 @@
+//! Reports problems, progress and results in formats CI servers understand.
+//!
+//! On GitHub Actions problems become `::error` and `::warning` workflow
+//! commands, which show up as annotations on the run, and results are
+//! appended to the Markdown job summary.  TeamCity gets service messages for
+//! problems and the steps of uploads, Jenkins gets plain markers that its
+//! log parsers and the warnings plugin pick up.  Azure Pipelines gets
+//! logging commands that add issues to the build and collapsible groups.
+use std::env;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use failure::{bail, Error};
+use log::{debug, warn};
+use url::Url;
+
+use crate::config::Config;
+
+static OUTPUT: AtomicUsize = AtomicUsize::new(0);
+static SUMMARY_STARTED: AtomicBool = AtomicBool::new(false);
+
+/// The CI server to format output for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CiOutput {
+    None,
+    GitHub,
+    TeamCity,
+    Jenkins,
+    Azure,
+}
+
+impl CiOutput {
+    /// Detects the CI server from its environment variables.
+    ///
+    /// Jenkins is never detected since its markers are only useful with
+    /// log parser rules set up for them.
+    pub fn detect() -> CiOutput {
+        if matches!(env::var("GITHUB_ACTIONS"), Ok(var) if var == "true") {
+            CiOutput::GitHub
+        } else if env::var_os("TEAMCITY_VERSION").is_some() {
+            CiOutput::TeamCity
+        } else if env::var_os("TF_BUILD").is_some() {
+            CiOutput::Azure
+        } else {
+            CiOutput::None
+        }
+    }
+}
+
+impl FromStr for CiOutput {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<CiOutput, Error> {
+        Ok(match s {
+            "none" => CiOutput::None,
+            "github" => CiOutput::GitHub,
+            "teamcity" => CiOutput::TeamCity,
+            "jenkins" => CiOutput::Jenkins,
+            "azure" => CiOutput::Azure,
+            _ => bail!("Unknown CI output format: {}", s),
+        })
+    }
+}
+
+/// The severity of an annotation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnnotationLevel {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for AnnotationLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            AnnotationLevel::Warning => write!(f, "WARNING"),
+            AnnotationLevel::Error => write!(f, "ERROR"),
+        }
+    }
+}
+
+pub fn set_output(output: CiOutput) {
+    OUTPUT.store(output as usize, Ordering::Relaxed);
+}
+
+pub fn output() -> CiOutput {
+    match OUTPUT.load(Ordering::Relaxed) {
+        1 => CiOutput::GitHub,
+        2 => CiOutput::TeamCity,
+        3 => CiOutput::Jenkins,
+        4 => CiOutput::Azure,
+        _ => CiOutput::None,
+    }
+}
+
+fn escape_data(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_property(value: &str) -> String {
+    escape_data(value).replace(':', "%3A").replace(',', "%2C")
+}
+
+fn format_annotation(level: AnnotationLevel, message: &str, file: Option<&str>) -> String {
+    let command = match level {
+        AnnotationLevel::Warning => "warning",
+        AnnotationLevel::Error => "error",
+    };
+    match file {
+        Some(file) => format!(
+            "::{} file={}::{}",
+            command,
+            escape_property(file),
+            escape_data(message)
+        ),
+        None => format!("::{}::{}", command, escape_data(message)),
+    }
+}
+
+/// Escapes a value of a TeamCity service message.
+fn escape_teamcity(value: &str) -> String {
+    let mut rv = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '|' => rv.push_str("||"),
+            '\'' => rv.push_str("|'"),
+            '\n' => rv.push_str("|n"),
+            '\r' => rv.push_str("|r"),
+            '[' => rv.push_str("|["),
+            ']' => rv.push_str("|]"),
+            c => rv.push(c),
+        }
+    }
+    rv
+}
+
+fn teamcity_message(name: &str, attrs: &[(&str, &str)]) -> String {
+    let mut rv = format!("##teamcity[{}", name);
+    for (key, value) in attrs {
+        rv.push_str(&format!(" {}='{}'", key, escape_teamcity(value)));
+    }
+    rv.push(']');
+    rv
+}
+
+/// Escapes a value of an Azure Pipelines logging command.
+fn escape_azure(value: &str) -> String {
+    value
+        .replace('%', "%AZP25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_azure_property(value: &str) -> String {
+    escape_azure(value).replace(';', "%3B").replace(']', "%5D")
+}
+
+fn azure_issue(level: AnnotationLevel, message: &str, file: Option<&str>) -> String {
+    let ty = match level {
+        AnnotationLevel::Warning => "warning",
+        AnnotationLevel::Error => "error",
+    };
+    match file {
+        Some(file) => format!(
+            "##vso[task.logissue type={};sourcepath={}]{}",
+            ty,
+            escape_azure_property(file),
+            escape_azure(message)
+        ),
+        None => format!("##vso[task.logissue type={}]{}", ty, escape_azure(message)),
+    }
+}
+
+/// Emits an annotation, optionally pointing at a file.
+///
+/// The GitHub runner picks up workflow commands on both output streams, so
+/// they go to stderr to keep the regular output of commands intact.
+pub fn annotate(level: AnnotationLevel, message: &str, file: Option<&Path>) {
+    match output() {
+        CiOutput::None => {}
+        CiOutput::GitHub => {
+            // annotations are only attached to files given relative to the checkout
+            let file = file.map(|path| {
+                env::var_os("GITHUB_WORKSPACE")
+                    .and_then(|workspace| path.strip_prefix(workspace).ok())
+                    .unwrap_or(path)
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            });
+            eprintln!("{}", format_annotation(level, message, file.as_deref()));
+        }
+        CiOutput::TeamCity => {
+            let text = match file {
+                Some(file) => format!("{}: {}", file.display(), message),
+                None => message.to_string(),
+            };
+            println!(
+                "{}",
+                teamcity_message(
+                    "message",
+                    &[("text", &text), ("status", &level.to_string())]
+                )
+            );
+        }
+        CiOutput::Jenkins => match file {
+            Some(file) => println!("[sentry-cli] {}: {}: {}", level, file.display(), message),
+            None => println!("[sentry-cli] {}: {}", level, message),
+        },
+        CiOutput::Azure => {
+            let file = file.map(|path| path.to_string_lossy());
+            println!("{}", azure_issue(level, message, file.as_deref()));
+        }
+    }
+}
+
+/// Tags the current build with a value, e.g. the name of a release.
+pub fn tag_build(tag: &str) {
+    match output() {
+        CiOutput::Azure => println!("##vso[build.addbuildtag]{}", escape_azure_property(tag)),
+        _ => warn!("Tagging builds is only supported on Azure Pipelines"),
+    }
+}
+
+/// A step of a longer operation, closed when dropped.
+pub struct Step {
+    name: String,
+}
+
+/// Opens a step that shows up as a collapsible block on TeamCity and as
+/// start and end markers on Jenkins.
+pub fn step<S: Into<String>>(name: S) -> Step {
+    let name = name.into();
+    match output() {
+        CiOutput::TeamCity => println!("{}", teamcity_message("blockOpened", &[("name", &name)])),
+        CiOutput::Jenkins => println!("[sentry-cli] >>> {}", name),
+        CiOutput::Azure => println!("##[group]{}", name),
+        _ => {}
+    }
+    Step { name }
+}
+
+impl Drop for Step {
+    fn drop(&mut self) {
+        match output() {
+            CiOutput::TeamCity => println!(
+                "{}",
+                teamcity_message("blockClosed", &[("name", &self.name)])
+            ),
+            CiOutput::Jenkins => println!("[sentry-cli] <<< {}", self.name),
+            CiOutput::Azure => println!("##[endgroup]"),
+            _ => {}
+        }
+    }
+}
+
+/// Appends a line of Markdown to the job summary.
+pub fn add_summary(line: &str) {
+    if output() != CiOutput::GitHub {
+        return;
+    }
+    let path = match env::var_os("GITHUB_STEP_SUMMARY") {
+        Some(path) => path,
+        None => return,
+    };
+
+    let mut markdown = String::new();
+    if !SUMMARY_STARTED.swap(true, Ordering::Relaxed) {
+        markdown.push_str("### Sentry\n\n");
+    }
+    markdown.push_str(line);
+    markdown.push('\n');
+
+    let rv = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(markdown.as_bytes()));
+    if let Err(err) = rv {
+        debug!("Could not write job summary: {}", err);
+    }
+}
+
+/// Returns the URL of a page of the Sentry web interface.
+pub fn web_url(segments: &[&str]) -> Option<String> {
+    let mut url = Url::parse(Config::current().get_base_url().ok()?).ok()?;
+    url.path_segments_mut()
+        .ok()?
+        .pop_if_empty()
+        .extend(segments)
+        .push("");
+    Some(url.to_string())
+}
+
+/// Formats a Markdown link to a page of the Sentry web interface.
+pub fn web_link(text: &str, segments: &[&str]) -> String {
+    match web_url(segments) {
+        Some(url) => format!("[`{}`]({})", text, url),
+        None => format!("`{}`", text),
+    }
+}
+
+/// Returns the URL of a release in the Sentry web interface.
+pub fn release_url(org: &str, version: &str) -> Option<String> {
+    web_url(&["organizations", org, "releases", version])
+}
+
+/// Formats a Markdown link to a release.
+pub fn release_link(org: &str, version: &str) -> String {
+    match release_url(org, version) {
+        Some(url) => format!("[`{}`]({})", version, url),
+        None => format!("`{}`", version),
+    }
+}
+
+#[test]
+fn test_format_annotation() {
+    assert_eq!(
+        format_annotation(AnnotationLevel::Error, "upload failed\n50%", None),
+        "::error::upload failed%0A50%25"
+    );
+    assert_eq!(
+        format_annotation(
+            AnnotationLevel::Warning,
+            "missing sourcemap",
+            Some("dist/a,b:c.js")
+        ),
+        "::warning file=dist/a%2Cb%3Ac.js::missing sourcemap"
+    );
+}
+
+#[test]
+fn test_teamcity_message() {
+    assert_eq!(
+        teamcity_message(
+            "message",
+            &[("text", "can't read [x]|y\n"), ("status", "ERROR")]
+        ),
+        "##teamcity[message text='can|'t read |[x|]||y|n' status='ERROR']"
+    );
+}
+
+#[test]
+fn test_azure_issue() {
+    assert_eq!(
+        azure_issue(AnnotationLevel::Error, "upload failed\n100%", None),
+        "##vso[task.logissue type=error]upload failed%0A100%AZP25"
+    );
+    assert_eq!(
+        azure_issue(
+            AnnotationLevel::Warning,
+            "missing sourcemap",
+            Some("dist/a;b].js")
+        ),
+        "##vso[task.logissue type=warning;sourcepath=dist/a%3Bb%5D.js]missing sourcemap"
+    );
+}
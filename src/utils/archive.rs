@@ -0,0 +1,222 @@
+//! Reads the files of ZIP archives and tarballs into memory.
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Component, Path};
+
+use failure::{bail, Error, ResultExt};
+use flate2::read::GzDecoder;
+
+/// The kinds of archives that are read like directories.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveKind {
+    /// Returns the kind of archive by the file extension of the path.
+    pub fn from_path(path: &Path) -> Option<ArchiveKind> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        if name.ends_with(".zip") {
+            Some(ArchiveKind::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveKind::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(ArchiveKind::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns whether the path is a file that is read as an archive.
+pub fn is_archive(path: &Path) -> bool {
+    path.is_file() && ArchiveKind::from_path(path).is_some()
+}
+
+/// Normalizes the path of an entry, dropping absolute and `..` components so
+/// nothing points outside the archive.
+fn normalize_name(name: &str) -> Option<String> {
+    let parts: Vec<_> = Path::new(name)
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => part.to_str(),
+            _ => None,
+        })
+        .collect();
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join("/"))
+    }
+}
+
+fn parse_octal(field: &[u8]) -> Result<u64, Error> {
+    let digits = String::from_utf8_lossy(field);
+    let digits = digits.trim_matches(|c: char| c == '\0' || c == ' ');
+    if digits.is_empty() {
+        return Ok(0);
+    }
+    Ok(u64::from_str_radix(digits, 8).context("Invalid number in tar header")?)
+}
+
+fn header_str(field: &[u8]) -> String {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+/// Returns the `path` of a PAX extended header.
+fn pax_path(data: &[u8]) -> Option<String> {
+    // records look like "<length> <key>=<value>\n"
+    String::from_utf8_lossy(data).lines().find_map(|record| {
+        let (_, pair) = record.split_once(' ')?;
+        pair.strip_prefix("path=").map(str::to_string)
+    })
+}
+
+/// Reads the regular files of a tar stream.  Understands ustar, GNU long
+/// names and PAX paths, which covers what `tar` and CI tools write.
+fn read_tar<R: Read>(mut reader: R) -> Result<Vec<(String, Vec<u8>)>, Error> {
+    let mut rv = vec![];
+    let mut long_name = None;
+    let mut header = [0u8; 512];
+    loop {
+        if let Err(err) = reader.read_exact(&mut header) {
+            if err.kind() == std::io::ErrorKind::UnexpectedEof {
+                break;
+            }
+            return Err(err.into());
+        }
+        // the archive ends with empty blocks
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+        if header[124] & 0x80 != 0 {
+            bail!("Tar entries larger than 8GB are not supported");
+        }
+        let size = parse_octal(&header[124..136])?;
+        let mut data = vec![];
+        (&mut reader).take(size).read_to_end(&mut data)?;
+        if (data.len() as u64) < size {
+            bail!("Unexpected end of tar archive");
+        }
+        let padding = (512 - size % 512) % 512;
+        std::io::copy(&mut (&mut reader).take(padding), &mut std::io::sink())?;
+
+        match header[156] {
+            b'L' => long_name = Some(header_str(&data)),
+            b'x' => long_name = pax_path(&data).or(long_name),
+            b'0' | 0 | b'7' => {
+                let name = match long_name.take() {
+                    Some(name) => name,
+                    None => {
+                        let name = header_str(&header[0..100]);
+                        let prefix = if &header[257..262] == b"ustar" {
+                            header_str(&header[345..500])
+                        } else {
+                            String::new()
+                        };
+                        if prefix.is_empty() {
+                            name
+                        } else {
+                            format!("{}/{}", prefix, name)
+                        }
+                    }
+                };
+                if let Some(name) = normalize_name(&name) {
+                    rv.push((name, data));
+                }
+            }
+            // directories, links and global headers carry no files
+            _ => long_name = None,
+        }
+    }
+    Ok(rv)
+}
+
+fn read_zip(file: File) -> Result<Vec<(String, Vec<u8>)>, Error> {
+    let mut zip = zip::ZipArchive::new(BufReader::new(file))?;
+    let mut rv = vec![];
+    for idx in 0..zip.len() {
+        let mut entry = zip.by_index(idx)?;
+        if entry.is_dir() {
+            continue;
+        }
+        if let Some(name) = normalize_name(entry.name()) {
+            let mut data = Vec::with_capacity(entry.size() as usize);
+            entry.read_to_end(&mut data)?;
+            rv.push((name, data));
+        }
+    }
+    Ok(rv)
+}
+
+/// Reads every file of an archive along with its path inside the archive.
+pub fn read_archive(path: &Path) -> Result<Vec<(String, Vec<u8>)>, Error> {
+    let kind = match ArchiveKind::from_path(path) {
+        Some(kind) => kind,
+        None => bail!("{} is not a ZIP archive or tarball", path.display()),
+    };
+    let file = File::open(path).with_context(|_| format!("Could not open {}", path.display()))?;
+    let rv = match kind {
+        ArchiveKind::Zip => read_zip(file),
+        ArchiveKind::Tar => read_tar(BufReader::new(file)),
+        ArchiveKind::TarGz => read_tar(GzDecoder::new(BufReader::new(file))),
+    };
+    Ok(rv.with_context(|_| format!("Could not read {}", path.display()))?)
+}
+
+#[cfg(test)]
+fn tar_header(name: &str, size: usize, kind: u8) -> Vec<u8> {
+    let mut header = vec![0u8; 512];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    header[124..135].copy_from_slice(format!("{:011o}", size).as_bytes());
+    header[156] = kind;
+    header[257..262].copy_from_slice(b"ustar");
+    header
+}
+
+#[test]
+fn test_read_tar() {
+    let long_name = format!("dist/{}/app.js", "nested".repeat(20));
+    let mut tar = tar_header("dist/", 0, b'5');
+    tar.extend(tar_header("dist/main.js", 5, b'0'));
+    tar.extend(b"main\n");
+    tar.extend(vec![0; 507]);
+    tar.extend(tar_header("././@LongLink", long_name.len(), b'L'));
+    tar.extend(long_name.as_bytes());
+    tar.extend(vec![0; 512 - long_name.len()]);
+    tar.extend(tar_header("ignored", 3, b'0'));
+    tar.extend(b"app");
+    tar.extend(vec![0; 509]);
+    tar.extend(tar_header("../evil.js", 0, b'0'));
+    tar.extend(vec![0; 1024]);
+
+    let files = read_tar(&tar[..]).unwrap();
+    assert_eq!(
+        files,
+        vec![
+            ("dist/main.js".to_string(), b"main\n".to_vec()),
+            (long_name, b"app".to_vec()),
+            ("evil.js".to_string(), vec![]),
+        ]
+    );
+}
+
+#[test]
+fn test_archive_kind() {
+    assert_eq!(
+        ArchiveKind::from_path(Path::new("build.TGZ")),
+        Some(ArchiveKind::TarGz)
+    );
+    assert_eq!(
+        ArchiveKind::from_path(Path::new("dist.tar.gz")),
+        Some(ArchiveKind::TarGz)
+    );
+    assert_eq!(
+        ArchiveKind::from_path(Path::new("dist.zip")),
+        Some(ArchiveKind::Zip)
+    );
+    assert_eq!(ArchiveKind::from_path(Path::new("app.js")), None);
+}
@@ -2,10 +2,10 @@
 
 use std::str::FromStr;
 
-use chrono::{DateTime, TimeZone, Utc};
+use chrono::{DateTime, Duration, TimeZone, Utc};
 use clap::AppSettings;
 use failure::{bail, Error};
-use symbolic::common::DebugId;
+use symbolic::common::{Arch, DebugId};
 use uuid::Uuid;
 
 fn validate_org(v: String) -> Result<(), String> {
@@ -31,7 +31,7 @@ pub fn validate_project(v: String) -> Result<(), String> {
     }
 }
 
-fn validate_version(v: String) -> Result<(), String> {
+pub fn validate_version(v: String) -> Result<(), String> {
     if v.trim() != v {
         Err(
             "Invalid release version. Releases must not contain leading or trailing spaces."
@@ -84,6 +84,142 @@ pub fn validate_id(s: String) -> Result<(), String> {
     }
 }
 
+pub fn validate_arch(s: String) -> Result<(), String> {
+    match Arch::from_str(&s) {
+        Ok(Arch::Unknown) | Err(_) => Err("Invalid architecture.".to_string()),
+        Ok(_) => Ok(()),
+    }
+}
+
+pub fn validate_duration(v: String) -> Result<(), String> {
+    if let Err(err) = get_duration(&v) {
+        Err(err.to_string())
+    } else {
+        Ok(())
+    }
+}
+
+pub fn validate_interval(v: String) -> Result<(), String> {
+    match get_duration(&v) {
+        Ok(duration) if duration < Duration::seconds(1) => {
+            Err("The interval must be at least one second.".into())
+        }
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+pub fn validate_glob(v: String) -> Result<(), String> {
+    glob::Pattern::new(&v)
+        .map(|_| ())
+        .map_err(|err| format!("Invalid glob pattern: {}", err))
+}
+
+pub fn validate_regex(v: String) -> Result<(), String> {
+    regex::Regex::new(&v)
+        .map(|_| ())
+        .map_err(|err| format!("Invalid regular expression: {}", err))
+}
+
+pub fn validate_monitor_slug(v: String) -> Result<(), String> {
+    if !v.is_empty()
+        && v.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        Ok(())
+    } else {
+        Err("Invalid monitor slug.".to_string())
+    }
+}
+
+/// Parses a duration like `90s`, `30m`, `12h`, `7d` or `2w`.  Plain
+/// integers are interpreted as seconds.
+pub fn get_duration(value: &str) -> Result<Duration, Error> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (num, unit) = value.split_at(split);
+    let num = match num.parse::<i64>() {
+        Ok(num) => num,
+        Err(_) => {
+            bail!("Invalid duration. Expected a number with an optional unit (s, m, h, d, w).")
+        }
+    };
+    let unit_secs = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => bail!(
+            "Invalid duration unit '{}'. Use one of s, m, h, d or w.",
+            unit
+        ),
+    };
+    match num.checked_mul(unit_secs) {
+        Some(secs) if secs <= Duration::max_value().num_seconds() => Ok(Duration::seconds(secs)),
+        _ => bail!("Invalid duration. {} is too long.", value),
+    }
+}
+
+pub fn validate_byte_size(v: String) -> Result<(), String> {
+    get_byte_size(&v).map(|_| ()).map_err(|err| err.to_string())
+}
+
+/// Parses a size like `512KB`, `10MB` or `1GB`, where units are powers of
+/// 1024.  Plain integers are interpreted as bytes.
+pub fn get_byte_size(value: &str) -> Result<u64, Error> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (num, unit) = value.split_at(split);
+    let num = match num.parse::<u64>() {
+        Ok(num) => num,
+        Err(_) => bail!("Invalid size. Expected a number with an optional unit (KB, MB, GB)."),
+    };
+    let factor: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        _ => bail!("Invalid size unit '{}'. Use one of KB, MB or GB.", unit),
+    };
+    match num.checked_mul(factor) {
+        Some(size) => Ok(size),
+        None => bail!("Size {} is too large", value),
+    }
+}
+
+pub fn validate_exit_code(v: String) -> Result<(), String> {
+    get_exit_code(&v).map(|_| ()).map_err(|err| err.to_string())
+}
+
+pub fn validate_env_var(v: String) -> Result<(), String> {
+    match v.find('=') {
+        Some(idx) if idx > 0 => Ok(()),
+        _ => Err("Invalid environment variable, KEY=VALUE required.".to_string()),
+    }
+}
+
+/// Parses a process exit code, either decimal or hexadecimal like the
+/// `0x80070005` codes of Windows.
+pub fn get_exit_code(value: &str) -> Result<i32, Error> {
+    let value = value.trim();
+    let code = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u32::from_str_radix(hex, 16).map(|code| code as i32).ok(),
+        None => value.parse().ok(),
+    };
+    match code {
+        Some(code) => Ok(code),
+        None => bail!("Invalid exit code '{}'", value),
+    }
+}
+
 pub fn get_timestamp(value: &str) -> Result<DateTime<Utc>, Error> {
     if let Ok(int) = value.parse::<i64>() {
         Ok(Utc.timestamp(int, 0))
@@ -104,6 +240,8 @@ pub trait ArgExt: Sized {
         self.org_arg().project_arg()
     }
     fn version_arg(self, index: u64) -> Self;
+    fn idempotent_arg(self) -> Self;
+    fn ignore_args(self) -> Self;
 }
 
 impl<'a: 'b, 'b> ArgExt for clap::App<'a, 'b> {
@@ -153,4 +291,43 @@ impl<'a: 'b, 'b> ArgExt for clap::App<'a, 'b> {
                 .help("The version of the release"),
         )
     }
+
+    fn idempotent_arg(self) -> clap::App<'a, 'b> {
+        self.arg(clap::Arg::with_name("idempotent").long("idempotent").help(
+            "Succeed without changes if an identical resource already exists.{n}\
+                     Whether anything changed is printed and recorded in the result \
+                     of --machine-readable.",
+        ))
+    }
+
+    fn ignore_args(self) -> clap::App<'a, 'b> {
+        self.arg(
+            clap::Arg::with_name("ignore")
+                .long("ignore")
+                .short("i")
+                .value_name("IGNORE")
+                .multiple(true)
+                .help("Ignores all files and folders matching the given glob"),
+        )
+        .arg(
+            clap::Arg::with_name("ignore_file")
+                .long("ignore-file")
+                .short("I")
+                .value_name("IGNORE_FILE")
+                .help(
+                    "Ignore all files and folders specified in the given \
+                     ignore file, e.g. .gitignore.",
+                ),
+        )
+    }
+}
+
+#[test]
+fn test_get_duration() {
+    assert_eq!(get_duration("90").unwrap(), Duration::seconds(90));
+    assert_eq!(get_duration("12h").unwrap(), Duration::hours(12));
+    assert_eq!(get_duration("2w").unwrap(), Duration::weeks(2));
+    assert!(get_duration("7y").is_err());
+    assert!(get_duration("9223372036854775807w").is_err());
+    assert!(get_duration("99999999999999999d").is_err());
 }
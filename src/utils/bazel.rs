@@ -0,0 +1,198 @@
+//! Implements Bazel's persistent worker protocol.
+//!
+//! Bazel starts the worker once with `--persistent_worker` and then sends
+//! one work request per action on stdin.  Only the JSON flavor of the
+//! protocol is spoken, so actions need the `requires-worker-protocol: json`
+//! execution requirement.  Requests run in the same process one after
+//! another, which saves starting a process per action.  Every request gets
+//! its own config and API client, so the flags of one request never leak
+//! into the next.
+use std::fs;
+use std::io::{self, Read, Write};
+
+use failure::{Error, ResultExt};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::fs::TempFile;
+
+/// The flags Bazel and users pass to start a persistent worker.
+const WORKER_FLAGS: &[&str] = &["--persistent_worker", "--persistent-worker"];
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WorkRequest {
+    #[serde(default)]
+    arguments: Vec<String>,
+    #[serde(default)]
+    request_id: i64,
+    #[serde(default)]
+    cancel: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WorkResponse {
+    exit_code: i32,
+    output: String,
+    request_id: i64,
+}
+
+/// Checks whether sentry-cli was started as a persistent worker.
+pub fn is_persistent_worker(args: &[String]) -> bool {
+    args.iter()
+        .skip(1)
+        .any(|arg| WORKER_FLAGS.contains(&arg.as_str()))
+}
+
+/// Expands `@file` arguments into the lines of the file.
+fn expand_flagfiles(args: Vec<String>) -> Result<Vec<String>, Error> {
+    let mut rv = vec![];
+    for arg in args {
+        match arg.strip_prefix('@') {
+            Some(path) => {
+                let contents = fs::read_to_string(path)
+                    .with_context(|_| format!("Could not read flagfile {}", path))?;
+                rv.extend(contents.lines().map(str::to_string));
+            }
+            None => rv.push(arg),
+        }
+    }
+    Ok(rv)
+}
+
+/// Redirects stdout and stderr into a file while a request runs.
+#[cfg(unix)]
+struct Capture {
+    saved: [libc::c_int; 2],
+}
+
+#[cfg(unix)]
+impl Capture {
+    fn start(file: &fs::File) -> io::Result<Capture> {
+        use std::os::unix::io::AsRawFd;
+
+        io::stdout().flush()?;
+        io::stderr().flush()?;
+        let saved = unsafe { [libc::dup(1), libc::dup(2)] };
+        if saved.contains(&-1) {
+            return Err(io::Error::last_os_error());
+        }
+        unsafe {
+            libc::dup2(file.as_raw_fd(), 1);
+            libc::dup2(file.as_raw_fd(), 2);
+        }
+        Ok(Capture { saved })
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Capture {
+    fn drop(&mut self) {
+        io::stdout().flush().ok();
+        io::stderr().flush().ok();
+        unsafe {
+            libc::dup2(self.saved[0], 1);
+            libc::dup2(self.saved[1], 2);
+            libc::close(self.saved[0]);
+            libc::close(self.saved[1]);
+        }
+    }
+}
+
+/// Runs a request in this process and returns its exit code and output.
+#[cfg(unix)]
+fn run_request<F>(args: &[String], execute: &mut F) -> Result<(i32, String), Error>
+where
+    F: FnMut(&[String]) -> i32,
+{
+    let tf = TempFile::create()?;
+    let code = {
+        let _capture = Capture::start(&tf.open()?)?;
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| execute(args))).unwrap_or(1)
+    };
+    let mut output = String::new();
+    tf.open()?.read_to_string(&mut output)?;
+    Ok((code, output))
+}
+
+/// Without `dup2` every request runs in a child process instead.
+#[cfg(not(unix))]
+fn run_request<F>(args: &[String], _execute: &mut F) -> Result<(i32, String), Error>
+where
+    F: FnMut(&[String]) -> i32,
+{
+    let output = std::process::Command::new(std::env::current_exe()?)
+        .args(&args[1..])
+        .output()?;
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    Ok((output.status.code().unwrap_or(1), text))
+}
+
+/// Serves work requests from stdin until Bazel closes it.
+///
+/// `execute` runs a command line and returns its exit code.  The arguments
+/// given at startup, minus the worker flag, are put in front of the
+/// arguments of every request.
+pub fn run_worker<F>(args: &[String], mut execute: F) -> Result<(), Error>
+where
+    F: FnMut(&[String]) -> i32,
+{
+    let startup_args: Vec<_> = args
+        .iter()
+        .filter(|arg| !WORKER_FLAGS.contains(&arg.as_str()))
+        .cloned()
+        .collect();
+    let stdin = io::stdin();
+    let requests = serde_json::Deserializer::from_reader(stdin.lock()).into_iter::<WorkRequest>();
+
+    for request in requests {
+        let request = request.context("Invalid work request")?;
+        // requests are handled one at a time, so they are done before a
+        // cancellation could arrive
+        if request.cancel {
+            continue;
+        }
+
+        let (exit_code, output) = match expand_flagfiles(request.arguments) {
+            Ok(arguments) => {
+                let mut args = startup_args.clone();
+                args.extend(arguments);
+                run_request(&args, &mut execute)?
+            }
+            Err(err) => (1, format!("error: {}\n", err)),
+        };
+
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        serde_json::to_writer(
+            &mut stdout,
+            &WorkResponse {
+                exit_code,
+                output,
+                request_id: request.request_id,
+            },
+        )?;
+        writeln!(stdout)?;
+        stdout.flush()?;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_expand_flagfiles() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("args");
+    fs::write(&path, "upload-dif\n--org\nwat-org\n").unwrap();
+
+    let args = expand_flagfiles(vec![
+        "--log-level=debug".into(),
+        format!("@{}", path.display()),
+    ])
+    .unwrap();
+    assert_eq!(
+        args,
+        vec!["--log-level=debug", "upload-dif", "--org", "wat-org"]
+    );
+}
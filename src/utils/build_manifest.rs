@@ -0,0 +1,203 @@
+//! Reads the apps and their output directories from monorepo build tools.
+//!
+//! Two formats are understood: the project graph Nx writes with
+//! `nx graph --affected --file=graph.json` and the task list Turborepo prints
+//! with `turbo run build --dry=json` or writes with `--summarize`.
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use failure::{bail, Error, ResultExt};
+use serde_json::Value;
+
+/// An app of the workspace and the directory its build writes to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildApp {
+    pub name: String,
+    pub output_dir: PathBuf,
+}
+
+impl BuildApp {
+    /// The name of the app usable in release names, with the scope of
+    /// npm packages turned into a prefix.
+    pub fn release_prefix(&self) -> String {
+        self.name.trim_start_matches('@').replace('/', "-")
+    }
+}
+
+/// Cuts an output glob off at the first component with wildcards.
+fn glob_base(pattern: &str) -> PathBuf {
+    pattern
+        .split('/')
+        .take_while(|component| !component.contains(&['*', '?', '[', '{'][..]))
+        .filter(|component| !component.is_empty() && *component != ".")
+        .collect()
+}
+
+fn nx_output_dir(root: &str, target: &Value) -> Option<PathBuf> {
+    let output_path = target
+        .pointer("/options/outputPath")
+        .and_then(Value::as_str);
+    if let Some(output_path) = output_path {
+        return Some(glob_base(output_path));
+    }
+    let output = target.get("outputs")?.as_array()?.first()?.as_str()?;
+    let output = output
+        .replace("{workspaceRoot}/", "")
+        .replace("{projectRoot}", root);
+    Some(glob_base(&output))
+}
+
+fn parse_nx(manifest: &Value) -> Vec<BuildApp> {
+    let affected: Option<Vec<&str>> = manifest
+        .get("affectedProjects")
+        .and_then(Value::as_array)
+        .map(|projects| projects.iter().filter_map(Value::as_str).collect());
+    let nodes = match manifest.pointer("/graph/nodes").and_then(Value::as_object) {
+        Some(nodes) => nodes,
+        None => return vec![],
+    };
+
+    let mut rv = vec![];
+    for (name, node) in nodes {
+        if !matches!(
+            node.get("type").and_then(Value::as_str),
+            Some("app") | Some("application")
+        ) {
+            continue;
+        }
+        if let Some(ref affected) = affected {
+            if !affected.contains(&name.as_str()) {
+                continue;
+            }
+        }
+        let data = &node["data"];
+        let root = data.get("root").and_then(Value::as_str).unwrap_or("");
+        if let Some(output_dir) = data
+            .pointer("/targets/build")
+            .and_then(|target| nx_output_dir(root, target))
+        {
+            rv.push(BuildApp {
+                name: name.clone(),
+                output_dir,
+            });
+        }
+    }
+    rv
+}
+
+fn parse_turbo(tasks: &[Value]) -> Vec<BuildApp> {
+    let mut apps = BTreeMap::new();
+    for task in tasks {
+        // run summaries record failed tasks, their outputs are incomplete
+        let exit_code = task.pointer("/execution/exitCode").and_then(Value::as_i64);
+        if exit_code.unwrap_or(0) != 0 {
+            continue;
+        }
+        let package = match task.get("package").and_then(Value::as_str) {
+            Some(package) if package != "//" => package,
+            _ => continue,
+        };
+        let directory = task.get("directory").and_then(Value::as_str).unwrap_or("");
+        let output = task
+            .get("outputs")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .find(|output| !output.starts_with('!'));
+        if let Some(output) = output {
+            apps.entry(package.to_string())
+                .or_insert_with(|| Path::new(directory).join(glob_base(output)));
+        }
+    }
+    apps.into_iter()
+        .map(|(name, output_dir)| BuildApp { name, output_dir })
+        .collect()
+}
+
+/// Parses an Nx project graph or a Turborepo task list.
+pub fn parse_build_manifest(contents: &[u8]) -> Result<Vec<BuildApp>, Error> {
+    let manifest: Value = serde_json::from_slice(contents)?;
+    if let Some(tasks) = manifest.get("tasks").and_then(Value::as_array) {
+        Ok(parse_turbo(tasks))
+    } else if manifest.get("graph").is_some() {
+        Ok(parse_nx(&manifest))
+    } else {
+        bail!("Unknown build manifest, expected an Nx project graph or Turborepo tasks");
+    }
+}
+
+/// Reads a build manifest from a file.
+pub fn read_build_manifest(path: &Path) -> Result<Vec<BuildApp>, Error> {
+    let contents = fs::read(path).with_context(|_| format!("Could not read {}", path.display()))?;
+    Ok(parse_build_manifest(&contents)
+        .with_context(|_| format!("Could not parse {}", path.display()))?)
+}
+
+#[test]
+fn test_parse_nx_graph() {
+    let apps = parse_build_manifest(
+        br#"{
+            "graph": {"nodes": {
+                "web": {"name": "web", "type": "app", "data": {
+                    "root": "apps/web",
+                    "targets": {"build": {"options": {"outputPath": "dist/apps/web"}}}
+                }},
+                "admin": {"name": "admin", "type": "app", "data": {
+                    "root": "apps/admin",
+                    "targets": {"build": {"outputs": ["{projectRoot}/build"]}}
+                }},
+                "docs": {"name": "docs", "type": "app", "data": {
+                    "root": "apps/docs",
+                    "targets": {"build": {"outputs": ["{workspaceRoot}/dist/docs"]}}
+                }},
+                "ui": {"name": "ui", "type": "lib", "data": {
+                    "root": "libs/ui",
+                    "targets": {"build": {"options": {"outputPath": "dist/libs/ui"}}}
+                }}
+            }},
+            "affectedProjects": ["web", "admin", "ui"]
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        apps,
+        vec![
+            BuildApp {
+                name: "admin".into(),
+                output_dir: "apps/admin/build".into(),
+            },
+            BuildApp {
+                name: "web".into(),
+                output_dir: "dist/apps/web".into(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_parse_turbo_tasks() {
+    let apps = parse_build_manifest(
+        br#"{
+            "tasks": [
+                {"taskId": "@acme/web#build", "package": "@acme/web",
+                 "directory": "apps/web", "outputs": [".next/**", "!.next/cache/**"]},
+                {"taskId": "docs#build", "package": "docs",
+                 "directory": "apps/docs", "outputs": ["dist/**"],
+                 "execution": {"exitCode": 1}},
+                {"taskId": "//#lint", "package": "//", "directory": "",
+                 "outputs": ["lint/**"]}
+            ]
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        apps,
+        vec![BuildApp {
+            name: "@acme/web".into(),
+            output_dir: "apps/web/.next".into(),
+        }]
+    );
+    assert_eq!(apps[0].release_prefix(), "acme-web");
+}
@@ -0,0 +1,81 @@
+//! Cancellation of running commands on SIGINT and SIGTERM.
+//!
+//! The signal handler of `system::run_or_interrupt` marks the process as
+//! cancelled.  Requests in flight are aborted from their progress callback
+//! and no new requests are sent, so upload workers fail soon after.  Before
+//! the process exits, temporary files that were not dropped yet are removed
+//! and a hint on how to resume is printed, if the command registered one.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicI32, Ordering};
+
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+
+static SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+lazy_static! {
+    static ref TEMP_PATHS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+    static ref RESUME_HINT: Mutex<Option<String>> = Mutex::new(None);
+}
+
+/// Cancels the running command because of the given signal.
+pub fn cancel(signal: i32) {
+    SIGNAL.store(signal, Ordering::SeqCst);
+}
+
+/// Checks whether the running command was cancelled.
+pub fn is_cancelled() -> bool {
+    SIGNAL.load(Ordering::SeqCst) != 0
+}
+
+/// The exit code of a process ended by the signal it was cancelled with.
+pub fn exit_code() -> i32 {
+    128 + SIGNAL.load(Ordering::SeqCst)
+}
+
+/// Remembers a temporary file or directory to remove on cancellation.
+pub fn register_temp_path(path: &Path) {
+    TEMP_PATHS.lock().push(path.to_path_buf());
+}
+
+/// Forgets a temporary path again once it was removed.
+pub fn unregister_temp_path(path: &Path) {
+    TEMP_PATHS.lock().retain(|p| p != path);
+}
+
+/// Shows how to resume the command if it is cancelled while the returned
+/// guard is alive.
+pub fn resume_hint(hint: String) -> ResumeHint {
+    *RESUME_HINT.lock() = Some(hint);
+    ResumeHint
+}
+
+/// Clears the resume hint when dropped, see `resume_hint`.
+#[must_use]
+pub struct ResumeHint;
+
+impl Drop for ResumeHint {
+    fn drop(&mut self) {
+        // a cancelled command unwinds before the hint is printed
+        if !is_cancelled() {
+            RESUME_HINT.lock().take();
+        }
+    }
+}
+
+/// Removes all remaining temporary paths and prints the resume hint.
+///
+/// This is safe to call more than once, later calls do nothing.
+pub fn finish() {
+    for path in TEMP_PATHS.lock().drain(..) {
+        if path.is_dir() {
+            fs::remove_dir_all(&path).ok();
+        } else {
+            fs::remove_file(&path).ok();
+        }
+    }
+    if let Some(hint) = RESUME_HINT.lock().take() {
+        eprintln!("{}", hint);
+    }
+}
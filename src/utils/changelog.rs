@@ -0,0 +1,155 @@
+//! Renders Markdown changelogs from release commits.
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::api::ReleaseCommit;
+
+lazy_static! {
+    static ref CONVENTIONAL_RE: Regex = Regex::new(r"^(\w+)(?:\(([^)]*)\))?!?:\s*(.+)$").unwrap();
+}
+
+/// How the entries of a changelog are grouped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangelogGrouping {
+    /// Group by conventional commit type, e.g. `feat` or `fix`.
+    Type,
+    /// Group by commit author.
+    Author,
+}
+
+struct Entry<'a> {
+    ty: Option<String>,
+    scope: Option<String>,
+    summary: String,
+    commit: &'a ReleaseCommit,
+}
+
+fn parse_entry(commit: &ReleaseCommit) -> Entry<'_> {
+    let subject = commit
+        .message
+        .as_deref()
+        .and_then(|m| m.lines().next())
+        .unwrap_or("")
+        .trim();
+    match CONVENTIONAL_RE.captures(subject) {
+        Some(caps) => Entry {
+            ty: Some(caps[1].to_lowercase()),
+            scope: caps.get(2).map(|x| x.as_str().to_string()),
+            summary: caps[3].to_string(),
+            commit,
+        },
+        None => Entry {
+            ty: None,
+            scope: None,
+            summary: subject.to_string(),
+            commit,
+        },
+    }
+}
+
+/// Returns the section title and its sort order for a commit type.
+fn type_section(ty: Option<&str>) -> (u8, &'static str) {
+    match ty {
+        Some("feat") => (0, "Features"),
+        Some("fix") => (1, "Bug Fixes"),
+        Some("perf") => (2, "Performance"),
+        Some("docs") => (3, "Documentation"),
+        Some("refactor") | Some("ref") => (4, "Refactoring"),
+        _ => (5, "Other Changes"),
+    }
+}
+
+fn author_name(commit: &ReleaseCommit) -> &str {
+    commit
+        .author
+        .as_ref()
+        .and_then(|a| a.name.as_deref().or(a.email.as_deref()))
+        .unwrap_or("Unknown")
+}
+
+/// Renders a Markdown changelog for the given commits.
+pub fn render_changelog(
+    title: &str,
+    commits: &[ReleaseCommit],
+    grouping: ChangelogGrouping,
+) -> String {
+    let mut sections: BTreeMap<(u8, String), Vec<Entry<'_>>> = BTreeMap::new();
+    for commit in commits {
+        let entry = parse_entry(commit);
+        let key = match grouping {
+            ChangelogGrouping::Type => {
+                let (order, name) = type_section(entry.ty.as_deref());
+                (order, name.to_string())
+            }
+            ChangelogGrouping::Author => (0, author_name(commit).to_string()),
+        };
+        sections.entry(key).or_default().push(entry);
+    }
+
+    let mut rv = format!("## {}\n", title);
+    if sections.is_empty() {
+        rv.push_str("\nNo changes.\n");
+    }
+    for ((_, name), entries) in sections {
+        write!(rv, "\n### {}\n\n", name).unwrap();
+        for entry in entries {
+            rv.push_str("- ");
+            if let Some(ref scope) = entry.scope {
+                write!(rv, "**{}:** ", scope).unwrap();
+            }
+            rv.push_str(&entry.summary);
+            let short_id = entry.commit.id.get(..7).unwrap_or(&entry.commit.id);
+            match grouping {
+                ChangelogGrouping::Type => {
+                    writeln!(rv, " ({}, {})", short_id, author_name(entry.commit))
+                }
+                ChangelogGrouping::Author => writeln!(rv, " ({})", short_id),
+            }
+            .unwrap();
+        }
+    }
+    rv
+}
+
+#[test]
+fn test_render_changelog() {
+    use crate::api::CommitAuthor;
+
+    let commit = |id: &str, message: &str, author: &str| ReleaseCommit {
+        id: id.into(),
+        message: Some(message.into()),
+        date_created: chrono::Utc::now(),
+        author: Some(CommitAuthor {
+            name: Some(author.into()),
+            email: None,
+        }),
+    };
+    let commits = vec![
+        commit("aaaaaaa1", "fix(checkout): Round totals\n\nDetails", "Jane"),
+        commit("bbbbbbb2", "feat: Add dark mode", "John"),
+        commit("ccccccc3", "Bump dependencies", "Jane"),
+    ];
+
+    assert_eq!(
+        render_changelog("1.3.0", &commits, ChangelogGrouping::Type),
+        "## 1.3.0\n\
+         \n### Features\n\n\
+         - Add dark mode (bbbbbbb, John)\n\
+         \n### Bug Fixes\n\n\
+         - **checkout:** Round totals (aaaaaaa, Jane)\n\
+         \n### Other Changes\n\n\
+         - Bump dependencies (ccccccc, Jane)\n"
+    );
+    assert_eq!(
+        render_changelog("1.3.0", &commits, ChangelogGrouping::Author),
+        "## 1.3.0\n\
+         \n### Jane\n\n\
+         - **checkout:** Round totals (aaaaaaa)\n\
+         - Bump dependencies (ccccccc)\n\
+         \n### John\n\n\
+         - Add dark mode (bbbbbbb)\n"
+    );
+}
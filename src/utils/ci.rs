@@ -0,0 +1,336 @@
+//! Detects the CI provider the command runs on.
+use std::env;
+
+use serde::Serialize;
+
+/// Looks up a non-empty variable via `get`.
+fn non_empty<F>(get: &F, key: &str) -> Option<String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    get(key).filter(|value| !value.is_empty())
+}
+
+fn detect_build_url_with<F>(get: F) -> Option<String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    // GitHub Actions only exposes the pieces of the run URL
+    if let (Some(server), Some(repo), Some(run_id)) = (
+        non_empty(&get, "GITHUB_SERVER_URL"),
+        non_empty(&get, "GITHUB_REPOSITORY"),
+        non_empty(&get, "GITHUB_RUN_ID"),
+    ) {
+        return Some(format!(
+            "{}/{}/actions/runs/{}",
+            server.trim_end_matches('/'),
+            repo,
+            run_id
+        ));
+    }
+
+    // classic release pipelines of Azure DevOps link to the release, which
+    // also shows the stage that deployed
+    if let Some(url) = non_empty(&get, "RELEASE_RELEASEWEBURL") {
+        return Some(url);
+    }
+
+    // Azure Pipelines
+    if let (Some(collection), Some(project), Some(build_id)) = (
+        non_empty(&get, "SYSTEM_COLLECTIONURI"),
+        non_empty(&get, "SYSTEM_TEAMPROJECT"),
+        non_empty(&get, "BUILD_BUILDID"),
+    ) {
+        return Some(format!(
+            "{}/{}/_build/results?buildId={}",
+            collection.trim_end_matches('/'),
+            project.replace(' ', "%20"),
+            build_id
+        ));
+    }
+
+    // Bitbucket Pipelines
+    if let (Some(origin), Some(build)) = (
+        non_empty(&get, "BITBUCKET_GIT_HTTP_ORIGIN"),
+        non_empty(&get, "BITBUCKET_BUILD_NUMBER"),
+    ) {
+        return Some(format!(
+            "{}/addon/pipelines/home#!/results/{}",
+            origin.trim_end_matches('/'),
+            build
+        ));
+    }
+
+    // providers that expose the build URL directly: GitLab CI, CircleCI,
+    // Buildkite, Travis CI, AWS CodeBuild and Jenkins
+    [
+        "CI_JOB_URL",
+        "CIRCLE_BUILD_URL",
+        "BUILDKITE_BUILD_URL",
+        "TRAVIS_BUILD_WEB_URL",
+        "CODEBUILD_BUILD_URL",
+        "BUILD_URL",
+    ]
+    .iter()
+    .find_map(|key| non_empty(&get, key))
+}
+
+/// Returns the URL of the current CI build, if it can be detected.
+pub fn detect_build_url() -> Option<String> {
+    detect_build_url_with(|key| env::var(key).ok())
+}
+
+/// Metadata about the CI build, normalized across providers.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct CiInfo {
+    pub provider: Option<&'static str>,
+    pub commit_sha: Option<String>,
+    pub branch: Option<String>,
+    pub pr_number: Option<String>,
+    pub build_url: Option<String>,
+    pub repository: Option<String>,
+}
+
+impl CiInfo {
+    /// Returns the metadata as `SENTRY_CI_*` variable assignments.
+    pub fn to_env(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("SENTRY_CI_PROVIDER", self.provider),
+            ("SENTRY_CI_COMMIT_SHA", self.commit_sha.as_deref()),
+            ("SENTRY_CI_BRANCH", self.branch.as_deref()),
+            ("SENTRY_CI_PR_NUMBER", self.pr_number.as_deref()),
+            ("SENTRY_CI_BUILD_URL", self.build_url.as_deref()),
+            ("SENTRY_CI_REPOSITORY", self.repository.as_deref()),
+        ]
+        .iter()
+        .filter_map(|&(key, value)| Some((key, value?)))
+        .collect()
+    }
+}
+
+/// Strips a `refs/heads/` or `origin/` prefix from a branch name.
+fn strip_branch(branch: String) -> String {
+    for prefix in &["refs/heads/", "origin/"] {
+        if let Some(rest) = branch.strip_prefix(prefix) {
+            return rest.to_string();
+        }
+    }
+    branch
+}
+
+fn detect_ci_with<F>(get: F) -> CiInfo
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let var = |key: &str| non_empty(&get, key);
+    let first = |keys: &[&str]| keys.iter().find_map(|key| var(key));
+    // some providers set the PR variable to `false` outside of PRs
+    let pr = |key: &str| var(key).filter(|value| value != "false");
+
+    let mut info = if var("GITHUB_ACTIONS").is_some() {
+        CiInfo {
+            provider: Some("github"),
+            commit_sha: var("GITHUB_SHA"),
+            branch: first(&["GITHUB_HEAD_REF", "GITHUB_REF_NAME"]),
+            pr_number: var("GITHUB_REF")
+                .and_then(|r| Some(r.strip_prefix("refs/pull/")?.split('/').next()?.to_string())),
+            repository: var("GITHUB_REPOSITORY"),
+            ..Default::default()
+        }
+    } else if var("GITLAB_CI").is_some() {
+        CiInfo {
+            provider: Some("gitlab"),
+            commit_sha: var("CI_COMMIT_SHA"),
+            branch: first(&["CI_MERGE_REQUEST_SOURCE_BRANCH_NAME", "CI_COMMIT_BRANCH"]),
+            pr_number: var("CI_MERGE_REQUEST_IID"),
+            repository: var("CI_PROJECT_PATH"),
+            ..Default::default()
+        }
+    } else if var("CIRCLECI").is_some() {
+        CiInfo {
+            provider: Some("circleci"),
+            commit_sha: var("CIRCLE_SHA1"),
+            branch: var("CIRCLE_BRANCH"),
+            pr_number: var("CIRCLE_PR_NUMBER").or_else(|| {
+                var("CIRCLE_PULL_REQUEST").and_then(|url| Some(url.rsplit('/').next()?.into()))
+            }),
+            repository: match (
+                var("CIRCLE_PROJECT_USERNAME"),
+                var("CIRCLE_PROJECT_REPONAME"),
+            ) {
+                (Some(user), Some(repo)) => Some(format!("{}/{}", user, repo)),
+                _ => None,
+            },
+            ..Default::default()
+        }
+    } else if var("TF_BUILD").is_some() {
+        CiInfo {
+            provider: Some("azure-pipelines"),
+            commit_sha: var("BUILD_SOURCEVERSION"),
+            branch: first(&["SYSTEM_PULLREQUEST_SOURCEBRANCH", "BUILD_SOURCEBRANCH"]),
+            pr_number: first(&[
+                "SYSTEM_PULLREQUEST_PULLREQUESTNUMBER",
+                "SYSTEM_PULLREQUEST_PULLREQUESTID",
+            ]),
+            repository: var("BUILD_REPOSITORY_NAME"),
+            ..Default::default()
+        }
+    } else if var("BITBUCKET_BUILD_NUMBER").is_some() {
+        CiInfo {
+            provider: Some("bitbucket"),
+            commit_sha: var("BITBUCKET_COMMIT"),
+            branch: var("BITBUCKET_BRANCH"),
+            pr_number: var("BITBUCKET_PR_ID"),
+            repository: var("BITBUCKET_REPO_FULL_NAME"),
+            ..Default::default()
+        }
+    } else if var("BUILDKITE").is_some() {
+        CiInfo {
+            provider: Some("buildkite"),
+            commit_sha: var("BUILDKITE_COMMIT"),
+            branch: var("BUILDKITE_BRANCH"),
+            pr_number: pr("BUILDKITE_PULL_REQUEST"),
+            ..Default::default()
+        }
+    } else if var("TRAVIS").is_some() {
+        CiInfo {
+            provider: Some("travis"),
+            commit_sha: var("TRAVIS_COMMIT"),
+            branch: first(&["TRAVIS_PULL_REQUEST_BRANCH", "TRAVIS_BRANCH"]),
+            pr_number: pr("TRAVIS_PULL_REQUEST"),
+            repository: var("TRAVIS_REPO_SLUG"),
+            ..Default::default()
+        }
+    } else if var("CODEBUILD_BUILD_ID").is_some() {
+        CiInfo {
+            provider: Some("codebuild"),
+            commit_sha: var("CODEBUILD_RESOLVED_SOURCE_VERSION"),
+            branch: var("CODEBUILD_WEBHOOK_HEAD_REF"),
+            pr_number: var("CODEBUILD_SOURCE_VERSION")
+                .and_then(|v| Some(v.strip_prefix("pr/")?.to_string())),
+            ..Default::default()
+        }
+    } else if var("JENKINS_URL").is_some() {
+        CiInfo {
+            provider: Some("jenkins"),
+            commit_sha: var("GIT_COMMIT"),
+            branch: first(&["CHANGE_BRANCH", "BRANCH_NAME", "GIT_BRANCH"]),
+            pr_number: var("CHANGE_ID"),
+            ..Default::default()
+        }
+    } else if var("TEAMCITY_VERSION").is_some() {
+        CiInfo {
+            provider: Some("teamcity"),
+            commit_sha: var("BUILD_VCS_NUMBER"),
+            ..Default::default()
+        }
+    } else {
+        CiInfo::default()
+    };
+
+    info.branch = info.branch.map(strip_branch);
+    info.build_url = detect_build_url_with(get);
+    info
+}
+
+/// Detects the CI provider and the metadata of the current build.
+pub fn detect_ci() -> CiInfo {
+    detect_ci_with(|key| env::var(key).ok())
+}
+
+#[test]
+fn test_detect_build_url() {
+    use std::collections::HashMap;
+
+    fn detect(vars: &[(&str, &str)]) -> Option<String> {
+        let vars: HashMap<_, _> = vars.iter().cloned().collect();
+        detect_build_url_with(|key| vars.get(key).map(|value| value.to_string()))
+    }
+
+    assert_eq!(detect(&[]), None);
+    assert_eq!(
+        detect(&[
+            ("GITHUB_SERVER_URL", "https://github.com"),
+            ("GITHUB_REPOSITORY", "getsentry/sentry-cli"),
+            ("GITHUB_RUN_ID", "42"),
+        ]),
+        Some("https://github.com/getsentry/sentry-cli/actions/runs/42".into())
+    );
+    assert_eq!(
+        detect(&[
+            ("CI_JOB_URL", "https://gitlab.com/wat/-/jobs/1"),
+            ("BUILD_URL", ""),
+        ]),
+        Some("https://gitlab.com/wat/-/jobs/1".into())
+    );
+    assert_eq!(
+        detect(&[
+            ("SYSTEM_COLLECTIONURI", "https://dev.azure.com/wat/"),
+            ("SYSTEM_TEAMPROJECT", "app"),
+            ("BUILD_BUILDID", "7"),
+        ]),
+        Some("https://dev.azure.com/wat/app/_build/results?buildId=7".into())
+    );
+    assert_eq!(
+        detect(&[
+            ("SYSTEM_COLLECTIONURI", "https://dev.azure.com/wat/"),
+            ("SYSTEM_TEAMPROJECT", "my app"),
+            ("BUILD_BUILDID", "7"),
+        ]),
+        Some("https://dev.azure.com/wat/my%20app/_build/results?buildId=7".into())
+    );
+    assert_eq!(
+        detect(&[
+            ("SYSTEM_COLLECTIONURI", "https://dev.azure.com/wat/"),
+            ("SYSTEM_TEAMPROJECT", "app"),
+            ("BUILD_BUILDID", "7"),
+            (
+                "RELEASE_RELEASEWEBURL",
+                "https://dev.azure.com/wat/app/_release?releaseId=3"
+            ),
+        ]),
+        Some("https://dev.azure.com/wat/app/_release?releaseId=3".into())
+    );
+}
+
+#[test]
+fn test_detect_ci() {
+    use std::collections::HashMap;
+
+    fn detect(vars: &[(&str, &str)]) -> CiInfo {
+        let vars: HashMap<_, _> = vars.iter().cloned().collect();
+        detect_ci_with(|key| vars.get(key).map(|value| value.to_string()))
+    }
+
+    assert_eq!(detect(&[]), CiInfo::default());
+    assert_eq!(
+        detect(&[
+            ("GITHUB_ACTIONS", "true"),
+            ("GITHUB_SHA", "abc"),
+            ("GITHUB_REF", "refs/pull/12/merge"),
+            ("GITHUB_HEAD_REF", "feature"),
+            ("GITHUB_REPOSITORY", "getsentry/sentry-cli"),
+        ]),
+        CiInfo {
+            provider: Some("github"),
+            commit_sha: Some("abc".into()),
+            branch: Some("feature".into()),
+            pr_number: Some("12".into()),
+            build_url: None,
+            repository: Some("getsentry/sentry-cli".into()),
+        }
+    );
+    let info = detect(&[
+        ("JENKINS_URL", "https://ci.example.com/"),
+        ("GIT_BRANCH", "origin/main"),
+        ("BUILD_URL", "https://ci.example.com/job/app/3/"),
+    ]);
+    assert_eq!(info.provider, Some("jenkins"));
+    assert_eq!(info.branch.as_deref(), Some("main"));
+    assert_eq!(
+        info.build_url.as_deref(),
+        Some("https://ci.example.com/job/app/3/")
+    );
+    let info = detect(&[("TRAVIS", "true"), ("TRAVIS_PULL_REQUEST", "false")]);
+    assert_eq!(info.pr_number, None);
+}
@@ -0,0 +1,116 @@
+//! Translates `CODEOWNERS` files into Sentry ownership rules.
+use std::collections::HashMap;
+
+/// Candidate locations of a `CODEOWNERS` file relative to the repository.
+pub const CODEOWNERS_PATHS: &[&str] = &[".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/// Ownership rules translated from a `CODEOWNERS` file.
+#[derive(Debug, Default)]
+pub struct OwnershipRules {
+    pub rules: Vec<String>,
+    /// Owners that have no equivalent in Sentry, e.g. GitHub users without
+    /// a mapping to an email address.
+    pub unmapped: Vec<String>,
+}
+
+impl OwnershipRules {
+    /// Returns the rules in the raw format of Sentry's ownership settings.
+    pub fn to_raw(&self) -> String {
+        let mut raw = self.rules.join("\n");
+        raw.push('\n');
+        raw
+    }
+}
+
+fn translate_pattern(pattern: &str) -> String {
+    let pattern = pattern.trim_start_matches('/');
+    if pattern.is_empty() {
+        "*".into()
+    } else if pattern.ends_with('/') {
+        format!("{}*", pattern)
+    } else {
+        pattern.into()
+    }
+}
+
+fn translate_owner(owner: &str, mappings: &HashMap<String, String>) -> Option<String> {
+    if let Some(mapped) = mappings.get(owner) {
+        Some(mapped.clone())
+    } else if let Some(name) = owner.strip_prefix('@') {
+        // `@org/team` references a team, plain `@user` handles cannot be
+        // resolved to Sentry users.
+        name.split_once('/').map(|(_, team)| format!("#{}", team))
+    } else if owner.contains('@') {
+        Some(owner.into())
+    } else {
+        None
+    }
+}
+
+/// Translates the contents of a `CODEOWNERS` file into ownership rules.
+///
+/// Owners are looked up in `mappings` first.  Otherwise GitHub teams map to
+/// Sentry teams of the same slug and email addresses are kept as they are.
+pub fn parse_codeowners(contents: &str, mappings: &HashMap<String, String>) -> OwnershipRules {
+    let mut rv = OwnershipRules::default();
+
+    for line in contents.lines() {
+        let line = line.trim();
+        // skip comments and GitLab sections
+        if line.is_empty()
+            || line.starts_with('#')
+            || line.starts_with('[')
+            || line.starts_with("^[")
+        {
+            continue;
+        }
+
+        let mut parts = line.split_whitespace();
+        let pattern = translate_pattern(parts.next().unwrap());
+        let mut owners = vec![];
+        for owner in parts.take_while(|part| !part.starts_with('#')) {
+            match translate_owner(owner, mappings) {
+                Some(owner) => owners.push(owner),
+                None => {
+                    if !rv.unmapped.iter().any(|x| x == owner) {
+                        rv.unmapped.push(owner.into());
+                    }
+                }
+            }
+        }
+
+        if !owners.is_empty() {
+            rv.rules
+                .push(format!("path:{} {}", pattern, owners.join(" ")));
+        }
+    }
+
+    rv
+}
+
+#[test]
+fn test_parse_codeowners() {
+    let mut mappings = HashMap::new();
+    mappings.insert("@octocat".to_string(), "octocat@example.com".to_string());
+
+    let rules = parse_codeowners(
+        "# global owners\n\
+         *       @wat-org/core\n\
+         \n\
+         /docs/  docs@example.com @wat-org/writers\n\
+         *.js    @octocat @someone # frontend\n\
+         [Backend]\n\
+         /src/api.rs @someone\n",
+        &mappings,
+    );
+
+    assert_eq!(
+        rules.rules,
+        vec![
+            "path:* #core",
+            "path:docs/* docs@example.com #writers",
+            "path:*.js octocat@example.com",
+        ]
+    );
+    assert_eq!(rules.unmapped, vec!["@someone"]);
+}
@@ -3,6 +3,8 @@ use std::path::Path;
 use std::str;
 
 use failure::{bail, Error, SyncFailure};
+use goblin::mach::load_command::{CommandVariant, LC_BUILD_VERSION};
+use goblin::mach::MachO;
 use proguard::ProguardMapping;
 use serde::ser::{SerializeStruct, Serializer};
 use serde::Serialize;
@@ -228,6 +230,114 @@ pub struct DifVariant {
     pub debug_id: DebugId,
     pub code_id: Option<CodeId>,
     pub arch: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub platform: Option<&'static str>,
+    #[serde(serialize_with = "serialize_display")]
+    pub features: ObjectDifFeatures,
+    pub problem: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+}
+
+impl DifVariant {
+    fn describe(&self) -> String {
+        let arch = self.arch.as_deref().unwrap_or("unknown");
+        match self.platform {
+            Some(platform) => format!("{} ({})", arch, platform),
+            None => arch.to_string(),
+        }
+    }
+}
+
+/// Warns about slices that share their debug identifier with another slice.
+///
+/// Sentry cannot tell such slices apart, so only one of them will be used
+/// for symbolication.
+fn warn_duplicate_ids(variants: &mut [DifVariant]) {
+    for index in 0..variants.len() {
+        let debug_id = variants[index].debug_id;
+        if debug_id.is_nil() {
+            continue;
+        }
+        let others: Vec<_> = variants
+            .iter()
+            .enumerate()
+            .filter(|&(other, variant)| other != index && variant.debug_id == debug_id)
+            .map(|(_, variant)| variant.describe())
+            .collect();
+        if !others.is_empty() {
+            variants[index].warning = Some(format!(
+                "debug identifier is also used by {}",
+                others.join(", ")
+            ));
+        }
+    }
+}
+
+fn serialize_display<T: fmt::Display, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+/// Returns the features of a single object.
+fn object_features(object: &Object<'_>) -> ObjectDifFeatures {
+    ObjectDifFeatures {
+        symtab: object.has_symbols(),
+        debug: object.has_debug_info(),
+        unwind: object.has_unwind_info(),
+        sources: object.has_sources(),
+    }
+}
+
+/// Determines the platform a Mach-O slice was built for.
+///
+/// This reads `LC_BUILD_VERSION` or the legacy `LC_VERSION_MIN_*` load
+/// commands, which allows to tell apart slices of the same architecture, for
+/// instance iOS and Mac Catalyst builds.
+pub fn macho_platform(data: &[u8]) -> Option<&'static str> {
+    let macho = MachO::parse(data, 0).ok()?;
+
+    for load_command in &macho.load_commands {
+        let platform = match load_command.command {
+            CommandVariant::VersionMinMacosx(_) => "macos",
+            CommandVariant::VersionMinIphoneos(_) => "ios",
+            CommandVariant::VersionMinTvos(_) => "tvos",
+            CommandVariant::VersionMinWatchos(_) => "watchos",
+            // goblin does not parse LC_BUILD_VERSION yet, the platform is the
+            // first field after the load command header.
+            CommandVariant::Unimplemented(header) if header.cmd == LC_BUILD_VERSION => {
+                let offset = load_command.offset + 8;
+                let bytes = data.get(offset..offset + 4)?;
+                let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+                let platform = if macho.little_endian {
+                    u32::from_le_bytes(bytes)
+                } else {
+                    u32::from_be_bytes(bytes)
+                };
+                match platform {
+                    1 => "macos",
+                    2 => "ios",
+                    3 => "tvos",
+                    4 => "watchos",
+                    5 => "bridgeos",
+                    6 => "maccatalyst",
+                    7 => "iossimulator",
+                    8 => "tvossimulator",
+                    9 => "watchossimulator",
+                    10 => "driverkit",
+                    11 => "visionos",
+                    12 => "visionossimulator",
+                    _ => "unknown",
+                }
+            }
+            _ => continue,
+        };
+        return Some(platform);
+    }
+
+    None
 }
 
 impl<'a> DifFile<'a> {
@@ -266,23 +376,50 @@ impl<'a> DifFile<'a> {
     }
 
     pub fn variants(&self) -> Vec<DifVariant> {
-        match self {
+        let mut variants = match self {
             DifFile::Archive(archive) => archive
                 .get()
                 .objects()
                 .filter_map(Result::ok)
-                .map(|object| DifVariant {
-                    debug_id: object.debug_id(),
-                    arch: Some(object.arch().name().to_string()),
-                    code_id: object.code_id(),
+                .map(|object| {
+                    let features = object_features(&object);
+                    let problem = if object.debug_id().is_nil() {
+                        Some("missing debug identifier, likely stripped")
+                    } else if !features.has_some() {
+                        Some("missing debug or unwind information")
+                    } else {
+                        None
+                    };
+                    DifVariant {
+                        debug_id: object.debug_id(),
+                        arch: Some(object.arch().name().to_string()),
+                        code_id: object.code_id(),
+                        platform: match object {
+                            Object::MachO(ref macho) => macho_platform(macho.data()),
+                            _ => None,
+                        },
+                        features,
+                        problem,
+                        warning: None,
+                    }
                 })
                 .collect(),
             DifFile::Proguard(pg) => vec![DifVariant {
                 debug_id: pg.get().uuid().into(),
                 arch: None,
                 code_id: None,
+                platform: None,
+                features: ObjectDifFeatures::none(),
+                problem: if pg.get().has_line_info() {
+                    None
+                } else {
+                    Some("missing line information")
+                },
+                warning: None,
             }],
-        }
+        };
+        warn_duplicate_ids(&mut variants);
+        variants
     }
 
     pub fn ids(&self) -> Vec<DebugId> {
@@ -302,10 +439,11 @@ impl<'a> DifFile<'a> {
             DifFile::Archive(archive) => {
                 let mut features = ObjectDifFeatures::none();
                 for object in archive.get().objects().filter_map(Result::ok) {
-                    features.symtab = features.symtab || object.has_symbols();
-                    features.debug = features.debug || object.has_debug_info();
-                    features.unwind = features.unwind || object.has_unwind_info();
-                    features.sources = features.sources || object.has_sources();
+                    let object_features = object_features(&object);
+                    features.symtab = features.symtab || object_features.symtab;
+                    features.debug = features.debug || object_features.debug;
+                    features.unwind = features.unwind || object_features.unwind;
+                    features.sources = features.sources || object_features.sources;
                 }
                 features
             }
@@ -400,3 +538,89 @@ impl DebuggingInformation for Archive<'_> {
         Ok(false)
     }
 }
+
+#[test]
+fn test_macho_platform() {
+    fn macho(cmds: &[(u32, &[u32])]) -> Vec<u8> {
+        let mut data = vec![];
+        let size: usize = cmds.iter().map(|(_, payload)| 8 + 4 * payload.len()).sum();
+        let header = [
+            0xfeed_facf,
+            0x0100_000c,
+            0,
+            0xa,
+            cmds.len() as u32,
+            size as u32,
+            0,
+            0,
+        ];
+        for value in &header {
+            data.extend(&u32::to_le_bytes(*value));
+        }
+        for (cmd, payload) in cmds {
+            data.extend(&u32::to_le_bytes(*cmd));
+            data.extend(&u32::to_le_bytes(8 + 4 * payload.len() as u32));
+            for value in payload.iter() {
+                data.extend(&u32::to_le_bytes(*value));
+            }
+        }
+        data
+    }
+
+    let uuid: &[u32] = &[1, 2, 3, 4];
+    assert_eq!(
+        macho_platform(&macho(&[(0x1b, uuid), (0x32, &[6, 0, 0, 0])])),
+        Some("maccatalyst")
+    );
+    assert_eq!(macho_platform(&macho(&[(0x25, &[0, 0])])), Some("ios"));
+    assert_eq!(macho_platform(&macho(&[(0x1b, uuid)])), None);
+    assert_eq!(macho_platform(b"\x7fELF"), None);
+}
+
+#[test]
+fn test_warn_duplicate_ids() {
+    fn variant(debug_id: &str, arch: &str, platform: &'static str) -> DifVariant {
+        DifVariant {
+            debug_id: debug_id.parse().unwrap(),
+            code_id: None,
+            arch: Some(arch.to_string()),
+            platform: Some(platform),
+            features: ObjectDifFeatures::none(),
+            problem: None,
+            warning: None,
+        }
+    }
+
+    let mut variants = vec![
+        variant("dfb8e43a-f242-3d73-a453-aeb6a777ef75", "arm64", "ios"),
+        variant(
+            "dfb8e43a-f242-3d73-a453-aeb6a777ef75",
+            "arm64",
+            "maccatalyst",
+        ),
+        variant(
+            "18ba1b41-4b8d-3ad4-9a0a-e2bb3c7b0e15",
+            "x86_64",
+            "maccatalyst",
+        ),
+        variant("00000000-0000-0000-0000-000000000000", "arm64", "visionos"),
+        variant(
+            "00000000-0000-0000-0000-000000000000",
+            "arm64",
+            "visionossimulator",
+        ),
+    ];
+    warn_duplicate_ids(&mut variants);
+
+    assert_eq!(
+        variants[0].warning.as_deref(),
+        Some("debug identifier is also used by arm64 (maccatalyst)")
+    );
+    assert_eq!(
+        variants[1].warning.as_deref(),
+        Some("debug identifier is also used by arm64 (ios)")
+    );
+    assert_eq!(variants[2].warning, None);
+    assert_eq!(variants[3].warning, None);
+    assert_eq!(variants[4].warning, None);
+}
@@ -19,13 +19,14 @@ use std::time::{Duration, Instant};
 
 use console::style;
 use failure::{bail, err_msg, Error, SyncFailure};
+use goblin::archive::{self, MAGIC as ARCHIVE_MAGIC};
 use indicatif::HumanBytes;
 use log::{debug, info, warn};
 use sha1::Digest;
-use symbolic::common::{AsSelf, ByteView, DebugId, SelfCell, Uuid};
+use symbolic::common::{Arch, AsSelf, ByteView, DebugId, SelfCell, Uuid};
 use symbolic::debuginfo::macho::{BcSymbolMap, UuidMapping};
 use symbolic::debuginfo::sourcebundle::SourceBundleWriter;
-use symbolic::debuginfo::{Archive, FileEntry, FileFormat, Object};
+use symbolic::debuginfo::{Archive, FileEntry, FileFormat, Object, ObjectKind};
 use walkdir::WalkDir;
 use which::which;
 use zip::{write::FileOptions, ZipArchive, ZipWriter};
@@ -35,12 +36,15 @@ use crate::api::{
 };
 use crate::config::Config;
 use crate::constants::{DEFAULT_MAX_DIF_SIZE, DEFAULT_MAX_WAIT};
+use crate::utils::annotations::{annotate, step, AnnotationLevel};
 use crate::utils::chunks::{
     upload_chunks, BatchedSliceExt, Chunk, ItemSize, ASSEMBLE_POLL_INTERVAL,
 };
 use crate::utils::dif::ObjectDifFeatures;
-use crate::utils::fs::{get_sha1_checksum, get_sha1_checksums, TempDir, TempFile};
+use crate::utils::file_search::IgnoreMatcher;
+use crate::utils::fs::{get_sha1_checksum, get_sha1_checksums, normalize_path, TempDir, TempFile};
 use crate::utils::progress::{ProgressBar, ProgressStyle};
+use crate::utils::size_report::SizeReport;
 use crate::utils::ui::{copy_with_progress, make_byte_progress_bar};
 
 /// A debug info file on the server.
@@ -375,6 +379,8 @@ enum DifSource<'a> {
     FileSystem(&'a Path),
     /// An entry in a ZIP file
     Zip(&'a mut ZipFileArchive, &'a str),
+    /// A member of a static library located in the file system
+    Archive(&'a Path, &'a str),
 }
 
 impl<'a> DifSource<'a> {
@@ -440,6 +446,16 @@ impl<'a> DifSource<'a> {
         match *self {
             DifSource::FileSystem(base) => Self::get_relative_fs(base, path.as_ref()),
             DifSource::Zip(ref mut zip, name) => Self::get_relative_zip(*zip, name, path.as_ref()),
+            DifSource::Archive(base, member) => {
+                // Members do not have directories of their own, so resolve
+                // files next to the library.
+                debug!(
+                    "resolving file relative to {} of {}",
+                    member,
+                    base.display()
+                );
+                Self::get_relative_fs(base, path.as_ref())
+            }
         }
     }
 }
@@ -448,12 +464,63 @@ impl<'a> DifSource<'a> {
 /// DIFs and their missing chunks.
 type MissingDifsInfo<'data, 'm> = (Vec<&'m ChunkedDifMatch<'data>>, Vec<Chunk<'m>>);
 
+/// The folder in which the Android Gradle plugin stores native debug symbols
+/// inside an Android App Bundle.
+const AAB_DEBUG_SYMBOLS: &str = "BUNDLE-METADATA/com.android.tools.build.debugsymbols/";
+
+/// The ABIs of native libraries in Android packages.
+const ANDROID_ABIS: &[&str] = &[
+    "armeabi",
+    "armeabi-v7a",
+    "arm64-v8a",
+    "x86",
+    "x86_64",
+    "mips",
+    "mips64",
+    "riscv64",
+];
+
+/// The kinds of Android packages whose native libraries are searched.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum AndroidPackage {
+    Apk,
+    AppBundle,
+}
+
+/// Returns the kind of Android package the given path points to.
+fn get_android_package(path: &Path) -> Option<AndroidPackage> {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("apk") => Some(AndroidPackage::Apk),
+        Some("aab") => Some(AndroidPackage::AppBundle),
+        _ => None,
+    }
+}
+
+/// Checks whether an entry of an APK or Android App Bundle contains native
+/// code or the debug symbols stripped from it.  Native libraries are only
+/// found in `lib/<abi>/` at the root of APKs, e.g. `lib/arm64-v8a/libapp.so`,
+/// or of the modules of app bundles, e.g. `base/lib/arm64-v8a/libapp.so`.
+fn is_android_native_entry(package: AndroidPackage, name: &str) -> bool {
+    if package == AndroidPackage::AppBundle && name.starts_with(AAB_DEBUG_SYMBOLS) {
+        return true;
+    }
+    let parts: Vec<_> = name.split('/').collect();
+    match (package, parts.as_slice()) {
+        (AndroidPackage::Apk, ["lib", abi, _])
+        | (AndroidPackage::AppBundle, [_, "lib", abi, _]) => ANDROID_ABIS.contains(abi),
+        _ => false,
+    }
+}
+
 /// Verifies that the given path contains a ZIP file and opens it.
+///
+/// APKs and Android App Bundles are ZIP files as well and are opened too.
 fn try_open_zip<P>(path: P) -> Result<Option<ZipFileArchive>, Error>
 where
     P: AsRef<Path>,
 {
-    if path.as_ref().extension() != Some("zip".as_ref()) {
+    let path = path.as_ref();
+    if path.extension() != Some("zip".as_ref()) && get_android_package(path).is_none() {
         return Ok(None);
     }
 
@@ -471,13 +538,46 @@ where
     })
 }
 
+/// Returns the names and contents of the members of a static library.
+///
+/// Symbol tables are skipped.  Members that exceed the buffer are skipped.
+fn parse_ar_members(data: &[u8]) -> Result<Vec<(String, &[u8])>, Error> {
+    let library = archive::Archive::parse(data)?;
+    let mut members = Vec::with_capacity(library.len());
+
+    for index in 0..library.len() {
+        let member = match library.get_at(index) {
+            Some(member) => member,
+            None => continue,
+        };
+        let name = member.extended_name();
+        if name.starts_with("__.SYMDEF") {
+            continue;
+        }
+        let offset = member.offset as usize;
+        if let Some(body) = data.get(offset..offset.saturating_add(member.size())) {
+            members.push((name.to_string(), body));
+        }
+    }
+
+    Ok(members)
+}
+
 /// Searches the given ZIP for potential DIFs and passes them to the callback.
 ///
 /// To avoid unnecessary file operations, the file extension is already checked
 /// for every entry before opening it.
 ///
-/// This function will not recurse into ZIPs contained in this ZIP.
-fn walk_difs_zip<F>(mut zip: ZipFileArchive, options: &DifUpload, mut func: F) -> Result<(), Error>
+/// This function will not recurse into ZIPs contained in this ZIP.  For
+/// APKs and Android App Bundles, only native libraries and their debug
+/// symbols are considered.  ART profiles (`*.prof`) are not uploaded, they
+/// are not used for symbolication.
+fn walk_difs_zip<F>(
+    mut zip: ZipFileArchive,
+    android_package: Option<AndroidPackage>,
+    options: &DifUpload,
+    mut func: F,
+) -> Result<(), Error>
 where
     F: FnMut(DifSource<'_>, String, ByteView<'static>) -> Result<(), Error>,
 {
@@ -486,6 +586,11 @@ where
             let zip_file = zip.by_index(index)?;
             let name = zip_file.name().to_string();
 
+            if let Some(package) = android_package {
+                if !is_android_native_entry(package, &name) {
+                    continue;
+                }
+            }
             if !options.valid_extension(Path::new(&name).extension()) {
                 continue;
             }
@@ -513,13 +618,15 @@ where
     P: AsRef<Path>,
     F: FnMut(DifSource<'_>, String, ByteView<'static>) -> Result<(), Error>,
 {
-    let location = location.as_ref();
+    let location = &normalize_path(location);
     let directory = if location.is_dir() {
         location
     } else {
         location.parent().unwrap_or_else(|| Path::new(""))
     };
 
+    let ignores = IgnoreMatcher::new(location, &options.ignores, options.ignore_file.as_deref())?;
+
     debug!("searching location {}", location.display());
     for entry in WalkDir::new(location)
         .follow_links(true)
@@ -532,10 +639,14 @@ where
         }
 
         let path = entry.path();
+        if ignores.is_ignored(path, false) {
+            debug!("skipping ignored file {}", path.display());
+            continue;
+        }
         match try_open_zip(path) {
             Ok(Some(zip)) => {
                 debug!("searching zip archive {}", path.display());
-                walk_difs_zip(zip, options, &mut func)?;
+                walk_difs_zip(zip, get_android_package(path), options, &mut func)?;
                 debug!("finished zip archive {}", path.display());
                 continue;
             }
@@ -560,6 +671,31 @@ where
             .to_string_lossy()
             .into_owned();
 
+        // Static libraries and static frameworks, e.g. inside XCFrameworks,
+        // keep their debug information in the contained object files.
+        if buffer.starts_with(&ARCHIVE_MAGIC[..]) {
+            debug!("searching static library {}", path.display());
+            let members = match parse_ar_members(&buffer) {
+                Ok(members) => members,
+                Err(e) => {
+                    debug!("skipping static library {}", path.display());
+                    debug!("error: {}", e);
+                    continue;
+                }
+            };
+            for (member, data) in members {
+                if !options.valid_extension(Path::new(&member).extension()) {
+                    continue;
+                }
+                func(
+                    DifSource::Archive(path, &member),
+                    format!("{}({})", name, member),
+                    ByteView::from_vec(data.to_vec()),
+                )?;
+            }
+            continue;
+        }
+
         func(DifSource::FileSystem(path), name, buffer)?;
     }
 
@@ -923,6 +1059,25 @@ fn resolve_hidden_symbols<'a>(dif: DifMatch<'a>, symbol_map: &Path) -> Result<Di
     DifMatch::take_temp(temp_path, dif.path())
 }
 
+/// Summarizes the sizes of all files by architecture.
+///
+/// Source bundles and auxiliary files are listed separately, so that their
+/// growth can be told apart from the growth of the debug information.
+fn report_sizes(difs: &[DifMatch<'_>]) -> SizeReport {
+    let mut report = SizeReport::new("Debug Symbols");
+    for dif in difs {
+        match dif.object() {
+            Some(object) if object.kind() == ObjectKind::Sources => {
+                report.add(&format!("{} (sources)", object.arch().name()), dif.size())
+            }
+            Some(object) => report.add(object.arch().name(), dif.size()),
+            None if dif.format() == DifFormat::BcSymbolMap => report.add("bcsymbolmap", dif.size()),
+            None => report.add("plist", dif.size()),
+        }
+    }
+    report
+}
+
 /// Runs all `DifMatch` objects through the provided callback and displays a
 /// progress bar while doing so.
 ///
@@ -1387,6 +1542,15 @@ fn poll_dif_assemble(
 
         println!("  {:>7} {}", style("ERROR").red(), dif.file_name());
         render_detail(&error.detail, fallback);
+        annotate(
+            AnnotationLevel::Error,
+            &format!(
+                "Could not process {}: {}",
+                dif.file_name(),
+                error.detail.as_deref().or(fallback).unwrap_or_default()
+            ),
+            None,
+        );
     }
 
     // Return only successful uploads
@@ -1398,11 +1562,14 @@ fn poll_dif_assemble(
 
 /// Uploads debug info files using the chunk-upload endpoint.
 fn upload_difs_chunked(
-    options: &DifUpload,
+    options: &mut DifUpload,
     chunk_options: &ChunkUploadOptions,
 ) -> Result<(Vec<DebugInfoFile>, bool), Error> {
     // Search for debug files in the file system and ZIPs
-    let found = search_difs(options)?;
+    let found = {
+        let _step = step("Searching for debug information files");
+        search_difs(options)?
+    };
     if found.is_empty() {
         println!("{} No debug information files found", style(">").dim());
         return Ok(Default::default());
@@ -1417,6 +1584,7 @@ fn upload_difs_chunked(
         let source_bundles = create_source_bundles(&processed)?;
         processed.extend(source_bundles);
     }
+    options.size_report = Some(report_sizes(&processed));
 
     // Calculate checksums and chunks
     let chunked = prepare_difs(processed, |m| {
@@ -1424,12 +1592,17 @@ fn upload_difs_chunked(
     })?;
 
     // Upload missing chunks to the server and remember incomplete difs
-    let missing_info = try_assemble_difs(&chunked, options)?;
-    upload_missing_chunks(&missing_info, chunk_options)?;
+    let missing_info = {
+        let _step = step("Uploading debug information files");
+        let missing_info = try_assemble_difs(&chunked, options)?;
+        upload_missing_chunks(&missing_info, chunk_options)?;
+        missing_info
+    };
 
     // Only if DIFs were missing, poll until assembling is complete
     let (missing_difs, _) = missing_info;
     if !missing_difs.is_empty() {
+        let _step = step("Processing debug information files");
         poll_dif_assemble(&missing_difs, options)
     } else {
         println!(
@@ -1495,6 +1668,7 @@ fn upload_in_batches(
     let mut dsyms = Vec::new();
 
     for (i, (batch, _)) in objects.batches(max_size, MAX_CHUNKS).enumerate() {
+        let _step = step(format!("Uploading batch {}", i + 1));
         println!("\n{}", style(format!("Batch {}", i + 1)).bold());
 
         println!(
@@ -1512,9 +1686,12 @@ fn upload_in_batches(
 }
 
 /// Uploads debug info files using the legacy endpoint.
-fn upload_difs_batched(options: &DifUpload) -> Result<Vec<DebugInfoFile>, Error> {
+fn upload_difs_batched(options: &mut DifUpload) -> Result<Vec<DebugInfoFile>, Error> {
     // Search for debug files in the file system and ZIPs
-    let found = search_difs(options)?;
+    let found = {
+        let _step = step("Searching for debug information files");
+        search_difs(options)?
+    };
     if found.is_empty() {
         println!("{} No debug information files found", style(">").dim());
         return Ok(Default::default());
@@ -1523,6 +1700,7 @@ fn upload_difs_batched(options: &DifUpload) -> Result<Vec<DebugInfoFile>, Error>
     // Try to resolve BCSymbolMaps
     let symbol_map = options.symbol_map.as_deref();
     let processed = process_symbol_maps(found, symbol_map)?;
+    options.size_report = Some(report_sizes(&processed));
 
     // Calculate checksums
     let hashed = prepare_difs(processed, HashedDifMatch::from)?;
@@ -1603,15 +1781,18 @@ pub enum DifFormat {
 /// If `symbol_map` is set and Apple dSYMs with hidden symbols are found, the
 /// uploader will first try to locate BCSymbolMaps and generate new dSYMs with
 /// resolved symbols.
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct DifUpload {
     org: String,
     project: String,
     paths: Vec<PathBuf>,
     ids: BTreeSet<DebugId>,
+    archs: BTreeSet<Arch>,
     formats: BTreeSet<DifFormat>,
     features: ObjectDifFeatures,
     extensions: BTreeSet<OsString>,
+    ignores: BTreeSet<String>,
+    ignore_file: Option<String>,
     symbol_map: Option<PathBuf>,
     zips_allowed: bool,
     max_file_size: u64,
@@ -1621,6 +1802,7 @@ pub struct DifUpload {
     include_sources: bool,
     bcsymbolmaps_allowed: bool,
     wait: bool,
+    size_report: Option<SizeReport>,
 }
 
 impl DifUpload {
@@ -1646,9 +1828,12 @@ impl DifUpload {
             project,
             paths: Vec::new(),
             ids: BTreeSet::new(),
+            archs: BTreeSet::new(),
             formats: BTreeSet::new(),
             features: ObjectDifFeatures::all(),
             extensions: BTreeSet::new(),
+            ignores: BTreeSet::new(),
+            ignore_file: None,
             symbol_map: None,
             zips_allowed: true,
             max_file_size: DEFAULT_MAX_DIF_SIZE,
@@ -1658,6 +1843,7 @@ impl DifUpload {
             include_sources: false,
             bcsymbolmaps_allowed: false,
             wait: false,
+            size_report: None,
         }
     }
 
@@ -1670,6 +1856,40 @@ impl DifUpload {
         self
     }
 
+    /// Skips all files and folders matching the glob.
+    pub fn ignore<S>(&mut self, glob: S) -> &mut Self
+    where
+        S: AsRef<str>,
+    {
+        self.ignores.insert(format!("!{}", glob.as_ref()));
+        self
+    }
+
+    /// Skips all files and folders matching any of the globs.
+    pub fn ignores<I>(&mut self, globs: I) -> &mut Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        for glob in globs {
+            self.ignore(glob);
+        }
+        self
+    }
+
+    /// Skips all files and folders listed in the ignore file, e.g. a
+    /// `.gitignore`.
+    pub fn ignore_file<P>(&mut self, path: P) -> &mut Self
+    where
+        P: Into<String>,
+    {
+        let path = path.into();
+        if !path.is_empty() {
+            self.ignore_file = Some(path);
+        }
+        self
+    }
+
     /// Adds paths to search for debug information files.
     pub fn search_paths<I>(&mut self, paths: I) -> &mut Self
     where
@@ -1682,6 +1902,17 @@ impl DifUpload {
         self
     }
 
+    /// Returns the paths that will be searched.
+    pub fn get_search_paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Removes all previously added search paths.
+    pub fn clear_search_paths(&mut self) -> &mut Self {
+        self.paths.clear();
+        self
+    }
+
     /// Add a `DebugId` to filter for.
     ///
     /// By default, all DebugIds will be included.
@@ -1708,6 +1939,18 @@ impl DifUpload {
         self
     }
 
+    /// Add architectures to filter for.
+    ///
+    /// By default, objects of all architectures will be included.  This is
+    /// useful to skip slices of universal binaries that are not shipped.
+    pub fn filter_archs<I>(&mut self, archs: I) -> &mut Self
+    where
+        I: IntoIterator<Item = Arch>,
+    {
+        self.archs.extend(archs);
+        self
+    }
+
     /// Add an `FileFormat` to filter for.
     ///
     /// By default, all object formats will be included.
@@ -1842,6 +2085,12 @@ impl DifUpload {
         Ok((upload_difs_batched(self)?, false))
     }
 
+    /// Returns the sizes of the files found by the last upload, grouped by
+    /// architecture.
+    pub fn size_report(&self) -> Option<&SizeReport> {
+        self.size_report.as_ref()
+    }
+
     /// Validate that the server supports all requested capabilities.
     fn validate_capabilities(&mut self) {
         // Checks whether source bundles are *explicitly* requested on the command line.
@@ -1895,6 +2144,14 @@ impl DifUpload {
         }
     }
 
+    /// Determines if the architecture of the given `Object` is included.
+    fn valid_arch(&self, dif: &DifMatch) -> bool {
+        match dif.object() {
+            Some(object) => self.archs.is_empty() || self.archs.contains(&object.arch()),
+            None => true,
+        }
+    }
+
     /// Determines if the given `Object` matches the features search criteria.
     ///
     /// If this is not an Object DIF then the object features filter does not apply so this
@@ -1940,6 +2197,12 @@ impl DifUpload {
             return false;
         }
 
+        // Skip if this DIF is a slice of an architecture we don't want.
+        if !self.valid_arch(dif) {
+            debug!("skipping {} because of architecture", dif.name);
+            return false;
+        }
+
         // Skip if this DIF does not have features we want.
         if !self.valid_features(dif) {
             debug!("skipping {} because of features", dif.name);
@@ -1962,3 +2225,92 @@ impl DifUpload {
         true
     }
 }
+
+#[test]
+fn test_parse_ar_members() {
+    fn header(name: &str, size: usize) -> Vec<u8> {
+        format!(
+            "{:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n",
+            name, 0, 0, 0, 644, size
+        )
+        .into_bytes()
+    }
+
+    let mut bsd = ARCHIVE_MAGIC.to_vec();
+    bsd.extend(header("__.SYMDEF", 4));
+    bsd.extend(b"syms");
+    bsd.extend(header("#1/12", 15));
+    bsd.extend(b"Foo.swift.o\0abc\n");
+    bsd.extend(header("Bar.o", 2));
+    bsd.extend(b"de");
+
+    let members = parse_ar_members(&bsd).unwrap();
+    assert_eq!(
+        members,
+        vec![
+            ("Foo.swift.o".to_string(), &b"abc"[..]),
+            ("Bar.o".to_string(), &b"de"[..])
+        ]
+    );
+
+    let mut gnu = ARCHIVE_MAGIC.to_vec();
+    gnu.extend(header("//", 18));
+    gnu.extend(b"a_long_name.o/\nxy\n");
+    gnu.extend(header("/0", 1));
+    gnu.extend(b"x\n");
+    gnu.extend(header("short.o/", 1));
+    gnu.extend(b"y");
+
+    let members = parse_ar_members(&gnu).unwrap();
+    assert_eq!(
+        members,
+        vec![
+            ("a_long_name.o".to_string(), &b"x"[..]),
+            ("short.o".to_string(), &b"y"[..])
+        ]
+    );
+
+    assert!(parse_ar_members(b"!<arch>\nbroken").is_err());
+}
+
+#[test]
+fn test_is_android_native_entry() {
+    use AndroidPackage::{Apk, AppBundle};
+
+    assert!(is_android_native_entry(Apk, "lib/arm64-v8a/libapp.so"));
+    assert!(is_android_native_entry(
+        AppBundle,
+        "base/lib/arm64-v8a/libapp.so"
+    ));
+    assert!(is_android_native_entry(
+        AppBundle,
+        "feature/lib/x86_64/libfeature.so"
+    ));
+    assert!(is_android_native_entry(
+        AppBundle,
+        "BUNDLE-METADATA/com.android.tools.build.debugsymbols/arm64-v8a/libapp.so.dbg"
+    ));
+    assert!(!is_android_native_entry(Apk, "assets/lib/x86/libgame.so"));
+    assert!(!is_android_native_entry(
+        Apk,
+        "base/lib/arm64-v8a/libapp.so"
+    ));
+    assert!(!is_android_native_entry(
+        AppBundle,
+        "lib/arm64-v8a/libapp.so"
+    ));
+    assert!(!is_android_native_entry(
+        AppBundle,
+        "base/assets/lib/data.bin"
+    ));
+    assert!(!is_android_native_entry(
+        AppBundle,
+        "assets/lib/data/libapp.so"
+    ));
+    assert!(!is_android_native_entry(AppBundle, "base/dex/classes.dex"));
+    assert!(!is_android_native_entry(Apk, "assets/dexopt/baseline.prof"));
+    assert!(!is_android_native_entry(
+        AppBundle,
+        "base/lib/arm64-v8a/nested/libapp.so"
+    ));
+}
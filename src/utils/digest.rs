@@ -0,0 +1,239 @@
+//! SHA1 and SHA256 hashing for checksums and signatures.
+//!
+//! Checksums of chunks and debug files are computed over the entire upload,
+//! which makes hashing a hot path for large uploads.  The `sha1` and `sha2`
+//! crates detect the SHA instructions of x86 and ARMv8 CPUs at runtime and
+//! use portable code on other CPUs.  CRC32 and the compression of uploads
+//! use their crates as they are.
+//!
+//! In FIPS mode SHA1, SHA256 and HMAC are computed by OpenSSL where it is
+//! available, see `utils::fips`.
+use std::io;
+use std::str::FromStr;
+
+use hmac::{Hmac, Mac};
+use sha1::Digest;
+use sha2::Digest as _;
+
+use crate::utils::fips;
+
+/// An incremental SHA1 hash, a drop-in for `sha1::Sha1`.
+#[derive(Clone)]
+pub struct Sha1 {
+    inner: Sha1Inner,
+}
+
+#[derive(Clone)]
+enum Sha1Inner {
+    Soft(sha1_accel::Sha1),
+    #[cfg(all(unix, not(target_os = "macos")))]
+    OpenSsl(openssl::hash::Hasher),
+}
+
+impl Default for Sha1 {
+    fn default() -> Sha1 {
+        Sha1::new()
+    }
+}
+
+impl Sha1 {
+    /// Creates a hash using the SHA instructions of the CPU if available, or
+    /// OpenSSL in FIPS mode.
+    pub fn new() -> Sha1 {
+        #[cfg(all(unix, not(target_os = "macos")))]
+        {
+            if fips::is_enabled() {
+                return Sha1 {
+                    inner: Sha1Inner::OpenSsl(openssl_hasher(MessageDigest::sha1())),
+                };
+            }
+        }
+        Sha1 {
+            inner: Sha1Inner::Soft(sha1_accel::Sha1::new()),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self.inner {
+            Sha1Inner::Soft(ref mut sha) => sha.update(data),
+            #[cfg(all(unix, not(target_os = "macos")))]
+            Sha1Inner::OpenSsl(ref mut hasher) => hasher.update(data).expect("OpenSSL SHA1 failed"),
+        }
+    }
+
+    pub fn digest(&self) -> Digest {
+        let hex = match self.inner {
+            Sha1Inner::Soft(ref sha) => to_hex(&sha.clone().finalize()),
+            #[cfg(all(unix, not(target_os = "macos")))]
+            Sha1Inner::OpenSsl(ref hasher) => openssl_finish(hasher),
+        };
+        Digest::from_str(&hex).unwrap()
+    }
+}
+
+/// An incremental SHA256 hash, computed by OpenSSL in FIPS mode.
+pub struct Sha256 {
+    inner: Sha256Inner,
+}
+
+enum Sha256Inner {
+    Soft(sha2::Sha256),
+    #[cfg(all(unix, not(target_os = "macos")))]
+    OpenSsl(openssl::hash::Hasher),
+}
+
+impl Default for Sha256 {
+    fn default() -> Sha256 {
+        Sha256::new()
+    }
+}
+
+impl Sha256 {
+    pub fn new() -> Sha256 {
+        #[cfg(all(unix, not(target_os = "macos")))]
+        {
+            if fips::is_enabled() {
+                return Sha256 {
+                    inner: Sha256Inner::OpenSsl(openssl_hasher(MessageDigest::sha256())),
+                };
+            }
+        }
+        Sha256 {
+            inner: Sha256Inner::Soft(sha2::Sha256::new()),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self.inner {
+            Sha256Inner::Soft(ref mut sha) => sha.update(data),
+            #[cfg(all(unix, not(target_os = "macos")))]
+            Sha256Inner::OpenSsl(ref mut hasher) => {
+                hasher.update(data).expect("OpenSSL SHA256 failed")
+            }
+        }
+    }
+
+    /// Returns the hex encoded hash.
+    pub fn hex_digest(self) -> String {
+        match self.inner {
+            Sha256Inner::Soft(sha) => to_hex(&sha.finalize()),
+            #[cfg(all(unix, not(target_os = "macos")))]
+            Sha256Inner::OpenSsl(hasher) => openssl_finish(&hasher),
+        }
+    }
+}
+
+impl io::Write for Sha256 {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Returns the hex encoded SHA256 hash of `data`.
+pub fn sha256_hex(data: &[u8]) -> String {
+    let mut sha = Sha256::new();
+    sha.update(data);
+    sha.hex_digest()
+}
+
+/// Returns the HMAC-SHA256 of `data`, computed by OpenSSL in FIPS mode.
+pub fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        if fips::is_enabled() {
+            return openssl_hmac_sha256(key, data);
+        }
+    }
+    let mut mac =
+        Hmac::<sha2::Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Encodes bytes as lowercase hex.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+use openssl::hash::MessageDigest;
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn openssl_hasher(md: MessageDigest) -> openssl::hash::Hasher {
+    openssl::hash::Hasher::new(md).expect("digest not available in OpenSSL")
+}
+
+/// Returns the hex encoded hash of the data so far, the hasher can be
+/// updated further.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn openssl_finish(hasher: &openssl::hash::Hasher) -> String {
+    to_hex(&hasher.clone().finish().expect("OpenSSL digest failed"))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn openssl_hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    use openssl::pkey::PKey;
+    use openssl::sign::Signer;
+
+    PKey::hmac(key)
+        .and_then(|key| {
+            let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+            signer.update(data)?;
+            signer.sign_to_vec()
+        })
+        .expect("HMAC-SHA256 not available in OpenSSL")
+}
+
+#[cfg(test)]
+fn check_sha1(new: impl Fn() -> Sha1) {
+    let data: Vec<u8> = (0..4096u32).map(|i| (i * 7 + i / 13) as u8).collect();
+    let mut sizes = vec![0, 1, 55, 56, 63, 64, 65, 119, 120, 128, 1000, 4096];
+    sizes.extend(0..130);
+    for size in sizes {
+        let expected = sha1::Sha1::from(&data[..size]).digest();
+
+        let mut sha = new();
+        sha.update(&data[..size]);
+        assert_eq!(sha.digest(), expected, "{} bytes", size);
+
+        // arbitrary splits have to produce the same hash
+        let mut sha = new();
+        for part in data[..size].chunks(37) {
+            sha.update(part);
+        }
+        assert_eq!(sha.digest(), expected, "{} bytes in parts", size);
+    }
+}
+
+#[test]
+fn test_sha1() {
+    check_sha1(Sha1::new);
+}
+
+#[test]
+fn test_hmac_sha256() {
+    // RFC 4231, test case 2
+    assert_eq!(
+        to_hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+        "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+}
+
+#[test]
+#[cfg(all(unix, not(target_os = "macos")))]
+fn test_openssl() {
+    check_sha1(|| Sha1 {
+        inner: Sha1Inner::OpenSsl(openssl_hasher(MessageDigest::sha1())),
+    });
+
+    let data = b"The quick brown fox jumps over the lazy dog";
+    let mut hasher = openssl_hasher(MessageDigest::sha256());
+    hasher.update(data).unwrap();
+    assert_eq!(openssl_finish(&hasher), sha256_hex(data));
+    assert_eq!(openssl_hmac_sha256(b"key", data), hmac_sha256(b"key", data));
+}
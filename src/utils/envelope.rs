@@ -0,0 +1,358 @@
+//! Builds envelopes to send events together with attachments, transactions,
+//! profiles, replays and metrics.
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use failure::{bail, Error};
+use itertools::Itertools;
+use sentry::internals::{Dsn, Uuid};
+use sentry::protocol::Event;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::api::Api;
+use crate::utils::metrics::Metric;
+
+/// A file sent along with an event.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Attachment {
+    pub filename: String,
+    pub content_type: String,
+    pub data: String,
+}
+
+impl Attachment {
+    /// Reads the end of a text file, keeping at most `max_size` bytes.
+    ///
+    /// When the file is larger, the leading partial line is dropped and a
+    /// marker notes how much was cut off.
+    pub fn from_log_tail(path: &Path, max_size: u64) -> Result<Attachment, Error> {
+        let mut file = fs::File::open(path)?;
+        let len = file.metadata()?.len();
+        let skipped = len.saturating_sub(max_size);
+        file.seek(SeekFrom::Start(skipped))?;
+        let mut buf = vec![];
+        file.read_to_end(&mut buf)?;
+
+        let mut data = String::from_utf8_lossy(&buf).into_owned();
+        if skipped > 0 {
+            let start = data.find('\n').map_or(data.len(), |idx| idx + 1);
+            data = format!(
+                "[... {} bytes truncated ...]\n{}",
+                skipped + start as u64,
+                &data[start..]
+            );
+        }
+
+        Ok(Attachment {
+            filename: path
+                .file_name()
+                .map_or_else(|| "log.txt".into(), |name| name.to_string_lossy().into()),
+            content_type: "text/plain".into(),
+            data,
+        })
+    }
+}
+
+fn write_item(rv: &mut Vec<u8>, mut header: Value, payload: &[u8]) -> Result<(), Error> {
+    header["length"] = payload.len().into();
+    serde_json::to_writer(&mut *rv, &header)?;
+    rv.push(b'\n');
+    rv.extend_from_slice(payload);
+    rv.push(b'\n');
+    Ok(())
+}
+
+fn write_header(event_id: Uuid) -> Result<Vec<u8>, Error> {
+    let mut rv = serde_json::to_vec(&json!({ "event_id": event_id.to_simple().to_string() }))?;
+    rv.push(b'\n');
+    Ok(rv)
+}
+
+/// Wraps an event serialized as JSON into an envelope.
+pub fn to_event_json_envelope(event: &[u8]) -> Result<Vec<u8>, Error> {
+    let event_id = serde_json::from_slice::<Value>(event)?["event_id"]
+        .as_str()
+        .and_then(|event_id| event_id.parse().ok())
+        .unwrap_or_else(Uuid::new_v4);
+    let mut rv = write_header(event_id)?;
+    write_item(&mut rv, json!({ "type": "event" }), event)?;
+    Ok(rv)
+}
+
+/// Serializes an event and its attachments into an envelope.
+pub fn to_envelope(event: &Event<'_>, attachments: &[Attachment]) -> Result<Vec<u8>, Error> {
+    let mut rv = write_header(event.event_id)?;
+    write_item(
+        &mut rv,
+        json!({ "type": "event" }),
+        &serde_json::to_vec(event)?,
+    )?;
+    for attachment in attachments {
+        write_item(
+            &mut rv,
+            json!({
+                "type": "attachment",
+                "filename": attachment.filename,
+                "content_type": attachment.content_type,
+            }),
+            attachment.data.as_bytes(),
+        )?;
+    }
+    Ok(rv)
+}
+
+/// Serializes a minidump and its attachments into an envelope, the way the
+/// minidump endpoint stores them.
+pub fn to_minidump_envelope(
+    event: &Value,
+    minidump: &Path,
+    attachments: &[PathBuf],
+) -> Result<Vec<u8>, Error> {
+    let event_id = match event["event_id"].as_str().map(str::parse) {
+        Some(Ok(event_id)) => event_id,
+        _ => bail!("event without event_id"),
+    };
+    let mut rv = write_header(event_id)?;
+    write_item(
+        &mut rv,
+        json!({ "type": "event" }),
+        &serde_json::to_vec(event)?,
+    )?;
+    let files = std::iter::once((minidump, "event.minidump")).chain(
+        attachments
+            .iter()
+            .map(|path| (path.as_path(), "event.attachment")),
+    );
+    for (path, attachment_type) in files {
+        write_item(
+            &mut rv,
+            json!({
+                "type": "attachment",
+                "attachment_type": attachment_type,
+                "filename": path.file_name().map(|name| name.to_string_lossy()),
+            }),
+            &fs::read(path)?,
+        )?;
+    }
+    Ok(rv)
+}
+
+/// Serializes files into an envelope that attaches them to an event that
+/// was already sent.
+pub fn to_attachments_envelope(event_id: Uuid, files: &[PathBuf]) -> Result<Vec<u8>, Error> {
+    let mut rv = write_header(event_id)?;
+    for path in files {
+        write_item(
+            &mut rv,
+            json!({
+                "type": "attachment",
+                "filename": path.file_name().map(|name| name.to_string_lossy()),
+            }),
+            &fs::read(path)?,
+        )?;
+    }
+    Ok(rv)
+}
+
+/// Serializes a transaction into an envelope.
+///
+/// The protocol types of the sentry crate do not know about transactions,
+/// so the transaction is passed as plain JSON with an `event_id`.
+pub fn to_transaction_envelope(transaction: &Value) -> Result<Vec<u8>, Error> {
+    let event_id = match transaction["event_id"].as_str().map(str::parse) {
+        Some(Ok(event_id)) => event_id,
+        _ => bail!("transaction without event_id"),
+    };
+    let mut rv = write_header(event_id)?;
+    write_item(
+        &mut rv,
+        json!({ "type": "transaction" }),
+        &serde_json::to_vec(transaction)?,
+    )?;
+    Ok(rv)
+}
+
+/// Serializes a transaction and the profile taken during it into an envelope.
+pub fn to_profile_envelope(transaction: &Value, profile: &Value) -> Result<Vec<u8>, Error> {
+    let mut rv = to_transaction_envelope(transaction)?;
+    write_item(
+        &mut rv,
+        json!({ "type": "profile" }),
+        &serde_json::to_vec(profile)?,
+    )?;
+    Ok(rv)
+}
+
+/// Serializes a segment of a replay into an envelope.
+///
+/// The envelope holds the replay event describing the segment and the
+/// recording, which is the rrweb JSON prefixed with a header line naming
+/// the segment.
+pub fn to_replay_envelope(replay_event: &Value, recording: &[u8]) -> Result<Vec<u8>, Error> {
+    let replay_id = match replay_event["replay_id"].as_str().map(str::parse) {
+        Some(Ok(replay_id)) => replay_id,
+        _ => bail!("replay event without replay_id"),
+    };
+    let mut rv = write_header(replay_id)?;
+    write_item(
+        &mut rv,
+        json!({ "type": "replay_event" }),
+        &serde_json::to_vec(replay_event)?,
+    )?;
+    let mut payload = serde_json::to_vec(&json!({ "segment_id": replay_event["segment_id"] }))?;
+    payload.push(b'\n');
+    payload.extend_from_slice(recording);
+    write_item(&mut rv, json!({ "type": "replay_recording" }), &payload)?;
+    Ok(rv)
+}
+
+/// Serializes a monitor check-in into an envelope.
+pub fn to_check_in_envelope(check_in: &Value) -> Result<Vec<u8>, Error> {
+    let mut rv = b"{}\n".to_vec();
+    write_item(
+        &mut rv,
+        json!({ "type": "check_in" }),
+        &serde_json::to_vec(check_in)?,
+    )?;
+    Ok(rv)
+}
+
+/// Serializes metrics into an envelope with a single `statsd` item.
+pub fn to_metrics_envelope(metrics: &[Metric]) -> Result<Vec<u8>, Error> {
+    let mut rv = b"{}\n".to_vec();
+    let payload = metrics.iter().map(|metric| metric.to_string()).join("\n");
+    write_item(&mut rv, json!({ "type": "statsd" }), payload.as_bytes())?;
+    Ok(rv)
+}
+
+/// Serializes log records into an envelope with a single `log` item.
+pub fn to_logs_envelope(logs: &[Value]) -> Result<Vec<u8>, Error> {
+    let mut rv = b"{}\n".to_vec();
+    write_item(
+        &mut rv,
+        json!({
+            "type": "log",
+            "item_count": logs.len(),
+            "content_type": "application/vnd.sentry.items.log+json",
+        }),
+        &serde_json::to_vec(&json!({ "items": logs }))?,
+    )?;
+    Ok(rv)
+}
+
+/// Sends an event with attachments and returns its ID.
+pub fn send_with_attachments(
+    dsn: &Dsn,
+    event: &Event<'_>,
+    attachments: &[Attachment],
+) -> Result<Uuid, Error> {
+    let resp = Api::current().send_envelope(dsn, to_envelope(event, attachments)?)?;
+    if resp.failed() {
+        bail!("Could not send event (status {})", resp.status());
+    }
+    Ok(event.event_id)
+}
+
+/// Sends files as attachments of an existing event.
+pub fn send_attachments(dsn: &Dsn, event_id: Uuid, files: &[PathBuf]) -> Result<(), Error> {
+    let resp = Api::current().send_envelope(dsn, to_attachments_envelope(event_id, files)?)?;
+    if resp.failed() {
+        bail!("Could not send attachments (status {})", resp.status());
+    }
+    Ok(())
+}
+
+/// Sends a transaction built by `to_transaction_envelope`.
+pub fn send_transaction(dsn: &Dsn, transaction: &Value) -> Result<(), Error> {
+    let resp = Api::current().send_envelope(dsn, to_transaction_envelope(transaction)?)?;
+    if resp.failed() {
+        bail!("Could not send transaction (status {})", resp.status());
+    }
+    Ok(())
+}
+
+/// Sends a transaction together with its profile.
+pub fn send_profile(dsn: &Dsn, transaction: &Value, profile: &Value) -> Result<(), Error> {
+    let resp = Api::current().send_envelope(dsn, to_profile_envelope(transaction, profile)?)?;
+    if resp.failed() {
+        bail!("Could not send profile (status {})", resp.status());
+    }
+    Ok(())
+}
+
+/// Sends a segment of a replay built by `to_replay_envelope`.
+pub fn send_replay_segment(dsn: &Dsn, replay_event: &Value, recording: &[u8]) -> Result<(), Error> {
+    let resp = Api::current().send_envelope(dsn, to_replay_envelope(replay_event, recording)?)?;
+    if resp.failed() {
+        bail!("Could not send replay segment (status {})", resp.status());
+    }
+    Ok(())
+}
+
+/// Sends a monitor check-in built by `to_check_in_envelope`.
+pub fn send_check_in(dsn: &Dsn, check_in: &Value) -> Result<(), Error> {
+    let resp = Api::current().send_envelope(dsn, to_check_in_envelope(check_in)?)?;
+    if resp.failed() {
+        bail!("Could not send check-in (status {})", resp.status());
+    }
+    Ok(())
+}
+
+/// Sends metrics to the metrics ingestion of a project.
+pub fn send_metrics(dsn: &Dsn, metrics: &[Metric]) -> Result<(), Error> {
+    let resp = Api::current().send_envelope(dsn, to_metrics_envelope(metrics)?)?;
+    if resp.failed() {
+        bail!("Could not send metrics (status {})", resp.status());
+    }
+    Ok(())
+}
+
+/// Sends a batch of log records built by `to_logs_envelope`.
+pub fn send_logs(dsn: &Dsn, logs: &[Value]) -> Result<(), Error> {
+    let resp = Api::current().send_envelope(dsn, to_logs_envelope(logs)?)?;
+    if resp.failed() {
+        bail!("Could not send logs (status {})", resp.status());
+    }
+    Ok(())
+}
+
+#[test]
+fn test_log_tail() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("build.log");
+    fs::write(&path, "first line\nsecond line\nthird line\n").unwrap();
+
+    let attachment = Attachment::from_log_tail(&path, 1024).unwrap();
+    assert_eq!(attachment.filename, "build.log");
+    assert_eq!(attachment.data, "first line\nsecond line\nthird line\n");
+
+    let attachment = Attachment::from_log_tail(&path, 15).unwrap();
+    assert_eq!(
+        attachment.data,
+        "[... 23 bytes truncated ...]\nthird line\n"
+    );
+}
+
+#[test]
+fn test_to_envelope() {
+    let event = Event::default();
+    let attachment = Attachment {
+        filename: "build.log".into(),
+        content_type: "text/plain".into(),
+        data: "oops\n".into(),
+    };
+    let envelope = String::from_utf8(to_envelope(&event, &[attachment]).unwrap()).unwrap();
+    let lines: Vec<_> = envelope.lines().collect();
+    assert_eq!(lines.len(), 6);
+    assert_eq!(
+        lines[0],
+        format!(r#"{{"event_id":"{}"}}"#, event.event_id.to_simple())
+    );
+    assert!(lines[1].contains(r#""type":"event""#));
+    assert!(lines[3].contains(r#""filename":"build.log""#));
+    assert!(lines[3].contains(r#""length":5"#));
+    assert_eq!(lines[4], "oops");
+    assert_eq!(lines[5], "");
+}
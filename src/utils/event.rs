@@ -1,16 +1,20 @@
 use std::borrow::Cow;
 use std::fs;
 use std::io::{BufRead, BufReader};
+use std::sync::Arc;
 use std::time::Duration;
 
 use chrono::Utc;
 use failure::{Error, ResultExt};
 use lazy_static::lazy_static;
+use log::warn;
 use regex::Regex;
 use sentry::protocol::{Breadcrumb, ClientSdkInfo, Event};
 use sentry::{internals::Dsn, Client, ClientOptions};
 
+use crate::config::Config;
 use crate::constants::USER_AGENT;
+use crate::utils::spool::{Spool, SpoolTransport};
 
 lazy_static! {
     static ref COMPONENT_RE: Regex = Regex::new(r#"^([^:]+): (.*)$"#).unwrap();
@@ -81,13 +85,19 @@ pub fn with_sentry_client<F, R>(dsn: Dsn, callback: F) -> R
 where
     F: FnOnce(&Client) -> R,
 {
-    let client = Client::from_config((
-        dsn,
-        ClientOptions {
-            user_agent: USER_AGENT.into(),
-            ..Default::default()
-        },
-    ));
+    let mut options = ClientOptions {
+        user_agent: USER_AGENT.into(),
+        ..Default::default()
+    };
+    if let Some(config) = Config::current_opt().filter(|config| config.should_spool()) {
+        match Spool::from_config(&config) {
+            Ok(spool) => {
+                options.transport = Box::new(Arc::new(SpoolTransport::new(spool, dsn.clone())));
+            }
+            Err(err) => warn!("Could not open spool: {}", err),
+        }
+    }
+    let client = Client::from_config((dsn, options));
 
     let rv = callback(&client);
     client.close(Some(Duration::from_secs(2)));
@@ -1,15 +1,19 @@
 use std::collections::BTreeSet;
 use std::fs;
 use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use clap::ArgMatches;
 use console::style;
 use failure::Error;
-use ignore::overrides::OverrideBuilder;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::overrides::{Override, OverrideBuilder};
 use ignore::types::TypesBuilder;
 use ignore::WalkBuilder;
-use log::info;
+use log::{info, warn};
 
+use crate::utils::archive::{is_archive, read_archive};
+use crate::utils::fs::normalize_path;
 use crate::utils::progress::{ProgressBar, ProgressStyle};
 
 pub struct ReleaseFileSearch {
@@ -17,6 +21,105 @@ pub struct ReleaseFileSearch {
     extensions: BTreeSet<String>,
     ignores: BTreeSet<String>,
     ignore_file: Option<String>,
+    follow_links: bool,
+}
+
+/// Decides which files to skip by the `--ignore` globs and `--ignore-file`
+/// of a command, for searches that do not walk with the `ignore` crate.
+pub struct IgnoreMatcher {
+    overrides: Option<Override>,
+    gitignore: Option<Gitignore>,
+}
+
+impl IgnoreMatcher {
+    /// Creates a matcher for paths below `root`.  The globs are ignore rules
+    /// as for `ReleaseFileSearch::ignores`, i.e. starting with `!`.
+    pub fn new<'i, I>(root: &Path, ignores: I, ignore_file: Option<&str>) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = &'i String>,
+    {
+        let root = &normalize_path(root);
+        let mut override_builder = OverrideBuilder::new(root);
+        let mut has_overrides = false;
+        for ignore in ignores {
+            override_builder.add(ignore)?;
+            has_overrides = true;
+        }
+        let overrides = if has_overrides {
+            Some(override_builder.build()?)
+        } else {
+            None
+        };
+        let gitignore = match ignore_file {
+            Some(ignore_file) => {
+                let mut builder = GitignoreBuilder::new(root);
+                // like for directories, a broken ignore file is not an error
+                builder.add(ignore_file);
+                builder.build().ok()
+            }
+            None => None,
+        };
+        Ok(IgnoreMatcher {
+            overrides,
+            gitignore,
+        })
+    }
+
+    /// Creates a matcher from the `ignore` and `ignore_file` arguments added
+    /// by `ArgExt::ignore_args`.
+    pub fn from_args(root: &Path, matches: &ArgMatches<'_>) -> Result<Self, Error> {
+        IgnoreMatcher::new(
+            root,
+            &ignores_from_args(matches),
+            matches
+                .value_of("ignore_file")
+                .filter(|path| !path.is_empty()),
+        )
+    }
+
+    /// Returns whether the path or one of its folders below the root is
+    /// ignored.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let path = &normalize_path(path);
+        if let Some(ref overrides) = self.overrides {
+            let mut is_dir = is_dir;
+            for ancestor in path.ancestors() {
+                if ancestor == overrides.path() || !ancestor.starts_with(overrides.path()) {
+                    break;
+                }
+                if overrides.matched(ancestor, is_dir).is_ignore() {
+                    return true;
+                }
+                is_dir = true;
+            }
+        }
+        self.gitignore.as_ref().is_some_and(|gitignore| {
+            gitignore
+                .matched_path_or_any_parents(path, is_dir)
+                .is_ignore()
+        })
+    }
+}
+
+/// Returns whether walking failed because of a broken symbolic link or a
+/// cycle of them.
+fn is_link_error(err: &ignore::Error) -> bool {
+    match err {
+        ignore::Error::Loop { .. } => true,
+        ignore::Error::WithPath { err, .. } | ignore::Error::WithDepth { err, .. } => {
+            is_link_error(err)
+        }
+        ignore::Error::Io(err) => err.kind() == std::io::ErrorKind::NotFound,
+        _ => false,
+    }
+}
+
+/// Returns the `--ignore` globs of a command as ignore rules.
+pub fn ignores_from_args(matches: &ArgMatches<'_>) -> Vec<String> {
+    matches
+        .values_of("ignore")
+        .map(|ignores| ignores.map(|i| format!("!{}", i)).collect())
+        .unwrap_or_default()
 }
 
 #[derive(Eq, PartialEq, Hash)]
@@ -29,13 +132,21 @@ pub struct ReleaseFileMatch {
 impl ReleaseFileSearch {
     pub fn new(path: PathBuf) -> Self {
         ReleaseFileSearch {
-            path,
+            path: normalize_path(path),
             extensions: BTreeSet::new(),
             ignore_file: None,
             ignores: BTreeSet::new(),
+            follow_links: true,
         }
     }
 
+    /// Whether the files and folders symbolic links point to are searched,
+    /// which is the default.  Otherwise symbolic links are skipped.
+    pub fn follow_links(&mut self, follow_links: bool) -> &mut Self {
+        self.follow_links = follow_links;
+        self
+    }
+
     pub fn extension<E>(&mut self, extension: E) -> &mut Self
     where
         E: Into<String>,
@@ -86,6 +197,7 @@ impl ReleaseFileSearch {
     }
 
     pub fn collect_file(path: PathBuf) -> Result<ReleaseFileMatch, Error> {
+        let path = normalize_path(path);
         let mut f = fs::File::open(path.clone())?;
         let mut contents = Vec::new();
         f.read_to_end(&mut contents)?;
@@ -106,11 +218,67 @@ impl ReleaseFileSearch {
         progress.enable_steady_tick(100);
         progress.set_style(progress_style);
 
+        let collected = if is_archive(&self.path) {
+            self.collect_archive_files()?
+        } else {
+            self.walk_files(&progress)?
+        };
+
+        progress.finish_and_clear();
+        println!(
+            "{} Found {} release {}",
+            style(">").dim(),
+            style(collected.len()).yellow(),
+            match collected.len() {
+                1 => "file",
+                _ => "files",
+            }
+        );
+
+        Ok(collected)
+    }
+
+    /// Returns a matcher for the ignores of this search, relative to its path.
+    pub fn ignore_matcher(&self) -> Result<IgnoreMatcher, Error> {
+        IgnoreMatcher::new(&self.path, &self.ignores, self.ignore_file.as_deref())
+    }
+
+    /// Reads the matching files of an archive.  Their paths are joined to
+    /// the path of the archive, as if it was a directory.
+    fn collect_archive_files(&self) -> Result<Vec<ReleaseFileMatch>, Error> {
+        let matcher = self.ignore_matcher()?;
+
+        let mut collected = Vec::new();
+        for (name, contents) in read_archive(&self.path)? {
+            if !self.extensions.is_empty()
+                && !self
+                    .extensions
+                    .iter()
+                    .any(|ext| name.ends_with(&format!(".{}", ext)))
+            {
+                continue;
+            }
+            let path = self.path.join(&name);
+            if matcher.is_ignored(&path, false) {
+                continue;
+            }
+
+            info!("found: {} ({} bytes)", path.display(), contents.len());
+            collected.push(ReleaseFileMatch {
+                base_path: self.path.clone(),
+                path,
+                contents,
+            });
+        }
+        Ok(collected)
+    }
+
+    fn walk_files(&self, progress: &ProgressBar) -> Result<Vec<ReleaseFileMatch>, Error> {
         let mut collected = Vec::new();
 
         let mut builder = WalkBuilder::new(&self.path);
         builder
-            .follow_links(true)
+            .follow_links(self.follow_links)
             .git_exclude(false)
             .git_ignore(false)
             .ignore(false);
@@ -139,10 +307,22 @@ impl ReleaseFileSearch {
         }
 
         for result in builder.build() {
-            let file = result?;
+            let file = match result {
+                Ok(file) => file,
+                // broken links and link cycles should not fail the whole search
+                Err(err) if self.follow_links && is_link_error(&err) => {
+                    warn!("Skipping {}", err);
+                    continue;
+                }
+                Err(err) => return Err(err.into()),
+            };
             if file.file_type().map_or(false, |t| t.is_dir()) {
                 continue;
             }
+            if file.path_is_symlink() && !self.follow_links {
+                info!("skipping symbolic link {}", file.path().display());
+                continue;
+            }
             progress.set_message(format!("{}", file.path().display()));
 
             info!(
@@ -165,17 +345,6 @@ impl ReleaseFileSearch {
             progress.set_prefix(collected.len().to_string());
         }
 
-        progress.finish_and_clear();
-        println!(
-            "{} Found {} release {}",
-            style(">").dim(),
-            style(collected.len()).yellow(),
-            match collected.len() {
-                1 => "file",
-                _ => "files",
-            }
-        );
-
         Ok(collected)
     }
 }
@@ -1,25 +1,36 @@
 //! Searches, processes and uploads release files.
 use std::collections::HashMap;
 use std::fmt;
+use std::fs;
 use std::io::BufWriter;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use console::style;
-use failure::{bail, Error};
+use failure::{bail, Error, ResultExt};
+use indicatif::HumanBytes;
 use parking_lot::RwLock;
 use rayon::prelude::*;
 use rayon::ThreadPoolBuilder;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha1::Digest;
 use symbolic::common::ByteView;
 use symbolic::debuginfo::sourcebundle::{SourceBundleWriter, SourceFileInfo, SourceFileType};
 use url::Url;
 
-use crate::api::{Api, ChunkUploadCapability, ChunkUploadOptions, FileContents, ProgressBarMode};
+use crate::api::{
+    Api, AssembleArtifactsResponse, ChunkUploadCapability, ChunkUploadOptions,
+    ChunkedArtifactBundleRequest, FileContents, ProgressBarMode,
+};
 use crate::constants::DEFAULT_MAX_WAIT;
+use crate::utils::annotations::{add_summary, release_link, step};
 use crate::utils::chunks::{upload_chunks, Chunk, ASSEMBLE_POLL_INTERVAL};
+use crate::utils::digest::sha256_hex;
 use crate::utils::fs::{get_sha1_checksums, TempFile};
+use crate::utils::machine;
 use crate::utils::progress::{ProgressBar, ProgressStyle};
 
 /// Fallback concurrency for release file uploads.
@@ -74,9 +85,261 @@ impl ReleaseFile {
 
 pub type ReleaseFiles = HashMap<String, ReleaseFile>;
 
+/// Limits on the size of release files that are uploaded.
+#[derive(Clone, Debug, Default)]
+pub struct UploadLimits {
+    pub max_file_size: Option<u64>,
+    pub max_total_size: Option<u64>,
+    /// Fail instead of skipping the files exceeding a limit.
+    pub fail: bool,
+}
+
+impl UploadLimits {
+    /// Removes the files exceeding a limit, or fails listing them.
+    pub fn apply(&self, files: &mut ReleaseFiles) -> Result<(), Error> {
+        let sizes = files
+            .iter()
+            .map(|(url, file)| (url.clone(), file.contents.len() as u64))
+            .collect();
+        for url in self.exceeding(sizes)? {
+            files.remove(&url);
+        }
+        Ok(())
+    }
+
+    /// Returns the names of the files exceeding a limit after printing them,
+    /// or fails listing them.
+    ///
+    /// Files are kept in the order of their names until the total limit is
+    /// reached, so the same files are skipped on every run.
+    pub fn exceeding(&self, mut sizes: Vec<(String, u64)>) -> Result<Vec<String>, Error> {
+        if self.max_file_size.is_none() && self.max_total_size.is_none() {
+            return Ok(vec![]);
+        }
+
+        sizes.sort();
+        let mut total = 0;
+        let mut exceeded = vec![];
+        let mut summary = String::new();
+        for (name, size) in sizes {
+            let reason = match (self.max_file_size, self.max_total_size) {
+                (Some(max), _) if size > max => {
+                    format!("larger than {} per file", HumanBytes(max))
+                }
+                (_, Some(max)) if total + size > max => {
+                    format!("over {} for all files", HumanBytes(max))
+                }
+                _ => {
+                    total += size;
+                    continue;
+                }
+            };
+            summary.push_str(&format!("\n  {} ({}, {})", name, HumanBytes(size), reason));
+            exceeded.push(name);
+        }
+
+        if exceeded.is_empty() {
+            return Ok(exceeded);
+        }
+        if self.fail {
+            bail!(
+                "{} {} upload limits:{}",
+                exceeded.len(),
+                if exceeded.len() == 1 {
+                    "file exceeds"
+                } else {
+                    "files exceed"
+                },
+                summary
+            );
+        }
+        println!(
+            "{} Skipped {} {} exceeding upload limits:{}",
+            style(">").dim(),
+            style(exceeded.len()).yellow(),
+            if exceeded.len() == 1 { "file" } else { "files" },
+            summary
+        );
+        Ok(exceeded)
+    }
+}
+
+/// The manifest of an upload plan, see `write_upload_plan`.
+#[derive(Debug, Serialize, Deserialize)]
+struct UploadPlanManifest {
+    version: u32,
+    release: String,
+    dist: Option<String>,
+    files: Vec<PlannedFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PlannedFile {
+    url: String,
+    path: PathBuf,
+    #[serde(rename = "type")]
+    ty: SourceFileType,
+    size: usize,
+    sha256: String,
+    headers: Vec<(String, String)>,
+}
+
+/// Release files that were planned for upload on another machine.
+pub struct UploadPlan {
+    pub release: String,
+    pub dist: Option<String>,
+    pub files: ReleaseFiles,
+}
+
+/// Writes the files of an upload to a plan directory, so they can be
+/// uploaded later with `read_upload_plan`.
+///
+/// The directory holds a `manifest.json` with the URL, checksum and
+/// headers of every file, and the contents of the files as processed, named
+/// by their checksum.
+pub fn write_upload_plan(
+    dir: &Path,
+    context: &UploadContext<'_>,
+    files: &ReleaseFiles,
+) -> Result<(), Error> {
+    let contents_dir = dir.join("files");
+    fs::create_dir_all(&contents_dir)
+        .with_context(|_| format!("Could not create {}", contents_dir.display()))?;
+
+    let mut planned = vec![];
+    for file in files.values() {
+        let checksum = ArtifactChecksum::new(context, &file.url, &file.contents);
+        let path = contents_dir.join(&checksum.sha256);
+        if !path.exists() {
+            fs::write(&path, &file.contents)
+                .with_context(|_| format!("Could not write {}", path.display()))?;
+        }
+        planned.push(PlannedFile {
+            url: file.url.clone(),
+            path: file.path.clone(),
+            ty: file.ty,
+            size: checksum.size,
+            sha256: checksum.sha256,
+            headers: file.headers.clone(),
+        });
+    }
+    planned.sort_by(|a, b| a.url.cmp(&b.url));
+
+    let manifest = UploadPlanManifest {
+        version: 1,
+        release: context.release.to_string(),
+        dist: context.dist.map(str::to_string),
+        files: planned,
+    };
+    let path = dir.join("manifest.json");
+    fs::write(&path, serde_json::to_vec_pretty(&manifest)?)
+        .with_context(|_| format!("Could not write {}", path.display()))?;
+    Ok(())
+}
+
+/// Reads a plan written by `write_upload_plan`, verifying that the files
+/// have not changed since.
+pub fn read_upload_plan(dir: &Path) -> Result<UploadPlan, Error> {
+    let path = dir.join("manifest.json");
+    let manifest =
+        fs::read(&path).with_context(|_| format!("Could not read {}", path.display()))?;
+    let manifest: UploadPlanManifest = serde_json::from_slice(&manifest)
+        .with_context(|_| format!("Invalid upload plan {}", path.display()))?;
+    if manifest.version != 1 {
+        bail!("Unsupported upload plan version {}", manifest.version);
+    }
+
+    let context = UploadContext {
+        org: "",
+        project: None,
+        release: &manifest.release,
+        dist: manifest.dist.as_deref(),
+        wait: false,
+    };
+    let mut files = ReleaseFiles::new();
+    for planned in &manifest.files {
+        let contents_path = dir.join("files").join(&planned.sha256);
+        let contents = fs::read(&contents_path)
+            .with_context(|_| format!("Could not read {}", contents_path.display()))?;
+        if ArtifactChecksum::new(&context, &planned.url, &contents).sha256 != planned.sha256 {
+            bail!(
+                "Contents of {} do not match the checksum in the upload plan",
+                planned.url
+            );
+        }
+        files.insert(
+            planned.url.clone(),
+            ReleaseFile {
+                url: planned.url.clone(),
+                path: planned.path.clone(),
+                contents,
+                ty: planned.ty,
+                headers: planned.headers.clone(),
+                messages: vec![],
+            },
+        );
+    }
+
+    Ok(UploadPlan {
+        release: manifest.release,
+        dist: manifest.dist,
+        files,
+    })
+}
+
+/// An uploaded artifact as recorded in a checksum manifest.
+#[derive(Clone, Debug, Serialize)]
+pub struct ArtifactChecksum {
+    pub name: String,
+    pub size: usize,
+    pub sha256: String,
+    pub release: String,
+    pub dist: Option<String>,
+}
+
+impl ArtifactChecksum {
+    pub fn new(context: &UploadContext<'_>, name: &str, contents: &[u8]) -> ArtifactChecksum {
+        ArtifactChecksum {
+            name: name.to_string(),
+            size: contents.len(),
+            sha256: sha256_hex(contents),
+            release: context.release.to_string(),
+            dist: context.dist.map(str::to_string),
+        }
+    }
+}
+
+/// Returns the checksums of the files as uploaded to the context, sorted by
+/// name.
+pub fn checksums_for_files(
+    context: &UploadContext<'_>,
+    files: &ReleaseFiles,
+) -> Vec<ArtifactChecksum> {
+    let mut rv: Vec<_> = files
+        .values()
+        .map(|file| ArtifactChecksum::new(context, &file.url, &file.contents))
+        .collect();
+    rv.sort_by(|a, b| a.name.cmp(&b.name));
+    rv
+}
+
+/// Writes the checksums of uploaded artifacts to a JSON manifest, so that a
+/// pipeline can sign it and later verify what Sentry serves.
+pub fn write_checksums_manifest(path: &Path, checksums: &[ArtifactChecksum]) -> Result<(), Error> {
+    let manifest = json!({
+        "version": 1,
+        "artifacts": checksums,
+    });
+    fs::write(path, serde_json::to_vec_pretty(&manifest)?)
+        .with_context(|_| format!("Could not write {}", path.display()))?;
+    Ok(())
+}
+
 pub struct ReleaseFileUpload<'a> {
     context: &'a UploadContext<'a>,
     files: ReleaseFiles,
+    dists: Vec<&'a str>,
+    atomic: bool,
 }
 
 impl<'a> ReleaseFileUpload<'a> {
@@ -84,9 +347,52 @@ impl<'a> ReleaseFileUpload<'a> {
         ReleaseFileUpload {
             context,
             files: HashMap::new(),
+            dists: vec![],
+            atomic: false,
         }
     }
 
+    /// Fails instead of uploading file by file if the server cannot make the
+    /// files visible all at once, and waits until it has processed them.
+    pub fn atomic(&mut self, atomic: bool) -> &mut Self {
+        self.atomic = atomic;
+        self
+    }
+
+    /// Uploads the files to each of the dists instead of the dist of the
+    /// context.  If the server supports it, the files are sent only once.
+    pub fn dists(&mut self, dists: &[&'a str]) -> &mut Self {
+        self.dists = dists.to_vec();
+        self
+    }
+
+    /// The checksums of the files for every dist they are uploaded to.
+    pub fn checksums(&self) -> Vec<ArtifactChecksum> {
+        self.contexts()
+            .iter()
+            .flat_map(|context| checksums_for_files(context, &self.files))
+            .collect()
+    }
+
+    /// The contexts of all dists the files are uploaded to.
+    fn contexts(&self) -> Vec<UploadContext<'a>> {
+        if self.dists.is_empty() {
+            return vec![UploadContext {
+                dist: self.context.dist,
+                wait: self.context.wait || self.atomic,
+                ..*self.context
+            }];
+        }
+        self.dists
+            .iter()
+            .map(|&dist| UploadContext {
+                dist: Some(dist),
+                wait: self.context.wait || self.atomic,
+                ..*self.context
+            })
+            .collect()
+    }
+
     pub fn files(&mut self, files: &ReleaseFiles) -> &mut Self {
         for (k, v) in files {
             self.files.insert(k.to_owned(), v.to_owned());
@@ -95,12 +401,65 @@ impl<'a> ReleaseFileUpload<'a> {
     }
 
     pub fn upload(&self) -> Result<(), Error> {
+        {
+            let _step = step(format!(
+                "Uploading release files for {}",
+                self.context.release
+            ));
+            self.upload_files()?;
+        }
+        for context in self.contexts() {
+            machine::push_result(
+                "uploads",
+                json!({
+                    "release": context.release,
+                    "dist": context.dist,
+                    "files": self.files.len(),
+                }),
+            );
+        }
+        add_summary(&format!(
+            "- Uploaded {} files to release {}",
+            self.files.len(),
+            release_link(self.context.org, self.context.release)
+        ));
+        Ok(())
+    }
+
+    fn upload_files(&self) -> Result<(), Error> {
         let api = Api::current();
 
         let chunk_options = api.get_chunk_upload_options(self.context.org)?;
+        // only assembled artifact bundles become visible all at once
+        if self.atomic
+            && !chunk_options
+                .as_ref()
+                .is_some_and(|o| o.supports(ChunkUploadCapability::ReleaseFiles))
+        {
+            bail!(
+                "The server does not support atomic uploads, files can only be \
+                 uploaded one by one. Upload without --atomic instead."
+            );
+        }
         if let Some(ref chunk_options) = chunk_options {
+            if self.dists.len() > 1
+                && chunk_options.supports(ChunkUploadCapability::ArtifactBundles)
+            {
+                return upload_files_for_dists(
+                    &UploadContext {
+                        wait: self.context.wait || self.atomic,
+                        ..*self.context
+                    },
+                    &self.files,
+                    &self.dists,
+                    chunk_options,
+                );
+            }
             if chunk_options.supports(ChunkUploadCapability::ReleaseFiles) {
-                return upload_files_chunked(self.context, &self.files, chunk_options);
+                for context in self.contexts() {
+                    upload_files_chunked(&context, &self.files, chunk_options)?;
+                }
+                return Ok(());
             }
         }
 
@@ -115,7 +474,10 @@ impl<'a> ReleaseFileUpload<'a> {
         }
 
         let concurrency = chunk_options.map_or(DEFAULT_CONCURRENCY, |o| usize::from(o.concurrency));
-        upload_files_parallel(self.context, &self.files, concurrency)
+        for context in self.contexts() {
+            upload_files_parallel(&context, &self.files, concurrency)?;
+        }
+        Ok(())
     }
 }
 
@@ -200,13 +562,12 @@ fn upload_files_parallel(
     Ok(())
 }
 
-fn upload_files_chunked(
-    context: &UploadContext,
-    files: &ReleaseFiles,
+/// Uploads the chunks of an artifact bundle and returns the checksums of the
+/// bundle and its chunks for assembling.
+fn upload_artifact_bundle(
+    archive: &TempFile,
     options: &ChunkUploadOptions,
-) -> Result<(), Error> {
-    let archive = build_artifact_bundle(context, files)?;
-
+) -> Result<(Digest, Vec<Digest>), Error> {
     let progress_style =
         ProgressStyle::default_spinner().template("{spinner} Optimizing bundle for upload...");
 
@@ -233,6 +594,19 @@ fn upload_files_chunked(
     upload_chunks(&chunks, options, progress_style)?;
     println!("{} Uploaded release files to Sentry", style(">").dim(),);
 
+    Ok((checksum, checksums))
+}
+
+/// Requests assembling of uploaded chunks and polls until the server has
+/// processed them, if the context asks to wait.
+fn assemble_artifact_bundle<F>(
+    context: &UploadContext,
+    options: &ChunkUploadOptions,
+    assemble: F,
+) -> Result<(), Error>
+where
+    F: Fn() -> Result<AssembleArtifactsResponse, Error>,
+{
     let progress_style = ProgressStyle::default_spinner().template("{spinner} Processing files...");
 
     let progress = ProgressBar::new_spinner();
@@ -245,10 +619,8 @@ fn upload_files_chunked(
         secs => Duration::from_secs(secs),
     };
 
-    let api = Api::current();
     let response = loop {
-        let response =
-            api.assemble_artifacts(context.org, context.release, checksum, &checksums)?;
+        let response = assemble()?;
 
         // Poll until there is a response, unless the user has specified to skip polling. In
         // that case, we return the potentially partial response from the server. This might
@@ -289,6 +661,59 @@ fn upload_files_chunked(
     Ok(())
 }
 
+fn upload_files_chunked(
+    context: &UploadContext,
+    files: &ReleaseFiles,
+    options: &ChunkUploadOptions,
+) -> Result<(), Error> {
+    let archive = build_artifact_bundle(context, files)?;
+    let (checksum, checksums) = upload_artifact_bundle(&archive, options)?;
+
+    let api = Api::current();
+    assemble_artifact_bundle(context, options, || {
+        Ok(api.assemble_artifacts(context.org, context.release, checksum, &checksums)?)
+    })
+}
+
+/// Uploads one artifact bundle without a dist and assembles it once for
+/// every dist, so identical files are not sent for each of them.
+fn upload_files_for_dists(
+    context: &UploadContext,
+    files: &ReleaseFiles,
+    dists: &[&str],
+    options: &ChunkUploadOptions,
+) -> Result<(), Error> {
+    let archive = build_artifact_bundle(
+        &UploadContext {
+            dist: None,
+            ..*context
+        },
+        files,
+    )?;
+    let (checksum, checksums) = upload_artifact_bundle(&archive, options)?;
+
+    let api = Api::current();
+    let projects: Vec<_> = context.project.into_iter().collect();
+    for &dist in dists {
+        let context = UploadContext {
+            dist: Some(dist),
+            ..*context
+        };
+        assemble_artifact_bundle(&context, options, || {
+            let request = ChunkedArtifactBundleRequest {
+                checksum,
+                chunks: &checksums,
+                projects: &projects,
+                version: context.release,
+                dist: context.dist,
+            };
+            Ok(api.assemble_artifact_bundle(context.org, &request)?)
+        })?;
+    }
+
+    Ok(())
+}
+
 fn build_artifact_bundle(context: &UploadContext, files: &ReleaseFiles) -> Result<TempFile, Error> {
     let progress_style = ProgressStyle::default_bar().template(
         "{prefix:.dim} Bundling files for upload... {msg:.dim}\
@@ -0,0 +1,171 @@
+//! Restricts cryptography to FIPS validated modules.
+//!
+//! In FIPS mode TLS and hashing have to go through a FIPS 140 validated
+//! module, which is OpenSSL running in FIPS mode.  Requests to Sentry are
+//! refused while it is not, TLS is limited to version 1.2 and up with
+//! approved cipher suites, and certificate verification cannot be turned
+//! off.  FIPS mode is only supported on Linux, elsewhere hashing does not go
+//! through a validated module and all requests are refused.
+//!
+//! The mode is enabled with `--fips` or the `fips.enabled` setting, builds
+//! with the `fips` feature always run in it.  OpenSSL is bundled by default
+//! and not validated, FIPS builds link the system OpenSSL by building with
+//! `OPENSSL_NO_VENDOR=1`.
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use failure::{bail, Error};
+use lazy_static::lazy_static;
+
+use crate::utils::tls::{self, TlsBackend};
+
+/// The cipher suites OpenSSL may negotiate for TLS 1.2 in FIPS mode.
+const OPENSSL_CIPHERS: &str = "ECDHE-ECDSA-AES128-GCM-SHA256:ECDHE-RSA-AES128-GCM-SHA256:\
+                               ECDHE-ECDSA-AES256-GCM-SHA384:ECDHE-RSA-AES256-GCM-SHA384";
+
+static ENABLED: AtomicBool = AtomicBool::new(cfg!(feature = "fips"));
+
+/// Turns on FIPS mode for the rest of the process.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Returns whether FIPS mode is enabled.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// A cryptographic module and whether it is FIPS validated.
+#[derive(Debug, Clone)]
+pub struct Module {
+    pub name: String,
+    /// Why the module is not validated, `None` if it is.
+    pub violation: Option<&'static str>,
+}
+
+impl Module {
+    pub fn is_validated(&self) -> bool {
+        self.violation.is_none()
+    }
+}
+
+/// Asks OpenSSL whether it runs in FIPS mode.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn openssl_fips_enabled() -> bool {
+    openssl_sys::init();
+    #[cfg(ossl300)]
+    {
+        unsafe { openssl_sys::EVP_default_properties_is_fips_enabled(std::ptr::null_mut()) == 1 }
+    }
+    #[cfg(all(ossl110, not(ossl300)))]
+    {
+        unsafe { openssl_sys::FIPS_mode() == 1 }
+    }
+    #[cfg(not(ossl110))]
+    {
+        false
+    }
+}
+
+/// Returns the OpenSSL libcurl and the hashing functions are linked against.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn openssl_module() -> Module {
+    lazy_static! {
+        // OpenSSL cannot leave or enter FIPS mode on its own once loaded
+        static ref MODULE: Module = Module {
+            name: unsafe {
+                std::ffi::CStr::from_ptr(openssl_sys::OpenSSL_version(openssl_sys::OPENSSL_VERSION))
+            }
+            .to_string_lossy()
+            .into_owned(),
+            violation: if openssl_fips_enabled() {
+                None
+            } else {
+                Some("OpenSSL is not running in FIPS mode")
+            },
+        };
+    }
+    MODULE.clone()
+}
+
+/// Returns the module libcurl uses for TLS.
+pub fn tls_module() -> Module {
+    let violation = if tls::selected_backend() == TlsBackend::Rustls {
+        Some("rustls is not FIPS validated")
+    } else if cfg!(windows) {
+        // Schannel uses the validated system module
+        None
+    } else {
+        #[cfg(all(unix, not(target_os = "macos")))]
+        {
+            openssl_module().violation
+        }
+        #[cfg(not(all(unix, not(target_os = "macos"))))]
+        {
+            Some("no FIPS validated TLS library is available on this platform")
+        }
+    };
+    Module {
+        name: tls::describe_backend(),
+        violation,
+    }
+}
+
+/// Returns the module that computes hashes and signatures in FIPS mode.
+pub fn hashing_module() -> Module {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        openssl_module()
+    }
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    {
+        Module {
+            name: "sha1 and sha2 crates".into(),
+            violation: Some("FIPS mode is only supported on Linux"),
+        }
+    }
+}
+
+/// Returns the cipher suites to restrict TLS to, if any.
+pub fn tls_cipher_list() -> Option<&'static str> {
+    if is_enabled() && cfg!(all(unix, not(target_os = "macos"))) {
+        Some(OPENSSL_CIPHERS)
+    } else {
+        None
+    }
+}
+
+/// Fails in FIPS mode if a request would not be FIPS compliant.
+pub fn check_request(verify_ssl: bool) -> Result<(), Error> {
+    if !is_enabled() {
+        return Ok(());
+    }
+    if !verify_ssl {
+        bail!("TLS certificate verification cannot be disabled in FIPS mode");
+    }
+    if let Some(violation) = tls_module().violation {
+        bail!("TLS is not FIPS compliant: {}", violation);
+    }
+    if let Some(violation) = hashing_module().violation {
+        bail!("Hashing is not FIPS compliant: {}", violation);
+    }
+    Ok(())
+}
+
+/// Fails in FIPS mode for operations without a validated implementation.
+pub fn ensure_allowed(operation: &str) -> Result<(), Error> {
+    if is_enabled() {
+        bail!("{} is not available in FIPS mode", operation);
+    }
+    Ok(())
+}
+
+#[test]
+#[cfg(all(unix, not(target_os = "macos")))]
+fn test_openssl_module() {
+    let module = hashing_module();
+    assert!(module.name.starts_with("OpenSSL "));
+    assert_eq!(module.is_validated(), openssl_fips_enabled());
+    if !is_enabled() {
+        assert!(check_request(false).is_ok());
+    }
+}
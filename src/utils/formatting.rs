@@ -24,6 +24,21 @@ impl<'a> fmt::Display for HumanDuration {
     }
 }
 
+/// Helper for rendering a series of values as a sparkline.
+pub struct Sparkline<'a>(pub &'a [u64]);
+
+impl<'a> fmt::Display for Sparkline<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+        let max = self.0.iter().copied().max().unwrap_or(0).max(1);
+        for &value in self.0 {
+            let idx = (value * (BARS.len() as u64 - 1)).div_ceil(max);
+            write!(f, "{}", BARS[idx as usize])?;
+        }
+        Ok(())
+    }
+}
+
 pub struct Table {
     title_row: Option<TableRow>,
     rows: Vec<TableRow>,
@@ -4,10 +4,14 @@ use std::io;
 use std::io::{Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
 
-use failure::{bail, Error};
-use sha1::{Digest, Sha1};
+use failure::{bail, format_err, Error};
+use log::debug;
+use sha1::Digest;
 use uuid::Uuid;
 
+use crate::utils::cancel;
+use crate::utils::digest::Sha1;
+
 pub trait SeekRead: Seek + Read {}
 impl<T: Seek + Read> SeekRead for T {}
 
@@ -23,6 +27,7 @@ impl TempDir {
         let mut path = env::temp_dir();
         path.push(Uuid::new_v4().to_hyphenated_ref().to_string());
         fs::create_dir(&path)?;
+        cancel::register_temp_path(&path);
         Ok(TempDir { path })
     }
 
@@ -35,6 +40,7 @@ impl TempDir {
 impl Drop for TempDir {
     fn drop(&mut self) {
         let _ = fs::remove_dir_all(&self.path);
+        cancel::unregister_temp_path(&self.path);
     }
 }
 
@@ -51,6 +57,7 @@ impl TempFile {
         path.push(Uuid::new_v4().to_hyphenated_ref().to_string());
 
         let tf = TempFile { path };
+        cancel::register_temp_path(&tf.path);
         tf.open()?;
         Ok(tf)
     }
@@ -61,6 +68,7 @@ impl TempFile {
         destination.push(Uuid::new_v4().to_hyphenated_ref().to_string());
 
         fs::rename(&path, &destination)?;
+        cancel::register_temp_path(&destination);
         Ok(TempFile { path: destination })
     }
 
@@ -90,6 +98,82 @@ impl TempFile {
 impl Drop for TempFile {
     fn drop(&mut self) {
         fs::remove_file(&self.path).ok();
+        cancel::unregister_temp_path(&self.path);
+    }
+}
+
+/// Extracts a ZIP archive into the given directory.
+///
+/// Entries with paths outside of the directory are skipped.
+pub fn extract_zip<P: AsRef<Path>>(path: P, dst: &Path) -> Result<(), Error> {
+    let path = path.as_ref();
+    let file = fs::File::open(path)?;
+    let mut archive = zip::ZipArchive::new(io::BufReader::new(file))
+        .map_err(|e| format_err!("Could not extract {}: {}", path.display(), e))?;
+
+    for index in 0..archive.len() {
+        let mut entry = archive
+            .by_index(index)
+            .map_err(|e| format_err!("Could not extract {}: {}", path.display(), e))?;
+        let target = match entry.enclosed_name() {
+            Some(name) => dst.join(name),
+            None => {
+                debug!("skipping unsafe zip entry {}", entry.name());
+                continue;
+            }
+        };
+
+        if entry.is_dir() {
+            fs::create_dir_all(&target)?;
+        } else {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            io::copy(&mut entry, &mut fs::File::create(&target)?)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Removes the `\\?\` prefix of verbatim paths on Windows, e.g. from
+/// `canonicalize` or CI workspaces, so that globs, ignore rules and file
+/// URLs work on them.
+///
+/// Long paths keep working without the prefix, the standard library adds
+/// it again where a path exceeds the 260 character limit of Windows.
+pub fn normalize_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let path = path.as_ref();
+    if cfg!(windows) {
+        if let Some(path) = path.to_str().and_then(strip_verbatim_prefix) {
+            return PathBuf::from(path);
+        }
+    }
+    path.to_path_buf()
+}
+
+/// Turns `\\?\C:\dir` into `C:\dir` and `\\?\UNC\server\share` into
+/// `\\server\share`.  Other verbatim paths, e.g. of volumes, are kept.
+fn strip_verbatim_prefix(path: &str) -> Option<String> {
+    if let Some(rest) = path.strip_prefix(r"\\?\UNC\") {
+        return Some(format!(r"\\{}", rest));
+    }
+    let rest = path.strip_prefix(r"\\?\")?;
+    let mut chars = rest.chars();
+    match (chars.next(), chars.next()) {
+        (Some(drive), Some(':')) if drive.is_ascii_alphabetic() => Some(rest.to_string()),
+        _ => None,
+    }
+}
+
+/// Formats a relative path as part of a URL, with forward slashes on all
+/// platforms.
+pub fn path_as_url<P: AsRef<Path>>(path: P) -> String {
+    let path = normalize_path(path).display().to_string();
+    if cfg!(windows) {
+        path.replace('\\', "/")
+    } else {
+        path
     }
 }
 
@@ -156,3 +240,39 @@ pub fn get_sha1_checksums(data: &[u8], chunk_size: u64) -> Result<(Digest, Vec<D
 
     Ok((total_sha.digest(), chunks))
 }
+
+#[test]
+fn test_strip_verbatim_prefix() {
+    assert_eq!(
+        strip_verbatim_prefix(r"\\?\C:\ci\dist\app.js").as_deref(),
+        Some(r"C:\ci\dist\app.js")
+    );
+    assert_eq!(
+        strip_verbatim_prefix(r"\\?\UNC\build\share\dist").as_deref(),
+        Some(r"\\build\share\dist")
+    );
+    assert_eq!(strip_verbatim_prefix(r"\\?\Volume{1234}\dist"), None);
+    assert_eq!(strip_verbatim_prefix(r"C:\ci\dist"), None);
+}
+
+#[test]
+fn test_extract_zip_skips_unsafe_entries() {
+    use std::io::Write;
+
+    let dir = tempfile::tempdir().unwrap();
+    let archive = dir.path().join("archive.zip");
+    let mut zip = zip::ZipWriter::new(fs::File::create(&archive).unwrap());
+    for name in &["../outside.txt", "/absolute.txt", "inside/file.txt"] {
+        zip.start_file(*name, zip::write::FileOptions::default())
+            .unwrap();
+        zip.write_all(b"data").unwrap();
+    }
+    zip.finish().unwrap();
+
+    let dst = dir.path().join("dst");
+    extract_zip(&archive, &dst).unwrap();
+
+    assert_eq!(fs::read(dst.join("inside/file.txt")).unwrap(), b"data");
+    assert!(!dir.path().join("outside.txt").exists());
+    assert!(!dst.join("absolute.txt").exists());
+}
@@ -0,0 +1,64 @@
+//! Writes reports about releases that GitLab CI picks up as job artifacts.
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use failure::{Error, ResultExt};
+use serde_json::json;
+
+use crate::api::ReleaseInfo;
+use crate::utils::annotations::release_url;
+
+fn ci_var(key: &str) -> Option<String> {
+    env::var(key).ok().filter(|value| !value.is_empty())
+}
+
+/// Renders a dotenv report for `artifacts:reports:dotenv`.
+///
+/// GitLab exposes these variables to later jobs of the pipeline and to
+/// `release:` keywords.
+pub fn format_dotenv(org: &str, release: &ReleaseInfo) -> String {
+    let mut rv = format!("SENTRY_ORG={}\nSENTRY_RELEASE={}\n", org, release.version);
+    if let Some(url) = release_url(org, &release.version) {
+        rv.push_str(&format!("SENTRY_RELEASE_URL={}\n", url));
+    }
+    rv
+}
+
+/// Renders a JSON document describing the release for GitLab's release
+/// evidence.
+pub fn format_evidence(org: &str, release: &ReleaseInfo) -> serde_json::Value {
+    json!({
+        "release": {
+            "tag_name": ci_var("CI_COMMIT_TAG").unwrap_or_else(|| release.version.clone()),
+            "name": release.version,
+            "created_at": release.date_created,
+            "released_at": release.date_released,
+            "sentry": {
+                "organization": org,
+                "version": release.version,
+                "url": release_url(org, &release.version),
+                "projects": release.projects.iter().map(|p| &p.slug).collect::<Vec<_>>(),
+                "last_commit": release.last_commit.as_ref().map(|c| &c.id),
+            },
+            "pipeline": {
+                "url": ci_var("CI_PIPELINE_URL"),
+                "job_url": ci_var("CI_JOB_URL"),
+                "commit_sha": ci_var("CI_COMMIT_SHA"),
+            },
+        }
+    })
+}
+
+pub fn write_dotenv(path: &Path, org: &str, release: &ReleaseInfo) -> Result<(), Error> {
+    fs::write(path, format_dotenv(org, release))
+        .with_context(|_| format!("Could not write dotenv report {}", path.display()))?;
+    Ok(())
+}
+
+pub fn write_evidence(path: &Path, org: &str, release: &ReleaseInfo) -> Result<(), Error> {
+    let evidence = serde_json::to_vec_pretty(&format_evidence(org, release))?;
+    fs::write(path, evidence)
+        .with_context(|_| format!("Could not write release evidence {}", path.display()))?;
+    Ok(())
+}
@@ -0,0 +1,265 @@
+//! Parses the lines of log files into structured records.
+//!
+//! Every line is parsed on its own, either as a JSON object, as logfmt
+//! `key=value` pairs or with a regular expression whose named groups become
+//! the fields of the record.  The `level`, `message` and `timestamp` fields
+//! are picked out under their common names, everything else is kept as
+//! attributes.
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, TimeZone, Utc};
+use failure::{bail, Error};
+use regex::Regex;
+use serde_json::{Map, Value};
+
+use crate::utils::args::get_timestamp;
+
+const LEVEL_FIELDS: &[&str] = &["level", "severity", "lvl", "loglevel"];
+const MESSAGE_FIELDS: &[&str] = &["message", "msg", "body", "text"];
+const TIMESTAMP_FIELDS: &[&str] = &["timestamp", "time", "ts", "@timestamp", "date"];
+
+/// The severity of a log record, ordered from least to most severe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl FromStr for LogLevel {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<LogLevel, Error> {
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "trace" | "verbose" => LogLevel::Trace,
+            "debug" | "dbg" => LogLevel::Debug,
+            "info" | "information" | "notice" => LogLevel::Info,
+            "warn" | "warning" => LogLevel::Warn,
+            "error" | "err" => LogLevel::Error,
+            "fatal" | "critical" | "crit" | "alert" | "emerg" | "panic" => LogLevel::Fatal,
+            _ => bail!("Unknown log level: {}", s),
+        })
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                LogLevel::Trace => "trace",
+                LogLevel::Debug => "debug",
+                LogLevel::Info => "info",
+                LogLevel::Warn => "warn",
+                LogLevel::Error => "error",
+                LogLevel::Fatal => "fatal",
+            }
+        )
+    }
+}
+
+/// A parsed log line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogRecord {
+    pub timestamp: Option<DateTime<Utc>>,
+    pub level: LogLevel,
+    pub message: String,
+    pub attributes: BTreeMap<String, Value>,
+}
+
+/// How log lines are parsed.
+#[derive(Debug)]
+pub enum LogParser {
+    Json,
+    Logfmt,
+    Regex(Regex),
+}
+
+impl FromStr for LogParser {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<LogParser, Error> {
+        Ok(match s {
+            "json" => LogParser::Json,
+            "logfmt" => LogParser::Logfmt,
+            _ => match s.strip_prefix("regex:") {
+                Some(pattern) => LogParser::Regex(Regex::new(pattern)?),
+                None => bail!(
+                    "Unknown log parser {}, expected json, logfmt or regex:PATTERN",
+                    s
+                ),
+            },
+        })
+    }
+}
+
+/// Splits a logfmt line into its pairs.  Keys without a value are `true`.
+fn parse_logfmt(line: &str) -> Option<Map<String, Value>> {
+    let mut rv = Map::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+        if chars.peek().is_none() {
+            break;
+        }
+
+        let mut key = String::new();
+        while let Some(&c) = chars.peek() {
+            if c == '=' || c.is_whitespace() {
+                break;
+            }
+            key.push(c);
+            chars.next();
+        }
+        if key.is_empty() {
+            return None;
+        }
+        if chars.peek() != Some(&'=') {
+            rv.insert(key, Value::Bool(true));
+            continue;
+        }
+        chars.next();
+
+        let mut value = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next()? {
+                    '"' => break,
+                    '\\' => value.push(match chars.next()? {
+                        'n' => '\n',
+                        't' => '\t',
+                        c => c,
+                    }),
+                    c => value.push(c),
+                }
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                value.push(c);
+                chars.next();
+            }
+        }
+        rv.insert(key, Value::String(value));
+    }
+    if rv.is_empty() {
+        None
+    } else {
+        Some(rv)
+    }
+}
+
+fn parse_record_timestamp(value: &Value) -> Option<DateTime<Utc>> {
+    match value {
+        // anything past 2286 in seconds is taken to be milliseconds
+        Value::Number(number) => match number.as_i64() {
+            Some(millis) if millis > 10_000_000_000 => Some(Utc.timestamp_millis(millis)),
+            Some(seconds) => Some(Utc.timestamp(seconds, 0)),
+            None => {
+                let seconds = number.as_f64()?;
+                Some(Utc.timestamp(seconds as i64, (seconds.fract() * 1e9) as u32))
+            }
+        },
+        Value::String(string) => get_timestamp(string).ok(),
+        _ => None,
+    }
+}
+
+fn take_field(fields: &mut Map<String, Value>, names: &[&str]) -> Option<Value> {
+    names.iter().find_map(|name| fields.remove(*name))
+}
+
+fn into_record(mut fields: Map<String, Value>) -> LogRecord {
+    let level = take_field(&mut fields, LEVEL_FIELDS)
+        .and_then(|level| level.as_str()?.parse().ok())
+        .unwrap_or(LogLevel::Info);
+    let timestamp = take_field(&mut fields, TIMESTAMP_FIELDS)
+        .as_ref()
+        .and_then(parse_record_timestamp);
+    let message = match take_field(&mut fields, MESSAGE_FIELDS) {
+        Some(Value::String(message)) => message,
+        Some(other) => other.to_string(),
+        None => String::new(),
+    };
+    LogRecord {
+        timestamp,
+        level,
+        message,
+        attributes: fields.into_iter().collect(),
+    }
+}
+
+impl LogParser {
+    /// Parses a line, returning `None` if it does not match.
+    pub fn parse(&self, line: &str) -> Option<LogRecord> {
+        let fields = match *self {
+            LogParser::Json => match serde_json::from_str(line) {
+                Ok(Value::Object(fields)) => fields,
+                _ => return None,
+            },
+            LogParser::Logfmt => parse_logfmt(line)?,
+            LogParser::Regex(ref re) => {
+                let caps = re.captures(line)?;
+                let mut fields: Map<_, _> = re
+                    .capture_names()
+                    .flatten()
+                    .filter_map(|name| Some((name.to_string(), caps.name(name)?.as_str().into())))
+                    .collect();
+                // without a message group the whole line is the message
+                if !MESSAGE_FIELDS.iter().any(|name| fields.contains_key(*name)) {
+                    fields.insert("message".into(), line.into());
+                }
+                fields
+            }
+        };
+        Some(into_record(fields))
+    }
+}
+
+#[test]
+fn test_parse_json() {
+    let record = LogParser::Json
+        .parse(r#"{"level":"WARNING","msg":"disk almost full","ts":1600000000123,"free":512}"#)
+        .unwrap();
+    assert_eq!(record.level, LogLevel::Warn);
+    assert_eq!(record.message, "disk almost full");
+    assert_eq!(
+        record.timestamp.unwrap().timestamp_millis(),
+        1_600_000_000_123
+    );
+    assert_eq!(record.attributes["free"], 512);
+    assert_eq!(LogParser::Json.parse("not json"), None);
+}
+
+#[test]
+fn test_parse_logfmt() {
+    let record = LogParser::Logfmt
+        .parse(r#"time=2020-09-13T12:26:40Z level=error msg="payment \"failed\"" retry"#)
+        .unwrap();
+    assert_eq!(record.level, LogLevel::Error);
+    assert_eq!(record.message, r#"payment "failed""#);
+    assert_eq!(record.timestamp.unwrap().timestamp(), 1_600_000_000);
+    assert_eq!(record.attributes["retry"], true);
+    assert_eq!(LogParser::Logfmt.parse(r#"msg="unterminated"#), None);
+}
+
+#[test]
+fn test_parse_regex() {
+    let parser: LogParser = r"regex:^\[(?P<level>\w+)\] (?P<user>\w+):".parse().unwrap();
+    let record = parser.parse("[crit] jane: login failed").unwrap();
+    assert_eq!(record.level, LogLevel::Fatal);
+    assert_eq!(record.message, "[crit] jane: login failed");
+    assert_eq!(record.attributes["user"], "jane");
+    assert_eq!(parser.parse("unrelated line"), None);
+}
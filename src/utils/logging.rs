@@ -10,6 +10,10 @@ use indicatif::ProgressBar;
 use lazy_static::lazy_static;
 use parking_lot::RwLock;
 
+use crate::utils::annotations::{annotate, AnnotationLevel};
+use crate::utils::crash_report;
+use crate::utils::machine;
+
 lazy_static! {
     static ref PROGRESS_BAR: RwLock<Option<Weak<ProgressBar>>> = RwLock::new(None);
     static ref MAX_LEVEL: AtomicUsize =
@@ -42,15 +46,24 @@ impl Logger {
 
 impl log::Log for Logger {
     fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
-        self.get_actual_level(metadata) <= max_level()
+        // debug records are always kept for crash reports
+        self.get_actual_level(metadata) <= max_level().max(log::LevelFilter::Debug)
     }
 
     fn log(&self, record: &log::Record<'_>) {
-        if !self.enabled(record.metadata()) {
+        let level = self.get_actual_level(record.metadata());
+        if level <= log::Level::Debug {
+            crash_report::record_log(format!(
+                "{} {:<5} {}",
+                Local::now().format("%H:%M:%S%.3f"),
+                level,
+                record.args()
+            ));
+        }
+        if level > max_level() {
             return;
         }
 
-        let level = self.get_actual_level(record.metadata());
         let (level_name, level_color) = match level {
             log::Level::Error => ("ERROR", Color::Red),
             log::Level::Warn => ("WARN ", Color::Red),
@@ -77,6 +90,20 @@ impl log::Log for Logger {
         } else {
             writeln!(io::stderr(), "{}", msg).ok();
         }
+
+        if short_target == "sentry_cli" {
+            match level {
+                log::Level::Error => {
+                    annotate(AnnotationLevel::Error, &record.args().to_string(), None);
+                    machine::error(&record.args().to_string(), &[]);
+                }
+                log::Level::Warn => {
+                    annotate(AnnotationLevel::Warning, &record.args().to_string(), None);
+                    machine::warning(&record.args().to_string());
+                }
+                _ => {}
+            }
+        }
     }
 
     fn flush(&self) {}
@@ -0,0 +1,126 @@
+//! Implements the machine readable output of `--machine-readable`.
+//!
+//! Every event is a JSON object on a line of its own with a `type` of
+//! `progress`, `warning`, `error` or `result`.  The final `result` event
+//! carries the exit code and whatever the command recorded with
+//! `set_result`.  While the protocol is enabled the regular output of
+//! commands goes to stderr so stdout only contains events.
+use std::fs::File;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use lazy_static::lazy_static;
+use log::debug;
+use parking_lot::Mutex;
+use serde_json::{json, Map, Value};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref OUTPUT: Mutex<Option<File>> = Mutex::new(None);
+    static ref RESULT: Mutex<Map<String, Value>> = Mutex::new(Map::new());
+}
+
+/// Moves the regular output to stderr and returns a handle to the original
+/// stdout for the events.
+#[cfg(unix)]
+fn take_stdout() -> io::Result<File> {
+    use std::os::unix::io::FromRawFd;
+
+    io::stdout().flush()?;
+    let fd = unsafe { libc::dup(1) };
+    if fd == -1 || unsafe { libc::dup2(2, 1) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { File::from_raw_fd(fd) })
+}
+
+/// Without `dup2` the events share stdout with the regular output.
+#[cfg(windows)]
+fn take_stdout() -> io::Result<File> {
+    use std::os::windows::io::{AsRawHandle, FromRawHandle};
+
+    let handle = io::stdout().as_raw_handle();
+    let file = unsafe { File::from_raw_handle(handle) };
+    // the handle belongs to stdout and must never be closed
+    let rv = file.try_clone();
+    std::mem::forget(file);
+    rv
+}
+
+/// Enables the machine readable protocol.
+pub fn enable() -> io::Result<()> {
+    *OUTPUT.lock() = Some(take_stdout()?);
+    ENABLED.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+fn emit(event: Value) {
+    let mut output = OUTPUT.lock();
+    if let Some(ref mut file) = *output {
+        let rv = serde_json::to_writer(&mut *file, &event)
+            .map_err(io::Error::from)
+            .and_then(|_| writeln!(file));
+        if let Err(err) = rv {
+            debug!("Could not write machine readable event: {}", err);
+        }
+    }
+}
+
+/// Reports the progress of an operation.  `total` is zero when unknown.
+pub fn progress(message: Option<&str>, current: u64, total: u64) {
+    if is_enabled() {
+        emit(json!({
+            "type": "progress",
+            "message": message,
+            "current": current,
+            "total": total,
+        }));
+    }
+}
+
+pub fn warning(message: &str) {
+    if is_enabled() {
+        emit(json!({"type": "warning", "message": message}));
+    }
+}
+
+/// Reports an error along with the errors that caused it.
+pub fn error(message: &str, causes: &[String]) {
+    if is_enabled() {
+        emit(json!({"type": "error", "message": message, "causes": causes}));
+    }
+}
+
+/// Records a value for the result event.
+pub fn set_result<V: Into<Value>>(key: &str, value: V) {
+    if is_enabled() {
+        RESULT.lock().insert(key.into(), value.into());
+    }
+}
+
+/// Appends a value to a list of the result event.
+pub fn push_result<V: Into<Value>>(key: &str, value: V) {
+    if is_enabled() {
+        let mut result = RESULT.lock();
+        match result.entry(key).or_insert_with(|| Value::Array(vec![])) {
+            Value::Array(values) => values.push(value.into()),
+            other => *other = Value::Array(vec![value.into()]),
+        }
+    }
+}
+
+/// Emits the result event before sentry-cli exits.
+pub fn finish(exit_code: i32) {
+    if is_enabled() {
+        let mut result = std::mem::take(&mut *RESULT.lock());
+        result.insert("type".into(), "result".into());
+        result.insert("success".into(), (exit_code == 0).into());
+        result.insert("exit_code".into(), exit_code.into());
+        emit(Value::Object(result));
+    }
+}
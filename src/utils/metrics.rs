@@ -0,0 +1,179 @@
+//! Formats custom metrics in the statsd flavor of Sentry's metrics ingestion.
+//!
+//! A metric is one line of `name@unit:value|type|#tags|Ttimestamp` in a
+//! `statsd` envelope item.  Names, units and tags are normalized the same
+//! way the SDKs do before sending, so what shows up in Sentry does not
+//! depend on the client that sent it.
+use std::collections::BTreeMap;
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use failure::{bail, Error};
+
+/// The kind of a metric and how its values are aggregated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MetricType {
+    Counter,
+    Distribution,
+    Gauge,
+    Set,
+}
+
+impl MetricType {
+    fn as_statsd(self) -> &'static str {
+        match self {
+            MetricType::Counter => "c",
+            MetricType::Distribution => "d",
+            MetricType::Gauge => "g",
+            MetricType::Set => "s",
+        }
+    }
+}
+
+/// A single metric value to send.
+#[derive(Clone, Debug)]
+pub struct Metric {
+    pub ty: MetricType,
+    pub name: String,
+    pub unit: Option<String>,
+    pub value: String,
+    pub tags: BTreeMap<String, String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '-' | '.' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+fn normalize_unit(unit: &str) -> String {
+    unit.chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+        .collect()
+}
+
+fn normalize_tag_key(key: &str) -> String {
+    key.chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/'))
+        .collect()
+}
+
+fn escape_tag_value(value: &str) -> String {
+    let mut rv = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => rv.push_str(r"\\"),
+            '\n' => rv.push_str(r"\n"),
+            '\r' => rv.push_str(r"\r"),
+            '\t' => rv.push_str(r"\t"),
+            '|' => rv.push_str(r"\u{7c}"),
+            ',' => rv.push_str(r"\u{2c}"),
+            c => rv.push(c),
+        }
+    }
+    rv
+}
+
+/// Hashes a set member the way the SDKs do for values that are not integers.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+impl Metric {
+    /// Checks the value and converts it to the form sent for its type.
+    ///
+    /// Sets only count unique integers, so other members are hashed.
+    pub fn normalize_value(ty: MetricType, value: &str) -> Result<String, Error> {
+        if ty == MetricType::Set {
+            return Ok(match value.parse::<u32>() {
+                Ok(int) => int.to_string(),
+                Err(_) => crc32(value.as_bytes()).to_string(),
+            });
+        }
+        match value.parse::<f64>() {
+            Ok(float) if float.is_finite() => Ok(value.to_string()),
+            _ => bail!("Invalid metric value {}, expected a number", value),
+        }
+    }
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", normalize_name(&self.name))?;
+        if let Some(ref unit) = self.unit {
+            let unit = normalize_unit(unit);
+            if !unit.is_empty() {
+                write!(f, "@{}", unit)?;
+            }
+        }
+        write!(f, ":{}|{}", self.value, self.ty.as_statsd())?;
+
+        let mut tags = self
+            .tags
+            .iter()
+            .map(|(key, value)| (normalize_tag_key(key), value))
+            .filter(|(key, _)| !key.is_empty());
+        if let Some((key, value)) = tags.next() {
+            write!(f, "|#{}:{}", key, escape_tag_value(value))?;
+            for (key, value) in tags {
+                write!(f, ",{}:{}", key, escape_tag_value(value))?;
+            }
+        }
+
+        write!(f, "|T{}", self.timestamp.timestamp())
+    }
+}
+
+#[test]
+fn test_format_metric() {
+    use chrono::TimeZone;
+
+    let mut tags = BTreeMap::new();
+    tags.insert("env".to_string(), "prod".to_string());
+    tags.insert("host name!".to_string(), "a|b,c\n".to_string());
+    let metric = Metric {
+        ty: MetricType::Distribution,
+        name: "deploy duration".into(),
+        unit: Some("second".into()),
+        value: "4.5".into(),
+        tags,
+        timestamp: Utc.timestamp(1_600_000_000, 0),
+    };
+    assert_eq!(
+        metric.to_string(),
+        r"deploy_duration@second:4.5|d|#env:prod,hostname:a\u{7c}b\u{2c}c\n|T1600000000"
+    );
+}
+
+#[test]
+fn test_normalize_value() {
+    assert_eq!(
+        Metric::normalize_value(MetricType::Counter, "2").unwrap(),
+        "2"
+    );
+    assert!(Metric::normalize_value(MetricType::Gauge, "many").is_err());
+    assert_eq!(
+        Metric::normalize_value(MetricType::Set, "42").unwrap(),
+        "42"
+    );
+    assert_eq!(
+        Metric::normalize_value(MetricType::Set, "jane@example.com").unwrap(),
+        crc32(b"jane@example.com").to_string()
+    );
+    assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+}
@@ -1,28 +1,58 @@
 //! Various utility functionality.
 pub mod android;
+pub mod annotations;
 pub mod appcenter;
+pub mod appstore;
+pub mod archive;
 pub mod args;
+pub mod bazel;
+pub mod build_manifest;
+pub mod cancel;
+pub mod changelog;
 pub mod chunks;
+pub mod ci;
+pub mod codeowners;
 pub mod codepush;
 pub mod cordova;
+pub mod crash_report;
 pub mod dif;
 pub mod dif_upload;
+pub mod digest;
 pub mod enc;
+pub mod envelope;
 pub mod event;
 pub mod file_search;
 pub mod file_upload;
+pub mod fips;
 pub mod formatting;
 pub mod fs;
+pub mod gitlab;
 pub mod http;
+pub mod log_parser;
 pub mod logging;
+pub mod machine;
+pub mod metrics;
+pub mod profile;
 pub mod progress;
+pub mod proguard_mapping;
 pub mod releases;
+pub mod remote_source;
+pub mod resource_usage;
 pub mod retry;
+pub mod sbom;
+pub mod script_hook;
+pub mod size_report;
 pub mod sourcemaps;
+pub mod spool;
 pub mod system;
+pub mod tls;
 pub mod ui;
 pub mod update;
+pub mod uptime;
 pub mod vcs;
+pub mod watch;
+pub mod webhooks;
+pub mod windows_task;
 pub mod xcode;
 
 #[cfg(feature = "with_crash_reporting")]
@@ -0,0 +1,350 @@
+//! Converts the output of sampling profilers into Sentry's sample format.
+//!
+//! Two inputs are understood: collapsed stacks as written by `py-spy record
+//! --format raw` or the `stackcollapse` scripts, with one `a;b;c count` line
+//! per distinct stack, and the samples printed by `perf script`.  Collapsed
+//! stacks carry no timing, so their samples are spread out at a fixed
+//! interval.
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::io::BufRead;
+use std::str::FromStr;
+
+use failure::{bail, Error};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::{json, Value};
+
+lazy_static! {
+    static ref PY_SPY_FRAME_RE: Regex = Regex::new(r"^(.*?) \((.*?):(\d+)\)$").unwrap();
+    static ref PERF_HEADER_RE: Regex =
+        Regex::new(r"^(\S.*?)\s+(\d+)(?:/(\d+))?\s+(?:\[\d+\]\s+)?(\d+\.\d+):").unwrap();
+    static ref PERF_FRAME_RE: Regex =
+        Regex::new(r"^\s+([0-9a-fA-F]+)\s+(.+?)(?:\s+\((.*)\))?$").unwrap();
+    static ref SYMBOL_OFFSET_RE: Regex = Regex::new(r"\+0x[0-9a-fA-F]+$").unwrap();
+}
+
+/// The format of a profiler output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProfileFormat {
+    Collapsed,
+    Perf,
+}
+
+impl FromStr for ProfileFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<ProfileFormat, Error> {
+        Ok(match s {
+            "collapsed" => ProfileFormat::Collapsed,
+            "perf" => ProfileFormat::Perf,
+            _ => bail!("Unknown profile format: {}", s),
+        })
+    }
+}
+
+impl ProfileFormat {
+    /// Guesses the format from the contents, `perf script` indents frames.
+    pub fn detect(contents: &str) -> ProfileFormat {
+        if contents
+            .lines()
+            .any(|line| line.starts_with(|c: char| c.is_whitespace()) && !line.trim().is_empty())
+        {
+            ProfileFormat::Perf
+        } else {
+            ProfileFormat::Collapsed
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct ProfileFrame {
+    function: String,
+    filename: Option<String>,
+    lineno: Option<u64>,
+    package: Option<String>,
+    instruction_addr: Option<String>,
+}
+
+impl ProfileFrame {
+    fn to_json(&self) -> Value {
+        let mut rv = json!({ "function": self.function });
+        if let Some(ref filename) = self.filename {
+            rv["filename"] = filename.as_str().into();
+        }
+        if let Some(lineno) = self.lineno {
+            rv["lineno"] = lineno.into();
+        }
+        if let Some(ref package) = self.package {
+            rv["package"] = package.as_str().into();
+        }
+        if let Some(ref addr) = self.instruction_addr {
+            rv["instruction_addr"] = addr.as_str().into();
+        }
+        rv
+    }
+}
+
+#[derive(Debug)]
+struct Sample {
+    thread_id: u64,
+    elapsed_ns: u64,
+    /// The frames of the stack, innermost first.
+    stack: Vec<ProfileFrame>,
+}
+
+/// A parsed profile.
+#[derive(Debug, Default)]
+pub struct Profile {
+    samples: Vec<Sample>,
+    threads: BTreeMap<u64, String>,
+}
+
+fn parse_collapsed_frame(frame: &str) -> ProfileFrame {
+    match PY_SPY_FRAME_RE.captures(frame) {
+        Some(caps) => ProfileFrame {
+            function: caps[1].to_string(),
+            filename: Some(caps[2].to_string()),
+            lineno: caps[3].parse().ok(),
+            package: None,
+            instruction_addr: None,
+        },
+        None => ProfileFrame {
+            function: frame.to_string(),
+            filename: None,
+            lineno: None,
+            package: None,
+            instruction_addr: None,
+        },
+    }
+}
+
+fn parse_collapsed<R: BufRead>(reader: R, interval_ns: u64) -> Result<Profile, Error> {
+    let mut profile = Profile::default();
+    profile.threads.insert(0, "main".into());
+    let mut elapsed_ns = 0;
+
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            continue;
+        }
+        let (stack, count) = match line.rsplit_once(' ').map(|(s, c)| (s, c.parse::<u64>())) {
+            Some((stack, Ok(count))) => (stack, count),
+            _ => bail!("Invalid collapsed stack on line {}", idx + 1),
+        };
+        let stack: Vec<_> = stack.split(';').rev().map(parse_collapsed_frame).collect();
+        for _ in 0..count {
+            profile.samples.push(Sample {
+                thread_id: 0,
+                elapsed_ns,
+                stack: stack.clone(),
+            });
+            elapsed_ns += interval_ns;
+        }
+    }
+
+    Ok(profile)
+}
+
+fn parse_perf_script<R: BufRead>(reader: R) -> Result<Profile, Error> {
+    let mut profile = Profile::default();
+    let mut start = None;
+    let mut current: Option<Sample> = None;
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            if let Some(sample) = current.take() {
+                profile.samples.push(sample);
+            }
+        } else if let Some(caps) = PERF_HEADER_RE.captures(&line) {
+            if let Some(sample) = current.take() {
+                profile.samples.push(sample);
+            }
+            let thread_id = caps.get(3).unwrap_or_else(|| caps.get(2).unwrap());
+            let thread_id: u64 = thread_id.as_str().parse()?;
+            let timestamp: f64 = caps[4].parse()?;
+            let start = *start.get_or_insert(timestamp);
+            profile
+                .threads
+                .entry(thread_id)
+                .or_insert_with(|| caps[1].to_string());
+            current = Some(Sample {
+                thread_id,
+                elapsed_ns: ((timestamp - start).max(0.0) * 1e9) as u64,
+                stack: vec![],
+            });
+        } else if let Some(caps) = PERF_FRAME_RE.captures(&line) {
+            if let Some(ref mut sample) = current {
+                sample.stack.push(ProfileFrame {
+                    function: SYMBOL_OFFSET_RE.replace(&caps[2], "").into_owned(),
+                    filename: None,
+                    lineno: None,
+                    package: caps.get(3).map(|dso| dso.as_str().to_string()),
+                    instruction_addr: Some(format!("0x{}", &caps[1])),
+                });
+            }
+        }
+    }
+    if let Some(sample) = current.take() {
+        profile.samples.push(sample);
+    }
+
+    Ok(profile)
+}
+
+impl Profile {
+    /// Parses the output of a profiler.  `interval_ns` is the time between
+    /// samples of collapsed stacks.
+    pub fn parse<R: BufRead>(
+        reader: R,
+        format: ProfileFormat,
+        interval_ns: u64,
+    ) -> Result<Profile, Error> {
+        let profile = match format {
+            ProfileFormat::Collapsed => parse_collapsed(reader, interval_ns)?,
+            ProfileFormat::Perf => parse_perf_script(reader)?,
+        };
+        if profile.samples.is_empty() {
+            bail!("The profile does not contain any samples");
+        }
+        Ok(profile)
+    }
+
+    /// The time from the first to the last sample.
+    pub fn duration_ns(&self) -> u64 {
+        self.samples.last().map_or(0, |sample| sample.elapsed_ns)
+    }
+
+    /// The thread most samples were taken on.
+    pub fn active_thread_id(&self) -> u64 {
+        let mut counts = HashMap::new();
+        for sample in &self.samples {
+            *counts.entry(sample.thread_id).or_insert(0) += 1;
+        }
+        counts
+            .into_iter()
+            .max_by_key(|&(thread_id, count)| (count, std::cmp::Reverse(thread_id)))
+            .map_or(0, |(thread_id, _)| thread_id)
+    }
+
+    /// Builds the `profile` object of the sample format, which stores every
+    /// distinct frame and stack once.
+    pub fn to_sample_profile(&self) -> Value {
+        let mut frames = vec![];
+        let mut frame_ids = HashMap::new();
+        let mut stacks: Vec<Vec<usize>> = vec![];
+        let mut stack_ids = HashMap::new();
+        let mut samples = vec![];
+
+        for sample in &self.samples {
+            let stack: Vec<usize> = sample
+                .stack
+                .iter()
+                .map(|frame| {
+                    *frame_ids.entry(frame).or_insert_with(|| {
+                        frames.push(frame.to_json());
+                        frames.len() - 1
+                    })
+                })
+                .collect();
+            let stack_id = *stack_ids.entry(stack.clone()).or_insert_with(|| {
+                stacks.push(stack);
+                stacks.len() - 1
+            });
+            samples.push(json!({
+                "stack_id": stack_id,
+                "thread_id": sample.thread_id.to_string(),
+                "elapsed_since_start_ns": sample.elapsed_ns.to_string(),
+            }));
+        }
+
+        let thread_metadata: serde_json::Map<_, _> = self
+            .threads
+            .iter()
+            .map(|(thread_id, name)| (thread_id.to_string(), json!({ "name": name })))
+            .collect();
+        json!({
+            "samples": samples,
+            "stacks": stacks,
+            "frames": frames,
+            "thread_metadata": thread_metadata,
+        })
+    }
+}
+
+/// The kernel release for the `os` context of profiles.
+#[cfg(unix)]
+pub fn os_version() -> String {
+    use std::ffi::CStr;
+
+    let mut uts: libc::utsname = unsafe { std::mem::zeroed() };
+    if unsafe { libc::uname(&mut uts) } != 0 {
+        return String::new();
+    }
+    unsafe { CStr::from_ptr(uts.release.as_ptr()) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(not(unix))]
+pub fn os_version() -> String {
+    String::new()
+}
+
+/// The `os` and `device` contexts required by the sample format.
+pub fn device_info() -> (Value, Value) {
+    (
+        json!({ "name": env::consts::OS, "version": os_version() }),
+        json!({ "architecture": env::consts::ARCH }),
+    )
+}
+
+#[test]
+fn test_parse_collapsed() {
+    let profile = Profile::parse(
+        &b"main (app.py:10);work (app.py:20) 2\nmain (app.py:10) 1\n"[..],
+        ProfileFormat::Collapsed,
+        10_000_000,
+    )
+    .unwrap();
+    assert_eq!(profile.duration_ns(), 20_000_000);
+
+    let rv = profile.to_sample_profile();
+    assert_eq!(
+        rv["frames"],
+        json!([
+            {"function": "work", "filename": "app.py", "lineno": 20},
+            {"function": "main", "filename": "app.py", "lineno": 10},
+        ])
+    );
+    assert_eq!(rv["stacks"], json!([[0, 1], [1]]));
+    assert_eq!(rv["samples"][1]["stack_id"], 0);
+    assert_eq!(rv["samples"][2]["stack_id"], 1);
+    assert_eq!(rv["samples"][2]["elapsed_since_start_ns"], "20000000");
+}
+
+#[test]
+fn test_parse_perf_script() {
+    let contents = "\
+worker 1234/1235 [001] 100.250000: 250000 cpu-clock:
+\t    55d0c0a1b2c3 compute+0x13 (/usr/bin/worker)
+\t    55d0c0a1b000 main+0x40 (/usr/bin/worker)
+
+worker 1234/1235 [001] 100.260000: 250000 cpu-clock:
+\t    55d0c0a1b000 main+0x40 (/usr/bin/worker)
+";
+    assert_eq!(ProfileFormat::detect(contents), ProfileFormat::Perf);
+    let profile = Profile::parse(contents.as_bytes(), ProfileFormat::Perf, 0).unwrap();
+    assert_eq!(profile.active_thread_id(), 1235);
+    assert_eq!(profile.duration_ns(), 10_000_000);
+
+    let rv = profile.to_sample_profile();
+    assert_eq!(rv["frames"][0]["function"], "compute");
+    assert_eq!(rv["frames"][0]["package"], "/usr/bin/worker");
+    assert_eq!(rv["frames"][0]["instruction_addr"], "0x55d0c0a1b2c3");
+    assert_eq!(rv["stacks"], json!([[0, 1], [1]]));
+    assert_eq!(rv["thread_metadata"]["1235"]["name"], "worker");
+}
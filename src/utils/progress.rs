@@ -1,24 +1,33 @@
 use console::{style, Term};
+use parking_lot::Mutex;
+use std::borrow::Cow;
 use std::env;
 use std::ops::Deref;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
 use crate::utils::logging;
+use crate::utils::machine;
 
 pub use indicatif::{ProgressDrawTarget, ProgressStyle};
 
 pub fn is_progress_bar_visible() -> bool {
-    env::var("SENTRY_NO_PROGRESS_BAR") != Ok("1".into())
+    env::var("SENTRY_NO_PROGRESS_BAR") != Ok("1".into()) && !machine::is_enabled()
 }
 
 pub struct ProgressBar {
     inner: Arc<indicatif::ProgressBar>,
+    message: Mutex<Option<String>>,
+    reported_percent: AtomicU64,
 }
 
 impl ProgressBar {
     pub fn new(len: u64) -> Self {
         if is_progress_bar_visible() {
             indicatif::ProgressBar::new(len).into()
+        } else if machine::is_enabled() {
+            // hidden bars forget their length, which progress events need
+            indicatif::ProgressBar::with_draw_target(len, ProgressDrawTarget::hidden()).into()
         } else {
             Self::hidden()
         }
@@ -32,6 +41,42 @@ impl ProgressBar {
         }
     }
 
+    pub fn inc(&self, delta: u64) {
+        self.inner.inc(delta);
+        self.report_progress(false);
+    }
+
+    pub fn set_position(&self, pos: u64) {
+        self.inner.set_position(pos);
+        self.report_progress(false);
+    }
+
+    pub fn set_message(&self, msg: impl Into<Cow<'static, str>>) {
+        let msg = msg.into();
+        if machine::is_enabled() {
+            *self.message.lock() = Some(msg.to_string());
+        }
+        self.inner.set_message(msg);
+        self.report_progress(true);
+    }
+
+    /// Emits a progress event for every new percent or message.
+    fn report_progress(&self, force: bool) {
+        if !machine::is_enabled() {
+            return;
+        }
+        let current = self.inner.position();
+        let total = match self.inner.length() {
+            // the length of spinners and hidden bars
+            u64::MAX => 0,
+            total => total,
+        };
+        let percent = (current * 100).checked_div(total).unwrap_or(0);
+        if self.reported_percent.swap(percent, Ordering::Relaxed) != percent || force {
+            machine::progress(self.message.lock().as_deref(), current, total);
+        }
+    }
+
     pub fn hidden() -> Self {
         indicatif::ProgressBar::hidden().into()
     }
@@ -73,7 +118,11 @@ impl From<indicatif::ProgressBar> for ProgressBar {
     fn from(pb: indicatif::ProgressBar) -> Self {
         let inner = Arc::new(pb);
         logging::set_progress_bar(Some(Arc::downgrade(&inner)));
-        ProgressBar { inner }
+        ProgressBar {
+            inner,
+            message: Mutex::new(None),
+            reported_percent: AtomicU64::new(u64::MAX),
+        }
     }
 }
 
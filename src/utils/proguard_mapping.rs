@@ -0,0 +1,644 @@
+//! Merges, validates and sanitizes ProGuard and R8 mapping files.
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+
+use failure::{bail, Error};
+use proguard::{LineMapping, ProguardRecord};
+
+/// The mapping of a single class including its members.
+#[derive(Debug)]
+struct ClassMapping {
+    original: String,
+    lines: Vec<String>,
+}
+
+/// A class of a parsed mapping file.
+#[derive(Debug)]
+struct ParsedClass {
+    original: String,
+    obfuscated: String,
+    lines: Vec<String>,
+}
+
+/// A mapping file split into its headers and classes.
+#[derive(Debug, Default)]
+struct ParsedMapping {
+    headers: Vec<String>,
+    classes: Vec<ParsedClass>,
+}
+
+/// A method of a library class.
+#[derive(Debug)]
+struct LibraryMethod {
+    ty: String,
+    name: String,
+    arguments: String,
+    obfuscated: String,
+    line_mapping: Option<LineMapping>,
+}
+
+/// A class of a library mapping, which is looked up by its obfuscated name.
+#[derive(Debug)]
+struct LibraryClass {
+    file: String,
+    original: String,
+    fields: HashMap<String, (String, String)>,
+    methods: Vec<LibraryMethod>,
+    lines: Vec<String>,
+}
+
+/// A mapping file merged from several mapping files.
+#[derive(Debug, Default)]
+pub struct MergedMapping {
+    headers: Vec<String>,
+    classes: Vec<ClassMapping>,
+}
+
+/// Checks whether a class name looks like it was generated by a minifier,
+/// e.g. `a.b.c`.
+fn looks_obfuscated(name: &str) -> bool {
+    name.split('.').all(|part| {
+        part.len() <= 2
+            && part
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+    })
+}
+
+/// Splits a mapping file into classes and collects invalid lines.
+fn parse_mapping(name: &str, contents: &[u8], errors: &mut Vec<String>) -> ParsedMapping {
+    let mut rv = ParsedMapping::default();
+
+    for (line_index, line) in contents.split(|&c| c == b'\n').enumerate() {
+        let line = String::from_utf8_lossy(line).trim_end().to_string();
+        if line.is_empty() {
+            continue;
+        }
+
+        match ProguardRecord::try_parse(line.as_bytes()) {
+            Ok(ProguardRecord::Class {
+                original,
+                obfuscated,
+            }) => rv.classes.push(ParsedClass {
+                original: original.to_string(),
+                obfuscated: obfuscated.to_string(),
+                lines: vec![],
+            }),
+            Ok(ProguardRecord::Header { .. }) => match rv.classes.last_mut() {
+                // R8 stores additional information about classes and
+                // members in comments following them.
+                Some(class) => class.lines.push(line),
+                None => rv.headers.push(line),
+            },
+            Ok(_) => match rv.classes.last_mut() {
+                Some(class) => class.lines.push(line),
+                None => errors.push(format!(
+                    "{}:{}: member outside of a class: {}",
+                    name,
+                    line_index + 1,
+                    line.trim()
+                )),
+            },
+            Err(_) => errors.push(format!(
+                "{}:{}: invalid mapping line: {}",
+                name,
+                line_index + 1,
+                line.trim()
+            )),
+        }
+    }
+
+    rv
+}
+
+/// Indexes the classes of a library mapping by their obfuscated names.
+fn parse_library_class(file: &str, class: &ParsedClass) -> LibraryClass {
+    let mut rv = LibraryClass {
+        file: file.to_string(),
+        original: class.original.clone(),
+        fields: HashMap::new(),
+        methods: vec![],
+        lines: class.lines.clone(),
+    };
+
+    for line in &class.lines {
+        match ProguardRecord::try_parse(line.as_bytes()) {
+            Ok(ProguardRecord::Field {
+                ty,
+                original,
+                obfuscated,
+            }) => {
+                rv.fields.insert(
+                    obfuscated.to_string(),
+                    (ty.to_string(), original.to_string()),
+                );
+            }
+            Ok(ProguardRecord::Method {
+                ty,
+                original,
+                obfuscated,
+                arguments,
+                original_class,
+                line_mapping,
+            }) => rv.methods.push(LibraryMethod {
+                ty: ty.to_string(),
+                name: match original_class {
+                    Some(original_class) => format!("{}.{}", original_class, original),
+                    None => original.to_string(),
+                },
+                arguments: arguments.to_string(),
+                obfuscated: obfuscated.to_string(),
+                line_mapping,
+            }),
+            _ => {}
+        }
+    }
+
+    rv
+}
+
+/// Maps a line of the library's obfuscated code to the original source.
+fn map_library_line(line: usize, mapping: Option<&LineMapping>) -> usize {
+    match mapping {
+        Some(LineMapping {
+            startline,
+            endline,
+            original_startline: Some(original_startline),
+            original_endline,
+        }) => {
+            let same_length = original_endline.is_some_and(|original_endline| {
+                original_endline.saturating_sub(*original_startline) == endline - startline
+            });
+            if line < *startline || line > *endline {
+                line
+            } else if same_length {
+                original_startline + (line - startline)
+            } else {
+                *original_startline
+            }
+        }
+        _ => line,
+    }
+}
+
+/// Formats a method line of a mapping file.
+fn format_method(
+    ty: &str,
+    name: &str,
+    arguments: &str,
+    obfuscated: &str,
+    lines: Option<(usize, usize, usize, Option<usize>)>,
+) -> String {
+    match lines {
+        Some((startline, endline, original_startline, original_endline)) => format!(
+            "    {}:{}:{} {}({}):{}{} -> {}",
+            startline,
+            endline,
+            ty,
+            name,
+            arguments,
+            original_startline,
+            original_endline.map_or(String::new(), |line| format!(":{}", line)),
+            obfuscated
+        ),
+        None => format!("    {} {}({}) -> {}", ty, name, arguments, obfuscated),
+    }
+}
+
+impl MergedMapping {
+    /// Merges mapping files given as pairs of names and contents.
+    ///
+    /// The first file is the mapping of the app, all others are mappings of
+    /// libraries that were obfuscated before they were included in the app.
+    /// The app mapping refers to library classes and members by their
+    /// obfuscated names, so these are replaced with the original names from
+    /// the library mappings.  Library classes not renamed by the app are
+    /// included as they are, unless the app uses their obfuscated name.
+    ///
+    /// All lines are validated and an error listing the invalid lines
+    /// is returned if any of them cannot be parsed.  The headers of the
+    /// first file are retained.
+    pub fn merge(files: &[(&str, &[u8])]) -> Result<MergedMapping, Error> {
+        let mut errors = vec![];
+        let mut mappings = files
+            .iter()
+            .map(|&(name, contents)| (name, parse_mapping(name, contents, &mut errors)))
+            .collect::<Vec<_>>();
+
+        if !errors.is_empty() {
+            bail!(
+                "Found {} invalid lines in the mapping files:\n  {}",
+                errors.len(),
+                errors.join("\n  ")
+            );
+        }
+
+        let mut rv = MergedMapping::default();
+        if mappings.is_empty() {
+            return Ok(rv);
+        }
+        let (_, app) = mappings.remove(0);
+        rv.headers = app.headers;
+
+        let mut libraries: HashMap<String, LibraryClass> = HashMap::new();
+        let mut ambiguous: HashMap<String, (String, String)> = HashMap::new();
+        for (name, mapping) in &mappings {
+            for class in &mapping.classes {
+                if let Some(existing) = libraries.get(&class.obfuscated) {
+                    ambiguous
+                        .entry(class.obfuscated.clone())
+                        .or_insert_with(|| (existing.file.clone(), name.to_string()));
+                } else {
+                    libraries.insert(class.obfuscated.clone(), parse_library_class(name, class));
+                }
+            }
+        }
+
+        let map_type = |ty: &str| -> String {
+            let base = ty.trim_end_matches("[]");
+            match libraries.get(base) {
+                Some(class) => format!("{}{}", class.original, &ty[base.len()..]),
+                None => ty.to_string(),
+            }
+        };
+        let map_arguments = |arguments: &str| -> String {
+            arguments
+                .split(',')
+                .filter(|argument| !argument.is_empty())
+                .map(&map_type)
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        let get_library = |name: &str| -> Result<Option<&LibraryClass>, Error> {
+            if let Some((first, second)) = ambiguous.get(name) {
+                bail!(
+                    "Obfuscated class {} is mapped by both {} and {}",
+                    name,
+                    first,
+                    second
+                );
+            }
+            Ok(libraries.get(name))
+        };
+
+        let app_names: HashSet<&str> = app
+            .classes
+            .iter()
+            .flat_map(|class| vec![class.original.as_str(), class.obfuscated.as_str()])
+            .collect();
+
+        for class in &app.classes {
+            let library = get_library(&class.original)?;
+            let mut lines = vec![format!(
+                "{} -> {}:",
+                library.map_or(class.original.as_str(), |l| l.original.as_str()),
+                class.obfuscated
+            )];
+
+            for line in &class.lines {
+                match ProguardRecord::try_parse(line.as_bytes()) {
+                    Ok(ProguardRecord::Field {
+                        ty,
+                        original,
+                        obfuscated,
+                    }) => {
+                        let (ty, name) = match library.and_then(|l| l.fields.get(original)) {
+                            Some((ty, name)) => (ty.clone(), name.as_str()),
+                            None => (map_type(ty), original),
+                        };
+                        lines.push(format!("    {} {} -> {}", ty, name, obfuscated));
+                    }
+                    Ok(ProguardRecord::Method {
+                        ty,
+                        original,
+                        obfuscated,
+                        arguments,
+                        original_class,
+                        line_mapping,
+                    }) => {
+                        let owner = match original_class {
+                            Some(original_class) => get_library(original_class)?,
+                            None => library,
+                        };
+                        if owner.is_none()
+                            && map_type(ty) == ty
+                            && map_arguments(arguments) == arguments
+                            && original_class.is_none_or(|c| map_type(c) == c)
+                        {
+                            lines.push(line.clone());
+                            continue;
+                        }
+                        let qualify = |name: &str, owner_name: &str| match original_class {
+                            Some(_) if !name.contains('.') => format!("{}.{}", owner_name, name),
+                            _ => name.to_string(),
+                        };
+                        let arguments = map_arguments(arguments);
+
+                        // Lines of the app mapping refer to the library's
+                        // obfuscated code, omitted original lines are the
+                        // same as the obfuscated ones.
+                        let app_lines =
+                            line_mapping
+                                .as_ref()
+                                .map(|mapping| match mapping.original_startline {
+                                    Some(startline) => (startline, mapping.original_endline),
+                                    None => (mapping.startline, Some(mapping.endline)),
+                                });
+
+                        let candidates: Vec<&LibraryMethod> = owner
+                            .map(|owner| {
+                                owner
+                                    .methods
+                                    .iter()
+                                    .filter(|method| method.obfuscated == original)
+                                    .collect()
+                            })
+                            .unwrap_or_default();
+                        let containing: Vec<&LibraryMethod> = match app_lines {
+                            Some((startline, _)) => candidates
+                                .iter()
+                                .filter(|method| {
+                                    method.line_mapping.as_ref().is_some_and(|mapping| {
+                                        mapping.startline <= startline
+                                            && startline <= mapping.endline
+                                    })
+                                })
+                                .cloned()
+                                .collect(),
+                            None => vec![],
+                        };
+                        let methods = if containing.is_empty() {
+                            candidates
+                                .into_iter()
+                                .filter(|method| method.arguments == arguments)
+                                .take(1)
+                                .collect()
+                        } else {
+                            containing
+                        };
+
+                        let map_lines = |method: Option<&LibraryMethod>| {
+                            let mapping = method.and_then(|m| m.line_mapping.as_ref());
+                            line_mapping.as_ref().zip(app_lines).map(
+                                |(app_mapping, (startline, endline))| {
+                                    (
+                                        app_mapping.startline,
+                                        app_mapping.endline,
+                                        map_library_line(startline, mapping),
+                                        endline.map(|line| map_library_line(line, mapping)),
+                                    )
+                                },
+                            )
+                        };
+
+                        if methods.is_empty() {
+                            let name = match (owner, original_class) {
+                                (Some(owner), Some(_)) => qualify(original, &owner.original),
+                                (None, Some(original_class)) => {
+                                    format!("{}.{}", map_type(original_class), original)
+                                }
+                                _ => original.to_string(),
+                            };
+                            lines.push(format_method(
+                                &map_type(ty),
+                                &name,
+                                &arguments,
+                                obfuscated,
+                                map_lines(None),
+                            ));
+                        }
+                        for method in methods {
+                            let name = match owner {
+                                Some(owner) => qualify(&method.name, &owner.original),
+                                None => method.name.clone(),
+                            };
+                            lines.push(format_method(
+                                &method.ty,
+                                &name,
+                                &method.arguments,
+                                obfuscated,
+                                map_lines(Some(method)),
+                            ));
+                        }
+                    }
+                    _ => lines.push(line.clone()),
+                }
+            }
+
+            rv.classes.push(ClassMapping {
+                original: library.map_or(class.original.clone(), |l| l.original.clone()),
+                lines,
+            });
+        }
+
+        // Library classes the app did not rename keep their obfuscated
+        // names at runtime, unless the app itself uses these names.
+        for (_, mapping) in &mappings {
+            for class in &mapping.classes {
+                if app_names.contains(class.obfuscated.as_str())
+                    || ambiguous.contains_key(&class.obfuscated)
+                {
+                    continue;
+                }
+                let library = &libraries[&class.obfuscated];
+                let mut lines = vec![format!("{} -> {}:", class.original, class.obfuscated)];
+                lines.extend(library.lines.iter().cloned());
+                rv.classes.push(ClassMapping {
+                    original: class.original.clone(),
+                    lines,
+                });
+            }
+        }
+
+        Ok(rv)
+    }
+
+    /// Returns the number of mapped classes.
+    pub fn class_count(&self) -> usize {
+        self.classes.len()
+    }
+
+    /// Returns classes whose original names look minified themselves.
+    ///
+    /// This happens when a library was obfuscated before it was included in
+    /// the app and its own mapping file is missing.  Stack frames in these
+    /// classes will remain obfuscated.
+    pub fn obfuscated_classes(&self) -> Vec<&str> {
+        self.classes
+            .iter()
+            .filter(|class| looks_obfuscated(&class.original))
+            .map(|class| class.original.as_str())
+            .collect()
+    }
+
+    /// Writes the merged mapping file.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for header in &self.headers {
+            writeln!(writer, "{}", header)?;
+        }
+        for class in &self.classes {
+            for line in &class.lines {
+                writeln!(writer, "{}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks whether a mapping file was written by DexGuard or DexProtector.
+///
+/// Both tools add their own directives to the ProGuard format, which can be
+/// removed with `sanitize_mapping`.
+pub fn is_dexguard_mapping(contents: &[u8]) -> bool {
+    contents
+        .split(|&c| c == b'\n')
+        .take_while(|line| line.starts_with(b"#") || line.iter().all(u8::is_ascii_whitespace))
+        .any(|line| {
+            let line = String::from_utf8_lossy(line).to_lowercase();
+            line.contains("dexguard") || line.contains("dexprotector")
+        })
+}
+
+/// Removes all lines that are not part of the ProGuard mapping format.
+///
+/// Returns the remaining mapping and the removed lines along with their line
+/// numbers.
+pub fn sanitize_mapping(contents: &[u8]) -> (Vec<u8>, Vec<(usize, String)>) {
+    let mut rv = Vec::with_capacity(contents.len());
+    let mut removed = vec![];
+    let mut in_class = false;
+
+    for (index, line) in contents.split(|&c| c == b'\n').enumerate() {
+        let trimmed = String::from_utf8_lossy(line);
+        let trimmed = trimmed.trim_end();
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        let keep = match ProguardRecord::try_parse(trimmed.as_bytes()) {
+            Ok(ProguardRecord::Header { .. }) => true,
+            Ok(ProguardRecord::Class { .. }) => {
+                in_class = true;
+                true
+            }
+            Ok(_) => in_class,
+            Err(_) => false,
+        };
+
+        if keep {
+            rv.extend_from_slice(trimmed.as_bytes());
+            rv.push(b'\n');
+        } else {
+            removed.push((index + 1, trimmed.to_string()));
+        }
+    }
+
+    (rv, removed)
+}
+
+#[test]
+fn test_sanitize_dexguard_mapping() {
+    let mapping = b"# DexGuard 9.0.8\n\
+                    com.example.Main -> a:\n    \
+                    1:1:void main():3:3 -> a\n\
+                    com.example.Main$$encrypted -> a$$encrypted: [string encryption]\n";
+    assert!(is_dexguard_mapping(mapping));
+    assert!(!is_dexguard_mapping(
+        b"# compiler: R8\ncom.example.Main -> a:\n"
+    ));
+
+    let (sanitized, removed) = sanitize_mapping(mapping);
+    assert_eq!(
+        String::from_utf8(sanitized).unwrap(),
+        "# DexGuard 9.0.8\ncom.example.Main -> a:\n    1:1:void main():3:3 -> a\n"
+    );
+    assert_eq!(removed.len(), 1);
+    assert_eq!(removed[0].0, 4);
+}
+
+#[test]
+fn test_merge_mappings() {
+    let app = b"# compiler: R8\n\
+                io.sentry.sample.MainActivity -> io.sentry.sample.MainActivity:\n    \
+                1:1:void onCreate(android.os.Bundle):10:10 -> onCreate\n\
+                a.a -> b.a:\n    \
+                void run() -> a\n";
+    let library = b"# compiler: R8\n\
+                    com.example.Lib -> c.a:\n    \
+                    int count -> a\n";
+
+    let merged = MergedMapping::merge(&[("app.txt", app), ("lib.txt", library)]).unwrap();
+    assert_eq!(merged.class_count(), 3);
+    assert_eq!(merged.obfuscated_classes(), vec!["a.a"]);
+
+    let mut output = vec![];
+    merged.write(&mut output).unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "# compiler: R8\n\
+         io.sentry.sample.MainActivity -> io.sentry.sample.MainActivity:\n    \
+         1:1:void onCreate(android.os.Bundle):10:10 -> onCreate\n\
+         a.a -> b.a:\n    \
+         void run() -> a\n\
+         com.example.Lib -> c.a:\n    \
+         int count -> a\n"
+    );
+}
+
+#[test]
+fn test_merge_colliding_mappings() {
+    let app = b"# compiler: R8\n\
+                com.example.app.Main -> a.a:\n    \
+                1:4:void main():12:15 -> a\n\
+                a.a -> b.a:\n    \
+                int a -> b\n    \
+                1:2:void a(a.b):2:3 -> c\n    \
+                3:3:void b() -> d\n\
+                a.b -> b.b:\n";
+    let library = b"# compiler: R8\n\
+                    com.example.lib.Client -> a.a:\n    \
+                    int retries -> a\n    \
+                    1:3:void send(com.example.lib.Request):10:12 -> a\n    \
+                    void close() -> b\n\
+                    com.example.lib.Request -> a.b:\n\
+                    com.example.lib.Util -> a.c:\n    \
+                    1:1:void log():5:5 -> a\n";
+
+    let merged = MergedMapping::merge(&[("app.txt", app), ("lib.txt", library)]).unwrap();
+    assert_eq!(merged.class_count(), 4);
+    assert!(merged.obfuscated_classes().is_empty());
+
+    let mut output = vec![];
+    merged.write(&mut output).unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "# compiler: R8\n\
+         com.example.app.Main -> a.a:\n    \
+         1:4:void main():12:15 -> a\n\
+         com.example.lib.Client -> b.a:\n    \
+         int retries -> b\n    \
+         1:2:void send(com.example.lib.Request):11:12 -> c\n    \
+         3:3:void close():3:3 -> d\n\
+         com.example.lib.Request -> b.b:\n\
+         com.example.lib.Util -> a.c:\n    \
+         1:1:void log():5:5 -> a\n"
+    );
+}
+
+#[test]
+fn test_merge_invalid_mappings() {
+    let err = MergedMapping::merge(&[("a.txt", b"    void run() -> a\n")]).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("a.txt:1: member outside of a class"));
+
+    let err = MergedMapping::merge(&[
+        ("app.txt", b"a -> b:\n"),
+        ("a.txt", b"com.example.First -> a:\n"),
+        ("b.txt", b"com.example.Second -> a:\n"),
+    ])
+    .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Obfuscated class a is mapped by both a.txt and b.txt"));
+}
@@ -9,6 +9,7 @@ use if_chain::if_chain;
 use lazy_static::lazy_static;
 use regex::Regex;
 
+use crate::utils::ci::detect_ci;
 use crate::utils::cordova::CordovaConfig;
 use crate::utils::vcs;
 use crate::utils::xcode::InfoPlist;
@@ -130,11 +131,17 @@ pub fn detect_release_name() -> Result<String, Error> {
 
     // For android we badly parse gradle files.  We do this because most of the
     // time now people set the ids and versions in the gradle files instead of
-    // the xml manifests.
+    // the xml manifests, which are only used as a fallback.
     if let Some(release) = infer_gradle_release_name(None)? {
         return Ok(release);
     }
 
+    // the commit the CI provider builds, see `sentry-cli ci detect`.  This
+    // also covers builds without a git checkout.
+    if let Some(release) = detect_ci().commit_sha {
+        return Ok(release);
+    }
+
     if let Ok(head) = vcs::find_head() {
         Ok(head)
     } else {
@@ -0,0 +1,214 @@
+//! Measures the resources used by wrapped commands.
+//!
+//! On Unix the usage is collected with `wait4` when the child is reaped.  On
+//! Windows the child is put into a job object, whose accounting also covers
+//! the processes the child starts.
+use std::io;
+use std::process;
+
+use serde::Serialize;
+
+/// The resources a child process used until it exited.
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct ResourceUsage {
+    /// The peak resident set size, or the peak committed memory on Windows.
+    pub max_rss_bytes: u64,
+    pub cpu_user_ms: u64,
+    pub cpu_system_ms: u64,
+    /// Block input and output operations, only reported on Unix.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub io_read_blocks: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub io_write_blocks: Option<u64>,
+    /// Bytes read and written, only reported on Windows.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub io_read_bytes: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub io_write_bytes: Option<u64>,
+}
+
+impl ResourceUsage {
+    #[cfg(unix)]
+    pub fn from_rusage(usage: &libc::rusage) -> ResourceUsage {
+        // Linux reports kilobytes, macOS bytes
+        let max_rss = usage.ru_maxrss as u64;
+        ResourceUsage {
+            max_rss_bytes: if cfg!(target_os = "macos") {
+                max_rss
+            } else {
+                max_rss * 1024
+            },
+            cpu_user_ms: timeval_ms(&usage.ru_utime),
+            cpu_system_ms: timeval_ms(&usage.ru_stime),
+            io_read_blocks: Some(usage.ru_inblock as u64),
+            io_write_blocks: Some(usage.ru_oublock as u64),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(unix)]
+fn timeval_ms(tv: &libc::timeval) -> u64 {
+    tv.tv_sec as u64 * 1000 + tv.tv_usec as u64 / 1000
+}
+
+/// Tracks the resource usage of a child from its start until it is reaped.
+pub struct UsageTracker {
+    #[cfg(windows)]
+    job: Option<job::Job>,
+}
+
+impl UsageTracker {
+    /// Starts tracking a child right after it was spawned.
+    #[cfg(not(windows))]
+    pub fn new(_child: &process::Child) -> UsageTracker {
+        UsageTracker {}
+    }
+
+    /// Starts tracking a child right after it was spawned.
+    #[cfg(windows)]
+    pub fn new(child: &process::Child) -> UsageTracker {
+        UsageTracker {
+            job: job::Job::assign(child)
+                .map_err(|err| log::warn!("Could not track resource usage: {}", err))
+                .ok(),
+        }
+    }
+
+    /// Waits for the child to exit and returns its status and usage.
+    ///
+    /// The child is reaped here, it must not be waited for again.
+    #[cfg(unix)]
+    pub fn wait(
+        self,
+        child: &mut process::Child,
+    ) -> io::Result<(process::ExitStatus, Option<ResourceUsage>)> {
+        use std::os::unix::process::ExitStatusExt;
+
+        let pid = child.id() as libc::pid_t;
+        loop {
+            let mut status = 0;
+            let mut usage = unsafe { std::mem::zeroed() };
+            if unsafe { libc::wait4(pid, &mut status, 0, &mut usage) } != -1 {
+                return Ok((
+                    process::ExitStatus::from_raw(status),
+                    Some(ResourceUsage::from_rusage(&usage)),
+                ));
+            }
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(err);
+            }
+        }
+    }
+
+    /// Waits for the child to exit and returns its status and usage.
+    #[cfg(windows)]
+    pub fn wait(
+        self,
+        child: &mut process::Child,
+    ) -> io::Result<(process::ExitStatus, Option<ResourceUsage>)> {
+        let status = child.wait()?;
+        let usage = self.job.and_then(|job| {
+            job.usage()
+                .map_err(|err| log::warn!("Could not read resource usage: {}", err))
+                .ok()
+        });
+        Ok((status, usage))
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    pub fn wait(
+        self,
+        child: &mut process::Child,
+    ) -> io::Result<(process::ExitStatus, Option<ResourceUsage>)> {
+        Ok((child.wait()?, None))
+    }
+}
+
+#[cfg(windows)]
+mod job {
+    use std::io;
+    use std::mem;
+    use std::os::windows::io::AsRawHandle;
+    use std::process;
+    use std::ptr;
+
+    use winapi::shared::minwindef::{DWORD, LPVOID};
+    use winapi::um::handleapi::CloseHandle;
+    use winapi::um::jobapi2::{
+        AssignProcessToJobObject, CreateJobObjectW, QueryInformationJobObject,
+    };
+    use winapi::um::winnt::{
+        JobObjectBasicAndIoAccountingInformation, JobObjectExtendedLimitInformation, HANDLE,
+        JOBOBJECTINFOCLASS, JOBOBJECT_BASIC_AND_IO_ACCOUNTING_INFORMATION,
+        JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+    };
+
+    use super::ResourceUsage;
+
+    /// A job object holding the child and the processes it starts.
+    pub struct Job(HANDLE);
+
+    impl Job {
+        pub fn assign(child: &process::Child) -> io::Result<Job> {
+            let handle = unsafe { CreateJobObjectW(ptr::null_mut(), ptr::null()) };
+            if handle.is_null() {
+                return Err(io::Error::last_os_error());
+            }
+            let job = Job(handle);
+            if unsafe { AssignProcessToJobObject(job.0, child.as_raw_handle() as HANDLE) } == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(job)
+        }
+
+        fn query<T>(&self, class: JOBOBJECTINFOCLASS) -> io::Result<T> {
+            let mut info: T = unsafe { mem::zeroed() };
+            let rv = unsafe {
+                QueryInformationJobObject(
+                    self.0,
+                    class,
+                    &mut info as *mut T as LPVOID,
+                    mem::size_of::<T>() as DWORD,
+                    ptr::null_mut(),
+                )
+            };
+            if rv == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(info)
+        }
+
+        pub fn usage(&self) -> io::Result<ResourceUsage> {
+            let accounting: JOBOBJECT_BASIC_AND_IO_ACCOUNTING_INFORMATION =
+                self.query(JobObjectBasicAndIoAccountingInformation)?;
+            let limits: JOBOBJECT_EXTENDED_LIMIT_INFORMATION =
+                self.query(JobObjectExtendedLimitInformation)?;
+
+            // CPU times are counted in units of 100ns
+            let (user, kernel) = unsafe {
+                (
+                    *accounting.BasicInfo.TotalUserTime.QuadPart(),
+                    *accounting.BasicInfo.TotalKernelTime.QuadPart(),
+                )
+            };
+            Ok(ResourceUsage {
+                max_rss_bytes: limits.PeakJobMemoryUsed as u64,
+                cpu_user_ms: user as u64 / 10_000,
+                cpu_system_ms: kernel as u64 / 10_000,
+                io_read_bytes: Some(accounting.IoInfo.ReadTransferCount),
+                io_write_bytes: Some(accounting.IoInfo.WriteTransferCount),
+                ..Default::default()
+            })
+        }
+    }
+
+    impl Drop for Job {
+        fn drop(&mut self) {
+            unsafe {
+                CloseHandle(self.0);
+            }
+        }
+    }
+}
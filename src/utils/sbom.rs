@@ -0,0 +1,342 @@
+//! Reads the dependencies of a build from a software bill of materials.
+//!
+//! CycloneDX and SPDX documents in JSON are understood, as well as SPDX in
+//! its tag-value form.  Only the packages an application depends on are
+//! kept, the application itself is left out.
+use std::collections::HashSet;
+use std::fmt;
+
+use failure::{bail, Error};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// The format of a software bill of materials.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SbomFormat {
+    CycloneDx,
+    Spdx,
+}
+
+impl fmt::Display for SbomFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            SbomFormat::CycloneDx => write!(f, "CycloneDX"),
+            SbomFormat::Spdx => write!(f, "SPDX"),
+        }
+    }
+}
+
+/// A package the application depends on.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub struct Dependency {
+    pub name: String,
+    pub version: Option<String>,
+    pub purl: Option<String>,
+    pub license: Option<String>,
+}
+
+impl Dependency {
+    /// The package ecosystem, e.g. `npm` or `cargo`, taken from the package URL.
+    pub fn ecosystem(&self) -> Option<&str> {
+        let rest = self.purl.as_deref()?.strip_prefix("pkg:")?;
+        rest.split('/').next().filter(|ty| !ty.is_empty())
+    }
+}
+
+/// The dependencies of a bill of materials.
+#[derive(Debug)]
+pub struct Sbom {
+    pub format: SbomFormat,
+    pub spec_version: String,
+    pub dependencies: Vec<Dependency>,
+}
+
+fn string(value: &Value) -> Option<String> {
+    value
+        .as_str()
+        .filter(|value| !value.is_empty() && *value != "NOASSERTION" && *value != "NONE")
+        .map(str::to_string)
+}
+
+fn cyclonedx_license(component: &Value) -> Option<String> {
+    let licenses = component["licenses"].as_array()?;
+    let names: Vec<_> = licenses
+        .iter()
+        .filter_map(|license| {
+            string(&license["expression"])
+                .or_else(|| string(&license["license"]["id"]))
+                .or_else(|| string(&license["license"]["name"]))
+        })
+        .collect();
+    if names.is_empty() {
+        None
+    } else {
+        Some(names.join(" AND "))
+    }
+}
+
+fn collect_cyclonedx_components(components: &Value, rv: &mut Vec<Dependency>) {
+    for component in components.as_array().into_iter().flatten() {
+        if let Some(name) = string(&component["name"]) {
+            let name = match string(&component["group"]) {
+                Some(group) => format!("{}/{}", group, name),
+                None => name,
+            };
+            rv.push(Dependency {
+                name,
+                version: string(&component["version"]),
+                purl: string(&component["purl"]),
+                license: cyclonedx_license(component),
+            });
+        }
+        collect_cyclonedx_components(&component["components"], rv);
+    }
+}
+
+fn parse_spdx_json(doc: &Value) -> Vec<Dependency> {
+    let mut roots: HashSet<&str> = doc["documentDescribes"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .collect();
+    for relationship in doc["relationships"].as_array().into_iter().flatten() {
+        if relationship["relationshipType"] == "DESCRIBES" {
+            if let Some(id) = relationship["relatedSpdxElement"].as_str() {
+                roots.insert(id);
+            }
+        }
+    }
+
+    doc["packages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|package| {
+            package["SPDXID"]
+                .as_str()
+                .is_none_or(|id| !roots.contains(id))
+        })
+        .filter_map(|package| {
+            Some(Dependency {
+                name: string(&package["name"])?,
+                version: string(&package["versionInfo"]),
+                purl: package["externalRefs"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .find(|reference| reference["referenceType"] == "purl")
+                    .and_then(|reference| string(&reference["referenceLocator"])),
+                license: string(&package["licenseConcluded"])
+                    .or_else(|| string(&package["licenseDeclared"])),
+            })
+        })
+        .collect()
+}
+
+fn parse_spdx_tag_value(contents: &str) -> Result<Sbom, Error> {
+    let mut spec_version = None;
+    let mut roots = HashSet::new();
+    // the SPDX ID of every package along with it
+    let mut packages: Vec<(Option<String>, Dependency)> = vec![];
+
+    for line in contents.lines() {
+        let (tag, value) = match line.split_once(':') {
+            Some((tag, value)) => (tag.trim(), value.trim()),
+            None => continue,
+        };
+        let value_json = Value::String(value.to_string());
+        match tag {
+            "SPDXVersion" => spec_version = Some(value.trim_start_matches("SPDX-").to_string()),
+            "Relationship" => {
+                let parts: Vec<_> = value.split_whitespace().collect();
+                if let [_, "DESCRIBES", id] = parts[..] {
+                    roots.insert(id.to_string());
+                }
+            }
+            "PackageName" => packages.push((
+                None,
+                Dependency {
+                    name: value.to_string(),
+                    version: None,
+                    purl: None,
+                    license: None,
+                },
+            )),
+            _ => {
+                let (id, package) = match packages.last_mut() {
+                    Some((id, package)) => (id, package),
+                    None => continue,
+                };
+                match tag {
+                    "SPDXID" => *id = Some(value.to_string()),
+                    "PackageVersion" => package.version = string(&value_json),
+                    "PackageLicenseConcluded" => package.license = string(&value_json),
+                    "PackageLicenseDeclared" if package.license.is_none() => {
+                        package.license = string(&value_json)
+                    }
+                    "ExternalRef" => {
+                        let parts: Vec<_> = value.split_whitespace().collect();
+                        if let [_, "purl", locator] = parts[..] {
+                            package.purl = Some(locator.to_string());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    let spec_version = match spec_version {
+        Some(spec_version) => spec_version,
+        None => bail!("Not a CycloneDX or SPDX document"),
+    };
+    Ok(Sbom {
+        format: SbomFormat::Spdx,
+        spec_version,
+        dependencies: packages
+            .into_iter()
+            .filter(|(id, _)| id.as_ref().is_none_or(|id| !roots.contains(id)))
+            .map(|(_, package)| package)
+            .collect(),
+    })
+}
+
+impl Sbom {
+    /// Parses a CycloneDX or SPDX document, detecting which it is.
+    pub fn parse(contents: &[u8]) -> Result<Sbom, Error> {
+        let mut sbom = match serde_json::from_slice::<Value>(contents) {
+            Ok(doc) if doc["bomFormat"] == "CycloneDX" => {
+                let mut dependencies = vec![];
+                collect_cyclonedx_components(&doc["components"], &mut dependencies);
+                Sbom {
+                    format: SbomFormat::CycloneDx,
+                    spec_version: string(&doc["specVersion"]).unwrap_or_default(),
+                    dependencies,
+                }
+            }
+            Ok(doc) if doc["spdxVersion"].is_string() => Sbom {
+                format: SbomFormat::Spdx,
+                spec_version: doc["spdxVersion"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .trim_start_matches("SPDX-")
+                    .to_string(),
+                dependencies: parse_spdx_json(&doc),
+            },
+            Ok(_) => bail!("Not a CycloneDX or SPDX document"),
+            Err(_) => match std::str::from_utf8(contents) {
+                Ok(contents) => parse_spdx_tag_value(contents)?,
+                Err(_) => bail!("Not a CycloneDX or SPDX document"),
+            },
+        };
+        sbom.dependencies.sort();
+        sbom.dependencies.dedup();
+        Ok(sbom)
+    }
+
+    /// The manifest stored with a release.
+    pub fn to_manifest(&self) -> Value {
+        json!({
+            "version": 1,
+            "source": {
+                "format": self.format.to_string(),
+                "specVersion": self.spec_version,
+            },
+            "dependencies": self.dependencies.iter().map(|dependency| {
+                let mut value = serde_json::to_value(dependency).unwrap_or_default();
+                value["ecosystem"] = json!(dependency.ecosystem());
+                value
+            }).collect::<Vec<_>>(),
+        })
+    }
+}
+
+#[test]
+fn test_parse_cyclonedx() {
+    let sbom = Sbom::parse(
+        br#"{
+            "bomFormat": "CycloneDX",
+            "specVersion": "1.4",
+            "metadata": {"component": {"name": "acme-web", "version": "1.0"}},
+            "components": [
+                {
+                    "name": "core",
+                    "group": "@babel",
+                    "version": "7.20.0",
+                    "purl": "pkg:npm/%40babel/core@7.20.0",
+                    "licenses": [{"license": {"id": "MIT"}}],
+                    "components": [{"name": "json5", "version": "2.2.1"}]
+                },
+                {"name": "lodash", "version": "4.17.21", "purl": "pkg:npm/lodash@4.17.21"}
+            ]
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(sbom.format, SbomFormat::CycloneDx);
+    assert_eq!(sbom.spec_version, "1.4");
+    let names: Vec<_> = sbom.dependencies.iter().map(|d| d.name.as_str()).collect();
+    assert_eq!(names, vec!["@babel/core", "json5", "lodash"]);
+    assert_eq!(sbom.dependencies[0].license.as_deref(), Some("MIT"));
+    assert_eq!(sbom.dependencies[0].ecosystem(), Some("npm"));
+}
+
+#[test]
+fn test_parse_spdx_json() {
+    let sbom = Sbom::parse(
+        br#"{
+            "spdxVersion": "SPDX-2.3",
+            "documentDescribes": ["SPDXRef-acme"],
+            "packages": [
+                {"SPDXID": "SPDXRef-acme", "name": "acme"},
+                {
+                    "SPDXID": "SPDXRef-serde",
+                    "name": "serde",
+                    "versionInfo": "1.0.130",
+                    "licenseConcluded": "NOASSERTION",
+                    "licenseDeclared": "MIT OR Apache-2.0",
+                    "externalRefs": [{
+                        "referenceCategory": "PACKAGE-MANAGER",
+                        "referenceType": "purl",
+                        "referenceLocator": "pkg:cargo/serde@1.0.130"
+                    }]
+                }
+            ]
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(sbom.format, SbomFormat::Spdx);
+    assert_eq!(sbom.spec_version, "2.3");
+    assert_eq!(
+        sbom.dependencies,
+        vec![Dependency {
+            name: "serde".into(),
+            version: Some("1.0.130".into()),
+            purl: Some("pkg:cargo/serde@1.0.130".into()),
+            license: Some("MIT OR Apache-2.0".into()),
+        }]
+    );
+}
+
+#[test]
+fn test_parse_spdx_tag_value() {
+    let sbom = Sbom::parse(
+        b"SPDXVersion: SPDX-2.2\n\
+          Relationship: SPDXRef-DOCUMENT DESCRIBES SPDXRef-app\n\
+          \n\
+          PackageName: app\n\
+          SPDXID: SPDXRef-app\n\
+          \n\
+          PackageName: requests\n\
+          SPDXID: SPDXRef-requests\n\
+          PackageVersion: 2.28.1\n\
+          PackageLicenseConcluded: Apache-2.0\n\
+          ExternalRef: PACKAGE-MANAGER purl pkg:pypi/requests@2.28.1\n",
+    )
+    .unwrap();
+    assert_eq!(sbom.spec_version, "2.2");
+    assert_eq!(sbom.dependencies.len(), 1);
+    assert_eq!(sbom.dependencies[0].name, "requests");
+    assert_eq!(sbom.dependencies[0].ecosystem(), Some("pypi"));
+    assert!(Sbom::parse(b"hello world").is_err());
+}
@@ -0,0 +1,398 @@
+//! Reports failures of scripts instrumented by the shell hooks.
+use std::borrow::Cow;
+use std::cmp::min;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use chrono::{TimeZone, Utc};
+use failure::{bail, Error};
+use lazy_static::lazy_static;
+use log::warn;
+use regex::Regex;
+use sentry::internals::Uuid;
+use sentry::protocol::{Breadcrumb, Event, Exception, Frame, Level, Stacktrace, User, Value};
+use username::get_user_name;
+
+use crate::config::Config;
+use crate::utils::envelope::{self, send_with_attachments, Attachment};
+use crate::utils::event::{attach_logfile, get_sdk_info, with_sentry_client};
+use crate::utils::releases::detect_release_name;
+use crate::utils::spool::Spool;
+
+lazy_static! {
+    static ref FRAME_RE: Regex = Regex::new(r#"^(.*?):(.*):(\d+)$"#).unwrap();
+}
+
+/// Adds the commands recorded by the debug trap as breadcrumbs.
+///
+/// Every line contains the start timestamp, the exit code and the command
+/// separated by tabs.  Matches of the redaction patterns are masked.
+fn attach_commands(event: &mut Event<'_>, path: &str, redact: &[Regex]) -> Result<(), Error> {
+    let f = match fs::File::open(path) {
+        Ok(f) => f,
+        Err(_) => return Ok(()),
+    };
+
+    for line in BufReader::new(f).lines() {
+        let line = line?;
+        let mut split = line.splitn(3, '\t');
+        let (timestamp, exit_code, command) = match (split.next(), split.next(), split.next()) {
+            (Some(timestamp), Some(exit_code), Some(command)) => (timestamp, exit_code, command),
+            _ => continue,
+        };
+
+        let mut command = Cow::Borrowed(command);
+        for pattern in redact {
+            if let Cow::Owned(redacted) = pattern.replace_all(&command, "[redacted]") {
+                command = Cow::Owned(redacted);
+            }
+        }
+
+        let exit_code: i32 = exit_code.parse().unwrap_or(0);
+        let mut breadcrumb = Breadcrumb {
+            category: Some("command".into()),
+            message: Some(command.into_owned()),
+            level: if exit_code == 0 {
+                Level::Info
+            } else {
+                Level::Error
+            },
+            ..Default::default()
+        };
+        if let Some(timestamp) = timestamp.parse().ok().map(|ts| Utc.timestamp(ts, 0)) {
+            breadcrumb.timestamp = timestamp;
+        }
+        breadcrumb
+            .data
+            .insert("exit_code".into(), Value::from(exit_code));
+        event.breadcrumbs.values.push(breadcrumb);
+    }
+
+    Ok(())
+}
+
+/// Sends an event for a failed script.
+///
+/// The traceback file contains one `function:file:line` frame per line and
+/// `@command:`, `@exit_code:` and optionally `@message:` entries describing
+/// the failure.  The commands, if given, are attached as breadcrumbs.
+///
+/// With `spool` the event is queued and sent from a background process
+/// instead, so that the script does not wait for the network.  The tail of
+/// `attach_log`, if given, is sent as an attachment of at most the given
+/// number of bytes.
+pub fn send_event(
+    error_type: &str,
+    traceback: &str,
+    logfile: &str,
+    commands: Option<(&str, Vec<Regex>)>,
+    environ: bool,
+    spool: bool,
+    attach_log: Option<(&str, u64)>,
+) -> Result<(), Error> {
+    let config = Config::current();
+
+    let mut event = Event {
+        environment: config.get_environment().map(Into::into),
+        release: detect_release_name().ok().map(Into::into),
+        sdk: Some(get_sdk_info()),
+        user: get_user_name().ok().map(|n| User {
+            username: Some(n),
+            ip_address: Some(Default::default()),
+            ..Default::default()
+        }),
+        ..Event::default()
+    };
+
+    if environ {
+        event.extra.insert(
+            "environ".into(),
+            Value::Object(env::vars().map(|(k, v)| (k, Value::String(v))).collect()),
+        );
+    }
+
+    let mut cmd = "unknown".to_string();
+    let mut exit_code = 1;
+    let mut message = None;
+    let mut frames = vec![];
+
+    if let Ok(f) = fs::File::open(traceback) {
+        let f = BufReader::new(f);
+        for line in f.lines() {
+            let line = line?;
+
+            // meta info
+            if line.starts_with('@') {
+                if let Some(rest) = line.strip_prefix("@command:") {
+                    cmd = rest.to_string();
+                } else if let Some(rest) = line.strip_prefix("@exit_code:") {
+                    exit_code = rest.parse().unwrap_or(exit_code);
+                } else if let Some(rest) = line.strip_prefix("@message:") {
+                    message = Some(rest.to_string());
+                } else {
+                    continue;
+                }
+            }
+
+            if let Some(cap) = FRAME_RE.captures(&line) {
+                match &cap[1] {
+                    "_sentry_err_trap" | "_sentry_exit_trap" | "_sentry_traceback" => continue,
+                    _ => {}
+                }
+                frames.push(Frame {
+                    filename: Some(cap[2].to_string()),
+                    abs_path: Path::new(&cap[2])
+                        .canonicalize()
+                        .map(|x| x.display().to_string())
+                        .ok(),
+                    lineno: cap[3].parse().ok(),
+                    function: Some(cap[1].to_string()),
+                    ..Default::default()
+                });
+            }
+        }
+    }
+
+    {
+        let mut source_caches = HashMap::new();
+        for frame in &mut frames {
+            let lineno = match frame.lineno {
+                Some(line) => line as usize,
+                None => continue,
+            };
+
+            let filename = frame.filename.as_deref().expect("frame without location");
+
+            if !source_caches.contains_key(filename) {
+                if let Ok(f) = fs::File::open(filename) {
+                    let lines: Vec<_> = BufReader::new(f)
+                        .lines()
+                        .map(|x| x.unwrap_or_else(|_| "".to_string()))
+                        .collect();
+                    source_caches.insert(filename, lines);
+                } else {
+                    source_caches.insert(filename, vec![]);
+                }
+            }
+            let source = &source_caches[filename];
+            frame.context_line = source.get(lineno.saturating_sub(1)).cloned();
+            if let Some(slice) = source.get(lineno.saturating_sub(5)..lineno.saturating_sub(1)) {
+                frame.pre_context = slice.to_vec();
+            };
+            if let Some(slice) = source.get(lineno..min(lineno + 5, source.len())) {
+                frame.post_context = slice.to_vec();
+            };
+        }
+    }
+
+    attach_logfile(&mut event, logfile, true)?;
+    if let Some((path, redact)) = commands {
+        attach_commands(&mut event, path, &redact)?;
+        event.breadcrumbs.values.sort_by_key(|b| b.timestamp);
+        if event.breadcrumbs.len() > 100 {
+            let skip = event.breadcrumbs.len() - 100;
+            event.breadcrumbs.values.drain(..skip);
+        }
+    }
+
+    event.exception.values.push(Exception {
+        ty: error_type.into(),
+        value: Some(match message {
+            Some(message) => format!(
+                "{} (command {} exited with status {})",
+                message, cmd, exit_code
+            ),
+            None => format!("command {} exited with status {}", cmd, exit_code),
+        }),
+        stacktrace: Some(Stacktrace {
+            frames,
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+
+    let mut attachments = vec![];
+    if let Some((path, max_size)) = attach_log {
+        match Attachment::from_log_tail(Path::new(path), max_size) {
+            Ok(attachment) => attachments.push(attachment),
+            Err(err) => warn!("Could not attach {}: {}", path, err),
+        }
+    }
+
+    let dsn = config.get_dsn()?;
+    let id = if spool {
+        let spool = Spool::from_config(&config)?;
+        let id = spool.enqueue(&dsn, event, attachments)?;
+        // with --spool the envelopes are left for whoever drains the spool
+        if !config.should_spool() {
+            spool.flush_in_background()?;
+        }
+        id
+    } else if !attachments.is_empty() {
+        send_with_attachments(&dsn, &event, &attachments)?
+    } else {
+        with_sentry_client(dsn, |c| c.capture_event(event, None))
+    };
+    println!("{}", id);
+
+    Ok(())
+}
+
+/// Parses a timestamp written by the hook, which is either fractional
+/// seconds or whole seconds when the shell cannot do better.
+fn parse_span_timestamp(value: &str) -> Option<f64> {
+    value
+        .parse()
+        .ok()
+        .or_else(|| value.split('.').next()?.parse().ok())
+}
+
+fn new_span_id() -> String {
+    Uuid::new_v4().to_simple().to_string()[..16].to_string()
+}
+
+/// Builds a transaction for a script run from the recorded spans.
+///
+/// The file starts with a `run` line and ends with an `exit` line, both with
+/// a timestamp.  In between `start` lines with a timestamp and a name open a
+/// section and `end` lines close the innermost one.  Sections still open
+/// when the script exits are closed at that point.
+fn build_transaction<R: BufRead>(reader: R, script: &str, exit_code: i32) -> Result<Value, Error> {
+    let trace_id = Uuid::new_v4().to_simple().to_string();
+    let root_span_id = new_span_id();
+    let status = if exit_code == 0 {
+        "ok"
+    } else {
+        "internal_error"
+    };
+
+    let mut started = None;
+    let mut finished = None;
+    let mut open: Vec<Value> = vec![];
+    let mut spans = vec![];
+
+    for line in reader.lines() {
+        let line = line?;
+        let mut split = line.splitn(3, '\t');
+        let kind = split.next().unwrap_or("");
+        let timestamp = match split.next().and_then(parse_span_timestamp) {
+            Some(timestamp) => timestamp,
+            None => continue,
+        };
+        match kind {
+            "run" => started = Some(timestamp),
+            "exit" => finished = Some(timestamp),
+            "start" => {
+                let parent_span_id = match open.last() {
+                    Some(parent) => parent["span_id"].clone(),
+                    None => root_span_id.clone().into(),
+                };
+                open.push(serde_json::json!({
+                    "trace_id": trace_id,
+                    "span_id": new_span_id(),
+                    "parent_span_id": parent_span_id,
+                    "op": "script.section",
+                    "description": split.next().unwrap_or("section"),
+                    "start_timestamp": timestamp,
+                }));
+            }
+            "end" => {
+                if let Some(mut span) = open.pop() {
+                    span["timestamp"] = timestamp.into();
+                    span["status"] = "ok".into();
+                    spans.push(span);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let started = match started {
+        Some(started) => started,
+        None => bail!("The spans of the script run are incomplete"),
+    };
+    let finished = finished.unwrap_or(started);
+    while let Some(mut span) = open.pop() {
+        span["timestamp"] = finished.into();
+        span["status"] = status.into();
+        spans.push(span);
+    }
+
+    let name = Path::new(script).file_name().map_or_else(
+        || script.to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    Ok(serde_json::json!({
+        "type": "transaction",
+        "event_id": Uuid::new_v4().to_simple().to_string(),
+        "transaction": name,
+        "platform": "other",
+        "sdk": get_sdk_info(),
+        "start_timestamp": started,
+        "timestamp": finished,
+        "contexts": {
+            "trace": {
+                "trace_id": trace_id,
+                "span_id": root_span_id,
+                "op": "script",
+                "status": status,
+            },
+        },
+        "tags": {
+            "exit_code": exit_code.to_string(),
+        },
+        "spans": spans,
+    }))
+}
+
+/// Sends the recorded spans of a script run as a transaction.
+pub fn send_transaction(spans: &str, script: &str, exit_code: i32) -> Result<(), Error> {
+    let config = Config::current();
+    let f = fs::File::open(spans)?;
+    let mut transaction = build_transaction(BufReader::new(f), script, exit_code)?;
+    if let Ok(release) = detect_release_name() {
+        transaction["release"] = release.into();
+    }
+    if let Some(environment) = config.get_environment() {
+        transaction["environment"] = environment.into();
+    }
+    envelope::send_transaction(&config.get_dsn()?, &transaction)
+}
+
+#[test]
+fn test_build_transaction() {
+    let spans = "run\t100.5\n\
+                 start\t101\tbackup\n\
+                 start\t102.25\tupload\n\
+                 end\t104\n\
+                 end\t105\n\
+                 start\t106\tcleanup\n\
+                 exit\t107.5\n";
+    let transaction = build_transaction(spans.as_bytes(), "/srv/nightly.sh", 1).unwrap();
+    assert_eq!(transaction["transaction"], "nightly.sh");
+    assert_eq!(transaction["start_timestamp"], 100.5);
+    assert_eq!(transaction["timestamp"], 107.5);
+    assert_eq!(transaction["contexts"]["trace"]["status"], "internal_error");
+
+    let root = &transaction["contexts"]["trace"]["span_id"];
+    let spans = transaction["spans"].as_array().unwrap();
+    let by_name = |name: &str| {
+        spans
+            .iter()
+            .find(|span| span["description"] == name)
+            .unwrap()
+    };
+    assert_eq!(spans.len(), 3);
+    assert_eq!(&by_name("backup")["parent_span_id"], root);
+    assert_eq!(
+        by_name("upload")["parent_span_id"],
+        by_name("backup")["span_id"]
+    );
+    assert_eq!(by_name("upload")["timestamp"], 104.0);
+    assert_eq!(by_name("backup")["status"], "ok");
+    assert_eq!(by_name("cleanup")["timestamp"], 107.5);
+    assert_eq!(by_name("cleanup")["status"], "internal_error");
+}
@@ -0,0 +1,126 @@
+//! Summarizes the sizes of uploaded artifacts.
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use console::style;
+use indicatif::HumanBytes;
+use sentry::internals::{Dsn, Uuid};
+use sentry::protocol::{Event, Level, LogEntry, Value};
+
+use crate::utils::event::{get_sdk_info, with_sentry_client};
+
+#[derive(Clone, Debug, Default)]
+struct SizeRow {
+    files: u64,
+    size: u64,
+}
+
+/// A breakdown of artifact sizes, e.g. by architecture or file type.
+#[derive(Clone, Debug, Default)]
+pub struct SizeReport {
+    title: String,
+    rows: BTreeMap<String, SizeRow>,
+}
+
+impl SizeReport {
+    /// Creates an empty report. The title names the kind of artifacts.
+    pub fn new<S: Into<String>>(title: S) -> SizeReport {
+        SizeReport {
+            title: title.into(),
+            rows: BTreeMap::new(),
+        }
+    }
+
+    /// Adds a file of the given size to a group.
+    pub fn add(&mut self, group: &str, size: u64) {
+        let row = self.rows.entry(group.to_string()).or_default();
+        row.files += 1;
+        row.size += size;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Returns the combined size of all files.
+    pub fn total_size(&self) -> u64 {
+        self.rows.values().map(|row| row.size).sum()
+    }
+
+    /// Prints the sizes of all groups and their total.
+    pub fn print(&self) {
+        println!();
+        println!(
+            "{}",
+            style(format!("{} Size Report", self.title)).dim().bold()
+        );
+        for (group, row) in &self.rows {
+            println!(
+                "  {} {} ({} {})",
+                style(group).yellow(),
+                HumanBytes(row.size),
+                row.files,
+                if row.files == 1 { "file" } else { "files" }
+            );
+        }
+        println!(
+            "  {} {}",
+            style("total").yellow().bold(),
+            HumanBytes(self.total_size())
+        );
+    }
+
+    /// Converts the report into an event with one measurement per group.
+    ///
+    /// Sizes are stored in bytes as `size.<group>` in the extra data and the
+    /// event is tagged with the release, so that sizes can be compared
+    /// across releases.
+    pub fn to_event(&self, release: Option<&str>) -> Event<'static> {
+        let mut event = Event {
+            level: Level::Info,
+            sdk: Some(get_sdk_info()),
+            release: release.map(|r| Cow::Owned(r.to_string())),
+            logentry: Some(LogEntry {
+                message: format!("{} Size Report", self.title),
+                params: vec![],
+            }),
+            ..Event::default()
+        };
+
+        event.tags.insert("report".into(), "size".into());
+        event.tags.insert("artifacts".into(), self.title.clone());
+        for (group, row) in &self.rows {
+            event
+                .extra
+                .insert(format!("size.{}", group), Value::from(row.size));
+            event
+                .extra
+                .insert(format!("files.{}", group), Value::from(row.files));
+        }
+        event
+            .extra
+            .insert("size.total".into(), Value::from(self.total_size()));
+        event
+    }
+
+    /// Sends the report to the project of the given DSN.
+    pub fn send(&self, dsn: Dsn, release: Option<&str>) -> Uuid {
+        let event = self.to_event(release);
+        with_sentry_client(dsn, |c| c.capture_event(event, None))
+    }
+}
+
+#[test]
+fn test_size_report() {
+    let mut report = SizeReport::new("Debug Symbols");
+    report.add("arm64", 100);
+    report.add("x86_64", 50);
+    report.add("arm64", 20);
+    assert_eq!(report.total_size(), 170);
+
+    let event = report.to_event(Some("1.0.0"));
+    assert_eq!(event.release.as_deref(), Some("1.0.0"));
+    assert_eq!(event.extra["size.arm64"], Value::from(120));
+    assert_eq!(event.extra["files.arm64"], Value::from(2));
+    assert_eq!(event.extra["size.total"], Value::from(170));
+}
@@ -2,7 +2,7 @@
 use std::collections::{HashMap, HashSet};
 use std::ffi::OsStr;
 use std::mem;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str;
 
 use console::style;
@@ -12,10 +12,16 @@ use log::{debug, info, warn};
 use symbolic::debuginfo::sourcebundle::SourceFileType;
 use url::Url;
 
+use crate::utils::annotations::{annotate, AnnotationLevel};
 use crate::utils::enc::decode_unknown_string;
 use crate::utils::file_search::ReleaseFileMatch;
-use crate::utils::file_upload::{ReleaseFile, ReleaseFileUpload, ReleaseFiles, UploadContext};
+use crate::utils::file_upload::{
+    checksums_for_files, write_upload_plan, ArtifactChecksum, LogLevel, ReleaseFile,
+    ReleaseFileUpload, ReleaseFiles, UploadContext, UploadLimits,
+};
+use crate::utils::fs::normalize_path;
 use crate::utils::progress::make_progress_bar;
+use crate::utils::size_report::SizeReport;
 
 fn is_likely_minified_js(code: &[u8]) -> bool {
     if let Ok(code_str) = decode_unknown_string(code) {
@@ -291,6 +297,15 @@ impl SourceMapProcessor {
 
             for msg in source.messages.iter() {
                 println!("      - {}: {}", style(&msg.0).red(), msg.1);
+                let level = match msg.0 {
+                    LogLevel::Warning => AnnotationLevel::Warning,
+                    LogLevel::Error => AnnotationLevel::Error,
+                };
+                annotate(
+                    level,
+                    &format!("{}: {}", source.url, msg.1),
+                    Some(&source.path),
+                );
             }
         }
     }
@@ -460,6 +475,13 @@ impl SourceMapProcessor {
 
         self.unpack_indexed_ram_bundles()?;
 
+        // sources are referenced without the verbatim prefix of the paths
+        let prefixes: Vec<_> = prefixes
+            .iter()
+            .map(|prefix| normalize_path(prefix).to_string_lossy().into_owned())
+            .collect();
+        let prefixes: Vec<&str> = prefixes.iter().map(String::as_str).collect();
+
         let pb = make_progress_bar(self.sources.len() as u64);
         for source in self.sources.values_mut() {
             pb.set_message(source.url.clone());
@@ -469,7 +491,7 @@ impl SourceMapProcessor {
             }
             let options = sourcemap::RewriteOptions {
                 load_local_source_contents: true,
-                strip_prefixes: prefixes,
+                strip_prefixes: &prefixes,
                 ..Default::default()
             };
             let mut new_source: Vec<u8> = Vec::new();
@@ -524,6 +546,20 @@ impl SourceMapProcessor {
         Ok(())
     }
 
+    /// Drops the files exceeding the limits, or fails listing them.
+    pub fn apply_limits(&mut self, limits: &UploadLimits) -> Result<(), Error> {
+        self.flush_pending_sources();
+        limits.apply(&mut self.sources)
+    }
+
+    /// Writes all files to an upload plan instead of uploading them.
+    pub fn write_plan(&mut self, dir: &Path, context: &UploadContext<'_>) -> Result<(), Error> {
+        self.flush_pending_sources();
+        write_upload_plan(dir, context, &self.sources)?;
+        self.dump_log("Source Map Upload Report");
+        Ok(())
+    }
+
     /// Uploads all files
     pub fn upload(&mut self, context: &UploadContext<'_>) -> Result<(), Error> {
         self.flush_pending_sources();
@@ -533,6 +569,54 @@ impl SourceMapProcessor {
         self.dump_log("Source Map Upload Report");
         Ok(())
     }
+
+    /// Uploads all files so that they become visible at once, failing if the
+    /// server does not support this.
+    pub fn upload_atomic(&mut self, context: &UploadContext<'_>) -> Result<(), Error> {
+        self.flush_pending_sources();
+        let mut uploader = ReleaseFileUpload::new(context);
+        uploader.files(&self.sources).atomic(true);
+        uploader.upload()?;
+        self.dump_log("Source Map Upload Report");
+        Ok(())
+    }
+
+    /// Uploads all files to each of the dists, sending the files only once
+    /// if the server supports it.
+    pub fn upload_to_dists(
+        &mut self,
+        context: &UploadContext<'_>,
+        dists: &[&str],
+    ) -> Result<(), Error> {
+        self.flush_pending_sources();
+        let mut uploader = ReleaseFileUpload::new(context);
+        uploader.files(&self.sources).dists(dists);
+        uploader.upload()?;
+        self.dump_log("Source Map Upload Report");
+        Ok(())
+    }
+
+    /// The checksums of all files as they are uploaded to the context.
+    pub fn checksums(&mut self, context: &UploadContext<'_>) -> Vec<ArtifactChecksum> {
+        self.flush_pending_sources();
+        checksums_for_files(context, &self.sources)
+    }
+
+    /// Summarizes the sizes of all files by their type.
+    pub fn size_report(&mut self) -> SizeReport {
+        self.flush_pending_sources();
+        let mut report = SizeReport::new("JavaScript Bundle");
+        for source in self.sources.values() {
+            let group = match source.ty {
+                SourceFileType::Source => "source",
+                SourceFileType::MinifiedSource => "minified",
+                SourceFileType::SourceMap => "sourcemap",
+                SourceFileType::IndexedRamBundle => "ram-bundle",
+            };
+            report.add(group, source.contents.len() as u64);
+        }
+        report
+    }
 }
 
 fn validate_script(source: &mut ReleaseFile) -> Result<(), Error> {
@@ -0,0 +1,269 @@
+//! Keeps envelopes on disk so that they can be delivered later.
+//!
+//! The spool is a folder of `<id>.envelope` files.  The envelope header
+//! gets the DSN it was meant for, so whatever picks the files up knows the
+//! project.  `bash-hook --async` queues events there and flushes them in
+//! the background, with `--spool` every envelope sentry-cli would send is
+//! written there instead, for a local Relay or a later flush to deliver.
+//! Files appear atomically, a consumer never sees a partial envelope.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{self, Stdio};
+use std::time::{Duration, SystemTime};
+
+use chrono::Utc;
+use failure::{format_err, Error, ResultExt};
+use log::{debug, warn};
+use sentry::internals::{Dsn, Transport, Uuid};
+use sentry::protocol::Event;
+use serde_json::Value;
+
+use crate::api::Api;
+use crate::config::Config;
+use crate::utils::envelope::{to_envelope, Attachment};
+
+/// Spooled envelopes older than this are dropped instead of sent.
+const MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// The outcome of flushing the spool.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FlushStats {
+    pub sent: usize,
+    pub pending: usize,
+    pub dropped: usize,
+}
+
+/// A folder of envelopes waiting to be sent.
+///
+/// Flushing claims a file by renaming it before sending, so that concurrent
+/// flushes never send the same envelope twice.
+#[derive(Debug, Clone)]
+pub struct Spool {
+    dir: PathBuf,
+}
+
+impl Spool {
+    /// Opens the configured spool folder.
+    pub fn open() -> Result<Spool, Error> {
+        Spool::from_config(&Config::current())
+    }
+
+    /// Opens the spool folder of the given config.
+    pub fn from_config(config: &Config) -> Result<Spool, Error> {
+        Ok(Spool::open_at(config.get_spool_dir()?))
+    }
+
+    /// Opens a spool folder, it is created with the first envelope.
+    pub fn open_at<P: Into<PathBuf>>(dir: P) -> Spool {
+        Spool { dir: dir.into() }
+    }
+
+    /// Writes an envelope for a DSN into the spool and returns its path.
+    pub fn write_envelope(&self, dsn: &Dsn, envelope: &[u8]) -> Result<PathBuf, Error> {
+        let (header, items) = match envelope.iter().position(|&b| b == b'\n') {
+            Some(idx) => (&envelope[..idx], &envelope[idx..]),
+            None => (envelope, &b"\n"[..]),
+        };
+        let mut header: Value =
+            serde_json::from_slice(header).context("Invalid envelope header")?;
+        header["dsn"] = dsn.to_string().into();
+        header["sent_at"] = Utc::now().to_rfc3339().into();
+        let id = match header["event_id"].as_str() {
+            Some(event_id) => event_id.to_string(),
+            None => Uuid::new_v4().to_simple().to_string(),
+        };
+
+        let mut contents = serde_json::to_vec(&header)?;
+        contents.extend_from_slice(items);
+
+        fs::create_dir_all(&self.dir)
+            .with_context(|_| format!("Could not create spool folder {}", self.dir.display()))?;
+        // write to a temporary name first so that a consumer never sees a
+        // partial envelope
+        let path = self.dir.join(format!("{}.envelope", id));
+        let tmp = self.dir.join(format!(".{}.tmp", id));
+        fs::write(&tmp, &contents)
+            .with_context(|_| format!("Could not write {}", tmp.display()))?;
+        fs::rename(&tmp, &path).with_context(|_| format!("Could not write {}", path.display()))?;
+        debug!("Spooled envelope {}", path.display());
+        Ok(path)
+    }
+
+    /// Writes an event and its attachments to the spool and returns its ID.
+    pub fn enqueue(
+        &self,
+        dsn: &Dsn,
+        event: Event<'static>,
+        attachments: Vec<Attachment>,
+    ) -> Result<Uuid, Error> {
+        self.write_envelope(dsn, &to_envelope(&event, &attachments)?)?;
+        Ok(event.event_id)
+    }
+
+    fn queued_files(&self) -> Result<Vec<PathBuf>, Error> {
+        let mut rv = vec![];
+        if !self.dir.is_dir() {
+            return Ok(rv);
+        }
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension() == Some("envelope".as_ref()) {
+                rv.push(path);
+            }
+        }
+        rv.sort_by_key(|path| fs::metadata(path).and_then(|m| m.modified()).ok());
+        Ok(rv)
+    }
+
+    /// Sends all queued envelopes.
+    ///
+    /// Envelopes that could not be delivered because of network errors, rate
+    /// limits or server errors stay in the spool for the next flush.
+    pub fn flush(&self) -> Result<FlushStats, Error> {
+        let api = Api::current();
+        let mut stats = FlushStats::default();
+
+        for path in self.queued_files()? {
+            let claimed = path.with_extension("sending");
+            if fs::rename(&path, &claimed).is_err() {
+                // another flush got to it first
+                continue;
+            }
+
+            match send_spooled(&api, &claimed) {
+                Ok(true) => {
+                    fs::remove_file(&claimed).ok();
+                    stats.sent += 1;
+                }
+                Ok(false) => {
+                    fs::remove_file(&claimed).ok();
+                    stats.dropped += 1;
+                }
+                Err(err) => {
+                    debug!("Could not send {}: {}", path.display(), err);
+                    fs::rename(&claimed, &path).ok();
+                    stats.pending += 1;
+                }
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Flushes the spool from a detached background process.
+    pub fn flush_in_background(&self) -> Result<(), Error> {
+        process::Command::new(std::env::current_exe()?)
+            .args(["bash-hook", "--flush"])
+            .env("SENTRY_SPOOL_DIR", &self.dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .context("Could not start background flush")?;
+        Ok(())
+    }
+}
+
+/// Sends a claimed envelope.  Returns `false` if it was given up on.
+fn send_spooled(api: &Api, path: &Path) -> Result<bool, Error> {
+    let age = fs::metadata(path)?
+        .modified()
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+    if matches!(age, Some(age) if age > MAX_AGE) {
+        warn!("Dropping expired envelope {}", path.display());
+        return Ok(false);
+    }
+
+    let envelope = fs::read(path)?;
+    let header = envelope.split(|&b| b == b'\n').next().unwrap_or_default();
+    let dsn = serde_json::from_slice::<Value>(header)
+        .ok()
+        .and_then(|header| header["dsn"].as_str()?.parse::<Dsn>().ok());
+    let dsn = match dsn {
+        Some(dsn) => dsn,
+        None => {
+            warn!("Dropping envelope {} without a DSN", path.display());
+            return Ok(false);
+        }
+    };
+    let resp = api.post_envelope(&dsn, envelope)?;
+    match resp.status() {
+        200..=299 => Ok(true),
+        429 | 500..=599 => Err(format_err!("server responded with {}", resp.status())),
+        status => {
+            warn!(
+                "Dropping envelope {} rejected with {}",
+                path.display(),
+                status
+            );
+            Ok(false)
+        }
+    }
+}
+
+/// A transport for the sentry client that writes events into the spool.
+pub struct SpoolTransport {
+    spool: Spool,
+    dsn: Dsn,
+}
+
+impl SpoolTransport {
+    pub fn new(spool: Spool, dsn: Dsn) -> SpoolTransport {
+        SpoolTransport { spool, dsn }
+    }
+}
+
+impl Transport for SpoolTransport {
+    fn send_event(&self, event: Event<'static>) {
+        let rv = to_envelope(&event, &[])
+            .and_then(|envelope| self.spool.write_envelope(&self.dsn, &envelope));
+        if let Err(err) = rv {
+            warn!("Could not spool event {}: {}", event.event_id, err);
+        }
+    }
+}
+
+#[test]
+fn test_write_envelope() {
+    let dir = tempfile::tempdir().unwrap();
+    let spool = Spool::open_at(dir.path());
+    let dsn: Dsn = "https://key@sentry.invalid/42".parse().unwrap();
+    let path = spool
+        .write_envelope(
+            &dsn,
+            b"{\"event_id\":\"a1b2c3d4000040008000000000000001\"}\n{\"type\":\"event\",\"length\":2}\n{}\n",
+        )
+        .unwrap();
+    assert_eq!(
+        path,
+        dir.path().join("a1b2c3d4000040008000000000000001.envelope")
+    );
+
+    let contents = fs::read_to_string(&path).unwrap();
+    let mut lines = contents.lines();
+    let header: Value = serde_json::from_str(lines.next().unwrap()).unwrap();
+    assert_eq!(header["dsn"], dsn.to_string());
+    assert!(header["sent_at"].is_string());
+    assert_eq!(lines.next(), Some(r#"{"type":"event","length":2}"#));
+    assert_eq!(lines.next(), Some("{}"));
+    assert_eq!(spool.queued_files().unwrap(), vec![path]);
+}
+
+#[test]
+fn test_spool_enqueue() {
+    let dir = tempfile::tempdir().unwrap();
+    let spool = Spool::open_at(dir.path());
+    let dsn: Dsn = "https://key@sentry.invalid/42".parse().unwrap();
+    let id = spool.enqueue(&dsn, Event::default(), vec![]).unwrap();
+
+    let files = spool.queued_files().unwrap();
+    assert_eq!(
+        files,
+        vec![dir.path().join(format!("{}.envelope", id.to_simple()))]
+    );
+    let contents = fs::read_to_string(&files[0]).unwrap();
+    let header: Value = serde_json::from_str(contents.lines().next().unwrap()).unwrap();
+    assert_eq!(header["dsn"], dsn.to_string());
+    assert_eq!(header["event_id"], id.to_simple().to_string());
+}
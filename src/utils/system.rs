@@ -1,6 +1,10 @@
 use std::borrow::Cow;
 use std::env;
 use std::process;
+#[cfg(not(windows))]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(not(windows))]
+use std::time::{Duration, Instant};
 
 use console::style;
 use failure::{Error, Fail};
@@ -8,7 +12,21 @@ use lazy_static::lazy_static;
 use regex::{Captures, Regex};
 
 use crate::config::Config;
+use crate::utils::annotations::{annotate, AnnotationLevel};
+#[cfg(not(windows))]
+use crate::utils::cancel;
+use crate::utils::crash_report::{get_crash_dir, CrashReport};
+use crate::utils::machine;
+
+/// How long a cancelled command gets to abort its requests and clean up.
+#[cfg(not(windows))]
+const CANCEL_TIMEOUT: Duration = Duration::from_secs(5);
 
+/// Runs the command until it finishes or is interrupted.
+///
+/// The first SIGINT or SIGTERM cancels the command, see `utils::cancel`,
+/// a second one or a command that does not finish in time ends the process
+/// right away.
 #[cfg(not(windows))]
 pub fn run_or_interrupt<F>(f: F)
 where
@@ -21,13 +39,14 @@ where
     ])
     .unwrap();
 
-    {
+    let worker = {
         let tx = tx.clone();
         std::thread::spawn(move || {
+            // also wakes up the main thread if the command panics
+            let _done = SendOnDrop(tx);
             f();
-            tx.send(0).ok();
-        });
-    }
+        })
+    };
 
     std::thread::spawn(move || {
         for signal in signals.forever() {
@@ -35,13 +54,54 @@ where
         }
     });
 
-    if let Ok(signal) = rx.recv() {
+    let mut deadline = None;
+    loop {
+        let signal = match deadline {
+            Some(deadline) => rx.recv_deadline(deadline).unwrap_or(0),
+            None => rx.recv().unwrap_or(0),
+        };
+        if signal == 0 || deadline.is_some() {
+            break;
+        }
+        if FORWARD_INTERRUPTS.load(Ordering::SeqCst) {
+            continue;
+        }
         if signal == signal_hook::consts::SIGINT {
             eprintln!("Interrupted!");
         }
+        cancel::cancel(signal);
+        deadline = Some(Instant::now() + CANCEL_TIMEOUT);
+    }
+
+    if cancel::is_cancelled() {
+        cancel::finish();
+        process::exit(cancel::exit_code());
+    }
+    if worker.join().is_err() {
+        process::exit(101);
     }
 }
 
+#[cfg(not(windows))]
+struct SendOnDrop(crossbeam_channel::Sender<i32>);
+
+#[cfg(not(windows))]
+impl Drop for SendOnDrop {
+    fn drop(&mut self) {
+        self.0.send(0).ok();
+    }
+}
+
+#[cfg(not(windows))]
+static FORWARD_INTERRUPTS: AtomicBool = AtomicBool::new(false);
+
+/// Keeps SIGINT and SIGTERM from ending sentry-cli, for commands that pass
+/// them on to a child process instead.
+#[cfg(not(windows))]
+pub fn forward_interrupts() {
+    FORWARD_INTERRUPTS.store(true, Ordering::SeqCst);
+}
+
 #[cfg(windows)]
 pub fn run_or_interrupt<F>(f: F)
 where
@@ -52,13 +112,14 @@ where
 }
 
 /// Propagate an exit status outwarts
-pub fn propagate_exit_status(status: process::ExitStatus) {
-    if !status.success() {
-        if let Some(code) = status.code() {
-            process::exit(code);
-        } else {
-            process::exit(1);
-        }
+///
+/// A failed status is returned as `QuietExit` so that the caller exits with
+/// the same code without printing another error.
+pub fn propagate_exit_status(status: process::ExitStatus) -> Result<(), Error> {
+    if status.success() {
+        Ok(())
+    } else {
+        Err(QuietExit(status.code().unwrap_or(1)).into())
     }
 }
 
@@ -121,7 +182,27 @@ pub fn print_error(err: &Error) {
         }
     }
 
-    if Config::current().get_log_level() < log::LevelFilter::Info {
+    machine::error(
+        &err.to_string(),
+        &err.iter_chain()
+            .skip(1)
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+    );
+
+    annotate(
+        AnnotationLevel::Error,
+        &err.iter_chain()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(": "),
+        None,
+    );
+
+    // global options can fail before the config is bound
+    let verbose =
+        Config::current_opt().map(|config| config.get_log_level() >= log::LevelFilter::Info);
+    if verbose != Some(true) {
         eprintln!();
         eprintln!("{}", style("Add --log-level=[info|debug] or export SENTRY_LOG_LEVEL=[info|debug] to see more output.").dim());
         eprintln!(
@@ -167,6 +248,27 @@ pub fn init_backtrace() {
             None => eprintln!("thread '{}' panicked at '{}'{:?}", thread, msg, backtrace),
         }
 
+        let report = CrashReport::new(
+            thread,
+            msg,
+            info.location()
+                .map(|location| format!("{}:{}", location.file(), location.line())),
+            format!("{:?}", backtrace),
+        );
+        match get_crash_dir().and_then(|dir| report.save(&dir)) {
+            Ok(path) => eprintln!(
+                "\n{}",
+                style(format!(
+                    "A crash report was written to {}.\n\
+                     Please run `sentry-cli bugreport` and attach the result to \
+                     bug reports.",
+                    path.display()
+                ))
+                .yellow()
+            ),
+            Err(err) => eprintln!("Could not write crash report: {}", err),
+        }
+
         #[cfg(feature = "with_client_implementation")]
         {
             crate::utils::crashreporting::flush_events();
@@ -0,0 +1,183 @@
+//! Selects the TLS library libcurl uses at runtime.
+//!
+//! libcurl is built with the native TLS library, or with rustls instead with
+//! the `rustls` feature.  The backend is confirmed with `curl_global_sslset`
+//! before the first request is made, so selecting a backend that was not
+//! compiled in fails with a list of the available ones.  A libcurl built
+//! with several backends cannot switch once it is initialized.
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+use std::str::FromStr;
+
+use failure::{bail, Error};
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+
+use crate::utils::fips;
+
+const CURLSSLSET_OK: c_int = 0;
+const CURLSSLSET_TOO_LATE: c_int = 2;
+
+#[repr(C)]
+#[allow(non_camel_case_types)]
+struct curl_ssl_backend {
+    id: c_int,
+    name: *const c_char,
+}
+
+extern "C" {
+    // available since curl 7.56, not exposed by curl-sys
+    fn curl_global_sslset(
+        id: c_int,
+        name: *const c_char,
+        avail: *mut *const *const curl_ssl_backend,
+    ) -> c_int;
+}
+
+lazy_static! {
+    static ref SELECTED: Mutex<Option<TlsBackend>> = Mutex::new(None);
+}
+
+/// The TLS backends that can be selected with `http.tls_backend`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TlsBackend {
+    /// The TLS library of the platform and its trust store: Schannel on
+    /// Windows and OpenSSL elsewhere, which uses the system trust store on
+    /// macOS.
+    Native,
+    /// rustls, which does not depend on the system libraries.
+    Rustls,
+}
+
+impl TlsBackend {
+    /// The name of the backend in libcurl.
+    fn curl_name(self) -> &'static str {
+        match self {
+            TlsBackend::Native if cfg!(windows) => "schannel",
+            TlsBackend::Native => "openssl",
+            TlsBackend::Rustls => "rustls",
+        }
+    }
+}
+
+impl fmt::Display for TlsBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            TlsBackend::Native => write!(f, "native"),
+            TlsBackend::Rustls => write!(f, "rustls"),
+        }
+    }
+}
+
+impl FromStr for TlsBackend {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<TlsBackend, Error> {
+        Ok(match s {
+            "native" => TlsBackend::Native,
+            "rustls" => TlsBackend::Rustls,
+            _ => bail!("Unknown TLS backend: {}", s),
+        })
+    }
+}
+
+/// Calls `curl_global_sslset` and returns its result and the names of the
+/// backends libcurl was built with.
+fn sslset(name: Option<&CStr>) -> (c_int, Vec<String>) {
+    let mut avail: *const *const curl_ssl_backend = ptr::null();
+    let rv = unsafe { curl_global_sslset(-1, name.map_or(ptr::null(), CStr::as_ptr), &mut avail) };
+
+    let mut names = vec![];
+    let mut iter = avail;
+    while !iter.is_null() && !unsafe { *iter }.is_null() {
+        let name = unsafe { CStr::from_ptr((**iter).name) };
+        names.push(name.to_string_lossy().into_owned());
+        iter = unsafe { iter.add(1) };
+    }
+    (rv, names)
+}
+
+/// Returns the names of the TLS backends libcurl was built with.
+pub fn available_backends() -> Vec<String> {
+    sslset(None).1
+}
+
+/// Returns the TLS library in use, e.g. `OpenSSL/3.0.2`.
+pub fn describe_backend() -> String {
+    let version = curl::Version::get();
+    let backends = version.ssl_version().unwrap_or("none");
+    // libcurl with several backends puts the inactive ones in parentheses
+    backends
+        .split_whitespace()
+        .find(|backend| !backend.starts_with('('))
+        .unwrap_or(backends)
+        .to_string()
+}
+
+/// Returns the TLS backend that was selected, the one sentry-cli was built
+/// with by default.
+pub fn selected_backend() -> TlsBackend {
+    SELECTED.lock().unwrap_or(if cfg!(feature = "rustls") {
+        TlsBackend::Rustls
+    } else {
+        TlsBackend::Native
+    })
+}
+
+/// Makes libcurl use the given TLS backend.
+///
+/// This has to happen before the first request.  Selecting the same backend
+/// again, as persistent workers do for every request, is a no-op.
+pub fn select_backend(backend: TlsBackend) -> Result<(), Error> {
+    let mut selected = SELECTED.lock();
+    match *selected {
+        Some(current) if current == backend => return Ok(()),
+        Some(current) => bail!(
+            "Cannot switch the TLS backend from {} to {} in a running process",
+            current,
+            backend
+        ),
+        None => {}
+    }
+    if backend == TlsBackend::Rustls && fips::is_enabled() {
+        bail!("The rustls TLS backend cannot be used in FIPS mode");
+    }
+
+    let name = CString::new(backend.curl_name()).unwrap();
+    match sslset(Some(&name)) {
+        (CURLSSLSET_OK, _) => {
+            *selected = Some(backend);
+            Ok(())
+        }
+        // libcurl cannot be shut down safely while handles exist
+        (CURLSSLSET_TOO_LATE, _) => bail!(
+            "Cannot switch the TLS backend to {} after libcurl was initialized",
+            backend
+        ),
+        (_, available) => bail!(
+            "The {} TLS backend ({}) is not available in this build of sentry-cli. \
+             Available backends: {}",
+            backend,
+            backend.curl_name(),
+            available.join(", ")
+        ),
+    }
+}
+
+#[test]
+fn test_parse_tls_backend() {
+    assert_eq!("native".parse::<TlsBackend>().unwrap(), TlsBackend::Native);
+    assert_eq!("rustls".parse::<TlsBackend>().unwrap(), TlsBackend::Rustls);
+    assert!("openssl".parse::<TlsBackend>().is_err());
+    assert_eq!(TlsBackend::Rustls.to_string(), "rustls");
+}
+
+#[test]
+fn test_available_backends() {
+    let available = available_backends();
+    assert!(available
+        .iter()
+        .any(|name| name == selected_backend().curl_name()));
+}
@@ -1,16 +1,29 @@
 use std::io;
-use std::io::{Read, Write};
+use std::io::{IsTerminal, Read, Write};
+
+use failure::{bail, Error};
 
 use crate::utils::progress::{ProgressBar, ProgressStyle};
 
+/// Reads a line from stdin and fails once stdin is closed.
+fn read_input_line() -> io::Result<String> {
+    let mut buf = String::new();
+    if io::stdin().read_line(&mut buf)? == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "stdin was closed before an answer was entered",
+        ));
+    }
+    Ok(buf)
+}
+
 /// Prints a message and loops until yes or no is entered.
 pub fn prompt_to_continue(message: &str) -> io::Result<bool> {
     loop {
         print!("{} [y/n] ", message);
         io::stdout().flush()?;
 
-        let mut buf = String::new();
-        io::stdin().read_line(&mut buf)?;
+        let buf = read_input_line()?;
         let input = buf.trim();
 
         if input == "y" {
@@ -22,13 +35,26 @@ pub fn prompt_to_continue(message: &str) -> io::Result<bool> {
     }
 }
 
+/// Asks to confirm a destructive change unless `--confirm` was passed.
+///
+/// Without a terminal on stdin this fails instead of prompting, so that
+/// automation does not wait for an answer that never comes.
+pub fn confirm(confirmed: bool, message: &str) -> Result<bool, Error> {
+    if confirmed {
+        return Ok(true);
+    }
+    if !io::stdin().is_terminal() {
+        bail!("Cannot ask for confirmation without a terminal, pass --confirm");
+    }
+    Ok(prompt_to_continue(message)?)
+}
+
 /// Prompts for input and returns it.
 pub fn prompt(message: &str) -> io::Result<String> {
     loop {
         print!("{}: ", message);
         io::stdout().flush()?;
-        let mut buf = String::new();
-        io::stdin().read_line(&mut buf)?;
+        let buf = read_input_line()?;
         let input = buf.trim();
         if !input.is_empty() {
             return Ok(input.to_owned());
@@ -3,6 +3,7 @@ use std::fs;
 use std::io;
 use std::io::Write;
 use std::path::Path;
+use std::str::FromStr;
 
 use chrono::{DateTime, Duration, Utc};
 use console::{style, user_attended};
@@ -15,6 +16,7 @@ use serde::{Deserialize, Serialize};
 use crate::api::{Api, SentryCliRelease};
 use crate::config::Config;
 use crate::constants::{APP_INFO, VERSION};
+use crate::utils::digest::Sha256;
 use crate::utils::fs::{is_writable, set_executable_mode};
 use crate::utils::system::{is_homebrew_install, is_npm_install, QuietExit};
 
@@ -64,6 +66,58 @@ fn rename_exe(exe: &Path, downloaded_path: &Path, elevate: bool) -> Result<(), E
     Ok(())
 }
 
+/// The release channel updates are installed from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UpdateChannel {
+    Stable,
+    Beta,
+}
+
+impl UpdateChannel {
+    pub fn name(self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => "stable",
+            UpdateChannel::Beta => "beta",
+        }
+    }
+
+    /// Returns the URL of the latest release of this channel.
+    ///
+    /// The registry only knows the latest stable release, so the beta
+    /// channel picks the newest version from the listing of all versions,
+    /// prereleases included.
+    fn latest_url(self, api: &Api, registry_url: &str) -> Result<Option<String>, Error> {
+        let base = format!("{}/apps/sentry-cli", registry_url.trim_end_matches('/'));
+        let name = match self {
+            UpdateChannel::Stable => "latest".to_string(),
+            UpdateChannel::Beta => {
+                let newest = api
+                    .list_registry_versions(&format!("{}/versions", base))?
+                    .iter()
+                    .filter_map(|v| Version::parse(v).ok())
+                    .max();
+                match newest {
+                    Some(version) => version.to_string(),
+                    None => return Ok(None),
+                }
+            }
+        };
+        Ok(Some(format!("{}/{}", base, name)))
+    }
+}
+
+impl FromStr for UpdateChannel {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<UpdateChannel, Error> {
+        Ok(match s {
+            "stable" => UpdateChannel::Stable,
+            "beta" => UpdateChannel::Beta,
+            _ => bail!("Unknown update channel '{}', expected stable or beta", s),
+        })
+    }
+}
+
 #[derive(Default, Serialize, Deserialize)]
 pub struct LastUpdateCheck {
     pub last_check_timestamp: Option<DateTime<Utc>>,
@@ -109,10 +163,15 @@ impl LastUpdateCheck {
 }
 
 pub struct SentryCliUpdateInfo {
+    channel: UpdateChannel,
     latest_release: Option<SentryCliRelease>,
 }
 
 impl SentryCliUpdateInfo {
+    pub fn channel(&self) -> UpdateChannel {
+        self.channel
+    }
+
     pub fn have_version_info(&self) -> bool {
         self.latest_release.is_some()
     }
@@ -141,7 +200,23 @@ impl SentryCliUpdateInfo {
         }
     }
 
+    /// The SHA256 checksum of the executable as published in the registry.
+    pub fn sha256(&self) -> Option<&str> {
+        self.latest_release
+            .as_ref()
+            .and_then(|rel| rel.sha256.as_deref())
+    }
+
+    /// Downloads the latest release and replaces the running executable
+    /// once its checksum matches the one published in the registry.
     pub fn download(&self) -> Result<(), Error> {
+        let expected_sha256 = match self.sha256() {
+            Some(sha256) => sha256.to_lowercase(),
+            None => bail!(
+                "The release registry publishes no checksum for {}, refusing to install it.",
+                self.latest_version()
+            ),
+        };
         let exe = env::current_exe()?;
         let elevate = !is_writable(&exe);
         info!("expecting elevation for update: {}", elevate);
@@ -153,12 +228,28 @@ impl SentryCliUpdateInfo {
         let mut f = fs::File::create(&tmp_path)?;
         let api = Api::current();
         match api.download_with_progress(self.download_url()?, &mut f) {
+            Ok(resp) if resp.failed() => {
+                fs::remove_file(tmp_path).ok();
+                bail!("Could not download sentry-cli (status {})", resp.status());
+            }
             Ok(_) => {}
             Err(err) => {
                 fs::remove_file(tmp_path).ok();
                 bail!(err);
             }
         };
+        drop(f);
+
+        let sha256 = sha256_file(&tmp_path)?;
+        if sha256 != expected_sha256 {
+            fs::remove_file(tmp_path).ok();
+            bail!(
+                "Checksum mismatch for the downloaded executable, expected {} but got {}",
+                expected_sha256,
+                sha256
+            );
+        }
+        info!("verified checksum {} of downloaded executable", sha256);
 
         set_executable_mode(&tmp_path)?;
         rename_exe(&exe, &tmp_path, elevate)?;
@@ -166,14 +257,22 @@ impl SentryCliUpdateInfo {
     }
 }
 
-pub fn get_latest_sentrycli_release() -> Result<SentryCliUpdateInfo, Error> {
+fn sha256_file(path: &Path) -> Result<String, Error> {
+    let mut sha = Sha256::new();
+    io::copy(&mut fs::File::open(path)?, &mut sha)?;
+    Ok(sha.hex_digest())
+}
+
+pub fn get_latest_sentrycli_release(channel: UpdateChannel) -> Result<SentryCliUpdateInfo, Error> {
     let api = Api::current();
+    let registry_url = Config::current().get_release_registry_url();
+    let latest_release = match channel.latest_url(&api, &registry_url) {
+        Ok(Some(url)) => api.get_latest_sentrycli_release(&url).unwrap_or(None),
+        _ => None,
+    };
     Ok(SentryCliUpdateInfo {
-        latest_release: if let Ok(release) = api.get_latest_sentrycli_release() {
-            release
-        } else {
-            None
-        },
+        channel,
+        latest_release,
     })
 }
 
@@ -214,7 +313,10 @@ fn update_nagger_impl() -> Result<(), Error> {
 
     if check.should_run_check() {
         info!("Running update nagger update check");
-        let ui = get_latest_sentrycli_release()?;
+        let channel = Config::current()
+            .get_update_channel()
+            .unwrap_or(UpdateChannel::Stable);
+        let ui = get_latest_sentrycli_release(channel)?;
         if ui.have_version_info() {
             check.update_for_info(&ui);
             let mut f = fs::File::create(&path)?;
@@ -0,0 +1,111 @@
+//! Probes HTTP(S) endpoints for `uptime check`.
+use std::ffi::CStr;
+use std::ptr;
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use curl::easy::{Easy, List};
+use failure::Error;
+
+/// What a single request to an endpoint found out.
+#[derive(Debug)]
+pub struct ProbeResult {
+    pub status: Option<u32>,
+    pub latency: Duration,
+    pub tls_expiry: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+}
+
+/// Parses the expiry date of a certificate as curl reports it.
+///
+/// OpenSSL formats it like `Jan  1 00:00:00 2030 GMT`, other TLS backends
+/// use `2030-01-01 00:00:00 GMT`.
+fn parse_cert_date(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    ["%b %e %H:%M:%S %Y GMT", "%Y-%m-%d %H:%M:%S GMT"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .map(|date| DateTime::from_utc(date, Utc))
+}
+
+/// Returns when the certificate of the server expires.  Only the first
+/// certificate of the chain is looked at, that is the one of the server.
+fn get_certificate_expiry(handle: &Easy) -> Option<DateTime<Utc>> {
+    let mut info: *mut curl_sys::curl_certinfo = ptr::null_mut();
+    // the curl crate has no getter for the certificate chain
+    unsafe {
+        let rv = curl_sys::curl_easy_getinfo(handle.raw(), curl_sys::CURLINFO_CERTINFO, &mut info);
+        if rv != curl_sys::CURLE_OK || info.is_null() || (*info).num_of_certs < 1 {
+            return None;
+        }
+        let mut field = *(*info).certinfo;
+        while !field.is_null() {
+            let data = CStr::from_ptr((*field).data).to_string_lossy();
+            if let Some(date) = data.strip_prefix("Expire date:") {
+                return parse_cert_date(date);
+            }
+            field = (*field).next;
+        }
+    }
+    None
+}
+
+fn perform(
+    handle: &mut Easy,
+    url: &str,
+    method: &str,
+    headers: &[&str],
+    timeout: Duration,
+) -> Result<(), Error> {
+    handle.url(url)?;
+    handle.timeout(timeout)?;
+    handle.certinfo(url.starts_with("https:"))?;
+    handle.useragent(&format!(
+        "sentry-cli/{} (uptime)",
+        env!("CARGO_PKG_VERSION")
+    ))?;
+    match method {
+        "GET" => {}
+        "HEAD" => handle.nobody(true)?,
+        other => handle.custom_request(other)?,
+    }
+    let mut list = List::new();
+    for header in headers {
+        list.append(header)?;
+    }
+    handle.http_headers(list)?;
+
+    let mut transfer = handle.transfer();
+    // the body does not matter, only how long it takes to arrive
+    transfer.write_function(|data| Ok(data.len()))?;
+    transfer.perform()?;
+    Ok(())
+}
+
+/// Sends a single request to the URL.  Failing to connect is not an error,
+/// it is reported in the result.
+pub fn probe(url: &str, method: &str, headers: &[&str], timeout: Duration) -> ProbeResult {
+    let mut handle = Easy::new();
+    let error = perform(&mut handle, url, method, headers, timeout)
+        .err()
+        .map(|err| err.to_string());
+    ProbeResult {
+        status: handle.response_code().ok().filter(|&code| code != 0),
+        latency: handle.total_time().unwrap_or_default(),
+        tls_expiry: get_certificate_expiry(&handle),
+        error,
+    }
+}
+
+#[test]
+fn test_parse_cert_date() {
+    assert_eq!(
+        parse_cert_date("Mar  5 12:00:00 2030 GMT").map(|date| date.to_rfc3339()),
+        Some("2030-03-05T12:00:00+00:00".into())
+    );
+    assert_eq!(
+        parse_cert_date(" 2030-11-15 08:30:00 GMT").map(|date| date.to_rfc3339()),
+        Some("2030-11-15T08:30:00+00:00".into())
+    );
+    assert_eq!(parse_cert_date("soon"), None);
+}
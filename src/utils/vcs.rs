@@ -1,15 +1,18 @@
+use std::cmp::Reverse;
+use std::env;
 use std::fmt;
 use std::path::PathBuf;
 
 use chrono::{DateTime, FixedOffset, TimeZone};
-use failure::{bail, format_err, Error};
+use failure::{bail, format_err, Error, ResultExt};
 use git2::{Commit, Repository, Time};
+use glob::Pattern;
 use if_chain::if_chain;
 use lazy_static::lazy_static;
 use log::{debug, info};
 use regex::Regex;
 
-use crate::api::{GitCommit, PatchSet, Ref, Repo};
+use crate::api::{Api, GitCommit, GitLabToken, PatchSet, Ref, ReleaseInfo, Repo};
 
 #[derive(Copy, Clone)]
 pub enum GitReference<'a> {
@@ -570,6 +573,227 @@ pub fn generate_patch_set(
     Ok(result)
 }
 
+/// Fetches the commits of a range from the API of the repository's provider.
+///
+/// This works without a local checkout and supports GitHub and GitLab.  The
+/// API tokens are read from `GITHUB_TOKEN` and `GITLAB_TOKEN` (or
+/// `CI_JOB_TOKEN`), self-hosted instances can be configured with
+/// `GITHUB_API_URL` and `CI_API_V4_URL`.  The files of every commit take
+/// another request per commit.  Ranges GitHub does not list completely are
+/// rejected.
+pub fn get_commits_from_provider(
+    api: &Api,
+    repo: &Repo,
+    range: &str,
+) -> Result<Vec<GitCommit>, Error> {
+    let (prev_rev, rev) = parse_rev_range(range);
+    let prev_rev = match prev_rev {
+        Some(prev_rev) => prev_rev,
+        None => bail!("Expected a commit range in the form FROM..TO"),
+    };
+    let path = repo
+        .url
+        .as_ref()
+        .map(|url| get_repo_from_remote(url))
+        .unwrap_or_else(|| repo.name.clone());
+    let env_var = |key: &str| env::var(key).ok().filter(|value| !value.is_empty());
+
+    let mut rv = vec![];
+    if repo.provider.id.contains("github") {
+        let api_url = env_var("GITHUB_API_URL").unwrap_or_else(|| "https://api.github.com".into());
+        let token = env_var("GITHUB_TOKEN");
+        let comparison = api
+            .compare_github_commits(&api_url, token.as_deref(), &path, &prev_rev, &rev)
+            .with_context(|_| format!("Could not fetch commits of {} from GitHub", path))?;
+        if comparison.commits.len() < comparison.total_commits {
+            bail!(
+                "GitHub only listed {} of the {} commits in {}, use a smaller range",
+                comparison.commits.len(),
+                comparison.total_commits,
+                range
+            );
+        }
+        for commit in comparison.commits.into_iter().rev() {
+            let commit = api.get_github_commit(&api_url, token.as_deref(), &path, &commit.sha)?;
+            let author = commit.commit.author;
+            rv.push(GitCommit {
+                patch_set: commit
+                    .files
+                    .into_iter()
+                    .map(|file| PatchSet {
+                        ty: match file.status.as_str() {
+                            "added" => "A",
+                            "removed" => "D",
+                            _ => "M",
+                        }
+                        .into(),
+                        path: file.filename,
+                    })
+                    .collect(),
+                repository: repo.name.clone(),
+                author_name: author.as_ref().and_then(|a| a.name.clone()),
+                author_email: author.as_ref().and_then(|a| a.email.clone()),
+                timestamp: match author {
+                    Some(author) => author.date,
+                    None => bail!("Commit {} has no author date", commit.sha),
+                },
+                message: commit.commit.message,
+                id: commit.sha,
+            });
+        }
+    } else if repo.provider.id.contains("gitlab") {
+        let api_url =
+            env_var("CI_API_V4_URL").unwrap_or_else(|| "https://gitlab.com/api/v4".into());
+        let access_token = env_var("GITLAB_TOKEN");
+        let job_token = env_var("CI_JOB_TOKEN");
+        let token = access_token
+            .as_deref()
+            .map(GitLabToken::Access)
+            .or_else(|| job_token.as_deref().map(GitLabToken::Job));
+        let commits = api
+            .compare_gitlab_commits(&api_url, token, &path, &prev_rev, &rev)
+            .with_context(|_| format!("Could not fetch commits of {} from GitLab", path))?;
+        for commit in commits.into_iter().rev() {
+            let diff = api.get_gitlab_commit_diff(&api_url, token, &path, &commit.id)?;
+            rv.push(GitCommit {
+                patch_set: diff
+                    .into_iter()
+                    .map(|file| PatchSet {
+                        ty: if file.new_file {
+                            "A"
+                        } else if file.deleted_file {
+                            "D"
+                        } else {
+                            "M"
+                        }
+                        .into(),
+                        path: file.new_path,
+                    })
+                    .collect(),
+                repository: repo.name.clone(),
+                author_name: commit.author_name,
+                author_email: commit.author_email,
+                timestamp: commit.authored_date,
+                message: commit.message,
+                id: commit.id,
+            });
+        }
+    } else {
+        bail!(
+            "Fetching commits is not supported for {} repositories",
+            repo.provider.name
+        );
+    }
+
+    Ok(rv)
+}
+
+/// How the previous release of a release is determined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreviousReleaseStrategy {
+    /// The previous release with commits as determined by Sentry.
+    Default,
+    /// The most recent release whose last commit is an ancestor of `HEAD`.
+    SameBranch,
+    /// The most recent finalized release with commits.
+    LatestFinalized,
+}
+
+impl PreviousReleaseStrategy {
+    pub fn parse(s: &str) -> Result<PreviousReleaseStrategy, Error> {
+        Ok(match s {
+            "default" => PreviousReleaseStrategy::Default,
+            "release-on-same-branch" => PreviousReleaseStrategy::SameBranch,
+            "latest-finalized" => PreviousReleaseStrategy::LatestFinalized,
+            _ => bail!("Unknown previous release strategy '{}'", s),
+        })
+    }
+}
+
+/// The release that commits are associated from.
+#[derive(Debug, PartialEq, Eq)]
+pub struct PreviousRelease {
+    pub version: String,
+    pub commit: String,
+}
+
+/// Returns the releases other than `version` that have a last commit, most
+/// recent first.
+fn release_candidates<'a>(
+    releases: &'a [ReleaseInfo],
+    version: &str,
+) -> Vec<(&'a ReleaseInfo, &'a str)> {
+    let mut rv: Vec<_> = releases
+        .iter()
+        .filter(|r| r.version != version)
+        .filter_map(|r| r.last_commit.as_ref().map(|c| (r, c.id.as_str())))
+        .collect();
+    rv.sort_by_key(|(r, _)| Reverse(r.date_created));
+    rv
+}
+
+/// Finds the previous release of `version` with the given strategy.
+///
+/// `PreviousReleaseStrategy::Default` is resolved by Sentry and always
+/// returns `None` here.
+pub fn find_previous_release(
+    repo: Option<&Repository>,
+    releases: &[ReleaseInfo],
+    version: &str,
+    strategy: PreviousReleaseStrategy,
+) -> Result<Option<PreviousRelease>, Error> {
+    let candidates = release_candidates(releases, version);
+    let found = match strategy {
+        PreviousReleaseStrategy::Default => None,
+        PreviousReleaseStrategy::LatestFinalized => candidates
+            .into_iter()
+            .find(|(r, _)| r.date_released.is_some()),
+        PreviousReleaseStrategy::SameBranch => {
+            let repo = match repo {
+                Some(repo) => repo,
+                None => bail!("The release-on-same-branch strategy requires a git repository"),
+            };
+            let head = repo.head()?.peel_to_commit()?.id();
+            let mut found = None;
+            for (release, commit) in candidates {
+                let oid = match git2::Oid::from_str(commit) {
+                    Ok(oid) => oid,
+                    Err(_) => continue,
+                };
+                // commits of other branches or outside of a shallow clone
+                // are not found and skipped
+                if oid == head || repo.graph_descendant_of(head, oid).unwrap_or(false) {
+                    found = Some((release, commit));
+                    break;
+                }
+            }
+            found
+        }
+    };
+    Ok(found.map(|(release, commit)| PreviousRelease {
+        version: release.version.clone(),
+        commit: commit.to_string(),
+    }))
+}
+
+/// Keeps the commits that touch at least one file matching the patterns.
+///
+/// If no patterns are given all commits are kept.
+pub fn filter_commits_by_paths(commits: Vec<GitCommit>, patterns: &[Pattern]) -> Vec<GitCommit> {
+    if patterns.is_empty() {
+        return commits;
+    }
+    commits
+        .into_iter()
+        .filter(|commit| {
+            commit
+                .patch_set
+                .iter()
+                .any(|patch| patterns.iter().any(|pattern| pattern.matches(&patch.path)))
+        })
+        .collect()
+}
+
 pub fn get_commit_time(time: Time) -> DateTime<FixedOffset> {
     FixedOffset::east(time.offset_minutes() * 60).timestamp(time.seconds(), 0)
 }
@@ -1094,3 +1318,116 @@ fn test_generate_patch_ignore_missing() {
         ".*.timestamp" => "[timestamp]"
     });
 }
+
+#[test]
+fn test_filter_commits_by_paths() {
+    let commit = |id: &str, paths: &[&str]| GitCommit {
+        patch_set: paths
+            .iter()
+            .map(|path| PatchSet {
+                path: path.to_string(),
+                ty: "M".into(),
+            })
+            .collect(),
+        repository: "wat-org/monorepo".into(),
+        author_name: None,
+        author_email: None,
+        timestamp: FixedOffset::east(0).timestamp(0, 0),
+        message: None,
+        id: id.into(),
+    };
+    let commits = vec![
+        commit("1", &["services/payments/src/lib.rs", "README.md"]),
+        commit("2", &["services/search/src/lib.rs"]),
+        commit("3", &["services/payments/Cargo.toml"]),
+    ];
+
+    let filtered =
+        filter_commits_by_paths(commits, &[Pattern::new("services/payments/**").unwrap()]);
+    assert_eq!(
+        filtered.iter().map(|c| c.id.as_str()).collect::<Vec<_>>(),
+        vec!["1", "3"]
+    );
+}
+
+#[cfg(test)]
+fn test_release(version: &str, days: i64, finalized: bool, commit: &str) -> ReleaseInfo {
+    use crate::api::LastCommit;
+    use chrono::{Duration, Utc};
+
+    let date_created = Utc::now() - Duration::days(days);
+    ReleaseInfo {
+        version: version.into(),
+        url: None,
+        date_created,
+        date_released: if finalized { Some(date_created) } else { None },
+        last_event: None,
+        new_groups: 0,
+        projects: vec![],
+        last_commit: Some(LastCommit { id: commit.into() }),
+        last_deploy: None,
+    }
+}
+
+#[test]
+fn test_find_previous_release_latest_finalized() {
+    let releases = vec![
+        test_release("1.0.0", 3, true, "a"),
+        test_release("1.1.0", 2, true, "b"),
+        test_release("1.2.0-beta", 1, false, "c"),
+        test_release("1.2.0", 0, false, "d"),
+    ];
+    assert_eq!(
+        find_previous_release(
+            None,
+            &releases,
+            "1.2.0",
+            PreviousReleaseStrategy::LatestFinalized
+        )
+        .unwrap(),
+        Some(PreviousRelease {
+            version: "1.1.0".into(),
+            commit: "b".into(),
+        })
+    );
+}
+
+#[test]
+fn test_find_previous_release_on_same_branch() {
+    let dir = tempdir().expect("Failed to generate temp dir.");
+    test_initialize(dir.path());
+    git_commit_test(dir.path(), "foo.js", b"foo", "\"first commit\"");
+    let repo = git2::Repository::open(dir.path()).expect("Failed");
+    let first = repo
+        .head()
+        .unwrap()
+        .peel_to_commit()
+        .unwrap()
+        .id()
+        .to_string();
+    git_commit_test(dir.path(), "bar.js", b"bar", "\"second commit\"");
+
+    let releases = vec![
+        test_release("main-1", 2, true, &first),
+        // a newer release built from another branch
+        test_release(
+            "feature-1",
+            1,
+            true,
+            "0123456789abcdef0123456789abcdef01234567",
+        ),
+    ];
+    assert_eq!(
+        find_previous_release(
+            Some(&repo),
+            &releases,
+            "main-2",
+            PreviousReleaseStrategy::SameBranch
+        )
+        .unwrap(),
+        Some(PreviousRelease {
+            version: "main-1".into(),
+            commit: first,
+        })
+    );
+}
@@ -0,0 +1,102 @@
+//! Polls directories for new or modified files.
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use walkdir::WalkDir;
+
+/// Detects files that are added to or modified in a set of directories.
+///
+/// A file is only reported once it has not changed between two polls, so
+/// that files which are still being written are not picked up too early.
+pub struct DirWatcher {
+    paths: Vec<PathBuf>,
+    seen: HashMap<PathBuf, SystemTime>,
+    pending: HashMap<PathBuf, SystemTime>,
+}
+
+impl DirWatcher {
+    /// Creates a watcher that ignores all files that exist already.
+    pub fn new(paths: Vec<PathBuf>) -> DirWatcher {
+        let mut rv = DirWatcher {
+            paths,
+            seen: HashMap::new(),
+            pending: HashMap::new(),
+        };
+        rv.seen = rv.scan();
+        rv
+    }
+
+    fn scan(&self) -> HashMap<PathBuf, SystemTime> {
+        let mut rv = HashMap::new();
+        for path in &self.paths {
+            for entry in WalkDir::new(path)
+                .follow_links(true)
+                .into_iter()
+                .filter_map(Result::ok)
+            {
+                if let Ok(metadata) = entry.metadata() {
+                    if let (true, Ok(modified)) = (metadata.is_file(), metadata.modified()) {
+                        rv.insert(entry.into_path(), modified);
+                    }
+                }
+            }
+        }
+        rv
+    }
+
+    /// Returns the files that changed since they were last reported.
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let mut changed = vec![];
+        let mut pending = HashMap::new();
+
+        for (path, modified) in self.scan() {
+            if self.seen.get(&path) == Some(&modified) {
+                continue;
+            }
+            if self.pending.get(&path) == Some(&modified) {
+                self.seen.insert(path.clone(), modified);
+                changed.push(path);
+            } else {
+                pending.insert(path, modified);
+            }
+        }
+
+        self.pending = pending;
+        changed.sort();
+        changed
+    }
+
+    /// Reports the given files again on the next poll, e.g. after a failed
+    /// upload.
+    pub fn retry(&mut self, paths: &[PathBuf]) {
+        for path in paths {
+            if let Some(modified) = self.seen.remove(path) {
+                self.pending.insert(path.clone(), modified);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_dir_watcher() {
+    use std::fs;
+
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("existing.sym"), "MODULE").unwrap();
+
+    let mut watcher = DirWatcher::new(vec![dir.path().to_path_buf()]);
+    assert!(watcher.poll().is_empty());
+
+    let path = dir.path().join("App.dSYM/Contents/Resources/DWARF/App");
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, "DWARF").unwrap();
+
+    // new files are reported once they are stable
+    assert!(watcher.poll().is_empty());
+    assert_eq!(watcher.poll(), vec![path.clone()]);
+    assert!(watcher.poll().is_empty());
+
+    watcher.retry(std::slice::from_ref(&path));
+    assert_eq!(watcher.poll(), vec![path]);
+}
@@ -0,0 +1,93 @@
+//! Sends signed webhooks about releases and deploys to external systems.
+use chrono::Utc;
+use clap::ArgMatches;
+use failure::Error;
+use log::warn;
+use serde::Serialize;
+
+use crate::api::Api;
+use crate::config::Config;
+use crate::utils::digest::{hmac_sha256, to_hex};
+
+/// The header that carries the signature of a webhook body.
+pub const SIGNATURE_HEADER: &str = "X-Sentry-Cli-Signature";
+
+/// The header that carries the Unix timestamp a webhook was sent at.
+pub const TIMESTAMP_HEADER: &str = "X-Sentry-Cli-Timestamp";
+
+/// A webhook endpoint configured with `--webhook` or in the config.
+pub struct Webhook {
+    url: String,
+    secret: Option<String>,
+}
+
+#[derive(Serialize)]
+struct WebhookBody<'a, P: Serialize> {
+    event: &'a str,
+    org: &'a str,
+    #[serde(flatten)]
+    payload: P,
+}
+
+/// Returns the hex encoded HMAC-SHA256 signature of `timestamp.body`.
+///
+/// Signing the timestamp lets receivers reject replayed webhooks.
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut message = format!("{}.", timestamp).into_bytes();
+    message.extend_from_slice(body);
+    format!(
+        "sha256={}",
+        to_hex(&hmac_sha256(secret.as_bytes(), &message))
+    )
+}
+
+impl Webhook {
+    /// Returns the configured webhook, if any.
+    pub fn from_matches(matches: &ArgMatches<'_>) -> Option<Webhook> {
+        let config = Config::current();
+        let url = matches
+            .value_of("webhook")
+            .map(str::to_owned)
+            .or_else(|| config.get_webhook_url())?;
+        Some(Webhook {
+            url,
+            secret: config.get_webhook_secret(),
+        })
+    }
+
+    /// Posts an event to the webhook.
+    ///
+    /// Delivery failures only print a warning since the webhook is sent
+    /// after the actual change was made.
+    pub fn send<P: Serialize>(&self, event: &str, org: &str, payload: P) -> Result<(), Error> {
+        let body = serde_json::to_vec(&WebhookBody {
+            event,
+            org,
+            payload,
+        })?;
+        let timestamp = Utc::now().timestamp();
+        let signature = self
+            .secret
+            .as_ref()
+            .map(|secret| sign(secret, timestamp, &body));
+        if let Err(err) =
+            Api::current().send_webhook(&self.url, body, timestamp, signature.as_deref())
+        {
+            warn!("Could not deliver webhook to {}: {}", self.url, err);
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_sign() {
+    assert_eq!(
+        sign("Jefe", 1623319200, b"what do ya want for nothing?"),
+        "sha256=a6c7c5c54f9b3928b44bbe77896f9c66e7eaf77298a4c6cfe364f1883a686258"
+    );
+    // the timestamp is signed along with the body
+    assert_ne!(
+        sign("Jefe", 1623319200, b"{}"),
+        sign("Jefe", 1623319201, b"{}")
+    );
+}
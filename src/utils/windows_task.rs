@@ -0,0 +1,247 @@
+//! Runs monitors as Windows scheduled tasks.
+//!
+//! `monitors install-task` registers a task with `schtasks` that invokes
+//! `monitors run`, so the crontab of the monitor has to be expressed as a
+//! Task Scheduler schedule.  Failures of the task are also written to the
+//! Application log, where they show up for administrators that do not look
+//! at Sentry.
+#[cfg(not(windows))]
+use std::env;
+
+use failure::{bail, Error};
+
+/// The source of the entries written to the Windows Event Log.
+pub const EVENT_SOURCE: &str = "sentry-cli";
+
+fn cron_number(field: &str, min: u32, max: u32) -> Option<u32> {
+    field.parse().ok().filter(|n| (min..=max).contains(n))
+}
+
+/// Parses `*` or `*/N`.  cron starts over at the start of the period, so
+/// only steps that divide it evenly repeat at a fixed interval.
+fn cron_step(field: &str, period: u32) -> Option<u32> {
+    match field {
+        "*" => Some(1),
+        _ => {
+            cron_number(field.strip_prefix("*/")?, 1, period).filter(|n| period.is_multiple_of(*n))
+        }
+    }
+}
+
+fn cron_weekday(field: &str) -> Option<&'static str> {
+    const DAYS: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+    if let Some(n) = cron_number(field, 0, 7) {
+        return Some(DAYS[n as usize % 7]);
+    }
+    DAYS.iter()
+        .find(|day| day.eq_ignore_ascii_case(field))
+        .copied()
+}
+
+fn schedule_args(crontab: &str) -> Option<Vec<String>> {
+    let fields: Vec<_> = crontab.split_whitespace().collect();
+    let (minute, hour, dom, dow) = match fields[..] {
+        // cron runs on either of the days if both are restricted
+        [minute, hour, dom, "*", dow] if dom == "*" || dow == "*" => (minute, hour, dom, dow),
+        _ => return None,
+    };
+    let start = |hour: u32, minute: u32| format!("{:02}:{:02}", hour, minute);
+
+    if hour == "*" && dom == "*" && dow == "*" {
+        if let Some(every) = cron_step(minute, 60) {
+            return Some(vec![
+                "/SC".into(),
+                "MINUTE".into(),
+                "/MO".into(),
+                every.to_string(),
+                "/ST".into(),
+                start(0, 0),
+            ]);
+        }
+    }
+    let minute = cron_number(minute, 0, 59)?;
+    let hour = match cron_number(hour, 0, 23) {
+        Some(hour) => hour,
+        None if dom == "*" && dow == "*" => {
+            let every = cron_step(hour, 24)?;
+            return Some(vec![
+                "/SC".into(),
+                "HOURLY".into(),
+                "/MO".into(),
+                every.to_string(),
+                "/ST".into(),
+                start(0, minute),
+            ]);
+        }
+        None => return None,
+    };
+    let mut args: Vec<String> = if dow != "*" {
+        let days = dow
+            .split(',')
+            .map(cron_weekday)
+            .collect::<Option<Vec<_>>>()?;
+        vec!["/SC".into(), "WEEKLY".into(), "/D".into(), days.join(",")]
+    } else if dom != "*" {
+        let day = cron_number(dom, 1, 31)?;
+        vec!["/SC".into(), "MONTHLY".into(), "/D".into(), day.to_string()]
+    } else {
+        vec!["/SC".into(), "DAILY".into()]
+    };
+    args.extend(vec!["/ST".into(), start(hour, minute)]);
+    Some(args)
+}
+
+/// Converts a crontab schedule into the schedule arguments of
+/// `schtasks /Create`.
+///
+/// Only schedules that run every few minutes or hours, daily, on some
+/// days of the week or on a day of the month are supported.  Steps have to
+/// divide an hour or a day evenly.
+pub fn task_schedule(crontab: &str) -> Result<Vec<String>, Error> {
+    match schedule_args(crontab) {
+        Some(args) => Ok(args),
+        None => bail!(
+            "The schedule '{}' cannot be expressed as a scheduled task. Use a \
+             schedule that runs every few minutes or hours, daily, weekly or on \
+             a day of the month. Steps have to divide an hour or a day evenly.",
+            crontab
+        ),
+    }
+}
+
+/// Returns the IANA name of the timezone scheduled tasks start in.
+///
+/// Task Scheduler uses the local time of the machine.  Elsewhere, where
+/// tasks are only printed, `TZ` takes precedence.
+pub fn local_timezone() -> Option<String> {
+    #[cfg(not(windows))]
+    {
+        if let Ok(tz) = env::var("TZ") {
+            let tz = tz.trim_start_matches(':');
+            if !tz.is_empty() {
+                return Some(tz.to_string());
+            }
+        }
+    }
+    iana_time_zone::get_timezone().ok()
+}
+
+/// Quotes an argument for a Windows command line, following the rules of
+/// `CommandLineToArgvW`.
+pub fn quote_arg(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains(&[' ', '\t', '"'][..]) {
+        return arg.to_string();
+    }
+    let mut rv = String::from("\"");
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            // backslashes before a quote and the quote itself are escaped
+            '"' => {
+                rv.push_str(&"\\".repeat(backslashes + 1));
+                backslashes = 0;
+            }
+            _ => backslashes = 0,
+        }
+        rv.push(c);
+    }
+    // as are backslashes before the closing quote
+    rv.push_str(&"\\".repeat(backslashes));
+    rv.push('"');
+    rv
+}
+
+/// Joins arguments into a Windows command line.
+pub fn command_line<S: AsRef<str>>(args: &[S]) -> String {
+    args.iter()
+        .map(|arg| quote_arg(arg.as_ref()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Writes an error to the Application log of the Windows Event Log.
+#[cfg(windows)]
+pub fn report_to_event_log(message: &str) -> Result<(), Error> {
+    use std::ffi::OsStr;
+    use std::io;
+    use std::os::windows::ffi::OsStrExt;
+    use std::ptr;
+
+    use winapi::um::winbase::{DeregisterEventSourceW, RegisterEventSourceW, ReportEventW};
+    use winapi::um::winnt::EVENTLOG_ERROR_TYPE;
+
+    fn wide(s: &str) -> Vec<u16> {
+        OsStr::new(s).encode_wide().chain(Some(0)).collect()
+    }
+
+    let source = wide(EVENT_SOURCE);
+    let message = wide(message);
+    let handle = unsafe { RegisterEventSourceW(ptr::null(), source.as_ptr()) };
+    if handle.is_null() {
+        return Err(io::Error::last_os_error().into());
+    }
+    let mut strings = [message.as_ptr()];
+    let rv = unsafe {
+        ReportEventW(
+            handle,
+            EVENTLOG_ERROR_TYPE,
+            0,
+            1,
+            ptr::null_mut(),
+            1,
+            0,
+            strings.as_mut_ptr(),
+            ptr::null_mut(),
+        )
+    };
+    let err = io::Error::last_os_error();
+    unsafe {
+        DeregisterEventSourceW(handle);
+    }
+    if rv == 0 {
+        return Err(err.into());
+    }
+    Ok(())
+}
+
+/// Writes an error to the Application log of the Windows Event Log.
+#[cfg(not(windows))]
+pub fn report_to_event_log(_message: &str) -> Result<(), Error> {
+    bail!("The Windows Event Log is only available on Windows");
+}
+
+#[test]
+fn test_task_schedule() {
+    let schedule = |crontab| task_schedule(crontab).unwrap().join(" ");
+    assert_eq!(schedule("*/15 * * * *"), "/SC MINUTE /MO 15 /ST 00:00");
+    assert_eq!(schedule("* * * * *"), "/SC MINUTE /MO 1 /ST 00:00");
+    assert_eq!(schedule("5 * * * *"), "/SC HOURLY /MO 1 /ST 00:05");
+    assert_eq!(schedule("0 */6 * * *"), "/SC HOURLY /MO 6 /ST 00:00");
+    assert_eq!(schedule("30 2 * * *"), "/SC DAILY /ST 02:30");
+    assert_eq!(schedule("0 9 * * 1,fri"), "/SC WEEKLY /D MON,FRI /ST 09:00");
+    assert_eq!(schedule("0 9 * * 7"), "/SC WEEKLY /D SUN /ST 09:00");
+    assert_eq!(schedule("0 0 15 * *"), "/SC MONTHLY /D 15 /ST 00:00");
+
+    assert!(task_schedule("0 9 * 1 *").is_err());
+    assert!(task_schedule("0 9 1 * 1").is_err());
+    assert!(task_schedule("0 9-17 * * *").is_err());
+    assert!(task_schedule("61 * * * *").is_err());
+    assert!(task_schedule("*/7 * * * *").is_err());
+    assert!(task_schedule("0 */5 * * *").is_err());
+    assert!(task_schedule("@daily").is_err());
+}
+
+#[test]
+fn test_command_line() {
+    assert_eq!(
+        command_line(&[
+            r"C:\Program Files\sentry-cli.exe",
+            "run",
+            "",
+            r#"say "hi""#,
+            r"C:\dir with space\",
+        ]),
+        r#""C:\Program Files\sentry-cli.exe" run "" "say \"hi\"" "C:\dir with space\\""#
+    );
+}
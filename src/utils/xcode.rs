@@ -282,6 +282,11 @@ impl InfoPlist {
         format!("{}@{}", self.bundle_id(), self.version())
     }
 
+    /// Returns the release name including the build number.
+    pub fn get_release_name_with_build(&self) -> String {
+        format!("{}+{}", self.get_release_name(), self.build())
+    }
+
     pub fn version(&self) -> &str {
         &self.version
     }
@@ -303,7 +308,11 @@ impl InfoPlist {
 /// the xcode console and continue in the background.  This becomes
 /// a dummy shim for non xcode runs or platforms.
 pub struct MayDetach<'a> {
-    output_file: Option<TempFile>,
+    output_file: Option<PathBuf>,
+    #[allow(dead_code)]
+    temp_file: Option<TempFile>,
+    #[allow(dead_code)]
+    log_file: Option<PathBuf>,
     #[allow(dead_code)]
     task_name: &'a str,
 }
@@ -312,6 +321,8 @@ impl<'a> MayDetach<'a> {
     fn new(task_name: &'a str) -> MayDetach<'a> {
         MayDetach {
             output_file: None,
+            temp_file: None,
+            log_file: None,
             task_name,
         }
     }
@@ -321,6 +332,12 @@ impl<'a> MayDetach<'a> {
         self.output_file.is_some()
     }
 
+    /// Writes the output to the given file when detaching instead of a
+    /// temporary file, so that it can be inspected after the build.
+    pub fn set_log_file<P: AsRef<Path>>(&mut self, path: P) {
+        self.log_file = Some(path.as_ref().to_path_buf());
+    }
+
     /// If we are launched from xcode this detaches us from the xcode console
     /// and continues execution in the background.  From this moment on output
     /// is captured and the user is notified with notifications.
@@ -330,15 +347,24 @@ impl<'a> MayDetach<'a> {
             return Ok(false);
         }
 
-        println!("Continuing in background.");
+        let output_file = match self.log_file {
+            Some(ref path) => {
+                fs::File::create(path)
+                    .with_context(|_| format!("Could not create log file {}", path.display()))?;
+                println!("Continuing in background. Logging to {}", path.display());
+                path.clone()
+            }
+            None => {
+                let temp_file = TempFile::create()?;
+                let path = temp_file.path().to_path_buf();
+                self.temp_file = Some(temp_file);
+                println!("Continuing in background.");
+                path
+            }
+        };
+
         show_notification("Sentry", &format!("{} starting", self.task_name))?;
-        let output_file = TempFile::create()?;
-        daemonize_redirect(
-            Some(output_file.path()),
-            Some(output_file.path()),
-            ChdirMode::NoChdir,
-        )
-        .unwrap();
+        daemonize_redirect(Some(&output_file), Some(&output_file), ChdirMode::NoChdir).unwrap();
         self.output_file = Some(output_file);
         Ok(true)
     }
@@ -368,7 +394,7 @@ impl<'a> MayDetach<'a> {
                 if let Some(ref output_file) = md.output_file {
                     crate::utils::system::print_error(&err);
                     if md.show_critical_info()? {
-                        open::that(&output_file.path())?;
+                        open::that(output_file)?;
                         std::thread::sleep(Duration::from_millis(5000));
                     }
                 }
@@ -505,6 +531,61 @@ pub fn show_notification(title: &str, message: &str) -> Result<(), Error> {
     Ok(())
 }
 
+/// Reports progress and problems of a build phase as Xcode build issues.
+///
+/// Xcode turns lines of the build log that start with `note:`, `warning:` or
+/// `error:` into entries of the issue navigator.  When disabled, only warnings
+/// are printed in the regular output format.
+pub struct XcodeIssues {
+    enabled: bool,
+}
+
+impl XcodeIssues {
+    pub fn new(enabled: bool) -> XcodeIssues {
+        XcodeIssues { enabled }
+    }
+
+    pub fn note(&self, message: &str) {
+        if self.enabled {
+            println!("{}", format_xcode_issue("note", message));
+        }
+    }
+
+    pub fn warning(&self, message: &str) {
+        if self.enabled {
+            println!("{}", format_xcode_issue("warning", message));
+        } else {
+            println!("Warning: {}", message);
+        }
+    }
+
+    /// Reports an error including its causes on a single line.
+    pub fn error(&self, err: &Error) {
+        if self.enabled {
+            let message = err
+                .iter_chain()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(": ");
+            println!("{}", format_xcode_issue("error", &message));
+        }
+    }
+}
+
+/// Xcode only shows the first line of an issue.
+fn format_xcode_issue(kind: &str, message: &str) -> String {
+    let message = message.split_whitespace().collect::<Vec<_>>().join(" ");
+    format!("{}: Sentry: {}", kind, message)
+}
+
+#[test]
+fn test_format_xcode_issue() {
+    assert_eq!(
+        format_xcode_issue("warning", "build produced\nno sourcemaps."),
+        "warning: Sentry: build produced no sourcemaps."
+    );
+}
+
 #[test]
 fn test_expansion() {
     let mut vars = HashMap::new();
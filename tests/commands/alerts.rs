@@ -0,0 +1,165 @@
+use std::fs;
+
+use assert_cmd::Command;
+use mockito::{mock, Matcher};
+use predicates::prelude::*;
+use predicates::str::contains;
+
+use crate::common;
+
+#[test]
+fn creates_alerts_from_spec() {
+    let _issue = mock("POST", "/api/0/projects/wat-org/wat-project/rules/")
+        .match_body(Matcher::PartialJson(serde_json::json!({
+            "name": "New issues in wat-project",
+            "actionMatch": "all",
+            "frequency": 30,
+        })))
+        .with_status(201)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"id":"7","name":"New issues in wat-project","conditions":[],"actions":[]}"#)
+        .expect(1)
+        .create();
+    let _metric = mock("POST", "/api/0/projects/wat-org/wat-project/alert-rules/")
+        .match_body(Matcher::Json(serde_json::json!({
+            "name": "Error rate",
+            "aggregate": "count()",
+            "timeWindow": 60,
+            "owner": "team:backend",
+        })))
+        .with_status(201)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"id":"42","name":"Error rate","aggregate":"count()"}"#)
+        .expect(1)
+        .create();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("alerts.yml");
+    fs::write(
+        &path,
+        "- type: issue\n  name: New issues in ${PROJECT}\n  actionMatch: all\n  frequency: 30\n  \
+         conditions: [{id: sentry.rules.conditions.first_seen_event.FirstSeenEventCondition}]\n\
+         - type: metric\n  name: Error rate\n  aggregate: count()\n  timeWindow: 60\n  \
+         owner: team:${TEAM}\n",
+    )
+    .unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec!["alerts", "create", "--var", "TEAM=backend"])
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout(
+            contains("Created issue alert New issues in wat-project (7)")
+                .and(contains("Created metric alert Error rate (42)")),
+        );
+
+    _issue.assert();
+    _metric.assert();
+}
+
+#[test]
+fn expands_variables_in_string_values_only() {
+    let _issue = mock("POST", "/api/0/projects/wat-org/wat-project/rules/")
+        .match_body(Matcher::PartialJson(serde_json::json!({
+            "name": "Errors\nactionMatch: none",
+            "frequency": 30,
+        })))
+        .with_status(201)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"id":"7","name":"Errors"}"#)
+        .expect(1)
+        .create();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("alerts.yml");
+    fs::write(
+        &path,
+        "type: issue\nname: ${NAME}\nfrequency: ${FREQUENCY}\n",
+    )
+    .unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "alerts",
+            "create",
+            "--var",
+            "NAME=Errors\nactionMatch: none",
+            "--var",
+            "FREQUENCY=30",
+        ])
+        .arg(&path)
+        .assert()
+        .success();
+
+    _issue.assert();
+}
+
+#[test]
+fn fails_for_undefined_variables() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("alerts.yml");
+    fs::write(&path, "type: issue\nname: ${SERVICE} errors\n").unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec!["alerts", "create"])
+        .arg(&path)
+        .assert()
+        .failure()
+        .stderr(contains("Undefined variables in spec: SERVICE"));
+}
+
+#[test]
+fn lists_alerts_as_json() {
+    let _issue = mock("GET", "/api/0/projects/wat-org/wat-project/rules/")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"[{"id":"7","name":"New issues","conditions":[],"actions":[]}]"#)
+        .create();
+    let _metric = mock("GET", "/api/0/projects/wat-org/wat-project/alert-rules/")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"[{"id":"42","name":"Error rate","aggregate":"count()"}]"#)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec!["alerts", "list", "--json"])
+        .assert()
+        .success()
+        .stdout(
+            contains(r#""issueAlerts""#)
+                .and(contains(r#""name": "New issues""#))
+                .and(contains(r#""aggregate": "count()""#)),
+        );
+}
+
+#[test]
+fn deletes_metric_alert() {
+    let _server = mock(
+        "DELETE",
+        "/api/0/projects/wat-org/wat-project/alert-rules/42/",
+    )
+    .with_status(204)
+    .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "alerts",
+            "delete",
+            "42",
+            "--type",
+            "metric",
+            "--confirm",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("Deleted metric alert 42!"));
+}
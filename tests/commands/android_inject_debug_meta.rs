@@ -0,0 +1,73 @@
+use std::fs;
+use std::io::{Read, Write};
+
+use assert_cmd::Command;
+use predicates::str::contains;
+use zip::write::FileOptions;
+use zip::CompressionMethod;
+
+use crate::common;
+
+#[test]
+fn injects_debug_meta_into_apk() {
+    let dir = tempfile::tempdir().unwrap();
+    let apk = dir.path().join("app.apk");
+    let output = dir.path().join("app-injected.apk");
+
+    let mut zip = zip::ZipWriter::new(fs::File::create(&apk).unwrap());
+    zip.start_file("AndroidManifest.xml", FileOptions::default())
+        .unwrap();
+    zip.write_all(b"manifest").unwrap();
+    zip.start_file("META-INF/CERT.RSA", FileOptions::default())
+        .unwrap();
+    zip.write_all(b"signature").unwrap();
+    zip.start_file(
+        "lib/arm64-v8a/libwat.so",
+        FileOptions::default().compression_method(CompressionMethod::Stored),
+    )
+    .unwrap();
+    zip.write_all(b"\x7fELF").unwrap();
+    zip.finish().unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args([
+            "android",
+            "inject-debug-meta",
+            "--proguard-uuid",
+            "8f8ae3e7-c2af-5f25-9f4d-1c1a0c2ea93e",
+            "--output",
+            output.to_str().unwrap(),
+            apk.to_str().unwrap(),
+        ])
+        .assert()
+        .success()
+        .stdout(contains("injected debug metadata"));
+
+    let mut archive = zip::ZipArchive::new(fs::File::open(&output).unwrap()).unwrap();
+    assert!(archive.by_name("META-INF/CERT.RSA").is_err());
+
+    let lib = archive.by_name("lib/arm64-v8a/libwat.so").unwrap();
+    assert_eq!(lib.data_start() % 4096, 0);
+    drop(lib);
+
+    let mut meta = String::new();
+    archive
+        .by_name("assets/sentry-debug-meta.properties")
+        .unwrap()
+        .read_to_string(&mut meta)
+        .unwrap();
+    assert!(meta.contains("io.sentry.ProguardUuids=8f8ae3e7-c2af-5f25-9f4d-1c1a0c2ea93e"));
+}
+
+#[test]
+fn requires_ids_to_inject() {
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["android", "inject-debug-meta", "app.apk"])
+        .assert()
+        .failure()
+        .stderr(contains("Nothing to inject"));
+}
@@ -0,0 +1,417 @@
+use assert_cmd::Command;
+use std::fs;
+
+use mockito::{mock, server_address, Matcher};
+use predicates::prelude::*;
+use predicates::str::contains;
+
+use crate::common;
+
+const CHECKIN: &str =
+    r#"{"id":"a1b2c3d4-0000-4000-8000-000000000001","status":"in_progress","duration":null}"#;
+
+#[test]
+fn embeds_monitor_slug() {
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["bash-hook", "--monitor-slug", "nightly-backup"])
+        .assert()
+        .success()
+        .stdout(
+            contains(r#"_SENTRY_MONITOR_SLUG="nightly-backup""#)
+                .and(contains(r#"_SENTRY_MONITOR_ORG="""#)),
+        );
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["bash-hook", "--monitor-slug", "$(reboot)"])
+        .assert()
+        .failure()
+        .stderr(contains("Invalid monitor slug"));
+}
+
+#[test]
+fn reports_checkins() {
+    let start = mock(
+        "POST",
+        "/api/0/organizations/wat-org/monitors/nightly-backup/checkins/",
+    )
+    .match_body(Matcher::Json(serde_json::json!({"status": "in_progress"})))
+    .with_status(201)
+    .with_header("content-type", "application/json")
+    .with_body(CHECKIN)
+    .create();
+    let finish = mock(
+        "PUT",
+        "/api/0/organizations/wat-org/monitors/nightly-backup/checkins/a1b2c3d4-0000-4000-8000-000000000001/",
+    )
+    .match_body(Matcher::Json(
+        serde_json::json!({"status": "ok", "duration": 3000}),
+    ))
+    .with_status(200)
+    .with_header("content-type", "application/json")
+    .with_body(CHECKIN)
+    .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args([
+            "bash-hook",
+            "--start-checkin",
+            "--monitor-slug",
+            "nightly-backup",
+        ])
+        .assert()
+        .success()
+        .stdout("a1b2c3d4-0000-4000-8000-000000000001\n");
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args([
+            "bash-hook",
+            "--finish-checkin",
+            "a1b2c3d4-0000-4000-8000-000000000001",
+            "--exit-code",
+            "0",
+            "--duration",
+            "3",
+            "--monitor-slug",
+            "nightly-backup",
+        ])
+        .assert()
+        .success();
+
+    start.assert();
+    finish.assert();
+}
+
+#[test]
+fn sends_redacted_command_breadcrumbs() {
+    let store = mock("POST", "/api/1/store/")
+        .match_body(Matcher::AllOf(vec![
+            Matcher::Regex(r#""message":"deploy --\[redacted\] prod""#.into()),
+            Matcher::Regex(r#""category":"command","level":"error""#.into()),
+            Matcher::Regex(r#""exit_code":3"#.into()),
+        ]))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("{}")
+        .expect(1)
+        .create();
+
+    let dir = tempfile::tempdir().unwrap();
+    let traceback = dir.path().join("traceback");
+    let log = dir.path().join("log");
+    let commands = dir.path().join("commands");
+    fs::write(&traceback, "@command:deploy\n@exit_code:3\n").unwrap();
+    fs::write(&log, "").unwrap();
+    fs::write(
+        &commands,
+        "1600000000\t0\tcd /srv\n1600000001\t3\tdeploy --token=s3cr3t prod\n",
+    )
+    .unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env(
+            "SENTRY_DSN",
+            format!("http://lolnope@{}/1", server_address()),
+        )
+        .args([
+            "bash-hook",
+            "--send-event",
+            "--traceback",
+            traceback.to_str().unwrap(),
+            "--log",
+            log.to_str().unwrap(),
+            "--commands",
+            commands.to_str().unwrap(),
+            "--redact",
+            r"token=\S+",
+            "--no-environ",
+        ])
+        .assert()
+        .success();
+
+    store.assert();
+}
+
+#[test]
+fn spools_events_with_async() {
+    let store = mock("POST", "/api/1/envelope/")
+        .match_body(Matcher::Regex(
+            r#""value":"command backup exited with status 7""#.into(),
+        ))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("{}")
+        .expect(1)
+        .create();
+
+    let dir = tempfile::tempdir().unwrap();
+    let spool = dir.path().join("spool");
+    let traceback = dir.path().join("traceback");
+    let log = dir.path().join("log");
+    fs::write(&traceback, "@command:backup\n@exit_code:7\n").unwrap();
+    fs::write(&log, "").unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env(
+            "SENTRY_DSN",
+            format!("http://lolnope@{}/1", server_address()),
+        )
+        .env("SENTRY_SPOOL_DIR", &spool)
+        .args([
+            "bash-hook",
+            "--send-event",
+            "--async",
+            "--traceback",
+            traceback.to_str().unwrap(),
+            "--log",
+            log.to_str().unwrap(),
+            "--no-environ",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"^[0-9a-f-]{36}\n$").unwrap());
+
+    // the background flush and this one never send the same event twice
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env("SENTRY_SPOOL_DIR", &spool)
+        .args(["bash-hook", "--flush"])
+        .assert()
+        .success();
+
+    for _ in 0..100 {
+        if fs::read_dir(&spool).unwrap().next().is_none() {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    store.assert();
+}
+
+#[test]
+fn prints_shell_variants() {
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["bash-hook", "--shell", "zsh", "--command-breadcrumbs"])
+        .assert()
+        .success()
+        .stdout(
+            contains("trap '_sentry_err_trap' ZERR")
+                .and(contains("$ZSH_DEBUG_CMD"))
+                .and(contains("___").not()),
+        );
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["bash-hook", "--shell", "sh"])
+        .assert()
+        .success()
+        .stdout(
+            contains("mkfifo")
+                .and(contains("_sentry_err_trap").not())
+                .and(contains("BASH_").not())
+                .and(contains("___").not()),
+        );
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["bash-hook", "--shell", "sh", "--command-breadcrumbs"])
+        .assert()
+        .failure()
+        .stderr(contains("requires bash or zsh"));
+}
+
+#[test]
+fn reports_sh_script_failure() {
+    let dir = tempfile::tempdir().unwrap();
+    let script = dir.path().join("deploy.sh");
+    let hook = Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["bash-hook", "--shell", "sh", "--no-environ"])
+        .output()
+        .unwrap()
+        .stdout;
+    fs::write(
+        &script,
+        format!(
+            "{}\necho deploying\nfalse\necho unreachable\n",
+            String::from_utf8(hook).unwrap()
+        ),
+    )
+    .unwrap();
+
+    let store = mock("POST", "/api/1/store/")
+        .match_body(Matcher::AllOf(vec![
+            Matcher::Regex(format!(
+                r#""value":"command {} exited with status 1""#,
+                regex::escape(script.to_str().unwrap())
+            )),
+            Matcher::Regex(r#""message":"deploying""#.into()),
+        ]))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("{}")
+        .expect(1)
+        .create();
+
+    Command::new("sh")
+        .envs(common::get_base_env())
+        .env(
+            "SENTRY_DSN",
+            format!("http://lolnope@{}/1", server_address()),
+        )
+        .arg(&script)
+        .assert()
+        .code(1)
+        .stdout("deploying\n");
+
+    store.assert();
+}
+
+#[test]
+fn attaches_log_tail() {
+    let dir = tempfile::tempdir().unwrap();
+    let service_log = dir.path().join("service.log");
+    fs::write(
+        &service_log,
+        "starting\nlistening\ncrashed: out of memory\n",
+    )
+    .unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args([
+            "bash-hook",
+            "--log",
+            service_log.to_str().unwrap(),
+            "--log-max-size",
+            "30",
+        ])
+        .assert()
+        .success()
+        .stdout(contains(format!(
+            "--attach-log '{}' --log-max-size 30",
+            service_log.display()
+        )));
+
+    let envelope = mock("POST", "/api/1/envelope/")
+        .match_header("content-type", "application/x-sentry-envelope")
+        .match_body(Matcher::AllOf(vec![
+            Matcher::Regex(r#""value":"command restart exited with status 2""#.into()),
+            Matcher::Regex(r#""type":"attachment""#.into()),
+            Matcher::Regex(r#""filename":"service.log""#.into()),
+            Matcher::Regex(
+                "\n\\[\\.\\.\\. 19 bytes truncated \\.\\.\\.\\]\ncrashed: out of memory\n".into(),
+            ),
+        ]))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("{}")
+        .expect(1)
+        .create();
+
+    let traceback = dir.path().join("traceback");
+    let log = dir.path().join("log");
+    fs::write(&traceback, "@command:restart\n@exit_code:2\n").unwrap();
+    fs::write(&log, "").unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env(
+            "SENTRY_DSN",
+            format!("http://lolnope@{}/1", server_address()),
+        )
+        .args([
+            "bash-hook",
+            "--send-event",
+            "--traceback",
+            traceback.to_str().unwrap(),
+            "--log",
+            log.to_str().unwrap(),
+            "--attach-log",
+            service_log.to_str().unwrap(),
+            "--log-max-size",
+            "30",
+            "--no-environ",
+        ])
+        .assert()
+        .success();
+
+    envelope.assert();
+}
+
+#[test]
+fn sends_script_transaction() {
+    let envelope = mock("POST", "/api/1/envelope/")
+        .match_body(Matcher::AllOf(vec![
+            Matcher::Regex(r#""type":"transaction""#.into()),
+            Matcher::Regex(r#""transaction":"nightly.sh""#.into()),
+            Matcher::Regex(r#""description":"db-backup""#.into()),
+            Matcher::Regex(r#""op":"script.section""#.into()),
+        ]))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("{}")
+        .expect(1)
+        .create();
+
+    let dir = tempfile::tempdir().unwrap();
+    let spans = dir.path().join("spans");
+    fs::write(
+        &spans,
+        "run\t1600000000.5\nstart\t1600000001.25\tdb-backup\nend\t1600000030\nexit\t1600000031\n",
+    )
+    .unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env(
+            "SENTRY_DSN",
+            format!("http://lolnope@{}/1", server_address()),
+        )
+        .args([
+            "bash-hook",
+            "--send-transaction",
+            "--spans",
+            spans.to_str().unwrap(),
+            "--exit-code",
+            "0",
+            "--script",
+            "./nightly.sh",
+        ])
+        .assert()
+        .success();
+
+    envelope.assert();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["bash-hook", "--tracing"])
+        .assert()
+        .success()
+        .stdout(
+            contains(r#"_SENTRY_TRACING="1""#)
+                .and(contains("sentry_span_start()"))
+                .and(contains("___").not()),
+        );
+}
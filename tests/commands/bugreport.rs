@@ -0,0 +1,54 @@
+use std::fs;
+use std::io::Read;
+
+use assert_cmd::Command;
+use predicates::str::contains;
+
+use crate::common;
+
+#[test]
+fn bundles_crash_reports() {
+    let dir = tempfile::tempdir().unwrap();
+    let crash_dir = dir.path().join("crashes");
+    fs::create_dir(&crash_dir).unwrap();
+    fs::write(
+        crash_dir.join("crash-20211001-120000-42.json"),
+        r#"{"message":"boom"}"#,
+    )
+    .unwrap();
+    fs::write(crash_dir.join("notes.txt"), "not a crash report").unwrap();
+    let output = dir.path().join("bugreport.zip");
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env("SENTRY_CRASH_DIR", &crash_dir)
+        .args(["bugreport", "--output"])
+        .arg(&output)
+        .assert()
+        .success()
+        .stdout(contains("Wrote bug report with 1 crash report to"));
+
+    let mut zip = zip::ZipArchive::new(fs::File::open(&output).unwrap()).unwrap();
+    let mut report = String::new();
+    zip.by_name("report.json")
+        .unwrap()
+        .read_to_string(&mut report)
+        .unwrap();
+    let report: serde_json::Value = serde_json::from_str(&report).unwrap();
+    assert_eq!(report["org"], "wat-org");
+    assert_eq!(report["auth"], "token");
+    assert_eq!(report["environment"]["SENTRY_AUTH_TOKEN"], "[redacted]");
+    assert_eq!(report["environment"]["SENTRY_ORG"], "wat-org");
+    assert_eq!(
+        report["crash_reports"],
+        serde_json::json!(["crash-20211001-120000-42.json"])
+    );
+
+    let mut crash = String::new();
+    zip.by_name("crashes/crash-20211001-120000-42.json")
+        .unwrap()
+        .read_to_string(&mut crash)
+        .unwrap();
+    assert_eq!(crash, r#"{"message":"boom"}"#);
+}
@@ -0,0 +1,57 @@
+use assert_cmd::Command;
+
+use crate::common;
+
+#[test]
+fn detects_github_actions() {
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env("GITHUB_ACTIONS", "true")
+        .env("GITHUB_SHA", "f00ba4")
+        .env("GITHUB_REF", "refs/pull/7/merge")
+        .env("GITHUB_HEAD_REF", "it's-a-branch")
+        .env("GITHUB_REPOSITORY", "getsentry/sentry-cli")
+        .env("GITHUB_SERVER_URL", "https://github.com")
+        .env("GITHUB_RUN_ID", "42")
+        .args(["ci", "detect"])
+        .assert()
+        .success()
+        .stdout(
+            "export SENTRY_CI_PROVIDER='github'\n\
+             export SENTRY_CI_COMMIT_SHA='f00ba4'\n\
+             export SENTRY_CI_BRANCH='it'\\''s-a-branch'\n\
+             export SENTRY_CI_PR_NUMBER='7'\n\
+             export SENTRY_CI_BUILD_URL='https://github.com/getsentry/sentry-cli/actions/runs/42'\n\
+             export SENTRY_CI_REPOSITORY='getsentry/sentry-cli'\n",
+        );
+}
+
+#[test]
+fn prints_json() {
+    let output = Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env("GITLAB_CI", "true")
+        .env("CI_COMMIT_SHA", "f00ba4")
+        .env("CI_COMMIT_BRANCH", "main")
+        .env("CI_JOB_URL", "https://gitlab.com/wat/-/jobs/1")
+        .env_remove("GITHUB_ACTIONS")
+        .args(["ci", "detect", "--format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let info: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        info,
+        serde_json::json!({
+            "provider": "gitlab",
+            "commit_sha": "f00ba4",
+            "branch": "main",
+            "pr_number": null,
+            "build_url": "https://gitlab.com/wat/-/jobs/1",
+            "repository": null,
+        })
+    );
+}
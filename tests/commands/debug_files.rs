@@ -0,0 +1,58 @@
+use std::fs;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use assert_cmd::cargo::cargo_bin;
+use mockito::{mock, Matcher};
+
+use crate::common;
+
+const BREAKPAD_SYM: &str = "MODULE Linux x86_64 3249D99D0C4049318610F4E4FB0B69370 wat\n\
+                            FILE 0 wat.c\n\
+                            FUNC 1000 10 0 main\n\
+                            1000 10 1 0\n";
+
+#[test]
+fn watch_uploads_new_debug_files() {
+    let _chunk_upload = mock("GET", "/api/0/organizations/watch-org/chunk-upload/")
+        .with_status(404)
+        .create();
+    let _missing = mock(
+        "GET",
+        "/api/0/projects/watch-org/wat-project/files/dsyms/unknown/",
+    )
+    .match_query(Matcher::Any)
+    .with_status(200)
+    .with_header("content-type", "application/json")
+    .with_body(r#"{"missing": ["a669fd3692a04bd5fda78c322c2a6ca6118ab595"]}"#)
+    .create();
+    let upload = mock("POST", "/api/0/projects/watch-org/wat-project/files/dsyms/")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("[]")
+        .expect(1)
+        .create();
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut child = Command::new(cargo_bin("sentry-cli"))
+        .envs(common::get_base_env())
+        .args(["debug-files", "watch", "--org", "watch-org"])
+        .args(["--no-reprocessing", "--interval", "1"])
+        .arg(dir.path())
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    thread::sleep(Duration::from_secs(2));
+    fs::write(dir.path().join("wat.sym"), BREAKPAD_SYM).unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(15);
+    while !upload.matched() && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(100));
+    }
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    upload.assert();
+}
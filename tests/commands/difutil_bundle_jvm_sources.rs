@@ -0,0 +1,79 @@
+use std::fs;
+use std::io::Read;
+
+use assert_cmd::Command;
+use predicates::str::contains;
+
+use crate::common;
+
+#[test]
+fn bundles_jvm_sources_by_package() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("src/main/java");
+    fs::create_dir_all(root.join("io/sentry/sample")).unwrap();
+    fs::create_dir_all(root.join("misplaced")).unwrap();
+    fs::write(
+        root.join("io/sentry/sample/MainActivity.java"),
+        "package io.sentry.sample;\n\npublic class MainActivity {}\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("misplaced/Util.kt"),
+        "// comment\npackage io.sentry.sample.util\n\nobject Util\n",
+    )
+    .unwrap();
+    fs::write(root.join("io/sentry/sample/notes.txt"), "not a source").unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args([
+            "difutil",
+            "bundle-jvm-sources",
+            root.to_str().unwrap(),
+            "--debug-id",
+            "a669fd36-92a0-4bd5-fda7-8c322c2a6ca6",
+            "--output",
+            dir.path().to_str().unwrap(),
+        ])
+        .assert()
+        .success()
+        .stdout(contains("Bundled 2 files"));
+
+    let bundle = dir.path().join("a669fd36-92a0-4bd5-fda7-8c322c2a6ca6.zip");
+    let mut zip = zip::ZipArchive::new(fs::File::open(bundle).unwrap()).unwrap();
+    let mut manifest = String::new();
+    zip.by_name("manifest.json")
+        .unwrap()
+        .read_to_string(&mut manifest)
+        .unwrap();
+    assert!(manifest.contains("\"debug_id\":\"a669fd36-92a0-4bd5-fda7-8c322c2a6ca6\""));
+    assert!(manifest.contains("\"url\":\"~/io/sentry/sample/MainActivity.jvm\""));
+    assert!(manifest.contains("\"url\":\"~/io/sentry/sample/util/Util.jvm\""));
+
+    let mut source = String::new();
+    zip.by_name("files/io/sentry/sample/util/Util.jvm")
+        .unwrap()
+        .read_to_string(&mut source)
+        .unwrap();
+    assert!(source.contains("object Util"));
+}
+
+#[test]
+fn fails_without_jvm_sources() {
+    let dir = tempfile::tempdir().unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args([
+            "difutil",
+            "bundle-jvm-sources",
+            dir.path().to_str().unwrap(),
+            "--debug-id",
+            "a669fd36-92a0-4bd5-fda7-8c322c2a6ca6",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains("No Java or Kotlin sources found"));
+}
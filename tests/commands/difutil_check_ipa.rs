@@ -0,0 +1,95 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use assert_cmd::Command;
+use mockito::{mock, Matcher};
+use predicates::prelude::*;
+use predicates::str::contains;
+
+use crate::common;
+
+/// Builds a minimal arm64 Mach-O executable with the given UUID.
+fn macho(uuid: [u8; 16]) -> Vec<u8> {
+    let mut data = vec![];
+    for value in &[0xfeed_facf_u32, 0x0100_000c, 0, 2, 1, 24, 0, 0, 0x1b, 24] {
+        data.extend(&value.to_le_bytes());
+    }
+    data.extend(&uuid);
+    data
+}
+
+fn write_ipa(path: &Path) {
+    let mut zip = zip::ZipWriter::new(fs::File::create(path).unwrap());
+    let options = zip::write::FileOptions::default();
+    zip.start_file("Payload/Wat.app/Wat", options).unwrap();
+    zip.write_all(&macho([0x11; 16])).unwrap();
+    zip.start_file("Payload/Wat.app/Info.plist", options)
+        .unwrap();
+    zip.write_all(b"<plist/>").unwrap();
+    zip.start_file("Payload/Wat.app/Frameworks/Foo.framework/Foo", options)
+        .unwrap();
+    zip.write_all(&macho([0x22; 16])).unwrap();
+    zip.finish().unwrap();
+}
+
+fn mock_difs(debug_id: &str, body: &str) -> mockito::Mock {
+    mock("GET", "/api/0/projects/wat-org/wat-project/files/dsyms/")
+        .match_query(Matcher::UrlEncoded("debug_id".into(), debug_id.into()))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(body)
+        .create()
+}
+
+const DIF: &str = r#"[{
+    "uuid": "11111111-1111-1111-1111-111111111111",
+    "debugId": "11111111-1111-1111-1111-111111111111",
+    "objectName": "Wat",
+    "cpuName": "arm64",
+    "sha1": "2dc7b5d4d8ad6ae7be7f3fdd7bd26c1eb3e9ff60",
+    "data": {"type": "dbg", "features": ["debug", "symtab"]}
+}]"#;
+
+#[test]
+fn reports_missing_debug_symbols() {
+    let _wat = mock_difs("11111111-1111-1111-1111-111111111111", DIF);
+    let _foo = mock_difs("22222222-2222-2222-2222-222222222222", "[]");
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("Wat.ipa");
+    write_ipa(&path);
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["difutil", "check-ipa", "--json", path.to_str().unwrap()])
+        .assert()
+        .failure()
+        .stdout(
+            contains(r#""name": "Wat.app/Wat""#)
+                .and(contains(r#""features": null"#))
+                .and(contains(r#""debug""#)),
+        )
+        .stderr(contains(
+            "debug symbols of 1 of 2 binaries are missing on Sentry",
+        ));
+}
+
+#[test]
+fn passes_when_all_debug_symbols_exist() {
+    let _wat = mock_difs("11111111-1111-1111-1111-111111111111", DIF);
+    let _foo = mock_difs("22222222-2222-2222-2222-222222222222", DIF);
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("Wat.ipa");
+    write_ipa(&path);
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["difutil", "check-ipa", path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(contains("Wat.app/Frameworks/Foo.framework/Foo (arm64)"));
+}
@@ -0,0 +1,75 @@
+use assert_cmd::Command;
+use mockito::{mock, Matcher};
+use predicates::prelude::*;
+use predicates::str::contains;
+
+use crate::common;
+
+#[test]
+fn hides_matching_environments() {
+    let _list = mock("GET", "/api/0/projects/wat-org/wat-project/environments/")
+        .match_query(Matcher::UrlEncoded("visibility".into(), "visible".into()))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"[{"id":"1","name":"production","isHidden":false},{"id":"2","name":"pr-123","isHidden":false},{"id":"3","name":"pr-124","isHidden":false}]"#)
+        .create();
+    let _hide_123 = mock(
+        "PUT",
+        "/api/0/projects/wat-org/wat-project/environments/pr-123/",
+    )
+    .match_body(Matcher::Json(serde_json::json!({"isHidden": true})))
+    .with_status(200)
+    .with_header("content-type", "application/json")
+    .with_body(r#"{"id":"2","name":"pr-123","isHidden":true}"#)
+    .expect(1)
+    .create();
+    let _hide_124 = mock(
+        "PUT",
+        "/api/0/projects/wat-org/wat-project/environments/pr-124/",
+    )
+    .match_body(Matcher::Json(serde_json::json!({"isHidden": true})))
+    .with_status(200)
+    .with_header("content-type", "application/json")
+    .with_body(r#"{"id":"3","name":"pr-124","isHidden":true}"#)
+    .expect(1)
+    .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec!["environments", "hide", "pr-*"])
+        .assert()
+        .success()
+        .stdout(
+            contains("Hid pr-123")
+                .and(contains("Hid pr-124"))
+                .and(contains("production").not()),
+        );
+
+    _hide_123.assert();
+    _hide_124.assert();
+}
+
+#[test]
+fn lists_hidden_environments_as_json() {
+    let _list = mock("GET", "/api/0/projects/wat-org/wat-project/environments/")
+        .match_query(Matcher::UrlEncoded("visibility".into(), "hidden".into()))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"[{"id":"2","name":"pr-123","isHidden":true}]"#)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "environments",
+            "list",
+            "--visibility",
+            "hidden",
+            "--json",
+        ])
+        .assert()
+        .success()
+        .stdout(contains(r#""name": "pr-123""#).and(contains(r#""isHidden": true"#)));
+}
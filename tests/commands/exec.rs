@@ -0,0 +1,139 @@
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
+
+use assert_cmd::cargo::cargo_bin;
+use mockito::{mock, server_address, Matcher};
+use predicates::str::contains;
+
+use crate::common;
+
+#[test]
+fn reports_exit_with_container_metadata() {
+    let store = mock("POST", "/api/1/store/")
+        .match_body(Matcher::PartialJson(serde_json::json!({
+            "exception": {"values": [{
+                "type": "ProcessError",
+                "value": "command sh exited with status 4",
+            }]},
+            "server_name": "web-1",
+            "tags": {
+                "container.hostname": "web-1",
+                "container.image": "registry:5000/app",
+                "container.image_tag": "1.2",
+                "exit_code": "4",
+            },
+        })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("{}")
+        .expect(1)
+        .create();
+
+    assert_cmd::Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env(
+            "SENTRY_DSN",
+            format!("http://lolnope@{}/1", server_address()),
+        )
+        .env("HOSTNAME", "web-1")
+        .env("SENTRY_CONTAINER_IMAGE", "registry:5000/app:1.2")
+        .args(["exec", "--", "sh", "-c", "exit 4"])
+        .assert()
+        .code(4)
+        .stderr(contains("exited with status 4"));
+
+    store.assert();
+}
+
+#[test]
+fn forwards_signals() {
+    let mut child = Command::new(cargo_bin("sentry-cli"))
+        .envs(common::get_base_env())
+        .env(
+            "SENTRY_DSN",
+            format!("http://lolnope@{}/1", server_address()),
+        )
+        .args([
+            "exec",
+            "--",
+            "sh",
+            "-c",
+            "trap 'echo terminated; exit 0' TERM; sleep 0.5; echo ready; while :; do sleep 0.1; done",
+        ])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+    assert_eq!(line, "ready\n");
+
+    Command::new("kill")
+        .args(["-TERM", &child.id().to_string()])
+        .status()
+        .unwrap();
+    line.clear();
+    stdout.read_line(&mut line).unwrap();
+    assert_eq!(line, "terminated\n");
+    assert!(child.wait().unwrap().success());
+}
+
+#[test]
+fn writes_envelopes_to_spool() {
+    let store = mock("POST", "/api/1/store/").expect(0).create();
+    let checkins = mock(
+        "POST",
+        "/api/0/organizations/wat-org/monitors/nightly/checkins/",
+    )
+    .expect(0)
+    .create();
+
+    let dir = tempfile::tempdir().unwrap();
+    assert_cmd::Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env(
+            "SENTRY_DSN",
+            format!("http://lolnope@{}/1", server_address()),
+        )
+        .args([
+            "--spool",
+            "--spool-dir",
+            dir.path().to_str().unwrap(),
+            "exec",
+            "--monitor-slug",
+            "nightly",
+            "--",
+            "sh",
+            "-c",
+            "exit 4",
+        ])
+        .assert()
+        .code(4);
+
+    store.assert();
+    checkins.assert();
+
+    let envelopes: Vec<String> = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| std::fs::read_to_string(entry.unwrap().path()).unwrap())
+        .collect();
+    assert_eq!(envelopes.len(), 3);
+    assert!(envelopes.iter().all(|envelope| envelope
+        .lines()
+        .next()
+        .unwrap()
+        .contains(r#""dsn":"http://lolnope"#)));
+    assert!(envelopes
+        .iter()
+        .any(|envelope| envelope.contains(r#""monitor_slug":"nightly","status":"in_progress""#)));
+    assert!(envelopes
+        .iter()
+        .any(|envelope| envelope.contains(r#""monitor_slug":"nightly","status":"error""#)));
+    assert!(envelopes
+        .iter()
+        .any(|envelope| envelope.contains(r#""type":"event""#)
+            && envelope.contains("command sh exited with status 4")));
+}
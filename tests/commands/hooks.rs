@@ -0,0 +1,81 @@
+use std::fs;
+use std::process;
+
+use assert_cmd::Command;
+use predicates::str::contains;
+
+use crate::common;
+
+fn init_repo() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let status = process::Command::new("git")
+        .args(["init", "-q"])
+        .current_dir(dir.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+    dir
+}
+
+#[test]
+fn installs_hooks() {
+    let dir = init_repo();
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .current_dir(dir.path())
+        .args(["hooks", "install", "--release-pattern", "v[0-9.]+"])
+        .assert()
+        .success()
+        .stdout(contains("Installed pre-commit hook"))
+        .stdout(contains("Installed pre-push hook"));
+
+    let script = fs::read_to_string(dir.path().join(".git/hooks/pre-push")).unwrap();
+    assert!(script.starts_with("#!/bin/sh\n# installed by sentry-cli hooks install\n"));
+    assert!(script.contains(" hooks run pre-push --release-pattern 'v[0-9.]+' -- \"$@\""));
+
+    // hooks from other tools are left alone
+    fs::write(dir.path().join(".git/hooks/pre-commit"), "#!/bin/sh\n").unwrap();
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .current_dir(dir.path())
+        .args(["hooks", "install", "--hook", "pre-commit"])
+        .assert()
+        .failure()
+        .stderr(contains("Use --force to replace it"));
+}
+
+#[test]
+fn rejects_staged_dsns() {
+    let dir = init_repo();
+    fs::write(
+        dir.path().join("app.js"),
+        "init({\n  dsn: 'https://0123456789abcdef0123456789abcdef@o1.ingest.sentry.io/42',\n});\n",
+    )
+    .unwrap();
+    let status = process::Command::new("git")
+        .args(["add", "app.js"])
+        .current_dir(dir.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .current_dir(dir.path())
+        .args(["hooks", "run", "pre-commit"])
+        .assert()
+        .failure()
+        .stdout(contains("app.js:2: contains a Sentry DSN"))
+        .stderr(contains("Staged files contain Sentry DSNs"));
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .current_dir(dir.path())
+        .args(["hooks", "run", "pre-commit", "--no-dsn-check"])
+        .assert()
+        .success();
+}
@@ -39,3 +39,76 @@ fn info_fails_without_auth_token() {
         .assert()
         .failure();
 }
+
+#[test]
+#[cfg(all(target_os = "linux", not(feature = "rustls")))]
+fn info_selects_tls_backend() {
+    let _server = mock("GET", "/api/0/")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"user":{"username":"kamil@sentry.io","id":"1337","name":"Kamil Ogórek","email":"kamil@sentry.io"},"auth":{"scopes":["project:read"]}}"#)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env("SENTRY_HTTP_TLS_BACKEND", "native")
+        .arg("info")
+        .assert()
+        .success()
+        .stdout(contains("TLS Backend: OpenSSL/"));
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["--tls-backend", "rustls", "info"])
+        .assert()
+        .failure()
+        .stderr(contains(
+            "The rustls TLS backend (rustls) is not available in this build of sentry-cli. \
+             Available backends: openssl",
+        ));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn info_reports_fips_compliance() {
+    let _server = mock("GET", "/api/0/")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"user":{"username":"kamil@sentry.io","id":"1337","name":"Kamil Ogórek","email":"kamil@sentry.io"},"auth":{"scopes":["project:read"]}}"#)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .arg("info")
+        .assert()
+        .success()
+        .stdout(contains("FIPS Mode: disabled"))
+        .stdout(contains("  TLS: OpenSSL/"));
+
+    // the bundled OpenSSL has no FIPS provider, so requests are refused
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env("SENTRY_FIPS_MODE", "1")
+        .arg("info")
+        .assert()
+        .failure()
+        .stdout(contains("FIPS Mode: enabled"))
+        .stdout(contains(
+            "(not FIPS validated: OpenSSL is not running in FIPS mode)",
+        ))
+        .stdout(contains("request not allowed in FIPS mode"));
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["--fips", "--tls-backend", "rustls", "info"])
+        .assert()
+        .failure()
+        .stderr(contains(
+            "The rustls TLS backend cannot be used in FIPS mode",
+        ));
+}
@@ -0,0 +1,69 @@
+use std::fs;
+
+use assert_cmd::Command;
+use predicates::str::contains;
+
+use crate::common;
+
+fn write_plist(dir: &tempfile::TempDir, bundle_id: &str) -> String {
+    let path = dir.path().join("Info.plist");
+    fs::write(
+        &path,
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>CFBundleName</key>
+    <string>Wat</string>
+    <key>CFBundleIdentifier</key>
+    <string>{}</string>
+    <key>CFBundleShortVersionString</key>
+    <string>1.2.0</string>
+    <key>CFBundleVersion</key>
+    <string>42</string>
+</dict>
+</plist>
+"#,
+            bundle_id
+        ),
+    )
+    .unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+#[test]
+fn prints_release_name() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_plist(&dir, "io.sentry.wat");
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["info-plist", "release-name", &path])
+        .assert()
+        .success()
+        .stdout("io.sentry.wat@1.2.0\n");
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["info-plist", "release-name", &path, "--with-build"])
+        .assert()
+        .success()
+        .stdout("io.sentry.wat@1.2.0+42\n");
+}
+
+#[test]
+fn fails_on_unresolved_build_settings() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = write_plist(&dir, "$(PRODUCT_BUNDLE_IDENTIFIER)");
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["info-plist", "release-name", &path])
+        .assert()
+        .failure()
+        .stderr(contains("unresolved build settings"));
+}
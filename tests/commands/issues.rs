@@ -0,0 +1,123 @@
+use assert_cmd::Command;
+use mockito::{mock, Matcher};
+use predicates::prelude::*;
+use predicates::str::contains;
+
+use crate::common;
+
+#[test]
+fn ignores_issues_for_duration() {
+    let _server = mock(
+        "PUT",
+        "/api/0/projects/wat-org/wat-project/issues/?id=1&id=2",
+    )
+    .match_body(Matcher::Json(serde_json::json!({
+        "status": "ignored",
+        "snoozeDuration": 120,
+    })))
+    .with_status(200)
+    .with_header("content-type", "application/json")
+    .with_body("{}")
+    .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "issues", "--id", "1", "--id", "2", "ignore", "--until", "2h",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("new status: ignored").and(contains("ignored for: 120 minutes")));
+}
+
+#[test]
+fn rejects_ignore_duration_below_one_minute() {
+    let _server = mock("PUT", "/api/0/projects/wat-org/wat-project/issues/?id=1")
+        .expect(0)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec!["issues", "--id", "1", "ignore", "--until", "30s"])
+        .assert()
+        .failure()
+        .stderr(contains("one minute or longer"));
+
+    _server.assert();
+}
+
+#[test]
+fn assigns_issues_matching_query() {
+    let _server = mock("PUT", "/api/0/projects/wat-org/wat-project/issues/")
+        .match_query(Matcher::UrlEncoded(
+            "query".into(),
+            "is:unresolved release:1.0.0".into(),
+        ))
+        .match_body(Matcher::Json(serde_json::json!({
+            "assignedTo": "team:42",
+        })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("{}")
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "issues",
+            "--query",
+            "is:unresolved release:1.0.0",
+            "assign",
+            "team:42",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("assigned to: team:42"));
+}
+
+#[test]
+fn resolves_issues_from_stdin() {
+    let _server = mock(
+        "PUT",
+        "/api/0/projects/wat-org/wat-project/issues/?id=3&id=4",
+    )
+    .match_body(Matcher::Json(serde_json::json!({
+        "status": "resolvedInNextRelease",
+    })))
+    .with_status(200)
+    .with_header("content-type", "application/json")
+    .with_body("{}")
+    .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec!["issues", "--stdin", "resolve", "--in-next-release"])
+        .write_stdin("3\n4\n")
+        .assert()
+        .success()
+        .stdout(contains("new status: resolvedInNextRelease"));
+}
+
+#[test]
+fn unsubscribes_from_issues() {
+    let _server = mock("PUT", "/api/0/projects/wat-org/wat-project/issues/?id=3")
+        .match_body(Matcher::Json(serde_json::json!({
+            "isSubscribed": false,
+        })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("{}")
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec!["issues", "--id", "3", "unsubscribe"])
+        .assert()
+        .success()
+        .stdout(contains("notifications: unsubscribed"));
+}
@@ -0,0 +1,95 @@
+use assert_cmd::Command;
+use mockito::{mock, Matcher, Mock};
+use predicates::prelude::*;
+use predicates::str::contains;
+
+use crate::common;
+
+fn issue(id: u32) -> serde_json::Value {
+    serde_json::json!({
+        "id": id.to_string(),
+        "shortId": format!("WAT-PROJECT-{}", id),
+        "title": format!("Error {}", id),
+        "culprit": "app/main.js",
+        "permalink": format!("https://sentry.io/organizations/wat-org/issues/{}/", id),
+        "level": "error",
+        "status": "unresolved",
+        "count": "1",
+        "userCount": 1,
+        "firstSeen": "2020-03-19T10:11:35.128919Z",
+        "lastSeen": "2020-05-18T13:39:06.033442Z",
+    })
+}
+
+fn mock_issues(query: &str, ids: &[u32]) -> Mock {
+    let issues: Vec<_> = ids.iter().map(|id| issue(*id)).collect();
+    mock("GET", "/api/0/projects/wat-org/wat-project/issues/")
+        .match_query(Matcher::UrlEncoded("query".into(), query.into()))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::to_string(&issues).unwrap())
+        .create()
+}
+
+#[test]
+fn fails_on_new_and_regressed_issues() {
+    let _base = mock_issues(r#"release:"1.2.0""#, &[1, 4]);
+    let _head = mock_issues(r#"release:"1.3.0""#, &[1, 2, 3, 5]);
+    let _new = mock_issues(r#"first-release:"1.3.0""#, &[2]);
+    let _regressed = mock_issues(r#"is:regressed release:"1.3.0""#, &[3]);
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "issues",
+            "compare",
+            "--base",
+            "1.2.0",
+            "--head",
+            "1.3.0",
+            "--fail-on-new",
+        ])
+        .assert()
+        .code(1)
+        .stdout(
+            contains("New issues: 1")
+                .and(contains("Regressed issues: 1"))
+                .and(contains("Persisting issues: 2"))
+                .and(contains("Gone issues: 1"))
+                .and(contains("WAT-PROJECT-2 Error 2"))
+                .and(contains("WAT-PROJECT-3 Error 3"))
+                .and(contains("WAT-PROJECT-5 Error 5").not()),
+        );
+}
+
+#[test]
+fn succeeds_without_new_issues() {
+    let _base = mock_issues(r#"is:unresolved release:"1.2.0""#, &[1]);
+    let _head = mock_issues(r#"is:unresolved release:"1.3.0""#, &[1]);
+    let _new = mock_issues(r#"is:unresolved first-release:"1.3.0""#, &[]);
+    let _regressed = mock_issues(r#"is:unresolved is:regressed release:"1.3.0""#, &[]);
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "issues",
+            "--status",
+            "unresolved",
+            "compare",
+            "--base",
+            "1.2.0",
+            "--head",
+            "1.3.0",
+            "--fail-on-new",
+            "--json",
+        ])
+        .assert()
+        .success()
+        .stdout(
+            contains(r#""new": []"#)
+                .and(contains(r#""regressed": []"#))
+                .and(contains(r#""persisting": 1"#)),
+        );
+}
@@ -0,0 +1,46 @@
+use assert_cmd::Command;
+use mockito::{mock, server_address, Matcher};
+
+use crate::common;
+
+#[test]
+fn sends_annotation_with_fingerprint() {
+    let _server = mock("POST", "/api/1/store/")
+        .match_body(Matcher::AllOf(vec![Matcher::PartialJson(
+            serde_json::json!({
+                "level": "warning",
+                "logger": "annotation",
+                "logentry": {"message": "cache flush performed"},
+                "fingerprint": ["annotation", "cache flush performed"],
+                "tags": {"region": "eu"},
+            }),
+        )]))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("{}")
+        .expect(1)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env(
+            "SENTRY_DSN",
+            format!("http://lolnope@{}/1", server_address()),
+        )
+        .args(vec![
+            "issues",
+            "annotate",
+            "cache flush performed",
+            "--level",
+            "warning",
+            "--tag",
+            "region:eu",
+            "--release",
+            "1.0.0",
+        ])
+        .assert()
+        .success();
+
+    _server.assert();
+}
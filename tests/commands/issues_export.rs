@@ -0,0 +1,76 @@
+use std::fs;
+
+use assert_cmd::Command;
+use mockito::{mock, Matcher};
+use predicates::prelude::*;
+use predicates::str::contains;
+
+use crate::common;
+
+const VALID_ISSUES_REQUEST_BODY: &str = r#"[{"id":"1149516","shortId":"WAT-PROJECT-1","title":"TypeError: wat, again","culprit":"app/main.js","permalink":"https://sentry.io/organizations/wat-org/issues/1149516/","level":"error","status":"unresolved","count":"42","userCount":7,"firstSeen":"2020-03-19T10:11:35Z","lastSeen":"2020-05-18T13:39:06Z"}]"#;
+
+#[test]
+fn exports_issues_as_csv() {
+    let _server = mock("GET", "/api/0/projects/wat-org/wat-project/issues/")
+        .match_query(Matcher::UrlEncoded("query".into(), "is:unresolved".into()))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(VALID_ISSUES_REQUEST_BODY)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "issues",
+            "export",
+            "--query",
+            "is:unresolved",
+            "--format",
+            "csv",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("id,shortId,title,culprit,status").and(contains(
+            r#"1149516,WAT-PROJECT-1,"TypeError: wat, again",app/main.js"#,
+        )));
+}
+
+#[test]
+fn exports_issues_with_events_as_jsonl() {
+    let _issues = mock("GET", "/api/0/projects/wat-org/wat-project/issues/")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(VALID_ISSUES_REQUEST_BODY)
+        .create();
+    let _events = mock("GET", "/api/0/issues/1149516/events/")
+        .match_query(Matcher::UrlEncoded("full".into(), "true".into()))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            r#"[{"eventID":"6a1bd1d2f1464bb0bd08e376a1c0f92a","title":"TypeError","entries":[]}]"#,
+        )
+        .create();
+
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("issues.jsonl");
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec!["issues", "export", "--with-events", "--output"])
+        .arg(&output)
+        .assert()
+        .success()
+        .stdout(contains("Exported 1 issues to"));
+
+    let contents = fs::read_to_string(&output).unwrap();
+    assert_eq!(contents.lines().count(), 1);
+    let issue: serde_json::Value = serde_json::from_str(contents.trim()).unwrap();
+    assert_eq!(issue["shortId"], "WAT-PROJECT-1");
+    assert_eq!(
+        issue["events"][0]["eventID"],
+        "6a1bd1d2f1464bb0bd08e376a1c0f92a"
+    );
+}
@@ -0,0 +1,91 @@
+use assert_cmd::Command;
+use mockito::{mock, Matcher};
+use predicates::prelude::*;
+use predicates::str::contains;
+
+use crate::common;
+
+const VALID_ISSUE_BODY: &str = r#"{"group":{"id":"1149516","shortId":"WAT-PROJECT-1","title":"TypeError: Cannot read property 'wat' of undefined","culprit":"app/main.js","permalink":"https://sentry.io/organizations/wat-org/issues/1149516/","level":"error","status":"unresolved","count":"42","userCount":7,"firstSeen":"2020-03-19T10:11:35.128919Z","lastSeen":"2020-05-18T13:39:06.033442Z"}}"#;
+
+#[test]
+fn links_issue_to_jira() {
+    let _issue = mock(
+        "GET",
+        "/api/0/organizations/wat-org/shortids/WAT-PROJECT-1/",
+    )
+    .with_status(200)
+    .with_header("content-type", "application/json")
+    .with_body(VALID_ISSUE_BODY)
+    .create();
+    let _integrations = mock("GET", "/api/0/organizations/wat-org/integrations/")
+        .match_query(Matcher::UrlEncoded("provider_key".into(), "jira".into()))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            r#"[{"id":"42","name":"wat.atlassian.net","provider":{"key":"jira","name":"Jira"}}]"#,
+        )
+        .create();
+    let _link = mock("PUT", "/api/0/groups/1149516/integrations/42/")
+        .match_body(Matcher::Json(
+            serde_json::json!({"externalIssue": "PROJ-123"}),
+        ))
+        .with_status(201)
+        .with_header("content-type", "application/json")
+        .with_body(
+            r#"{"id":"7","key":"PROJ-123","url":"https://wat.atlassian.net/browse/PROJ-123"}"#,
+        )
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "issues",
+            "link",
+            "WAT-PROJECT-1",
+            "--provider",
+            "jira",
+            "--key",
+            "PROJ-123",
+        ])
+        .assert()
+        .success()
+        .stdout(
+            contains("Linked WAT-PROJECT-1 to Jira issue PROJ-123")
+                .and(contains("URL: https://wat.atlassian.net/browse/PROJ-123")),
+        );
+}
+
+#[test]
+fn fails_without_integration() {
+    let _issue = mock(
+        "GET",
+        "/api/0/organizations/wat-org/shortids/WAT-PROJECT-1/",
+    )
+    .with_status(200)
+    .with_header("content-type", "application/json")
+    .with_body(VALID_ISSUE_BODY)
+    .create();
+    let _integrations = mock("GET", "/api/0/organizations/wat-org/integrations/")
+        .match_query(Matcher::UrlEncoded("provider_key".into(), "github".into()))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("[]")
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "issues",
+            "link",
+            "WAT-PROJECT-1",
+            "--provider",
+            "github",
+            "--key",
+            "wat/wat#1",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains("No matching github integration installed"));
+}
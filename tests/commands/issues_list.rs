@@ -0,0 +1,93 @@
+use assert_cmd::Command;
+use mockito::{mock, Matcher};
+use predicates::prelude::*;
+use predicates::str::contains;
+
+use crate::common;
+
+const VALID_ISSUES_REQUEST_BODY: &str = r#"[{"id":"1149516","shortId":"WAT-PROJECT-1","title":"TypeError: Cannot read property 'wat' of undefined","culprit":"app/main.js","permalink":"https://sentry.io/organizations/wat-org/issues/1149516/","level":"error","status":"unresolved","count":"42","userCount":7,"firstSeen":"2020-03-19T10:11:35.128919Z","lastSeen":"2020-05-18T13:39:06.033442Z","project":{"id":"1861017","name":"wat-project","slug":"wat-project"}}]"#;
+
+#[test]
+fn lists_issues_as_json() {
+    let _server = mock("GET", "/api/0/projects/wat-org/wat-project/issues/")
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("query".into(), "is:unresolved release:1.0.0".into()),
+            Matcher::UrlEncoded("sort".into(), "freq".into()),
+        ]))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(VALID_ISSUES_REQUEST_BODY)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "issues",
+            "list",
+            "--query",
+            "is:unresolved release:1.0.0",
+            "--sort",
+            "freq",
+            "--json",
+        ])
+        .assert()
+        .success()
+        .stdout(
+            contains(r#""shortId": "WAT-PROJECT-1""#)
+                .and(contains(r#""count": "42""#))
+                .and(contains(r#""userCount": 7"#)),
+        );
+}
+
+#[test]
+fn adds_status_to_query() {
+    let _server = mock("GET", "/api/0/projects/wat-org/wat-project/issues/")
+        .match_query(Matcher::UrlEncoded(
+            "query".into(),
+            "is:resolved release:1.0.0".into(),
+        ))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("[]")
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "issues",
+            "--status",
+            "resolved",
+            "list",
+            "--query",
+            "release:1.0.0",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("No issues found"));
+}
+
+#[test]
+fn encodes_query_delimiters() {
+    let _server = mock("GET", "/api/0/projects/wat-org/wat-project/issues/")
+        .match_query(Matcher::UrlEncoded(
+            "query".into(),
+            "message:\"a&b=c+d%\"".into(),
+        ))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("[]")
+        .expect(1)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec!["issues", "list", "--query", "message:\"a&b=c+d%\""])
+        .assert()
+        .success()
+        .stdout(contains("No issues found"));
+
+    _server.assert();
+}
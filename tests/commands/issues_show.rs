@@ -0,0 +1,70 @@
+use assert_cmd::Command;
+use mockito::mock;
+use predicates::prelude::*;
+use predicates::str::contains;
+
+use crate::common;
+
+const VALID_ISSUE_BODY: &str = r#"{"id":"1149516","shortId":"WAT-PROJECT-1","title":"TypeError: Cannot read property 'wat' of undefined","culprit":"app/main.js","permalink":"https://sentry.io/organizations/wat-org/issues/1149516/","level":"error","status":"unresolved","count":"42","userCount":7,"firstSeen":"2020-03-19T10:11:35.128919Z","lastSeen":"2020-05-18T13:39:06.033442Z"}"#;
+
+const VALID_EVENT_BODY: &str = r#"{"eventID":"6a1bd1d2f1464bb0bd08e376a1c0f92a","title":"TypeError: Cannot read property 'wat' of undefined","dateCreated":"2020-05-18T13:39:06Z","platform":"javascript","tags":[{"key":"release","value":"vue-1"}],"entries":[{"type":"exception","data":{"values":[{"type":"TypeError","value":"Cannot read property 'wat' of undefined","stacktrace":{"frames":[{"filename":"node_modules/vue/dist/vue.js","function":"invoke","lineNo":100,"colNo":3,"inApp":false,"context":[]},{"filename":"app/main.js","function":"handleClick","lineNo":10,"colNo":5,"inApp":true,"context":[[9,"function handleClick(e) {"],[10,"  return e.wat.value;"],[11,"}"]],"vars":{"e":"undefined"}}]}}]}}]}"#;
+
+#[test]
+fn shows_issue_with_latest_event() {
+    let _issue = mock(
+        "GET",
+        "/api/0/organizations/wat-org/shortids/WAT-PROJECT-1/",
+    )
+    .with_status(200)
+    .with_header("content-type", "application/json")
+    .with_body(format!(
+        r#"{{"groupId":"1149516","group":{}}}"#,
+        VALID_ISSUE_BODY
+    ))
+    .create();
+    let _event = mock("GET", "/api/0/issues/1149516/events/latest/")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(VALID_EVENT_BODY)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "issues",
+            "show",
+            "WAT-PROJECT-1",
+            "--in-app",
+            "--vars",
+        ])
+        .assert()
+        .success()
+        .stdout(
+            contains("WAT-PROJECT-1 TypeError: Cannot read property 'wat' of undefined")
+                .and(contains("Events: 42"))
+                .and(contains("release: vue-1"))
+                .and(contains("at handleClick (app/main.js:10:5)"))
+                .and(contains("> 10 |   return e.wat.value;"))
+                .and(contains(r#"e = "undefined""#))
+                .and(contains("(1 frames hidden)"))
+                .and(contains("vue.js").not()),
+        );
+}
+
+#[test]
+fn fails_for_unknown_issue() {
+    let _issue = mock("GET", "/api/0/issues/1/")
+        .with_status(404)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"detail":"The requested resource does not exist"}"#)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec!["issues", "show", "1"])
+        .assert()
+        .failure()
+        .stderr(contains("Issue 1 not found"));
+}
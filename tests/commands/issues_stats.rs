@@ -0,0 +1,41 @@
+use assert_cmd::Command;
+use mockito::{mock, Matcher};
+use predicates::prelude::*;
+use predicates::str::contains;
+
+use crate::common;
+
+const VALID_ISSUES_REQUEST_BODY: &str = r#"[{"id":"1149516","shortId":"WAT-PROJECT-1","title":"TypeError","status":"unresolved","count":"42","userCount":7,"firstSeen":"2020-03-19T10:11:35.128919Z","lastSeen":"2020-05-18T13:39:06.033442Z","stats":{"24h":[[1589760000,0],[1589763600,2],[1589767200,8],[1589770800,4]]}}]"#;
+
+#[test]
+fn shows_issue_stats_as_json() {
+    let _server = mock("GET", "/api/0/projects/wat-org/wat-project/issues/")
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("query".into(), "is:unresolved".into()),
+            Matcher::UrlEncoded("sort".into(), "freq".into()),
+            Matcher::UrlEncoded("statsPeriod".into(), "24h".into()),
+        ]))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(VALID_ISSUES_REQUEST_BODY)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "issues",
+            "stats",
+            "--query",
+            "is:unresolved",
+            "--json",
+        ])
+        .assert()
+        .success()
+        .stdout(
+            contains(r#""shortId": "WAT-PROJECT-1""#)
+                .and(contains(r#""events": 14"#))
+                .and(contains(r#""users": 7"#))
+                .and(contains(r#""sparkline": "▁▃█▅""#)),
+        );
+}
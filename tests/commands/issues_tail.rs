@@ -0,0 +1,130 @@
+use std::io::{BufRead, BufReader};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+use assert_cmd::cargo::cargo_bin;
+use assert_cmd::Command;
+use mockito::{mock, Matcher};
+use predicates::prelude::*;
+use predicates::str::contains;
+
+use crate::common;
+
+const VALID_EVENTS_BODY: &str = r#"[{"eventID":"6a1bd1d2f1464bb0bd08e376a1c0f92a","title":"TypeError: newest","dateCreated":"2020-05-18T13:39:06Z","tags":[{"key":"release","value":"vue-2"}]},{"eventID":"0b7cf7e1c4cb4c71a3e2fa0c7b2b23b4","title":"TypeError: oldest","dateCreated":"2020-05-18T13:30:00Z","tags":[]}]"#;
+
+#[test]
+fn prints_recent_events_of_issue() {
+    let _issue = mock("GET", "/api/0/issues/1149516/")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"id":"1149516","shortId":"WAT-PROJECT-1","title":"TypeError","culprit":"app/main.js","permalink":"https://sentry.io/organizations/wat-org/issues/1149516/","level":"error","status":"unresolved","count":"42","userCount":7,"firstSeen":"2020-03-19T10:11:35.128919Z","lastSeen":"2020-05-18T13:39:06.033442Z"}"#)
+        .create();
+    let _events = mock("GET", "/api/0/issues/1149516/events/")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(VALID_EVENTS_BODY)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec!["issues", "tail", "1149516", "--no-follow"])
+        .assert()
+        .success()
+        .stdout(
+            contains("2020-05-18 13:30:00 0b7cf7e1 TypeError: oldest\n").and(contains(
+                "2020-05-18 13:39:06 6a1bd1d2 TypeError: newest [vue-2]",
+            )),
+        );
+}
+
+#[test]
+fn prints_recent_events_matching_query() {
+    let _events = mock("GET", "/api/0/projects/wat-org/wat-project/events/")
+        .match_query(Matcher::UrlEncoded("query".into(), "release:vue-2".into()))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(VALID_EVENTS_BODY)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "issues",
+            "tail",
+            "release:vue-2",
+            "--no-follow",
+            "-n",
+            "1",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("TypeError: newest").and(contains("oldest").not()));
+}
+
+#[test]
+fn rejects_short_interval() {
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec!["issues", "tail", "release:vue-2", "--interval", "0s"])
+        .assert()
+        .failure()
+        .stderr(contains("The interval must be at least one second."));
+}
+
+#[test]
+fn stops_polling_at_seen_events() {
+    let first_page = mock("GET", "/api/0/projects/wat-org/wat-project/events/")
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("query".into(), "release:tail-3".into()),
+            Matcher::UrlEncoded("cursor".into(), "".into()),
+        ]))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_header(
+            "link",
+            &format!(
+                r#"<{}/api/0/projects/wat-org/wat-project/events/?cursor=100:1:0>; rel="next"; results="true"; cursor="100:1:0""#,
+                mockito::server_url()
+            ),
+        )
+        .with_body(VALID_EVENTS_BODY)
+        .expect_at_least(2)
+        .create();
+    let second_page = mock("GET", "/api/0/projects/wat-org/wat-project/events/")
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("query".into(), "release:tail-3".into()),
+            Matcher::UrlEncoded("cursor".into(), "100:1:0".into()),
+        ]))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"[{"eventID":"9d3ab53c4d414fe8b3fa4dc06a4e5d2e","title":"TypeError: older","dateCreated":"2020-05-18T13:00:00Z","tags":[]}]"#)
+        .expect(1)
+        .create();
+
+    let mut child = std::process::Command::new(cargo_bin("sentry-cli"))
+        .envs(common::get_base_env())
+        .args(vec!["issues", "tail", "release:tail-3", "--interval", "1s"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+    assert!(line.contains("TypeError: older"), "{}", line);
+
+    // the first page is polled again, but the seen events end the search
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !first_page.matched() && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    std::thread::sleep(Duration::from_millis(500));
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    first_page.assert();
+    second_page.assert();
+}
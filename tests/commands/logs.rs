@@ -0,0 +1,104 @@
+use std::fs;
+
+use assert_cmd::Command;
+use mockito::{mock, server_address, Matcher};
+use predicates::str::contains;
+
+use crate::common;
+
+#[test]
+fn sends_logfmt_records_as_logs() {
+    let envelope = mock("POST", "/api/1/envelope/")
+        .match_header("content-type", "application/x-sentry-envelope")
+        .match_body(Matcher::AllOf(vec![
+            Matcher::Regex(r#""type":"log""#.into()),
+            Matcher::Regex(r#""item_count":2"#.into()),
+            Matcher::Regex(r#""body":"payment failed""#.into()),
+            Matcher::Regex(r#""level":"error""#.into()),
+            Matcher::Regex(r#""order":\{"type":"string","value":"42"\}"#.into()),
+            Matcher::Regex(r#""timestamp":1600000000.0"#.into()),
+        ]))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("{}")
+        .expect(1)
+        .create();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("app.log");
+    fs::write(
+        &path,
+        "time=2020-09-13T12:26:40Z level=error msg=\"payment failed\" order=42\n\
+         level=debug msg=\"cache miss\"\n\
+         level=warning msg=\"slow response\"\n\
+         === restarted ===\n",
+    )
+    .unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env(
+            "SENTRY_DSN",
+            format!("http://lolnope@{}/1", server_address()),
+        )
+        .args(["logs", "send", "--parser", "logfmt", "--file"])
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout(contains("Sent 2 log records"))
+        .stdout(contains("Skipped 1 lines the parser did not match"));
+
+    envelope.assert();
+}
+
+#[test]
+fn sends_matching_records_as_events() {
+    let envelope = mock("POST", "/api/1/envelope/")
+        .match_body(Matcher::AllOf(vec![
+            Matcher::Regex(r#""type":"event""#.into()),
+            Matcher::Regex(r#""level":"fatal""#.into()),
+            Matcher::Regex(r#""user":"jane""#.into()),
+        ]))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("{}")
+        .expect(1)
+        .create();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("app.log");
+    fs::write(
+        &path,
+        "[info] jane: logged in\n[crit] jane: lost the session\n",
+    )
+    .unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env(
+            "SENTRY_DSN",
+            format!("http://lolnope@{}/1", server_address()),
+        )
+        .args(["logs", "send", "--as", "events", "--parser"])
+        .arg(r"regex:^\[(?P<level>\w+)\] (?P<user>\w+): (?P<message>.*)$")
+        .arg("--file")
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout(contains("Sent 1 events"));
+
+    envelope.assert();
+}
+
+#[test]
+fn rejects_invalid_parsers() {
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["logs", "send", "--file", "app.log", "--parser", "xml"])
+        .assert()
+        .failure()
+        .stderr(contains("Unknown log parser xml"));
+}
@@ -1,7 +1,61 @@
+mod alerts;
+mod android_inject_debug_meta;
+mod bash_hook;
+mod bugreport;
+mod ci;
+mod debug_files;
+mod difutil_bundle_jvm_sources;
+mod difutil_check_ipa;
+mod environments;
+mod exec;
+mod hooks;
 mod info;
+mod info_plist;
+mod issues;
+mod issues_compare;
+mod issues_create;
+mod issues_export;
+mod issues_link;
+mod issues_list;
+mod issues_show;
+mod issues_stats;
+mod issues_tail;
+mod logs;
+mod monitors;
+mod organizations_members;
+mod persistent_worker;
+mod powershell_hook;
+mod projects;
+mod projects_config;
+mod projects_keys;
+mod projects_ownership;
+mod projects_quota;
+mod projects_symbol_sources;
+mod react_native_gradle;
 mod releases;
+mod releases_changelog;
 mod releases_delete;
+mod releases_deploys;
+mod releases_files_upload;
+mod releases_files_upload_sourcemaps;
 mod releases_finalize;
 mod releases_info;
 mod releases_list;
 mod releases_new;
+mod releases_set_commits;
+mod releases_set_dependencies;
+mod releases_suspects;
+mod repos;
+mod run;
+mod send_attachment;
+mod send_metric;
+mod send_replay;
+mod systemd_hook;
+mod teams;
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+mod update;
+mod upload_dif;
+mod upload_dsym;
+mod upload_minidump;
+mod upload_profile;
+mod uptime;
@@ -0,0 +1,276 @@
+use assert_cmd::Command;
+use mockito::{mock, Matcher};
+use predicates::str::contains;
+
+use crate::common;
+
+#[test]
+fn creates_monitor_idempotently() {
+    let _missing = mock(
+        "GET",
+        "/api/0/organizations/wat-org/monitors/nightly-backup/",
+    )
+    .with_status(404)
+    .create();
+    let create = mock("POST", "/api/0/organizations/wat-org/monitors/")
+        .match_body(Matcher::Json(serde_json::json!({
+            "name": "nightly-backup",
+            "slug": "nightly-backup",
+            "type": "cron_job",
+            "config": {
+                "schedule_type": "crontab",
+                "schedule": "0 3 * * *",
+                "checkin_margin": 5,
+            },
+        })))
+        .with_status(201)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"id":"6a9c8d6e-2c1b-4b83-a3b5-4d3d3c42b2a1","name":"nightly-backup","status":"active"}"#)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "monitors",
+            "create",
+            "nightly-backup",
+            "--schedule",
+            "0 3 * * *",
+            "--checkin-margin",
+            "5",
+            "--idempotent",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("Created monitor nightly-backup"));
+
+    create.assert();
+}
+
+#[test]
+fn skips_existing_monitor_when_idempotent() {
+    let _existing = mock("GET", "/api/0/organizations/wat-org/monitors/hourly-sync/")
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"id":"1","name":"hourly-sync","slug":"hourly-sync","status":"active","config":{"schedule_type":"crontab","schedule":"0 * * * *","checkin_margin":5,"max_runtime":null,"timezone":"UTC"}}"#)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "monitors",
+            "create",
+            "hourly-sync",
+            "--schedule",
+            "0 * * * *",
+            "--idempotent",
+        ])
+        .assert()
+        .success()
+        .stdout(contains(
+            "Monitor hourly-sync (1) already exists, nothing changed.",
+        ));
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "monitors",
+            "create",
+            "hourly-sync",
+            "--schedule",
+            "*/30 * * * *",
+            "--idempotent",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains("already exists with different settings"));
+}
+
+#[test]
+fn upserts_monitor_with_first_checkin() {
+    let start = mock(
+        "POST",
+        "/api/0/organizations/wat-org/monitors/hourly-sync/checkins/",
+    )
+    .match_body(Matcher::Json(serde_json::json!({
+        "status": "in_progress",
+        "monitor_config": {
+            "schedule_type": "crontab",
+            "schedule": "0 * * * *",
+            "checkin_margin": 5,
+        },
+    })))
+    .with_status(201)
+    .with_header("content-type", "application/json")
+    .with_body(
+        r#"{"id":"a1b2c3d4-0000-4000-8000-000000000001","status":"in_progress","duration":null}"#,
+    )
+    .expect(1)
+    .create();
+    let finish = mock(
+        "PUT",
+        "/api/0/organizations/wat-org/monitors/hourly-sync/checkins/a1b2c3d4-0000-4000-8000-000000000001/",
+    )
+    .match_body(Matcher::AllOf(vec![
+        Matcher::PartialJson(serde_json::json!({"status": "ok"})),
+        Matcher::Regex(r#""resource_usage":\{"max_rss_bytes":\d+,"cpu_user_ms":\d+"#.into()),
+    ]))
+    .with_status(200)
+    .with_header("content-type", "application/json")
+    .with_body(r#"{"id":"a1b2c3d4-0000-4000-8000-000000000001","status":"ok","duration":3}"#)
+    .expect(1)
+    .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args([
+            "monitors",
+            "run",
+            "hourly-sync",
+            "--schedule",
+            "0 * * * *",
+            "--checkin-margin",
+            "5",
+            "--",
+            "true",
+        ])
+        .assert()
+        .success();
+
+    start.assert();
+    finish.assert();
+}
+
+#[test]
+fn rejects_schedule_for_monitor_ids() {
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args([
+            "monitors",
+            "run",
+            "a1b2c3d4-0000-4000-8000-000000000001",
+            "--schedule",
+            "0 * * * *",
+            "--",
+            "true",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains("--schedule requires the slug of the monitor"));
+}
+
+#[test]
+fn accepts_success_exit_codes() {
+    let _start = mock(
+        "POST",
+        "/api/0/organizations/wat-org/monitors/weekly-install/checkins/",
+    )
+    .with_status(201)
+    .with_header("content-type", "application/json")
+    .with_body(
+        r#"{"id":"a1b2c3d4-0000-4000-8000-000000000002","status":"in_progress","duration":null}"#,
+    )
+    .create();
+    let finish = mock(
+        "PUT",
+        "/api/0/organizations/wat-org/monitors/weekly-install/checkins/a1b2c3d4-0000-4000-8000-000000000002/",
+    )
+    .match_body(Matcher::PartialJson(serde_json::json!({"status": "ok"})))
+    .with_status(200)
+    .with_header("content-type", "application/json")
+    .with_body(r#"{"id":"a1b2c3d4-0000-4000-8000-000000000002","status":"ok","duration":1}"#)
+    .expect(1)
+    .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args([
+            "monitors",
+            "run",
+            "weekly-install",
+            "--success-exit-code",
+            "0x3",
+            "--",
+            "sh",
+            "-c",
+            "exit 3",
+        ])
+        .assert()
+        .success();
+
+    finish.assert();
+}
+
+#[test]
+fn prints_scheduled_task_command() {
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args([
+            "monitors",
+            "install-task",
+            "nightly-backup",
+            "--schedule",
+            "30 2 * * 1-5",
+            "--dry-run",
+            "--",
+            "backup.exe",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains("cannot be expressed as a scheduled task"));
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env("TZ", "Europe/Vienna")
+        .args([
+            "monitors",
+            "install-task",
+            "nightly-backup",
+            "--schedule",
+            "30 2 * * *",
+            "--success-exit-code",
+            "3010",
+            "--dry-run",
+            "--",
+            "backup.exe",
+            "--full",
+        ])
+        .assert()
+        .success()
+        .stdout(contains(r#"schtasks /Create /F /TN "Sentry monitor nightly-backup" /TR "#))
+        .stdout(contains(
+            r#" monitors --org wat-org run nightly-backup --schedule \"30 2 * * *\" --timezone Europe/Vienna --success-exit-code 3010 --event-log -- backup.exe --full" /SC DAILY /ST 02:30"#,
+        ));
+}
+
+#[test]
+fn rejects_scheduled_task_in_other_timezone() {
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env("TZ", "Europe/Vienna")
+        .args([
+            "monitors",
+            "install-task",
+            "nightly-backup",
+            "--schedule",
+            "30 2 * * *",
+            "--timezone",
+            "America/New_York",
+            "--dry-run",
+            "--",
+            "backup.exe",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains(
+            "Scheduled tasks run in the local time of this machine (Europe/Vienna)",
+        ));
+}
@@ -0,0 +1,115 @@
+use assert_cmd::Command;
+use mockito::{mock, Matcher};
+use predicates::prelude::*;
+use predicates::str::contains;
+
+use crate::common;
+
+const VALID_MEMBERS_BODY: &str = r#"[{"id":"7","email":"jane@example.com","name":"Jane","role":"owner","pending":false,"dateCreated":"2020-03-19T10:11:35.128919Z"},{"id":"8","email":"joe@example.com","name":"Joe","role":"member","pending":true}]"#;
+
+#[test]
+fn lists_members_with_role_as_json() {
+    let _server = mock("GET", "/api/0/organizations/wat-org/members/")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(VALID_MEMBERS_BODY)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "organizations",
+            "members",
+            "list",
+            "--role",
+            "member",
+            "--json",
+        ])
+        .assert()
+        .success()
+        .stdout(
+            contains(r#""email": "joe@example.com""#)
+                .and(contains(r#""pending": true"#))
+                .and(contains("jane@example.com").not()),
+        );
+}
+
+#[test]
+fn invites_members() {
+    let _server = mock("POST", "/api/0/organizations/wat-org/members/")
+        .match_body(Matcher::Json(serde_json::json!({
+            "email": "new@example.com",
+            "role": "admin",
+            "teams": ["wat-squad"],
+        })))
+        .with_status(201)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"id":"9","email":"new@example.com","role":"admin","pending":true}"#)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "organizations",
+            "members",
+            "invite",
+            "new@example.com",
+            "--role",
+            "admin",
+            "--team",
+            "wat-squad",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("Invited new@example.com as admin (9)"));
+}
+
+#[test]
+fn removes_members_by_email() {
+    let _lookup = mock("GET", "/api/0/organizations/wat-org/members/")
+        .match_query(Matcher::UrlEncoded(
+            "query".into(),
+            "email:jane@example.com".into(),
+        ))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(VALID_MEMBERS_BODY)
+        .create();
+    let _missing = mock("GET", "/api/0/organizations/wat-org/members/")
+        .match_query(Matcher::UrlEncoded(
+            "query".into(),
+            "email:gone@example.com".into(),
+        ))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("[]")
+        .create();
+    let _remove = mock("DELETE", "/api/0/organizations/wat-org/members/7/")
+        .with_status(204)
+        .expect(1)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "organizations",
+            "members",
+            "remove",
+            "jane@example.com",
+            "gone@example.com",
+            "--confirm",
+        ])
+        .assert()
+        .success()
+        .stdout(
+            contains("Removed jane@example.com from wat-org").and(contains(
+                "Did nothing. gone@example.com is not a member of wat-org.",
+            )),
+        );
+
+    _remove.assert();
+}
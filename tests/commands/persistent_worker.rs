@@ -0,0 +1,113 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Stdio};
+
+use assert_cmd::cargo::cargo_bin;
+use mockito::{mock, server_url, Matcher};
+
+use crate::common;
+
+#[test]
+fn serves_work_requests() {
+    let mut child = Command::new(cargo_bin("sentry-cli"))
+        .envs(common::get_base_env())
+        .env("GITLAB_CI", "true")
+        .env("CI_COMMIT_SHA", "f00ba4")
+        .env_remove("GITHUB_ACTIONS")
+        .arg("--persistent_worker")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+
+    let mut request = move |request: serde_json::Value| {
+        writeln!(stdin, "{}", request).unwrap();
+        let mut line = String::new();
+        stdout.read_line(&mut line).unwrap();
+        serde_json::from_str::<serde_json::Value>(&line).unwrap()
+    };
+
+    assert_eq!(
+        request(serde_json::json!({
+            "arguments": ["ci", "detect", "--format", "dotenv"],
+            "requestId": 3,
+        })),
+        serde_json::json!({
+            "exitCode": 0,
+            "output": "SENTRY_CI_PROVIDER=gitlab\nSENTRY_CI_COMMIT_SHA=f00ba4\n",
+            "requestId": 3,
+        })
+    );
+
+    let response = request(serde_json::json!({
+        "arguments": ["ci", "detect", "--format", "xml"],
+        "requestId": 4,
+    }));
+    assert_eq!(response["exitCode"], 1);
+    assert_eq!(response["requestId"], 4);
+    assert!(response["output"].as_str().unwrap().contains("xml"));
+
+    // closing stdin stops the worker
+    drop(request);
+    assert!(child.wait().unwrap().success());
+}
+
+#[test]
+fn uses_the_server_of_each_request() {
+    let first = mock("GET", "/api/0/organizations/worker-org/repos/")
+        .match_query(Matcher::Any)
+        .match_header("authorization", "Bearer first-token")
+        .match_header("host", mockito::server_address().to_string().as_str())
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("[]")
+        .expect(1)
+        .create();
+    let second = mock("GET", "/api/0/organizations/worker-org/repos/")
+        .match_query(Matcher::Any)
+        .match_header("authorization", "Bearer second-token")
+        .match_header(
+            "host",
+            format!("localhost:{}", mockito::server_address().port()).as_str(),
+        )
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("[]")
+        .expect(1)
+        .create();
+
+    let mut child = Command::new(cargo_bin("sentry-cli"))
+        .envs(common::get_base_env())
+        .arg("--persistent_worker")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+
+    let mut request = move |url: String, token: &str| {
+        let request = serde_json::json!({
+            "arguments": [
+                "--url", url, "--auth-token", token,
+                "repos", "--org", "worker-org", "list",
+            ],
+        });
+        writeln!(stdin, "{}", request).unwrap();
+        let mut line = String::new();
+        stdout.read_line(&mut line).unwrap();
+        serde_json::from_str::<serde_json::Value>(&line).unwrap()
+    };
+
+    let response = request(server_url(), "first-token");
+    assert_eq!(response["exitCode"], 0, "{}", response["output"]);
+    let url = format!("http://localhost:{}", mockito::server_address().port());
+    let response = request(url, "second-token");
+    assert_eq!(response["exitCode"], 0, "{}", response["output"]);
+
+    drop(request);
+    assert!(child.wait().unwrap().success());
+    first.assert();
+    second.assert();
+}
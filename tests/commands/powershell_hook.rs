@@ -0,0 +1,107 @@
+use std::fs;
+
+use assert_cmd::Command;
+use mockito::{mock, server_address, Matcher};
+use predicates::prelude::*;
+use predicates::str::contains;
+
+use crate::common;
+
+#[test]
+fn prints_powershell_script() {
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["powershell-hook", "--cli", "sentry-cli.exe"])
+        .assert()
+        .success()
+        .stdout(
+            contains("$ErrorActionPreference = 'Stop'")
+                .and(contains("$_SentryExitOnError = $true"))
+                .and(contains(r#"$_SentryCli = "sentry-cli.exe""#))
+                .and(contains("function Invoke-WithSentry"))
+                .and(contains("___").not()),
+        );
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["powershell-hook", "--no-exit", "--no-environ"])
+        .assert()
+        .success()
+        .stdout(
+            contains("$_SentryExitOnError = $false")
+                .and(contains("--log $_SentryLogFile --no-environ"))
+                .and(contains("$ErrorActionPreference").not()),
+        );
+}
+
+#[test]
+fn prints_cmd_wrapper() {
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["powershell-hook", "--cmd", "--cli", "sentry-cli.exe"])
+        .assert()
+        .success()
+        .stdout(
+            contains("call %*")
+                .and(contains(r#""sentry-cli.exe" powershell-hook --send-event"#))
+                .and(contains("___").not()),
+        );
+}
+
+#[test]
+fn sends_powershell_error() {
+    let store = mock("POST", "/api/1/store/")
+        .match_body(Matcher::PartialJson(serde_json::json!({
+            "exception": {"values": [{
+                "type": "PowerShellError",
+                "value": "Cannot find path (command Get-Item missing.txt exited with status 1)",
+                "stacktrace": {"frames": [{
+                    "function": "<ScriptBlock>",
+                    "filename": r"C:\build\deploy.ps1",
+                    "lineno": 12,
+                }]},
+            }]},
+        })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("{}")
+        .expect(1)
+        .create();
+
+    let dir = tempfile::tempdir().unwrap();
+    let traceback = dir.path().join("traceback");
+    let log = dir.path().join("log");
+    fs::write(
+        &traceback,
+        "<ScriptBlock>:C:\\build\\deploy.ps1:12\n\
+         @command:Get-Item missing.txt\n\
+         @exit_code:1\n\
+         @message:Cannot find path\n",
+    )
+    .unwrap();
+    fs::write(&log, "").unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env(
+            "SENTRY_DSN",
+            format!("http://lolnope@{}/1", server_address()),
+        )
+        .args([
+            "powershell-hook",
+            "--send-event",
+            "--traceback",
+            traceback.to_str().unwrap(),
+            "--log",
+            log.to_str().unwrap(),
+            "--no-environ",
+        ])
+        .assert()
+        .success();
+
+    store.assert();
+}
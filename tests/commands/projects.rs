@@ -0,0 +1,124 @@
+use assert_cmd::Command;
+use mockito::{mock, Matcher};
+use predicates::str::contains;
+
+use crate::common;
+
+#[test]
+fn creates_project_for_team() {
+    let _server = mock("POST", "/api/0/teams/wat-org/wat-team/projects/")
+        .match_body(Matcher::Json(serde_json::json!({
+            "name": "Wat Project",
+            "platform": "python",
+            "default_rules": false,
+        })))
+        .with_status(201)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"id":"1861017","slug":"wat-project","name":"Wat Project","team":{"id":"1","slug":"wat-team","name":"Wat Team"}}"#)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "projects",
+            "create",
+            "Wat Project",
+            "--team",
+            "wat-team",
+            "--platform",
+            "python",
+            "--no-default-rules",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("Created project wat-project (1861017)"));
+}
+
+#[test]
+fn deletes_project_with_confirmation() {
+    let _server = mock("DELETE", "/api/0/projects/wat-org/wat-project/")
+        .with_status(204)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec!["projects", "delete", "wat-project", "--confirm"])
+        .assert()
+        .success()
+        .stdout(contains("Deleted project wat-project!"));
+}
+
+#[test]
+fn refuses_project_deletion_without_terminal() {
+    let _server = mock("DELETE", "/api/0/projects/wat-org/wat-project/")
+        .with_status(204)
+        .expect(0)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec!["projects", "delete", "wat-project"])
+        .assert()
+        .failure()
+        .stderr(contains("pass --confirm"));
+
+    _server.assert();
+}
+
+#[test]
+fn skips_existing_project_when_idempotent() {
+    let _project = mock("GET", "/api/0/projects/wat-org/wat-project/")
+        .with_header("content-type", "application/json")
+        .with_body(
+            r#"{"id":"1861017","slug":"wat-project","name":"Wat Project","platform":"python"}"#,
+        )
+        .create();
+    let create = mock("POST", "/api/0/teams/wat-org/wat-team/projects/")
+        .expect(0)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "projects",
+            "create",
+            "Wat Project",
+            "--team",
+            "wat-team",
+            "--slug",
+            "wat-project",
+            "--platform",
+            "python",
+            "--idempotent",
+        ])
+        .assert()
+        .success()
+        .stdout(contains(
+            "Project wat-project (1861017) already exists, nothing changed.",
+        ));
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "projects",
+            "create",
+            "Wat Project",
+            "--team",
+            "wat-team",
+            "--slug",
+            "wat-project",
+            "--platform",
+            "go",
+            "--idempotent",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains("already exists with different settings"));
+
+    create.assert();
+}
@@ -0,0 +1,199 @@
+use std::fs;
+
+use assert_cmd::Command;
+use mockito::{mock, Matcher, Mock};
+use predicates::prelude::*;
+use predicates::str::contains;
+
+use crate::common;
+
+fn mock_live_config() -> Vec<Mock> {
+    vec![
+        mock("GET", "/api/0/projects/wat-org/wat-project/")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"id":"1","slug":"wat-project","name":"Wat Project","platform":"python","subjectPrefix":"[wat]","resolveAge":0,"isBookmarked":false}"#)
+            .create(),
+        mock("GET", "/api/0/projects/wat-org/wat-project/rules/")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"[{"id":"7","name":"Notify on new issues","actionMatch":"all","frequency":30,"conditions":[{"id":"sentry.rules.conditions.first_seen_event.FirstSeenEventCondition"}],"actions":[{"id":"sentry.mail.actions.NotifyEmailAction","targetType":"IssueOwners"}],"dateCreated":"2020-03-19T10:11:35Z"}]"#)
+            .create(),
+        mock("GET", "/api/0/projects/wat-org/wat-project/ownership/")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"raw":"path:src/* #backend","fallthrough":true,"autoAssignment":false,"isActive":true}"#)
+            .create(),
+        mock("GET", "/api/0/projects/wat-org/wat-project/filters/")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"[{"id":"browser-extensions","active":false},{"id":"legacy-browsers","active":["ie_pre_9"]}]"#)
+            .create(),
+    ]
+}
+
+#[test]
+fn exports_config_as_yaml() {
+    let _mocks = mock_live_config();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec!["projects", "export-config"])
+        .assert()
+        .success()
+        .stdout(
+            contains("subjectPrefix: \"[wat]\"")
+                .and(contains("name: Notify on new issues"))
+                .and(contains("fallthrough: true"))
+                .and(contains("browser-extensions: false"))
+                .and(contains("dateCreated").not()),
+        );
+}
+
+#[test]
+fn checks_config_for_drift() {
+    let _mocks = mock_live_config();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("sentry.yml");
+    fs::write(
+        &path,
+        "settings:\n  subjectPrefix: \"[wat]\"\n  resolveAge: 72\n\
+         alertRules:\n  - name: Notify on new issues\n    frequency: 60\n    actionMatch: all\n    \
+         conditions: [{id: sentry.rules.conditions.first_seen_event.FirstSeenEventCondition}]\n    \
+         actions: [{id: sentry.mail.actions.NotifyEmailAction, targetType: IssueOwners}]\n  \
+         - name: Page on spikes\n\
+         filters:\n  legacy-browsers: [ie_pre_9]\n",
+    )
+    .unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec!["projects", "apply-config", "--check"])
+        .arg(&path)
+        .assert()
+        .code(1)
+        .stdout(
+            contains("~ settings.resolveAge: 0 -> 72")
+                .and(contains(
+                    "~ alertRules[Notify on new issues].frequency: 30 -> 60",
+                ))
+                .and(contains("+ alertRules[Page on spikes]"))
+                .and(contains("subjectPrefix").not())
+                .and(contains("legacy-browsers").not()),
+        );
+}
+
+#[test]
+fn applies_config() {
+    let _mocks = mock_live_config();
+    let _settings = mock("PUT", "/api/0/projects/wat-org/wat-project/")
+        .match_body(Matcher::Json(serde_json::json!({"resolveAge": 72})))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"resolveAge":72}"#)
+        .expect(1)
+        .create();
+    let _filter = mock(
+        "PUT",
+        "/api/0/projects/wat-org/wat-project/filters/browser-extensions/",
+    )
+    .match_body(Matcher::Json(serde_json::json!({"active": true})))
+    .with_status(204)
+    .expect(1)
+    .create();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("sentry.yml");
+    fs::write(
+        &path,
+        "settings:\n  resolveAge: 72\nfilters:\n  browser-extensions: true\n",
+    )
+    .unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec!["projects", "apply-config", "--confirm"])
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout(contains("Applied 2 changes."));
+
+    _settings.assert();
+    _filter.assert();
+}
+
+#[test]
+fn keeps_unset_alert_rule_fields() {
+    let _mocks = mock_live_config();
+    let _rule = mock("PUT", "/api/0/projects/wat-org/wat-project/rules/7/")
+        .match_body(Matcher::Json(serde_json::json!({
+            "id": "7",
+            "name": "Notify on new issues",
+            "actionMatch": "all",
+            "frequency": 60,
+            "conditions": [{"id": "sentry.rules.conditions.first_seen_event.FirstSeenEventCondition"}],
+            "actions": [{"id": "sentry.mail.actions.NotifyEmailAction", "targetType": "IssueOwners"}],
+        })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"id":"7","name":"Notify on new issues","frequency":60}"#)
+        .expect(1)
+        .create();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("sentry.yml");
+    fs::write(
+        &path,
+        "alertRules:\n  - name: Notify on new issues\n    frequency: 60\n",
+    )
+    .unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec!["projects", "apply-config", "--confirm"])
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout(
+            contains("~ alertRules[Notify on new issues].frequency: 30 -> 60")
+                .and(contains("Applied 1 changes.")),
+        );
+
+    _rule.assert();
+}
+
+#[test]
+fn keeps_unset_ownership_fields() {
+    let _mocks = mock_live_config();
+    let _ownership = mock("PUT", "/api/0/projects/wat-org/wat-project/ownership/")
+        .match_body(Matcher::Json(serde_json::json!({
+            "raw": "path:src/* #backend",
+            "fallthrough": true,
+            "autoAssignment": true,
+        })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"raw":"path:src/* #backend","fallthrough":true,"autoAssignment":true}"#)
+        .expect(1)
+        .create();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("sentry.yml");
+    fs::write(&path, "ownership:\n  autoAssignment: true\n").unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec!["projects", "apply-config", "--confirm"])
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout(
+            contains("~ ownership.autoAssignment: false -> true")
+                .and(contains("fallthrough").not())
+                .and(contains("Applied 1 changes.")),
+        );
+
+    _ownership.assert();
+}
@@ -0,0 +1,49 @@
+use std::fs;
+
+use assert_cmd::Command;
+use mockito::{mock, Matcher};
+use predicates::str::contains;
+
+use crate::common;
+
+#[test]
+fn syncs_ownership_from_codeowners() {
+    let _get = mock("GET", "/api/0/projects/wat-org/wat-project/ownership/")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"raw":"path:* #old-team\n","fallthrough":false,"autoAssignment":true}"#)
+        .create();
+    let _put = mock("PUT", "/api/0/projects/wat-org/wat-project/ownership/")
+        .match_body(Matcher::Json(serde_json::json!({
+            "raw": "path:* #core\npath:*.js jane@example.com\n",
+            "fallthrough": false,
+            "autoAssignment": true,
+        })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"raw":"","fallthrough":false}"#)
+        .expect(1)
+        .create();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("CODEOWNERS");
+    fs::write(&path, "* @wat-org/core\n*.js @octocat @someone\n").unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "projects",
+            "ownership",
+            "sync",
+            "--map",
+            "@octocat=jane@example.com",
+            "--from",
+        ])
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout(contains("Synced 2 ownership rules"))
+        .stderr(contains("skipping owner @someone"));
+
+    _put.assert();
+}
@@ -0,0 +1,165 @@
+use std::fs;
+
+use assert_cmd::Command;
+use mockito::{mock, Matcher};
+use predicates::str::contains;
+
+use crate::common;
+
+const PROJECT_DETAILS_BODY: &str = r#"{"id":"1","slug":"wat-project","name":"Wat Project","symbolSources":"[{\"id\":\"internal\",\"type\":\"http\",\"name\":\"Internal\",\"url\":\"https://symbols.example.com/\",\"layout\":{\"type\":\"symstore\",\"casing\":\"default\"}}]"}"#;
+
+#[test]
+fn adds_s3_symbol_source() {
+    let _details = mock("GET", "/api/0/projects/wat-org/wat-project/")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(PROJECT_DETAILS_BODY)
+        .create();
+    let _update = mock("PUT", "/api/0/projects/wat-org/wat-project/")
+        .match_body(Matcher::Json(serde_json::json!({
+            "symbolSources": serde_json::to_string(&serde_json::json!([
+                {"id": "internal", "type": "http", "name": "Internal", "url": "https://symbols.example.com/", "layout": {"type": "symstore", "casing": "default"}},
+                {"id": "s3-symbols", "type": "s3", "name": "S3", "bucket": "wat-symbols", "region": "eu-west-1", "access_key": "AKIA", "secret_key": "s3cr3t", "layout": {"type": "native", "casing": "default"}},
+            ])).unwrap(),
+        })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("{}")
+        .expect(1)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "projects",
+            "symbol-sources",
+            "add",
+            "--type",
+            "s3",
+            "--id",
+            "s3-symbols",
+            "--name",
+            "S3",
+            "--bucket",
+            "wat-symbols",
+            "--region",
+            "eu-west-1",
+            "--access-key",
+            "AKIA",
+            "--secret-key",
+            "s3cr3t",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("Added symbol source s3-symbols"));
+
+    _update.assert();
+}
+
+#[test]
+fn reads_symbol_source_password_from_file() {
+    let _details = mock("GET", "/api/0/projects/wat-org/wat-project/")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"id":"1","slug":"wat-project","name":"Wat Project","symbolSources":""}"#)
+        .create();
+    let _update = mock("PUT", "/api/0/projects/wat-org/wat-project/")
+        .match_body(Matcher::Json(serde_json::json!({
+            "symbolSources": serde_json::to_string(&serde_json::json!([
+                {"id": "internal", "type": "http", "name": "Internal", "url": "https://symbols.example.com/", "username": "ci", "password": "hunter2", "layout": {"type": "native", "casing": "default"}},
+            ])).unwrap(),
+        })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("{}")
+        .expect(1)
+        .create();
+
+    let dir = tempfile::tempdir().unwrap();
+    let password_file = dir.path().join("password");
+    fs::write(&password_file, "hunter2\n").unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "projects",
+            "symbol-sources",
+            "add",
+            "--type",
+            "http",
+            "--id",
+            "internal",
+            "--name",
+            "Internal",
+            "--url",
+            "https://symbols.example.com/",
+            "--username",
+            "ci",
+            "--password-file",
+            password_file.to_str().unwrap(),
+        ])
+        .assert()
+        .success()
+        .stdout(contains("Added symbol source internal"));
+
+    _update.assert();
+}
+
+#[test]
+fn requires_s3_secret_key() {
+    let _details = mock("GET", "/api/0/projects/wat-org/wat-project/")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(PROJECT_DETAILS_BODY)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "projects",
+            "symbol-sources",
+            "add",
+            "--type",
+            "s3",
+            "--name",
+            "S3",
+            "--bucket",
+            "wat-symbols",
+            "--region",
+            "eu-west-1",
+            "--access-key",
+            "AKIA",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains("S3 symbol sources need a secret key"));
+}
+
+#[test]
+fn removes_symbol_source() {
+    let _details = mock("GET", "/api/0/projects/wat-org/wat-project/")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(PROJECT_DETAILS_BODY)
+        .create();
+    let _update = mock("PUT", "/api/0/projects/wat-org/wat-project/")
+        .match_body(Matcher::Json(serde_json::json!({"symbolSources": "[]"})))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("{}")
+        .expect(1)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec!["projects", "symbol-sources", "remove", "internal"])
+        .assert()
+        .success()
+        .stdout(contains("Removed symbol source internal"));
+
+    _update.assert();
+}
@@ -0,0 +1,187 @@
+use std::fs;
+
+use assert_cmd::Command;
+use mockito::{mock, server_url, Matcher};
+use predicates::prelude::*;
+use predicates::str::{contains, is_match};
+
+use crate::common;
+
+const RELEASE: &str = r#"{"dateReleased":null,"newGroups":0,"commitCount":0,"url":null,"data":{},"lastDeploy":null,"deployCount":0,"dateCreated":"2020-06-29T11:36:59.612687Z","lastEvent":null,"version":"wat-beta","firstEvent":null,"lastCommit":null,"shortVersion":"wat","authors":[],"owner":null,"versionInfo":null,"ref":null,"projects":[]}"#;
+
+#[test]
+fn uploads_selected_variant() {
+    let release = mock("POST", "/api/0/projects/wat-org/wat-project/releases/")
+        .match_body(Matcher::PartialJsonString(
+            r#"{"version":"wat-beta"}"#.to_string(),
+        ))
+        .with_status(201)
+        .with_header("content-type", "application/json")
+        .with_body(RELEASE)
+        .create();
+    let _chunk_upload = mock("GET", "/api/0/organizations/wat-org/chunk-upload/")
+        .with_status(404)
+        .create();
+    let _files = mock(
+        "GET",
+        "/api/0/projects/wat-org/wat-project/releases/wat-beta/files/",
+    )
+    .match_query(Matcher::Any)
+    .with_status(200)
+    .with_header("content-type", "application/json")
+    .with_body("[]")
+    .create();
+    let upload = mock(
+        "POST",
+        "/api/0/projects/wat-org/wat-project/releases/wat-beta/files/",
+    )
+    .with_status(409)
+    .expect(4)
+    .create();
+
+    let dir = tempfile::tempdir().unwrap();
+    for variant in &["production", "beta"] {
+        let out = dir.path().join(variant);
+        fs::create_dir(&out).unwrap();
+        fs::write(out.join("index.android.bundle"), "console.log(1);\n").unwrap();
+        fs::write(
+            out.join("index.android.bundle.map"),
+            r#"{"version":3,"sources":["index.js"],"sourcesContent":["console.log(1);"],"names":[],"mappings":"AAAA"}"#,
+        )
+        .unwrap();
+    }
+    let variants = dir.path().join("sentry-variants.json");
+    fs::write(
+        &variants,
+        r#"{"variants": [
+            {"name": "productionRelease", "bundle": "production/index.android.bundle",
+             "sourcemap": "production/index.android.bundle.map", "release": "wat-production"},
+            {"name": "betaRelease", "bundle": "beta/index.android.bundle",
+             "sourcemap": "beta/index.android.bundle.map", "release": "wat-beta",
+             "dists": ["1", "2"]}
+        ]}"#,
+    )
+    .unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args([
+            "react-native",
+            "gradle",
+            "--variants",
+            variants.to_str().unwrap(),
+            "--variant",
+            "betaRelease",
+            "--size-report",
+        ])
+        .assert()
+        .success()
+        .stdout(
+            contains("Uploading variant betaRelease")
+                .and(contains("release wat-beta distribution 2"))
+                .and(contains("productionRelease").not())
+                .and(contains("JavaScript Bundle Size Report"))
+                .and(is_match(r"sourcemap [\d.]+ ?\w*B \(1 file\)").unwrap()),
+        );
+
+    release.assert();
+    upload.assert();
+}
+
+#[test]
+fn uploads_bundle_once_for_all_dists() {
+    let _release = mock("POST", "/api/0/projects/wat-org/wat-project/releases/")
+        .with_status(201)
+        .with_header("content-type", "application/json")
+        .with_body(RELEASE)
+        .create();
+    let _chunk_options = mock("GET", "/api/0/organizations/wat-org/chunk-upload/")
+        .with_header("content-type", "application/json")
+        .with_body(format!(
+            r#"{{"url": "{}/api/0/organizations/wat-org/chunk-upload/",
+                "chunksPerRequest": 64, "maxRequestSize": 33554432, "chunkSize": 8388608,
+                "hashAlgorithm": "sha1", "concurrency": 1,
+                "accept": ["release_files", "artifact_bundles"]}}"#,
+            server_url()
+        ))
+        .create();
+    let chunks = mock("POST", "/api/0/organizations/wat-org/chunk-upload/")
+        .with_status(200)
+        .expect(1)
+        .create();
+    let assemble = |dist: &str| {
+        mock(
+            "POST",
+            "/api/0/organizations/wat-org/artifactbundle/assemble/",
+        )
+        .match_body(Matcher::PartialJson(serde_json::json!({
+            "projects": ["wat-project"],
+            "version": "wat-beta",
+            "dist": dist,
+        })))
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"state": "created", "missingChunks": []}"#)
+        .expect(1)
+        .create()
+    };
+    let assemble_1 = assemble("1");
+    let assemble_2 = assemble("2");
+
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("index.android.bundle"), "console.log(1);\n").unwrap();
+    fs::write(
+        dir.path().join("index.android.bundle.map"),
+        r#"{"version":3,"sources":["index.js"],"sourcesContent":["console.log(1);"],"names":[],"mappings":"AAAA"}"#,
+    )
+    .unwrap();
+    let variants = dir.path().join("sentry-variants.json");
+    fs::write(
+        &variants,
+        r#"{"variants": [
+            {"name": "betaRelease", "bundle": "index.android.bundle",
+             "sourcemap": "index.android.bundle.map", "release": "wat-beta",
+             "dists": ["1", "2"]}
+        ]}"#,
+    )
+    .unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args([
+            "react-native",
+            "gradle",
+            "--variants",
+            variants.to_str().unwrap(),
+        ])
+        .assert()
+        .success()
+        .stdout(contains("release wat-beta distribution 1").and(contains("> Dist: 2")));
+
+    chunks.assert();
+    assemble_1.assert();
+    assemble_2.assert();
+}
+
+#[test]
+fn fails_for_unknown_variant() {
+    let dir = tempfile::tempdir().unwrap();
+    let variants = dir.path().join("sentry-variants.json");
+    fs::write(&variants, r#"{"variants": []}"#).unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args([
+            "react-native",
+            "gradle",
+            "--variants",
+            variants.to_str().unwrap(),
+            "--variant",
+            "betaRelease",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains("Variant betaRelease not found"));
+}
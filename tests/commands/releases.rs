@@ -36,3 +36,29 @@ fn allow_for_overriding_organization_with_flag_for_subcommands() {
         .assert()
         .success();
 }
+
+#[test]
+fn proposes_version_of_android_app() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("app")).unwrap();
+    std::fs::write(dir.path().join("build.gradle"), "").unwrap();
+    std::fs::write(
+        dir.path().join("app/build.gradle"),
+        "android {\n  defaultConfig {\n    applicationId \"io.sentry.sample\"\n    \
+         versionCode 42\n    versionName \"1.2.3\"\n  }\n}\n",
+    )
+    .unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env_remove("SOURCE_VERSION")
+        .env_remove("HEROKU_SLUG_COMMIT")
+        .env_remove("CODEBUILD_RESOLVED_SOURCE_VERSION")
+        .env_remove("CIRCLE_SHA1")
+        .current_dir(dir.path())
+        .args(["releases", "propose-version"])
+        .assert()
+        .success()
+        .stdout("io.sentry.sample@1.2.3\n");
+}
@@ -0,0 +1,52 @@
+use assert_cmd::Command;
+use mockito::{mock, Matcher};
+
+use crate::common;
+
+#[test]
+fn renders_changelog_between_releases() {
+    let _releases = mock("GET", "/api/0/projects/wat-org/wat-project/releases/")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            r#"[
+            {"version":"1.3.0","dateCreated":"2021-06-03T10:00:00Z"},
+            {"version":"1.2.1","dateCreated":"2021-06-02T10:00:00Z"},
+            {"version":"1.2.0","dateCreated":"2021-06-01T10:00:00Z"}
+        ]"#,
+        )
+        .create();
+    let _commits_130 = mock("GET", "/api/0/organizations/wat-org/releases/1.3.0/commits/")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"[{"id":"bbbbbbb2","message":"feat(ui): Add dark mode","dateCreated":"2021-06-03T09:00:00Z","author":{"name":"John","email":null}}]"#)
+        .create();
+    let _commits_121 = mock("GET", "/api/0/organizations/wat-org/releases/1.2.1/commits/")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"[{"id":"aaaaaaa1","message":"fix: Round totals","dateCreated":"2021-06-02T09:00:00Z","author":{"name":"Jane","email":null}}]"#)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "releases",
+            "changelog",
+            "--from",
+            "1.2.0",
+            "--to",
+            "1.3.0",
+        ])
+        .assert()
+        .success()
+        .stdout(
+            "## 1.3.0\n\
+             \n### Features\n\n\
+             - **ui:** Add dark mode (bbbbbbb, John)\n\
+             \n### Bug Fixes\n\n\
+             - Round totals (aaaaaaa, Jane)\n",
+        );
+}
@@ -0,0 +1,323 @@
+use assert_cmd::Command;
+use mockito::{mock, Matcher};
+use predicates::prelude::*;
+use predicates::str::contains;
+
+use crate::common;
+
+const DEPLOYS_BODY: &str = r#"[{"id":"1","environment":"staging","name":null,"url":null,"dateStarted":null,"dateFinished":"2021-06-01T10:00:00Z"},{"id":"2","environment":"production","name":"rollout","url":null,"dateStarted":null,"dateFinished":"2021-06-02T10:00:00Z"},{"id":"3","environment":"production","name":null,"url":null,"dateStarted":null,"dateFinished":"2021-06-10T10:00:00Z"}]"#;
+
+#[test]
+fn lists_deploys_filtered_by_env_and_date() {
+    let _server = mock(
+        "GET",
+        "/api/0/organizations/wat-org/releases/wat-release/deploys/",
+    )
+    .with_status(200)
+    .with_header("content-type", "application/json")
+    .with_body(DEPLOYS_BODY)
+    .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "releases",
+            "deploys",
+            "wat-release",
+            "list",
+            "--env",
+            "production",
+            "--until",
+            "2021-06-05T00:00:00Z",
+            "--json",
+        ])
+        .assert()
+        .success()
+        .stdout(
+            contains(r#""name": "rollout""#)
+                .and(contains(r#""id": "1""#).not())
+                .and(contains(r#""id": "3""#).not()),
+        );
+}
+
+#[test]
+fn prints_latest_deployed_release() {
+    let _releases = mock("GET", "/api/0/organizations/wat-org/releases/")
+        .match_query(Matcher::UrlEncoded(
+            "environment".into(),
+            "production".into(),
+        ))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"[
+            {"version":"new","dateCreated":"2021-06-09T10:00:00Z","lastDeploy":{"id":"4","environment":"staging","dateFinished":"2021-06-11T10:00:00Z"}},
+            {"version":"old","dateCreated":"2021-06-01T10:00:00Z","lastDeploy":{"id":"2","environment":"production","dateFinished":"2021-06-02T10:00:00Z"}},
+            {"version":"never","dateCreated":"2021-05-01T10:00:00Z","lastDeploy":null}
+        ]"#)
+        .create();
+    let _deploys = mock("GET", "/api/0/organizations/wat-org/releases/new/deploys/")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"[{"id":"3","environment":"production","dateFinished":"2021-06-10T10:00:00Z"},{"id":"4","environment":"staging","dateFinished":"2021-06-11T10:00:00Z"}]"#)
+        .expect(1)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec!["releases", "deploys", "latest", "--env", "production"])
+        .assert()
+        .success()
+        .stdout("new\n");
+
+    _deploys.assert();
+}
+
+#[test]
+fn finds_latest_deploy_on_later_pages() {
+    let _first_page = mock("GET", "/api/0/organizations/wat-org/releases/")
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("environment".into(), "production".into()),
+            Matcher::UrlEncoded("cursor".into(), "".into()),
+        ]))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_header(
+            "link",
+            r#"<http://localhost/api/0/organizations/wat-org/releases/?cursor=100:1:0>; rel="next"; results="true"; cursor="100:1:0""#,
+        )
+        .with_body(r#"[{"version":"old","dateCreated":"2021-06-01T10:00:00Z","lastDeploy":{"id":"2","environment":"production","dateFinished":"2021-06-02T10:00:00Z"}}]"#)
+        .create();
+    let _second_page = mock("GET", "/api/0/organizations/wat-org/releases/")
+        .match_query(Matcher::UrlEncoded("cursor".into(), "100:1:0".into()))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"[{"version":"new","dateCreated":"2021-05-01T10:00:00Z","lastDeploy":{"id":"3","environment":"production","dateFinished":"2021-06-10T10:00:00Z"}}]"#)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec!["releases", "deploys", "latest", "--env", "production"])
+        .assert()
+        .success()
+        .stdout("new\n");
+}
+
+#[test]
+fn fails_without_deploys_to_env() {
+    let _releases = mock("GET", "/api/0/organizations/wat-org/releases/")
+        .match_query(Matcher::UrlEncoded("environment".into(), "canary".into()))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("[]")
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec!["releases", "deploys", "latest", "--env", "canary"])
+        .assert()
+        .failure()
+        .stderr(contains("No deploys to canary found"));
+}
+
+#[test]
+fn attaches_ci_build_url_to_new_deploy() {
+    let _server = mock(
+        "POST",
+        "/api/0/organizations/wat-org/releases/wat-release/deploys/",
+    )
+    .match_body(Matcher::PartialJson(serde_json::json!({
+        "environment": "production",
+        "url": "https://github.com/wat-org/wat/actions/runs/42",
+    })))
+    .with_status(201)
+    .with_header("content-type", "application/json")
+    .with_body(r#"{"id":"5","environment":"production","name":null,"url":"https://github.com/wat-org/wat/actions/runs/42","dateStarted":null,"dateFinished":"2021-06-10T10:00:00Z"}"#)
+    .expect(1)
+    .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env("GITHUB_SERVER_URL", "https://github.com")
+        .env("GITHUB_REPOSITORY", "wat-org/wat")
+        .env("GITHUB_RUN_ID", "42")
+        .args(vec![
+            "releases",
+            "deploys",
+            "wat-release",
+            "new",
+            "--env",
+            "production",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("Created new deploy unnamed for 'production'"));
+
+    _server.assert();
+}
+
+#[test]
+fn rolls_back_to_previous_release() {
+    let _deploys = mock("GET", "/api/0/organizations/wat-org/deploys/")
+        .match_query(Matcher::UrlEncoded(
+            "environment".into(),
+            "production".into(),
+        ))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"[
+            {"id":"1","releaseVersion":"v1","environment":"production","dateFinished":"2021-06-02T10:00:00Z"},
+            {"id":"2","releaseVersion":"v2","environment":"production","dateFinished":"2021-06-10T10:00:00Z"},
+            {"id":"4","releaseVersion":"v3","environment":"production","dateStarted":"2021-06-11T10:00:00Z"}
+        ]"#)
+        .expect(1)
+        .create();
+    let _issues = mock("GET", "/api/0/projects/wat-org/wat-project/issues/")
+        .match_query(Matcher::UrlEncoded(
+            "query".into(),
+            "is:resolved release:\"v1\"".into(),
+        ))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"[
+            {"id":"11","shortId":"WAT-1","title":"Boom","status":"resolved","firstSeen":"2021-06-01T10:00:00Z","lastSeen":"2021-06-09T10:00:00Z","statusDetails":{"inRelease":"v2"}},
+            {"id":"12","shortId":"WAT-2","title":"Bang","status":"resolved","firstSeen":"2021-06-01T10:00:00Z","lastSeen":"2021-06-09T10:00:00Z","statusDetails":{}}
+        ]"#)
+        .expect(1)
+        .create();
+    let reopen = mock("PUT", "/api/0/projects/wat-org/wat-project/issues/?id=11")
+        .match_body(Matcher::Json(serde_json::json!({"status": "unresolved"})))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("{}")
+        .expect(1)
+        .create();
+    let deploy = mock("POST", "/api/0/organizations/wat-org/releases/v1/deploys/")
+        .match_body(Matcher::PartialJson(serde_json::json!({
+            "environment": "production",
+            "name": "Rollback from v2",
+        })))
+        .with_status(201)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"id":"3","environment":"production","name":"Rollback from v2","dateFinished":"2021-06-11T10:00:00Z"}"#)
+        .expect(1)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "releases",
+            "deploys",
+            "rollback",
+            "--env",
+            "production",
+            "--reopen-resolved",
+        ])
+        .assert()
+        .success()
+        .stdout(
+            contains("Rolled back production from v2 to v1")
+                .and(contains("Created deploy Rollback from v2 of v1"))
+                .and(contains("Reopened 1 issues resolved in v2"))
+                .and(contains("WAT-1 Boom")),
+        );
+
+    _deploys.assert();
+    _issues.assert();
+    deploy.assert();
+    reopen.assert();
+}
+
+#[test]
+fn sends_signed_webhook_for_new_deploy() {
+    let _server = mock(
+        "POST",
+        "/api/0/organizations/wat-org/releases/wat-release/deploys/",
+    )
+    .with_status(201)
+    .with_header("content-type", "application/json")
+    .with_body(r#"{"id":"5","environment":"staging","name":null,"url":null,"dateStarted":null,"dateFinished":"2021-06-10T10:00:00Z"}"#)
+    .create();
+    let hook = mock("POST", "/hooks/deploys")
+        .match_header(
+            "x-sentry-cli-signature",
+            Matcher::Regex("^sha256=[0-9a-f]{64}$".into()),
+        )
+        .match_header("x-sentry-cli-timestamp", Matcher::Regex(r"^\d+$".into()))
+        .match_body(Matcher::PartialJson(serde_json::json!({
+            "event": "deploy.created",
+            "org": "wat-org",
+            "release": {"version": "wat-release"},
+            "deploy": {"environment": "staging"},
+        })))
+        .with_status(204)
+        .expect(1)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env("SENTRY_WEBHOOK_SECRET", "s3cret")
+        .args(vec![
+            "releases",
+            "deploys",
+            "wat-release",
+            "new",
+            "--env",
+            "staging",
+            "--no-build-url",
+            "--webhook",
+            &format!("{}/hooks/deploys", mockito::server_url()),
+        ])
+        .assert()
+        .success()
+        .stderr(contains("warning").not());
+
+    hook.assert();
+}
+
+#[test]
+fn skips_existing_deploy_when_idempotent() {
+    let _deploys = mock(
+        "GET",
+        "/api/0/organizations/wat-org/releases/idempotent-release/deploys/",
+    )
+    .with_header("content-type", "application/json")
+    .with_body(r#"[{"id":"5","environment":"production","name":"blue","url":"https://ci.example.com/1","dateStarted":null,"dateFinished":"2021-06-10T10:00:00Z"}]"#)
+    .create();
+    let create = mock(
+        "POST",
+        "/api/0/organizations/wat-org/releases/idempotent-release/deploys/",
+    )
+    .expect(0)
+    .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "--machine-readable",
+            "releases",
+            "deploys",
+            "idempotent-release",
+            "new",
+            "--env",
+            "production",
+            "--name",
+            "blue",
+            "--idempotent",
+        ])
+        .assert()
+        .success()
+        .stdout(contains(r#""changed":false"#))
+        .stderr(contains(
+            "Deploy blue for 'production' already exists, nothing changed.",
+        ));
+
+    create.assert();
+}
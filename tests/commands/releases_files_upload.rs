@@ -0,0 +1,553 @@
+use assert_cmd::Command;
+use mockito::{mock, server_url, Matcher};
+use predicates::str::contains;
+
+use crate::common;
+
+#[test]
+fn uploads_file_from_s3() {
+    let object = mock("GET", "/builds/web/app.js")
+        .match_header(
+            "authorization",
+            Matcher::Regex(
+                r"^AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/\d{8}/eu-west-1/s3/aws4_request,SignedHeaders=host;x-amz-content-sha256;x-amz-date,Signature=[0-9a-f]{64}$".into(),
+            ),
+        )
+        .with_body("console.log(1);\n")
+        .expect(1)
+        .create();
+    let upload = mock(
+        "POST",
+        "/api/0/projects/wat-org/wat-project/releases/1.0/files/",
+    )
+    .match_body(Matcher::Regex("console.log\\(1\\);".into()))
+    .with_status(201)
+    .with_header("content-type", "application/json")
+    .with_body(
+        r#"{"id":"1","sha1":"fa4e4b1e","name":"~/app.js","size":16,"dist":null,"headers":{}}"#,
+    )
+    .expect(1)
+    .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env("AWS_ENDPOINT_URL", server_url())
+        .env("AWS_REGION", "eu-west-1")
+        .env("AWS_ACCESS_KEY_ID", "AKIDEXAMPLE")
+        .env("AWS_SECRET_ACCESS_KEY", "secret")
+        .args([
+            "releases",
+            "files",
+            "1.0",
+            "upload",
+            "s3://builds/web/app.js",
+            "~/app.js",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("A fa4e4b1e  (16 bytes)"));
+
+    object.assert();
+    upload.assert();
+}
+
+#[test]
+fn fails_for_missing_objects() {
+    let _object = mock("GET", "/builds/missing.js").with_status(404).create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env("AWS_ENDPOINT_URL", server_url())
+        .args([
+            "releases",
+            "files",
+            "1.0",
+            "upload",
+            "s3://builds/missing.js",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains(
+            "Could not download s3://builds/missing.js (status 404)",
+        ));
+}
+
+#[test]
+fn stores_headers_by_glob() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("app.js"), "console.log(1);\n").unwrap();
+    std::fs::create_dir(dir.path().join("wasm")).unwrap();
+    std::fs::write(dir.path().join("wasm/app.wasm"), "\0asm").unwrap();
+
+    let _chunk_upload = mock("GET", "/api/0/organizations/wat-org/chunk-upload/")
+        .with_status(404)
+        .create();
+    let _files = mock(
+        "GET",
+        Matcher::Regex("^/api/0/projects/wat-org/wat-project/releases/1.0/files/".into()),
+    )
+    .with_header("content-type", "application/json")
+    .with_body("[]")
+    .create();
+    let js = mock(
+        "POST",
+        "/api/0/projects/wat-org/wat-project/releases/1.0/files/",
+    )
+    .match_body(Matcher::Regex(
+        r"(?s)~/app\.js.*SourceMap:app\.js\.map".into(),
+    ))
+    .with_status(201)
+    .with_header("content-type", "application/json")
+    .with_body(r#"{"id":"1","sha1":"","name":"~/app.js","size":16,"dist":null,"headers":{}}"#)
+    .expect(1)
+    .create();
+    let wasm = mock(
+        "POST",
+        "/api/0/projects/wat-org/wat-project/releases/1.0/files/",
+    )
+    .match_body(Matcher::Regex(
+        r"(?s)~/wasm/app\.wasm.*SourceMap:app\.js\.map.*Content-Type:application/wasm".into(),
+    ))
+    .with_status(201)
+    .with_header("content-type", "application/json")
+    .with_body(r#"{"id":"2","sha1":"","name":"~/wasm/app.wasm","size":4,"dist":null,"headers":{}}"#)
+    .expect(1)
+    .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args([
+            "releases",
+            "files",
+            "1.0",
+            "upload",
+            "--ext",
+            "js",
+            "--ext",
+            "wasm",
+            "-H",
+            "SourceMap: app.js.map",
+            "--header-rule",
+            "*.wasm=Content-Type:application/wasm",
+        ])
+        .arg(dir.path())
+        .assert()
+        .success();
+
+    js.assert();
+    wasm.assert();
+}
+
+#[test]
+fn writes_checksums_manifest() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("app.js");
+    std::fs::write(&path, "console.log(1);\n").unwrap();
+    let manifest = dir.path().join("manifest.json");
+
+    let _upload = mock(
+        "POST",
+        "/api/0/projects/wat-org/wat-project/releases/1.0/files/",
+    )
+    .with_status(201)
+    .with_header("content-type", "application/json")
+    .with_body(
+        r#"{"id":"1","sha1":"fa4e4b1e","name":"~/app.js","size":16,"dist":"web","headers":{}}"#,
+    )
+    .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["releases", "files", "1.0", "upload", "--dist", "web"])
+        .arg(&path)
+        .arg("~/app.js")
+        .arg("--checksums-out")
+        .arg(&manifest)
+        .assert()
+        .success();
+
+    let manifest: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(&manifest).unwrap()).unwrap();
+    assert_eq!(
+        manifest,
+        serde_json::json!({
+            "version": 1,
+            "artifacts": [{
+                "name": "~/app.js",
+                "size": 16,
+                "sha256": "b603d946eb2b396ca4ecf65c223daff659dbe6f1cfeac235b7c61d3ba6964cae",
+                "release": "1.0",
+                "dist": "web",
+            }],
+        })
+    );
+}
+
+#[cfg(unix)]
+fn symlinked_dist() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("Static")).unwrap();
+    std::fs::write(dir.path().join("Static/App.js"), "console.log(1);\n").unwrap();
+    std::os::unix::fs::symlink("App.js", dir.path().join("Static/Alias.js")).unwrap();
+    std::os::unix::fs::symlink("Missing.js", dir.path().join("Static/Broken.js")).unwrap();
+    dir
+}
+
+#[cfg(unix)]
+#[test]
+fn skips_broken_symlinks() {
+    let dir = symlinked_dist();
+    let _chunk_upload = mock("GET", "/api/0/organizations/wat-org/chunk-upload/")
+        .with_status(404)
+        .create();
+    let _files = mock(
+        "GET",
+        Matcher::Regex("^/api/0/projects/wat-org/wat-project/releases/1.0/files/".into()),
+    )
+    .with_header("content-type", "application/json")
+    .with_body("[]")
+    .create();
+    let upload = mock(
+        "POST",
+        "/api/0/projects/wat-org/wat-project/releases/1.0/files/",
+    )
+    .match_body(Matcher::Regex("~/Static/A".into()))
+    .with_status(201)
+    .with_header("content-type", "application/json")
+    .with_body(
+        r#"{"id":"1","sha1":"","name":"~/Static/App.js","size":16,"dist":null,"headers":{}}"#,
+    )
+    .expect(2)
+    .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["releases", "files", "1.0", "upload"])
+        .arg(dir.path())
+        .assert()
+        .success()
+        .stdout(contains("Found 2 release files"));
+
+    upload.assert();
+}
+
+#[cfg(unix)]
+#[test]
+fn lowercases_urls_without_symlinks() {
+    let dir = symlinked_dist();
+    let _chunk_upload = mock("GET", "/api/0/organizations/wat-org/chunk-upload/")
+        .with_status(404)
+        .create();
+    let _files = mock(
+        "GET",
+        Matcher::Regex("^/api/0/projects/wat-org/wat-project/releases/1.0/files/".into()),
+    )
+    .with_header("content-type", "application/json")
+    .with_body("[]")
+    .create();
+    let upload = mock(
+        "POST",
+        "/api/0/projects/wat-org/wat-project/releases/1.0/files/",
+    )
+    .match_body(Matcher::Regex("https://example.com/static/app.js".into()))
+    .with_status(201)
+    .with_header("content-type", "application/json")
+    .with_body(r#"{"id":"1","sha1":"","name":"https://example.com/static/app.js","size":16,"dist":null,"headers":{}}"#)
+    .expect(1)
+    .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args([
+            "releases",
+            "files",
+            "1.0",
+            "upload",
+            "--no-follow-symlinks",
+            "--url-case",
+            "lower",
+            "--url-prefix",
+            "https://example.com",
+        ])
+        .arg(dir.path())
+        .assert()
+        .success()
+        .stdout(contains("Found 1 release file"));
+
+    upload.assert();
+}
+
+fn dist_with_large_file() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("app.js"), "console.log(1);\n").unwrap();
+    std::fs::write(dir.path().join("vendor.js"), "x".repeat(2048)).unwrap();
+    dir
+}
+
+#[test]
+fn skips_files_exceeding_size_limit() {
+    let dir = dist_with_large_file();
+    let _chunk_upload = mock("GET", "/api/0/organizations/wat-org/chunk-upload/")
+        .with_status(404)
+        .create();
+    let _files = mock(
+        "GET",
+        Matcher::Regex("^/api/0/projects/wat-org/wat-project/releases/1.0/files/".into()),
+    )
+    .with_header("content-type", "application/json")
+    .with_body("[]")
+    .create();
+    let upload = mock(
+        "POST",
+        "/api/0/projects/wat-org/wat-project/releases/1.0/files/",
+    )
+    .match_body(Matcher::Regex("~/app\\.js".into()))
+    .with_status(201)
+    .with_header("content-type", "application/json")
+    .with_body(r#"{"id":"1","sha1":"","name":"~/app.js","size":16,"dist":null,"headers":{}}"#)
+    .expect(1)
+    .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["releases", "files", "1.0", "upload", "--max-file-size", "1KB"])
+        .arg(dir.path())
+        .assert()
+        .success()
+        .stdout(contains(
+            "Skipped 1 file exceeding upload limits:\n  ~/vendor.js (2.00KiB, larger than 1.00KiB per file)",
+        ));
+
+    upload.assert();
+}
+
+#[test]
+fn fails_for_files_exceeding_total_limit() {
+    let dir = dist_with_large_file();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env("SENTRY_MAX_UPLOAD_TOTAL_SIZE", "2048")
+        .args(["releases", "files", "1.0", "upload", "--fail-on-oversize"])
+        .arg(dir.path())
+        .assert()
+        .failure()
+        .stderr(contains(
+            "1 file exceeds upload limits:\n  ~/vendor.js (2.00KiB, over 2.00KiB for all files)",
+        ));
+}
+
+#[test]
+fn applies_upload_plan() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("dist")).unwrap();
+    std::fs::write(dir.path().join("dist/app.js"), "console.log(1);\n").unwrap();
+    let plan = dir.path().join("plan");
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args([
+            "releases", "files", "1.0", "upload", "--dist", "web", "--plan",
+        ])
+        .arg(&plan)
+        .arg(dir.path().join("dist"))
+        .assert()
+        .success()
+        .stdout(contains("Wrote upload plan for 1 files"));
+    assert!(plan
+        .join("files/b603d946eb2b396ca4ecf65c223daff659dbe6f1cfeac235b7c61d3ba6964cae")
+        .exists());
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["releases", "files", "2.0", "upload", "--apply"])
+        .arg(&plan)
+        .assert()
+        .failure()
+        .stderr(contains("The upload plan is for release 1.0, not 2.0"));
+
+    let _chunk_upload = mock("GET", "/api/0/organizations/wat-org/chunk-upload/")
+        .with_status(404)
+        .create();
+    let _release = mock("POST", "/api/0/projects/wat-org/wat-project/releases/")
+        .with_status(201)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"version":"1.0","dateCreated":"2020-06-29T11:36:59.612687Z","url":null,"data":{},"dateReleased":null,"newGroups":0,"commitCount":0,"deployCount":0,"lastDeploy":null,"lastEvent":null,"firstEvent":null,"lastCommit":null,"authors":[],"owner":null,"ref":null,"projects":[]}"#)
+        .create();
+    let _files = mock(
+        "GET",
+        Matcher::Regex("^/api/0/projects/wat-org/wat-project/releases/1.0/files/".into()),
+    )
+    .with_header("content-type", "application/json")
+    .with_body("[]")
+    .create();
+    let upload = mock(
+        "POST",
+        "/api/0/projects/wat-org/wat-project/releases/1.0/files/",
+    )
+    .match_body(Matcher::Regex(r"(?s)~/app\.js.*web".into()))
+    .with_status(201)
+    .with_header("content-type", "application/json")
+    .with_body(r#"{"id":"1","sha1":"","name":"~/app.js","size":16,"dist":"web","headers":{}}"#)
+    .expect(1)
+    .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["releases", "files", "1.0", "upload", "--apply"])
+        .arg(&plan)
+        .assert()
+        .success()
+        .stdout(contains("Applying upload plan with 1 files"));
+
+    upload.assert();
+}
+
+const RELEASE: &str = r#"{"version":"1.0","dateCreated":"2020-06-29T11:36:59.612687Z","url":null,"data":{},"dateReleased":null,"newGroups":0,"commitCount":0,"deployCount":0,"lastDeploy":null,"lastEvent":null,"firstEvent":null,"lastCommit":null,"authors":[],"owner":null,"ref":null,"projects":[]}"#;
+
+#[test]
+fn finalizes_release_after_atomic_upload() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("app.js"), "console.log(1);\n").unwrap();
+
+    let _chunk_options = mock("GET", "/api/0/organizations/wat-org/chunk-upload/")
+        .with_header("content-type", "application/json")
+        .with_body(format!(
+            r#"{{"url": "{}/api/0/organizations/wat-org/chunk-upload/",
+                "chunksPerRequest": 64, "maxRequestSize": 33554432, "chunkSize": 8388608,
+                "hashAlgorithm": "sha1", "concurrency": 1, "accept": ["release_files"]}}"#,
+            server_url()
+        ))
+        .create();
+    let _chunks = mock("POST", "/api/0/organizations/wat-org/chunk-upload/")
+        .with_status(200)
+        .create();
+    let assemble = mock(
+        "POST",
+        "/api/0/organizations/wat-org/releases/1.0/assemble/",
+    )
+    .with_header("content-type", "application/json")
+    .with_body(r#"{"state": "ok", "missingChunks": []}"#)
+    .expect(1)
+    .create();
+    let finalize = mock("PUT", "/api/0/projects/wat-org/wat-project/releases/1.0/")
+        .match_body(Matcher::Regex("dateReleased".into()))
+        .with_header("content-type", "application/json")
+        .with_body(RELEASE)
+        .expect(1)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["releases", "files", "1.0", "upload", "--atomic"])
+        .arg(dir.path())
+        .assert()
+        .success()
+        .stdout(contains("Finalized release 1.0"));
+
+    assemble.assert();
+    finalize.assert();
+}
+
+#[test]
+fn fails_atomic_upload_without_chunk_upload() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("app.js"), "console.log(1);\n").unwrap();
+
+    let _chunk_upload = mock("GET", "/api/0/organizations/wat-org/chunk-upload/")
+        .with_status(404)
+        .create();
+    let upload = mock(
+        "POST",
+        "/api/0/projects/wat-org/wat-project/releases/1.0/files/",
+    )
+    .expect(0)
+    .create();
+    let finalize = mock("PUT", "/api/0/projects/wat-org/wat-project/releases/1.0/")
+        .expect(0)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["releases", "files", "1.0", "upload", "--atomic"])
+        .arg(dir.path())
+        .assert()
+        .failure()
+        .stderr(contains("The server does not support atomic uploads"));
+
+    upload.assert();
+    finalize.assert();
+}
+
+#[cfg(unix)]
+#[test]
+fn cancels_upload_on_interrupt() {
+    use std::process::Stdio;
+    use std::sync::{mpsc, Mutex};
+    use std::time::Duration;
+
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("app.js"), "console.log(1);\n").unwrap();
+
+    let (tx, rx) = mpsc::channel();
+    let tx = Mutex::new(tx);
+    let _chunk_upload = mock("GET", "/api/0/organizations/wat-org/chunk-upload/")
+        .with_status(404)
+        .create();
+    let _files = mock(
+        "GET",
+        Matcher::Regex("^/api/0/projects/wat-org/wat-project/releases/1.0/files/".into()),
+    )
+    .with_header("content-type", "application/json")
+    .with_body_from_fn(move |w| {
+        tx.lock().unwrap().send(()).ok();
+        std::thread::sleep(Duration::from_secs(3));
+        w.write_all(b"[]")
+    })
+    .create();
+    let upload = mock(
+        "POST",
+        "/api/0/projects/wat-org/wat-project/releases/1.0/files/",
+    )
+    .expect(0)
+    .create();
+
+    let child = std::process::Command::new(assert_cmd::cargo::cargo_bin("sentry-cli"))
+        .envs(common::get_base_env())
+        .args(["releases", "files", "1.0", "upload"])
+        .arg(dir.path())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    rx.recv_timeout(Duration::from_secs(30)).unwrap();
+    std::process::Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(130));
+    assert!(stderr.contains("Interrupted!"), "{}", stderr);
+    assert!(
+        stderr.contains("Files that were not uploaded yet are missing from release 1.0"),
+        "{}",
+        stderr
+    );
+    upload.assert();
+}
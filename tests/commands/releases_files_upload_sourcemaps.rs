@@ -0,0 +1,130 @@
+use std::fs;
+use std::io::Write;
+
+use assert_cmd::Command;
+use mockito::{mock, Matcher};
+use predicates::str::contains;
+
+use crate::common;
+
+#[test]
+fn uploads_apps_from_build_manifest() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("apps/web/dist")).unwrap();
+    fs::write(dir.path().join("apps/web/dist/app.js"), "console.log(1);\n").unwrap();
+    fs::write(
+        dir.path().join("turbo.json"),
+        r#"{"tasks": [
+            {"package": "@acme/web", "directory": "apps/web", "outputs": ["dist/**"]},
+            {"package": "docs", "directory": "apps/docs", "outputs": ["out/**"]}
+        ]}"#,
+    )
+    .unwrap();
+
+    let _chunk_upload = mock("GET", "/api/0/organizations/wat-org/chunk-upload/")
+        .with_status(404)
+        .create();
+    let release = mock("POST", "/api/0/projects/wat-org/wat-project/releases/")
+        .match_body(Matcher::PartialJson(
+            serde_json::json!({"version": "acme-web@1.0"}),
+        ))
+        .with_status(201)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"version":"acme-web@1.0","dateCreated":"2020-06-29T11:36:59.612687Z","url":null,"data":{},"dateReleased":null,"newGroups":0,"commitCount":0,"deployCount":0,"lastDeploy":null,"lastEvent":null,"firstEvent":null,"lastCommit":null,"authors":[],"owner":null,"ref":null,"projects":[]}"#)
+        .create();
+    let _files = mock(
+        "GET",
+        Matcher::Regex(
+            "^/api/0/projects/wat-org/wat-project/releases/acme-web(@|%40)1.0/files/".into(),
+        ),
+    )
+    .with_header("content-type", "application/json")
+    .with_body("[]")
+    .create();
+    let upload = mock(
+        "POST",
+        Matcher::Regex(
+            "^/api/0/projects/wat-org/wat-project/releases/acme-web(@|%40)1.0/files/$".into(),
+        ),
+    )
+    .with_status(201)
+    .with_header("content-type", "application/json")
+    .with_body(r#"{"id":"1","sha1":"","name":"~/app.js","size":16,"dist":null,"headers":{}}"#)
+    .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .current_dir(dir.path())
+        .args([
+            "releases",
+            "files",
+            "1.0",
+            "upload-sourcemaps",
+            "--from-build-manifest",
+            "turbo.json",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("Uploading apps/web/dist to release acme-web@1.0"))
+        .stderr(contains("Skipping docs"));
+
+    release.assert();
+    upload.assert();
+}
+
+#[test]
+fn uploads_sourcemaps_from_zip() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("dist.zip");
+    let mut zip = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+    let options = zip::write::FileOptions::default();
+    zip.start_file("static/app.js", options).unwrap();
+    zip.write_all(b"console.log(1);\n//# sourceMappingURL=app.js.map\n")
+        .unwrap();
+    zip.start_file("static/app.js.map", options).unwrap();
+    zip.write_all(br#"{"version":3,"sources":[],"names":[],"mappings":""}"#)
+        .unwrap();
+    zip.start_file("README.md", options).unwrap();
+    zip.write_all(b"# dist\n").unwrap();
+    zip.finish().unwrap();
+
+    let _chunk_upload = mock("GET", "/api/0/organizations/wat-org/chunk-upload/")
+        .with_status(404)
+        .create();
+    let _release = mock("POST", "/api/0/projects/wat-org/wat-project/releases/")
+        .with_status(201)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"version":"1.0","dateCreated":"2020-06-29T11:36:59.612687Z","url":null,"data":{},"dateReleased":null,"newGroups":0,"commitCount":0,"deployCount":0,"lastDeploy":null,"lastEvent":null,"firstEvent":null,"lastCommit":null,"authors":[],"owner":null,"ref":null,"projects":[]}"#)
+        .create();
+    let _files = mock(
+        "GET",
+        Matcher::Regex("^/api/0/projects/wat-org/wat-project/releases/1.0/files/".into()),
+    )
+    .with_header("content-type", "application/json")
+    .with_body("[]")
+    .create();
+    let upload = mock(
+        "POST",
+        "/api/0/projects/wat-org/wat-project/releases/1.0/files/",
+    )
+    .match_body(Matcher::Regex("~/static/app\\.js".into()))
+    .with_status(201)
+    .with_header("content-type", "application/json")
+    .with_body(
+        r#"{"id":"1","sha1":"","name":"~/static/app.js","size":16,"dist":null,"headers":{}}"#,
+    )
+    .expect(2)
+    .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["releases", "files", "1.0", "upload-sourcemaps"])
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout(contains("Found 2 release files"));
+
+    upload.assert();
+}
@@ -113,3 +113,256 @@ fn creates_release_which_is_instantly_finalized() {
         .success()
         .stdout(contains("Created release wat-release."));
 }
+
+#[test]
+fn writes_github_job_summary() {
+    let _server = mock("POST", "/api/0/projects/wat-org/wat-project/releases/")
+        .with_status(201)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"dateReleased":null,"newGroups":0,"commitCount":0,"url":null,"data":{},"lastDeploy":null,"deployCount":0,"dateCreated":"2020-06-29T11:36:59.612687Z","lastEvent":null,"version":"summary-release","firstEvent":null,"lastCommit":null,"shortVersion":"summary","authors":[],"owner":null,"versionInfo":{"buildHash":null,"version":{"raw":"summary-release"},"description":"summary-release","package":null},"ref":null,"projects":[{"name":"test","platform":"javascript","slug":"test","platforms":["javascript"],"newGroups":0,"id":1861017}]}"#)
+        .create();
+    let summary = tempfile::NamedTempFile::new().unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env("GITHUB_STEP_SUMMARY", summary.path())
+        .args(vec![
+            "--ci-annotations",
+            "releases",
+            "new",
+            "summary-release",
+        ])
+        .assert()
+        .success();
+
+    let summary = std::fs::read_to_string(summary.path()).unwrap();
+    assert_eq!(
+        summary,
+        format!(
+            "### Sentry\n\n- Created release [`summary-release`]({}/organizations/wat-org/releases/summary-release/)\n",
+            mockito::server_url()
+        )
+    );
+}
+
+#[test]
+fn annotates_errors_on_github() {
+    let _server = mock("POST", "/api/0/projects/wat-org/wat-project/releases/")
+        .match_body(Matcher::PartialJsonString(
+            r#"{"version":"broken-release"}"#.to_string(),
+        ))
+        .with_status(400)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"detail":"invalid version"}"#)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env("GITHUB_ACTIONS", "true")
+        .env_remove("SENTRY_CI_OUTPUT")
+        .args(vec!["releases", "new", "broken-release"])
+        .assert()
+        .failure()
+        .stderr(contains("::error::"));
+}
+
+#[test]
+fn writes_gitlab_reports() {
+    let _server = mock("POST", "/api/0/projects/wat-org/wat-project/releases/")
+        .with_status(201)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"dateReleased":null,"newGroups":0,"commitCount":0,"url":null,"data":{},"lastDeploy":null,"deployCount":0,"dateCreated":"2020-06-29T11:36:59.612687Z","lastEvent":null,"version":"gitlab-release","firstEvent":null,"lastCommit":null,"shortVersion":"gitlab","authors":[],"owner":null,"versionInfo":{"buildHash":null,"version":{"raw":"gitlab-release"},"description":"gitlab-release","package":null},"ref":null,"projects":[{"name":"test","platform":"javascript","slug":"test","platforms":["javascript"],"newGroups":0,"id":1861017}]}"#)
+        .create();
+    let dir = tempfile::tempdir().unwrap();
+    let dotenv = dir.path().join("sentry.env");
+    let evidence = dir.path().join("evidence.json");
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env("CI_COMMIT_TAG", "v1.0.0")
+        .env(
+            "CI_PIPELINE_URL",
+            "https://gitlab.example.com/wat/-/pipelines/7",
+        )
+        .args(["releases", "new", "gitlab-release", "--dotenv"])
+        .arg(&dotenv)
+        .arg("--evidence")
+        .arg(&evidence)
+        .assert()
+        .success();
+
+    assert_eq!(
+        std::fs::read_to_string(&dotenv).unwrap(),
+        format!(
+            "SENTRY_ORG=wat-org\nSENTRY_RELEASE=gitlab-release\nSENTRY_RELEASE_URL={}/organizations/wat-org/releases/gitlab-release/\n",
+            mockito::server_url()
+        )
+    );
+    let evidence: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&evidence).unwrap()).unwrap();
+    assert_eq!(evidence["release"]["tag_name"], "v1.0.0");
+    assert_eq!(evidence["release"]["name"], "gitlab-release");
+    assert_eq!(evidence["release"]["sentry"]["projects"][0], "test");
+    assert_eq!(
+        evidence["release"]["pipeline"]["url"],
+        "https://gitlab.example.com/wat/-/pipelines/7"
+    );
+}
+
+#[test]
+fn marks_errors_for_jenkins() {
+    let _server = mock("POST", "/api/0/projects/wat-org/wat-project/releases/")
+        .match_body(Matcher::PartialJsonString(
+            r#"{"version":"jenkins-release"}"#.to_string(),
+        ))
+        .with_status(400)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"detail":"invalid version"}"#)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env("SENTRY_CI_OUTPUT", "jenkins")
+        .args(vec!["releases", "new", "jenkins-release"])
+        .assert()
+        .failure()
+        .stdout(contains("[sentry-cli] ERROR: "));
+}
+
+#[test]
+fn tags_azure_builds() {
+    let _server = mock("POST", "/api/0/projects/wat-org/wat-project/releases/")
+        .match_body(Matcher::PartialJsonString(
+            r#"{"version":"azure-release"}"#.to_string(),
+        ))
+        .with_status(201)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"dateReleased":null,"newGroups":0,"commitCount":0,"url":null,"data":{},"lastDeploy":null,"deployCount":0,"dateCreated":"2020-06-29T11:36:59.612687Z","lastEvent":null,"version":"azure-release","firstEvent":null,"lastCommit":null,"shortVersion":"azure","authors":[],"owner":null,"versionInfo":{"buildHash":null,"version":{"raw":"azure-release"},"description":"azure-release","package":null},"ref":null,"projects":[{"name":"test","platform":"javascript","slug":"test","platforms":["javascript"],"newGroups":0,"id":1861017}]}"#)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env("SENTRY_CI_OUTPUT", "azure")
+        .args(vec!["releases", "new", "azure-release", "--tag-build"])
+        .assert()
+        .success()
+        .stdout(contains("##vso[build.addbuildtag]azure-release"));
+}
+
+#[test]
+fn logs_azure_issues() {
+    let _server = mock("POST", "/api/0/projects/wat-org/wat-project/releases/")
+        .match_body(Matcher::PartialJsonString(
+            r#"{"version":"azure-release"}"#.to_string(),
+        ))
+        .with_status(400)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"detail":"invalid version"}"#)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env("SENTRY_CI_OUTPUT", "azure")
+        .args(vec!["releases", "new", "azure-release"])
+        .assert()
+        .failure()
+        .stdout(contains("##vso[task.logissue type=error]"));
+}
+
+#[test]
+fn prints_machine_readable_events() {
+    let _server = mock("POST", "/api/0/projects/wat-org/wat-project/releases/")
+        .match_body(Matcher::PartialJsonString(
+            r#"{"version":"machine-release"}"#.to_string(),
+        ))
+        .with_status(201)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"dateReleased":null,"newGroups":0,"commitCount":0,"url":null,"data":{},"lastDeploy":null,"deployCount":0,"dateCreated":"2020-06-29T11:36:59.612687Z","lastEvent":null,"version":"machine-release","firstEvent":null,"lastCommit":null,"shortVersion":"wat","authors":[],"owner":null,"versionInfo":{"buildHash":null,"version":{"raw":"machine-release"},"description":"machine-release","package":null},"ref":null,"projects":[{"name":"test","platform":"javascript","slug":"test","platforms":["javascript"],"newGroups":0,"id":1861017}]}"#)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec!["--machine-readable", "releases", "new", "machine-release"])
+        .assert()
+        .success()
+        .stdout(
+            "{\"exit_code\":0,\"release\":\"machine-release\",\"success\":true,\"type\":\"result\"}\n",
+        )
+        .stderr(contains("Created release machine-release."));
+}
+
+#[test]
+fn prints_machine_readable_errors() {
+    let _server = mock("POST", "/api/0/projects/wat-org/wat-project/releases/")
+        .match_body(Matcher::PartialJsonString(
+            r#"{"version":"broken-release"}"#.to_string(),
+        ))
+        .with_status(400)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"detail":"invalid version"}"#)
+        .create();
+
+    let output = Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "--machine-readable",
+            "releases",
+            "new",
+            "broken-release",
+        ])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let events: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(events.len(), 2);
+    assert_eq!(
+        events[0],
+        serde_json::json!({
+            "type": "error",
+            "message": "API request failed",
+            "causes": ["sentry reported an error: invalid version (http status: 400)"],
+        })
+    );
+    assert_eq!(
+        events[1],
+        serde_json::json!({"type": "result", "success": false, "exit_code": 1})
+    );
+}
+
+#[test]
+fn skips_existing_release_when_idempotent() {
+    let _existing = mock("GET", "/api/0/organizations/wat-org/releases/existing-release/")
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"dateReleased":null,"newGroups":0,"url":null,"dateCreated":"2020-06-29T11:36:59.612687Z","lastEvent":null,"version":"existing-release","projects":[{"name":"Wat Project","slug":"wat-project"}]}"#)
+        .create();
+    let create = mock("POST", "/api/0/projects/wat-org/wat-project/releases/")
+        .match_body(Matcher::PartialJsonString(
+            r#"{"version":"existing-release"}"#.to_string(),
+        ))
+        .expect(0)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec!["releases", "new", "existing-release", "--idempotent"])
+        .assert()
+        .success()
+        .stdout(contains(
+            "Release existing-release already exists, nothing changed.",
+        ));
+
+    create.assert();
+}
@@ -0,0 +1,294 @@
+use std::process;
+
+use assert_cmd::Command;
+use mockito::{mock, server_url, Matcher};
+use predicates::str::contains;
+
+use crate::common;
+
+#[test]
+fn sets_commits_from_github_api() {
+    let _repos = mock("GET", "/api/0/organizations/wat-org/repos/")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"[{"id":"1","name":"wat-org/wat","url":"https://github.com/wat-org/wat","provider":{"id":"integrations:github","name":"GitHub"},"status":"active","dateCreated":"2021-06-01T10:00:00Z"}]"#)
+        .create();
+    let _release = mock("POST", "/api/0/projects/wat-org/wat-project/releases/")
+        .with_status(208)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"version":"wat-release","dateCreated":"2021-06-01T10:00:00Z"}"#)
+        .create();
+    let _compare = mock("GET", "/repos/wat-org/wat/compare/aaa...bbb")
+        .match_query(Matcher::UrlEncoded("page".into(), "1".into()))
+        .match_header("authorization", "token gh-token")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_header(
+            "link",
+            r#"<https://api.github.com/repositories/1/compare/aaa...bbb?page=2>; rel="next""#,
+        )
+        .with_body(r#"{"total_commits":2,"commits":[{"sha":"c1","commit":{"message":"first"}}]}"#)
+        .create();
+    let _compare_next = mock("GET", "/repos/wat-org/wat/compare/aaa...bbb")
+        .match_query(Matcher::UrlEncoded("page".into(), "2".into()))
+        .match_header("authorization", "token gh-token")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"total_commits":2,"commits":[{"sha":"bbb","commit":{"message":"second"}}]}"#)
+        .create();
+    let _c1 = mock("GET", "/repos/wat-org/wat/commits/c1")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"sha":"c1","commit":{"message":"first","author":{"name":"Jane","email":"jane@example.com","date":"2021-06-01T10:00:00Z"}},"files":[{"filename":"src/a.rs","status":"added"}]}"#)
+        .create();
+    let _c2 = mock("GET", "/repos/wat-org/wat/commits/bbb")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"sha":"bbb","commit":{"message":"second","author":{"name":"Jane","email":"jane@example.com","date":"2021-06-02T10:00:00Z"}},"files":[{"filename":"src/a.rs","status":"modified"},{"filename":"src/b.rs","status":"removed"}]}"#)
+        .create();
+    let update = mock("PUT", "/api/0/organizations/wat-org/releases/wat-release/")
+        .match_body(Matcher::PartialJson(serde_json::json!({
+            "commits": [
+                {
+                    "id": "bbb",
+                    "repository": "wat-org/wat",
+                    "patch_set": [{"path": "src/a.rs", "type": "M"}, {"path": "src/b.rs", "type": "D"}],
+                },
+                {
+                    "id": "c1",
+                    "repository": "wat-org/wat",
+                    "patch_set": [{"path": "src/a.rs", "type": "A"}],
+                },
+            ]
+        })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"version":"wat-release","dateCreated":"2021-06-01T10:00:00Z"}"#)
+        .expect(1)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env("GITHUB_API_URL", server_url())
+        .env("GITHUB_TOKEN", "gh-token")
+        .args(vec![
+            "releases",
+            "set-commits",
+            "wat-release",
+            "--repo",
+            "wat-org/wat",
+            "--range",
+            "aaa..bbb",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("Success! Set commits for release wat-release."));
+
+    update.assert();
+}
+
+#[test]
+fn sets_commits_from_gitlab_api() {
+    let _repos = mock("GET", "/api/0/organizations/wat-org/repos/")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"[{"id":"1","name":"wat-org/wat","url":"https://gitlab.com/wat-org/wat","provider":{"id":"integrations:gitlab","name":"GitLab"},"status":"active","dateCreated":"2021-06-01T10:00:00Z"}]"#)
+        .create();
+    let _release = mock("POST", "/api/0/projects/wat-org/wat-project/releases/")
+        .with_status(208)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"version":"wat-release","dateCreated":"2021-06-01T10:00:00Z"}"#)
+        .create();
+    let _compare = mock("GET", "/projects/wat-org%2Fwat/repository/compare")
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("from".into(), "aaa".into()),
+            Matcher::UrlEncoded("to".into(), "bbb".into()),
+        ]))
+        .match_header("authorization", "Bearer gl-token")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"commits":[{"id":"bbb","message":"second","author_name":"Jane","author_email":"jane@example.com","authored_date":"2021-06-02T10:00:00Z"}]}"#)
+        .create();
+    let _diff = mock("GET", "/projects/wat-org%2Fwat/repository/commits/bbb/diff")
+        .match_query(Matcher::UrlEncoded("page".into(), "1".into()))
+        .match_header("authorization", "Bearer gl-token")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_header("x-next-page", "2")
+        .with_body(r#"[{"new_path":"src/a.rs","new_file":true}]"#)
+        .create();
+    let _diff_next = mock("GET", "/projects/wat-org%2Fwat/repository/commits/bbb/diff")
+        .match_query(Matcher::UrlEncoded("page".into(), "2".into()))
+        .match_header("authorization", "Bearer gl-token")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_header("x-next-page", "")
+        .with_body(r#"[{"new_path":"src/b.rs","deleted_file":true}]"#)
+        .create();
+    let update = mock("PUT", "/api/0/organizations/wat-org/releases/wat-release/")
+        .match_body(Matcher::PartialJson(serde_json::json!({
+            "commits": [{
+                "id": "bbb",
+                "repository": "wat-org/wat",
+                "patch_set": [{"path": "src/a.rs", "type": "A"}, {"path": "src/b.rs", "type": "D"}],
+            }]
+        })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"version":"wat-release","dateCreated":"2021-06-01T10:00:00Z"}"#)
+        .expect(1)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env("CI_API_V4_URL", server_url())
+        .env("GITLAB_TOKEN", "gl-token")
+        .args(vec![
+            "releases",
+            "set-commits",
+            "wat-release",
+            "--repo",
+            "wat-org/wat",
+            "--range",
+            "aaa..bbb",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("Success! Set commits for release wat-release."));
+
+    update.assert();
+}
+
+#[test]
+fn rejects_truncated_github_range() {
+    let _repos = mock("GET", "/api/0/organizations/wat-org/repos/")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"[{"id":"1","name":"wat-org/wat","url":"https://github.com/wat-org/wat","provider":{"id":"integrations:github","name":"GitHub"},"status":"active","dateCreated":"2021-06-01T10:00:00Z"}]"#)
+        .create();
+    let _release = mock("POST", "/api/0/projects/wat-org/wat-project/releases/")
+        .with_status(208)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"version":"wat-release","dateCreated":"2021-06-01T10:00:00Z"}"#)
+        .create();
+    let _compare = mock("GET", "/repos/wat-org/wat/compare/aaa...bbb")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            r#"{"total_commits":12000,"commits":[{"sha":"bbb","commit":{"message":"second"}}]}"#,
+        )
+        .create();
+    let update = mock("PUT", "/api/0/organizations/wat-org/releases/wat-release/")
+        .with_status(200)
+        .expect(0)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env("GITHUB_API_URL", server_url())
+        .args(vec![
+            "releases",
+            "set-commits",
+            "wat-release",
+            "--repo",
+            "wat-org/wat",
+            "--range",
+            "aaa..bbb",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains(
+            "GitHub only listed 1 of the 12000 commits in aaa..bbb",
+        ));
+
+    update.assert();
+}
+
+#[test]
+fn sets_commits_without_previous_release() {
+    let dir = tempfile::tempdir().unwrap();
+    for args in &[
+        &["init", "-q"][..],
+        &[
+            "-c",
+            "user.name=Jane",
+            "-c",
+            "user.email=jane@example.com",
+            "commit",
+            "-q",
+            "--allow-empty",
+            "-m",
+            "initial",
+        ][..],
+    ] {
+        let status = process::Command::new("git")
+            .args(*args)
+            .current_dir(dir.path())
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    let _repos = mock("GET", "/api/0/organizations/wat-org/repos/")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("[]")
+        .create();
+    let _release = mock("POST", "/api/0/projects/wat-org/wat-project/releases/")
+        .with_status(208)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"version":"first-release","dateCreated":"2021-06-01T10:00:00Z"}"#)
+        .create();
+    let _releases = mock("GET", "/api/0/projects/wat-org/wat-project/releases/")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("[]")
+        .create();
+    let previous = mock(
+        "GET",
+        "/api/0/organizations/wat-org/releases/first-release/previous-with-commits/",
+    )
+    .expect(0)
+    .create();
+    let update = mock(
+        "PUT",
+        "/api/0/organizations/wat-org/releases/first-release/",
+    )
+    .match_body(Matcher::Regex(r#""author_name":"Jane""#.into()))
+    .with_status(200)
+    .with_header("content-type", "application/json")
+    .with_body(r#"{"version":"first-release","dateCreated":"2021-06-01T10:00:00Z"}"#)
+    .expect(1)
+    .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .current_dir(dir.path())
+        .args(vec![
+            "releases",
+            "set-commits",
+            "first-release",
+            "--local",
+            "--previous",
+            "latest-finalized",
+        ])
+        .assert()
+        .success()
+        .stdout(contains(
+            "Could not find the previous commit. Creating a release with 20 commits.",
+        ));
+
+    previous.assert();
+    update.assert();
+}
@@ -0,0 +1,88 @@
+use std::fs;
+
+use assert_cmd::Command;
+use mockito::{mock, Matcher};
+use predicates::str::contains;
+
+use crate::common;
+
+#[test]
+fn replaces_dependencies_of_release() {
+    let dir = tempfile::tempdir().unwrap();
+    let sbom = dir.path().join("bom.json");
+    fs::write(
+        &sbom,
+        r#"{
+            "bomFormat": "CycloneDX",
+            "specVersion": "1.4",
+            "components": [
+                {"name": "lodash", "version": "4.17.20", "purl": "pkg:npm/lodash@4.17.20"},
+                {"name": "react", "version": "17.0.2", "purl": "pkg:npm/react@17.0.2"}
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    let files = mock(
+        "GET",
+        Matcher::Regex("^/api/0/projects/wat-org/wat-project/releases/1.0/files/".into()),
+    )
+    .with_header("content-type", "application/json")
+    .with_body(r#"[{"id":"7","sha1":"","name":"~/dependencies.json","size":2,"dist":null,"headers":{}},{"id":"8","sha1":"","name":"~/app.js","size":2,"dist":null,"headers":{}}]"#)
+    .create();
+    let delete = mock(
+        "DELETE",
+        "/api/0/projects/wat-org/wat-project/releases/1.0/files/7/",
+    )
+    .with_status(204)
+    .expect(1)
+    .create();
+    let upload = mock(
+        "POST",
+        "/api/0/projects/wat-org/wat-project/releases/1.0/files/",
+    )
+    .match_body(Matcher::AllOf(vec![
+        Matcher::Regex(r#""format":"CycloneDX""#.into()),
+        Matcher::Regex(r#""name":"lodash""#.into()),
+        Matcher::Regex(r#""ecosystem":"npm""#.into()),
+        Matcher::Regex("~/dependencies.json".into()),
+    ]))
+    .with_status(201)
+    .with_header("content-type", "application/json")
+    .with_body(
+        r#"{"id":"9","sha1":"","name":"~/dependencies.json","size":300,"dist":null,"headers":{}}"#,
+    )
+    .expect(1)
+    .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["releases", "set-dependencies", "1.0", "--sbom"])
+        .arg(&sbom)
+        .assert()
+        .success()
+        .stdout(contains(
+            "Stored 2 dependencies of release 1.0 (CycloneDX 1.4).",
+        ));
+
+    files.assert();
+    delete.assert();
+    upload.assert();
+}
+
+#[test]
+fn rejects_unknown_documents() {
+    let dir = tempfile::tempdir().unwrap();
+    let sbom = dir.path().join("package.json");
+    fs::write(&sbom, r#"{"name": "acme-web"}"#).unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["releases", "set-dependencies", "1.0", "--sbom"])
+        .arg(&sbom)
+        .assert()
+        .failure()
+        .stderr(contains("Not a CycloneDX or SPDX document"));
+}
@@ -0,0 +1,87 @@
+use assert_cmd::Command;
+use mockito::{mock, Matcher};
+use predicates::prelude::*;
+use predicates::str::contains;
+
+use crate::common;
+
+#[test]
+fn shows_release_commits_and_files() {
+    let _commits = mock(
+        "GET",
+        "/api/0/organizations/wat-org/releases/wat-release/commits/",
+    )
+    .match_query(Matcher::Any)
+    .with_status(200)
+    .with_header("content-type", "application/json")
+    .with_body(r#"[{"id":"8d3e2f4a3c4e7d9b1a2c3d4e5f60718293a4b5c6","message":"Fix checkout\n\nDetails","dateCreated":"2021-06-01T10:00:00Z","author":{"name":"Jane","email":"jane@example.com"}}]"#)
+    .create();
+    let _files = mock(
+        "GET",
+        "/api/0/organizations/wat-org/releases/wat-release/commitfiles/",
+    )
+    .match_query(Matcher::Any)
+    .with_status(200)
+    .with_header("content-type", "application/json")
+    .with_body(r#"[{"filename":"src/checkout.js","type":"M","commitMessage":"Fix checkout"}]"#)
+    .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec!["releases", "suspects", "wat-release"])
+        .assert()
+        .success()
+        .stdout(
+            contains("Release wat-release has 1 commits touching 1 files")
+                .and(contains("  8d3e2f4a3c4e Jane: Fix checkout"))
+                .and(contains("  M src/checkout.js")),
+        )
+        .stderr(contains("warning").not());
+}
+
+#[test]
+fn warns_about_large_commit_ranges() {
+    let _commits = mock(
+        "GET",
+        "/api/0/organizations/wat-org/releases/wat-release/commits/",
+    )
+    .match_query(Matcher::Any)
+    .with_status(200)
+    .with_header("content-type", "application/json")
+    .with_body(r#"[{"id":"a1","message":"one","dateCreated":"2021-06-01T10:00:00Z","author":null},{"id":"a2","message":"two","dateCreated":"2021-06-01T10:00:00Z","author":null}]"#)
+    .create();
+    let _files = mock(
+        "GET",
+        "/api/0/organizations/wat-org/releases/wat-release/commitfiles/",
+    )
+    .match_query(Matcher::Any)
+    .with_status(200)
+    .with_header("content-type", "application/json")
+    .with_body(r#"[{"filename":"a.js","type":"A","commitMessage":"one"}]"#)
+    .create();
+    let _previous = mock(
+        "GET",
+        "/api/0/organizations/wat-org/releases/wat-release/previous-with-commits/",
+    )
+    .with_status(200)
+    .with_header("content-type", "application/json")
+    .with_body(r#"{"version":"old-release","dateCreated":"2021-05-01T10:00:00Z"}"#)
+    .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "releases",
+            "suspects",
+            "wat-release",
+            "--max-commits",
+            "1",
+        ])
+        .assert()
+        .success()
+        .stderr(contains(
+            "warning: 2 commits are associated with release wat-release. The commit of the previous release old-release was probably not found",
+        ));
+}
@@ -0,0 +1,68 @@
+use assert_cmd::Command;
+use mockito::{mock, Matcher};
+use predicates::str::contains;
+
+use crate::common;
+
+const REPOS_BODY: &str = r#"[{"id":"3","name":"wat-org/payments","url":"https://github.com/wat-org/payments","provider":{"id":"integrations:github","name":"GitHub"},"status":"active","dateCreated":"2021-06-01T10:00:00Z"}]"#;
+
+#[test]
+fn creates_repo_with_integration() {
+    let _integrations = mock("GET", "/api/0/organizations/wat-org/integrations/")
+        .match_query(Matcher::UrlEncoded("provider_key".into(), "github".into()))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"[{"id":"42","name":"wat-org","provider":{"key":"github","name":"GitHub"}}]"#)
+        .create();
+    let create = mock("POST", "/api/0/organizations/wat-org/repos/")
+        .match_body(Matcher::Json(serde_json::json!({
+            "provider": "integrations:github",
+            "installation": "42",
+            "identifier": "wat-org/payments",
+        })))
+        .with_status(201)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"id":"3","name":"wat-org/payments","url":"https://github.com/wat-org/payments","provider":{"id":"integrations:github","name":"GitHub"},"status":"active","dateCreated":"2021-06-01T10:00:00Z"}"#)
+        .expect(1)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "repos",
+            "create",
+            "wat-org/payments",
+            "--provider",
+            "github",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("Created repository wat-org/payments (3)"));
+
+    create.assert();
+}
+
+#[test]
+fn deletes_repo_by_name() {
+    let _list = mock("GET", "/api/0/organizations/wat-org/repos/")
+        .match_query(Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(REPOS_BODY)
+        .create();
+    let delete = mock("DELETE", "/api/0/organizations/wat-org/repos/3/")
+        .with_status(202)
+        .expect(1)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec!["repos", "delete", "wat-org/payments", "--confirm"])
+        .assert()
+        .success()
+        .stdout(contains("Deleted repository wat-org/payments!"));
+
+    delete.assert();
+}
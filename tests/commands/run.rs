@@ -0,0 +1,176 @@
+use assert_cmd::Command;
+use mockito::{mock, server_address, Matcher};
+use predicates::prelude::*;
+use predicates::str::contains;
+
+use crate::common;
+
+#[test]
+fn reports_failed_command() {
+    let store = mock("POST", "/api/1/store/")
+        .match_body(Matcher::AllOf(vec![
+            Matcher::PartialJson(serde_json::json!({
+                "exception": {"values": [{
+                    "type": "ProcessError",
+                    "value": "command sh exited with status 3",
+                }]},
+                "tags": {"command": "sh", "exit_code": "3", "team": "infra"},
+                "extra": {
+                    "argv": ["sh", "-c", "echo first >&2; echo oops >&2; exit 3"],
+                    "exit_code": 3,
+                    "stderr": "oops",
+                },
+            })),
+            Matcher::Regex(r#""resource_usage":\{[^}]*"max_rss_bytes":\d+"#.into()),
+        ]))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("{}")
+        .expect(1)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env(
+            "SENTRY_DSN",
+            format!("http://lolnope@{}/1", server_address()),
+        )
+        .args([
+            "run",
+            "--tail-lines",
+            "1",
+            "--tag",
+            "team:infra",
+            "--",
+            "sh",
+            "-c",
+            "echo first >&2; echo oops >&2; exit 3",
+        ])
+        .assert()
+        .code(3)
+        .stderr(contains("first\noops\n").and(contains("exited with status 3")));
+
+    store.assert();
+}
+
+#[test]
+fn passes_successful_command() {
+    let store = mock("POST", "/api/1/store/")
+        .match_body(Matcher::Regex("ProcessError".into()))
+        .with_status(200)
+        .expect(0)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env(
+            "SENTRY_DSN",
+            format!("http://lolnope@{}/1", server_address()),
+        )
+        .args(["run", "--", "sh", "-c", "echo fine"])
+        .assert()
+        .success()
+        .stdout("fine\n");
+
+    store.assert();
+}
+
+#[test]
+fn runs_command_in_clean_environment() {
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env(
+            "SENTRY_DSN",
+            format!("http://lolnope@{}/1", server_address()),
+        )
+        .args([
+            "run",
+            "--clean-env",
+            "--set-env",
+            "FOO=bar=baz",
+            "--cwd",
+            "/",
+            "--",
+            "sh",
+            "-c",
+            r#"printf '%s|%s|%s' "${SENTRY_AUTH_TOKEN-unset}" "$FOO" "$(pwd)""#,
+        ])
+        .assert()
+        .success()
+        .stdout("unset|bar=baz|/");
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["run", "--set-env", "FOO", "--", "true"])
+        .assert()
+        .failure()
+        .stderr(contains("KEY=VALUE required"));
+}
+
+#[test]
+fn sends_environ_without_secrets() {
+    let leaked = mock("POST", "/api/1/store/")
+        .match_body(Matcher::Regex("SENTRY_AUTH_TOKEN|SENTRY_DSN".into()))
+        .with_status(200)
+        .create();
+    let store = mock("POST", "/api/1/store/")
+        .match_body(Matcher::PartialJson(serde_json::json!({
+            "extra": {"environ": {"DEPLOY_TARGET": "staging"}},
+        })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("{}")
+        .expect(1)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env(
+            "SENTRY_DSN",
+            format!("http://lolnope@{}/1", server_address()),
+        )
+        .env("DEPLOY_TARGET", "staging")
+        .args(["run", "--send-environ", "--", "sh", "-c", "exit 1"])
+        .assert()
+        .code(1);
+
+    assert!(!leaked.matched());
+    store.assert();
+}
+
+#[cfg(unix)]
+#[test]
+fn sends_non_unicode_environ() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let store = mock("POST", "/api/1/store/")
+        .match_body(Matcher::PartialJson(serde_json::json!({
+            "extra": {"environ": {"NON_UNICODE_VALUE": "\u{fffd}"}},
+        })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("{}")
+        .expect(1)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env(
+            "SENTRY_DSN",
+            format!("http://lolnope@{}/1", server_address()),
+        )
+        .env("NON_UNICODE_VALUE", OsStr::from_bytes(b"\xff"))
+        .env(OsStr::from_bytes(b"NON_UNICODE_\xff"), "key")
+        .args(["run", "--send-environ", "--", "sh", "-c", "exit 1"])
+        .assert()
+        .code(1);
+
+    store.assert();
+}
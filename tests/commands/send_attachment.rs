@@ -0,0 +1,76 @@
+use std::fs;
+
+use assert_cmd::Command;
+use mockito::{mock, server_address, Matcher};
+use predicates::str::contains;
+
+use crate::common;
+
+#[test]
+fn attaches_files_to_event() {
+    let envelope = mock("POST", "/api/1/envelope/")
+        .match_header("content-type", "application/x-sentry-envelope")
+        .match_body(Matcher::AllOf(vec![
+            Matcher::Regex(r#"^\{"event_id":"a1b2c3d4000040008000000000000001"\}\n"#.into()),
+            Matcher::Regex(r#""filename":"gdb.txt""#.into()),
+            Matcher::Regex(r#"\n#0  0x0000 in main \(\)\n"#.into()),
+            Matcher::Regex(r#""filename":"dmesg.log""#.into()),
+        ]))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("{}")
+        .expect(1)
+        .create();
+
+    let dir = tempfile::tempdir().unwrap();
+    let backtrace = dir.path().join("gdb.txt");
+    let dmesg = dir.path().join("dmesg.log");
+    fs::write(&backtrace, "#0  0x0000 in main ()").unwrap();
+    fs::write(&dmesg, "segfault at 0").unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env(
+            "SENTRY_DSN",
+            format!("http://lolnope@{}/1", server_address()),
+        )
+        .args([
+            "send-attachment",
+            "--event-id",
+            "a1b2c3d4-0000-4000-8000-000000000001",
+        ])
+        .arg(&backtrace)
+        .arg(&dmesg)
+        .assert()
+        .success()
+        .stdout(contains(
+            "Attached 2 files to event a1b2c3d4000040008000000000000001",
+        ));
+
+    envelope.assert();
+}
+
+#[test]
+fn rejects_missing_files() {
+    let envelope = mock("POST", "/api/1/envelope/").expect(0).create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env(
+            "SENTRY_DSN",
+            format!("http://lolnope@{}/1", server_address()),
+        )
+        .args([
+            "send-attachment",
+            "--event-id",
+            "a1b2c3d4000040008000000000000001",
+            "does-not-exist.txt",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains("does-not-exist.txt does not exist"));
+
+    envelope.assert();
+}
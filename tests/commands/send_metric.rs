@@ -0,0 +1,62 @@
+use assert_cmd::Command;
+use mockito::{mock, server_address, Matcher};
+use predicates::str::contains;
+
+use crate::common;
+
+#[test]
+fn sends_counter() {
+    let envelope = mock("POST", "/api/1/envelope/")
+        .match_header("content-type", "application/x-sentry-envelope")
+        .match_body(Matcher::AllOf(vec![
+            Matcher::Regex(r#""type":"statsd""#.into()),
+            Matcher::Regex(r"\ndeploys:1\|c\|#env:prod\|T1600000000\n".into()),
+        ]))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("{}")
+        .expect(1)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env(
+            "SENTRY_DSN",
+            format!("http://lolnope@{}/1", server_address()),
+        )
+        .args([
+            "send-metric",
+            "increment",
+            "--name",
+            "deploys",
+            "--tag",
+            "env:prod",
+            "--timestamp",
+            "1600000000",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("Sent metric deploys:1|c|#env:prod|T1600000000"));
+
+    envelope.assert();
+}
+
+#[test]
+fn rejects_non_numeric_values() {
+    let envelope = mock("POST", "/api/1/envelope/").expect(0).create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env(
+            "SENTRY_DSN",
+            format!("http://lolnope@{}/1", server_address()),
+        )
+        .args(["send-metric", "gauge", "--name", "queue", "--value", "many"])
+        .assert()
+        .failure()
+        .stderr(contains("Invalid metric value many"));
+
+    envelope.assert();
+}
@@ -0,0 +1,66 @@
+use std::fs;
+
+use assert_cmd::Command;
+use mockito::{mock, server_address, Matcher};
+use predicates::prelude::*;
+use predicates::str::contains;
+
+use crate::common;
+
+#[test]
+fn sends_replay_segments() {
+    let envelope = mock("POST", "/api/1/envelope/")
+        .match_header("content-type", "application/x-sentry-envelope")
+        .match_body(Matcher::AllOf(vec![
+            Matcher::Regex(r#""type":"replay_event""#.into()),
+            Matcher::Regex(r#""replay_id":"a1b2c3d4000040008000000000000001""#.into()),
+            Matcher::Regex(r#""urls":\["https://kiosk.example.com/"\]"#.into()),
+            Matcher::Regex(r#""replay_start_timestamp":1600000000.0"#.into()),
+            Matcher::Regex(r#""type":"replay_recording""#.into()),
+            Matcher::Regex(r#"\n\{"segment_id":[01]\}\n\[\{"#.into()),
+        ]))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("{}")
+        .expect(2)
+        .create();
+
+    let dir = tempfile::tempdir().unwrap();
+    let replay_event = dir.path().join("replay.json");
+    let first = dir.path().join("segment-0.json");
+    let second = dir.path().join("segment-1.json");
+    fs::write(
+        &replay_event,
+        r#"{"replay_id":"a1b2c3d4-0000-4000-8000-000000000001","urls":["https://kiosk.example.com/"]}"#,
+    )
+    .unwrap();
+    fs::write(
+        &first,
+        r#"[{"type":4,"timestamp":1600000000000},{"type":2,"timestamp":1600000001500}]"#,
+    )
+    .unwrap();
+    fs::write(&second, r#"[{"type":3,"timestamp":1600000005000}]"#).unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env(
+            "SENTRY_DSN",
+            format!("http://lolnope@{}/1", server_address()),
+        )
+        .args([
+            "send-replay",
+            "--replay-event",
+            replay_event.to_str().unwrap(),
+            first.to_str().unwrap(),
+            second.to_str().unwrap(),
+        ])
+        .assert()
+        .success()
+        .stdout(
+            contains("Sent segment 1")
+                .and(contains("Sent replay a1b2c3d4000040008000000000000001")),
+        );
+
+    envelope.assert();
+}
@@ -0,0 +1,70 @@
+use std::env;
+use std::fs;
+
+use assert_cmd::Command;
+use mockito::{mock, server_address, Matcher};
+
+use crate::common;
+
+#[cfg(unix)]
+fn write_tool(dir: &std::path::Path, name: &str, script: &str) {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = dir.join(name);
+    fs::write(&path, format!("#!/bin/sh\n{}", script)).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+#[test]
+#[cfg(unix)]
+fn reports_failed_unit() {
+    let dir = tempfile::tempdir().unwrap();
+    write_tool(
+        dir.path(),
+        "systemctl",
+        "printf 'Id=backup.service\\nResult=exit-code\\nExecMainStatus=3\\nNRestarts=\\n'\n",
+    );
+    write_tool(
+        dir.path(),
+        "journalctl",
+        r#"echo '{"__REALTIME_TIMESTAMP":"1600000000000000","PRIORITY":"6","SYSLOG_IDENTIFIER":"backup","MESSAGE":"starting backup"}'
+echo '{"__REALTIME_TIMESTAMP":"1600000001000000","PRIORITY":"3","SYSLOG_IDENTIFIER":"backup","MESSAGE":[100,105,115,107,32,102,117,108,108]}'
+"#,
+    );
+
+    let store = mock("POST", "/api/1/store/")
+        .match_body(Matcher::PartialJson(serde_json::json!({
+            "exception": {"values": [{
+                "type": "SystemdUnitFailure",
+                "value": "unit backup.service failed with result exit-code (status 3)",
+            }]},
+            "tags": {"unit": "backup.service", "result": "exit-code", "exit_code": "3"},
+            "fingerprint": ["systemd", "backup.service", "exit-code"],
+            "breadcrumbs": {"values": [
+                {"category": "backup", "message": "starting backup"},
+                {"category": "backup", "message": "disk full", "level": "error"},
+            ]},
+        })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("{}")
+        .expect(1)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env(
+            "SENTRY_DSN",
+            format!("http://lolnope@{}/1", server_address()),
+        )
+        .env(
+            "PATH",
+            format!("{}:{}", dir.path().display(), env::var("PATH").unwrap()),
+        )
+        .args(["systemd-hook", "backup.service"])
+        .assert()
+        .success();
+
+    store.assert();
+}
@@ -0,0 +1,92 @@
+use assert_cmd::Command;
+use mockito::{mock, Matcher};
+use predicates::prelude::*;
+use predicates::str::contains;
+
+use crate::common;
+
+#[test]
+fn creates_team() {
+    let _server = mock("POST", "/api/0/organizations/wat-org/teams/")
+        .match_body(Matcher::Json(serde_json::json!({"name": "Wat Squad"})))
+        .with_status(201)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"id":"42","slug":"wat-squad","name":"Wat Squad"}"#)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec!["teams", "create", "Wat Squad"])
+        .assert()
+        .success()
+        .stdout(contains("Created team wat-squad (42)"));
+}
+
+#[test]
+fn adds_members_by_email_and_id() {
+    let _lookup = mock("GET", "/api/0/organizations/wat-org/members/")
+        .match_query(Matcher::UrlEncoded(
+            "query".into(),
+            "email:jane@example.com".into(),
+        ))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"[{"id":"7","email":"jane@example.com","name":"Jane","role":"member"}]"#)
+        .create();
+    let _add_jane = mock(
+        "POST",
+        "/api/0/organizations/wat-org/members/7/teams/wat-squad/",
+    )
+    .with_status(201)
+    .with_header("content-type", "application/json")
+    .with_body("{}")
+    .create();
+    let _add_other = mock(
+        "POST",
+        "/api/0/organizations/wat-org/members/8/teams/wat-squad/",
+    )
+    .with_status(201)
+    .with_header("content-type", "application/json")
+    .with_body("{}")
+    .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec![
+            "teams",
+            "add-member",
+            "wat-squad",
+            "jane@example.com",
+            "8",
+        ])
+        .assert()
+        .success()
+        .stdout(
+            contains("Added jane@example.com to team wat-squad")
+                .and(contains("Added 8 to team wat-squad")),
+        );
+}
+
+#[test]
+fn adds_project_to_team() {
+    let _server = mock(
+        "POST",
+        "/api/0/projects/wat-org/wat-project/teams/wat-squad/",
+    )
+    .with_status(201)
+    .with_header("content-type", "application/json")
+    .with_body("{}")
+    .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(vec!["teams", "add-project", "wat-squad", "wat-project"])
+        .assert()
+        .success()
+        .stdout(contains(
+            "Gave team wat-squad access to project wat-project",
+        ));
+}
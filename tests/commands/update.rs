@@ -0,0 +1,75 @@
+use assert_cmd::Command;
+use mockito::{mock, server_url};
+use predicates::str::contains;
+
+use crate::common;
+
+const ASSET: &str = "sentry-cli-Linux-x86_64";
+const SHA256: &str = "b5bb9d8014a0f9b1d61e21e796d78dccdf1352f23cd32812f4850b878ae4944c";
+
+fn mock_registry(path: &str, sha256: &str) -> mockito::Mock {
+    mock("GET", path)
+        .with_header("content-type", "application/json")
+        .with_body(format!(
+            r#"{{"version": "99.0.0-beta.1",
+                "file_urls": {{"{asset}": "{url}/download/{asset}"}},
+                "files": {{"{asset}": {{"checksums": {{"sha256-hex": "{sha256}"}}}}}}}}"#,
+            asset = ASSET,
+            url = server_url(),
+            sha256 = sha256,
+        ))
+        .create()
+}
+
+#[test]
+fn prints_update_of_channel_as_json() {
+    let stable = mock("GET", "/apps/sentry-cli/latest").expect(0).create();
+    let versions = mock("GET", "/apps/sentry-cli/versions")
+        .with_header("content-type", "application/json")
+        .with_body(
+            r#"{"latest": "98.0.0",
+                "versions": ["1.0.0", "99.0.0-alpha.2", "98.0.0", "99.0.0-beta.1"]}"#,
+        )
+        .create();
+    let beta = mock_registry("/apps/sentry-cli/99.0.0-beta.1", SHA256);
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env("SENTRY_RELEASE_REGISTRY_URL", server_url())
+        .args(["update", "--channel", "beta", "--check-only"])
+        .assert()
+        .success()
+        .stdout(contains(r#""channel": "beta""#))
+        .stdout(contains(r#""latest_version": "99.0.0-beta.1""#))
+        .stdout(contains(r#""update_available": true"#))
+        .stdout(contains(format!(r#""sha256": "{}""#, SHA256)));
+
+    stable.assert();
+    versions.assert();
+    beta.assert();
+}
+
+#[test]
+fn rejects_download_with_wrong_checksum() {
+    let _latest = mock_registry("/apps/sentry-cli/latest", SHA256);
+    let download = mock("GET", format!("/download/{}", ASSET).as_str())
+        .with_body("not sentry-cli")
+        .expect(1)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env("SENTRY_RELEASE_REGISTRY_URL", server_url())
+        .env("SENTRY_UPDATE_CHANNEL", "stable")
+        .args(["update"])
+        .assert()
+        .failure()
+        .stderr(contains("Checksum mismatch for the downloaded executable"));
+
+    download.assert();
+    assert!(!assert_cmd::cargo::cargo_bin("sentry-cli")
+        .with_file_name(".sentry-cli.part")
+        .exists());
+}
@@ -0,0 +1,356 @@
+use std::fs;
+use std::io::Write;
+
+use assert_cmd::Command;
+use mockito::{mock, Matcher};
+use predicates::prelude::*;
+use predicates::str::contains;
+
+use crate::common;
+
+const BREAKPAD_SYM: &str = "MODULE Linux x86_64 3249D99D0C4049318610F4E4FB0B69370 wat\n\
+                            FILE 0 wat.c\n\
+                            FUNC 1000 10 0 main\n\
+                            1000 10 1 0\n";
+
+#[test]
+fn uploads_debug_files_from_zip() {
+    let _chunk_upload = mock("GET", "/api/0/organizations/wat-org/chunk-upload/")
+        .with_status(404)
+        .create();
+    let _missing = mock(
+        "GET",
+        "/api/0/projects/wat-org/wat-project/files/dsyms/unknown/",
+    )
+    .match_query(Matcher::Any)
+    .with_status(200)
+    .with_header("content-type", "application/json")
+    .with_body(r#"{"missing": ["a669fd3692a04bd5fda78c322c2a6ca6118ab595"]}"#)
+    .create();
+    let upload = mock("POST", "/api/0/projects/wat-org/wat-project/files/dsyms/")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("[]")
+        .create();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("symbols.zip");
+    let mut zip = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+    zip.start_file("build/wat.sym", zip::write::FileOptions::default())
+        .unwrap();
+    zip.write_all(BREAKPAD_SYM.as_bytes()).unwrap();
+    zip.finish().unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["upload-dif", "--no-reprocessing", path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(
+            contains("Found 1 debug information file")
+                .and(contains("Uploading debug symbol files")),
+        );
+
+    upload.assert();
+}
+
+#[test]
+fn searches_dsyms_of_xcarchive() {
+    let _chunk_upload = mock("GET", "/api/0/organizations/wat-org/chunk-upload/")
+        .with_status(404)
+        .create();
+
+    let dir = tempfile::tempdir().unwrap();
+    let archive = dir.path().join("Wat.xcarchive");
+    fs::create_dir_all(archive.join("dSYMs")).unwrap();
+    fs::create_dir_all(archive.join("Products")).unwrap();
+    fs::write(archive.join("Products/wat.sym"), BREAKPAD_SYM).unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["upload-dif", "--no-reprocessing", archive.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(contains("Found 0 debug information files"));
+}
+
+#[test]
+fn skips_other_architectures() {
+    let _chunk_upload = mock("GET", "/api/0/organizations/wat-org/chunk-upload/")
+        .with_status(404)
+        .create();
+
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("wat.sym"), BREAKPAD_SYM).unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args([
+            "upload-dif",
+            "--arch",
+            "arm64",
+            "--no-reprocessing",
+            dir.path().to_str().unwrap(),
+        ])
+        .assert()
+        .success()
+        .stdout(contains("Found 0 debug information files"));
+}
+
+#[test]
+fn skips_ignored_folders() {
+    let _chunk_upload = mock("GET", "/api/0/organizations/wat-org/chunk-upload/")
+        .with_status(404)
+        .create();
+
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("vendor/fixtures")).unwrap();
+    fs::write(dir.path().join("vendor/fixtures/wat.sym"), BREAKPAD_SYM).unwrap();
+    fs::create_dir_all(dir.path().join("testdata")).unwrap();
+    fs::write(dir.path().join("testdata/wat.sym"), BREAKPAD_SYM).unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args([
+            "upload-dif",
+            "--ignore",
+            "fixtures",
+            "testdata",
+            "--no-reprocessing",
+            dir.path().to_str().unwrap(),
+        ])
+        .assert()
+        .success()
+        .stdout(contains("Found 0 debug information files"));
+}
+
+#[test]
+fn uploads_native_symbols_from_app_bundle() {
+    let _chunk_upload = mock("GET", "/api/0/organizations/wat-org/chunk-upload/")
+        .with_status(404)
+        .create();
+    let _missing = mock(
+        "GET",
+        "/api/0/projects/wat-org/wat-project/files/dsyms/unknown/",
+    )
+    .match_query(Matcher::Any)
+    .with_status(200)
+    .with_header("content-type", "application/json")
+    .with_body(r#"{"missing": ["a669fd3692a04bd5fda78c322c2a6ca6118ab595"]}"#)
+    .create();
+    let upload = mock("POST", "/api/0/projects/wat-org/wat-project/files/dsyms/")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("[]")
+        .create();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("app-release.aab");
+    let mut zip = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+    zip.start_file(
+        "BUNDLE-METADATA/com.android.tools.build.debugsymbols/arm64-v8a/libwat.so.sym",
+        zip::write::FileOptions::default(),
+    )
+    .unwrap();
+    zip.write_all(BREAKPAD_SYM.as_bytes()).unwrap();
+    // assets are not searched, even if they look like debug files
+    zip.start_file("base/assets/other.sym", zip::write::FileOptions::default())
+        .unwrap();
+    zip.write_all(BREAKPAD_SYM.replace("3249D99D", "4249D99D").as_bytes())
+        .unwrap();
+    zip.finish().unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["upload-dif", "--no-reprocessing", path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(contains("Found 1 debug information file"));
+
+    upload.assert();
+}
+
+#[test]
+fn searches_split_apks() {
+    let _chunk_upload = mock("GET", "/api/0/organizations/wat-org/chunk-upload/")
+        .with_status(404)
+        .create();
+    let _missing = mock(
+        "GET",
+        "/api/0/projects/wat-org/wat-project/files/dsyms/unknown/",
+    )
+    .match_query(Matcher::Any)
+    .with_status(200)
+    .with_header("content-type", "application/json")
+    .with_body(r#"{"missing": ["a669fd3692a04bd5fda78c322c2a6ca6118ab595"]}"#)
+    .create();
+    let upload = mock("POST", "/api/0/projects/wat-org/wat-project/files/dsyms/")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("[]")
+        .create();
+
+    let mut split = zip::ZipWriter::new(std::io::Cursor::new(vec![]));
+    split
+        .start_file(
+            "lib/arm64-v8a/libwat.so",
+            zip::write::FileOptions::default(),
+        )
+        .unwrap();
+    split.write_all(BREAKPAD_SYM.as_bytes()).unwrap();
+    let split = split.finish().unwrap().into_inner();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("app.apks");
+    let mut zip = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+    zip.start_file(
+        "splits/base-arm64_v8a.apk",
+        zip::write::FileOptions::default(),
+    )
+    .unwrap();
+    zip.write_all(&split).unwrap();
+    zip.finish().unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["upload-dif", "--no-reprocessing", path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(contains("Found 1 debug information file"));
+
+    upload.assert();
+}
+
+#[test]
+fn prints_size_report() {
+    let _chunk_upload = mock("GET", "/api/0/organizations/wat-org/chunk-upload/")
+        .with_status(404)
+        .create();
+    let _missing = mock(
+        "GET",
+        "/api/0/projects/wat-org/wat-project/files/dsyms/unknown/",
+    )
+    .match_query(Matcher::Any)
+    .with_status(200)
+    .with_header("content-type", "application/json")
+    .with_body(r#"{"missing": []}"#)
+    .create();
+
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("wat.sym"), BREAKPAD_SYM).unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args([
+            "upload-dif",
+            "--no-reprocessing",
+            "--size-report",
+            dir.path().to_str().unwrap(),
+        ])
+        .assert()
+        .success()
+        .stdout(
+            contains("Debug Symbols Size Report")
+                .and(contains("x86_64"))
+                .and(contains(format!("{}B (1 file)", BREAKPAD_SYM.len()))),
+        );
+}
+
+#[test]
+fn prints_teamcity_steps() {
+    let _chunk_upload = mock("GET", "/api/0/organizations/wat-org/chunk-upload/")
+        .with_status(404)
+        .create();
+    let _missing = mock(
+        "GET",
+        "/api/0/projects/wat-org/wat-project/files/dsyms/unknown/",
+    )
+    .match_query(Matcher::Any)
+    .with_status(200)
+    .with_header("content-type", "application/json")
+    .with_body(r#"{"missing": ["a669fd3692a04bd5fda78c322c2a6ca6118ab595"]}"#)
+    .create();
+    let _upload = mock("POST", "/api/0/projects/wat-org/wat-project/files/dsyms/")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("[]")
+        .create();
+
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("wat.sym"), BREAKPAD_SYM).unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .args(["--ci-output", "teamcity", "upload-dif", "--no-reprocessing"])
+        .arg(dir.path())
+        .assert()
+        .success()
+        .stdout(
+            contains("##teamcity[blockOpened name='Searching for debug information files']")
+                .and(contains(
+                    "##teamcity[blockClosed name='Searching for debug information files']",
+                ))
+                .and(contains("##teamcity[blockOpened name='Uploading batch 1']"))
+                .and(contains("##teamcity[blockClosed name='Uploading batch 1']")),
+        );
+}
+
+#[test]
+fn uploads_debug_files_from_gcs_prefix() {
+    let _chunk_upload = mock("GET", "/api/0/organizations/wat-org/chunk-upload/")
+        .with_status(404)
+        .create();
+    let _missing = mock(
+        "GET",
+        "/api/0/projects/wat-org/wat-project/files/dsyms/unknown/",
+    )
+    .match_query(Matcher::Any)
+    .with_status(200)
+    .with_header("content-type", "application/json")
+    .with_body(r#"{"missing": ["a669fd3692a04bd5fda78c322c2a6ca6118ab595"]}"#)
+    .create();
+    let upload = mock("POST", "/api/0/projects/wat-org/wat-project/files/dsyms/")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("[]")
+        .create();
+    let listing = mock("GET", "/storage/v1/b/symbols/o")
+        .match_query(Matcher::UrlEncoded("prefix".into(), "linux/".into()))
+        .match_header("authorization", "Bearer ya29.token")
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"items": [{"name": "linux/"}, {"name": "linux/build/wat.sym"}]}"#)
+        .expect(1)
+        .create();
+    let object = mock(
+        "GET",
+        Matcher::Regex("^/storage/v1/b/symbols/o/linux%2Fbuild%2Fwat.sym\\?alt=media$".into()),
+    )
+    .with_body(BREAKPAD_SYM)
+    .expect(1)
+    .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env("STORAGE_EMULATOR_HOST", mockito::server_url())
+        .env("GOOGLE_OAUTH_ACCESS_TOKEN", "ya29.token")
+        .args(["upload-dif", "--no-reprocessing", "gs://symbols/linux/"])
+        .assert()
+        .success()
+        .stdout(
+            contains("Downloading gs://symbols/linux/")
+                .and(contains("Found 1 debug information file")),
+        );
+
+    listing.assert();
+    object.assert();
+    upload.assert();
+}
@@ -0,0 +1,75 @@
+use std::fs;
+
+use assert_cmd::Command;
+use mockito::{mock, server_address, Matcher};
+use predicates::str::contains;
+
+use crate::common;
+
+#[test]
+fn uploads_minidump_with_attachments() {
+    let upload = mock("POST", "/api/1/minidump/")
+        .match_body(Matcher::AllOf(vec![
+            Matcher::Regex(r#"name="upload_file_minidump"; filename="crash.dmp""#.into()),
+            Matcher::Regex(r#"name="attachment1"; filename="app.log""#.into()),
+            Matcher::Regex(r#""release":"wat-release""#.into()),
+            Matcher::Regex(r#""tags":\{"device":"kiosk-7"\}"#.into()),
+        ]))
+        .with_status(200)
+        .with_header("content-type", "text/plain")
+        .with_body("a1b2c3d4-0000-4000-8000-000000000001")
+        .expect(1)
+        .create();
+
+    let dir = tempfile::tempdir().unwrap();
+    let minidump = dir.path().join("crash.dmp");
+    let log = dir.path().join("app.log");
+    fs::write(&minidump, b"MDMP\x93\xa7\x00\x00").unwrap();
+    fs::write(&log, "starting up\n").unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env(
+            "SENTRY_DSN",
+            format!("http://lolnope@{}/1", server_address()),
+        )
+        .args([
+            "upload-minidump",
+            minidump.to_str().unwrap(),
+            "--attach",
+            log.to_str().unwrap(),
+            "--release",
+            "wat-release",
+            "--tag",
+            "device:kiosk-7",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("a1b2c3d4000040008000000000000001"));
+
+    upload.assert();
+}
+
+#[test]
+fn rejects_files_that_are_not_minidumps() {
+    let upload = mock("POST", "/api/1/minidump/").expect(0).create();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("crash.dmp");
+    fs::write(&path, "not a crash").unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env(
+            "SENTRY_DSN",
+            format!("http://lolnope@{}/1", server_address()),
+        )
+        .args(["upload-minidump", path.to_str().unwrap()])
+        .assert()
+        .failure()
+        .stderr(contains("is not a minidump"));
+
+    upload.assert();
+}
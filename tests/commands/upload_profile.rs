@@ -0,0 +1,54 @@
+use std::fs;
+
+use assert_cmd::Command;
+use mockito::{mock, server_address, Matcher};
+use predicates::str::contains;
+
+use crate::common;
+
+#[test]
+fn uploads_collapsed_stacks() {
+    let envelope = mock("POST", "/api/1/envelope/")
+        .match_header("content-type", "application/x-sentry-envelope")
+        .match_body(Matcher::AllOf(vec![
+            Matcher::Regex(r#""type":"transaction""#.into()),
+            Matcher::Regex(r#""transaction":"nightly-import""#.into()),
+            Matcher::Regex(r#""type":"profile""#.into()),
+            Matcher::Regex(r#""function":"parse_rows","lineno":42"#.into()),
+            Matcher::Regex(r#""elapsed_since_start_ns":"20000000""#.into()),
+        ]))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("{}")
+        .expect(1)
+        .create();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("profile.txt");
+    fs::write(
+        &path,
+        "main (import.py:10);parse_rows (import.py:42) 2\nmain (import.py:10) 1\n",
+    )
+    .unwrap();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env(
+            "SENTRY_DSN",
+            format!("http://lolnope@{}/1", server_address()),
+        )
+        .args([
+            "upload-profile",
+            path.to_str().unwrap(),
+            "--transaction",
+            "nightly-import",
+            "--release",
+            "wat-release",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("for transaction nightly-import"));
+
+    envelope.assert();
+}
@@ -0,0 +1,71 @@
+use assert_cmd::Command;
+use mockito::{mock, server_address, server_url, Matcher};
+use predicates::str::contains;
+
+use crate::common;
+
+#[test]
+fn reports_passed_check_as_check_in() {
+    let health = mock("GET", "/health").with_status(200).expect(1).create();
+    let envelope = mock("POST", "/api/1/envelope/")
+        .match_body(Matcher::AllOf(vec![
+            Matcher::Regex(r#""type":"check_in""#.into()),
+            Matcher::Regex(r#""monitor_slug":"internal-api""#.into()),
+            Matcher::Regex(r#""status":"ok""#.into()),
+        ]))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("{}")
+        .expect(1)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env(
+            "SENTRY_DSN",
+            format!("http://lolnope@{}/1", server_address()),
+        )
+        .args(["uptime", "check", "--monitor", "internal-api"])
+        .arg(format!("{}/health", server_url()))
+        .assert()
+        .success()
+        .stdout(contains("/health: 200 in"))
+        .stdout(contains("Check passed"));
+
+    health.assert();
+    envelope.assert();
+}
+
+#[test]
+fn reports_failed_check_as_event() {
+    let health = mock("HEAD", "/ready").with_status(503).expect(1).create();
+    let envelope = mock("POST", "/api/1/envelope/")
+        .match_body(Matcher::AllOf(vec![
+            Matcher::Regex(r#""type":"event""#.into()),
+            Matcher::Regex(r#"failed: unexpected status 503"#.into()),
+            Matcher::Regex(r#""status":"503""#.into()),
+        ]))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body("{}")
+        .expect(1)
+        .create();
+
+    Command::cargo_bin("sentry-cli")
+        .unwrap()
+        .envs(common::get_base_env())
+        .env(
+            "SENTRY_DSN",
+            format!("http://lolnope@{}/1", server_address()),
+        )
+        .args(["uptime", "check", "--method", "head", "--event"])
+        .arg(format!("{}/ready", server_url()))
+        .assert()
+        .code(1)
+        .stdout(contains("Check failed"))
+        .stdout(contains("  - unexpected status 503"));
+
+    health.assert();
+    envelope.assert();
+}
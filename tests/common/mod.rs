@@ -7,5 +7,6 @@ pub fn get_base_env() -> HashMap<String, String> {
     env.insert(String::from("SENTRY_AUTH_TOKEN"), String::from("lolnope"));
     env.insert(String::from("SENTRY_ORG"), String::from("wat-org"));
     env.insert(String::from("SENTRY_PROJECT"), String::from("wat-project"));
+    env.insert(String::from("SENTRY_CI_OUTPUT"), String::from("none"));
     env
 }